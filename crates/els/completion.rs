@@ -227,6 +227,20 @@ fn module_item(name: &str, mistype: bool, insert: Option<u32>) -> CompletionItem
     item
 }
 
+/// completion for a keyword enum parameter, e.g. `mode: {"r", "w", "a"}`
+fn literal_item(lit: &str, pt: &ParamTy) -> CompletionItem {
+    let mut item = CompletionItem::new_simple(format!("\"{lit}\""), pt.typ().to_string());
+    item.kind = Some(CompletionItemKind::ENUM_MEMBER);
+    let score = i32::from(CompletionOrder::Normal) + i32::from(CompletionOrder::TypeMatched);
+    item.sort_text = Some(format!(
+        "{}_{}",
+        char::from_u32(score as u32).unwrap_or(CompletionOrder::STD_ITEM),
+        item.label
+    ));
+    item.filter_text = Some(lit.to_string());
+    item
+}
+
 fn module_completions() -> Vec<CompletionItem> {
     let mut comps = Vec::with_capacity(BUILTIN_PYTHON_MODS.len());
     for mod_name in BUILTIN_PYTHON_MODS.into_iter() {
@@ -569,6 +583,11 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 }
                 _ => None,
             });
+        if let Some(pt) = arg_pt.as_ref() {
+            for lit in pt.typ().str_literals().unwrap_or_default() {
+                result.push(literal_item(&lit, pt));
+            }
+        }
         let receiver_t = comp_kind
             .should_be_method()
             .then(|| self.get_min_expr(&uri, pos, -2))
@@ -577,8 +596,18 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         let Some(mod_ctx) = self.modules.get(&uri).map(|m| &m.context) else {
             return Ok(None);
         };
-        for (name, vi) in contexts.into_iter().flat_map(|ctx| ctx.local_dir()) {
-            if comp_kind.should_be_method() && vi.vis.is_private() {
+        // the innermost context of the cursor
+        let caller = self
+            .get_local_ctx(&uri, pos)
+            .first()
+            .copied()
+            .unwrap_or(mod_ctx);
+        for (name, vi) in contexts
+            .into_iter()
+            .flat_map(|ctx| ctx.local_dir_from(caller))
+        {
+            // private attributes are accessed with `::`
+            if comp_kind.should_be_method() && trigger == Some(".") && vi.vis.is_private() {
                 continue;
             }
             // only show static methods, if the receiver is a type
@@ -4,7 +4,7 @@ use erg_compiler::artifact::BuildRunnable;
 use erg_compiler::context::register::PylyzerStatus;
 use erg_compiler::erg_parser::parse::Parsable;
 use erg_compiler::erg_parser::token::{Token, TokenCategory};
-use erg_compiler::hir::Expr;
+use erg_compiler::hir::{Accessor, Expr};
 use erg_compiler::ty::HasType;
 use erg_compiler::varinfo::VarInfo;
 
@@ -86,6 +86,15 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                             }
                         } else if let Expr::Accessor(acc) = def.body.block.last().unwrap() {
                             let vi = acc.var_info();
+                            let name = match acc {
+                                Accessor::Ident(ident) => ident.inspect(),
+                                Accessor::Attr(attr) => attr.ident.inspect(),
+                            };
+                            let ctxs = self.get_local_ctx(&uri, pos);
+                            let vi = ctxs
+                                .first()
+                                .and_then(|ctx| ctx.get_re_export_origin(name, vi))
+                                .unwrap_or(vi);
                             match (&vi.def_loc.module, util::loc_to_range(vi.def_loc.loc)) {
                                 (Some(path), Some(range)) => {
                                     let def_uri = NormalizedUrl::try_from(path.as_path()).unwrap();
@@ -116,7 +125,13 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                         }
                     }
                 }
-                match (vi.def_loc.module, util::loc_to_range(vi.def_loc.loc)) {
+                // `bar.x` (`bar.er`: `.x = foo.x`) => jump to `x` of `foo.er`
+                let ctxs = self.get_local_ctx(&uri, pos);
+                let vi = ctxs
+                    .first()
+                    .and_then(|ctx| ctx.get_re_export_origin(token.inspect(), &vi))
+                    .unwrap_or(&vi);
+                match (&vi.def_loc.module, util::loc_to_range(vi.def_loc.loc)) {
                     (Some(path), Some(range)) => {
                         let def_uri = Url::from_file_path(path).unwrap();
                         Ok(GotoDefinitionResponse::Array(vec![
@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -8,9 +9,12 @@ use erg_common::spawn::spawn_new_thread;
 use erg_common::style::*;
 use erg_common::traits::Stream;
 use erg_compiler::artifact::BuildRunnable;
+use erg_compiler::context::ModuleContext;
 use erg_compiler::erg_parser::ast::Module;
 use erg_compiler::erg_parser::parse::Parsable;
 use erg_compiler::error::CompileErrors;
+use erg_compiler::lower::ASTLowerer;
+use erg_compiler::ty::Type;
 
 use lsp_types::{
     Diagnostic, DiagnosticSeverity, NumberOrString, Position, PublishDiagnosticsParams, Range, Url,
@@ -19,6 +23,7 @@ use serde_json::json;
 
 use crate::diff::{ASTDiff, HIRDiff};
 use crate::server::{send, send_log, AnalysisResult, DefaultFeatures, ELSResult, Server};
+use crate::summary::{hash_code, ModuleSummary, VarSummary};
 use crate::util::{self, NormalizedUrl};
 
 impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
@@ -33,6 +38,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         code: S,
     ) -> ELSResult<()> {
         send_log(format!("checking {uri}"))?;
+        let code = code.into();
         let path = util::uri_to_path(&uri);
         let mode = if path.to_string_lossy().ends_with(".d.er") {
             "declare"
@@ -40,7 +46,8 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             "exec"
         };
         let mut checker = self.get_checker(path.clone());
-        let artifact = match checker.build(code.into(), mode) {
+        let mut published = vec![];
+        let artifact = match checker.build(code.clone(), mode) {
             Ok(artifact) => {
                 send_log(format!(
                     "checking {uri} passed, found warns: {}",
@@ -51,7 +58,8 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 self.send_diagnostics(uri.clone().raw(), vec![])?;
                 for (uri, diags) in uri_and_diags.into_iter() {
                     send_log(format!("{uri}, warns: {}", diags.len()))?;
-                    self.send_diagnostics(uri, diags)?;
+                    self.send_diagnostics(uri.clone(), diags.clone())?;
+                    published.push((uri, diags));
                 }
                 artifact.into()
             }
@@ -70,11 +78,25 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 }
                 for (uri, diags) in uri_and_diags.into_iter() {
                     send_log(format!("{uri}, errs & warns: {}", diags.len()))?;
-                    self.send_diagnostics(uri, diags)?;
+                    self.send_diagnostics(uri.clone(), diags.clone())?;
+                    published.push((uri, diags));
                 }
                 artifact
             }
         };
+        let (deps, vars) = checker.get_context().map_or((vec![], vec![]), |module| {
+            (
+                self.summarize_deps(&path, module),
+                self.summarize_vars(&path, module),
+            )
+        });
+        self.summaries.insert(
+            uri.clone(),
+            ModuleSummary::new(&code, deps, published, vars),
+        );
+        if let Err(err) = self.summaries.save_debounced() {
+            send_log(format!("failed to save the module summaries: {err}"))?;
+        }
         if let Some(shared) = self.get_shared() {
             if mode == "declare" {
                 shared.py_mod_cache.register(
@@ -118,18 +140,87 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         };
         let ast_diff = ASTDiff::diff(old, &new);
         crate::_log!("diff: {ast_diff}");
+        let modified_subr = self
+            .analysis_result
+            .get_ast(&uri)
+            .and_then(|old| ast_diff.modified_subr_body(old));
         if let Some(mut lowerer) = self.steal_lowerer(&uri) {
+            let old_t = modified_subr
+                .as_ref()
+                .and_then(|name| Self::registered_type(&lowerer, name));
             let hir = self.analysis_result.get_mut_hir(&uri);
             if let Some((hir_diff, hir)) = HIRDiff::new(ast_diff, &mut lowerer).zip(hir) {
                 crate::_log!("hir_diff: {hir_diff}");
                 hir_diff.update(hir);
             }
-            self.restore_lowerer(uri, lowerer);
+            let new_t = modified_subr
+                .as_ref()
+                .and_then(|name| Self::registered_type(&lowerer, name));
+            self.restore_lowerer(uri.clone(), lowerer);
+            // Only the body of a subroutine was re-checked.
+            // If its signature is changed, the module and its dependents must be checked again.
+            if let Some(name) = modified_subr.filter(|_| old_t != new_t) {
+                send_log(format!("the signature of {name} is changed"))?;
+                let code = self.file_cache.get_entire_code(&uri)?;
+                return self.check_file(uri, code);
+            }
         }
         // skip checking for dependents
         Ok(())
     }
 
+    pub(crate) fn registered_type(lowerer: &ASTLowerer, name: &str) -> Option<Type> {
+        lowerer
+            .get_mod_ctx()
+            .context
+            .get_var_info(name)
+            .map(|(_, vi)| vi.t.clone())
+    }
+
+    /// The hashes of the modules that `path` depends on
+    fn summarize_deps(&self, path: &Path, module: &ModuleContext) -> Vec<(Url, u64)> {
+        module
+            .context
+            .graph()
+            .ancestors(path)
+            .into_iter()
+            .filter_map(|dep| {
+                let uri = NormalizedUrl::from_file_path(dep.to_path_buf()).ok()?;
+                let code = self.file_cache.get_entire_code(&uri).ok()?;
+                Some((uri.raw(), hash_code(&code)))
+            })
+            .collect()
+    }
+
+    /// The top-level variables defined in `path` and their references
+    fn summarize_vars(&self, path: &Path, module: &ModuleContext) -> Vec<VarSummary> {
+        module
+            .context
+            .local_dir()
+            .into_iter()
+            .filter(|(_, vi)| vi.def_loc.module.as_deref() == Some(path))
+            .map(|(name, vi)| VarSummary {
+                name: name.to_string(),
+                t: vi.t.to_string(),
+                range: util::loc_to_range(vi.def_loc.loc),
+                refs: self.get_refs_from_abs_loc(&vi.def_loc),
+            })
+            .collect()
+    }
+
+    /// Publishes the diagnostics of the previous session (the same as `check_file` did).
+    pub(crate) fn send_summary_diagnostics(
+        &self,
+        uri: NormalizedUrl,
+        summary: ModuleSummary,
+    ) -> ELSResult<()> {
+        self.send_diagnostics(uri.raw(), vec![])?;
+        for (uri, diags) in summary.diagnostics {
+            self.send_diagnostics(uri, diags)?;
+        }
+        Ok(())
+    }
+
     fn make_uri_and_diags(
         &mut self,
         uri: NormalizedUrl,
@@ -170,10 +261,15 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 DiagnosticSeverity::ERROR
             };
             let source = if PYTHON_MODE { "pylyzer" } else { "els" };
+            // errors without a stable code are identified by the internal number
+            let code = err
+                .core
+                .code
+                .map_or_else(|| format!("E{}", err.core.errno), |code| code.to_string());
             let diag = Diagnostic::new(
                 Range::new(start, end),
                 Some(severity),
-                Some(NumberOrString::String(format!("E{}", err.core.errno))),
+                Some(NumberOrString::String(code)),
                 Some(source.to_string()),
                 message,
                 None,
@@ -239,3 +335,46 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use erg_common::config::ErgConfig;
+    use erg_common::traits::Runnable;
+
+    use erg_compiler::artifact::Buildable;
+    use erg_compiler::erg_parser::parse::{Parsable, SimpleParser};
+    use erg_compiler::lower::ASTLowerer;
+    use erg_compiler::ty::Type;
+    use erg_compiler::HIRBuilder;
+
+    use crate::diff::{ASTDiff, HIRDiff};
+    use crate::server::Server;
+
+    /// Re-checks the body of `f` modified in `new` as `quick_check_file` does,
+    /// and returns the types of `f` registered before and after that.
+    fn quick_check(old: &str, new: &str) -> (Type, Type) {
+        let mut builder = HIRBuilder::new(ErgConfig::default());
+        builder.build(old.to_string(), "exec").unwrap();
+        let mut lowerer = ASTLowerer::new_with_ctx(builder.pop_context().unwrap());
+        let old = SimpleParser::parse(old.to_string()).unwrap().ast;
+        let new = SimpleParser::parse(new.to_string()).unwrap().ast;
+        let diff = ASTDiff::diff(&old, &new);
+        assert_eq!(diff.modified_subr_body(&old).as_deref(), Some("f"));
+        let old_t = Server::<HIRBuilder>::registered_type(&lowerer, "f").unwrap();
+        HIRDiff::new(diff, &mut lowerer).unwrap();
+        let new_t = Server::<HIRBuilder>::registered_type(&lowerer, "f").unwrap();
+        (old_t, new_t)
+    }
+
+    #[test]
+    fn test_body_only_edit() {
+        let (old_t, new_t) = quick_check("f(a: Int) = a + 1\n", "f(a: Int) = a + 2\n");
+        assert_eq!(old_t, new_t);
+    }
+
+    #[test]
+    fn test_return_type_changed() {
+        let (old_t, new_t) = quick_check("f(a: Int) = a + 1\n", "f(a: Int) = str a\n");
+        assert_ne!(old_t, new_t);
+    }
+}
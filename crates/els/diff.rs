@@ -63,6 +63,23 @@ impl ASTDiff {
                 .unwrap_or(Self::Nop),
         }
     }
+
+    /// Returns the name of the subroutine if only its body is modified,
+    /// i.e. the signature (decorators, bounds, parameters and return type spec) is the same as `old`.
+    pub fn modified_subr_body<M: Deref<Target = Module>>(&self, old: M) -> Option<String> {
+        let Self::Modification(idx, ast::Expr::Def(new)) = self else {
+            return None;
+        };
+        let Some(ast::Expr::Def(old)) = old.get(*idx) else {
+            return None;
+        };
+        match (&old.sig, &new.sig) {
+            (ast::Signature::Subr(old), ast::Signature::Subr(new)) if old == new => {
+                Some(new.ident.inspect().to_string())
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -105,6 +122,10 @@ impl HIRDiff {
                     if let Some(name) = def.sig.name_as_str() {
                         lowerer.unregister(name);
                     }
+                    // the body is checked against the (unchanged part of the) signature
+                    if let Err(err) = lowerer.preregister_def(def) {
+                        crate::_log!("err: {err}");
+                    }
                 }
                 let expr = lowerer
                     .lower_chunk(expr)
@@ -137,3 +158,34 @@ impl HIRDiff {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use erg_compiler::erg_parser::parse::{Parsable, SimpleParser};
+
+    use super::*;
+
+    fn modified_subr_body(old: &str, new: &str) -> Option<String> {
+        let old = SimpleParser::parse(old.to_string()).unwrap().ast;
+        let new = SimpleParser::parse(new.to_string()).unwrap().ast;
+        ASTDiff::diff(&old, &new).modified_subr_body(&old)
+    }
+
+    #[test]
+    fn test_modified_subr_body() {
+        let old = "x = 1\nf a = a + 1\n";
+        assert_eq!(
+            modified_subr_body(old, "x = 1\nf a = a + 2\n").as_deref(),
+            Some("f")
+        );
+        // the return type may change, but the signature is the same
+        assert_eq!(
+            modified_subr_body(old, "x = 1\nf a = str a\n").as_deref(),
+            Some("f")
+        );
+        assert_eq!(modified_subr_body(old, "x = 1\nf a, b = a + b\n"), None);
+        assert_eq!(modified_subr_body(old, "x = 1\nf(a: Int) = a + 1\n"), None);
+        assert_eq!(modified_subr_body(old, "x = 2\nf a = a + 1\n"), None);
+        assert_eq!(modified_subr_body(old, old), None);
+    }
+}
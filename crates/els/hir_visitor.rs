@@ -88,6 +88,7 @@ impl<'a> HIRVisitor<'a> {
             Expr::Def(def) => self.get_def_ns(cur_ns, def, pos),
             Expr::Lambda(lambda) => self.get_lambda_ns(cur_ns, lambda, pos),
             Expr::TypeAsc(type_asc) => self.get_expr_ns(cur_ns, &type_asc.expr, pos),
+            Expr::Coerce(coerce) => self.get_expr_ns(cur_ns, &coerce.expr, pos),
             Expr::Dummy(dummy) => self.get_dummy_ns(cur_ns, dummy, pos),
             Expr::Compound(block) | Expr::Code(block) => self.get_block_ns(cur_ns, block, pos),
         }
@@ -209,6 +210,7 @@ impl<'a> HIRVisitor<'a> {
             Expr::Set(set) => self.get_expr_from_set(expr, set, token),
             Expr::Tuple(tuple) => self.get_expr_from_tuple(expr, tuple, token),
             Expr::TypeAsc(type_asc) => self.get_expr(&type_asc.expr, token),
+            Expr::Coerce(coerce) => self.get_expr(&coerce.expr, token),
             Expr::Dummy(dummy) => self.get_expr_from_dummy(dummy, token),
             Expr::Compound(block) | Expr::Code(block) => self.get_expr_from_block(block, token),
             Expr::ReDef(redef) => self.get_expr_from_redef(expr, redef, token),
@@ -357,6 +359,13 @@ impl<'a> HIRVisitor<'a> {
         if util::pos_in_loc(&lambda.params, util::loc_to_pos(token.loc())?) {
             return Some(expr);
         }
+        if let Some(expr) = lambda
+            .guard
+            .as_ref()
+            .and_then(|guard| self.get_expr(guard, token))
+        {
+            return Some(expr);
+        }
         self.get_expr_from_block(&lambda.body, token)
     }
 
@@ -502,6 +511,7 @@ impl<'a> HIRVisitor<'a> {
             Expr::Set(set) => self.get_set_info(set, token),
             Expr::Tuple(tuple) => self.get_tuple_info(tuple, token),
             Expr::TypeAsc(type_asc) => self.get_tasc_info(type_asc, token),
+            Expr::Coerce(coerce) => self.get_expr_info(&coerce.expr, token),
             Expr::Dummy(dummy) => self.get_dummy_info(dummy, token),
             Expr::Compound(block) | Expr::Code(block) => self.get_block_info(block, token),
             Expr::ReDef(redef) => self.get_redef_info(redef, token),
@@ -659,6 +669,12 @@ impl<'a> HIRVisitor<'a> {
 
     fn get_lambda_info(&self, lambda: &Lambda, token: &Token) -> Option<VarInfo> {
         self.get_params_info(&lambda.params, token)
+            .or_else(|| {
+                lambda
+                    .guard
+                    .as_ref()
+                    .and_then(|guard| self.get_expr_info(guard, token))
+            })
             .or_else(|| self.get_block_info(&lambda.body, token))
     }
 
@@ -173,6 +173,12 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                             );
                             contents.push(typ);
                         }
+                    } else if let Some(var) = self.get_summary_var(&uri, &token) {
+                        let typ = MarkedString::from_language_code(
+                            ERG_LANG.into(),
+                            format!("{}: {}", token.content, var.t),
+                        );
+                        contents.push(typ);
                     }
                 }
             }
@@ -16,5 +16,6 @@ mod rename;
 mod semantic;
 mod server;
 mod sig_help;
+mod summary;
 mod util;
 pub use server::*;
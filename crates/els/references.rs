@@ -25,6 +25,8 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 if let Some(vi) = visitor.get_info(&tok) {
                     return self.get_refs_from_abs_loc(&vi.def_loc);
                 }
+            } else if let Some(var) = self.get_summary_var(uri, &tok) {
+                return var.refs;
             }
         }
         vec![]
@@ -154,6 +154,8 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             .ref_inner()
             .iter()
             .filter(|node| node.id == path || self_node.depends_on(&node.id))
+            // the files included by `include_str`/`include_bytes` are not modules
+            .filter(|node| node.id.extension().is_some_and(|ext| ext == "er"))
             .map(|node| NormalizedUrl::new(Url::from_file_path(node.id.to_path_buf()).unwrap()))
             .collect()
     }
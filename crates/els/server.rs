@@ -48,6 +48,7 @@ use crate::completion::CompletionCache;
 use crate::file_cache::FileCache;
 use crate::hir_visitor::HIRVisitor;
 use crate::message::{ErrorMessage, LSPResult, LogMessage, ShowMessage};
+use crate::summary::SummaryCache;
 use crate::util::{self, NormalizedUrl};
 
 pub type ELSResult<T> = Result<T, Box<dyn std::error::Error>>;
@@ -317,6 +318,8 @@ pub struct Server<Checker: BuildRunnable = HIRBuilder, Parser: Parsable = Simple
     // TODO: remove modules, analysis_result, and add `shared: SharedCompilerResource`
     pub(crate) modules: ModuleCache,
     pub(crate) analysis_result: AnalysisResultCache,
+    /// the summaries of the modules checked in the previous sessions
+    pub(crate) summaries: SummaryCache,
     pub(crate) current_sig: Option<Expr>,
     pub(crate) channels: Option<SendChannels>,
     pub(crate) _parser: std::marker::PhantomData<fn() -> Parser>,
@@ -336,6 +339,7 @@ impl<C: BuildRunnable, P: Parsable> Clone for Server<C, P> {
             comp_cache: self.comp_cache.clone(),
             modules: self.modules.clone(),
             analysis_result: self.analysis_result.clone(),
+            summaries: self.summaries.clone(),
             current_sig: self.current_sig.clone(),
             channels: self.channels.clone(),
             _parser: std::marker::PhantomData,
@@ -345,7 +349,9 @@ impl<C: BuildRunnable, P: Parsable> Clone for Server<C, P> {
 }
 
 impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
-    pub fn new(cfg: ErgConfig) -> Self {
+    pub fn new(mut cfg: ErgConfig) -> Self {
+        // the server must keep running even if the compiler panics
+        cfg.no_panic = true;
         Self {
             comp_cache: CompletionCache::new(cfg.copy()),
             cfg,
@@ -357,6 +363,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             file_cache: FileCache::new(),
             modules: ModuleCache::new(),
             analysis_result: AnalysisResultCache::new(),
+            summaries: SummaryCache::new(),
             current_sig: None,
             channels: None,
             _parser: std::marker::PhantomData,
@@ -481,6 +488,8 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         result.capabilities.code_lens_provider = Some(CodeLensOptions {
             resolve_provider: Some(false),
         });
+        self.summaries = SummaryCache::load(SummaryCache::summary_path(&self.home));
+        send_log(format!("loaded {} module summaries", self.summaries.len()))?;
         self.init_services();
         send(&json!({
             "jsonrpc": "2.0",
@@ -531,11 +540,14 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
 
     fn exit(&self) -> ELSResult<()> {
         send_log("exiting ELS")?;
+        // in case `shutdown` was not requested
+        self.save_summaries()?;
         std::process::exit(0);
     }
 
     fn shutdown(&self, id: i64) -> ELSResult<()> {
         send_log("shutting down ELS")?;
+        self.save_summaries()?;
         send(&json!({
             "jsonrpc": "2.0",
             "id": id,
@@ -543,6 +555,13 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         }))
     }
 
+    fn save_summaries(&self) -> ELSResult<()> {
+        if let Err(err) = self.summaries.save() {
+            send_log(format!("failed to save the module summaries: {err}"))?;
+        }
+        Ok(())
+    }
+
     /// Copied and modified from RLS, https://github.com/rust-lang/rls/blob/master/rls/src/server/io.rs
     fn read_message(&self) -> Result<Value, io::Error> {
         // Read in the "Content-Length: xx" part.
@@ -692,7 +711,13 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 let code = params.text_document.text;
                 let ver = params.text_document.version;
                 self.file_cache.update(&uri, code.clone(), Some(ver));
-                self.check_file(uri, code)
+                if let Some(summary) = self.get_summary(&uri) {
+                    // the file will be checked in the background (see `start_auto_diagnostics`)
+                    send_log(format!("{uri} is not changed since the last session"))?;
+                    self.send_summary_diagnostics(uri, summary)
+                } else {
+                    self.check_file(uri, code)
+                }
             }
             "textDocument/didSave" => {
                 let uri =
@@ -0,0 +1,294 @@
+//! Persistent summaries of the checked modules.
+//!
+//! When a module is checked, its diagnostics, the types of its top-level variables and their references are saved to
+//! `~/.erg/cache/els/{hash of the workspace}.json`, keyed by the hashes of the source code and its dependencies.
+//! The summaries are loaded when the server is initialized, so the results of the previous session are available
+//! for the unchanged files until they are checked again in the background.
+//! The file is written at most once per [`SAVE_INTERVAL`], and when the server is shut down.
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use erg_common::dict::Dict;
+use erg_common::env::erg_path;
+use erg_common::get_hash;
+use erg_common::shared::Shared;
+
+use erg_compiler::artifact::BuildRunnable;
+use erg_compiler::erg_parser::parse::Parsable;
+use erg_compiler::erg_parser::token::{Token, TokenCategory};
+
+use lsp_types::{Diagnostic, Location, Range, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::server::Server;
+use crate::util::NormalizedUrl;
+
+/// Summaries written by another version of ELS are discarded.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Modules are checked on every edit, so the summaries are not written each time.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VarSummary {
+    pub name: String,
+    pub t: String,
+    pub range: Option<Range>,
+    pub refs: Vec<Location>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleSummary {
+    /// hash of the source code
+    pub hash: u64,
+    /// hashes of the source code of the modules imported (directly or indirectly)
+    pub deps: Vec<(Url, u64)>,
+    /// A module may also report errors in the modules it imports.
+    pub diagnostics: Vec<(Url, Vec<Diagnostic>)>,
+    pub vars: Vec<VarSummary>,
+}
+
+impl ModuleSummary {
+    pub fn new(
+        code: &str,
+        deps: Vec<(Url, u64)>,
+        diagnostics: Vec<(Url, Vec<Diagnostic>)>,
+        vars: Vec<VarSummary>,
+    ) -> Self {
+        Self {
+            hash: hash_code(code),
+            deps,
+            diagnostics,
+            vars,
+        }
+    }
+
+    pub fn get_var(&self, name: &str) -> Option<&VarSummary> {
+        self.vars.iter().find(|var| var.name == name)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SummaryFile {
+    version: String,
+    modules: Vec<(Url, ModuleSummary)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SummaryCache {
+    path: Option<PathBuf>,
+    summaries: Shared<Dict<NormalizedUrl, ModuleSummary>>,
+    /// whether there are summaries not yet written
+    dirty: Shared<bool>,
+    last_saved: Shared<Option<Instant>>,
+}
+
+pub(crate) fn hash_code(code: &str) -> u64 {
+    get_hash(&code) as u64
+}
+
+impl SummaryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The summaries of `workspace` are stored in `~/.erg/cache/els`.
+    pub fn summary_path(workspace: &Path) -> PathBuf {
+        erg_path()
+            .join("cache")
+            .join("els")
+            .join(format!("{:x}.json", get_hash(&workspace)))
+    }
+
+    /// A missing or broken summary file is treated as empty.
+    pub fn load(path: PathBuf) -> Self {
+        let mut summaries = Dict::new();
+        let file = File::open(&path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, SummaryFile>(BufReader::new(file)).ok());
+        if let Some(file) = file.filter(|file| file.version == VERSION) {
+            for (uri, summary) in file.modules {
+                summaries.insert(NormalizedUrl::new(uri), summary);
+            }
+        }
+        Self {
+            path: Some(path),
+            summaries: Shared::new(summaries),
+            dirty: Shared::new(false),
+            last_saved: Shared::new(Some(Instant::now())),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.summaries.borrow().len()
+    }
+
+    /// Returns the summary of `uri` if neither `code` nor the dependencies (read by `code_of`)
+    /// have been changed since it was saved.
+    pub fn get(
+        &self,
+        uri: &NormalizedUrl,
+        code: &str,
+        code_of: impl Fn(&Url) -> Option<String>,
+    ) -> Option<ModuleSummary> {
+        let summaries = self.summaries.borrow();
+        let summary = summaries.get(uri)?;
+        let unchanged = summary.hash == hash_code(code)
+            && summary
+                .deps
+                .iter()
+                .all(|(dep, hash)| code_of(dep).is_some_and(|code| hash_code(&code) == *hash));
+        unchanged.then(|| summary.clone())
+    }
+
+    pub fn insert(&self, uri: NormalizedUrl, summary: ModuleSummary) {
+        self.summaries.borrow_mut().insert(uri, summary);
+        *self.dirty.borrow_mut() = true;
+    }
+
+    /// Same as `save`, but does nothing if the summaries were saved within [`SAVE_INTERVAL`].
+    pub fn save_debounced(&self) -> std::io::Result<()> {
+        if self
+            .last_saved
+            .borrow()
+            .is_some_and(|saved| saved.elapsed() < SAVE_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        if !*self.dirty.borrow() {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let modules = self
+            .summaries
+            .borrow()
+            .iter()
+            .map(|(uri, summary)| (uri.clone().raw(), summary.clone()))
+            .collect();
+        let file = SummaryFile {
+            version: VERSION.to_string(),
+            modules,
+        };
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, &file).map_err(std::io::Error::from)?;
+        *self.dirty.borrow_mut() = false;
+        *self.last_saved.borrow_mut() = Some(Instant::now());
+        Ok(())
+    }
+}
+
+impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
+    /// Used until the module is checked in this session.
+    /// Only the top-level variables are summarized, so a local variable with the same name is not distinguished.
+    pub(crate) fn get_summary_var(&self, uri: &NormalizedUrl, token: &Token) -> Option<VarSummary> {
+        if !token.category_is(TokenCategory::Symbol) {
+            return None;
+        }
+        let summary = self.get_summary(uri)?;
+        summary.get_var(&token.content).cloned()
+    }
+
+    /// Returns the summary of `uri` if neither it nor its dependencies have been changed.
+    pub(crate) fn get_summary(&self, uri: &NormalizedUrl) -> Option<ModuleSummary> {
+        let code = self.file_cache.get_entire_code(uri).ok()?;
+        self.summaries.get(uri, &code, |dep| {
+            self.file_cache
+                .get_entire_code(&NormalizedUrl::new(dep.clone()))
+                .ok()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_summary_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("els_summary_{}", std::process::id()))
+            .join(format!("{name}.json"))
+    }
+
+    fn url(name: &str) -> Url {
+        Url::parse(&format!("file:///workspace/{name}.er")).unwrap()
+    }
+
+    fn summary(code: &str, deps: Vec<(Url, u64)>) -> ModuleSummary {
+        let var = VarSummary {
+            name: "x".to_string(),
+            t: "Int".to_string(),
+            range: None,
+            refs: vec![],
+        };
+        ModuleSummary::new(code, deps, vec![], vec![var])
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = temp_summary_path("save_and_load");
+        let cache = SummaryCache::load(path.clone());
+        assert_eq!(cache.len(), 0);
+        let main = NormalizedUrl::new(url("main"));
+        cache.insert(main.clone(), summary("x = 1", vec![]));
+        cache.save().unwrap();
+        let loaded = SummaryCache::load(path.clone());
+        assert_eq!(loaded.len(), 1);
+        let summary = loaded.get(&main, "x = 1", |_| None).unwrap();
+        assert_eq!(summary.get_var("x").unwrap().t, "Int");
+        assert!(loaded.get(&main, "x = 2", |_| None).is_none());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_save_debounced() {
+        let path = temp_summary_path("save_debounced");
+        let cache = SummaryCache::load(path.clone());
+        cache.insert(NormalizedUrl::new(url("main")), summary("x = 1", vec![]));
+        // just loaded
+        cache.save_debounced().unwrap();
+        assert!(!path.exists());
+        cache.save().unwrap();
+        assert!(path.exists());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let path = temp_summary_path("version_mismatch");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = SummaryFile {
+            version: "0.0.0".to_string(),
+            modules: vec![(url("main"), summary("x = 1", vec![]))],
+        };
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        assert_eq!(SummaryCache::load(path.clone()).len(), 0);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_dependency_changed() {
+        let cache = SummaryCache::new();
+        let main = NormalizedUrl::new(url("main"));
+        let deps = vec![(url("foo"), hash_code(".x = 1"))];
+        cache.insert(main.clone(), summary("foo = import \"foo\"", deps));
+        let code_of = |code: &'static str| move |_: &Url| Some(code.to_string());
+        assert!(cache
+            .get(&main, "foo = import \"foo\"", code_of(".x = 1"))
+            .is_some());
+        assert!(cache
+            .get(&main, "foo = import \"foo\"", code_of(".x = \"a\""))
+            .is_none());
+        // the dependency has been removed
+        assert!(cache.get(&main, "foo = import \"foo\"", |_| None).is_none());
+    }
+}
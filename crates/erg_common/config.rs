@@ -4,15 +4,18 @@
 use std::env;
 use std::fmt;
 use std::io::{stdin, IsTerminal, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::str::FromStr;
 
+use crate::env::erg_std_path;
 use crate::help_messages::{command_message, mode_message, OPTIONS};
 use crate::io::{Input, Output};
 use crate::levenshtein::get_similar_name;
 use crate::normalize_path;
+use crate::pathutil::{remove_verbatim, squash};
 use crate::python_util::{detect_magic_number, get_python_version, PythonVersion};
+use crate::registry::Registry;
 use crate::serialize::{get_magic_num_from_bytes, get_ver_from_magic_num};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -27,6 +30,122 @@ pub enum ErgMode {
     Execute,
     LanguageServer,
     Read,
+    GenDecl,
+    Traceback,
+    Fix,
+    Pack,
+    Build,
+    Explain,
+    Test,
+}
+
+/// The backend that the compiler generates code for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CompileTarget {
+    /// Python bytecode (`.pyc`)
+    #[default]
+    Python,
+    /// WebAssembly (`.wasm`, experimental)
+    Wasm,
+}
+
+impl TryFrom<&str> for CompileTarget {
+    type Error = ();
+    fn try_from(s: &str) -> Result<Self, ()> {
+        match s {
+            "python" | "py" | "pyc" => Ok(Self::Python),
+            "wasm" | "webassembly" => Ok(Self::Wasm),
+            _ => Err(()),
+        }
+    }
+}
+
+impl CompileTarget {
+    pub const fn is_wasm(&self) -> bool {
+        matches!(self, Self::Wasm)
+    }
+}
+
+/// A set of optional checks (`--strictness`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Strictness {
+    #[default]
+    Default,
+    /// In addition to the default checks:
+    /// * `Int / Int` requires `//`, an explicit conversion (e.g. `Float(x) / y`), or a proof of divisibility
+    Strict,
+}
+
+impl TryFrom<&str> for Strictness {
+    type Error = ();
+    fn try_from(s: &str) -> Result<Self, ()> {
+        match s {
+            "default" => Ok(Self::Default),
+            "strict" => Ok(Self::Strict),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Strictness {
+    pub const fn checks_int_div(&self) -> bool {
+        matches!(self, Self::Strict)
+    }
+}
+
+/// A root directory searched for Erg modules (see `ErgConfig::module_search_order`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleRoot {
+    /// the directory of the importing module
+    Project,
+    /// `ErgConfig::search_paths` (e.g. the source directories of the dependencies)
+    Deps,
+    /// the Erg standard library (`std`)
+    Std,
+}
+
+impl TryFrom<&str> for ModuleRoot {
+    type Error = ();
+    fn try_from(s: &str) -> Result<Self, ()> {
+        match s {
+            "project" | "local" => Ok(Self::Project),
+            "deps" | "search-path" => Ok(Self::Deps),
+            "std" => Ok(Self::Std),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ModuleRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Project => write!(f, "project"),
+            Self::Deps => write!(f, "deps"),
+            Self::Std => write!(f, "std"),
+        }
+    }
+}
+
+impl ModuleRoot {
+    pub const DEFAULT_ORDER: [Self; 3] = [Self::Project, Self::Deps, Self::Std];
+
+    /// Parses a comma-separated list of the roots (e.g. `deps,project`).
+    /// The roots not listed are searched after the listed ones, in the default order.
+    pub fn parse_order(s: &str) -> Result<Vec<Self>, String> {
+        let mut order = vec![];
+        for root in s.split(',').map(str::trim) {
+            let root = Self::try_from(root).map_err(|_| root.to_string())?;
+            if !order.contains(&root) {
+                order.push(root);
+            }
+        }
+        for root in Self::DEFAULT_ORDER {
+            if !order.contains(&root) {
+                order.push(root);
+            }
+        }
+        Ok(order)
+    }
 }
 
 impl TryFrom<&str> for ErgMode {
@@ -43,6 +162,13 @@ impl TryFrom<&str> for ErgMode {
             "run" | "execute" => Ok(Self::Execute),
             "server" | "language-server" => Ok(Self::LanguageServer),
             "byteread" | "read" | "reader" => Ok(Self::Read),
+            "gen-decl" | "decl" => Ok(Self::GenDecl),
+            "traceback" | "tb" => Ok(Self::Traceback),
+            "fix" => Ok(Self::Fix),
+            "pack" => Ok(Self::Pack),
+            "build" => Ok(Self::Build),
+            "explain" => Ok(Self::Explain),
+            "test" => Ok(Self::Test),
             _ => Err(()),
         }
     }
@@ -61,6 +187,13 @@ impl From<ErgMode> for &str {
             ErgMode::Execute => "execute",
             ErgMode::LanguageServer => "language-server",
             ErgMode::Read => "read",
+            ErgMode::GenDecl => "gen-decl",
+            ErgMode::Traceback => "traceback",
+            ErgMode::Fix => "fix",
+            ErgMode::Pack => "pack",
+            ErgMode::Build => "build",
+            ErgMode::Explain => "explain",
+            ErgMode::Test => "test",
         }
     }
 }
@@ -84,22 +217,66 @@ pub struct ErgConfig {
     pub py_magic_num: Option<u32>, // the magic number cannot be uniquely determined from `target_version`
     pub py_command: Option<&'static str>,
     pub target_version: Option<PythonVersion>,
+    pub target: CompileTarget,
+    pub strictness: Strictness,
     pub py_server_timeout: u64,
+    /// the maximum nesting depth of the type evaluation (e.g. of projection types) (`--max-type-depth`).
+    /// A more deeply nested type is reported as too complex, instead of overflowing the stack
+    pub max_type_depth: usize,
     pub quiet_repl: bool,
     pub show_type: bool,
+    /// emit a `.pyi` stub alongside the `.pyc` file
+    pub dump_pyi: bool,
+    /// emit the transpiled Python script (`.py`) alongside the `.pyc` file (`--dump-py`)
+    pub dump_py: bool,
+    /// emit a source map (`.er.map`) alongside the `.pyc` file
+    pub dump_source_map: bool,
+    /// drop the imported modules whose bindings are never used before linking (opt-in, since their side effects are also dropped)
+    pub prune_modules: bool,
+    /// cross-check the declarations of the Python modules with the Python environment (`--verify-decls`)
+    pub verify_decls: bool,
+    /// do not emit the doc comments as `__doc__` (`--strip-docs`)
+    pub strip_docs: bool,
+    /// insert runtime type checks where a Python value is bound to a variable with an Erg type (`--check-py-boundary`)
+    pub check_py_boundary: bool,
+    /// lex and parse the imported modules on multiple threads before the type checking (`--parallel-parse`)
+    pub parallel_parse: bool,
+    /// check only the public signatures of the imported Erg modules first, and their bodies when their members are used (`--lazy-import`)
+    pub lazy_import: bool,
+    /// re-run the check whenever the entry file or its imported modules change (`erg check --watch`)
+    pub watch: bool,
+    /// report the panics of the lowering as internal compiler errors instead of aborting (`--no-panic`)
+    pub no_panic: bool,
     pub input: Input,
     pub output: Output,
     pub dist_dir: Option<&'static str>,
+    /// output path of the compilation trace (Chrome trace format)
+    pub trace_output: Option<&'static str>,
+    /// print the time of each phase and module after the compilation (`--timings`)
+    pub timings: bool,
     /// module name to be executed
     pub module: &'static str,
     /// verbosity level for system messages.
     /// * 0: display errors, warns
     /// * 1 (default): display errors, warnings and hints
+    /// * 2: also display the compilation statistics (e.g. the number of the deduplicated constants)
     pub verbose: u8,
     /// needed for `jupyter-erg`
     pub ps1: &'static str,
     pub ps2: &'static str,
     pub runtime_args: Vec<&'static str>,
+    /// additional directories searched for Erg modules (`.er`) and declaration files (`.d.er`),
+    /// e.g. the source directories of the dependencies, third-party signature packs for C extension modules
+    pub search_paths: Vec<PathBuf>,
+    /// directories of declaration files (`.d.er`) which override the others (e.g. corrected declarations of the Python standard library).
+    /// The former has priority over the latter.
+    pub decl_paths: Vec<PathBuf>,
+    /// the order in which the roots are searched for Erg modules (`--module-search-order`)
+    pub module_search_order: Vec<ModuleRoot>,
+    /// a directory without `__init__.er` can be imported as a namespace package (`--namespace-packages`)
+    pub namespace_packages: bool,
+    /// user-defined compile-time flags (`-D key=value`), which can be referred to as `Cfg.key`
+    pub flags: Vec<(String, String)>,
 }
 
 impl Default for ErgConfig {
@@ -112,17 +289,38 @@ impl Default for ErgConfig {
             py_magic_num: None,
             py_command: None,
             target_version: None,
+            target: CompileTarget::Python,
+            strictness: Strictness::Default,
             py_server_timeout: 10,
+            max_type_depth: 256,
             quiet_repl: false,
             show_type: false,
+            dump_pyi: false,
+            dump_py: false,
+            dump_source_map: false,
+            prune_modules: false,
+            verify_decls: false,
+            strip_docs: false,
+            check_py_boundary: false,
+            parallel_parse: false,
+            lazy_import: false,
+            watch: false,
+            no_panic: false,
             input: Input::repl(),
             output: Output::stdout(),
             dist_dir: None,
+            trace_output: None,
+            timings: false,
             module: "<module>",
             verbose: 1,
             ps1: ">>> ",
             ps2: "... ",
             runtime_args: vec![],
+            search_paths: vec![],
+            decl_paths: vec![],
+            module_search_order: ModuleRoot::DEFAULT_ORDER.to_vec(),
+            namespace_packages: false,
+            flags: vec![],
         }
     }
 }
@@ -181,6 +379,131 @@ impl ErgConfig {
         }
     }
 
+    pub fn dump_wasm_path(&self) -> PathBuf {
+        let mut dump_path = self.dump_path();
+        dump_path.set_extension("wasm");
+        dump_path
+    }
+
+    pub fn dump_wasm_filename(&self) -> String {
+        let dump_filename = self.dump_filename();
+        if dump_filename.ends_with(".er") {
+            dump_filename.replace(".er", ".wasm")
+        } else {
+            dump_filename + ".wasm"
+        }
+    }
+
+    pub fn dump_pyz_path(&self) -> PathBuf {
+        let mut dump_path = self.dump_path();
+        dump_path.set_extension("pyz");
+        dump_path
+    }
+
+    pub fn dump_py_path(&self) -> PathBuf {
+        let mut dump_path = self.dump_path();
+        dump_path.set_extension("py");
+        dump_path
+    }
+
+    pub fn dump_pyi_path(&self) -> PathBuf {
+        let mut dump_path = self.dump_path();
+        dump_path.set_extension("pyi");
+        dump_path
+    }
+
+    pub fn dump_source_map_path(&self) -> PathBuf {
+        let mut dump_path = self.dump_path();
+        dump_path.set_extension("er.map");
+        dump_path
+    }
+
+    /// `-o 2` or higher (`--release`) is a release build (like `python -O`, where `__debug__` is `False`)
+    pub const fn is_release(&self) -> bool {
+        self.opt_level >= 2
+    }
+
+    pub fn dump_decl_path(&self) -> PathBuf {
+        let mut dump_path = self.dump_path();
+        dump_path.set_extension("d.er");
+        dump_path
+    }
+
+    /// Resolves a declaration file.
+    ///
+    /// resolution order:
+    /// 1. `decl_paths`
+    /// 2. the local declaration files (see `Input::resolve_decl_path`)
+    /// 3. `search_paths`
+    /// 4. the builtin declaration files (`lib/pystd`, `lib/external`)
+    /// 5. `site-packages`
+    pub fn resolve_decl_path(&self, path: &Path) -> Option<PathBuf> {
+        self.decl_paths
+            .iter()
+            .find_map(|root| Input::resolve_std_decl_path(root, path))
+            .or_else(|| self.input.resolve_decl_path_with(path, &self.search_paths))
+    }
+
+    /// Resolves an Erg module.
+    ///
+    /// resolution order:
+    /// 1. `{root}/{path/to}.er`, `{root}/{path/to}/__init__.er` for each root of `module_search_order`
+    /// 2. `{root}/{path/to}` (a namespace package, only if `namespace_packages` is enabled)
+    pub fn resolve_real_path(&self, path: &Path) -> Option<PathBuf> {
+        self.real_path_candidates(path)
+            .into_iter()
+            .find(|(_, path)| Self::is_module_at(path))
+            .and_then(|(_, path)| path.canonicalize().ok())
+            .map(normalize_path)
+    }
+
+    /// All the modules which `path` may refer to (in the resolution order).
+    /// The first one is the module resolved by `resolve_real_path`, and the others are shadowed by it.
+    pub fn resolve_real_path_all(&self, path: &Path) -> Vec<(ModuleRoot, PathBuf)> {
+        self.real_path_candidates(path)
+            .into_iter()
+            .filter(|(_, path)| Self::is_module_at(path))
+            .filter_map(|(root, path)| Some((root, normalize_path(path.canonicalize().ok()?))))
+            .collect()
+    }
+
+    /// The paths tried by `resolve_real_path` (in the resolution order).
+    pub fn real_path_candidates(&self, path: &Path) -> Vec<(ModuleRoot, PathBuf)> {
+        let mut roots = vec![];
+        for root in self.module_search_order.iter() {
+            match root {
+                ModuleRoot::Project => roots.push((*root, self.input.dir())),
+                ModuleRoot::Deps => {
+                    roots.extend(self.search_paths.iter().map(|dir| (*root, dir.clone())));
+                }
+                ModuleRoot::Std => roots.push((*root, erg_std_path().clone())),
+            }
+        }
+        let mut candidates = vec![];
+        for (root, dir) in roots.iter() {
+            candidates.push((*root, dir.join(format!("{}.er", path.display()))));
+            candidates.push((*root, dir.join(path).join("__init__.er")));
+        }
+        if self.namespace_packages {
+            candidates.extend(roots.into_iter().map(|(root, dir)| (root, dir.join(path))));
+        }
+        candidates
+    }
+
+    /// `.er` files are modules, and the other candidates are namespace packages (directories).
+    fn is_module_at(path: &Path) -> bool {
+        if path.extension().is_some_and(|ext| ext == "er") {
+            path.is_file()
+        } else {
+            path.is_dir()
+        }
+    }
+
+    pub fn resolve_path(&self, path: &Path) -> Option<PathBuf> {
+        self.resolve_real_path(path)
+            .or_else(|| self.resolve_decl_path(path))
+    }
+
     pub fn inherit(&self, path: PathBuf) -> Self {
         let path = normalize_path(path);
         Self {
@@ -199,7 +522,8 @@ impl ErgConfig {
             match &arg[..] {
                 /* Commands */
                 "lex" | "parse" | "desugar" | "typecheck" | "check" | "compile" | "transpile"
-                | "run" | "execute" | "server" | "tc" => {
+                | "run" | "execute" | "server" | "tc" | "gen-decl" | "traceback" | "tb" | "fix"
+                | "pack" | "build" | "explain" | "test" => {
                     cfg.mode = ErgMode::try_from(&arg[..]).unwrap();
                 }
                 /* Options */
@@ -218,6 +542,36 @@ impl ErgConfig {
                 "--compile" | "--dump-as-pyc" => {
                     cfg.mode = ErgMode::Compile;
                 }
+                "--dump-py" => {
+                    cfg.dump_py = true;
+                }
+                "--dump-pyi" => {
+                    cfg.dump_pyi = true;
+                }
+                "--source-map" => {
+                    cfg.dump_source_map = true;
+                }
+                "--prune-modules" => {
+                    cfg.prune_modules = true;
+                }
+                "--verify-decls" => {
+                    cfg.verify_decls = true;
+                }
+                "--strip-docs" => {
+                    cfg.strip_docs = true;
+                }
+                "--check-py-boundary" => {
+                    cfg.check_py_boundary = true;
+                }
+                "--parallel-parse" => {
+                    cfg.parallel_parse = true;
+                }
+                "--lazy-import" => {
+                    cfg.lazy_import = true;
+                }
+                "--watch" => {
+                    cfg.watch = true;
+                }
                 "--language-server" => {
                     cfg.mode = ErgMode::LanguageServer;
                 }
@@ -231,6 +585,13 @@ impl ErgConfig {
                     }
                     process::exit(0);
                 }
+                "--max-type-depth" => {
+                    cfg.max_type_depth = args
+                        .next()
+                        .expect("the value of `--max-type-depth` is not passed")
+                        .parse::<usize>()
+                        .expect("the value of `--max-type-depth` is not a number");
+                }
                 "-m" | "--module" => {
                     let module = args
                         .next()
@@ -249,6 +610,9 @@ impl ErgConfig {
                         process::exit(1);
                     });
                 }
+                "--no-panic" => {
+                    cfg.no_panic = true;
+                }
                 "--ping" => {
                     println!("pong");
                     process::exit(0);
@@ -274,6 +638,9 @@ impl ErgConfig {
                         .parse::<u8>()
                         .expect("the value of `-o` is not a number");
                 }
+                "--release" => {
+                    cfg.opt_level = cfg.opt_level.max(2);
+                }
                 "--output-dir" | "--dest" => {
                     let output_dir = args
                         .next()
@@ -281,6 +648,37 @@ impl ErgConfig {
                         .into_boxed_str();
                     cfg.dist_dir = Some(Box::leak(output_dir));
                 }
+                "--module-search-order" => {
+                    let order = args
+                        .next()
+                        .expect("the value of `--module-search-order` is not passed");
+                    cfg.module_search_order =
+                        ModuleRoot::parse_order(&order).unwrap_or_else(|root| {
+                            eprintln!("invalid module root: {root}");
+                            process::exit(1);
+                        });
+                }
+                "--namespace-packages" => {
+                    cfg.namespace_packages = true;
+                }
+                "--search-path" => {
+                    let path = args
+                        .next()
+                        .expect("the value of `--search-path` is not passed");
+                    cfg.search_paths.push(PathBuf::from(path));
+                }
+                "--decl-path" => {
+                    let path = args
+                        .next()
+                        .expect("the value of `--decl-path` is not passed");
+                    cfg.decl_paths.push(PathBuf::from(path));
+                }
+                "-D" | "--define" => {
+                    let flag = args.next().expect("the value of `--define` is not passed");
+                    // `-D key` is the same as `-D key=true`
+                    let (key, value) = flag.split_once('=').unwrap_or((&flag, "true"));
+                    cfg.flags.push((key.to_string(), value.to_string()));
+                }
                 "--py-command" | "--python-command" => {
                     let py_command = args
                         .next()
@@ -323,6 +721,22 @@ impl ErgConfig {
                 "-t" | "--show-type" => {
                     cfg.show_type = true;
                 }
+                "--target" => {
+                    let target = args.next().expect("the value of `--target` is not passed");
+                    cfg.target = CompileTarget::try_from(&target[..]).unwrap_or_else(|_| {
+                        eprintln!("invalid target: {target}");
+                        process::exit(1);
+                    });
+                }
+                "--strictness" => {
+                    let strictness = args
+                        .next()
+                        .expect("the value of `--strictness` is not passed");
+                    cfg.strictness = Strictness::try_from(&strictness[..]).unwrap_or_else(|_| {
+                        eprintln!("invalid strictness: {strictness}");
+                        process::exit(1);
+                    });
+                }
                 "--target-version" => {
                     let target_version = args
                         .next()
@@ -331,6 +745,16 @@ impl ErgConfig {
                         .expect("the value of `--target-version` is not a valid Python version");
                     cfg.target_version = Some(target_version);
                 }
+                "--timings" => {
+                    cfg.timings = true;
+                }
+                "--trace" => {
+                    let trace_output = args
+                        .next()
+                        .expect("the value of `--trace` is not passed")
+                        .into_boxed_str();
+                    cfg.trace_output = Some(Box::leak(trace_output));
+                }
                 "--verbose" => {
                     cfg.verbose = args
                         .next()
@@ -379,6 +803,11 @@ USAGE:
                     );
                     process::exit(2);
                 }
+                // `erg explain E0004`: the argument is an error code, not a path
+                _ if cfg.mode == ErgMode::Explain => {
+                    cfg.input = Input::str(arg);
+                    break;
+                }
                 _ => {
                     let path = PathBuf::from_str(&arg[..])
                         .unwrap_or_else(|_| panic!("invalid file path: {arg}"));
@@ -393,7 +822,14 @@ USAGE:
                 }
             }
         }
-        if cfg.input.is_repl() && cfg.mode != ErgMode::LanguageServer {
+        // `erg build` without a path builds the package in the current directory
+        // `erg explain` without a code lists all the codes
+        if cfg.input.is_repl()
+            && !matches!(
+                cfg.mode,
+                ErgMode::LanguageServer | ErgMode::Build | ErgMode::Explain
+            )
+        {
             let is_stdin_piped = !stdin().is_terminal();
             let input = if is_stdin_piped {
                 let mut buffer = String::new();
@@ -407,3 +843,344 @@ USAGE:
         cfg
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencySource {
+    /// `name = { path = "..." }` (the root directory of the dependency)
+    Path(PathBuf),
+    /// `name = "1.0.0"` or `name = { version = "1.0.0" }` (fetched from the registry)
+    Registry { version: String },
+}
+
+/// An entry of `[dependencies]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub source: DependencySource,
+}
+
+/// The package manifest (`erg.toml`).
+///
+/// ```toml
+/// [package]
+/// name = "app"
+/// version = "0.1.0"
+/// entry = "src/main.er" # optional
+/// decls = "decls" # optional
+///
+/// [dependencies]
+/// util = { path = "../util" }
+/// json = "1.0.0"
+///
+/// [registry]
+/// index = "https://example.com/erg-index"
+/// ```
+///
+/// Only the subset of TOML above is supported.
+/// The modules of a package are placed in `src`, which is added to `ErgConfig::search_paths` of the dependents.
+/// `decls` is added to `ErgConfig::decl_paths`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageManifest {
+    /// the directory containing `erg.toml`
+    pub root: PathBuf,
+    pub name: String,
+    pub version: String,
+    /// the main module (relative to `root`)
+    pub entry: PathBuf,
+    /// the directory of the declaration files overriding the others (absolute)
+    pub decls: Option<PathBuf>,
+    pub dependencies: Vec<Dependency>,
+    /// the index of the registry (a URL or a directory), see `Registry`
+    pub registry: Option<String>,
+}
+
+/// Removes a comment (`# ...`) outside of strings.
+fn strip_toml_comment(line: &str) -> &str {
+    let mut in_str = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_str = !in_str,
+            '#' if !in_str => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_toml_str(value: &str) -> Option<String> {
+    let value = value.strip_prefix('"')?.strip_suffix('"')?;
+    (!value.contains('"')).then(|| value.to_string())
+}
+
+impl PackageManifest {
+    pub const FILE_NAME: &'static str = "erg.toml";
+
+    pub fn parse(root: PathBuf, src: &str) -> Result<Self, String> {
+        let file = Self::FILE_NAME;
+        let mut section = "";
+        let mut name = None;
+        let mut version = None;
+        let mut entry = None;
+        let mut decls = None;
+        let mut dependencies = vec![];
+        let mut registry = None;
+        for (i, line) in src.lines().enumerate() {
+            let lineno = i + 1;
+            let line = strip_toml_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = header.trim();
+                if !matches!(section, "package" | "dependencies" | "registry") {
+                    return Err(format!("{file}:{lineno}: unknown section `[{section}]`"));
+                }
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("{file}:{lineno}: expected `key = value`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match section {
+                "package" => {
+                    let value = parse_toml_str(value)
+                        .ok_or_else(|| format!("{file}:{lineno}: `{key}` must be a string"))?;
+                    match key {
+                        "name" => name = Some(value),
+                        "version" => version = Some(value),
+                        "entry" => entry = Some(PathBuf::from(value)),
+                        "decls" => decls = Some(squash(root.join(value))),
+                        _ => return Err(format!("{file}:{lineno}: unknown key `{key}`")),
+                    }
+                }
+                "dependencies" => {
+                    let source = if let Some(version) = parse_toml_str(value) {
+                        Some(DependencySource::Registry { version })
+                    } else {
+                        value
+                            .strip_prefix('{')
+                            .and_then(|v| v.strip_suffix('}'))
+                            .and_then(|v| v.split_once('='))
+                            .and_then(|(k, v)| match (k.trim(), parse_toml_str(v.trim())?) {
+                                ("path", path) => {
+                                    Some(DependencySource::Path(squash(root.join(path))))
+                                }
+                                ("version", version) => {
+                                    Some(DependencySource::Registry { version })
+                                }
+                                _ => None,
+                            })
+                    };
+                    let source = source.ok_or_else(|| {
+                        format!(
+                            "{file}:{lineno}: expected `{key} = {{ path = \"...\" }}` or `{key} = \"{{version}}\"`"
+                        )
+                    })?;
+                    dependencies.push(Dependency {
+                        name: key.to_string(),
+                        source,
+                    });
+                }
+                "registry" => {
+                    let value = parse_toml_str(value)
+                        .ok_or_else(|| format!("{file}:{lineno}: `{key}` must be a string"))?;
+                    match key {
+                        // a local index is relative to the package root
+                        "index" if value.contains("://") => registry = Some(value),
+                        "index" => {
+                            registry = Some(squash(root.join(value)).to_string_lossy().into_owned())
+                        }
+                        _ => return Err(format!("{file}:{lineno}: unknown key `{key}`")),
+                    }
+                }
+                _ => return Err(format!("{file}:{lineno}: `{key}` must be in a section")),
+            }
+        }
+        Ok(Self {
+            name: name.ok_or_else(|| format!("{file}: `name` is not specified"))?,
+            version: version.unwrap_or_else(|| "0.1.0".to_string()),
+            entry: entry.unwrap_or_else(|| PathBuf::from("src/main.er")),
+            decls,
+            root,
+            dependencies,
+            registry,
+        })
+    }
+
+    /// Reads `{root}/erg.toml`.
+    pub fn load(root: &Path) -> Result<Self, String> {
+        // `root` must be absolute to resolve `path = "../foo"`
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let root = PathBuf::from(remove_verbatim(&root));
+        let path = root.join(Self::FILE_NAME);
+        let src = std::fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        Self::parse(root, &src)
+    }
+
+    /// Finds the root directory of the package containing `dir`.
+    pub fn find_root(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .find(|dir| dir.join(Self::FILE_NAME).is_file())
+            .map(Path::to_path_buf)
+    }
+
+    pub fn src_dir(&self) -> PathBuf {
+        self.root.join("src")
+    }
+
+    pub fn entry_path(&self) -> PathBuf {
+        self.root.join(&self.entry)
+    }
+
+    /// The registry specified in `[registry]` (the packages are stored in `Registry::default_store`)
+    pub fn registry(&self) -> Option<Registry> {
+        let index = self.registry.as_ref()?;
+        Some(Registry::new(index.clone(), Registry::default_store()))
+    }
+
+    /// Returns the (direct and indirect) dependencies.
+    /// A package comes after its dependencies, and `self` is not included.
+    /// A dependency without `erg.toml` is treated as a package without dependencies.
+    pub fn resolve_dependencies(&self) -> Result<Vec<PackageManifest>, String> {
+        self.resolve_dependencies_with(self.registry().as_ref())
+    }
+
+    /// Same as `resolve_dependencies`, but the registry dependencies (including the indirect ones) are installed from `registry`.
+    pub fn resolve_dependencies_with(
+        &self,
+        registry: Option<&Registry>,
+    ) -> Result<Vec<PackageManifest>, String> {
+        let mut resolved = vec![];
+        let mut stack = vec![self.name.clone()];
+        self.resolve_dependencies_rec(registry, &mut stack, &mut resolved)?;
+        Ok(resolved)
+    }
+
+    fn resolve_dependencies_rec(
+        &self,
+        registry: Option<&Registry>,
+        stack: &mut Vec<String>,
+        resolved: &mut Vec<PackageManifest>,
+    ) -> Result<(), String> {
+        for dep in self.dependencies.iter() {
+            let path = match &dep.source {
+                DependencySource::Path(path) => path.clone(),
+                DependencySource::Registry { version } => {
+                    let Some(registry) = registry else {
+                        return Err(format!(
+                            "the dependency `{}` requires a registry (`[registry]` is not specified)",
+                            dep.name
+                        ));
+                    };
+                    registry.install(&dep.name, version)?
+                }
+            };
+            if !path.is_dir() {
+                return Err(format!(
+                    "the dependency `{}` is not found: {}",
+                    dep.name,
+                    path.display()
+                ));
+            }
+            if resolved.iter().any(|pkg| pkg.root == path) {
+                continue;
+            }
+            let manifest = if path.join(Self::FILE_NAME).is_file() {
+                Self::load(&path)?
+            } else {
+                Self {
+                    root: path,
+                    name: dep.name.clone(),
+                    version: "0.1.0".to_string(),
+                    entry: PathBuf::from("src/main.er"),
+                    decls: None,
+                    dependencies: vec![],
+                    registry: None,
+                }
+            };
+            if stack.contains(&manifest.name) {
+                stack.push(manifest.name);
+                return Err(format!("cyclic dependencies: {}", stack.join(" -> ")));
+            }
+            stack.push(manifest.name.clone());
+            manifest.resolve_dependencies_rec(registry, stack, resolved)?;
+            stack.pop();
+            resolved.push(manifest);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let src = r#"
+# comment
+[package]
+name = "app" # trailing comment
+version = "1.0.0"
+decls = "./decls"
+
+[dependencies]
+util = { path = "../util" }
+json = "1.0.0"
+yaml = { version = "0.3.0" }
+
+[registry]
+index = "https://example.com/index"
+"#;
+        let manifest = PackageManifest::parse(PathBuf::from("/pkg/app"), src).unwrap();
+        assert_eq!(manifest.name, "app");
+        assert_eq!(manifest.version, "1.0.0");
+        assert_eq!(manifest.entry, PathBuf::from("src/main.er"));
+        assert_eq!(manifest.decls, Some(PathBuf::from("/pkg/app/decls")));
+        assert_eq!(
+            manifest.dependencies,
+            vec![
+                Dependency {
+                    name: "util".into(),
+                    source: DependencySource::Path(PathBuf::from("/pkg/util")),
+                },
+                Dependency {
+                    name: "json".into(),
+                    source: DependencySource::Registry {
+                        version: "1.0.0".into()
+                    },
+                },
+                Dependency {
+                    name: "yaml".into(),
+                    source: DependencySource::Registry {
+                        version: "0.3.0".into()
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            manifest.registry.as_deref(),
+            Some("https://example.com/index")
+        );
+        let err = PackageManifest::parse(PathBuf::new(), "[package]\nname = app\n").unwrap_err();
+        assert_eq!(err, "erg.toml:2: `name` must be a string");
+        let err = PackageManifest::parse(PathBuf::new(), "[deps]\n").unwrap_err();
+        assert_eq!(err, "erg.toml:1: unknown section `[deps]`");
+        assert!(PackageManifest::parse(PathBuf::new(), "[package]\nversion = \"1\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_module_search_order() {
+        use ModuleRoot::*;
+        assert_eq!(
+            ModuleRoot::parse_order("deps").unwrap(),
+            [Deps, Project, Std]
+        );
+        assert_eq!(
+            ModuleRoot::parse_order("std, project,std").unwrap(),
+            [Std, Project, Deps]
+        );
+        assert_eq!(ModuleRoot::parse_order("deps,site").unwrap_err(), "site");
+    }
+}
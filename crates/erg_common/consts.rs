@@ -8,3 +8,5 @@ pub const ERG_MODE: bool = !cfg!(feature = "py_compat");
 pub const ELS: bool = cfg!(feature = "els");
 pub const DEBUG_MODE: bool = cfg!(feature = "debug");
 pub const EXPERIMENTAL_MODE: bool = cfg!(feature = "experimental");
+pub const CHECK_INVARIANTS: bool = cfg!(feature = "check_invariants");
+pub const MEM_PROFILE: bool = cfg!(feature = "mem_profile");
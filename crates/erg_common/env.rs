@@ -2,6 +2,7 @@ use std::env::var;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use crate::consts::CASE_SENSITIVE;
 use crate::normalize_path;
 use crate::python_util::get_sys_path;
 use crate::style::colors::*;
@@ -67,6 +68,16 @@ fn _python_site_packages() -> impl Iterator<Item = PathBuf> {
         })
 }
 
+/// `ERG_CASE_SENSITIVE=true|false` overrides the case-sensitivity of the build platform's file system
+fn _case_sensitive_paths() -> bool {
+    match var("ERG_CASE_SENSITIVE").as_deref() {
+        Ok("true" | "1") => true,
+        Ok("false" | "0") => false,
+        _ => CASE_SENSITIVE,
+    }
+}
+
+pub static CASE_SENSITIVE_PATHS: OnceLock<bool> = OnceLock::new();
 pub static ERG_PATH: OnceLock<PathBuf> = OnceLock::new();
 pub static ERG_STD_PATH: OnceLock<PathBuf> = OnceLock::new();
 pub static ERG_STD_DECL_PATH: OnceLock<PathBuf> = OnceLock::new();
@@ -74,6 +85,11 @@ pub static ERG_PYSTD_PATH: OnceLock<PathBuf> = OnceLock::new();
 pub static ERG_EXTERNAL_LIB_PATH: OnceLock<PathBuf> = OnceLock::new();
 pub static PYTHON_SITE_PACKAGES: OnceLock<Vec<PathBuf>> = OnceLock::new();
 
+/// Whether paths (e.g. the keys of `ModuleCache`) are distinguished by case.
+pub fn case_sensitive_paths() -> bool {
+    *CASE_SENSITIVE_PATHS.get_or_init(_case_sensitive_paths)
+}
+
 pub fn erg_path() -> &'static PathBuf {
     ERG_PATH.get_or_init(|| normalize_path(_erg_path())) // .with(|s| s.clone())
 }
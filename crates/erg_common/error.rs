@@ -130,6 +130,161 @@ impl ErrorKind {
     pub fn is_exception(&self) -> bool {
         (200..=255).contains(&(*self as u8))
     }
+
+    /// Returns the name of the Python builtin exception class that corresponds to this kind.
+    /// Compile-time-only kinds (e.g. `PurityError`) are mapped to the closest Python exception.
+    pub const fn to_py_exception(&self) -> &'static str {
+        match self {
+            Self::AssignError | Self::PurityError | Self::HasEffect | Self::MoveError => {
+                "RuntimeError"
+            }
+            Self::AttributeError | Self::RuntimeAttributeError => "AttributeError",
+            Self::BytecodeError | Self::CompilerSystemError | Self::VMSystemError => "SystemError",
+            Self::EnvironmentError | Self::IoError | Self::OSError => "OSError",
+            Self::FeatureError | Self::NotImplementedError => "NotImplementedError",
+            Self::ImportError => "ImportError",
+            Self::IndentationError => "IndentationError",
+            Self::NameError | Self::VisibilityError => "NameError",
+            Self::PatternError | Self::ValueError => "ValueError",
+            Self::SyntaxError | Self::NotConstExpr => "SyntaxError",
+            Self::TabError => "TabError",
+            Self::TypeError
+            | Self::RuntimeTypeError
+            | Self::InheritanceError
+            | Self::MethodError
+            | Self::DummyError => "TypeError",
+            Self::UnboundLocalError => "UnboundLocalError",
+            Self::AttributeWarning
+            | Self::CastWarning
+            | Self::TypeWarning
+            | Self::NameWarning
+            | Self::UnusedWarning
+            | Self::Warning => "Warning",
+            Self::DeprecationWarning => "DeprecationWarning",
+            Self::FutureWarning => "FutureWarning",
+            Self::ImportWarning => "ImportWarning",
+            Self::PendingDeprecationWarning => "PendingDeprecationWarning",
+            Self::SyntaxWarning => "SyntaxWarning",
+            Self::ArithmeticError => "ArithmeticError",
+            Self::AssertionError => "AssertionError",
+            Self::BlockingIOError => "BlockingIOError",
+            Self::BrokenPipeError => "BrokenPipeError",
+            Self::BufferError => "BufferError",
+            Self::ChildProcessError => "ChildProcessError",
+            Self::ConnectionAbortedError => "ConnectionAbortedError",
+            Self::ConnectionError => "ConnectionError",
+            Self::ConnectionRefusedError => "ConnectionRefusedError",
+            Self::ConnectionResetError => "ConnectionResetError",
+            Self::EOFError => "EOFError",
+            Self::FileExistsError => "FileExistsError",
+            Self::FileNotFoundError => "FileNotFoundError",
+            Self::IndexError => "IndexError",
+            Self::InterruptedError => "InterruptedError",
+            Self::IsADirectoryError => "IsADirectoryError",
+            Self::KeyError => "KeyError",
+            Self::LookupError => "LookupError",
+            Self::MemoryError => "MemoryError",
+            Self::ModuleNotFoundError => "ModuleNotFoundError",
+            Self::NotADirectoryError => "NotADirectoryError",
+            Self::OverflowError => "OverflowError",
+            Self::PermissionError => "PermissionError",
+            Self::ProcessLookupError => "ProcessLookupError",
+            Self::RecursionError => "RecursionError",
+            Self::ReferenceError => "ReferenceError",
+            Self::RuntimeError => "RuntimeError",
+            Self::RuntimeUnicodeError | Self::UnicodeError => "UnicodeError",
+            Self::TimeoutError => "TimeoutError",
+            Self::UserError | Self::UserException | Self::Exception => "Exception",
+            Self::WindowsError => "WindowsError",
+            Self::ZeroDivisionError => "ZeroDivisionError",
+            Self::BytesWarning => "BytesWarning",
+            Self::ResourceWarning => "ResourceWarning",
+            Self::RuntimeWarning => "RuntimeWarning",
+            Self::UnicodeWarning => "UnicodeWarning",
+            Self::UserWarning => "UserWarning",
+            Self::BaseException => "BaseException",
+            Self::GeneratorExit => "GeneratorExit",
+            Self::KeyboardInterrupt => "KeyboardInterrupt",
+            Self::StopAsyncIteration => "StopAsyncIteration",
+            Self::StopIteration => "StopIteration",
+            Self::SystemExit => "SystemExit",
+        }
+    }
+
+    /// The reverse of `to_py_exception`.
+    /// Python exceptions are mapped to the runtime variants (e.g. `TypeError` -> `RuntimeTypeError`).
+    /// Returns `None` if `name` is not a Python builtin exception class.
+    pub fn from_py_exception(name: &str) -> Option<Self> {
+        match name {
+            "AttributeError" => Some(Self::RuntimeAttributeError),
+            "TypeError" => Some(Self::RuntimeTypeError),
+            "UnicodeError"
+            | "UnicodeDecodeError"
+            | "UnicodeEncodeError"
+            | "UnicodeTranslateError" => Some(Self::RuntimeUnicodeError),
+            "SystemError" => Some(Self::VMSystemError),
+            "IOError" | "EnvironmentError" => Some(Self::OSError),
+            "FloatingPointError" => Some(Self::ArithmeticError),
+            "Exception" => Some(Self::Exception),
+            "NotImplementedError"
+            | "ImportError"
+            | "IndentationError"
+            | "NameError"
+            | "SyntaxError"
+            | "TabError"
+            | "UnboundLocalError"
+            | "ArithmeticError"
+            | "AssertionError"
+            | "BlockingIOError"
+            | "BrokenPipeError"
+            | "BufferError"
+            | "ChildProcessError"
+            | "ConnectionAbortedError"
+            | "ConnectionError"
+            | "ConnectionRefusedError"
+            | "ConnectionResetError"
+            | "EOFError"
+            | "FileExistsError"
+            | "FileNotFoundError"
+            | "IndexError"
+            | "InterruptedError"
+            | "IsADirectoryError"
+            | "KeyError"
+            | "LookupError"
+            | "MemoryError"
+            | "ModuleNotFoundError"
+            | "NotADirectoryError"
+            | "OSError"
+            | "OverflowError"
+            | "PermissionError"
+            | "ProcessLookupError"
+            | "RecursionError"
+            | "ReferenceError"
+            | "RuntimeError"
+            | "TimeoutError"
+            | "ValueError"
+            | "WindowsError"
+            | "ZeroDivisionError"
+            | "Warning"
+            | "DeprecationWarning"
+            | "FutureWarning"
+            | "ImportWarning"
+            | "PendingDeprecationWarning"
+            | "SyntaxWarning"
+            | "BytesWarning"
+            | "ResourceWarning"
+            | "RuntimeWarning"
+            | "UnicodeWarning"
+            | "UserWarning"
+            | "BaseException"
+            | "GeneratorExit"
+            | "KeyboardInterrupt"
+            | "StopAsyncIteration"
+            | "StopIteration"
+            | "SystemExit" => Some(Self::from(name)),
+            _ => None,
+        }
+    }
 }
 
 impl From<&str> for ErrorKind {
@@ -735,6 +890,26 @@ impl SubMessage {
     }
 }
 
+/// A stable identifier of an error (e.g. `E0004`).
+/// Unlike `ErrorCore.errno`, this does not change between versions, so it can be used to look up the description (`erg explain`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ErrorCode(pub u16);
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "E{:04}", self.0)
+    }
+}
+
+impl std::str::FromStr for ErrorCode {
+    type Err = ();
+    /// `E0004`, `e0004` and `4` are all accepted.
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let num = s.strip_prefix(['E', 'e']).unwrap_or(s);
+        num.parse::<u16>().map(Self).map_err(|_| ())
+    }
+}
+
 /// In Erg, common parts used by error.
 /// Must be wrap when to use.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -744,6 +919,7 @@ pub struct ErrorCore {
     pub errno: usize,
     pub kind: ErrorKind,
     pub loc: Location,
+    pub code: Option<ErrorCode>,
     theme: Theme,
 }
 
@@ -769,10 +945,16 @@ impl ErrorCore {
             errno,
             kind,
             loc,
+            code: None,
             theme: THEME,
         }
     }
 
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     pub fn dummy(errno: usize) -> Self {
         Self::new(
             vec![SubMessage::only_loc(Location::Unknown)],
@@ -888,7 +1070,7 @@ impl ErrorCore {
 /// {offset}        :         {sub_msgs}
 /// {offset}        :         {.hint}
 ///
-/// {.kind}: {.desc}
+/// {.kind}[{.code}]: {.desc}
 ///
 /// ```
 ///
@@ -943,6 +1125,9 @@ pub trait ErrorDisplay {
             msg += &sub_msg.format_code_and_pointer(self, color, gutter_color, mark, chars);
         }
         msg += &core.kind.to_string();
+        if let Some(code) = core.code {
+            msg += &format!("[{code}]");
+        }
         msg += ": ";
         msg += &core.main_message;
         msg += "\n\n";
@@ -17,12 +17,33 @@ OPTIONS
     --version/-V                         バージョンを表示
     --verbose 0|1|2                      冗長性レベルを指定
     --opt-level/-o 0|1|2|3               最適化レベルを指定
+    --release                            リリースビルド(-o 2と同じ。debug_only!のブロックを除去)
     --python-version/-p (uint 32 number) Pythonバージョンを指定
     --py-server-timeout (uint 64 number) PythonのREPLサーバーのタイムアウト時間を指定
+    --max-type-depth (uint number)       型の評価のネストの深さの上限を指定(デフォルト: 256)
     --dump-as-pyc                        .pycファイルにダンプ
     --mode (mode)                        指定モードで実行(詳細は--mode --helpを参照)
     --code/-c (string)                   文字列として渡したプログラムを実行
     --module/-m (string)                 モジュールを実行
+    --timings                            各フェーズ・モジュールの処理時間を集計して表示
+    --trace (path)                       各フェーズの処理時間をChrome trace形式で出力
+    --search-path (path)                 宣言ファイル(.d.er)を探索するディレクトリを追加
+    --decl-path (path)                   他より優先される宣言ファイル(.d.er)のディレクトリを追加
+    --define/-D (key=value)              コンパイル時フラグを定義(Cfg.keyで参照できる)
+    --source-map                         ソースマップ(.er.map)を.pycファイルと共に出力
+    --dump-py                            トランスパイルしたPythonスクリプト(.py)を.pycファイルと共に出力
+    --prune-modules                      使用されないモジュールをリンクしない(モジュールの副作用も除去される)
+    --verify-decls                       Pythonモジュールの宣言を実行環境と照合する(モジュールがインポートされる)
+    --strip-docs                         ドキュメントコメントを__doc__として出力しない
+    --check-py-boundary                  Pythonの値を型指定された変数に束縛する際に実行時の型検査を挿入
+    --parallel-parse                     インポートされるモジュールを型検査の前に並列に構文解析
+    --lazy-import                        インポートされるモジュールの公開シグネチャのみを先に検査し、本体はメンバが使われたときに検査
+    --module-search-order (roots)        Ergモジュールを探索するルートの順序を指定(デフォルト: project,deps,std)
+    --namespace-packages                 __init__.erのないディレクトリを名前空間パッケージとしてインポート可能にする
+    --watch                              ファイルの変更を監視して再検査(checkモードのみ)
+    --no-panic                           コンパイラのパニックを中断せずに内部エラーとして報告
+    --strictness default|strict          検査の厳しさを指定(strictではInt / Intに//か明示的な変換が必要)
+    --target python|wasm                 コンパイルターゲットを指定(wasmは実験的)
 
 COMMAND
     lex                                  字句解析
@@ -32,7 +53,14 @@ COMMAND
     compile                              コンパイル
     transpile                            トランスパイル
     run|exec                             実行(デフォルト)
-    server                               言語サーバーを起動",
+    server                               言語サーバーを起動
+    gen-decl                             Pythonモジュールから宣言ファイル(.d.er)を生成
+    traceback|tb                         PythonのトレースバックをErgのソース位置に変換
+    fix                                  非推奨の構文を自動で書き換え
+    pack                                 依存モジュールと共に単一の実行可能ファイル(.pyz)にまとめる
+    build                                erg.tomlのパッケージを依存関係の順にビルドする
+    test <filename>                      テスト関数(test_*, @Test)を実行する
+    explain <code>                       エラーコード(E0004など)の説明を表示",
 
     "simplified_chinese" =>
     "\
@@ -48,12 +76,33 @@ OPTIONS
     --version/-V                         显示版本
     --verbose 0|1|2                      指定细致程度
     --opt-level/-o 0|1|2|3               指定优化级别
+    --release                            发布构建(同 -o 2，移除 debug_only! 块)
     --python-version/-p (uint 32 number) Python 版本
     --py-server-timeout (uint 64 number) 指定等待 REPL 输出的秒数
+    --max-type-depth (uint number)       指定类型求值的最大嵌套深度(默认: 256)
     --dump-as-pyc                        转储为 .pyc 文件
     --mode (mode)                        执行模式 (更多信息见`--mode --help`)
     --code/-c (string)                   作为字符串传入程序
     --module/-m (string)                 要执行的模块
+    --timings                            汇总并显示各阶段、各模块的耗时
+    --trace (path)                       以 Chrome trace 格式输出各阶段的耗时
+    --search-path (path)                 添加搜索声明文件(.d.er)的目录
+    --decl-path (path)                   添加优先于其他声明文件(.d.er)的目录
+    --define/-D (key=value)              定义编译时标志(可通过 Cfg.key 引用)
+    --source-map                         同时输出源映射(.er.map)与 .pyc 文件
+    --dump-py                            同时输出转译后的 Python 脚本(.py)与 .pyc 文件
+    --prune-modules                      不链接未使用的模块(模块的副作用也会被移除)
+    --verify-decls                       将 Python 模块的声明与运行环境进行核对(模块会被导入)
+    --strip-docs                         不将文档注释输出为 __doc__
+    --check-py-boundary                  在将 Python 值绑定到指定了类型的变量时插入运行时类型检查
+    --parallel-parse                     在类型检查之前并行地对导入的模块进行语法分析
+    --lazy-import                        先只检查导入模块的公开签名，在使用其成员时再检查模块主体
+    --module-search-order (roots)        指定搜索 Erg 模块的根目录顺序(默认: project,deps,std)
+    --namespace-packages                 允许将没有 __init__.er 的目录作为命名空间包导入
+    --watch                              监视文件的变更并重新检查(仅 check 模式)
+    --no-panic                           将编译器的 panic 报告为内部错误而不中止
+    --strictness default|strict          检查的严格程度(strict 下 Int / Int 需要 // 或显式转换)
+    --target python|wasm                 指定编译目标(wasm 为实验性)
 
 COMMAND
     lex                                  字词解析
@@ -63,7 +112,14 @@ COMMAND
     compile                              编译
     transpile                            转译
     run|exec                             执行(默认模式)
-    server                               执行语言服务器",
+    server                               执行语言服务器
+    gen-decl                             从 Python 模块生成声明文件(.d.er)
+    traceback|tb                         将 Python 回溯转换为 Erg 源代码位置
+    fix                                  自动重写已弃用的语法
+    pack                                 与依赖模块一起打包为单个可执行文件(.pyz)
+    build                                按依赖顺序构建erg.toml中的包
+    test <filename>                      运行测试函数(test_*, @Test)
+    explain <code>                       显示错误代码(如E0004)的说明",
 
     "traditional_chinese" =>
         "\
@@ -79,12 +135,33 @@ OPTIONS
     --version/-V                         顯示版本
     --verbose 0|1|2                      指定細緻程度
     --opt-level/-o 0|1|2|3               指定優化級別
+    --release                            發布建置(同 -o 2，移除 debug_only! 區塊)
     --python-version/-p (uint 32 number) Python 版本
     --py-server-timeout (uint 64 number) 指定等待 REPL 輸出的秒數
+    --max-type-depth (uint number)       指定類型求值的最大嵌套深度(默認: 256)
     --dump-as-pyc                        轉儲為 .pyc 文件
     --mode (mode)                        執行模式 (更多信息見`--mode --help`)
     --code/-c (string)                   作為字串傳入程式
     --module/-m (string)                 要執行的模塊
+    --timings                            彙總並顯示各階段、各模組的耗時
+    --trace (path)                       以 Chrome trace 格式輸出各階段的耗時
+    --search-path (path)                 添加搜索聲明文件(.d.er)的目錄
+    --decl-path (path)                   添加優先於其他聲明文件(.d.er)的目錄
+    --define/-D (key=value)              定義編譯時標誌(可通過 Cfg.key 引用)
+    --source-map                         同時輸出源映射(.er.map)與 .pyc 文件
+    --dump-py                            同時輸出轉譯後的 Python 腳本(.py)與 .pyc 文件
+    --prune-modules                      不鏈接未使用的模塊(模塊的副作用也會被移除)
+    --verify-decls                       將 Python 模組的聲明與執行環境進行核對(模組會被匯入)
+    --strip-docs                         不將文件註解輸出為 __doc__
+    --check-py-boundary                  在將 Python 值綁定到指定了類型的變數時插入執行時類型檢查
+    --parallel-parse                     在類型檢查之前並行地對匯入的模組進行語法分析
+    --lazy-import                        先只檢查匯入模組的公開簽名，在使用其成員時再檢查模組主體
+    --module-search-order (roots)        指定搜尋 Erg 模組的根目錄順序(預設: project,deps,std)
+    --namespace-packages                 允許將沒有 __init__.er 的目錄作為命名空間套件匯入
+    --watch                              監視檔案的變更並重新檢查(僅 check 模式)
+    --no-panic                           將編譯器的 panic 報告為內部錯誤而不中止
+    --strictness default|strict          檢查的嚴格程度(strict 下 Int / Int 需要 // 或顯式轉換)
+    --target python|wasm                 指定編譯目標(wasm 為實驗性)
 
 COMMAND
     lex                                  字詞解析
@@ -94,7 +171,14 @@ COMMAND
     compile                              編譯
     transpile                            轉譯
     run|exec                             執行(預設模式)
-    server                               執行語言伺服器",
+    server                               執行語言伺服器
+    gen-decl                             從 Python 模塊生成聲明文件(.d.er)
+    traceback|tb                         將 Python 回溯轉換為 Erg 原始碼位置
+    fix                                  自動重寫已棄用的語法
+    pack                                 與依賴模塊一起打包為單個可執行文件(.pyz)
+    build                                按依賴順序構建erg.toml中的包
+    test <filename>                      運行測試函數(test_*, @Test)
+    explain <code>                       顯示錯誤代碼(如E0004)的說明",
 
     "english" =>
         "\
@@ -110,12 +194,33 @@ OPTIONS
     --version/-V                         show version
     --verbose 0|1|2                      verbosity level
     --opt-level/-o 0|1|2|3               optimization level
+    --release                            release build (same as -o 2, removes the debug_only! blocks)
     --python-version/-p (uint 32 number) Python version
     --py-server-timeout (uint 64 number) timeout for the Python REPL server
+    --max-type-depth (uint number)       maximum nesting depth of the type evaluation (default: 256)
     --dump-as-pyc                        dump as .pyc file
     --mode (mode)                        execution mode (See `--mode --help` for details)
     --code/-c (string)                   program passed in as string
     --module/-m (string)                 module to be executed
+    --timings                            print a summary of the time of each phase and module
+    --trace (path)                       output the time of each phase in the Chrome trace format
+    --search-path (path)                 add a directory to search for declaration files (.d.er)
+    --decl-path (path)                   add a directory of declaration files (.d.er) overriding the others
+    --define/-D (key=value)              define a compile-time flag (referred to as Cfg.key)
+    --source-map                         emit a source map (.er.map) alongside the .pyc file
+    --dump-py                            emit the transpiled Python script (.py) alongside the .pyc file
+    --prune-modules                      do not link unused modules (their side effects are also removed)
+    --verify-decls                       cross-check the declarations of Python modules with the runtime (the modules are imported)
+    --strip-docs                         do not emit the doc comments as __doc__
+    --check-py-boundary                  insert runtime type checks where Python values are bound to typed variables
+    --parallel-parse                     parse the imported modules in parallel before the type checking
+    --lazy-import                        check only the public signatures of the imported modules first, and their bodies when their members are used
+    --module-search-order (roots)        order of the roots searched for Erg modules (default: project,deps,std)
+    --namespace-packages                 allow importing a directory without __init__.er as a namespace package
+    --watch                              re-check whenever the file or its imported modules change (check mode only)
+    --no-panic                           report the panics of the compiler as internal errors instead of aborting
+    --strictness default|strict          strictness of the checks (strict: Int / Int requires // or an explicit conversion)
+    --target python|wasm                 compilation target (wasm is experimental)
 
 COMMAND
     lex                                  lexical analysis
@@ -125,7 +230,14 @@ COMMAND
     compile                              compile
     transpile                            transpile
     run|exec                             execute (default mode)
-    server                               execute language server",
+    server                               execute language server
+    gen-decl                             generate a declaration file (.d.er) from a Python module
+    traceback|tb                         translate a Python traceback into Erg source locations
+    fix                                  rewrite deprecated constructs automatically
+    pack                                 bundle the script and its dependencies into a single executable file (.pyz)
+    build                                build the package of erg.toml in dependency order
+    test <filename>                      run the test functions (test_*, @Test)
+    explain <code>                       show the description of an error code (e.g. E0004)",
     )
 }
 
@@ -134,7 +246,7 @@ pub fn mode_message<'a>() -> &'a str {
         "japanese" =>
         "\
 USAGE:
-    erg --mode [lex | parse | lower | check | compile | exec | read] [SUBCOMMAND] [ARGS]...
+    erg --mode [lex | parse | lower | check | compile | exec | read | gen-decl | traceback] [SUBCOMMAND] [ARGS]...
 
 lex
     <filename>.erやREPLなどから入力を受け取り、字句を解析
@@ -164,12 +276,18 @@ run/exec
     compileを実行し、更に<filename>.pycを実行
 
 read
-    <filename>.pycをデシリアライズしコードオブジェクトの情報をダンプ",
+    <filename>.pycをデシリアライズしコードオブジェクトの情報をダンプ
+
+gen-decl
+    <filename>.pyの公開APIを解析し、宣言ファイルの雛形<filename>.d.erを出力
+
+traceback
+    標準入力(または<filename>)のPythonのトレースバックを、ソースマップ(<filename>.er.map)を用いてErgのソース位置に変換",
 
     "simplified_chinese" =>
     "\
 USAGE:
-    erg --mode [lex | parse | lower | check | compile | exec | read] [SUBCOMMAND] [ARGS]...
+    erg --mode [lex | parse | lower | check | compile | exec | read | gen-decl | traceback] [SUBCOMMAND] [ARGS]...
 
 lex
     从 <filename>.er, REPL 等接受输入, 并标记文本
@@ -200,12 +318,18 @@ run/exec
     在执行 <文件名>.pyc 后删除 <文件名>.pyc
 
 read
-    反序列化 <文件名>.pyc 和 dump",
+    反序列化 <文件名>.pyc 和 dump
+
+gen-decl
+    分析 <文件名>.py 的公开 API, 并输出声明文件模板 <文件名>.d.er
+
+traceback
+    使用源映射(<文件名>.er.map)将标准输入(或 <文件名>)中的 Python 回溯转换为 Erg 源代码位置",
 
     "traditional_chinese" =>
     "\
 USAGE:
-        erg --mode [lex | parse | lower | check | compile | exec | read] [SUBCOMMAND] [ARGS]...
+        erg --mode [lex | parse | lower | check | compile | exec | read | gen-decl | traceback] [SUBCOMMAND] [ARGS]...
 
 lex
     從 <檔名>.er, REPL 等接受輸入, 並標記文字
@@ -236,12 +360,18 @@ exec
     在執行 <檔名>.pyc 後删除 <檔名>.pyc
 
 read
-    反序列化 <檔名>.pyc 和 dump",
+    反序列化 <檔名>.pyc 和 dump
+
+gen-decl
+    分析 <檔名>.py 的公開 API, 並輸出聲明文件模板 <檔名>.d.er
+
+traceback
+    使用源映射(<檔名>.er.map)將標準輸入(或 <檔名>)中的 Python 回溯轉換為 Erg 原始碼位置",
 
     "english" =>
     "\
 USAGE:
-    erg --mode [lex | parse | lower | check | compile | exec | read] [SUBCOMMAND] [ARGS]...
+    erg --mode [lex | parse | lower | check | compile | exec | read | gen-decl | traceback] [SUBCOMMAND] [ARGS]...
 
 lex
     Receive input from <filename>.er, REPL, etc. and lex the text
@@ -271,7 +401,13 @@ run/exec
     Execute compile and then <filename>.pyc
 
 read
-    Deserialize <filename>.pyc and dump code object information",
+    Deserialize <filename>.pyc and dump code object information
+
+gen-decl
+    Analyze the public API of <filename>.py and output a skeleton declaration file <filename>.d.er
+
+traceback
+    Translate a Python traceback from stdin (or <filename>) into Erg source locations using the source maps (<filename>.er.map)",
     )
 }
 
@@ -280,26 +416,40 @@ pub const OPTIONS: &[&str] = &[
     "-c",
     "--code",
     "--check",
+    "--check-py-boundary",
     "--compile",
+    "--decl-path",
+    "--define",
+    "-D",
     "--dest",
     "--dump-as-pyc",
+    "--dump-py",
+    "--dump-pyi",
     "--language-server",
+    "--lazy-import",
+    "--no-panic",
     "--no-std",
     "--help",
     "-?",
     "-h",
     "--hex-py-magic-num",
     "--hex-python-magic-number",
+    "--max-type-depth",
     "--mode",
     "--module",
     "-m",
+    "--module-search-order",
+    "--namespace-packages",
     "--optimization-level",
     "--opt-level",
     "-o",
     "--output-dir",
+    "--parallel-parse",
     "--ping",
     "--ps1",
     "--ps2",
+    "--prune-modules",
+    "--release",
     "--python-version",
     "-p",
     "--py-server-timeout",
@@ -309,10 +459,19 @@ pub const OPTIONS: &[&str] = &[
     "--python-magic-number",
     "--quiet-startup",
     "--quiet-repl",
+    "--search-path",
+    "--source-map",
     "--show-type",
     "-t",
+    "--strictness",
+    "--strip-docs",
+    "--target",
     "--target-version",
+    "--timings",
+    "--trace",
     "--version",
     "-V",
     "--verbose",
+    "--verify-decls",
+    "--watch",
 ];
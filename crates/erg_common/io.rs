@@ -545,6 +545,21 @@ impl Input {
         ))
     }
 
+    /// The local paths tried by `resolve_decl_path` (in the resolution order).
+    pub fn local_decl_path_candidates(&self, path: &Path) -> Vec<PathBuf> {
+        let local = self.dir().join(path);
+        let Some(last) = path.file_name() else {
+            return vec![];
+        };
+        let parent = local.parent().map_or(PathBuf::new(), Path::to_path_buf);
+        vec![
+            local.with_extension("d.er"),
+            local.join("__init__.d.er"),
+            parent.join("__pycache__").join(last).with_extension("d.er"),
+            local.join("__pycache__").join("__init__.d.er"),
+        ]
+    }
+
     pub fn resolve_path(&self, path: &Path) -> Option<PathBuf> {
         self.resolve_real_path(path)
             .or_else(|| self.resolve_decl_path(path))
@@ -556,14 +571,26 @@ impl Input {
     /// 3. `std/{path/to}.er`
     /// 4. `std/{path/to}/__init__.er`
     pub fn resolve_real_path(&self, path: &Path) -> Option<PathBuf> {
+        self.resolve_real_path_with(path, &[])
+    }
+
+    /// Same as `resolve_real_path`, but `search_paths` are searched before `std`.
+    /// The layout of a search path is the same as `std` (`{path/to}.er`, `{path/to}/__init__.er`).
+    pub fn resolve_real_path_with(&self, path: &Path, search_paths: &[PathBuf]) -> Option<PathBuf> {
         if let Ok(path) = self.resolve_local(path) {
-            Some(path)
-        } else if let Ok(path) = erg_std_path()
-            .join(format!("{}.er", path.display()))
-            .canonicalize()
-        {
+            return Some(path);
+        }
+        search_paths
+            .iter()
+            .map(PathBuf::as_path)
+            .chain([erg_std_path().as_path()])
+            .find_map(|root| Self::resolve_std_real_path(root, path))
+    }
+
+    fn resolve_std_real_path(root: &Path, path: &Path) -> Option<PathBuf> {
+        if let Ok(path) = root.join(format!("{}.er", path.display())).canonicalize() {
             Some(normalize_path(path))
-        } else if let Ok(path) = erg_std_path()
+        } else if let Ok(path) = root
             .join(format!("{}", path.display()))
             .join("__init__.er")
             .canonicalize()
@@ -587,6 +614,12 @@ impl Input {
     /// 9.  `site-packages/{path}/__pycache__/{to}.d.er`
     /// 10. `site-packages/{path/to}/__pycache__/__init__.d.er`
     pub fn resolve_decl_path(&self, path: &Path) -> Option<PathBuf> {
+        self.resolve_decl_path_with(path, &[])
+    }
+
+    /// Same as `resolve_decl_path`, but `search_paths` are searched before the builtin declaration files (`std`).
+    /// The layout of a search path is the same as `std` (`{path.d/to}.d.er`, `{path.d/to.d}/__init__.d.er`).
+    pub fn resolve_decl_path_with(&self, path: &Path, search_paths: &[PathBuf]) -> Option<PathBuf> {
         if let Ok(path) = self.resolve_local_decl(self.dir(), path) {
             return Some(path);
         }
@@ -605,6 +638,11 @@ impl Input {
                 }
             }
         }
+        for search_path in search_paths {
+            if let Some(path) = Self::resolve_std_decl_path(search_path, path) {
+                return Some(path);
+            }
+        }
         let py_roots = [erg_pystd_path, erg_py_external_lib_path];
         for root in py_roots {
             if let Some(path) = Self::resolve_std_decl_path(root(), path) {
@@ -621,7 +659,7 @@ impl Input {
 
     /// 1. `site-packages/{path/to}.d.er`
     /// 2. `site-packages/{path.d/to.d}/__init__.d.er`
-    fn resolve_std_decl_path(root: &Path, path: &Path) -> Option<PathBuf> {
+    pub(crate) fn resolve_std_decl_path(root: &Path, path: &Path) -> Option<PathBuf> {
         let mut path = add_postfix_foreach(path, ".d");
         path.set_extension("d.er"); // set_extension overrides the previous one
         if let Ok(path) = root.join(&path).canonicalize() {
@@ -0,0 +1,317 @@
+//! A minimal JSON (RFC 8259) parser.
+//!
+//! Used to evaluate JSON literals at compile time (`parse_json`), so it only reads JSON texts and keeps the order of the object members.
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// A number without a fraction and an exponent that fits in `i64`
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    /// The members in the order of appearance (duplicate names are kept as they are)
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub msg: String,
+    /// 1-origin
+    pub line: usize,
+    /// 1-origin
+    pub col: usize,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.msg, self.line, self.col)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+struct JsonParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            chars: src.chars().peekable(),
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn error(&self, msg: impl Into<String>) -> JsonError {
+        JsonError {
+            msg: msg.into(),
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected '{expected}', but found '{c}'"))),
+            None => Err(self.error(format!("expected '{expected}', but reached the end"))),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_ws();
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::Str),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some('-' | '0'..='9') => self.number(),
+            Some(c) => Err(self.error(format!("unexpected character '{c}'"))),
+            None => Err(self.error("expected a value, but reached the end")),
+        }
+    }
+
+    fn digits(&mut self, text: &mut String) -> Result<(), JsonError> {
+        let len = text.len();
+        while let Some(c @ '0'..='9') = self.peek() {
+            text.push(c);
+            self.next();
+        }
+        if text.len() == len {
+            Err(self.error("expected a digit"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let mut text = String::new();
+        if self.peek() == Some('-') {
+            text.push('-');
+            self.next();
+        }
+        if self.peek() == Some('0') {
+            text.push('0');
+            self.next();
+            if matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("leading zeros are not allowed"));
+            }
+        } else {
+            self.digits(&mut text)?;
+        }
+        let mut is_int = true;
+        if self.peek() == Some('.') {
+            is_int = false;
+            text.push('.');
+            self.next();
+            self.digits(&mut text)?;
+        }
+        if let Some(e @ ('e' | 'E')) = self.peek() {
+            is_int = false;
+            text.push(e);
+            self.next();
+            if let Some(sign @ ('+' | '-')) = self.peek() {
+                text.push(sign);
+                self.next();
+            }
+            self.digits(&mut text)?;
+        }
+        match text.parse::<i64>() {
+            Ok(i) if is_int => Ok(Json::Int(i)),
+            _ => text
+                .parse::<f64>()
+                .map(Json::Float)
+                .map_err(|_| self.error(format!("invalid number: {text}"))),
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let Some(digit) = self.next().and_then(|c| c.to_digit(16)) else {
+                return Err(self.error("invalid unicode escape"));
+            };
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.hex4()?;
+                            // surrogate pair
+                            if (0xD800..0xDC00).contains(&code) {
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(self.error("invalid surrogate pair"));
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        Some(c) => return Err(self.error(format!("invalid escape '\\{c}'"))),
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    s.push(c);
+                }
+                Some(c) if c < ' ' => {
+                    return Err(self.error("control characters must be escaped in strings"))
+                }
+                Some(c) => s.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        let mut elems = vec![];
+        self.skip_ws();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(Json::Array(elems));
+        }
+        loop {
+            elems.push(self.value()?);
+            self.skip_ws();
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Json::Array(elems)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        let mut members = vec![];
+        self.skip_ws();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_ws();
+            let name = self.string()?;
+            self.skip_ws();
+            self.expect(':')?;
+            members.push((name, self.value()?));
+            self.skip_ws();
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(Json::Object(members)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
+
+/// Parses a JSON text. Trailing characters other than whitespaces are errors.
+pub fn parse(src: &str) -> Result<Json, JsonError> {
+    let mut parser = JsonParser::new(src);
+    let value = parser.value()?;
+    parser.skip_ws();
+    match parser.peek() {
+        Some(c) => Err(parser.error(format!("unexpected character '{c}'"))),
+        None => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, Json};
+
+    #[test]
+    fn test_parse_json() {
+        let json = parse(r#" {"a": [1, -2.5e1, true, null], "b": {"c": "あ\n"}} "#).unwrap();
+        assert_eq!(
+            json,
+            Json::Object(vec![
+                (
+                    "a".into(),
+                    Json::Array(vec![
+                        Json::Int(1),
+                        Json::Float(-25.0),
+                        Json::Bool(true),
+                        Json::Null
+                    ])
+                ),
+                (
+                    "b".into(),
+                    Json::Object(vec![("c".into(), Json::Str("あ\n".into()))])
+                ),
+            ])
+        );
+        assert_eq!(parse(r#""😀""#).unwrap(), Json::Str("😀".into()));
+        assert_eq!(parse("99999999999999999999").unwrap(), Json::Float(1e20));
+    }
+
+    #[test]
+    fn test_parse_json_err() {
+        let err = parse("{\"a\": 1,\n \"b\" 2}").unwrap_err();
+        assert_eq!((err.line, err.col), (2, 6));
+        assert!(parse("[1, 2,]").is_err());
+        assert!(parse("01").is_err());
+        assert!(parse("1 2").is_err());
+        assert!(parse("\"a").is_err());
+    }
+}
@@ -14,6 +14,7 @@ pub mod fresh;
 pub mod fxhash;
 pub mod help_messages;
 pub mod io;
+pub mod json;
 pub mod lang;
 pub mod levenshtein;
 pub mod macros;
@@ -24,6 +25,7 @@ pub mod opcode311;
 pub mod pathutil;
 pub mod python_util;
 pub mod random;
+pub mod registry;
 pub mod serialize;
 pub mod set;
 pub mod shared;
@@ -31,12 +33,11 @@ pub mod spawn;
 pub mod stdin;
 pub mod str;
 pub mod style;
+pub mod trace;
 pub mod traits;
 pub mod triple;
 pub mod tsort;
 
-use consts::CASE_SENSITIVE;
-
 use crate::set::Set;
 pub use crate::str::Str;
 pub use crate::triple::Triple;
@@ -155,9 +156,10 @@ pub fn unique_in_place<T: Eq + std::hash::Hash + Clone>(v: &mut Vec<T>) {
 }
 
 /// at least, this is necessary for Windows and macOS
+/// (see `env::case_sensitive_paths`)
 pub fn normalize_path(path: PathBuf) -> PathBuf {
     let verbatim_replaced = path.to_str().unwrap().replace("\\\\?\\", "");
-    let lower = if !CASE_SENSITIVE {
+    let lower = if !env::case_sensitive_paths() {
         verbatim_replaced.to_lowercase()
     } else {
         verbatim_replaced
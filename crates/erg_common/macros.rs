@@ -446,18 +446,22 @@ macro_rules! debug_info {
 #[macro_export]
 macro_rules! log {
     (info $($arg: tt)*) => {{
+        $crate::trace_event!(Info, $($arg)*);
         $crate::log!(c DEBUG_MAIN, $($arg)*);
     }};
 
     (err $($arg: tt)*) => {{
+        $crate::trace_event!(Error, $($arg)*);
         $crate::log!(c DEBUG_ERROR, $($arg)*);
     }};
 
     (info_f $output:ident, $($arg: tt)*) => {{
+        $crate::trace_event!(Info, $($arg)*);
         $crate::log!(f+c $output, DEBUG_MAIN, $($arg)*);
     }};
 
     (err_f $output:ident, $($arg: tt)*) => {{
+        $crate::trace_event!(Error, $($arg)*);
         $crate::log!(f+c $output, DEBUG_ERROR, $($arg)*);
     }};
 
@@ -496,6 +500,7 @@ macro_rules! log {
     }};
 
     ($($arg: tt)*) => {{
+        $crate::trace_event!(Debug, $($arg)*);
         if cfg!(feature = "debug") {
             use $crate::style::*;
             $crate::debug_info!();
@@ -505,6 +510,39 @@ macro_rules! log {
     }};
 }
 
+/// Records a leveled event to the global tracer (see `erg_common::trace`).
+/// The message is formatted only if tracing is enabled.
+#[macro_export]
+macro_rules! trace_event {
+    ($level: ident, $($arg: tt)*) => {{
+        #[allow(unused_imports)]
+        use $crate::style::*;
+        $crate::trace::GLOBAL_TRACER.event(
+            $crate::trace::Level::$level,
+            module_path!(),
+            || format!($($arg)*),
+        );
+    }};
+}
+
+/// Enters a phase span of the global tracer. The span ends when the returned guard is dropped.
+/// If the module is omitted, the span inherits the module of the enclosing span.
+///
+/// ```
+/// # use erg_common::trace_span;
+/// let _span = trace_span!("parse");
+/// let _span = trace_span!("lower", "foo.er");
+/// ```
+#[macro_export]
+macro_rules! trace_span {
+    ($phase: expr) => {
+        $crate::trace::GLOBAL_TRACER.enter($phase, || None)
+    };
+    ($phase: expr, $module: expr) => {
+        $crate::trace::GLOBAL_TRACER.enter($phase, || Some($module.to_string()))
+    };
+}
+
 #[macro_export]
 macro_rules! log_with_time {
     (f $output: ident, $($arg: tt)*) => {
@@ -179,3 +179,196 @@ impl_u8_enum! {BinOpCode;
     InplaceTrueDivide = 24,
     InplaceXor = 25,
 }
+
+impl Opcode311 {
+    pub const fn takes_arg(&self) -> bool {
+        *self as u8 >= 90
+    }
+
+    /// The opcodes that can be used in the inline bytecode (`asm`).
+    /// Jumps, name/constant accesses, and the opcodes that depend on the frame state are not allowed,
+    /// because their effects cannot be verified statically.
+    pub fn from_asm_name(name: &str) -> Option<Self> {
+        match name {
+            "NOP" => Some(Self::NOP),
+            "POP_TOP" => Some(Self::POP_TOP),
+            "UNARY_POSITIVE" => Some(Self::UNARY_POSITIVE),
+            "UNARY_NEGATIVE" => Some(Self::UNARY_NEGATIVE),
+            "UNARY_NOT" => Some(Self::UNARY_NOT),
+            "UNARY_INVERT" => Some(Self::UNARY_INVERT),
+            "BINARY_SUBSCR" => Some(Self::BINARY_SUBSCR),
+            "GET_LEN" => Some(Self::GET_LEN),
+            "STORE_SUBSCR" => Some(Self::STORE_SUBSCR),
+            "GET_ITER" => Some(Self::GET_ITER),
+            "LIST_TO_TUPLE" => Some(Self::LIST_TO_TUPLE),
+            "UNPACK_SEQUENCE" => Some(Self::UNPACK_SEQUENCE),
+            "SWAP" => Some(Self::SWAP),
+            "COPY" => Some(Self::COPY),
+            "BUILD_TUPLE" => Some(Self::BUILD_TUPLE),
+            "BUILD_LIST" => Some(Self::BUILD_LIST),
+            "BUILD_SET" => Some(Self::BUILD_SET),
+            "BUILD_MAP" => Some(Self::BUILD_MAP),
+            "COMPARE_OP" => Some(Self::COMPARE_OP),
+            "IS_OP" => Some(Self::IS_OP),
+            "CONTAINS_OP" => Some(Self::CONTAINS_OP),
+            "BINARY_OP" => Some(Self::BINARY_OP),
+            _ => None,
+        }
+    }
+
+    /// (the number of popped objects, the number of pushed objects)
+    ///
+    /// Returns `None` if the opcode cannot be used in the inline bytecode or `arg` is invalid.
+    pub const fn stack_effect(&self, arg: usize) -> Option<(usize, usize)> {
+        match self {
+            Self::NOP => Some((0, 0)),
+            Self::POP_TOP => Some((1, 0)),
+            Self::UNARY_POSITIVE
+            | Self::UNARY_NEGATIVE
+            | Self::UNARY_NOT
+            | Self::UNARY_INVERT
+            | Self::GET_ITER
+            | Self::LIST_TO_TUPLE => Some((1, 1)),
+            Self::GET_LEN => Some((1, 2)),
+            Self::BINARY_SUBSCR => Some((2, 1)),
+            Self::STORE_SUBSCR => Some((3, 0)),
+            Self::UNPACK_SEQUENCE => Some((1, arg)),
+            Self::SWAP if arg >= 2 => Some((arg, arg)),
+            Self::COPY if arg >= 1 => Some((arg, arg + 1)),
+            Self::BUILD_TUPLE | Self::BUILD_LIST | Self::BUILD_SET => Some((arg, 1)),
+            Self::BUILD_MAP => Some((arg * 2, 1)),
+            Self::COMPARE_OP if arg <= 5 => Some((2, 1)),
+            Self::IS_OP | Self::CONTAINS_OP if arg <= 1 => Some((2, 1)),
+            Self::BINARY_OP if arg <= BinOpCode::InplaceXor as usize => Some((2, 1)),
+            _ => None,
+        }
+    }
+
+    /// The number of the inline cache entries (2 bytes each) following the instruction
+    pub const fn cache_entries(&self) -> usize {
+        match self {
+            Self::LOAD_METHOD => 10,
+            Self::LOAD_GLOBAL => 5,
+            Self::BINARY_SUBSCR | Self::LOAD_ATTR | Self::STORE_ATTR | Self::CALL => 4,
+            Self::COMPARE_OP => 2,
+            Self::BINARY_OP | Self::STORE_SUBSCR | Self::UNPACK_SEQUENCE | Self::PRECALL => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// An instruction of the inline bytecode (`asm`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsmInstr {
+    pub op: Opcode311,
+    pub arg: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownOpcode(String),
+    MissingArg(Opcode311),
+    UnexpectedArg(Opcode311),
+    InvalidArg(Opcode311, String),
+    /// the instruction pops more objects than the stack has
+    StackUnderflow {
+        op: Opcode311,
+        depth: usize,
+    },
+    /// the stack must have exactly one object (the result) at the end
+    Unbalanced {
+        depth: usize,
+    },
+}
+
+/// Parses the inline bytecode.
+/// The instructions are separated by newlines or `;`, and each instruction is `OPNAME [arg]`.
+/// `#` starts a comment.
+pub fn parse_asm(code: &str) -> Result<Vec<AsmInstr>, AsmError> {
+    let mut instrs = vec![];
+    for line in code.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        for instr in line.split(';') {
+            let mut words = instr.split_whitespace();
+            let Some(name) = words.next() else {
+                continue;
+            };
+            let op = Opcode311::from_asm_name(name)
+                .ok_or_else(|| AsmError::UnknownOpcode(name.to_string()))?;
+            let arg = match (words.next(), op.takes_arg()) {
+                (Some(arg), true) => arg
+                    .parse::<usize>()
+                    .map_err(|_| AsmError::InvalidArg(op, arg.to_string()))?,
+                (None, true) => return Err(AsmError::MissingArg(op)),
+                (Some(_), false) => return Err(AsmError::UnexpectedArg(op)),
+                (None, false) => 0,
+            };
+            if let Some(extra) = words.next() {
+                return Err(AsmError::InvalidArg(op, extra.to_string()));
+            }
+            if op.stack_effect(arg).is_none() {
+                return Err(AsmError::InvalidArg(op, arg.to_string()));
+            }
+            instrs.push(AsmInstr { op, arg });
+        }
+    }
+    Ok(instrs)
+}
+
+/// Verifies that the instructions consume the `argc` arguments pushed on the stack and leave exactly one object.
+/// Returns the maximum stack depth.
+pub fn verify_asm(instrs: &[AsmInstr], argc: usize) -> Result<usize, AsmError> {
+    let mut depth = argc;
+    let mut max_depth = argc;
+    for instr in instrs {
+        let Some((pop, push)) = instr.op.stack_effect(instr.arg) else {
+            return Err(AsmError::InvalidArg(instr.op, instr.arg.to_string()));
+        };
+        if pop > depth {
+            return Err(AsmError::StackUnderflow {
+                op: instr.op,
+                depth,
+            });
+        }
+        depth = depth - pop + push;
+        max_depth = max_depth.max(depth);
+    }
+    if depth != 1 {
+        return Err(AsmError::Unbalanced { depth });
+    }
+    Ok(max_depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_asm() {
+        let add = parse_asm("BINARY_OP 0").unwrap();
+        assert_eq!(verify_asm(&add, 2), Ok(2));
+        assert_eq!(verify_asm(&add, 3), Err(AsmError::Unbalanced { depth: 2 }));
+        let swap_sub = parse_asm("SWAP 2; BINARY_OP 10 # y - x").unwrap();
+        assert_eq!(verify_asm(&swap_sub, 2), Ok(2));
+        let dup = parse_asm("COPY 1\nBINARY_OP 5\nBINARY_OP 5").unwrap();
+        assert_eq!(
+            verify_asm(&dup, 1),
+            Err(AsmError::StackUnderflow {
+                op: Opcode311::BINARY_OP,
+                depth: 1
+            })
+        );
+        assert_eq!(
+            parse_asm("LOAD_FAST 0"),
+            Err(AsmError::UnknownOpcode("LOAD_FAST".into()))
+        );
+        assert_eq!(
+            parse_asm("BINARY_OP"),
+            Err(AsmError::MissingArg(Opcode311::BINARY_OP))
+        );
+        assert_eq!(
+            parse_asm("SWAP 1"),
+            Err(AsmError::InvalidArg(Opcode311::SWAP, "1".into()))
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use std::borrow::Borrow;
 use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
 use std::ops::Deref;
 use std::path::{Component, Path, PathBuf};
 
@@ -9,6 +11,7 @@ use crate::normalize_path;
 ///
 /// `PathBuf` may give false equivalence decisions in non-case-sensitive file systems.
 /// Use this for dictionary keys, etc.
+/// Whether the case is ignored depends on the platform (see `env::case_sensitive_paths`).
 /// See also: `els::util::NormalizedUrl`
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct NormalizedPathBuf(PathBuf);
@@ -120,6 +123,90 @@ pub fn is_cur_dir<P: AsRef<Path>>(path: P) -> bool {
         .map_or(false, |c| c == Component::CurDir)
 }
 
+/// Returns the OS error if `path` exists but cannot be accessed
+/// (e.g. permission denied, broken symbolic link).
+/// Returns `None` if `path` is accessible or does not exist at all.
+/// ```
+/// # use erg_common::pathutil::access_error;
+/// assert!(access_error("lib.rs").is_none());
+/// assert!(access_error("not_exist.er").is_none());
+/// ```
+pub fn access_error<P: AsRef<Path>>(path: P) -> Option<io::Error> {
+    let path = path.as_ref();
+    match path.symlink_metadata() {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => Some(err),
+        Ok(meta) => match File::open(path) {
+            Ok(_) => None,
+            Err(err) if meta.file_type().is_symlink() && err.kind() == io::ErrorKind::NotFound => {
+                Some(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("broken symbolic link ({err})"),
+                ))
+            }
+            Err(err) => Some(err),
+        },
+    }
+}
+
+/// The paths of the entries in `dir` (empty if `dir` cannot be read).
+pub fn dir_entries<P: AsRef<Path>>(dir: P) -> Vec<PathBuf> {
+    let Ok(entries) = dir.as_ref().read_dir() else {
+        return vec![];
+    };
+    entries.flatten().map(|entry| entry.path()).collect()
+}
+
+/// Returns the entries (`entries` is the listing of the directory of `path`) whose names are equal to `path` ignoring case
+/// (e.g. `Foo.er` and `foo.er`), if there are two or more of them.
+/// Such files cannot be distinguished on case-insensitive file systems.
+pub fn case_collisions<P: AsRef<Path>>(path: P, entries: &[PathBuf]) -> Vec<PathBuf> {
+    let Some(name) = path.as_ref().file_name() else {
+        return vec![];
+    };
+    let name = name.to_string_lossy().to_lowercase();
+    let mut same_names = entries
+        .iter()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .is_some_and(|entry| entry.to_string_lossy().to_lowercase() == name)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if same_names.len() < 2 {
+        return vec![];
+    }
+    same_names.sort();
+    same_names
+}
+
+/// Returns `true` if `lhs` and `rhs` are the same physical file
+/// (the same inode on Unix, e.g. hard links and symbolic links; the same real path on other platforms).
+/// ```
+/// # use erg_common::pathutil::same_file;
+/// assert!(same_file("lib.rs", "./lib.rs"));
+/// assert!(!same_file("lib.rs", "env.rs"));
+/// assert!(!same_file("not_exist.er", "not_exist.er"));
+/// ```
+pub fn same_file<P: AsRef<Path>, Q: AsRef<Path>>(lhs: P, rhs: Q) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (lhs.as_ref().metadata(), rhs.as_ref().metadata()) {
+            (Ok(l), Ok(r)) => l.dev() == r.dev() && l.ino() == r.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match (lhs.as_ref().canonicalize(), rhs.as_ref().canonicalize()) {
+            (Ok(l), Ok(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
 /// ```
 /// # use std::path::{PathBuf};
 /// # use erg_common::pathutil::add_postfix_foreach;
@@ -549,6 +549,79 @@ pub const EXT_COMMON_ALIAS: [&str; 7] = [
     "urllib3",
 ];
 
+/// The built-in functions and classes of Python (except the exceptions)
+pub const BUILTIN_PYTHON_FUNCS: [&str; 69] = [
+    "abs",
+    "aiter",
+    "all",
+    "anext",
+    "any",
+    "ascii",
+    "bin",
+    "bool",
+    "breakpoint",
+    "bytearray",
+    "bytes",
+    "callable",
+    "chr",
+    "classmethod",
+    "compile",
+    "complex",
+    "delattr",
+    "dict",
+    "dir",
+    "divmod",
+    "enumerate",
+    "eval",
+    "exec",
+    "filter",
+    "float",
+    "format",
+    "frozenset",
+    "getattr",
+    "globals",
+    "hasattr",
+    "hash",
+    "hex",
+    "id",
+    "input",
+    "int",
+    "isinstance",
+    "issubclass",
+    "iter",
+    "len",
+    "list",
+    "locals",
+    "map",
+    "max",
+    "memoryview",
+    "min",
+    "next",
+    "object",
+    "oct",
+    "open",
+    "ord",
+    "pow",
+    "print",
+    "property",
+    "range",
+    "repr",
+    "reversed",
+    "round",
+    "set",
+    "setattr",
+    "slice",
+    "sorted",
+    "staticmethod",
+    "str",
+    "sum",
+    "super",
+    "tuple",
+    "type",
+    "vars",
+    "zip",
+];
+
 pub fn opt_which_python() -> Result<String, String> {
     let (cmd, python) = if cfg!(windows) {
         ("where", "python")
@@ -625,6 +698,20 @@ pub fn module_exists(py_command: &str, module: &str) -> bool {
     out.status.success()
 }
 
+/// Returns `true` if `module` is a C extension (or builtin) module, which has no Python source.
+pub fn is_extension_module(py_command: &str, module: &str) -> bool {
+    let code = "import importlib.util, sys
+spec = importlib.util.find_spec(sys.argv[1])
+origin = spec.origin if spec is not None else None
+exit(0 if origin is not None and (origin == 'built-in' or origin.endswith(('.so', '.pyd'))) else 1)";
+    Command::new(py_command)
+        .arg("-c")
+        .arg(code)
+        .arg(module.replace('/', "."))
+        .output()
+        .is_ok_and(|out| out.status.success())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PythonVersion {
     pub major: u8,
@@ -0,0 +1,180 @@
+//! A client of the package registry.
+//!
+//! A registry index has the following layout (served over HTTP(S), or a local directory):
+//!
+//! ```console
+//! {index}/{name}/index.txt          # `{version} {sha256}` per line
+//! {index}/{name}/{name}-{version}.zip # the package root (`erg.toml`, `src/`, ...)
+//! ```
+//!
+//! The downloaded packages are verified with the SHA-256 checksums and unpacked into `{store}/{name}/{version}`.
+//! Downloading and unpacking are delegated to Python (`urllib`, `hashlib`, `zipfile`).
+use std::env::var;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::env::erg_path;
+use crate::log;
+use crate::python_util::opt_which_python;
+
+/// Prints the content of `sys.argv[1]` (a URL or a local path).
+const FETCH: &str = "import sys, urllib.request
+src = sys.argv[1]
+if '://' in src:
+    with urllib.request.urlopen(src) as res:
+        data = res.read()
+else:
+    with open(src, 'rb') as f:
+        data = f.read()
+";
+
+/// Verifies the archive with the checksum `sys.argv[2]` and unpacks it into `sys.argv[3]`.
+const INSTALL: &str = "import hashlib, io, os, tempfile, zipfile
+digest, dest = sys.argv[2], sys.argv[3]
+actual = hashlib.sha256(data).hexdigest()
+if actual != digest:
+    sys.exit(f'checksum mismatch: expected {digest}, but found {actual}')
+os.makedirs(os.path.dirname(dest), exist_ok=True)
+tmp = tempfile.mkdtemp(dir=os.path.dirname(dest))
+zipfile.ZipFile(io.BytesIO(data)).extractall(tmp)
+os.replace(tmp, dest)
+";
+
+/// A published version of a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub version: String,
+    /// SHA-256 of the archive (hex)
+    pub sha256: String,
+}
+
+impl RegistryEntry {
+    /// Parses `index.txt`.
+    pub fn parse_index(src: &str) -> Result<Vec<Self>, String> {
+        let mut entries = vec![];
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(version), Some(sha256), None)
+                    if sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit()) =>
+                {
+                    entries.push(Self {
+                        version: version.to_string(),
+                        sha256: sha256.to_ascii_lowercase(),
+                    });
+                }
+                _ => {
+                    return Err(format!(
+                        "index.txt:{}: expected `{{version}} {{sha256}}`",
+                        i + 1
+                    ))
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registry {
+    /// the URL (or the local directory) of the index
+    pub index: String,
+    /// the directory where the packages are unpacked
+    pub store: PathBuf,
+}
+
+impl Registry {
+    pub fn new(index: impl Into<String>, store: PathBuf) -> Self {
+        Self {
+            index: index.into(),
+            store,
+        }
+    }
+
+    /// `$ERG_PACKAGE_STORE` or `$ERG_PATH/lib/packages`
+    pub fn default_store() -> PathBuf {
+        var("ERG_PACKAGE_STORE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| erg_path().join("lib").join("packages"))
+    }
+
+    pub fn package_dir(&self, name: &str, version: &str) -> PathBuf {
+        self.store.join(name).join(version)
+    }
+
+    fn url(&self, name: &str, file: &str) -> String {
+        format!("{}/{name}/{file}", self.index.trim_end_matches('/'))
+    }
+
+    fn run_python(script: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+        let py_command = opt_which_python()?;
+        let out = Command::new(py_command)
+            .arg("-c")
+            .arg(script)
+            .args(args)
+            .output()
+            .map_err(|err| format!("failed to execute Python: {err}"))?;
+        if out.status.success() {
+            Ok(out.stdout)
+        } else {
+            Err(String::from_utf8_lossy(&out.stderr).trim_end().to_string())
+        }
+    }
+
+    /// Returns the published versions of `name`.
+    pub fn versions(&self, name: &str) -> Result<Vec<RegistryEntry>, String> {
+        let url = self.url(name, "index.txt");
+        let script = format!("{FETCH}sys.stdout.buffer.write(data)\n");
+        let index = Self::run_python(&script, &[&url])
+            .map_err(|err| format!("failed to fetch {url}: {err}"))?;
+        RegistryEntry::parse_index(&String::from_utf8_lossy(&index))
+            .map_err(|err| format!("{url}: {err}"))
+    }
+
+    /// Downloads and unpacks `name` (exactly `version`), and returns the package directory.
+    /// Nothing is downloaded if the package is already in the store.
+    pub fn install(&self, name: &str, version: &str) -> Result<PathBuf, String> {
+        let dir = self.package_dir(name, version);
+        if dir.is_dir() {
+            return Ok(dir);
+        }
+        let Some(entry) = self
+            .versions(name)?
+            .into_iter()
+            .find(|entry| entry.version == version)
+        else {
+            return Err(format!("{name} v{version} is not found in {}", self.index));
+        };
+        let url = self.url(name, &format!("{name}-{version}.zip"));
+        let script = format!("{FETCH}{INSTALL}");
+        let dest = dir.to_string_lossy();
+        Self::run_python(&script, &[&url, &entry.sha256, &dest])
+            .map_err(|err| format!("failed to install {name} v{version} from {url}: {err}"))?;
+        log!(info "installed {name} v{version} into {}", dir.display());
+        Ok(dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_index() {
+        let sha256 = "ab".repeat(32);
+        let src = format!(
+            "# versions\n0.1.0 {sha256}\n\n0.2.0 {}\n",
+            sha256.to_uppercase()
+        );
+        let entries = RegistryEntry::parse_index(&src).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].version, "0.2.0");
+        assert_eq!(entries[1].sha256, sha256);
+        let err = RegistryEntry::parse_index("0.1.0 abc\n").unwrap_err();
+        assert_eq!(err, "index.txt:1: expected `{version} {sha256}`");
+    }
+}
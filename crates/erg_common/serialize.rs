@@ -45,6 +45,9 @@ pub fn get_timestamp_bytes() -> [u8; 4] {
     secs.to_le_bytes()
 }
 
+/// If the type byte has this flag, the object is registered so that it can be referred by `TYPE_REF` (`DataTypePrefix::Ref`)
+pub const FLAG_REF: u8 = 0x80;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum DataTypePrefix {
@@ -92,6 +95,7 @@ impl From<u8> for DataTypePrefix {
             'F' => Self::False,
             'N' => Self::None,
             'S' => Self::StopIter,
+            'r' => Self::Ref,
             's' | '\u{00F3}' => Self::Str,
             'Z' | '\u{00DA}' => Self::ShortAsciiInterned,
             'z' | '\u{00FA}' => Self::ShortAscii,
@@ -164,6 +168,38 @@ pub fn str_into_bytes(cont: Str, is_interned: bool) -> Vec<u8> {
     bytes
 }
 
+/// Serializes an integer as `TYPE_LONG` (a sequence of 15-bit digits).
+/// `magnitude` is the absolute value in little-endian 32-bit limbs.
+pub fn long_into_bytes(negative: bool, magnitude: &[u32]) -> Vec<u8> {
+    const SHIFT: u32 = 15;
+    let mut digits = vec![];
+    let (mut acc, mut bits) = (0u64, 0);
+    for limb in magnitude {
+        acc |= (*limb as u64) << bits;
+        bits += 32;
+        while bits >= SHIFT {
+            digits.push((acc & ((1 << SHIFT) - 1)) as u16);
+            acc >>= SHIFT;
+            bits -= SHIFT;
+        }
+    }
+    digits.push(acc as u16);
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    let len = if negative {
+        -(digits.len() as i32)
+    } else {
+        digits.len() as i32
+    };
+    let mut bytes = vec![DataTypePrefix::Long as u8];
+    bytes.append(&mut len.to_le_bytes().to_vec());
+    for digit in digits {
+        bytes.append(&mut digit.to_le_bytes().to_vec());
+    }
+    bytes
+}
+
 pub fn raw_string_into_bytes(mut cont: Vec<u8>) -> Vec<u8> {
     let mut tuple = vec![DataTypePrefix::Str as u8];
     tuple.append(&mut (cont.len() as u32).to_le_bytes().to_vec());
@@ -0,0 +1,505 @@
+//! provides a structured tracing layer (phase spans and leveled events).
+//!
+//! Collected traces can be exported in the Chrome trace format
+//! (viewable with `chrome://tracing` or Perfetto) to visualize the compile time per module and phase.
+//!
+//! ```
+//! use erg_common::trace_span;
+//! let _span = trace_span!("lower", "foo.er");
+//! // `parse`, `lex`, ... spans inside inherit the module target (`foo.er`)
+//! ```
+//!
+//! The phases being entered are recorded even if the tracer is disabled (see `current_phases`),
+//! so that the crash reporter can tell which phase the compiler panicked in.
+//!
+//! `Tracer::timings` summarizes the collected spans per phase and per module (`--timings`).
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+/// Messages longer than this are truncated (the AST and code object dumps can be huge).
+const MAX_MESSAGE_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warn => write!(f, "warn"),
+            Self::Info => write!(f, "info"),
+            Self::Debug => write!(f, "debug"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEventKind {
+    /// a completed span (e.g. the parsing phase of a module)
+    Span { phase: &'static str, dur: u64 },
+    /// a point event (e.g. a `log!` message)
+    Instant {
+        level: Level,
+        target: &'static str,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub kind: TraceEventKind,
+    /// the module being processed
+    pub module: Option<String>,
+    /// microseconds since the tracer was enabled
+    pub ts: u64,
+    pub tid: u64,
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// removes ANSI escape sequences (colors) from `log!` messages
+fn strip_ansi(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+impl TraceEvent {
+    /// Chrome trace format (a `traceEvents` element)
+    pub fn to_chrome_json(&self) -> String {
+        let module = self.module.as_deref().unwrap_or("<unknown>");
+        match &self.kind {
+            TraceEventKind::Span { phase, dur } => format!(
+                r#"{{"name":"{phase}","cat":"phase","ph":"X","ts":{},"dur":{dur},"pid":1,"tid":{},"args":{{"module":"{}"}}}}"#,
+                self.ts,
+                self.tid,
+                escape_json(module),
+            ),
+            TraceEventKind::Instant {
+                level,
+                target,
+                message,
+            } => {
+                let name = message.lines().next().unwrap_or_default();
+                format!(
+                    r#"{{"name":"{}","cat":"{level}","ph":"i","s":"t","ts":{},"pid":1,"tid":{},"args":{{"module":"{}","target":"{target}","message":"{}"}}}}"#,
+                    escape_json(name),
+                    self.ts,
+                    self.tid,
+                    escape_json(module),
+                    escape_json(message),
+                )
+            }
+        }
+    }
+}
+
+thread_local! {
+    static THREAD_ID: Cell<Option<u64>> = const { Cell::new(None) };
+    /// the modules of the spans being entered in this thread
+    static MODULE_STACK: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    /// the phases of the spans being entered in this thread
+    static PHASE_STACK: RefCell<Vec<&'static str>> = const { RefCell::new(vec![]) };
+    /// the accumulated phases being entered in this thread (see `Tracer::accumulate`)
+    static ACCUMULATE_STACK: RefCell<Vec<&'static str>> = const { RefCell::new(vec![]) };
+}
+
+fn thread_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    THREAD_ID.with(|id| {
+        id.get().unwrap_or_else(|| {
+            let new = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            id.set(Some(new));
+            new
+        })
+    })
+}
+
+/// The module of the innermost span being entered in this thread
+pub fn current_module() -> Option<String> {
+    MODULE_STACK.with(|stack| stack.borrow().last().cloned())
+}
+
+/// The phases being entered in this thread (outermost first)
+pub fn current_phases() -> Vec<&'static str> {
+    PHASE_STACK.with(|stack| stack.borrow().clone())
+}
+
+/// Summary of the collected spans (`--timings`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// (phase, total time (μs), count), the slowest first
+    pub phases: Vec<(&'static str, u64, usize)>,
+    /// (module, parsing time (μs), type checking time (μs)), the slowest first
+    pub modules: Vec<(String, u64, u64)>,
+}
+
+fn ms(micros: u64) -> f64 {
+    micros as f64 / 1000.0
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<24} {:>12} {:>8}", "phase", "time (ms)", "count")?;
+        for (phase, total, count) in self.phases.iter() {
+            writeln!(f, "{phase:<24} {:>12.3} {count:>8}", ms(*total))?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>12}",
+            "module", "parse (ms)", "check (ms)"
+        )?;
+        for (module, parse, check) in self.modules.iter() {
+            writeln!(f, "{module:<24} {:>12.3} {:>12.3}", ms(*parse), ms(*check))?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects spans and events. Does nothing until `enable` is called.
+#[derive(Debug)]
+pub struct Tracer {
+    enabled: AtomicBool,
+    start: OnceLock<Instant>,
+    events: Mutex<Vec<TraceEvent>>,
+    /// (phase, total time, count) of the accumulated phases
+    accumulated: Mutex<Vec<(&'static str, u64, usize)>>,
+}
+
+pub static GLOBAL_TRACER: Tracer = Tracer::new();
+
+impl Tracer {
+    pub const fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            start: OnceLock::new(),
+            events: Mutex::new(vec![]),
+            accumulated: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn enable(&self) {
+        self.start.get_or_init(Instant::now);
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn now(&self) -> u64 {
+        self.start
+            .get()
+            .map_or(0, |start| start.elapsed().as_micros() as u64)
+    }
+
+    /// Enters a phase span, which ends when the returned guard is dropped.
+    /// If `module` returns `None`, the span inherits the module of the enclosing span.
+    pub fn enter(
+        &'static self,
+        phase: &'static str,
+        module: impl FnOnce() -> Option<String>,
+    ) -> SpanGuard {
+        let module = module();
+        let pushed = module.is_some();
+        if let Some(module) = module {
+            MODULE_STACK.with(|stack| stack.borrow_mut().push(module));
+        }
+        PHASE_STACK.with(|stack| stack.borrow_mut().push(phase));
+        SpanGuard {
+            tracer: self.is_enabled().then_some(self),
+            phase,
+            start: self.now(),
+            pushed,
+        }
+    }
+
+    /// Enters a phase which is entered too often to be recorded as spans (e.g. unification).
+    /// Only the total time and the count are recorded, and the nested entries of the same phase are ignored.
+    pub fn accumulate(&'static self, phase: &'static str) -> AccumulateGuard {
+        if !self.is_enabled() {
+            return AccumulateGuard {
+                tracer: None,
+                phase,
+                start: 0,
+            };
+        }
+        let outermost = ACCUMULATE_STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            let outermost = !stack.contains(&phase);
+            stack.push(phase);
+            outermost
+        });
+        AccumulateGuard {
+            tracer: Some((self, outermost)),
+            phase,
+            start: self.now(),
+        }
+    }
+
+    pub fn event(&self, level: Level, target: &'static str, message: impl FnOnce() -> String) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut message = strip_ansi(&message());
+        if message.len() > MAX_MESSAGE_LEN {
+            let mut end = MAX_MESSAGE_LEN;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+            message.push_str("...");
+        }
+        let event = TraceEvent {
+            kind: TraceEventKind::Instant {
+                level,
+                target,
+                message,
+            },
+            module: current_module(),
+            ts: self.now(),
+            tid: thread_id(),
+        };
+        self.events.lock().push(event);
+    }
+
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.events.lock().clone()
+    }
+
+    pub fn clear(&self) {
+        self.events.lock().clear();
+        self.accumulated.lock().clear();
+    }
+
+    /// Summarizes the spans per phase and per module.
+    /// The time of a module is the wall time of its `build_ast` (parsing) and `lower` (type checking) spans.
+    pub fn timings(&self) -> Timings {
+        let mut phases = self.accumulated.lock().clone();
+        let mut modules: Vec<(String, u64, u64)> = vec![];
+        for event in self.events.lock().iter() {
+            let TraceEventKind::Span { phase, dur } = event.kind else {
+                continue;
+            };
+            if let Some(entry) = phases.iter_mut().find(|(p, _, _)| *p == phase) {
+                entry.1 += dur;
+                entry.2 += 1;
+            } else {
+                phases.push((phase, dur, 1));
+            }
+            let (Some(module), "build_ast" | "lower") = (&event.module, phase) else {
+                continue;
+            };
+            let entry = if let Some(entry) = modules.iter_mut().find(|(m, _, _)| m == module) {
+                entry
+            } else {
+                modules.push((module.clone(), 0, 0));
+                modules.last_mut().unwrap()
+            };
+            if phase == "build_ast" {
+                entry.1 += dur;
+            } else {
+                entry.2 += dur;
+            }
+        }
+        phases.sort_by_key(|(_, total, _)| std::cmp::Reverse(*total));
+        modules.sort_by_key(|(_, parse, check)| std::cmp::Reverse(parse + check));
+        Timings { phases, modules }
+    }
+
+    pub fn to_chrome_trace(&self) -> String {
+        let events = self.events.lock();
+        let events = events
+            .iter()
+            .map(|event| event.to_chrome_json())
+            .collect::<Vec<_>>();
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+
+    /// Writes the collected trace in the Chrome trace format.
+    pub fn dump<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_chrome_trace().as_bytes())
+    }
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[must_use]
+#[derive(Debug)]
+pub struct SpanGuard {
+    tracer: Option<&'static Tracer>,
+    phase: &'static str,
+    start: u64,
+    pushed: bool,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        PHASE_STACK.with(|stack| stack.borrow_mut().pop());
+        if let Some(tracer) = self.tracer {
+            let event = TraceEvent {
+                kind: TraceEventKind::Span {
+                    phase: self.phase,
+                    dur: tracer.now().saturating_sub(self.start),
+                },
+                module: current_module(),
+                ts: self.start,
+                tid: thread_id(),
+            };
+            tracer.events.lock().push(event);
+        }
+        if self.pushed {
+            MODULE_STACK.with(|stack| stack.borrow_mut().pop());
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug)]
+pub struct AccumulateGuard {
+    /// (tracer, whether this is the outermost entry of the phase)
+    tracer: Option<(&'static Tracer, bool)>,
+    phase: &'static str,
+    start: u64,
+}
+
+impl Drop for AccumulateGuard {
+    fn drop(&mut self) {
+        let Some((tracer, outermost)) = self.tracer else {
+            return;
+        };
+        ACCUMULATE_STACK.with(|stack| stack.borrow_mut().pop());
+        if !outermost {
+            return;
+        }
+        let dur = tracer.now().saturating_sub(self.start);
+        let mut accumulated = tracer.accumulated.lock();
+        if let Some(entry) = accumulated.iter_mut().find(|(p, _, _)| *p == self.phase) {
+            entry.1 += dur;
+            entry.2 += 1;
+        } else {
+            accumulated.push((self.phase, dur, 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        static TRACER: Tracer = Tracer::new();
+        {
+            let _span = TRACER.enter("check", || Some("foo.er".into()));
+            TRACER.event(Level::Info, "test", || "ignored".into());
+            assert_eq!(current_phases(), vec!["check"]);
+            assert_eq!(current_module().as_deref(), Some("foo.er"));
+        }
+        assert!(current_phases().is_empty());
+        assert!(TRACER.events().is_empty());
+        TRACER.enable();
+        {
+            let _span = TRACER.enter("check", || Some("foo.er".into()));
+            let _inner = TRACER.enter("parse", || None);
+            TRACER.event(Level::Info, "test", || "\x1b[32m\"parsed\"\x1b[m".into());
+        }
+        let events = TRACER.events();
+        assert_eq!(events.len(), 3);
+        assert!(events
+            .iter()
+            .all(|ev| ev.module.as_deref() == Some("foo.er")));
+        assert!(matches!(
+            &events[0].kind,
+            TraceEventKind::Instant { message, .. } if message == "\"parsed\""
+        ));
+        assert!(matches!(
+            events[1].kind,
+            TraceEventKind::Span { phase: "parse", .. }
+        ));
+        assert!(matches!(
+            events[2].kind,
+            TraceEventKind::Span { phase: "check", .. }
+        ));
+        assert!(TRACER
+            .to_chrome_trace()
+            .contains(r#""name":"\"parsed\"","cat":"info""#));
+    }
+
+    #[test]
+    fn test_timings() {
+        static TRACER: Tracer = Tracer::new();
+        TRACER.enable();
+        {
+            let _span = TRACER.enter("lower", || Some("foo.er".into()));
+            let _outer = TRACER.accumulate("unify");
+            // nested entries are not counted
+            let _inner = TRACER.accumulate("unify");
+        }
+        {
+            let _span = TRACER.enter("build_ast", || Some("bar.er".into()));
+        }
+        let timings = TRACER.timings();
+        let phases = timings
+            .phases
+            .iter()
+            .map(|(phase, _, count)| (*phase, *count))
+            .collect::<Vec<_>>();
+        assert!(phases.contains(&("unify", 1)));
+        assert!(phases.contains(&("lower", 1)));
+        assert!(phases.contains(&("build_ast", 1)));
+        let modules = timings
+            .modules
+            .iter()
+            .map(|(module, _, _)| &module[..])
+            .collect::<Vec<_>>();
+        assert_eq!(modules.len(), 2);
+        assert!(modules.contains(&"foo.er") && modules.contains(&"bar.er"));
+        assert!(timings.to_string().starts_with("phase"));
+    }
+}
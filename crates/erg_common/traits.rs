@@ -665,6 +665,10 @@ pub trait Runnable: Sized + Default {
     fn clear(&mut self);
     fn eval(&mut self, src: String) -> Result<String, Self::Errs>;
     fn exec(&mut self) -> Result<ExitStatus, Self::Errs>;
+    /// Disassembles the subroutine `name` defined in the session (`:dis name` in the REPL).
+    fn disassemble(&self, _name: &str) -> Option<String> {
+        None
+    }
     fn expect_block(&self, src: &str) -> BlockKind {
         let multi_line_str = "\"\"\"";
         if src.contains(multi_line_str) && src.rfind(multi_line_str) == src.find(multi_line_str) {
@@ -780,6 +784,15 @@ pub trait Runnable: Sized + Default {
                             instance.clear();
                             continue;
                         }
+                        dis if dis.starts_with(":dis ") => {
+                            let name = dis.trim_start_matches(":dis ").trim();
+                            let out = instance.disassemble(name).unwrap_or_else(|| {
+                                format!("{name} is not a subroutine defined in this session\n")
+                            });
+                            output.write_all(out.as_bytes()).unwrap();
+                            output.flush().unwrap();
+                            continue;
+                        }
                         "" => {
                             // eval after the end of the block
                             if vm.now_block.len() == 2 {
@@ -2,7 +2,7 @@ use erg_common::config::ErgConfig;
 use erg_common::dict::Dict;
 use erg_common::error::MultiErrorDisplay;
 use erg_common::traits::{ExitStatus, Runnable, Stream};
-use erg_common::Str;
+use erg_common::{trace_span, Str};
 
 use erg_parser::ast::{VarName, AST};
 use erg_parser::build_ast::ASTBuilder;
@@ -11,9 +11,11 @@ use crate::artifact::{BuildRunnable, Buildable, CompleteArtifact, IncompleteArti
 use crate::context::{Context, ContextKind, ContextProvider, ModuleContext};
 use crate::effectcheck::SideEffectChecker;
 use crate::error::{CompileError, CompileErrors, LowerWarnings};
+use crate::invariant::InvariantChecker;
 use crate::lower::ASTLowerer;
-use crate::module::SharedCompilerResource;
+use crate::module::{ParseResult, SharedCompilerResource};
 use crate::ownercheck::OwnershipChecker;
+use crate::prefetch::ModulePrefetcher;
 use crate::ty::VisibilityModifier;
 use crate::varinfo::VarInfo;
 
@@ -149,6 +151,7 @@ impl HIRBuilder {
     }
 
     pub fn check(&mut self, ast: AST, mode: &str) -> Result<CompleteArtifact, IncompleteArtifact> {
+        let _span = trace_span!("check", ast.name);
         let mut artifact = self.lowerer.lower(ast, mode)?;
         let effect_checker = SideEffectChecker::new(self.cfg().clone());
         let hir = effect_checker
@@ -161,6 +164,7 @@ impl HIRBuilder {
             self.lowerer.module.context.clear_invalid_vars();
             IncompleteArtifact::new(Some(hir), errs, artifact.warns.take_all().into())
         })?;
+        InvariantChecker::check(&hir, "check");
         Ok(CompleteArtifact::new(hir, artifact.warns))
     }
 
@@ -170,8 +174,23 @@ impl HIRBuilder {
         mode: &str,
     ) -> Result<CompleteArtifact, IncompleteArtifact> {
         let mut ast_builder = ASTBuilder::new(self.cfg().copy());
-        let artifact = ast_builder
-            .build(src)
+        let parsed = ast_builder.build(src);
+        if self.cfg().parallel_parse {
+            if let Ok(artifact) = &parsed {
+                let shared = self.lowerer.module.context.shared().clone();
+                ModulePrefetcher::new(self.cfg().copy(), shared).prefetch(&artifact.ast);
+            }
+        }
+        self.check_parsed(parsed, mode)
+    }
+
+    /// Checks the result of `ASTBuilder::build` (e.g. a module parsed by `ModulePrefetcher`).
+    pub fn check_parsed(
+        &mut self,
+        parsed: ParseResult,
+        mode: &str,
+    ) -> Result<CompleteArtifact, IncompleteArtifact> {
+        let artifact = parsed
             .map_err(|iart| IncompleteArtifact::new(None, iart.errors.into(), iart.warns.into()))?;
         self.lowerer
             .warns
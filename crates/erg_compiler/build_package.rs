@@ -0,0 +1,188 @@
+//! defines `PackageBuilder` (`erg build`).
+//!
+//! `erg build` builds the package described by `erg.toml` (see `PackageManifest`).
+//! The `src` directories of the dependencies are added to `ErgConfig::search_paths`,
+//! and the dependencies are analyzed before the dependents (`import "util"` reuses the result in the shared module cache).
+//! The `decls` directories are added to `ErgConfig::decl_paths`.
+//! The dependencies with versions are installed from the registry (see `Registry`).
+//! The entry module is compiled into `build/{name}.pyc` (the imported Erg modules are linked into it).
+//!
+//! ```sh
+//! erg build # in the directory containing `erg.toml` (or its subdirectory)
+//! python build/app.pyc
+//! ```
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use erg_common::config::{ErgConfig, PackageManifest};
+use erg_common::error::MultiErrorDisplay;
+use erg_common::registry::Registry;
+use erg_common::spawn::spawn_new_thread;
+use erg_common::traits::{ExitStatus, Stream};
+use erg_common::{log, trace_span};
+
+use crate::artifact::ErrorArtifact;
+use crate::build_hir::HIRBuilder;
+use crate::module::SharedCompilerResource;
+use crate::Compiler;
+
+#[derive(Debug)]
+pub struct PackageBuilder {
+    cfg: ErgConfig,
+    manifest: PackageManifest,
+    dependencies: Vec<PackageManifest>,
+    shared: SharedCompilerResource,
+}
+
+impl PackageBuilder {
+    pub fn new(cfg: ErgConfig, manifest: PackageManifest) -> Result<Self, String> {
+        let registry = manifest.registry();
+        Self::new_with_registry(cfg, manifest, registry)
+    }
+
+    /// The registry dependencies are installed from `registry` (instead of `PackageManifest::registry`).
+    pub fn new_with_registry(
+        cfg: ErgConfig,
+        manifest: PackageManifest,
+        registry: Option<Registry>,
+    ) -> Result<Self, String> {
+        let dependencies = manifest.resolve_dependencies_with(registry.as_ref())?;
+        let mut cfg = cfg.inherit(manifest.entry_path());
+        cfg.search_paths
+            .extend(dependencies.iter().map(PackageManifest::src_dir));
+        // the declarations of the package itself have priority over those of the dependencies
+        let decls = dependencies.iter().filter_map(|dep| dep.decls.as_ref());
+        cfg.decl_paths
+            .extend(manifest.decls.iter().chain(decls).cloned());
+        let shared = SharedCompilerResource::new(cfg.copy());
+        Ok(Self {
+            cfg,
+            manifest,
+            dependencies,
+            shared,
+        })
+    }
+
+    /// Finds `erg.toml` from `dir` or its ancestors.
+    pub fn load(cfg: ErgConfig, dir: &Path) -> Result<Self, String> {
+        let Some(root) = PackageManifest::find_root(dir) else {
+            return Err(format!(
+                "could not find `{}` in {} or any parent directory",
+                PackageManifest::FILE_NAME,
+                dir.display()
+            ));
+        };
+        let manifest = PackageManifest::load(&root)?;
+        Self::new(cfg, manifest)
+    }
+
+    pub fn manifest(&self) -> &PackageManifest {
+        &self.manifest
+    }
+
+    /// The dependencies in the build order
+    pub fn dependencies(&self) -> &[PackageManifest] {
+        &self.dependencies
+    }
+
+    pub fn dump_pyc_path(&self) -> PathBuf {
+        self.manifest
+            .root
+            .join("build")
+            .join(format!("{}.pyc", self.manifest.name))
+    }
+
+    /// Analyzes the module `import "{package name}"` refers to and registers it in the shared module cache.
+    /// A package without such a module (e.g. an application) has nothing to analyze.
+    fn build_dependency(&self, pkg: &PackageManifest) {
+        let Some(path) = self.cfg.resolve_real_path(Path::new(&pkg.name)) else {
+            log!(info "{} has no library module", pkg.name);
+            return;
+        };
+        let mut cfg = self.cfg.inherit(path.clone());
+        let src = cfg.input.read();
+        let name = pkg.name.clone();
+        let shared = self.shared.inherit(path.clone());
+        let _path = path.clone();
+        let run = move || {
+            let mut builder = HIRBuilder::new_with_cache(cfg, name, shared.clone());
+            let (hir, warns, errors) = match builder.build(src, "exec") {
+                Ok(artifact) => (Some(artifact.object), artifact.warns, None),
+                Err(artifact) => (artifact.object, artifact.warns, Some(artifact.errors)),
+            };
+            if let Some(hir) = hir {
+                shared
+                    .mod_cache
+                    .register(_path, Some(hir), builder.pop_mod_ctx().unwrap());
+            }
+            shared.warns.extend(warns);
+            if let Some(errors) = errors {
+                shared.errors.extend(errors);
+            }
+        };
+        let handle = spawn_new_thread(run, &pkg.name);
+        self.shared.promises.insert(path.clone(), handle);
+        let _ = self.shared.promises.join(&path);
+    }
+
+    /// Builds the dependencies and compiles the entry module.
+    pub fn build(&mut self) -> Result<PathBuf, ErrorArtifact> {
+        let _span = trace_span!("build_package", &self.manifest.name[..]);
+        for pkg in self.dependencies.iter() {
+            self.build_dependency(pkg);
+        }
+        let errors = self.shared.errors.take();
+        if !errors.is_empty() {
+            return Err(ErrorArtifact::new(errors, self.shared.warns.take()));
+        }
+        self.shared.warns.take().write_all_stderr();
+        let path = self.dump_pyc_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("failed to create the build directory");
+        }
+        let mut compiler = Compiler::new_with_cache(self.cfg.copy(), self.shared.clone());
+        let src = self.cfg.input.read();
+        let warns = compiler.compile_and_dump_as_pyc(&path, src, "exec")?;
+        warns.write_all_stderr();
+        Ok(path)
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        // `erg build [dir]`
+        let dir = cfg
+            .input
+            .path()
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        let dir = if dir.is_file() {
+            dir.parent().map(Path::to_path_buf).unwrap_or_default()
+        } else {
+            dir
+        };
+        let mut builder = match Self::load(cfg, &dir) {
+            Ok(builder) => builder,
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitStatus::ERR1;
+            }
+        };
+        match builder.build() {
+            Ok(path) => {
+                let manifest = builder.manifest();
+                println!(
+                    "built: {} v{} ({})",
+                    manifest.name,
+                    manifest.version,
+                    path.display()
+                );
+                ExitStatus::OK
+            }
+            Err(eart) => {
+                eart.warns.write_all_stderr();
+                eart.errors.write_all_stderr();
+                ExitStatus::ERR1
+            }
+        }
+    }
+}
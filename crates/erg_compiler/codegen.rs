@@ -1,26 +1,32 @@
 //! generates `CodeObj` (equivalent to PyCodeObject of CPython) from `AST`.
 //!
 //! ASTからPythonバイトコード(コードオブジェクト)を生成する
+pub mod wasm;
+
 use std::fmt;
+use std::mem;
 use std::process;
 
-use crate::ty::codeobj::{CodeObj, CodeObjFlags, MakeFunctionFlags};
-use crate::ty::value::GenTypeObj;
+use crate::ty::codeobj::{CodeObj, CodeObjFlags, CodePosition, MakeFunctionFlags};
+use crate::ty::value::{GenTypeObj, TypeObj};
 use erg_common::cache::CacheSet;
 use erg_common::config::ErgConfig;
 use erg_common::env::erg_std_path;
-use erg_common::error::{ErrorDisplay, Location};
+use erg_common::error::{ErrorDisplay, ErrorKind, Location};
 use erg_common::fresh::SharedFreshNameGenerator;
 use erg_common::io::Input;
 use erg_common::opcode::{CommonOpcode, CompareOp};
 use erg_common::opcode308::Opcode308;
 use erg_common::opcode310::Opcode310;
-use erg_common::opcode311::{BinOpCode, Opcode311};
+use erg_common::opcode311::{parse_asm, BinOpCode, Opcode311};
 use erg_common::option_enum_unwrap;
 use erg_common::python_util::{env_python_version, PythonVersion};
+use erg_common::set::Set;
 use erg_common::traits::{Locational, Stream};
 use erg_common::Str;
-use erg_common::{debug_power_assert, fn_name, fn_name_full, impl_stream, log, switch_unreachable};
+use erg_common::{
+    debug_power_assert, fn_name, fn_name_full, impl_stream, log, switch_unreachable, trace_span,
+};
 use erg_parser::ast::VisModifierSpec;
 use erg_parser::ast::{DefId, DefKind};
 use CommonOpcode::*;
@@ -33,10 +39,14 @@ use erg_parser::token::{Token, TokenKind};
 use crate::compile::{AccessKind, Name, StoreLoadKind};
 use crate::error::CompileError;
 use crate::hir::{
-    Accessor, Args, Array, BinOp, Block, Call, ClassDef, Def, DefBody, Expr, Identifier, Lambda,
+    Accessor, Args, Array, BinOp, Block, Call, ClassDef, Coercion, Def, DefBody, Expr, Identifier,
+    Lambda,
     Literal, NonDefaultParamSignature, Params, PatchDef, PosArg, ReDef, Record, Signature,
     SubrSignature, Tuple, UnaryOp, VarSignature, HIR,
 };
+use crate::module::SharedPyExceptionMap;
+use crate::optimize::peephole::PeepholeOptimizer;
+use crate::sourcemap::{CodeSourceMap, SourceMap, SourceMapEntry};
 use crate::ty::value::ValueObj;
 use crate::ty::{HasType, Type, TypeCode, TypePair, VisibilityModifier};
 use crate::varinfo::VarInfo;
@@ -69,7 +79,12 @@ fn debind(ident: &Identifier) -> Option<Str> {
     }
 }
 
-fn escape_name(name: &str, vis: &VisibilityModifier, def_line: u32, def_col: u32) -> Str {
+pub(crate) fn escape_name(
+    name: &str,
+    vis: &VisibilityModifier,
+    def_line: u32,
+    def_col: u32,
+) -> Str {
     let name = name.replace('!', "__erg_proc__");
     let name = name.replace('$', "__erg_shared__");
     if vis.is_private() {
@@ -85,6 +100,22 @@ fn escape_name(name: &str, vis: &VisibilityModifier, def_line: u32, def_col: u32
     }
 }
 
+/// `::x_L1_C2` -> `x`
+/// The inverse of `escape_name` for private names (`!` and `$` remain escaped).
+fn demangle_name(name: &str) -> Option<&str> {
+    let name = name.strip_prefix("::")?;
+    let is_num = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let name = match name.rsplit_once("_C") {
+        Some((base, col)) if is_num(col) => base,
+        _ => name,
+    };
+    let name = match name.rsplit_once("_L") {
+        Some((base, line)) if is_num(line) => base,
+        _ => name,
+    };
+    (!name.is_empty()).then_some(name)
+}
+
 fn escape_ident(ident: Identifier) -> Str {
     let vis = ident.vis();
     if &ident.inspect()[..] == "Self" {
@@ -107,6 +138,36 @@ fn escape_ident(ident: Identifier) -> Str {
     }
 }
 
+fn is_declared_in_py(vi: &VarInfo) -> bool {
+    vi.def_loc
+        .module
+        .as_ref()
+        .is_some_and(|path| path.to_string_lossy().ends_with(".d.er"))
+}
+
+/// Is the value of `expr` obtained from a Python module (declared in a `.d.er` file)?
+fn is_py_value(expr: &Expr) -> bool {
+    match expr {
+        Expr::Accessor(Accessor::Attr(attr)) if attr.obj.ref_t().is_py_module() => true,
+        Expr::Accessor(acc) => is_declared_in_py(acc.var_info()),
+        Expr::Call(call) => call.attr_name.as_ref().map_or_else(
+            || is_py_value(&call.obj),
+            |attr| call.obj.ref_t().is_py_module() || is_declared_in_py(&attr.vi),
+        ),
+        _ => false,
+    }
+}
+
+/// `Obj`, unions, refinements, and structural types cannot be checked with `isinstance`
+fn is_checkable_at_runtime(t: &Type) -> bool {
+    t != &Type::Obj
+        && !t.is_union_type()
+        && !t.is_intersection_type()
+        && !t.is_refinement()
+        && !t.is_structural()
+        && !t.has_qvar()
+}
+
 #[derive(Debug, Clone)]
 pub struct PyCodeGenUnit {
     pub(crate) id: usize,
@@ -117,6 +178,7 @@ pub struct PyCodeGenUnit {
     pub(crate) lasti: usize,
     pub(crate) prev_lasti: usize,
     pub(crate) _refs: Vec<ValueObj>, // ref-counted objects
+    pub(crate) spans: Vec<SourceMapEntry>,
 }
 
 impl PartialEq for PyCodeGenUnit {
@@ -156,6 +218,7 @@ impl PyCodeGenUnit {
             lasti: 0,
             prev_lasti: 0,
             _refs: vec![],
+            spans: vec![],
         }
     }
 }
@@ -173,6 +236,10 @@ pub struct PyCodeGenerator {
     prelude_loaded: bool,
     mutate_op_loaded: bool,
     in_op_loaded: bool,
+    record_ops_loaded: bool,
+    checked_getitem_loaded: bool,
+    py_boundary_loaded: bool,
+    derive_loaded: bool,
     record_type_loaded: bool,
     module_type_loaded: bool,
     control_loaded: bool,
@@ -181,10 +248,12 @@ pub struct PyCodeGenerator {
     unit_size: usize,
     units: PyCodeGenStack,
     fresh_gen: SharedFreshNameGenerator,
+    source_map: SourceMap,
+    py_exceptions: SharedPyExceptionMap,
 }
 
 impl PyCodeGenerator {
-    pub fn new(cfg: ErgConfig) -> Self {
+    pub fn new(cfg: ErgConfig, py_exceptions: SharedPyExceptionMap) -> Self {
         Self {
             py_version: cfg.target_version.unwrap_or_else(env_python_version),
             cfg,
@@ -192,6 +261,10 @@ impl PyCodeGenerator {
             prelude_loaded: false,
             mutate_op_loaded: false,
             in_op_loaded: false,
+            record_ops_loaded: false,
+            checked_getitem_loaded: false,
+            py_boundary_loaded: false,
+            derive_loaded: false,
             record_type_loaded: false,
             module_type_loaded: false,
             control_loaded: false,
@@ -200,6 +273,8 @@ impl PyCodeGenerator {
             unit_size: 0,
             units: PyCodeGenStack::empty(),
             fresh_gen: SharedFreshNameGenerator::new("codegen"),
+            source_map: SourceMap::default(),
+            py_exceptions,
         }
     }
 
@@ -211,6 +286,10 @@ impl PyCodeGenerator {
             prelude_loaded: false,
             mutate_op_loaded: false,
             in_op_loaded: false,
+            record_ops_loaded: false,
+            checked_getitem_loaded: false,
+            py_boundary_loaded: false,
+            derive_loaded: false,
             record_type_loaded: false,
             module_type_loaded: false,
             control_loaded: false,
@@ -219,6 +298,8 @@ impl PyCodeGenerator {
             unit_size: 0,
             units: PyCodeGenStack::empty(),
             fresh_gen: self.fresh_gen.clone(),
+            source_map: SourceMap::default(),
+            py_exceptions: self.py_exceptions.clone(),
         }
     }
 
@@ -230,6 +311,10 @@ impl PyCodeGenerator {
         self.prelude_loaded = false;
         self.mutate_op_loaded = false;
         self.in_op_loaded = false;
+        self.record_ops_loaded = false;
+        self.checked_getitem_loaded = false;
+        self.py_boundary_loaded = false;
+        self.derive_loaded = false;
         self.record_type_loaded = false;
         self.module_type_loaded = false;
         self.control_loaded = false;
@@ -246,6 +331,107 @@ impl PyCodeGenerator {
         self.str_cache.get(s)
     }
 
+    /// the source map of the module most recently emitted
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    fn pop_unit(&mut self) -> PyCodeGenUnit {
+        let mut unit = self.units.pop().unwrap();
+        if self.cfg.opt_level > 0 {
+            PeepholeOptimizer::optimize(&mut unit.codeobj, &mut unit.spans, self.py_version);
+        }
+        if self.py_version.minor >= Some(11) {
+            let positions = Self::code_positions(&unit);
+            unit.codeobj.set_positions(&positions);
+        }
+        self.source_map.push(CodeSourceMap::new(
+            unit.codeobj.name.clone(),
+            unit.codeobj.firstlineno,
+            mem::take(&mut unit.spans),
+        ));
+        unit
+    }
+
+    /// The position of each instruction (code unit) is the span of the innermost expression containing it.
+    /// Instructions not contained in any expression (e.g. `RESUME`, `POP_TOP`) inherit the previous line.
+    fn code_positions(unit: &PyCodeGenUnit) -> Vec<CodePosition> {
+        let mut positions = Vec::with_capacity(unit.codeobj.code.len() / 2);
+        let mut spans = unit
+            .spans
+            .iter()
+            .filter(|span| span.offset < span.end)
+            .peekable();
+        let mut stack: Vec<&SourceMapEntry> = vec![];
+        let mut line = Some(unit.codeobj.firstlineno);
+        for offset in (0..unit.codeobj.code.len()).step_by(2) {
+            while let Some(span) = spans.next_if(|span| span.offset <= offset) {
+                stack.push(span);
+            }
+            while stack.last().is_some_and(|span| span.end <= offset) {
+                stack.pop();
+            }
+            let pos = if let Some(span) = stack.last() {
+                let loc = &span.loc;
+                line = loc.ln_begin();
+                (line, loc.ln_end(), loc.col_begin(), loc.col_end())
+            } else {
+                (line, line, None, None)
+            };
+            positions.push(pos);
+        }
+        positions
+    }
+
+    fn collect_names(codeobj: &CodeObj, names: &mut Set<Str>) {
+        names.extend(codeobj.names.iter().cloned());
+        for cons in codeobj.consts.iter() {
+            if let ValueObj::Code(code) = cons {
+                Self::collect_names(code, names);
+            }
+        }
+    }
+
+    /// Demangles the names of the fast local variables (e.g. `::x_L1_C2` -> `x`) so that debuggers show the Erg names.
+    /// Fast locals are accessed by index, but the code objects don't have `CO_OPTIMIZED`,
+    /// so `locals()` (called by debuggers) writes them to the module namespace.
+    /// Therefore, names that conflict with the names used in the module (`used`) are left mangled.
+    fn demangle_locals(codeobj: &mut CodeObj, used: &Set<Str>) {
+        for cons in codeobj.consts.iter_mut() {
+            if let ValueObj::Code(code) = cons {
+                Self::demangle_locals(code, used);
+            }
+        }
+        let locals = codeobj
+            .varnames
+            .iter()
+            .chain(codeobj.freevars.iter())
+            .chain(codeobj.cellvars.iter())
+            .collect::<Set<_>>();
+        let demangled = locals
+            .iter()
+            .map(|name| demangle_name(name).unwrap_or(name))
+            .collect::<Vec<_>>();
+        let renames = locals
+            .iter()
+            .filter_map(|name| {
+                let new = demangle_name(name)?;
+                let unique = demangled.iter().filter(|n| **n == new).count() == 1;
+                (unique && !used.contains(new)).then(|| ((*name).clone(), Str::rc(new)))
+            })
+            .collect::<Vec<_>>();
+        for name in codeobj
+            .varnames
+            .iter_mut()
+            .chain(codeobj.freevars.iter_mut())
+            .chain(codeobj.cellvars.iter_mut())
+        {
+            if let Some((_, new)) = renames.iter().find(|(old, _)| old == name) {
+                *name = new.clone();
+            }
+        }
+    }
+
     #[inline]
     fn toplevel_block(&self) -> &PyCodeGenUnit {
         self.units.first().unwrap()
@@ -436,6 +622,33 @@ impl PyCodeGenerator {
         shift_bytes
     }
 
+    /// Writes a jump instruction whose argument is edited later by `edit_reserved_jump`.
+    /// An `EXTENDED_ARG` is reserved in advance, so editing never shifts the following instructions.
+    /// returns: the index of the jump instruction
+    fn write_reserved_jump<C: Into<u8>>(&mut self, jump: C) -> usize {
+        self.write_instr(CommonOpcode::EXTENDED_ARG as u8);
+        self.write_arg(0);
+        let idx = self.lasti();
+        self.write_instr(jump);
+        self.write_arg(0);
+        idx
+    }
+
+    fn edit_reserved_jump(&mut self, idx: usize, jump_to: usize) {
+        let arg = if self.py_version.minor >= Some(10) {
+            jump_to / 2
+        } else {
+            jump_to
+        };
+        let Ok(arg) = u16::try_from(arg) else {
+            self.crash(&format!("edit_reserved_jump: too far jump: {idx} {jump_to}"));
+        };
+        let [high, low] = arg.to_be_bytes();
+        let code = &mut self.mut_cur_block_codeobj().code;
+        code[idx - 1] = high;
+        code[idx + 1] = low;
+    }
+
     fn write_instr<C: Into<u8>>(&mut self, code: C) {
         self.mut_cur_block_codeobj().code.push(code.into());
         self.mut_cur_block().lasti += 1;
@@ -715,7 +928,8 @@ impl PyCodeGenerator {
         }
         let escaped = escape_ident(ident);
         match &escaped[..] {
-            "if__" | "for__" | "while__" | "with__" | "discard__" => {
+            "if__" | "for__" | "while__" | "with__" | "discard__" | "unsafe_python__"
+            | "debug_only__" => {
                 self.load_control();
             }
             "int__" | "nat__" | "str__" | "float__" => {
@@ -1010,10 +1224,24 @@ impl PyCodeGenerator {
         if def.def_kind().is_trait() {
             return self.emit_trait_def(def);
         }
+        let is_subr = def.sig.is_subr()
+            || matches!(def.body.block.first(), Some(Expr::Lambda(_)) if def.body.block.len() == 1);
+        let doc = def.doc.filter(|_| is_subr);
+        let ident = def.sig.ident().clone();
         match def.sig {
             Signature::Subr(sig) => self.emit_subr_def(None, sig, def.body),
             Signature::Var(sig) => self.emit_var_def(sig, def.body),
         }
+        if let Some(doc) = doc {
+            self.emit_set_doc(ident, doc);
+        }
+    }
+
+    /// `f.__doc__ = doc`
+    fn emit_set_doc(&mut self, ident: Identifier, doc: Str) {
+        self.emit_load_const(doc);
+        self.emit_load_name_instr(ident);
+        self.emit_store_instr(Identifier::public("__doc__"), BoundAttr);
     }
 
     fn emit_push_null(&mut self) {
@@ -1101,11 +1329,17 @@ impl PyCodeGenerator {
     // class T(metaclass=ABCMeta):
     //    def f(): pass
     fn emit_trait_block(&mut self, kind: DefKind, sig: &Signature, mut block: Block) -> CodeObj {
-        debug_assert_eq!(kind, DefKind::Trait);
+        debug_assert!(matches!(kind, DefKind::Trait | DefKind::Subsume));
         let name = sig.ident().inspect().clone();
         let Expr::Call(mut trait_call) = block.remove(0) else { unreachable!() };
-        let req = if let Some(Expr::Record(req)) = trait_call.args.remove_left_or_key("Requirement")
-        {
+        // `Subsume(Super, Additional := {...})`: the requirements of `Super` are emitted by itself
+        let key = if kind == DefKind::Subsume {
+            trait_call.args.remove_left_or_key("Super");
+            "Additional"
+        } else {
+            "Requirement"
+        };
+        let req = if let Some(Expr::Record(req)) = trait_call.args.remove_left_or_key(key) {
             req.attrs.into_iter()
         } else {
             vec![].into_iter()
@@ -1157,7 +1391,7 @@ impl PyCodeGenerator {
             self.mut_cur_block_codeobj().flags += CodeObjFlags::NewLocals as u32;
         }
         // end of flagging
-        let unit = self.units.pop().unwrap();
+        let unit = self.pop_unit();
         if !self.units.is_empty() {
             let ld = unit.prev_lineno - self.cur_block().prev_lineno;
             if ld != 0 {
@@ -1196,7 +1430,7 @@ impl PyCodeGenerator {
             self.emit_load_const(ValueObj::None);
             self.write_instr(RETURN_VALUE);
             self.write_arg(0);
-            let unit = self.units.pop().unwrap();
+            let unit = self.pop_unit();
             if !self.units.is_empty() {
                 let ld = unit
                     .prev_lineno
@@ -1251,11 +1485,67 @@ impl PyCodeGenerator {
         self.write_arg(0);
         self.emit_load_const(ident.inspect().clone());
         // LOAD subclasses
-        let subclasses_len = self.emit_require_type(obj, require_or_sup);
+        let subclasses_len = self.emit_require_type(obj.clone(), require_or_sup);
         self.emit_call_instr(2 + subclasses_len, Name);
         self.stack_dec_n((1 + 2 + subclasses_len) - 1);
-        self.emit_store_instr(ident, Name);
+        self.emit_store_instr(ident.clone(), Name);
         self.stack_dec();
+        if obj.derive().is_some() {
+            self.emit_derive(ident, &obj);
+        }
+    }
+
+    /// C = Class {x = Int}, Derive := Eq and Show
+    /// => #derive(C, ("Eq", "Show"), (("x", "::x"),))
+    fn emit_derive(&mut self, ident: Identifier, obj: &GenTypeObj) {
+        log!(info "entered {}", fn_name!());
+        if !self.derive_loaded {
+            self.load_derive();
+        }
+        let traits = obj
+            .derive()
+            .unwrap()
+            .typ()
+            .intersection_types()
+            .into_iter()
+            .map(|t| ValueObj::Str(t.qual_name()))
+            .collect::<Vec<_>>();
+        // the names of the attributes set in `__init__` (see `emit_init_method`)
+        // the fields are compared in alphabetical order
+        let fields = match obj.base_or_sup() {
+            Some(TypeObj::Builtin {
+                t: Type::Record(rec),
+                ..
+            }) => {
+                let mut fields = rec.keys().collect::<Vec<_>>();
+                fields.sort_by(|l, r| l.symbol.cmp(&r.symbol));
+                fields
+                    .into_iter()
+                    .map(|field| {
+                        let attr = if field.vis.is_private() {
+                            format!("::{}", field.symbol).into()
+                        } else {
+                            field.symbol.clone()
+                        };
+                        ValueObj::Tuple(
+                            vec![ValueObj::Str(field.symbol.clone()), ValueObj::Str(attr)].into(),
+                        )
+                    })
+                    .collect()
+            }
+            Some(_) => vec![ValueObj::Tuple(
+                vec![ValueObj::Str(Str::ever("base")), ValueObj::Str(Str::ever("::base"))].into(),
+            )],
+            None => vec![],
+        };
+        self.emit_push_null();
+        self.emit_load_name_instr(Identifier::private("#derive"));
+        self.emit_load_name_instr(ident);
+        self.emit_load_const(ValueObj::Tuple(traits.into()));
+        self.emit_load_const(ValueObj::Tuple(fields.into()));
+        self.emit_call_instr(3, Name);
+        self.stack_dec_n((1 + 3) - 1);
+        self.emit_pop_top();
     }
 
     fn emit_patch_def(&mut self, patch_def: PatchDef) {
@@ -1303,7 +1593,16 @@ impl PyCodeGenerator {
 
     fn emit_var_def(&mut self, sig: VarSignature, mut body: DefBody) {
         log!(info "entered {} ({sig} = {})", fn_name!(), body.block);
-        if body.block.len() == 1 {
+        let boundary_spec = sig.t_spec.as_ref().filter(|spec| {
+            self.cfg.check_py_boundary
+                && body.block.len() == 1
+                && is_py_value(&body.block[0])
+                && is_checkable_at_runtime(&spec.spec_t)
+        });
+        if let Some(spec) = boundary_spec {
+            let spec = *spec.expr.clone();
+            self.emit_py_boundary(body.block.remove(0), spec, sig.ident.inspect());
+        } else if body.block.len() == 1 {
             self.emit_expr(body.block.remove(0));
         } else {
             self.emit_frameless_block(body.block, vec![]);
@@ -1468,6 +1767,14 @@ impl PyCodeGenerator {
 
     fn emit_binop(&mut self, bin: BinOp) {
         log!(info "entered {} ({bin})", fn_name!());
+        // `{x = 1} + {y = 2}`, `{x = 1; y = 2} - {"y"}` (records are tuples in Python)
+        if matches!(
+            bin.info.py_name.as_deref(),
+            Some("record_merge" | "record_remove")
+        ) {
+            self.emit_record_op(bin);
+            return;
+        }
         // TODO: and/orのプリミティブ命令の実装
         // Range operators are not operators in Python
         match &bin.op.kind {
@@ -1520,6 +1827,55 @@ impl PyCodeGenerator {
         self.emit_binop_instr(bin.op, type_pair);
     }
 
+    /// `x: T = py_value` => `x = py_boundary(py_value, T, "x", TypeError)`
+    /// The raw Python object is checked before it is wrapped by the Erg class (e.g. `Int("1")` succeeds).
+    #[allow(clippy::identity_op)]
+    fn emit_py_boundary(&mut self, value: Expr, spec: Expr, name: &str) {
+        let init_stack_len = self.stack_len();
+        if !self.py_boundary_loaded {
+            self.load_py_boundary();
+        }
+        self.push_lnotab(&value);
+        let span = self.begin_span(&value);
+        let wrapped = self.load_erg_type_wrapper(value.ref_t());
+        self.emit_push_null();
+        self.emit_load_name_instr(Identifier::private("#py_boundary"));
+        self.emit_unwrapped_expr(value);
+        self.emit_expr(spec);
+        self.emit_load_const(name);
+        self.emit_load_py_exception(ErrorKind::RuntimeTypeError);
+        self.emit_call_instr(4, Name);
+        // (1 (subroutine) + argc + kwsc) input objects -> 1 return object
+        self.stack_dec_n((1 + 4 + 0) - 1);
+        if wrapped {
+            self.emit_call_instr(1, Name);
+            self.stack_dec();
+        }
+        self.end_span(span);
+        debug_assert_eq!(self.stack_len(), init_stack_len + 1);
+    }
+
+    #[allow(clippy::identity_op)]
+    fn emit_record_op(&mut self, bin: BinOp) {
+        let init_stack_len = self.stack_len();
+        if !self.record_ops_loaded {
+            self.load_record_ops();
+        }
+        self.emit_push_null();
+        let helper = if bin.info.py_name.as_deref() == Some("record_merge") {
+            "#record_merge"
+        } else {
+            "#record_remove"
+        };
+        self.emit_load_name_instr(Identifier::private(helper));
+        self.emit_expr(*bin.lhs);
+        self.emit_expr(*bin.rhs);
+        self.emit_call_instr(2, Name);
+        // (1 (subroutine) + argc + kwsc) input objects -> 1 return object
+        self.stack_dec_n((1 + 2 + 0) - 1);
+        debug_assert_eq!(self.stack_len(), init_stack_len + 1);
+    }
+
     fn emit_binop_instr(&mut self, binop: Token, type_pair: TypePair) {
         if self.py_version.minor >= Some(11) {
             self.emit_binop_instr_311(binop, type_pair);
@@ -1922,35 +2278,54 @@ impl PyCodeGenerator {
                 todo!("default values in match expression are not supported yet")
             }
             let param = lambda.params.non_defaults.remove(0);
-            let pop_jump_points = self.emit_match_pattern(param, args.is_empty());
+            let is_last_arm = args.is_empty();
+            // If it's the last arm, there's no need to check the guard (as well as the pattern)
+            let guard = lambda.guard.filter(|_| !is_last_arm);
+            let mut pop_jump_points =
+                self.emit_match_pattern(param, is_last_arm, guard.is_some());
+            if let Some(guard) = guard {
+                // balance `self.stack_dec()` below (done by the type check of the pattern otherwise)
+                if pop_jump_points.is_empty() {
+                    self.stack_inc();
+                }
+                // subject(for the next arm) guard
+                self.emit_expr(*guard);
+                // jump to the next case
+                pop_jump_points.push(self.write_reserved_jump(Opcode310::POP_JUMP_IF_FALSE));
+                self.stack_dec();
+                self.emit_pop_top();
+            }
             self.emit_frameless_block(lambda.body, Vec::new());
             // If we move on to the next arm, the stack size will increase
             // so `self.stack_dec();` for now (+1 at the end).
             self.stack_dec();
+            if !pop_jump_points.is_empty() {
+                // jump to the end
+                jump_forward_points.push(self.write_reserved_jump(JUMP_FORWARD));
+            }
             for pop_jump_point in pop_jump_points.into_iter() {
                 let idx = if self.py_version.minor >= Some(11) {
-                    self.lasti() - pop_jump_point // - 2
+                    self.lasti() - pop_jump_point - 2
                 } else {
-                    self.lasti() + 2
+                    self.lasti()
                 };
-                self.calc_edit_jump(pop_jump_point + 1, idx); // jump to POP_TOP
-                jump_forward_points.push(self.lasti());
-                self.write_instr(JUMP_FORWARD); // jump to the end
-                self.write_arg(0);
+                self.edit_reserved_jump(pop_jump_point, idx); // jump to the next arm
             }
         }
         let lasti = self.lasti();
         for jump_point in jump_forward_points.into_iter() {
-            self.calc_edit_jump(jump_point + 1, lasti - jump_point - 1);
+            self.edit_reserved_jump(jump_point, lasti - jump_point - 1);
         }
         self.stack_inc();
         debug_assert_eq!(self.stack_len(), init_stack_len + 1);
     }
 
+    /// If `guarded`, the subject is left on the stack for the next arm
     fn emit_match_pattern(
         &mut self,
         param: NonDefaultParamSignature,
         is_last_arm: bool,
+        guarded: bool,
     ) -> Vec<usize> {
         log!(info "entered {}", fn_name!());
         let mut pop_jump_points = vec![];
@@ -1993,12 +2368,14 @@ impl PyCodeGenerator {
                     self.write_arg(2);
                 }
                 self.stack_dec();
-                pop_jump_points.push(self.lasti());
                 // in 3.11, POP_JUMP_IF_FALSE is replaced with POP_JUMP_FORWARD_IF_FALSE
                 // but the numbers are the same, only the way the jumping points are calculated is different.
-                self.write_instr(Opcode310::POP_JUMP_IF_FALSE); // jump to the next case
-                self.write_arg(0);
+                // jump to the next case
+                pop_jump_points.push(self.write_reserved_jump(Opcode310::POP_JUMP_IF_FALSE));
                 self.stack_dec();
+                if guarded {
+                    self.dup_top();
+                }
             }
         }
         match param.raw.pat {
@@ -2181,6 +2558,8 @@ impl PyCodeGenerator {
             "while!" => self.emit_while_instr(args),
             "if" | "if!" => self.emit_if_instr(args),
             "match" | "match!" => self.emit_match_instr(args, true),
+            "asm" if local.vi.kind.is_builtin() => self.emit_asm_instr(args),
+            "intrinsic" if local.vi.kind.is_builtin() => self.emit_intrinsic_instr(args),
             "with!" => match self.py_version.minor {
                 Some(11) => self.emit_with_instr_311(args),
                 Some(10) => self.emit_with_instr_310(args),
@@ -2197,6 +2576,51 @@ impl PyCodeGenerator {
         }
     }
 
+    /// `asm "SWAP 2; BINARY_OP 10", x, y` => `LOAD x; LOAD y; SWAP 2; BINARY_OP 10`
+    /// The instructions have been verified in the lowering process (see `ASTLowerer::lower_intrinsic`).
+    fn emit_asm_instr(&mut self, mut args: Args) {
+        log!(info "entered {}", fn_name!());
+        let Some(Expr::Lit(lit)) = args.remove_left_or_key("code") else {
+            self.crash("asm: the code must be a literal");
+        };
+        let instrs = match &lit.value {
+            ValueObj::Str(code) => parse_asm(code),
+            other => self.crash(&format!("asm: invalid code: {other}")),
+        };
+        let instrs = match instrs {
+            Ok(instrs) => instrs,
+            Err(err) => self.crash(&format!("asm: unverified code: {err:?}")),
+        };
+        while let Some(arg) = args.try_remove_pos(0) {
+            self.emit_expr(arg.expr);
+        }
+        for instr in instrs {
+            let Some((pop, push)) = instr.op.stack_effect(instr.arg) else {
+                self.crash(&format!("asm: unverified instruction: {instr:?}"));
+            };
+            self.write_instr(instr.op);
+            self.write_arg(instr.arg);
+            self.write_bytes(&vec![0; instr.op.cache_entries() * 2]);
+            self.stack_dec_n(pop);
+            self.stack_inc_n(push);
+        }
+    }
+
+    /// `intrinsic "len", xs` => `len(xs)` (the name is not escaped, so it refers to the Python built-in function)
+    fn emit_intrinsic_instr(&mut self, mut args: Args) {
+        log!(info "entered {}", fn_name!());
+        let Some(Expr::Lit(lit)) = args.remove_left_or_key("name") else {
+            self.crash("intrinsic: the name must be a literal");
+        };
+        let ValueObj::Str(name) = &lit.value else {
+            self.crash(&format!("intrinsic: invalid name: {}", lit.value));
+        };
+        let line = lit.ln_begin().unwrap_or(0);
+        self.emit_push_null();
+        self.emit_load_name_instr(Identifier::public_with_line(DOT, name.clone(), line));
+        self.emit_args_311(args, Name, true);
+    }
+
     fn emit_call_method(&mut self, obj: Expr, method_name: Identifier, args: Args) {
         log!(info "entered {}", fn_name!());
         match &method_name.inspect()[..] {
@@ -2216,6 +2640,9 @@ impl PyCodeGenerator {
             }
             _ => {}
         }
+        if method_name.vi.py_name.as_deref() == Some("checked_getitem") {
+            return self.emit_checked_getitem(obj, args);
+        }
         if let Some(func_name) = debind(&method_name) {
             return self.emit_call_fake_method(obj, func_name, method_name, args);
         }
@@ -2269,7 +2696,8 @@ impl PyCodeGenerator {
             let kw = if is_py_api {
                 arg.keyword.content
             } else {
-                escape_name(&arg.keyword.content, &VisibilityModifier::Private, 0, 0)
+                // the parameter names are demangled (see `demangle_locals`)
+                escape_name(&arg.keyword.content, &VisibilityModifier::Public, 0, 0)
             };
             kws.push(ValueObj::Str(kw));
             self.emit_expr(arg.expr);
@@ -2388,6 +2816,31 @@ impl PyCodeGenerator {
         self.emit_args_311(args, Name, true);
     }
 
+    /// `arr[i]` => `checked_getitem(arr, i, IndexError)` (if `0 <= i < len(arr)` is not proven)
+    #[allow(clippy::identity_op)]
+    fn emit_checked_getitem(&mut self, obj: Expr, mut args: Args) {
+        log!(info "entered {}", fn_name!());
+        let init_stack_len = self.stack_len();
+        if !self.checked_getitem_loaded {
+            self.load_checked_getitem();
+        }
+        self.emit_push_null();
+        self.emit_load_name_instr(Identifier::private("#checked_getitem"));
+        self.emit_expr(obj);
+        self.emit_expr(args.remove(0));
+        self.emit_load_py_exception(ErrorKind::IndexError);
+        self.emit_call_instr(3, Name);
+        // (1 (subroutine) + argc + kwsc) input objects -> 1 return object
+        self.stack_dec_n((1 + 3 + 0) - 1);
+        debug_assert_eq!(self.stack_len(), init_stack_len + 1);
+    }
+
+    /// Loads the Python exception class raised for `kind` (see `SharedPyExceptionMap`).
+    fn emit_load_py_exception(&mut self, kind: ErrorKind) {
+        let exc = self.py_exceptions.py_exception(kind);
+        self.emit_load_name_instr(Identifier::public(exc));
+    }
+
     // assert takes 1 or 2 arguments (0: cond, 1: message)
     fn emit_assert_instr(&mut self, mut args: Args) {
         log!(info "entered {}", fn_name!());
@@ -2402,7 +2855,8 @@ impl PyCodeGenerator {
             self.write_arg(0);
             self.stack_inc();
         } else {
-            self.emit_load_global_instr(Identifier::public("AssertionError"));
+            let exc = self.py_exceptions.py_exception(ErrorKind::AssertionError);
+            self.emit_load_global_instr(Identifier::public(exc));
         }
         if let Some(expr) = args.try_remove(0) {
             self.emit_expr(expr);
@@ -2511,6 +2965,11 @@ impl PyCodeGenerator {
     }
 
     fn emit_dict(&mut self, dict: crate::hir::Dict) {
+        let init_stack_len = self.stack_len();
+        if !self.cfg.no_std {
+            self.emit_push_null();
+            self.emit_load_name_instr(Identifier::public("Dict"));
+        }
         match dict {
             crate::hir::Dict::Normal(dic) => {
                 let len = dic.kvs.len();
@@ -2528,21 +2987,24 @@ impl PyCodeGenerator {
             }
             other => todo!("{other}"),
         }
+        if !self.cfg.no_std {
+            self.emit_call_instr(1, Name);
+            self.stack_dec();
+        }
+        debug_assert_eq!(self.stack_len(), init_stack_len + 1);
     }
 
+    /// Makes the record type (`NamedTuple`) and stores it into `#rec`.
     #[allow(clippy::identity_op)]
-    fn emit_record(&mut self, rec: Record) {
-        log!(info "entered {} ({rec})", fn_name!());
-        let init_stack_len = self.stack_len();
-        let attrs_len = rec.attrs.len();
+    fn emit_record_type(&mut self, fields: Vec<Str>) {
+        let attrs_len = fields.len();
         self.emit_push_null();
-        // making record type
         let ident = Identifier::private("#NamedTuple");
         self.emit_load_name_instr(ident);
         // record name, let it be anonymous
         self.emit_load_const("Record");
-        for field in rec.attrs.iter() {
-            self.emit_load_const(ValueObj::Str(field.sig.ident().inspect().clone()));
+        for field in fields {
+            self.emit_load_const(ValueObj::Str(field));
         }
         self.write_instr(BUILD_LIST);
         self.write_arg(attrs_len);
@@ -2556,6 +3018,20 @@ impl PyCodeGenerator {
         self.stack_dec_n((1 + 2 + 0) - 1);
         let ident = Identifier::private("#rec");
         self.emit_store_instr(ident, Name);
+    }
+
+    #[allow(clippy::identity_op)]
+    fn emit_record(&mut self, rec: Record) {
+        log!(info "entered {} ({rec})", fn_name!());
+        let init_stack_len = self.stack_len();
+        let attrs_len = rec.attrs.len();
+        // making record type
+        let fields = rec
+            .attrs
+            .iter()
+            .map(|field| field.sig.ident().inspect().clone())
+            .collect();
+        self.emit_record_type(fields);
         // making record instance
         let ident = Identifier::private("#rec");
         self.emit_push_null();
@@ -2569,6 +3045,37 @@ impl PyCodeGenerator {
         debug_assert_eq!(self.stack_len(), init_stack_len + 1);
     }
 
+    /// `{.x = 1; .y = 2}` (as `{.x = Int}`) -> `{.x = 1}`
+    #[allow(clippy::identity_op)]
+    fn emit_coercion(&mut self, coerce: Coercion) {
+        log!(info "entered {} ({coerce})", fn_name!());
+        let Some(fields) = coerce.record_fields() else {
+            self.emit_expr(*coerce.expr);
+            return;
+        };
+        let init_stack_len = self.stack_len();
+        let fields = fields
+            .keys()
+            .map(|field| field.symbol.clone())
+            .collect::<Vec<_>>();
+        let attrs_len = fields.len();
+        // the original record is evaluated only once
+        self.emit_expr(*coerce.expr);
+        self.emit_store_instr(Identifier::private("#coerced"), Name);
+        self.emit_record_type(fields.clone());
+        self.emit_push_null();
+        self.emit_load_name_instr(Identifier::private("#rec"));
+        for field in fields {
+            self.emit_load_name_instr(Identifier::private("#coerced"));
+            let line = self.cur_block().prev_lineno;
+            self.emit_load_attr_instr(Identifier::public_with_line(DOT, field, line));
+        }
+        self.emit_call_instr(attrs_len, Name);
+        // (1 (subroutine) + argc + kwsc) input objects -> 1 return object
+        self.stack_dec_n((1 + attrs_len + 0) - 1);
+        debug_assert_eq!(self.stack_len(), init_stack_len + 1);
+    }
+
     /// Emits independent code blocks (e.g., linked other modules)
     fn emit_code(&mut self, code: Block) {
         let mut gen = self.inherit();
@@ -2664,10 +3171,40 @@ impl PyCodeGenerator {
         }
     }
 
+    /// Records the span of `expr` to the source map.
+    /// The end offset is set by `end_span` after `expr` is emitted.
+    fn begin_span(&mut self, expr: &Expr) -> Option<usize> {
+        if expr.ln_begin().unwrap_or(0) == 0 {
+            return None;
+        }
+        let entry = SourceMapEntry::new(
+            self.lasti(),
+            self.lasti(),
+            self.cur_block().prev_lineno,
+            expr.loc(),
+        );
+        self.mut_cur_block().spans.push(entry);
+        Some(self.cur_block().spans.len() - 1)
+    }
+
+    fn end_span(&mut self, idx: Option<usize>) {
+        if let Some(idx) = idx {
+            let lasti = self.lasti();
+            if let Some(entry) = self.mut_cur_block().spans.get_mut(idx) {
+                entry.end = lasti;
+            }
+        }
+    }
+
     fn emit_chunk(&mut self, chunk: Expr) {
         log!(info "entered {} ({chunk})", fn_name!());
         self.push_lnotab(&chunk);
+        let span = self.begin_span(&chunk);
         match chunk {
+            // the doc comments are emitted as `__doc__` (see `emit_def`)
+            Expr::Lit(lit) if lit.is_doc_comment() && self.cfg.strip_docs => {
+                self.emit_load_const(ValueObj::None)
+            }
             Expr::Lit(lit) => self.emit_load_const(lit.value),
             Expr::Accessor(acc) => self.emit_acc(acc),
             Expr::Def(def) => self.emit_def(def),
@@ -2683,66 +3220,85 @@ impl PyCodeGenerator {
             Expr::Set(set) => self.emit_set(set),
             Expr::Dict(dict) => self.emit_dict(dict),
             Expr::Record(rec) => self.emit_record(rec),
+            Expr::Coerce(coerce) => self.emit_coercion(coerce),
             Expr::Code(code) => self.emit_code(code),
             Expr::Compound(chunks) => self.emit_compound(chunks),
             Expr::Import(acc) => self.emit_import(acc),
             Expr::Dummy(_) | Expr::TypeAsc(_) => {}
         }
+        self.end_span(span);
     }
 
     fn emit_expr(&mut self, expr: Expr) {
         log!(info "entered {} ({expr})", fn_name!());
         self.push_lnotab(&expr);
-        let mut wrapped = true;
-        if !self.cfg.no_std {
-            match expr.ref_t().derefine() {
-                Bool => {
+        let span = self.begin_span(&expr);
+        let wrapped = self.load_erg_type_wrapper(expr.ref_t());
+        self.emit_unwrapped_expr(expr);
+        if wrapped {
+            self.emit_call_instr(1, Name);
+            self.stack_dec();
+        }
+        self.end_span(span);
+    }
+
+    /// Loads the Erg class (e.g. `Int`) that wraps the Python object of type `t`.
+    /// Returns `false` if the object is not wrapped.
+    fn load_erg_type_wrapper(&mut self, t: &Type) -> bool {
+        if self.cfg.no_std {
+            return false;
+        }
+        match t.derefine() {
+            Bool => {
+                self.emit_push_null();
+                self.emit_load_name_instr(Identifier::public("Bool"));
+            }
+            Nat => {
+                self.emit_push_null();
+                self.emit_load_name_instr(Identifier::public("Nat"));
+            }
+            Int => {
+                self.emit_push_null();
+                self.emit_load_name_instr(Identifier::public("Int"));
+            }
+            Float => {
+                self.emit_push_null();
+                self.emit_load_name_instr(Identifier::public("Float"));
+            }
+            Str => {
+                self.emit_push_null();
+                self.emit_load_name_instr(Identifier::public("Str"));
+            }
+            other => match &other.qual_name()[..] {
+                "Bytes" => {
                     self.emit_push_null();
-                    self.emit_load_name_instr(Identifier::public("Bool"));
+                    self.emit_load_name_instr(Identifier::public("Bytes"));
                 }
-                Nat => {
+                "Array" => {
                     self.emit_push_null();
-                    self.emit_load_name_instr(Identifier::public("Nat"));
+                    self.emit_load_name_instr(Identifier::public("Array"));
                 }
-                Int => {
+                "Dict" => {
                     self.emit_push_null();
-                    self.emit_load_name_instr(Identifier::public("Int"));
+                    self.emit_load_name_instr(Identifier::public("Dict"));
                 }
-                Float => {
+                "Set" => {
                     self.emit_push_null();
-                    self.emit_load_name_instr(Identifier::public("Float"));
+                    self.emit_load_name_instr(Identifier::public("Set"));
                 }
-                Str => {
+                "Tuple" => {
                     self.emit_push_null();
-                    self.emit_load_name_instr(Identifier::public("Str"));
+                    self.emit_load_name_instr(Identifier::public("tuple"));
                 }
-                other => match &other.qual_name()[..] {
-                    "Bytes" => {
-                        self.emit_push_null();
-                        self.emit_load_name_instr(Identifier::public("Bytes"));
-                    }
-                    "Array" => {
-                        self.emit_push_null();
-                        self.emit_load_name_instr(Identifier::public("Array"));
-                    }
-                    "Dict" => {
-                        self.emit_push_null();
-                        self.emit_load_name_instr(Identifier::public("Dict"));
-                    }
-                    "Set" => {
-                        self.emit_push_null();
-                        self.emit_load_name_instr(Identifier::public("Set"));
-                    }
-                    "Tuple" => {
-                        self.emit_push_null();
-                        self.emit_load_name_instr(Identifier::public("tuple"));
-                    }
-                    _ => {
-                        wrapped = false;
-                    }
-                },
-            }
+                _ => {
+                    return false;
+                }
+            },
         }
+        true
+    }
+
+    fn emit_unwrapped_expr(&mut self, expr: Expr) {
         match expr {
             Expr::Lit(lit) => self.emit_load_const(lit.value),
             Expr::Accessor(acc) => self.emit_acc(acc),
@@ -2762,13 +3318,10 @@ impl PyCodeGenerator {
             Expr::Code(code) => self.emit_code(code),
             Expr::Compound(chunks) => self.emit_compound(chunks),
             Expr::TypeAsc(tasc) => self.emit_expr(*tasc.expr),
+            Expr::Coerce(coerce) => self.emit_coercion(coerce),
             Expr::Import(acc) => self.emit_import(acc),
             Expr::Dummy(_) => {}
         }
-        if !self.cfg.no_std && wrapped {
-            self.emit_call_instr(1, Name);
-            self.stack_dec();
-        }
     }
 
     /// forブロックなどで使う
@@ -2828,6 +3381,10 @@ impl PyCodeGenerator {
         self.emit_store_instr(Identifier::public("__module__"), Name);
         self.emit_load_const(name);
         self.emit_store_instr(Identifier::public("__qualname__"), Name);
+        if let Some(doc) = class.doc.clone() {
+            self.emit_load_const(doc);
+            self.emit_store_instr(Identifier::public("__doc__"), Name);
+        }
         self.emit_init_method(&class.sig, class.__new__.clone());
         if class.need_to_gen_new {
             self.emit_new_func(&class.sig, class.__new__);
@@ -2858,7 +3415,7 @@ impl PyCodeGenerator {
             self.mut_cur_block_codeobj().flags += CodeObjFlags::NewLocals as u32;
         }
         // end of flagging
-        let unit = self.units.pop().unwrap();
+        let unit = self.pop_unit();
         if !self.units.is_empty() {
             let ld = unit.prev_lineno - self.cur_block().prev_lineno;
             if ld != 0 {
@@ -3087,7 +3644,7 @@ impl PyCodeGenerator {
             self.edit_code(idx_copy_free_vars, CommonOpcode::NOP as usize);
         }
         // end of flagging
-        let unit = self.units.pop().unwrap();
+        let unit = self.pop_unit();
         // increase lineno
         if !self.units.is_empty() {
             let ld = unit
@@ -3127,6 +3684,18 @@ impl PyCodeGenerator {
         self.in_op_loaded = true;
     }
 
+    fn load_checked_getitem(&mut self) {
+        let mod_name = Identifier::public("_erg_std_prelude");
+        self.emit_global_import_items(
+            mod_name,
+            vec![(
+                Identifier::public("checked_getitem"),
+                Some(Identifier::private("#checked_getitem")),
+            )],
+        );
+        self.checked_getitem_loaded = true;
+    }
+
     fn load_mutate_op(&mut self) {
         let mod_name = Identifier::public("_erg_std_prelude");
         self.emit_global_import_items(
@@ -3139,6 +3708,48 @@ impl PyCodeGenerator {
         self.mutate_op_loaded = true;
     }
 
+    fn load_record_ops(&mut self) {
+        let mod_name = Identifier::public("_erg_std_prelude");
+        self.emit_global_import_items(
+            mod_name,
+            vec![
+                (
+                    Identifier::public("record_merge"),
+                    Some(Identifier::private("#record_merge")),
+                ),
+                (
+                    Identifier::public("record_remove"),
+                    Some(Identifier::private("#record_remove")),
+                ),
+            ],
+        );
+        self.record_ops_loaded = true;
+    }
+
+    fn load_py_boundary(&mut self) {
+        let mod_name = Identifier::public("_erg_std_prelude");
+        self.emit_global_import_items(
+            mod_name,
+            vec![(
+                Identifier::public("py_boundary"),
+                Some(Identifier::private("#py_boundary")),
+            )],
+        );
+        self.py_boundary_loaded = true;
+    }
+
+    fn load_derive(&mut self) {
+        let mod_name = Identifier::public("_erg_std_prelude");
+        self.emit_global_import_items(
+            mod_name,
+            vec![(
+                Identifier::public("derive"),
+                Some(Identifier::private("#derive")),
+            )],
+        );
+        self.derive_loaded = true;
+    }
+
     fn load_control(&mut self) {
         let mod_name = Identifier::public("_erg_control");
         self.emit_import_all_instr(mod_name);
@@ -3177,6 +3788,30 @@ impl PyCodeGenerator {
         self.emit_import_all_instr(erg_std_mod);
     }
 
+    /// `from _erg_traceback import install; install()`
+    /// translates the runtime tracebacks into Erg source locations with the source map
+    /// `install(("foo.FooError", "ValueError", ...))`
+    /// The exceptions raised by `pyimport`ed modules are reported with the kinds registered from the declarations.
+    fn load_traceback_hook(&mut self, registered: Vec<Str>) {
+        self.emit_global_import_items(
+            Identifier::public("_erg_traceback"),
+            vec![(
+                Identifier::public("install"),
+                Some(Identifier::private("#install_traceback_hook")),
+            )],
+        );
+        self.emit_push_null();
+        self.emit_load_name_instr(Identifier::private("#install_traceback_hook"));
+        let registered = registered
+            .into_iter()
+            .map(ValueObj::Str)
+            .collect::<Vec<_>>();
+        self.emit_load_const(ValueObj::Tuple(registered.into()));
+        self.emit_call_instr(1, Name);
+        self.stack_dec();
+        self.emit_pop_top();
+    }
+
     fn load_record_type(&mut self) {
         self.emit_global_import_items(
             Identifier::public("collections"),
@@ -3214,7 +3849,9 @@ impl PyCodeGenerator {
     }
 
     pub fn emit(&mut self, hir: HIR) -> CodeObj {
+        let _span = trace_span!("codegen");
         log!(info "the code-generating process has started.{RESET}");
+        self.source_map = SourceMap::new(self.cfg.input.enclosed_name());
         self.unit_size += 1;
         self.units.push(PyCodeGenUnit::new(
             self.unit_size,
@@ -3232,6 +3869,13 @@ impl PyCodeGenerator {
         if !self.cfg.no_std && !self.prelude_loaded {
             self.load_prelude();
         }
+        let registered = self.py_exceptions.registered();
+        if (self.cfg.dump_source_map || !registered.is_empty())
+            && !self.cfg.no_std
+            && !self.input().is_repl()
+        {
+            self.load_traceback_hook(registered);
+        }
         for chunk in hir.module.into_iter() {
             self.emit_chunk(chunk);
             // TODO: discard
@@ -3268,7 +3912,7 @@ impl PyCodeGenerator {
             self.mut_cur_block_codeobj().flags += CodeObjFlags::NewLocals as u32;
         }
         // end of flagging
-        let unit = self.units.pop().unwrap();
+        let unit = self.pop_unit();
         if !self.units.is_empty() {
             let ld = unit.prev_lineno - self.cur_block().prev_lineno;
             if ld != 0 {
@@ -3278,7 +3922,14 @@ impl PyCodeGenerator {
                 self.mut_cur_block().prev_lineno += ld;
             }
         }
+        let mut codeobj = unit.codeobj;
+        // the names used in the previous inputs are unknown
+        if !self.input().is_repl() {
+            let mut used = Set::new();
+            Self::collect_names(&codeobj, &mut used);
+            Self::demangle_locals(&mut codeobj, &used);
+        }
         log!(info "the code-generating process has completed.{RESET}");
-        unit.codeobj
+        codeobj
     }
 }
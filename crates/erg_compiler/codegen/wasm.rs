@@ -0,0 +1,1253 @@
+//! generates a WebAssembly module from HIR (`--target wasm`, experimental)
+//!
+//! Only a small subset of Erg is supported:
+//! `Int`/`Nat` (as 64-bit integers), `Float`, `Bool`, `Str` literals and `None`,
+//! module-level variables and subroutines, and some builtins
+//! (`print!`, `if`, `while!`, `for!` over integer ranges, `not`, `assert`, `abs`, `inc!`, `dec!`, `update!`).
+//! Anything else is reported as a feature error.
+//!
+//! The generated module imports the output functions from the host module `erg` (see `lib/std/_erg_wasm_runner.js`),
+//! and exports `memory` and `main`.
+use std::mem;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::error::Location;
+use erg_common::traits::{Locational, Stream};
+use erg_common::Str;
+use erg_parser::token::TokenKind;
+
+use crate::error::{CompileError, CompileErrors, CompileResult, SingleCompileResult};
+use crate::hir::{
+    Accessor, Args, BinOp, Block, Call, Def, Expr, Identifier, Lambda, Literal, Module, Signature,
+    SubrSignature, UnaryOp, HIR,
+};
+use crate::ty::value::ValueObj;
+use crate::ty::{HasType, Type};
+use crate::varinfo::AbsLocation;
+
+const RUNNER: &str = include_str!("../lib/std/_erg_wasm_runner.js");
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: u32 = 1;
+/// The string constants are placed from this address (`0` is left as a null pointer).
+const DATA_OFFSET: u32 = 8;
+const PAGE_SIZE: u32 = 65536;
+
+const I32: u8 = 0x7F;
+const I64: u8 = 0x7E;
+const F64: u8 = 0x7C;
+const EMPTY_BLOCK: u8 = 0x40;
+
+mod op {
+    pub const UNREACHABLE: u8 = 0x00;
+    pub const BLOCK: u8 = 0x02;
+    pub const LOOP: u8 = 0x03;
+    pub const IF: u8 = 0x04;
+    pub const ELSE: u8 = 0x05;
+    pub const END: u8 = 0x0B;
+    pub const BR: u8 = 0x0C;
+    pub const BR_IF: u8 = 0x0D;
+    pub const CALL: u8 = 0x10;
+    pub const DROP: u8 = 0x1A;
+    pub const SELECT: u8 = 0x1B;
+    pub const LOCAL_GET: u8 = 0x20;
+    pub const LOCAL_SET: u8 = 0x21;
+    pub const LOCAL_TEE: u8 = 0x22;
+    pub const GLOBAL_GET: u8 = 0x23;
+    pub const GLOBAL_SET: u8 = 0x24;
+    pub const I32_CONST: u8 = 0x41;
+    pub const I64_CONST: u8 = 0x42;
+    pub const F64_CONST: u8 = 0x44;
+    pub const I32_EQZ: u8 = 0x45;
+    pub const I32_EQ: u8 = 0x46;
+    pub const I32_NE: u8 = 0x47;
+    pub const I64_EQ: u8 = 0x51;
+    pub const I64_NE: u8 = 0x52;
+    pub const I64_LT_S: u8 = 0x53;
+    pub const I64_GT_S: u8 = 0x55;
+    pub const I64_LE_S: u8 = 0x57;
+    pub const I64_GE_S: u8 = 0x59;
+    pub const F64_EQ: u8 = 0x61;
+    pub const F64_NE: u8 = 0x62;
+    pub const F64_LT: u8 = 0x63;
+    pub const F64_GT: u8 = 0x64;
+    pub const F64_LE: u8 = 0x65;
+    pub const F64_GE: u8 = 0x66;
+    pub const I32_AND: u8 = 0x71;
+    pub const I64_ADD: u8 = 0x7C;
+    pub const I64_SUB: u8 = 0x7D;
+    pub const I64_MUL: u8 = 0x7E;
+    pub const I64_DIV_S: u8 = 0x7F;
+    pub const I64_REM_S: u8 = 0x81;
+    pub const I64_XOR: u8 = 0x85;
+    pub const F64_ABS: u8 = 0x99;
+    pub const F64_NEG: u8 = 0x9A;
+    pub const F64_FLOOR: u8 = 0x9C;
+    pub const F64_ADD: u8 = 0xA0;
+    pub const F64_SUB: u8 = 0xA1;
+    pub const F64_MUL: u8 = 0xA2;
+    pub const F64_DIV: u8 = 0xA3;
+    pub const I64_EXTEND_I32_U: u8 = 0xAD;
+    pub const F64_CONVERT_I32_U: u8 = 0xB8;
+    pub const F64_CONVERT_I64_S: u8 = 0xB9;
+}
+
+fn write_uleb(bytes: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn write_sleb(bytes: &mut Vec<u8>, mut n: i64) {
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if (n == 0 && byte & 0x40 == 0) || (n == -1 && byte & 0x40 != 0) {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn write_name(bytes: &mut Vec<u8>, name: &str) {
+    write_uleb(bytes, name.len() as u64);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+fn write_section(module: &mut Vec<u8>, id: u8, payload: Vec<u8>) {
+    module.push(id);
+    write_uleb(module, payload.len() as u64);
+    module.extend(payload);
+}
+
+/// How an Erg value is represented in WebAssembly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repr {
+    /// `i64`
+    Int,
+    /// `f64`
+    Float,
+    /// `i32` (0 or 1)
+    Bool,
+    /// `i32` (a pointer to `[len: u32][UTF-8 bytes]`)
+    Str,
+    /// no value
+    None,
+}
+
+impl Repr {
+    fn from_type(t: &Type) -> Option<Self> {
+        match t {
+            Type::FreeVar(fv) if fv.is_linked() => Self::from_type(&fv.crack()),
+            Type::FreeVar(fv) => fv.get_subsup().and_then(|(sub, _)| Self::from_type(&sub)),
+            Type::Refinement(refine) => Self::from_type(&refine.t),
+            Type::Ref(t) | Type::RefMut { before: t, .. } => Self::from_type(t),
+            Type::Int | Type::Nat => Some(Self::Int),
+            Type::Float => Some(Self::Float),
+            Type::Bool => Some(Self::Bool),
+            Type::Str => Some(Self::Str),
+            Type::NoneType => Some(Self::None),
+            Type::Mono(name) => match &name[..] {
+                "Int!" | "Nat!" => Some(Self::Int),
+                "Float!" => Some(Self::Float),
+                "Bool!" => Some(Self::Bool),
+                "Str!" => Some(Self::Str),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    const fn val_type(&self) -> Option<u8> {
+        match self {
+            Self::Int => Some(I64),
+            Self::Float => Some(F64),
+            Self::Bool | Self::Str => Some(I32),
+            Self::None => None,
+        }
+    }
+
+    const fn block_type(&self) -> u8 {
+        match self.val_type() {
+            Some(t) => t,
+            None => EMPTY_BLOCK,
+        }
+    }
+
+    const fn is_numeric(&self) -> bool {
+        matches!(self, Self::Int | Self::Float | Self::Bool)
+    }
+}
+
+/// The functions imported from the host (the index of each function is its discriminant).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HostFunc {
+    WriteInt,
+    WriteFloat,
+    WriteBool,
+    WriteStr,
+    WriteNone,
+    FlushLine,
+}
+
+impl HostFunc {
+    const ALL: [Self; 6] = [
+        Self::WriteInt,
+        Self::WriteFloat,
+        Self::WriteBool,
+        Self::WriteStr,
+        Self::WriteNone,
+        Self::FlushLine,
+    ];
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::WriteInt => "write_int",
+            Self::WriteFloat => "write_float",
+            Self::WriteBool => "write_bool",
+            Self::WriteStr => "write_str",
+            Self::WriteNone => "write_none",
+            Self::FlushLine => "flush_line",
+        }
+    }
+
+    const fn param(&self) -> Option<u8> {
+        match self {
+            Self::WriteInt => Some(I64),
+            Self::WriteFloat => Some(F64),
+            Self::WriteBool | Self::WriteStr => Some(I32),
+            Self::WriteNone | Self::FlushLine => None,
+        }
+    }
+
+    const fn write(repr: Repr) -> Self {
+        match repr {
+            Repr::Int => Self::WriteInt,
+            Repr::Float => Self::WriteFloat,
+            Repr::Bool => Self::WriteBool,
+            Repr::Str => Self::WriteStr,
+            Repr::None => Self::WriteNone,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FuncSig {
+    /// the parameters of `Repr::None` are not passed
+    params: Vec<Repr>,
+    ret: Repr,
+}
+
+impl FuncSig {
+    fn val_types(&self) -> (Vec<u8>, Vec<u8>) {
+        let params = self.params.iter().filter_map(Repr::val_type).collect();
+        let results = self.ret.val_type().into_iter().collect();
+        (params, results)
+    }
+}
+
+#[derive(Debug)]
+struct FuncBody {
+    name: Str,
+    n_params: usize,
+    /// the types of the parameters and the locals
+    locals: Vec<u8>,
+    code: Vec<u8>,
+    /// `None` if the variable has no value (`Repr::None`)
+    vars: Dict<AbsLocation, (Option<u32>, Repr)>,
+}
+
+impl FuncBody {
+    fn new(name: Str) -> Self {
+        Self {
+            name,
+            n_params: 0,
+            locals: vec![],
+            code: vec![],
+            vars: Dict::new(),
+        }
+    }
+
+    fn new_local(&mut self, val_type: u8) -> u32 {
+        self.locals.push(val_type);
+        (self.locals.len() - 1) as u32
+    }
+
+    fn add_var(&mut self, def_loc: AbsLocation, repr: Repr) -> Option<u32> {
+        let idx = repr.val_type().map(|t| self.new_local(t));
+        self.vars.insert(def_loc, (idx, repr));
+        idx
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let locals = &self.locals[self.n_params..];
+        write_uleb(&mut bytes, locals.len() as u64);
+        for t in locals {
+            write_uleb(&mut bytes, 1);
+            bytes.push(*t);
+        }
+        bytes.extend(self.code);
+        bytes.push(op::END);
+        let mut code = vec![];
+        write_uleb(&mut code, bytes.len() as u64);
+        code.extend(bytes);
+        code
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Storage {
+    Local(u32),
+    Global(u32),
+    Nothing,
+}
+
+/// Generates a WebAssembly module (binary format) from HIR.
+#[derive(Debug)]
+pub struct WasmCodeGenerator {
+    cfg: ErgConfig,
+    types: Vec<(Vec<u8>, Vec<u8>)>,
+    /// the subroutines defined at the module level (the bodies are `None` until compiled)
+    funcs: Vec<(FuncSig, Option<FuncBody>)>,
+    func_indices: Dict<AbsLocation, (u32, FuncSig)>,
+    globals: Vec<u8>,
+    global_vars: Dict<AbsLocation, (Option<u32>, Repr)>,
+    data: Vec<u8>,
+    strs: Dict<Str, u32>,
+    cur: FuncBody,
+}
+
+impl WasmCodeGenerator {
+    pub fn new(cfg: ErgConfig) -> Self {
+        Self {
+            cfg,
+            types: vec![],
+            funcs: vec![],
+            func_indices: Dict::new(),
+            globals: vec![],
+            global_vars: Dict::new(),
+            data: vec![],
+            strs: Dict::new(),
+            cur: FuncBody::new(Str::ever("<module>")),
+        }
+    }
+
+    pub fn emit(mut self, hir: HIR) -> CompileResult<Vec<u8>> {
+        self.emit_module(&hir.module).map_err(CompileErrors::from)?;
+        Ok(self.into_bytes())
+    }
+
+    fn feature_error(&self, loc: &impl Locational, name: &str) -> CompileError {
+        CompileError::feature_error(
+            self.cfg.input.clone(),
+            loc.loc(),
+            &format!("{name} in WebAssembly"),
+            self.cur.name.to_string(),
+        )
+    }
+
+    fn repr_of(&self, expr: &impl HasTypeAndLoc) -> SingleCompileResult<Repr> {
+        Repr::from_type(expr.ref_t())
+            .ok_or_else(|| self.feature_error(expr, &format!("the type {}", expr.ref_t())))
+    }
+
+    fn write(&mut self, byte: u8) {
+        self.cur.code.push(byte);
+    }
+
+    fn write_idx(&mut self, instr: u8, idx: u32) {
+        self.cur.code.push(instr);
+        write_uleb(&mut self.cur.code, idx as u64);
+    }
+
+    fn write_i32_const(&mut self, n: i32) {
+        self.write(op::I32_CONST);
+        write_sleb(&mut self.cur.code, n as i64);
+    }
+
+    fn write_i64_const(&mut self, n: i64) {
+        self.write(op::I64_CONST);
+        write_sleb(&mut self.cur.code, n);
+    }
+
+    fn write_f64_const(&mut self, f: f64) {
+        self.write(op::F64_CONST);
+        self.cur.code.extend(f.to_le_bytes());
+    }
+
+    fn call_host(&mut self, func: HostFunc) {
+        self.write_idx(op::CALL, func as u32);
+    }
+
+    fn func_index(&self, nth: usize) -> u32 {
+        (HostFunc::ALL.len() + nth) as u32
+    }
+
+    fn type_index(&mut self, params: Vec<u8>, results: Vec<u8>) -> u32 {
+        let t = (params, results);
+        if let Some(idx) = self.types.iter().position(|ty| ty == &t) {
+            idx as u32
+        } else {
+            self.types.push(t);
+            (self.types.len() - 1) as u32
+        }
+    }
+
+    /// Returns the address of the string constant.
+    fn str_ptr(&mut self, s: &Str) -> u32 {
+        if let Some(&ptr) = self.strs.get(s) {
+            return ptr;
+        }
+        let ptr = DATA_OFFSET + self.data.len() as u32;
+        self.data.extend((s.len() as u32).to_le_bytes());
+        self.data.extend(s.as_bytes());
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+        self.strs.insert(s.clone(), ptr);
+        ptr
+    }
+
+    /// Registers the module-level variables and subroutines first, so that they can be referred before the definitions.
+    fn register_module_defs(&mut self, module: &Module) -> SingleCompileResult<()> {
+        for chunk in module.iter() {
+            let Expr::Def(def) = chunk else {
+                continue;
+            };
+            match &def.sig {
+                Signature::Var(sig) => {
+                    let repr = self.repr_of(&sig.ident)?;
+                    let idx = repr.val_type().map(|t| {
+                        self.globals.push(t);
+                        (self.globals.len() - 1) as u32
+                    });
+                    self.global_vars
+                        .insert(sig.ident.vi.def_loc.clone(), (idx, repr));
+                }
+                Signature::Subr(sig) => {
+                    let fsig = self.subr_sig(sig)?;
+                    let idx = self.func_index(self.funcs.len());
+                    self.funcs.push((fsig.clone(), None));
+                    self.func_indices
+                        .insert(sig.ident.vi.def_loc.clone(), (idx, fsig));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn subr_sig(&self, sig: &SubrSignature) -> SingleCompileResult<FuncSig> {
+        if let Some(var_params) = &sig.params.var_params {
+            return Err(self.feature_error(var_params.as_ref(), "variable length parameters"));
+        }
+        if let Some(default) = sig.params.defaults.first() {
+            return Err(self.feature_error(default, "default parameters"));
+        }
+        let mut params = vec![];
+        for param in sig.params.non_defaults.iter() {
+            let repr = Repr::from_type(&param.vi.t).ok_or_else(|| {
+                self.feature_error(param, &format!("the parameter type {}", param.vi.t))
+            })?;
+            params.push(repr);
+        }
+        let ret = sig.ident.ref_t().return_t().and_then(Repr::from_type);
+        let ret = ret.ok_or_else(|| {
+            self.feature_error(&sig.ident, &format!("the type {}", sig.ident.ref_t()))
+        })?;
+        Ok(FuncSig { params, ret })
+    }
+
+    fn emit_module(&mut self, module: &Module) -> SingleCompileResult<()> {
+        self.register_module_defs(module)?;
+        for chunk in module.iter() {
+            let repr = self.emit_expr(chunk)?;
+            self.coerce(repr, Repr::None, chunk)?;
+        }
+        Ok(())
+    }
+
+    fn emit_expr(&mut self, expr: &Expr) -> SingleCompileResult<Repr> {
+        match expr {
+            Expr::Lit(lit) => self.emit_literal(lit),
+            Expr::Accessor(Accessor::Ident(ident)) => self.emit_load(ident),
+            Expr::BinOp(bin) => self.emit_binop(bin),
+            Expr::UnaryOp(unary) => self.emit_unaryop(unary),
+            Expr::Call(call) => self.emit_call(call),
+            Expr::Def(def) => {
+                self.emit_def(def)?;
+                Ok(Repr::None)
+            }
+            Expr::TypeAsc(tasc) => self.emit_expr(&tasc.expr),
+            Expr::Coerce(_) => Err(self.feature_error(expr, "collections")),
+            Expr::Compound(block) => self.emit_block(block),
+            Expr::Accessor(Accessor::Attr(attr)) => Err(self.feature_error(attr, "attributes")),
+            Expr::Array(_) | Expr::Tuple(_) | Expr::Set(_) | Expr::Dict(_) | Expr::Record(_) => {
+                Err(self.feature_error(expr, "collections"))
+            }
+            Expr::Lambda(_) => Err(self.feature_error(expr, "anonymous functions")),
+            Expr::ClassDef(_) | Expr::PatchDef(_) => Err(self.feature_error(expr, "classes")),
+            Expr::Import(_) => Err(self.feature_error(expr, "imports")),
+            Expr::ReDef(_) | Expr::Code(_) | Expr::Dummy(_) => {
+                Err(self.feature_error(expr, "this expression"))
+            }
+        }
+    }
+
+    fn emit_expr_as(&mut self, expr: &Expr, want: Repr) -> SingleCompileResult<()> {
+        let repr = self.emit_expr(expr)?;
+        self.coerce(repr, want, expr)
+    }
+
+    /// Emits the block, and returns the representation of the last expression.
+    fn emit_block(&mut self, block: &Block) -> SingleCompileResult<Repr> {
+        let mut last = Repr::None;
+        for (i, chunk) in block.iter().enumerate() {
+            let repr = self.emit_expr(chunk)?;
+            if i == block.len() - 1 {
+                last = repr;
+            } else {
+                self.coerce(repr, Repr::None, chunk)?;
+            }
+        }
+        Ok(last)
+    }
+
+    fn emit_block_as(&mut self, block: &Block, want: Repr) -> SingleCompileResult<()> {
+        let repr = self.emit_block(block)?;
+        if let Some(last) = block.last() {
+            self.coerce(repr, want, last)
+        } else {
+            self.coerce(repr, want, &Location::Unknown)
+        }
+    }
+
+    /// Converts the value on the stack from `from` to `to`.
+    fn coerce(&mut self, from: Repr, to: Repr, loc: &impl Locational) -> SingleCompileResult<()> {
+        match (from, to) {
+            _ if from == to => {}
+            (_, Repr::None) => {
+                if from.val_type().is_some() {
+                    self.write(op::DROP);
+                }
+            }
+            (Repr::Bool, Repr::Int) => self.write(op::I64_EXTEND_I32_U),
+            (Repr::Bool, Repr::Float) => self.write(op::F64_CONVERT_I32_U),
+            (Repr::Int, Repr::Float) => self.write(op::F64_CONVERT_I64_S),
+            _ => {
+                return Err(
+                    self.feature_error(loc, &format!("the conversion from {from:?} to {to:?}"))
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_literal(&mut self, lit: &Literal) -> SingleCompileResult<Repr> {
+        match &lit.value {
+            ValueObj::Int(i) => {
+                self.write_i64_const(*i as i64);
+                Ok(Repr::Int)
+            }
+            ValueObj::Nat(n) => {
+                self.write_i64_const(*n as i64);
+                Ok(Repr::Int)
+            }
+            ValueObj::Float(f) => {
+                self.write_f64_const(*f);
+                Ok(Repr::Float)
+            }
+            ValueObj::Ratio(r) => {
+                self.write_f64_const(r.to_f64());
+                Ok(Repr::Float)
+            }
+            ValueObj::Bool(b) => {
+                self.write_i32_const(*b as i32);
+                Ok(Repr::Bool)
+            }
+            ValueObj::Str(s) => {
+                let ptr = self.str_ptr(s);
+                self.write_i32_const(ptr as i32);
+                Ok(Repr::Str)
+            }
+            ValueObj::None => Ok(Repr::None),
+            other => Err(self.feature_error(lit, &format!("the literal {other}"))),
+        }
+    }
+
+    fn lookup_var(&self, ident: &Identifier) -> Option<(Storage, Repr)> {
+        let def_loc = &ident.vi.def_loc;
+        if let Some((idx, repr)) = self.cur.vars.get(def_loc) {
+            Some((idx.map_or(Storage::Nothing, Storage::Local), *repr))
+        } else if let Some((idx, repr)) = self.global_vars.get(def_loc) {
+            Some((idx.map_or(Storage::Nothing, Storage::Global), *repr))
+        } else {
+            None
+        }
+    }
+
+    fn emit_load(&mut self, ident: &Identifier) -> SingleCompileResult<Repr> {
+        match self.lookup_var(ident) {
+            Some((Storage::Local(idx), repr)) => {
+                self.write_idx(op::LOCAL_GET, idx);
+                Ok(repr)
+            }
+            Some((Storage::Global(idx), repr)) => {
+                self.write_idx(op::GLOBAL_GET, idx);
+                Ok(repr)
+            }
+            Some((Storage::Nothing, repr)) => Ok(repr),
+            None => Err(self.feature_error(ident, &format!("the variable `{}`", ident.inspect()))),
+        }
+    }
+
+    fn emit_store(&mut self, ident: &Identifier) -> SingleCompileResult<Repr> {
+        match self.lookup_var(ident) {
+            Some((Storage::Local(idx), repr)) => {
+                self.write_idx(op::LOCAL_SET, idx);
+                Ok(repr)
+            }
+            Some((Storage::Global(idx), repr)) => {
+                self.write_idx(op::GLOBAL_SET, idx);
+                Ok(repr)
+            }
+            Some((Storage::Nothing, repr)) => Ok(repr),
+            None => Err(self.feature_error(ident, &format!("the variable `{}`", ident.inspect()))),
+        }
+    }
+
+    fn emit_def(&mut self, def: &Def) -> SingleCompileResult<()> {
+        match &def.sig {
+            Signature::Var(sig) => {
+                if self.lookup_var(&sig.ident).is_none() {
+                    let repr = self.repr_of(&sig.ident)?;
+                    self.cur.add_var(sig.ident.vi.def_loc.clone(), repr);
+                }
+                let (_, repr) = self.lookup_var(&sig.ident).unwrap();
+                self.emit_block_as(&def.body.block, repr)?;
+                self.emit_store(&sig.ident)?;
+                Ok(())
+            }
+            Signature::Subr(sig) => self.emit_subr(sig, &def.body.block),
+        }
+    }
+
+    fn emit_subr(&mut self, sig: &SubrSignature, body: &Block) -> SingleCompileResult<()> {
+        let Some((idx, fsig)) = self.func_indices.get(&sig.ident.vi.def_loc).cloned() else {
+            return Err(self.feature_error(&sig.ident, "nested subroutines"));
+        };
+        let mut func = FuncBody::new(sig.ident.inspect().clone());
+        for (param, repr) in sig.params.non_defaults.iter().zip(fsig.params.iter()) {
+            func.add_var(param.vi.def_loc.clone(), *repr);
+        }
+        func.n_params = func.locals.len();
+        let prev = mem::replace(&mut self.cur, func);
+        let res = self.emit_block_as(body, fsig.ret);
+        let func = mem::replace(&mut self.cur, prev);
+        res?;
+        self.funcs[idx as usize - HostFunc::ALL.len()].1 = Some(func);
+        Ok(())
+    }
+
+    fn numeric_operand(&self, bin: &BinOp) -> SingleCompileResult<Repr> {
+        let lhs = self.repr_of(bin.lhs.as_ref())?;
+        let rhs = self.repr_of(bin.rhs.as_ref())?;
+        if !lhs.is_numeric() || !rhs.is_numeric() {
+            return Err(self.feature_error(
+                bin,
+                &format!("the operator `{}` for {lhs:?} and {rhs:?}", bin.op.content),
+            ));
+        }
+        // the operands may be narrower than the result (e.g. `x * x` where `x: Float` is inlined with `x := 2`)
+        let ret = Repr::from_type(bin.ref_t());
+        if lhs == Repr::Float || rhs == Repr::Float || ret == Some(Repr::Float) {
+            Ok(Repr::Float)
+        } else {
+            Ok(Repr::Int)
+        }
+    }
+
+    fn emit_operands(&mut self, bin: &BinOp, operand: Repr) -> SingleCompileResult<()> {
+        self.emit_expr_as(&bin.lhs, operand)?;
+        self.emit_expr_as(&bin.rhs, operand)
+    }
+
+    fn emit_binop(&mut self, bin: &BinOp) -> SingleCompileResult<Repr> {
+        match bin.op.kind {
+            TokenKind::AndOp => {
+                self.emit_expr_as(&bin.lhs, Repr::Bool)?;
+                self.write(op::IF);
+                self.write(I32);
+                self.emit_expr_as(&bin.rhs, Repr::Bool)?;
+                self.write(op::ELSE);
+                self.write_i32_const(0);
+                self.write(op::END);
+                Ok(Repr::Bool)
+            }
+            TokenKind::OrOp => {
+                self.emit_expr_as(&bin.lhs, Repr::Bool)?;
+                self.write(op::IF);
+                self.write(I32);
+                self.write_i32_const(1);
+                self.write(op::ELSE);
+                self.emit_expr_as(&bin.rhs, Repr::Bool)?;
+                self.write(op::END);
+                Ok(Repr::Bool)
+            }
+            TokenKind::DblEq | TokenKind::NotEq
+                if self.repr_of(bin.lhs.as_ref())? == Repr::Bool
+                    && self.repr_of(bin.rhs.as_ref())? == Repr::Bool =>
+            {
+                self.emit_operands(bin, Repr::Bool)?;
+                if bin.op.is(TokenKind::DblEq) {
+                    self.write(op::I32_EQ);
+                } else {
+                    self.write(op::I32_NE);
+                }
+                Ok(Repr::Bool)
+            }
+            TokenKind::Plus | TokenKind::Minus | TokenKind::Star => {
+                let operand = self.numeric_operand(bin)?;
+                self.emit_operands(bin, operand)?;
+                let instr = match (bin.op.kind, operand) {
+                    (TokenKind::Plus, Repr::Int) => op::I64_ADD,
+                    (TokenKind::Minus, Repr::Int) => op::I64_SUB,
+                    (TokenKind::Star, Repr::Int) => op::I64_MUL,
+                    (TokenKind::Plus, _) => op::F64_ADD,
+                    (TokenKind::Minus, _) => op::F64_SUB,
+                    _ => op::F64_MUL,
+                };
+                self.write(instr);
+                Ok(operand)
+            }
+            TokenKind::Slash => {
+                self.numeric_operand(bin)?;
+                self.emit_operands(bin, Repr::Float)?;
+                self.write(op::F64_DIV);
+                Ok(Repr::Float)
+            }
+            TokenKind::FloorDiv => {
+                let operand = self.numeric_operand(bin)?;
+                self.emit_operands(bin, operand)?;
+                if operand == Repr::Int {
+                    self.emit_int_floor_div();
+                } else {
+                    self.write(op::F64_DIV);
+                    self.write(op::F64_FLOOR);
+                }
+                Ok(operand)
+            }
+            TokenKind::Mod => {
+                let operand = self.numeric_operand(bin)?;
+                self.emit_operands(bin, operand)?;
+                if operand == Repr::Int {
+                    self.emit_int_mod();
+                } else {
+                    self.emit_float_mod();
+                }
+                Ok(operand)
+            }
+            TokenKind::Pow if self.numeric_operand(bin)? == Repr::Int => {
+                self.emit_operands(bin, Repr::Int)?;
+                self.emit_int_pow();
+                Ok(Repr::Int)
+            }
+            TokenKind::Less
+            | TokenKind::Gre
+            | TokenKind::LessEq
+            | TokenKind::GreEq
+            | TokenKind::DblEq
+            | TokenKind::NotEq => {
+                let operand = self.numeric_operand(bin)?;
+                self.emit_operands(bin, operand)?;
+                let instr = match (bin.op.kind, operand) {
+                    (TokenKind::Less, Repr::Int) => op::I64_LT_S,
+                    (TokenKind::Gre, Repr::Int) => op::I64_GT_S,
+                    (TokenKind::LessEq, Repr::Int) => op::I64_LE_S,
+                    (TokenKind::GreEq, Repr::Int) => op::I64_GE_S,
+                    (TokenKind::DblEq, Repr::Int) => op::I64_EQ,
+                    (TokenKind::NotEq, Repr::Int) => op::I64_NE,
+                    (TokenKind::Less, _) => op::F64_LT,
+                    (TokenKind::Gre, _) => op::F64_GT,
+                    (TokenKind::LessEq, _) => op::F64_LE,
+                    (TokenKind::GreEq, _) => op::F64_GE,
+                    (TokenKind::DblEq, _) => op::F64_EQ,
+                    _ => op::F64_NE,
+                };
+                self.write(instr);
+                Ok(Repr::Bool)
+            }
+            _ => Err(self.feature_error(bin, &format!("the operator `{}`", bin.op.content))),
+        }
+    }
+
+    /// `[a: i64, b: i64] -> [a // b: i64]` (rounded toward negative infinity, as in Python)
+    fn emit_int_floor_div(&mut self) {
+        let (a, b) = (self.cur.new_local(I64), self.cur.new_local(I64));
+        self.write_idx(op::LOCAL_SET, b);
+        self.write_idx(op::LOCAL_SET, a);
+        self.write_idx(op::LOCAL_GET, a);
+        self.write_idx(op::LOCAL_GET, b);
+        self.write(op::I64_DIV_S);
+        // (a % b != 0 and (a ^ b) < 0) as Int
+        self.write_idx(op::LOCAL_GET, a);
+        self.write_idx(op::LOCAL_GET, b);
+        self.write(op::I64_REM_S);
+        self.write_i64_const(0);
+        self.write(op::I64_NE);
+        self.write_idx(op::LOCAL_GET, a);
+        self.write_idx(op::LOCAL_GET, b);
+        self.write(op::I64_XOR);
+        self.write_i64_const(0);
+        self.write(op::I64_LT_S);
+        self.write(op::I32_AND);
+        self.write(op::I64_EXTEND_I32_U);
+        self.write(op::I64_SUB);
+    }
+
+    /// `[a: i64, b: i64] -> [a % b: i64]` (the result has the same sign as `b`, as in Python)
+    fn emit_int_mod(&mut self) {
+        let (b, r) = (self.cur.new_local(I64), self.cur.new_local(I64));
+        self.write_idx(op::LOCAL_SET, b);
+        self.write_idx(op::LOCAL_GET, b);
+        self.write(op::I64_REM_S);
+        self.write_idx(op::LOCAL_TEE, r);
+        // r + (if r != 0 and (r ^ b) < 0 then b else 0)
+        self.write_idx(op::LOCAL_GET, b);
+        self.write_i64_const(0);
+        self.write_idx(op::LOCAL_GET, r);
+        self.write_i64_const(0);
+        self.write(op::I64_NE);
+        self.write_idx(op::LOCAL_GET, r);
+        self.write_idx(op::LOCAL_GET, b);
+        self.write(op::I64_XOR);
+        self.write_i64_const(0);
+        self.write(op::I64_LT_S);
+        self.write(op::I32_AND);
+        self.write(op::SELECT);
+        self.write(op::I64_ADD);
+    }
+
+    /// `[a: f64, b: f64] -> [a - floor(a / b) * b: f64]`
+    fn emit_float_mod(&mut self) {
+        let (a, b) = (self.cur.new_local(F64), self.cur.new_local(F64));
+        self.write_idx(op::LOCAL_SET, b);
+        self.write_idx(op::LOCAL_TEE, a);
+        self.write_idx(op::LOCAL_GET, a);
+        self.write_idx(op::LOCAL_GET, b);
+        self.write(op::F64_DIV);
+        self.write(op::F64_FLOOR);
+        self.write_idx(op::LOCAL_GET, b);
+        self.write(op::F64_MUL);
+        self.write(op::F64_SUB);
+    }
+
+    /// `[base: i64, exp: i64] -> [base ** exp: i64]` (`exp` is a `Nat`)
+    fn emit_int_pow(&mut self) {
+        let (base, exp, acc) = (
+            self.cur.new_local(I64),
+            self.cur.new_local(I64),
+            self.cur.new_local(I64),
+        );
+        self.write_idx(op::LOCAL_SET, exp);
+        self.write_idx(op::LOCAL_SET, base);
+        self.write_i64_const(1);
+        self.write_idx(op::LOCAL_SET, acc);
+        self.emit_loop(|gen| {
+            gen.write_idx(op::LOCAL_GET, exp);
+            gen.write_i64_const(0);
+            gen.write(op::I64_LE_S);
+            gen.write_idx(op::BR_IF, 1);
+            gen.write_idx(op::LOCAL_GET, acc);
+            gen.write_idx(op::LOCAL_GET, base);
+            gen.write(op::I64_MUL);
+            gen.write_idx(op::LOCAL_SET, acc);
+            gen.write_idx(op::LOCAL_GET, exp);
+            gen.write_i64_const(1);
+            gen.write(op::I64_SUB);
+            gen.write_idx(op::LOCAL_SET, exp);
+            Ok(())
+        })
+        .unwrap();
+        self.write_idx(op::LOCAL_GET, acc);
+    }
+
+    /// Emits `block { loop { body; br 0 } }`.
+    /// `br_if 1` in `body` exits the loop.
+    fn emit_loop(
+        &mut self,
+        body: impl FnOnce(&mut Self) -> SingleCompileResult<()>,
+    ) -> SingleCompileResult<()> {
+        self.write(op::BLOCK);
+        self.write(EMPTY_BLOCK);
+        self.write(op::LOOP);
+        self.write(EMPTY_BLOCK);
+        body(self)?;
+        self.write_idx(op::BR, 0);
+        self.write(op::END);
+        self.write(op::END);
+        Ok(())
+    }
+
+    fn emit_unaryop(&mut self, unary: &UnaryOp) -> SingleCompileResult<Repr> {
+        let repr = self.repr_of(unary.expr.as_ref())?;
+        match unary.op.kind {
+            TokenKind::Mutate => self.emit_expr(&unary.expr),
+            TokenKind::PrePlus if repr.is_numeric() => {
+                let repr = if repr == Repr::Float { repr } else { Repr::Int };
+                self.emit_expr_as(&unary.expr, repr)?;
+                Ok(repr)
+            }
+            TokenKind::PreMinus if repr == Repr::Float => {
+                self.emit_expr_as(&unary.expr, repr)?;
+                self.write(op::F64_NEG);
+                Ok(repr)
+            }
+            TokenKind::PreMinus if repr.is_numeric() => {
+                self.write_i64_const(0);
+                self.emit_expr_as(&unary.expr, Repr::Int)?;
+                self.write(op::I64_SUB);
+                Ok(Repr::Int)
+            }
+            _ => Err(self.feature_error(unary, &format!("the operator `{}`", unary.op.content))),
+        }
+    }
+
+    fn emit_call(&mut self, call: &Call) -> SingleCompileResult<Repr> {
+        if !call.args.kw_args.is_empty() || call.args.var_args.is_some() {
+            return Err(self.feature_error(call, "keyword or variable length arguments"));
+        }
+        if let Some(method) = &call.attr_name {
+            return self.emit_call_method(&call.obj, method, &call.args);
+        }
+        let Expr::Accessor(Accessor::Ident(ident)) = call.obj.as_ref() else {
+            return Err(self.feature_error(call.obj.as_ref(), "calling this object"));
+        };
+        if let Some((idx, fsig)) = self.func_indices.get(&ident.vi.def_loc).cloned() {
+            for (arg, param) in call.args.pos_args.iter().zip(fsig.params.iter()) {
+                self.emit_expr_as(&arg.expr, *param)?;
+            }
+            self.write_idx(op::CALL, idx);
+            return Ok(fsig.ret);
+        }
+        if self.lookup_var(ident).is_some() {
+            return Err(self.feature_error(ident, "first-class subroutines"));
+        }
+        match &ident.inspect()[..] {
+            "print!" => self.emit_print(&call.args),
+            "if" | "if!" => self.emit_if(call),
+            "while!" => self.emit_while(call),
+            "for" | "for!" => self.emit_for(call),
+            "not" if call.args.pos_args.len() == 1 => {
+                self.emit_expr_as(&call.args.pos_args[0].expr, Repr::Bool)?;
+                self.write(op::I32_EQZ);
+                Ok(Repr::Bool)
+            }
+            "assert" if !call.args.pos_args.is_empty() => {
+                self.emit_expr_as(&call.args.pos_args[0].expr, Repr::Bool)?;
+                self.write(op::I32_EQZ);
+                self.write(op::IF);
+                self.write(EMPTY_BLOCK);
+                self.write(op::UNREACHABLE);
+                self.write(op::END);
+                Ok(Repr::None)
+            }
+            "abs" if call.args.pos_args.len() == 1 => self.emit_abs(&call.args.pos_args[0].expr),
+            _ => Err(self.feature_error(ident, &format!("the subroutine `{}`", ident.inspect()))),
+        }
+    }
+
+    fn emit_call_method(
+        &mut self,
+        obj: &Expr,
+        method: &Identifier,
+        args: &Args,
+    ) -> SingleCompileResult<Repr> {
+        let receiver = match obj {
+            Expr::Accessor(Accessor::Ident(ident)) if self.lookup_var(ident).is_some() => ident,
+            _ => {
+                return Err(
+                    self.feature_error(method, &format!("the method `{}`", method.inspect()))
+                )
+            }
+        };
+        let (_, repr) = self.lookup_var(receiver).unwrap();
+        match (&method.inspect()[..], repr) {
+            ("inc!" | "dec!", Repr::Int) if args.is_empty() => {
+                self.emit_load(receiver)?;
+                self.write_i64_const(1);
+                if method.inspect() == "inc!" {
+                    self.write(op::I64_ADD);
+                } else {
+                    self.write(op::I64_SUB);
+                }
+                self.emit_store(receiver)?;
+                Ok(Repr::None)
+            }
+            ("update!", _) => match args.pos_args.first().map(|arg| &arg.expr) {
+                Some(Expr::Lambda(lambda)) if lambda.params.non_defaults.len() == 1 => {
+                    let param = &lambda.params.non_defaults[0];
+                    let idx = self.cur.add_var(param.vi.def_loc.clone(), repr);
+                    self.emit_load(receiver)?;
+                    if let Some(idx) = idx {
+                        self.write_idx(op::LOCAL_SET, idx);
+                    }
+                    self.emit_block_as(&lambda.body, repr)?;
+                    self.emit_store(receiver)?;
+                    Ok(Repr::None)
+                }
+                _ => Err(self.feature_error(method, "`update!` without a lambda")),
+            },
+            _ => Err(self.feature_error(method, &format!("the method `{}`", method.inspect()))),
+        }
+    }
+
+    fn emit_print(&mut self, args: &Args) -> SingleCompileResult<Repr> {
+        for (i, arg) in args.pos_args.iter().enumerate() {
+            if i != 0 {
+                let sep = self.str_ptr(&Str::ever(" "));
+                self.write_i32_const(sep as i32);
+                self.call_host(HostFunc::WriteStr);
+            }
+            let repr = self.emit_expr(&arg.expr)?;
+            self.call_host(HostFunc::write(repr));
+        }
+        self.call_host(HostFunc::FlushLine);
+        Ok(Repr::None)
+    }
+
+    /// Emits the body of a `do` block (or the expression itself).
+    fn emit_branch(&mut self, expr: &Expr, want: Repr) -> SingleCompileResult<()> {
+        match expr {
+            Expr::Lambda(lambda) if lambda.params.is_empty() => {
+                self.emit_block_as(&lambda.body, want)
+            }
+            other => self.emit_expr_as(other, want),
+        }
+    }
+
+    fn emit_if(&mut self, call: &Call) -> SingleCompileResult<Repr> {
+        let args = &call.args.pos_args;
+        if args.len() < 2 {
+            return Err(self.feature_error(call, "`if` without a block"));
+        }
+        let ret = if args.len() >= 3 {
+            self.repr_of(call)?
+        } else {
+            Repr::None
+        };
+        self.emit_expr_as(&args[0].expr, Repr::Bool)?;
+        self.write(op::IF);
+        self.write(ret.block_type());
+        self.emit_branch(&args[1].expr, ret)?;
+        if let Some(else_) = args.get(2) {
+            self.write(op::ELSE);
+            self.emit_branch(&else_.expr, ret)?;
+        }
+        self.write(op::END);
+        Ok(ret)
+    }
+
+    fn emit_while(&mut self, call: &Call) -> SingleCompileResult<Repr> {
+        let args = &call.args.pos_args;
+        let (Some(cond), Some(body)) = (args.first(), args.get(1)) else {
+            return Err(self.feature_error(call, "`while!` without a block"));
+        };
+        self.emit_loop(|gen| {
+            gen.emit_branch(&cond.expr, Repr::Bool)?;
+            gen.write(op::I32_EQZ);
+            gen.write_idx(op::BR_IF, 1);
+            gen.emit_branch(&body.expr, Repr::None)
+        })?;
+        Ok(Repr::None)
+    }
+
+    fn emit_for(&mut self, call: &Call) -> SingleCompileResult<Repr> {
+        let args = &call.args.pos_args;
+        let (Some(Expr::BinOp(range)), Some(Expr::Lambda(lambda))) =
+            (args.first().map(|a| &a.expr), args.get(1).map(|a| &a.expr))
+        else {
+            return Err(self.feature_error(call, "`for!` over this iterable"));
+        };
+        let exit_instr = match range.op.kind {
+            TokenKind::Closed => op::I64_GT_S,
+            TokenKind::RightOpen => op::I64_GE_S,
+            _ => return Err(self.feature_error(range, "`for!` over this iterable")),
+        };
+        self.emit_range_for(range, exit_instr, lambda)?;
+        Ok(Repr::None)
+    }
+
+    fn emit_range_for(
+        &mut self,
+        range: &BinOp,
+        exit_instr: u8,
+        lambda: &Lambda,
+    ) -> SingleCompileResult<()> {
+        let [param] = &lambda.params.non_defaults[..] else {
+            return Err(self.feature_error(lambda, "`for!` with this block"));
+        };
+        let i = self
+            .cur
+            .add_var(param.vi.def_loc.clone(), Repr::Int)
+            .unwrap();
+        let end = self.cur.new_local(I64);
+        self.emit_expr_as(&range.lhs, Repr::Int)?;
+        self.write_idx(op::LOCAL_SET, i);
+        self.emit_expr_as(&range.rhs, Repr::Int)?;
+        self.write_idx(op::LOCAL_SET, end);
+        self.emit_loop(|gen| {
+            gen.write_idx(op::LOCAL_GET, i);
+            gen.write_idx(op::LOCAL_GET, end);
+            gen.write(exit_instr);
+            gen.write_idx(op::BR_IF, 1);
+            gen.emit_block_as(&lambda.body, Repr::None)?;
+            gen.write_idx(op::LOCAL_GET, i);
+            gen.write_i64_const(1);
+            gen.write(op::I64_ADD);
+            gen.write_idx(op::LOCAL_SET, i);
+            Ok(())
+        })
+    }
+
+    fn emit_abs(&mut self, expr: &Expr) -> SingleCompileResult<Repr> {
+        if self.repr_of(expr)? == Repr::Float {
+            self.emit_expr_as(expr, Repr::Float)?;
+            self.write(op::F64_ABS);
+            return Ok(Repr::Float);
+        }
+        self.emit_expr_as(expr, Repr::Int)?;
+        let x = self.cur.new_local(I64);
+        self.write_idx(op::LOCAL_SET, x);
+        // if x < 0 then -x else x
+        self.write_i64_const(0);
+        self.write_idx(op::LOCAL_GET, x);
+        self.write(op::I64_SUB);
+        self.write_idx(op::LOCAL_GET, x);
+        self.write_idx(op::LOCAL_GET, x);
+        self.write_i64_const(0);
+        self.write(op::I64_LT_S);
+        self.write(op::SELECT);
+        Ok(Repr::Int)
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        let host_types = HostFunc::ALL
+            .iter()
+            .map(|f| self.type_index(f.param().into_iter().collect(), vec![]))
+            .collect::<Vec<_>>();
+        let funcs = mem::take(&mut self.funcs);
+        let mut func_types = vec![];
+        for (fsig, _) in funcs.iter() {
+            let (params, results) = fsig.val_types();
+            func_types.push(self.type_index(params, results));
+        }
+        let main_type = self.type_index(vec![], vec![]);
+        let main_idx = self.func_index(funcs.len());
+        let main = mem::replace(&mut self.cur, FuncBody::new(Str::ever("")));
+        let mut module = MAGIC.to_vec();
+        module.extend(VERSION.to_le_bytes());
+        // type section
+        let mut payload = vec![];
+        write_uleb(&mut payload, self.types.len() as u64);
+        for (params, results) in self.types.iter() {
+            payload.push(0x60);
+            write_uleb(&mut payload, params.len() as u64);
+            payload.extend(params);
+            write_uleb(&mut payload, results.len() as u64);
+            payload.extend(results);
+        }
+        write_section(&mut module, 1, payload);
+        // import section
+        let mut payload = vec![];
+        write_uleb(&mut payload, HostFunc::ALL.len() as u64);
+        for (func, ty) in HostFunc::ALL.iter().zip(host_types) {
+            write_name(&mut payload, "erg");
+            write_name(&mut payload, func.name());
+            payload.push(0x00);
+            write_uleb(&mut payload, ty as u64);
+        }
+        write_section(&mut module, 2, payload);
+        // function section
+        let mut payload = vec![];
+        write_uleb(&mut payload, func_types.len() as u64 + 1);
+        for ty in func_types.iter().chain([main_type].iter()) {
+            write_uleb(&mut payload, *ty as u64);
+        }
+        write_section(&mut module, 3, payload);
+        // memory section
+        let mut payload = vec![1];
+        let pages = (DATA_OFFSET + self.data.len() as u32).div_ceil(PAGE_SIZE);
+        payload.push(0x00);
+        write_uleb(&mut payload, pages as u64);
+        write_section(&mut module, 5, payload);
+        // global section
+        let mut payload = vec![];
+        write_uleb(&mut payload, self.globals.len() as u64);
+        for t in self.globals.iter() {
+            payload.push(*t);
+            payload.push(0x01); // mutable
+            match *t {
+                I64 => payload.extend([op::I64_CONST, 0]),
+                F64 => {
+                    payload.push(op::F64_CONST);
+                    payload.extend(0f64.to_le_bytes());
+                }
+                _ => payload.extend([op::I32_CONST, 0]),
+            }
+            payload.push(op::END);
+        }
+        write_section(&mut module, 6, payload);
+        // export section
+        let mut payload = vec![2];
+        write_name(&mut payload, "memory");
+        payload.extend([0x02, 0]);
+        write_name(&mut payload, "main");
+        payload.push(0x00);
+        write_uleb(&mut payload, main_idx as u64);
+        write_section(&mut module, 7, payload);
+        // code section
+        let mut payload = vec![];
+        write_uleb(&mut payload, funcs.len() as u64 + 1);
+        for (_, func) in funcs {
+            let func = func.unwrap_or_else(|| FuncBody::new(Str::ever("")));
+            payload.extend(func.into_bytes());
+        }
+        payload.extend(main.into_bytes());
+        write_section(&mut module, 10, payload);
+        // data section
+        let mut payload = vec![1, 0x00];
+        payload.push(op::I32_CONST);
+        write_sleb(&mut payload, DATA_OFFSET as i64);
+        payload.push(op::END);
+        write_uleb(&mut payload, self.data.len() as u64);
+        payload.extend(self.data);
+        write_section(&mut module, 11, payload);
+        module
+    }
+}
+
+trait HasTypeAndLoc: HasType + Locational {}
+impl<T: HasType + Locational> HasTypeAndLoc for T {}
+
+/// Executes the WebAssembly module with Node.js, and returns the exit code.
+pub fn exec_wasm<P: AsRef<Path>, T: Into<Stdio>>(path: P, stdout: T) -> Option<i32> {
+    Command::new("node")
+        .arg("-e")
+        .arg(RUNNER)
+        .arg(path.as_ref())
+        .stdout(stdout)
+        .spawn()
+        .ok()?
+        .wait()
+        .ok()?
+        .code()
+}
@@ -1,27 +1,36 @@
 //! defines `Compiler`.
 //!
 //! コンパイラーを定義する
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use erg_common::config::ErgConfig;
 use erg_common::dict::Dict;
 use erg_common::error::MultiErrorDisplay;
-use erg_common::log;
 use erg_common::traits::{ExitStatus, Runnable, Stream};
+use erg_common::{log, trace_span, Str};
 use erg_parser::ast::VarName;
 
-use crate::artifact::{CompleteArtifact, ErrorArtifact};
+use crate::artifact::{Buildable, CompleteArtifact, ErrorArtifact};
 use crate::context::{Context, ContextProvider};
+use crate::invariant::InvariantChecker;
 use crate::optimize::HIROptimizer;
 use crate::ty::codeobj::CodeObj;
+use crate::ty::value::ValueObj;
 
 use crate::build_hir::HIRBuilder;
+use crate::codegen::wasm::WasmCodeGenerator;
 use crate::codegen::PyCodeGenerator;
 use crate::desugar_hir::HIRDesugarer;
 use crate::error::{CompileError, CompileErrors, CompileWarnings};
-use crate::hir::Expr;
+use crate::hir::{Expr, HIR};
+use crate::init_order::InitOrderAnalyzer;
 use crate::link_hir::HIRLinker;
 use crate::module::SharedCompilerResource;
+use crate::prune::ModulePruner;
+use crate::sourcemap::SourceMap;
+use crate::stub::PyStubGenerator;
+use crate::transpile::ScriptGenerator;
 use crate::varinfo::VarInfo;
 
 /// * registered as global -> Global
@@ -116,6 +125,9 @@ pub struct Compiler {
     builder: HIRBuilder,
     shared: SharedCompilerResource,
     code_generator: PyCodeGenerator,
+    init_order: Vec<PathBuf>,
+    /// the code objects of the subroutines compiled so far (e.g. `f`, `C.method`), for `:dis`
+    codes: Dict<Str, CodeObj>,
 }
 
 impl Default for Compiler {
@@ -131,12 +143,7 @@ impl Runnable for Compiler {
 
     fn new(cfg: ErgConfig) -> Self {
         let shared = SharedCompilerResource::new(cfg.copy());
-        Self {
-            shared: shared.clone(),
-            builder: HIRBuilder::new_with_cache(cfg.copy(), "<module>", shared),
-            code_generator: PyCodeGenerator::new(cfg.copy()),
-            cfg,
-        }
+        Self::new_with_cache(cfg, shared)
     }
 
     #[inline]
@@ -154,6 +161,7 @@ impl Runnable for Compiler {
     fn initialize(&mut self) {
         self.builder.initialize();
         self.code_generator.clear();
+        self.codes.clear();
         // .mod_cache will be initialized in .builder
     }
 
@@ -163,15 +171,20 @@ impl Runnable for Compiler {
     }
 
     fn exec(&mut self) -> Result<ExitStatus, Self::Errs> {
-        let path = self.cfg.dump_pyc_path();
         let src = self.cfg.input.read();
-        let warns = self
-            .compile_and_dump_as_pyc(path, src, "exec")
-            .map_err(|eart| {
-                eart.warns.write_all_stderr();
-                eart.errors
-            })?;
+        let res = if self.cfg.target.is_wasm() {
+            self.compile_and_dump_as_wasm(self.cfg.dump_wasm_path(), src, "exec")
+        } else {
+            self.compile_and_dump_as_pyc(self.cfg.dump_pyc_path(), src, "exec")
+        };
+        let warns = res.map_err(|eart| {
+            eart.warns.write_all_stderr();
+            eart.errors
+        })?;
         warns.write_all_stderr();
+        if self.cfg.dump_pyi {
+            self.dump_pyi();
+        }
         Ok(ExitStatus::compile_passed(warns.len()))
     }
 
@@ -183,6 +196,10 @@ impl Runnable for Compiler {
         arti.warns.write_all_stderr();
         Ok(arti.object.code_info(Some(self.code_generator.py_version)))
     }
+
+    fn disassemble(&self, name: &str) -> Option<String> {
+        Compiler::disassemble(self, name)
+    }
 }
 
 impl ContextProvider for Compiler {
@@ -200,6 +217,44 @@ impl ContextProvider for Compiler {
 }
 
 impl Compiler {
+    /// Creates a compiler sharing the modules already analyzed (e.g. the dependencies of a package).
+    pub fn new_with_cache(cfg: ErgConfig, shared: SharedCompilerResource) -> Self {
+        Self {
+            shared: shared.clone(),
+            code_generator: PyCodeGenerator::new(cfg.copy(), shared.py_exceptions.clone()),
+            builder: HIRBuilder::new_with_cache(cfg.copy(), "<module>", shared),
+            init_order: vec![],
+            codes: Dict::new(),
+            cfg,
+        }
+    }
+
+    /// Disassembles the subroutine (e.g. `f`, `C.method`) compiled so far.
+    pub fn disassemble(&self, name: &str) -> Option<String> {
+        self.codes
+            .get(name)
+            .map(|code| code.code_info(Some(self.code_generator.py_version)))
+    }
+
+    fn register_codes(&mut self, code: &CodeObj, namespace: Option<&str>) {
+        for cons in code.consts.iter() {
+            let ValueObj::Code(sub) = cons else {
+                continue;
+            };
+            // lambdas, comprehensions, etc.
+            if sub.name.starts_with('<') {
+                continue;
+            }
+            let name = if let Some(namespace) = namespace {
+                Str::from(format!("{namespace}.{}", sub.name))
+            } else {
+                sub.name.clone()
+            };
+            self.register_codes(sub, Some(&name));
+            self.codes.insert(name, sub.as_ref().clone());
+        }
+    }
+
     pub fn compile_and_dump_as_pyc<P: AsRef<Path>>(
         &mut self,
         pyc_path: P,
@@ -207,9 +262,31 @@ impl Compiler {
         mode: &str,
     ) -> Result<CompileWarnings, ErrorArtifact> {
         let arti = self.compile(src, mode)?;
-        arti.object
+        let stats = arti
+            .object
             .dump_as_pyc(pyc_path, self.cfg.py_magic_num)
             .expect("failed to dump a .pyc file (maybe permission denied)");
+        if self.cfg.verbose >= 2 {
+            eprintln!("{stats}");
+        }
+        if self.cfg.dump_source_map {
+            self.dump_source_map();
+        }
+        Ok(arti.warns)
+    }
+
+    /// Compiles `src` into a WebAssembly module (experimental, see `WasmCodeGenerator`).
+    pub fn compile_and_dump_as_wasm<P: AsRef<Path>>(
+        &mut self,
+        wasm_path: P,
+        src: String,
+        mode: &str,
+    ) -> Result<CompileWarnings, ErrorArtifact> {
+        let arti = self.build_optimize_link_desugar(src, mode)?;
+        let wasm = WasmCodeGenerator::new(self.cfg.copy())
+            .emit(arti.object)
+            .map_err(|errs| ErrorArtifact::new(errs, arti.warns.clone()))?;
+        fs::write(wasm_path, wasm).expect("failed to dump a .wasm file (maybe permission denied)");
         Ok(arti.warns)
     }
 
@@ -221,8 +298,12 @@ impl Compiler {
     ) -> Result<CompleteArtifact<Option<Expr>>, ErrorArtifact> {
         let arti = self.eval_compile(src, mode)?;
         let (code, last) = arti.object;
-        code.dump_as_pyc(pyc_path, self.cfg.py_magic_num)
+        let stats = code
+            .dump_as_pyc(pyc_path, self.cfg.py_magic_num)
             .expect("failed to dump a .pyc file (maybe permission denied)");
+        if self.cfg.verbose >= 2 {
+            eprintln!("{stats}");
+        }
         Ok(CompleteArtifact::new(last, arti.warns))
     }
 
@@ -231,9 +312,11 @@ impl Compiler {
         src: String,
         mode: &str,
     ) -> Result<CompleteArtifact<CodeObj>, ErrorArtifact> {
+        let _span = trace_span!("compile", self.cfg.input.unescaped_filename());
         log!(info "the compiling process has started.");
         let arti = self.build_optimize_link_desugar(src, mode)?;
         let codeobj = self.code_generator.emit(arti.object);
+        self.register_codes(&codeobj, None);
         log!(info "code object:\n{}", codeobj.code_info(Some(self.code_generator.py_version)));
         log!(info "the compiling process has completed");
         Ok(CompleteArtifact::new(codeobj, arti.warns))
@@ -253,6 +336,7 @@ impl Compiler {
         let arti = self.build_optimize_link_desugar(src, mode)?;
         let last = arti.object.module.last().cloned();
         let codeobj = self.code_generator.emit(arti.object);
+        self.register_codes(&codeobj, None);
         log!(info "code object:\n{}", codeobj.code_info(Some(self.code_generator.py_version)));
         log!(info "the compiling process has completed");
         Ok(CompleteArtifact::new((codeobj, last), arti.warns))
@@ -263,14 +347,64 @@ impl Compiler {
         src: String,
         mode: &str,
     ) -> Result<CompleteArtifact, ErrorArtifact> {
-        let artifact = self.builder.build(src, mode)?;
+        let mut artifact = self.builder.build(src, mode)?;
+        if self.cfg.prune_modules && !self.cfg.input.is_repl() {
+            ModulePruner::new(&self.cfg, &self.shared).prune(&mut artifact.object);
+        }
+        self.init_order = InitOrderAnalyzer::new(&self.cfg, &self.shared.mod_cache)
+            .analyze(&artifact.object)
+            .map_err(|errs| ErrorArtifact::new(errs, artifact.warns.clone()))?;
         let linker = HIRLinker::new(&self.cfg, &self.shared.mod_cache);
         let hir = linker.link(artifact.object);
         let hir = HIRDesugarer::desugar(hir);
-        let hir = HIROptimizer::optimize(self.cfg.clone(), self.shared.clone(), hir);
+        // the script is generated from the same HIR as `Transpiler` does (i.e. before the optimization)
+        if self.cfg.dump_py && !self.cfg.input.is_repl() {
+            self.dump_py(hir.clone());
+        }
+        let ctx = &self.builder.get_context().unwrap().context;
+        let hir = HIROptimizer::optimize(self.cfg.clone(), self.shared.clone(), ctx, hir);
+        InvariantChecker::check(&hir, "optimize");
         Ok(CompleteArtifact::new(hir, artifact.warns))
     }
 
+    /// Emits a `.pyi` stub of the module most recently compiled.
+    pub fn dump_pyi(&self) {
+        if let Some(module) = self.builder.get_context() {
+            PyStubGenerator::new()
+                .dump(&module.context, self.cfg.dump_pyi_path())
+                .expect("failed to dump a .pyi file (maybe permission denied)");
+        }
+    }
+
+    /// Emits the transpiled Python script of `hir` (`--dump-py`).
+    fn dump_py(&self, hir: HIR) {
+        let script = ScriptGenerator::new().transpile(hir);
+        fs::write(self.cfg.dump_py_path(), script.code)
+            .expect("failed to dump a .py file (maybe permission denied)");
+    }
+
+    pub const fn shared(&self) -> &SharedCompilerResource {
+        &self.shared
+    }
+
+    /// The paths of the modules linked at the last compilation, in the order of initialization.
+    /// The last one is the main module.
+    pub fn init_order(&self) -> &[PathBuf] {
+        &self.init_order
+    }
+
+    pub fn source_map(&self) -> &SourceMap {
+        self.code_generator.source_map()
+    }
+
+    /// Emits a source map of the module most recently compiled.
+    pub fn dump_source_map(&self) {
+        self.code_generator
+            .source_map()
+            .dump(self.cfg.dump_source_map_path())
+            .expect("failed to dump a .er.map file (maybe permission denied)");
+    }
+
     pub fn initialize_generator(&mut self) {
         self.code_generator.initialize();
     }
@@ -3,15 +3,16 @@ use std::option::Option; // conflicting to Type::Option
 
 use erg_common::consts::DEBUG_MODE;
 use erg_common::dict::Dict;
+use erg_common::set::Set;
 use erg_common::style::colors::DEBUG_ERROR;
 use erg_common::traits::StructuralEq;
 use erg_common::{assume_unreachable, log};
 use erg_common::{Str, Triple};
 
 use crate::context::initialize::const_func::sub_tpdict_get;
-use crate::ty::constructors::{and, bounded, not, or, poly};
-use crate::ty::free::{Constraint, FreeKind, FreeTyVar};
-use crate::ty::typaram::{TyParam, TyParamOrdering};
+use crate::ty::constructors::{and, bounded, not, or, poly, refinement};
+use crate::ty::free::{probe, Constraint, FreeKind, FreeTyVar};
+use crate::ty::typaram::{OpKind, TyParam, TyParamOrdering};
 use crate::ty::value::ValueObj;
 use crate::ty::value::ValueObj::Inf;
 use crate::ty::{Field, GuardType, Predicate, RefinementType, SubrKind, SubrType, Type};
@@ -257,31 +258,26 @@ impl Context {
             return (Maybe, false);
         }
         if let Some((typ, ty_ctx)) = self.get_nominal_type_ctx(rhs) {
-            if typ.has_qvar() {
-                if let Err(err) = self.substitute_typarams(typ, rhs) {
-                    Self::undo_substitute_typarams(typ);
-                    if DEBUG_MODE {
-                        panic!("{typ} / {rhs}: err: {err}");
-                    }
-                }
-            }
-            for rhs_sup in ty_ctx.super_classes.iter() {
-                // Not `supertype_of` (only structures are compared)
-                match Self::cheap_supertype_of(lhs, rhs_sup) {
-                    (Absolutely, true) => {
-                        Self::undo_substitute_typarams(typ);
-                        return (Absolutely, true);
-                    }
-                    (Maybe, _) => {
-                        if self.structural_supertype_of(lhs, rhs_sup) {
-                            Self::undo_substitute_typarams(typ);
-                            return (Absolutely, true);
+            // the substitution is undone after the check
+            let judge = probe(|| {
+                if typ.has_qvar() {
+                    if let Err(err) = self.substitute_typarams(typ, rhs) {
+                        if DEBUG_MODE {
+                            panic!("{typ} / {rhs}: err: {err}");
                         }
                     }
-                    _ => {}
                 }
+                ty_ctx.super_classes.iter().any(|rhs_sup| {
+                    // Not `supertype_of` (only structures are compared)
+                    match Self::cheap_supertype_of(lhs, rhs_sup) {
+                        (Absolutely, judge) => judge,
+                        (Maybe, _) => self.structural_supertype_of(lhs, rhs_sup),
+                    }
+                })
+            });
+            if judge {
+                return (Absolutely, true);
             }
-            Self::undo_substitute_typarams(typ);
         }
         (Maybe, false)
     }
@@ -294,38 +290,32 @@ impl Context {
             return (Maybe, false);
         }
         if let Some((typ, rhs_ctx)) = self.get_nominal_type_ctx(rhs) {
-            if typ.has_qvar() {
-                if let Err(err) = self.substitute_typarams(typ, rhs) {
-                    Self::undo_substitute_typarams(typ);
-                    if DEBUG_MODE {
-                        panic!("err: {err}");
-                    }
-                }
-            } else if typ.has_undoable_linked_var() {
-                if let Err(err) = self.overwrite_typarams(typ, rhs) {
-                    Self::undo_substitute_typarams(typ);
-                    if DEBUG_MODE {
-                        panic!("err: {err}");
-                    }
-                }
-            }
-            for rhs_sup in rhs_ctx.super_traits.iter() {
-                // Not `supertype_of` (only structures are compared)
-                match Self::cheap_supertype_of(lhs, rhs_sup) {
-                    (Absolutely, true) => {
-                        Self::undo_substitute_typarams(typ);
-                        return (Absolutely, true);
+            // the substitution is undone after the check
+            let judge = probe(|| {
+                if typ.has_qvar() {
+                    if let Err(err) = self.substitute_typarams(typ, rhs) {
+                        if DEBUG_MODE {
+                            panic!("err: {err}");
+                        }
                     }
-                    (Maybe, _) => {
-                        if self.structural_supertype_of(lhs, rhs_sup) {
-                            Self::undo_substitute_typarams(typ);
-                            return (Absolutely, true);
+                } else if typ.has_undoable_linked_var() {
+                    if let Err(err) = self.overwrite_typarams(typ, rhs) {
+                        if DEBUG_MODE {
+                            panic!("err: {err}");
                         }
                     }
-                    _ => {}
                 }
+                rhs_ctx.super_traits.iter().any(|rhs_sup| {
+                    // Not `supertype_of` (only structures are compared)
+                    match Self::cheap_supertype_of(lhs, rhs_sup) {
+                        (Absolutely, judge) => judge,
+                        (Maybe, _) => self.structural_supertype_of(lhs, rhs_sup),
+                    }
+                })
+            });
+            if judge {
+                return (Absolutely, true);
             }
-            Self::undo_substitute_typarams(typ);
         }
         (Maybe, false)
     }
@@ -343,7 +333,13 @@ impl Context {
         match (lhs, rhs) {
             // Proc :> Func if params are compatible
             // * default params can be omitted (e.g. (Int, x := Int) -> Int <: (Int) -> Int)
-            (Subr(ls), Subr(rs)) if ls.kind == rs.kind || ls.kind.is_proc() => {
+            // * the kind variable (~>) is compatible with both Func and Proc
+            (Subr(ls), Subr(rs))
+                if ls.kind == rs.kind
+                    || ls.kind.is_proc()
+                    || ls.kind.is_var()
+                    || rs.kind.is_var() =>
+            {
                 let default_check = || {
                     for lpt in ls.default_params.iter() {
                         if let Some(rpt) = rs
@@ -515,6 +511,10 @@ impl Context {
                 }
                 true
             }
+            // {.x = Int} :> Module("foo") if `foo.x: Int`
+            (Record(_), module) if module.is_module() => self
+                .module_record_t(module)
+                .is_some_and(|rec| self.supertype_of(lhs, &rec)),
             (Type, Record(rec)) => {
                 for (_, t) in rec.iter() {
                     if !self.supertype_of(&Type, t) {
@@ -770,13 +770,8 @@ impl Context {
                     return Dict::new();
                 };
                 let mod_fields = if other.is_module() {
-                    if let Ok(ValueObj::Str(mod_name)) =
-                        ValueObj::try_from(other.typarams()[0].clone())
-                    {
-                        self.get_mod(&mod_name).unwrap().local_dir()
-                    } else {
-                        Dict::new()
-                    }
+                    self.get_mod_with_t(other)
+                        .map_or(Dict::new(), |ctx| ctx.local_dir())
                 } else {
                     Dict::new()
                 };
@@ -1238,6 +1233,10 @@ impl Context {
     fn union_refinement(&self, lhs: &RefinementType, rhs: &RefinementType) -> RefinementType {
         // TODO: warn if lhs.t !:> rhs.t && rhs.t !:> lhs.t
         let union = self.union(&lhs.t, &rhs.t);
+        // {1, 2} or {2, 3} == {1, 2, 3}
+        if let Some(elems) = self.eval_enum_elems(OpKind::Or, lhs, rhs) {
+            return lhs.clone().with_enum_elems(union, elems);
+        }
         let name = lhs.var.clone();
         let rhs_pred = rhs.pred.clone().change_subject_name(name);
         // FIXME: predの包含関係も考慮する
@@ -1310,11 +1309,30 @@ impl Context {
         rhs: &RefinementType,
     ) -> RefinementType {
         let intersec = self.intersection(&lhs.t, &rhs.t);
+        // {1, 2} and {2, 3} == {2}
+        if let Some(elems) = self.eval_enum_elems(OpKind::And, lhs, rhs) {
+            return lhs.clone().with_enum_elems(intersec, elems);
+        }
         let name = lhs.var.clone();
         let rhs_pred = rhs.pred.clone().change_subject_name(name);
         RefinementType::new(lhs.var.clone(), intersec, *lhs.pred.clone() & rhs_pred)
     }
 
+    /// Applies the set operation to the elements of two enum types.
+    /// Returns `None` if either of them is not an enum type (e.g. `{I: Int | I >= 0}`).
+    fn eval_enum_elems(
+        &self,
+        op: OpKind,
+        lhs: &RefinementType,
+        rhs: &RefinementType,
+    ) -> Option<Set<TyParam>> {
+        let (l, r) = (lhs.enum_elems()?, rhs.enum_elems()?);
+        match self.eval_bin_tp(op, TyParam::Set(l), TyParam::Set(r)) {
+            Ok(TyParam::Set(elems)) => Some(elems),
+            _ => None,
+        }
+    }
+
     /// ```erg
     /// {x: Int | True}.try_squash() == Ok(Int)
     /// {x: Int or NoneType | x != None}.squash() == Ok(Int)
@@ -1580,6 +1598,58 @@ impl Context {
         }
     }
 
+    /// Tries to prove `0 <= i < len` from the bounds of the index type `idx_t` (`i: idx_t`).
+    /// * `Some(true)`: the index is always in bounds
+    /// * `Some(false)`: the bounds of the index exceed the range (e.g. `{I: Nat | I <= 5}` for `len == 3`)
+    /// * `None`: the index is not bounded (e.g. `Int`), so the access must be checked at runtime
+    pub(crate) fn index_in_bounds(&self, idx_t: &Type, len: &TyParam) -> Option<bool> {
+        match idx_t {
+            FreeVar(fv) if fv.is_linked() => return self.index_in_bounds(&fv.crack(), len),
+            // `{0, 3}`: every element must be in bounds
+            Refinement(refine) if refine.pred.ors().len() > 1 => {
+                let mut res = Some(true);
+                for pred in refine.pred.ors() {
+                    let elem_t = refinement(refine.var.clone(), *refine.t.clone(), pred.clone());
+                    match self.index_in_bounds(&elem_t, len) {
+                        Some(false) => return Some(false),
+                        None => res = None,
+                        Some(true) => {}
+                    }
+                }
+                return res;
+            }
+            _ => {}
+        }
+        if !self.subtype_of(idx_t, &Int) {
+            return None;
+        }
+        let zero = TyParam::value(0usize);
+        let is_finite =
+            |tp: &TyParam| !matches!(tp, TyParam::Value(ValueObj::Inf | ValueObj::NegInf));
+        let inf = self
+            .inf(idx_t)
+            .or_else(|| match idx_t {
+                // `{I: Nat | I <= 2}` has no explicit lower bound
+                Refinement(refine) if self.subtype_of(&refine.t, &Nat) => Some(zero.clone()),
+                _ => None,
+            })
+            .filter(is_finite);
+        let sup = self.sup(idx_t).filter(is_finite);
+        let cmp = |bound: &Option<TyParam>, rhs: &TyParam| {
+            bound.as_ref().and_then(|bound| self.try_cmp(bound, rhs))
+        };
+        let out_of_bounds = |bound: &Option<TyParam>| {
+            cmp(bound, &zero) == Some(Less) || matches!(cmp(bound, len), Some(Greater | Equal))
+        };
+        if matches!(cmp(&inf, &zero), Some(Greater | Equal)) && cmp(&sup, len) == Some(Less) {
+            Some(true)
+        } else if out_of_bounds(&inf) || out_of_bounds(&sup) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
     /// If lhs and rhs are in a subtype relation, return the smaller one
     /// Return None if they are not related
     /// lhsとrhsが包含関係にあるとき小さいほうを返す
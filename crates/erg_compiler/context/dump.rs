@@ -0,0 +1,139 @@
+//! Canonical textual dump of `Context`, used for golden tests.
+//!
+//! Each line of the dump is one of the following (sorted):
+//! ```text
+//! decl .print!: ...
+//! local .Int.abs: (self: Int) -> Nat
+//! local .Int|<: Add(Int)|.__add__: ...
+//! super .Int <: Float
+//! impl Int <: Add(Int)
+//! ```
+use std::fmt::Write as _;
+
+use crate::context::{ClassDefType, Context, ContextKind};
+use crate::varinfo::VarInfo;
+
+use ast::VarName;
+use erg_parser::ast;
+
+fn qual_name(prefix: &str, name: &VarName, vi: &VarInfo) -> String {
+    let sep = if vi.vis.is_public() { "." } else { "::" };
+    format!("{prefix}{sep}{name}")
+}
+
+/// The fresh names (e.g. `%v_global_81`) depend on the order of the generation, so they are renumbered in each line.
+fn canonicalize_fresh_names(line: &str) -> String {
+    let mut names = vec![];
+    let mut res = String::new();
+    let mut rest = line;
+    while let Some(i) = rest.find("%v_") {
+        res.push_str(&rest[..i]);
+        let len = rest[i..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '%'))
+            .unwrap_or(rest.len() - i);
+        let name = &rest[i..i + len];
+        let n = names.iter().position(|n| n == &name).unwrap_or_else(|| {
+            names.push(name);
+            names.len() - 1
+        });
+        let _ = write!(res, "%v{n}");
+        rest = &rest[i + len..];
+    }
+    res.push_str(rest);
+    res
+}
+
+impl Context {
+    /// Returns a canonical, sorted textual dump of the declarations, local variables,
+    /// types (with their methods and super types), patches, and trait implementations.
+    /// Since each line is independent, the dumps can be compared line by line (see `Context::diff_dump`).
+    pub fn dump(&self) -> String {
+        let mut lines = vec![];
+        self.dump_into("", &mut lines);
+        if self.kind == ContextKind::Module {
+            if let Some(shared) = self.shared.as_ref() {
+                for impls in shared.trait_impls.ref_inner().values() {
+                    for imp in impls.iter() {
+                        lines.push(format!("impl {} <: {}", imp.sub_type, imp.sup_trait));
+                    }
+                }
+            }
+        }
+        let mut lines = lines
+            .iter()
+            .map(|line| canonicalize_fresh_names(line))
+            .collect::<Vec<_>>();
+        lines.sort();
+        let mut dump = String::new();
+        for line in lines {
+            let _ = writeln!(dump, "{line}");
+        }
+        dump
+    }
+
+    fn dump_into(&self, prefix: &str, lines: &mut Vec<String>) {
+        for (name, vi) in self.decls.iter() {
+            lines.push(format!("decl {}: {}", qual_name(prefix, name, vi), vi.t));
+        }
+        for (name, vi) in self.locals.iter() {
+            lines.push(format!("local {}: {}", qual_name(prefix, name, vi), vi.t));
+        }
+        for (name, (_, ctx)) in self.mono_types.iter().chain(self.poly_types.iter()) {
+            let vi = self.get_current_scope_var(name);
+            let path = vi.map_or(format!("{prefix}.{name}"), |vi| qual_name(prefix, name, vi));
+            ctx.dump_type_into(&path, lines);
+        }
+        for (name, ctx) in self.patches.iter() {
+            let path = format!("{prefix}.{name}");
+            ctx.dump_type_into(&path, lines);
+        }
+    }
+
+    fn dump_type_into(&self, path: &str, lines: &mut Vec<String>) {
+        for sup in self.super_classes.iter().chain(self.super_traits.iter()) {
+            lines.push(format!("super {path} <: {sup}"));
+        }
+        self.dump_into(path, lines);
+        for (def_t, methods) in self.methods_list.iter() {
+            let prefix = match def_t {
+                ClassDefType::Simple(_) => path.to_string(),
+                ClassDefType::ImplTrait { impl_trait, .. } => format!("{path}|<: {impl_trait}|"),
+            };
+            methods.dump_into(&prefix, lines);
+        }
+    }
+
+    /// Compares two dumps (returned by `Context::dump`) and returns the lines only in `expected` (prefixed by `-`)
+    /// and the lines only in `actual` (prefixed by `+`).
+    /// Returns an empty string if they are the same.
+    pub fn diff_dump(expected: &str, actual: &str) -> String {
+        let mut expected = expected.lines().collect::<Vec<_>>();
+        let mut actual = actual.lines().collect::<Vec<_>>();
+        expected.sort_unstable();
+        actual.sort_unstable();
+        let (mut i, mut j) = (0, 0);
+        let mut diff = String::new();
+        while i < expected.len() || j < actual.len() {
+            match (expected.get(i), actual.get(j)) {
+                (Some(l), Some(r)) if l == r => {
+                    i += 1;
+                    j += 1;
+                }
+                (Some(l), Some(r)) if l < r => {
+                    let _ = writeln!(diff, "-{l}");
+                    i += 1;
+                }
+                (Some(l), None) => {
+                    let _ = writeln!(diff, "-{l}");
+                    i += 1;
+                }
+                (_, Some(r)) => {
+                    let _ = writeln!(diff, "+{r}");
+                    j += 1;
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+        diff
+    }
+}
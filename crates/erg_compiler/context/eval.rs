@@ -1,6 +1,6 @@
+use std::cell::Cell;
 use std::mem;
 
-use erg_common::consts::DEBUG_MODE;
 use erg_common::dict::Dict;
 use erg_common::error::Location;
 #[allow(unused)]
@@ -14,17 +14,20 @@ use OpKind::*;
 use erg_parser::ast::Dict as AstDict;
 use erg_parser::ast::Set as AstSet;
 use erg_parser::ast::*;
-use erg_parser::desugar::Desugarer;
+use erg_parser::desugar::{Desugarer, NameOccurrence};
 use erg_parser::token::{Token, TokenKind};
 
 use crate::ty::constructors::{
     array_t, dict_t, mono, poly, proj, proj_call, ref_, ref_mut, refinement, set_t, subr_t,
     tp_enum, tuple_t, v_enum,
 };
-use crate::ty::free::{FreeTyVar, HasLevel};
+use crate::ty::free::{is_recording, probe_generalized, HasLevel};
+use crate::ty::rational::Rational;
 use crate::ty::typaram::{OpKind, TyParam};
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
-use crate::ty::{ConstSubr, HasType, Predicate, SubrKind, Type, UserConstSubr, ValueArgs};
+use crate::ty::{
+    ConstSubr, HasType, Predicate, SubrKind, Type, UserConstSubr, ValueArgs, VisibilityModifier,
+};
 
 use crate::context::instantiate_spec::ParamKind;
 use crate::context::{ClassDefType, Context, ContextKind, RegistrationMode};
@@ -33,6 +36,20 @@ use crate::error::{EvalError, EvalErrors, EvalResult, SingleEvalResult};
 use super::instantiate::TyVarCache;
 use Type::{Failure, Never, Subr};
 
+thread_local! {
+    /// the current nesting depth of `eval_t_params`, `eval_proj` and `eval_proj_call`
+    static EVAL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Leaves the nested type evaluation when dropped (see `Context::enter_type_eval`).
+struct EvalDepthGuard;
+
+impl Drop for EvalDepthGuard {
+    fn drop(&mut self) {
+        EVAL_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
 macro_rules! feature_error {
     ($ctx: expr, $loc: expr, $name: expr) => {
         $crate::feature_error!(EvalErrors, EvalError, $ctx, $loc, $name)
@@ -88,11 +105,48 @@ fn op_to_name(op: OpKind) -> &'static str {
         OpKind::BitXor => "__bitxor__",
         OpKind::Shl => "__shl__",
         OpKind::Shr => "__shr__",
+        // `x in c` is `c.__contains__(x)`
+        OpKind::In | OpKind::NotIn => "__contains__",
+    }
+}
+
+/// The next float after `x` in the direction of `toward` (C's `nextafter`).
+fn next_after(x: f64, toward: f64) -> f64 {
+    if x.is_nan() || toward.is_nan() {
+        return f64::NAN;
+    }
+    if x == toward {
+        return toward;
+    }
+    if x == 0.0 {
+        // the smallest subnormal number
+        return f64::from_bits(1).copysign(toward);
+    }
+    // the bit patterns of floats of the same sign are ordered by their magnitudes
+    let bits = x.to_bits();
+    if (toward > x) == (x > 0.0) {
+        f64::from_bits(bits + 1)
+    } else {
+        f64::from_bits(bits - 1)
+    }
+}
+
+/// The float nearest to `r` in the direction of `toward`, excluding `r` itself.
+/// e.g. `succ` of `0.1` (`1/10`) is the float next to `0.1f64`, because `0.1f64` is greater than `1/10`.
+fn ratio_next_after(r: Rational, toward: f64) -> f64 {
+    let f = r.to_f64();
+    // `None` only if the magnitude is extreme (then `f` is regarded as rounded toward `r`)
+    let rounded_toward = Rational::from_f64(f)
+        .is_some_and(|exact| (toward > f && exact > r) || (toward < f && exact < r));
+    if rounded_toward {
+        f
+    } else {
+        next_after(f, toward)
     }
 }
 
 impl Context {
-    fn try_get_op_kind_from_token(&self, token: &Token) -> EvalResult<OpKind> {
+    pub(crate) fn try_get_op_kind_from_token(&self, token: &Token) -> EvalResult<OpKind> {
         match token.kind {
             TokenKind::Plus => Ok(OpKind::Add),
             TokenKind::Minus => Ok(OpKind::Sub),
@@ -114,6 +168,8 @@ impl Context {
             TokenKind::BitOr => Ok(OpKind::BitOr),
             TokenKind::Shl => Ok(OpKind::Shl),
             TokenKind::Shr => Ok(OpKind::Shr),
+            TokenKind::InOp => Ok(OpKind::In),
+            TokenKind::NotInOp => Ok(OpKind::NotIn),
             TokenKind::PrePlus => Ok(OpKind::Pos),
             TokenKind::PreMinus => Ok(OpKind::Neg),
             TokenKind::PreBitNot => Ok(OpKind::Invert),
@@ -220,7 +276,15 @@ impl Context {
         let lhs = self.eval_const_expr(&bin.args[0])?;
         let rhs = self.eval_const_expr(&bin.args[1])?;
         let op = self.try_get_op_kind_from_token(&bin.op)?;
-        self.eval_bin(op, lhs, rhs)
+        // e.g. `1 // 0` or an overflow, which is left to the runtime
+        self.eval_bin(op, lhs, rhs).map_err(|_| {
+            EvalErrors::from(EvalError::not_const_expr(
+                self.cfg.input.clone(),
+                line!() as usize,
+                bin.loc(),
+                self.caused_by(),
+            ))
+        })
     }
 
     fn eval_const_unary(&self, unary: &UnaryOp) -> EvalResult<ValueObj> {
@@ -243,9 +307,45 @@ impl Context {
         Ok(ValueArgs::new(evaluated_pos_args, evaluated_kw_args))
     }
 
+    /// Same as `eval_args`, but the lambdas passed to the method are evaluated lazily.
+    fn eval_method_args(&self, args: &Args) -> EvalResult<ValueArgs> {
+        let eval = |expr: &Expr| match expr {
+            Expr::Lambda(lambda) => self.eval_const_lambda(lambda, true),
+            other => self.eval_const_expr(other),
+        };
+        let mut evaluated_pos_args = vec![];
+        for arg in args.pos_args().iter() {
+            evaluated_pos_args.push(eval(&arg.expr)?);
+        }
+        let mut evaluated_kw_args = dict! {};
+        for arg in args.kw_args().iter() {
+            evaluated_kw_args.insert(arg.keyword.inspect().clone(), eval(&arg.expr)?);
+        }
+        Ok(ValueArgs::new(evaluated_pos_args, evaluated_kw_args))
+    }
+
     fn eval_const_call(&self, call: &Call) -> EvalResult<ValueObj> {
+        if let Some(attr_name) = &call.attr_name {
+            return self.eval_const_method_call(call, attr_name);
+        }
+        // `if` and `quote` are not macros unless they are redefined as const subroutines
+        if let Some(subr) = self.get_macro(call) {
+            let expanded = self.expand_macro(subr, call)?;
+            return self.eval_const_expr(&expanded);
+        }
         if let Expr::Accessor(acc) = call.obj.as_ref() {
             match acc {
+                Accessor::Ident(ident)
+                    if &ident.inspect()[..] == "if" && self.rec_get_const_obj("if").is_none() =>
+                {
+                    self.eval_const_if(call)
+                }
+                Accessor::Ident(ident)
+                    if &ident.inspect()[..] == "quote"
+                        && self.rec_get_const_obj("quote").is_none() =>
+                {
+                    self.eval_const_quote(call)
+                }
                 Accessor::Ident(ident) => {
                     let obj = self.rec_get_const_obj(ident.inspect()).ok_or_else(|| {
                         EvalError::no_var_error(
@@ -274,7 +374,7 @@ impl Context {
                         })?
                         .clone();
                     let args = self.eval_args(&call.args)?;
-                    self.call(subr, args, call.loc())
+                    self.call_with_args(subr, args, Some(call), call.loc())
                 }
                 // TODO: eval attr
                 Accessor::Attr(_attr) => Err(EvalErrors::from(EvalError::not_const_expr(
@@ -290,7 +390,7 @@ impl Context {
                     call.loc(),
                     self.caused_by(),
                 ))),
-                _ => unreachable!(),
+                other => feature_error!(self, other.loc(), "constant call of this accessor"),
             }
         } else {
             Err(EvalErrors::from(EvalError::not_const_expr(
@@ -302,30 +402,198 @@ impl Context {
         }
     }
 
-    fn call(&self, subr: ConstSubr, args: ValueArgs, loc: Location) -> EvalResult<ValueObj> {
+    /// `[3, 1, 2].sort()` => `[1, 2, 3]`
+    ///
+    /// Only the const methods (e.g. `Array.map`, `Array.union`) can be called.
+    fn eval_const_method_call(&self, call: &Call, attr_name: &Identifier) -> EvalResult<ValueObj> {
+        let not_const = || {
+            EvalErrors::from(EvalError::not_const_expr(
+                self.cfg.input.clone(),
+                line!() as usize,
+                call.loc(),
+                self.caused_by(),
+            ))
+        };
+        let obj = self.eval_const_expr(&call.obj)?;
+        let name = Token::symbol(attr_name.inspect());
+        let method = self
+            .get_nominal_super_type_ctxs(&obj.class())
+            .ok_or_else(not_const)?
+            .into_iter()
+            .find_map(|ctx| {
+                ctx.get_const_local(&name, &self.name).ok().or_else(|| {
+                    ctx.methods_list
+                        .iter()
+                        .find_map(|(_, methods)| methods.get_const_local(&name, &self.name).ok())
+                })
+            });
+        let Some(ValueObj::Subr(subr)) = method else {
+            return Err(not_const());
+        };
+        let mut args = self.eval_method_args(&call.args)?;
+        if subr.sig_t().is_method() {
+            args.pos_args.insert(0, obj);
+        }
+        self.call(subr, args, call.loc())
+    }
+
+    /// `if Cfg.debug, do: 1, else: 2` => `1` (if `Cfg.debug == True`)
+    ///
+    /// Only the chosen branch is evaluated, so the other branch may be non-const.
+    /// If there is no else branch and the condition is false, the result is `None`.
+    fn eval_const_if(&self, call: &Call) -> EvalResult<ValueObj> {
+        let not_const = |loc: Location| {
+            EvalErrors::from(EvalError::not_const_expr(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                self.caused_by(),
+            ))
+        };
+        let Some(cond) = call.args.nth_or_key(0, "cond") else {
+            return Err(not_const(call.loc()));
+        };
+        let ValueObj::Bool(cond) = self.eval_const_expr(cond)? else {
+            return Err(not_const(cond.loc()));
+        };
+        let branch = if cond {
+            call.args.nth_or_key(1, "then")
+        } else {
+            call.args.nth_or_key(2, "else")
+        };
+        let Some(branch) = branch else {
+            return if cond {
+                Err(not_const(call.loc()))
+            } else {
+                Ok(ValueObj::None)
+            };
+        };
+        match self.eval_const_expr(branch)? {
+            ValueObj::Subr(subr) => {
+                self.call(subr, ValueArgs::new(vec![], Dict::new()), branch.loc())
+            }
+            _ => Err(not_const(branch.loc())),
+        }
+    }
+
+    /// `quote(x + 1)` => `Ast(x + 1)`
+    ///
+    /// The names bound to `Ast`s (e.g. the parameters of a macro) are replaced with the quoted expressions,
+    /// so the other names in the expression are not evaluated (or even resolved) here.
+    /// The names defined in the expression are renamed (e.g. `y` => `F::y` in the macro `F`) beforehand,
+    /// so that they do not capture the names in the arguments.
+    fn eval_const_quote(&self, call: &Call) -> EvalResult<ValueObj> {
+        let Some(expr) = call.args.nth_or_key(0, "expr") else {
+            return Err(EvalErrors::from(EvalError::not_const_expr(
+                self.cfg.input.clone(),
+                line!() as usize,
+                call.loc(),
+                self.caused_by(),
+            )));
+        };
+        let mut bound = set! {};
+        Desugarer::walk_idents(expr, &mut |occurrence| {
+            if let NameOccurrence::Bind(name) = occurrence {
+                bound.insert(name.inspect().clone());
+            }
+        });
+        let rename = |name: &VarName| {
+            bound
+                .contains(name.inspect())
+                .then(|| Str::from(self.mangle_name(name.inspect(), &VisibilityModifier::Private)))
+        };
+        let expr = Desugarer::rename_idents(expr.clone(), &rename);
+        let subst = |ident: &Identifier| match self.rec_get_const_obj(ident.inspect()) {
+            Some(ValueObj::Ast(expr)) => Some(*expr.clone()),
+            _ => None,
+        };
+        let expr = Desugarer::substitute_idents(expr, &subst);
+        Ok(ValueObj::Ast(Box::new(expr)))
+    }
+
+    /// Returns the macro called by `call`.
+    /// A macro is a user-defined const subroutine whose parameters are all `Ast`s.
+    pub(crate) fn get_macro(&self, call: &Call) -> Option<ConstSubr> {
+        if call.attr_name.is_some() {
+            return None;
+        }
+        let Expr::Accessor(Accessor::Ident(ident)) = call.obj.as_ref() else {
+            return None;
+        };
+        let Some(ValueObj::Subr(subr @ ConstSubr::User(_))) =
+            self.rec_get_const_obj(ident.inspect())
+        else {
+            return None;
+        };
+        let sig_t = subr.sig_t();
+        let params = sig_t.non_default_params()?;
+        let ast = mono("Ast");
+        let is_macro = !params.is_empty()
+            && params
+                .iter()
+                .chain(sig_t.var_params())
+                .chain(sig_t.default_params()?)
+                .all(|pt| pt.typ() == &ast);
+        is_macro.then(|| subr.clone())
+    }
+
+    /// Calls the macro with the quoted (not evaluated) arguments and returns the expanded expression.
+    pub(crate) fn expand_macro(&self, subr: ConstSubr, call: &Call) -> EvalResult<Expr> {
+        let quote = |expr: &Expr| ValueObj::Ast(Box::new(expr.clone()));
+        let pos_args = call
+            .args
+            .pos_args()
+            .iter()
+            .map(|arg| quote(&arg.expr))
+            .collect();
+        let kw_args = call
+            .args
+            .kw_args()
+            .iter()
+            .map(|arg| (arg.keyword.inspect().clone(), quote(&arg.expr)))
+            .collect();
+        let args = ValueArgs::new(pos_args, kw_args);
+        match self.call_with_args(subr, args, Some(call), call.loc())? {
+            ValueObj::Ast(expr) => Ok(*expr),
+            other => Err(EvalErrors::from(EvalError::type_mismatch_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                call.loc(),
+                self.caused_by(),
+                &call.obj.to_string(),
+                None,
+                &mono("Ast"),
+                &other.t(),
+                None,
+                Some("a macro must return a quoted expression (`quote(...)`)".into()),
+            ))),
+        }
+    }
+
+    pub(crate) fn call(
+        &self,
+        subr: ConstSubr,
+        args: ValueArgs,
+        loc: Location,
+    ) -> EvalResult<ValueObj> {
+        self.call_with_args(subr, args, None, loc)
+    }
+
+    /// `ast_call` is the call before evaluation, which is used to point out the wrong argument.
+    fn call_with_args(
+        &self,
+        subr: ConstSubr,
+        args: ValueArgs,
+        ast_call: Option<&Call>,
+        loc: Location,
+    ) -> EvalResult<ValueObj> {
         match subr {
             ConstSubr::User(user) => {
-                // HACK: should avoid cloning
-                let mut subr_ctx = Context::instant(
-                    user.name.clone(),
-                    self.cfg.clone(),
-                    2,
-                    self.shared.clone(),
-                    self.clone(),
-                );
-                // TODO: var_args
-                for (arg, sig) in args
-                    .pos_args
-                    .into_iter()
-                    .zip(user.params.non_defaults.iter())
-                {
-                    let name = VarName::from_str(sig.inspect().unwrap().clone());
-                    subr_ctx.consts.insert(name, arg);
-                }
-                for (name, arg) in args.kw_args.into_iter() {
-                    subr_ctx.consts.insert(VarName::from_str(name), arg);
-                }
-                subr_ctx.eval_const_block(&user.block())
+                let caused_by = self.caused_by();
+                self.with_instant_frame(user.name.clone(), 2, |subr_ctx| {
+                    subr_ctx.bind_const_args(&user, args, ast_call, loc, caused_by)?;
+                    subr_ctx.eval_const_block(&user.block())
+                })
             }
             ConstSubr::Builtin(builtin) => builtin.call(args, self).map_err(|mut e| {
                 if e.0.loc.is_unknown() {
@@ -350,6 +618,137 @@ impl Context {
         }
     }
 
+    /// Binds the arguments to the parameters of a user-defined const subroutine, as a call at runtime does.
+    ///
+    /// * The positional arguments that are not bound to the non-default parameters are collected into the variable-length parameter (as an `Array`),
+    ///   or bound to the default parameters if there is none.
+    /// * The default parameters after the variable-length parameter can only be passed by keyword.
+    /// * The default values are evaluated only if the arguments are not passed, so they can refer to the preceding parameters.
+    fn bind_const_args(
+        &mut self,
+        callee: &UserConstSubr,
+        args: ValueArgs,
+        ast_call: Option<&Call>,
+        loc: Location,
+        caused_by: String,
+    ) -> EvalResult<()> {
+        let params = &callee.params;
+        let ast_args = ast_call.map(|call| &call.args);
+        let callee_name = match ast_call.map(|call| call.obj.as_ref()) {
+            Some(Expr::Accessor(Accessor::Ident(ident))) => &ident.inspect()[..],
+            _ => &callee.name[..],
+        };
+        let pos_loc = |i: usize| {
+            ast_args
+                .and_then(|args| args.pos_args().get(i))
+                .map_or(loc, |arg| arg.loc())
+        };
+        let kw_loc = |name: &str| {
+            ast_args
+                .and_then(|args| {
+                    args.kw_args()
+                        .iter()
+                        .find(|arg| &arg.keyword.inspect()[..] == name)
+                })
+                .map_or(loc, |arg| arg.loc())
+        };
+        let ValueArgs {
+            pos_args,
+            mut kw_args,
+        } = args;
+        let (pos_args_len, kw_args_len) = (pos_args.len(), kw_args.len());
+        let mut pos_args = pos_args.into_iter().enumerate();
+        let mut errs = EvalErrors::empty();
+        let mut missing = vec![];
+        let bind = |ctx: &mut Self, sig: &NonDefaultParamSignature, arg: ValueObj| {
+            if let Some(name) = sig.inspect() {
+                ctx.consts.insert(VarName::from_str(name.clone()), arg);
+            }
+        };
+        for sig in params.non_defaults.iter() {
+            if let Some((_, arg)) = pos_args.next() {
+                if let Some(name) = sig.inspect().filter(|name| kw_args.remove(*name).is_some()) {
+                    errs.push(EvalError::multiple_args_error(
+                        self.cfg.input.clone(),
+                        line!() as usize,
+                        kw_loc(name),
+                        callee_name,
+                        caused_by.clone(),
+                        name,
+                    ));
+                }
+                bind(self, sig, arg);
+            } else if let Some(arg) = sig.inspect().and_then(|name| kw_args.remove(name)) {
+                bind(self, sig, arg);
+            } else {
+                missing.push(sig.inspect().cloned().unwrap_or(Str::ever("_")));
+            }
+        }
+        if let Some(var_params) = params.var_params.as_ref() {
+            let rest = pos_args.by_ref().map(|(_, arg)| arg).collect::<Vec<_>>();
+            bind(self, var_params, ValueObj::Array(rest.into()));
+        }
+        for sig in params.defaults.iter() {
+            let name = sig.sig.inspect();
+            if let Some((_, arg)) = pos_args.next() {
+                if let Some(name) = name.filter(|name| kw_args.remove(*name).is_some()) {
+                    errs.push(EvalError::multiple_args_error(
+                        self.cfg.input.clone(),
+                        line!() as usize,
+                        kw_loc(name),
+                        callee_name,
+                        caused_by.clone(),
+                        name,
+                    ));
+                }
+                bind(self, &sig.sig, arg);
+            } else if let Some(arg) = name.and_then(|name| kw_args.remove(name)) {
+                bind(self, &sig.sig, arg);
+            } else {
+                let default = self.eval_const_expr(&sig.default_val)?;
+                bind(self, &sig.sig, default);
+            }
+        }
+        if let Some((i, _)) = pos_args.next() {
+            errs.push(EvalError::too_many_args_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                pos_loc(i),
+                callee_name,
+                caused_by.clone(),
+                params.non_defaults.len() + params.defaults.len(),
+                pos_args_len,
+                kw_args_len,
+            ));
+        }
+        for name in kw_args.keys() {
+            errs.push(EvalError::unexpected_kw_arg_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                kw_loc(name),
+                callee_name,
+                caused_by.clone(),
+                name,
+                None,
+            ));
+        }
+        if !missing.is_empty() {
+            errs.push(EvalError::args_missing_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                callee_name,
+                caused_by.clone(),
+                missing,
+            ));
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+
     fn eval_const_def(&mut self, def: &Def) -> EvalResult<ValueObj> {
         if def.is_const() {
             let __name__ = def.sig.ident().unwrap().inspect();
@@ -365,7 +764,7 @@ impl Context {
             // TODO: set params
             let kind = ContextKind::from(def);
             self.grow(__name__, kind, vis, tv_cache);
-            let obj = self.eval_const_block(&def.body.block).map_err(|errs| {
+            let obj = self.eval_const_def_body(&def.body.block).map_err(|errs| {
                 self.pop();
                 errs
             })?;
@@ -467,36 +866,35 @@ impl Context {
     }
 
     fn eval_const_normal_record(&self, record: &NormalRecord) -> EvalResult<ValueObj> {
-        let mut attrs = vec![];
-        // HACK: should avoid cloning
-        let mut record_ctx = Context::instant(
-            Str::ever("<unnamed record>"),
-            self.cfg.clone(),
-            2,
-            self.shared.clone(),
-            self.clone(),
-        );
-        for attr in record.attrs.iter() {
-            // let name = attr.sig.ident().map(|i| i.inspect());
-            let elem = record_ctx.eval_const_block(&attr.body.block)?;
-            let ident = match &attr.sig {
-                Signature::Var(var) => match &var.pat {
-                    VarPattern::Ident(ident) => self.instantiate_field(ident)?,
+        self.with_instant_frame(Str::ever("<unnamed record>"), 2, |record_ctx| {
+            let mut attrs = vec![];
+            for attr in record.attrs.iter() {
+                // let name = attr.sig.ident().map(|i| i.inspect());
+                let elem = record_ctx.eval_const_block(&attr.body.block)?;
+                let ident = match &attr.sig {
+                    Signature::Var(var) => match &var.pat {
+                        VarPattern::Ident(ident) => self.instantiate_field(ident)?,
+                        other => {
+                            return feature_error!(
+                                self,
+                                other.loc(),
+                                &format!("record field: {other}")
+                            )
+                        }
+                    },
                     other => {
                         return feature_error!(self, other.loc(), &format!("record field: {other}"))
                     }
-                },
-                other => {
-                    return feature_error!(self, other.loc(), &format!("record field: {other}"))
-                }
-            };
-            attrs.push((ident, elem));
-        }
-        Ok(ValueObj::Record(attrs.into_iter().collect()))
+                };
+                attrs.push((ident, elem));
+            }
+            Ok(ValueObj::Record(attrs.into_iter().collect()))
+        })
     }
 
     /// FIXME: grow
-    fn eval_const_lambda(&self, lambda: &Lambda) -> EvalResult<ValueObj> {
+    /// If `deferred`, the body is evaluated only when the lambda is called (e.g. `[1, 2].map(x -> x + 1)`).
+    fn eval_const_lambda(&self, lambda: &Lambda, deferred: bool) -> EvalResult<ValueObj> {
         let mut tmp_tv_cache =
             self.instantiate_ty_bounds(&lambda.sig.bounds, RegistrationMode::Normal)?;
         let mut non_default_params = Vec::with_capacity(lambda.sig.params.non_defaults.len());
@@ -526,26 +924,29 @@ impl Context {
         };
         let mut default_params = Vec::with_capacity(lambda.sig.params.defaults.len());
         for sig in lambda.sig.params.defaults.iter() {
-            let expr = self.eval_const_expr(&sig.default_val)?;
+            // the default value may refer to the preceding parameters (e.g. `(x, y := x) -> ...`)
+            let default_t = match self.eval_const_expr(&sig.default_val) {
+                Ok(default) => default.t(),
+                Err(_) => Type::Obj,
+            };
             let pt = self.instantiate_param_ty(
                 &sig.sig,
                 None,
                 &mut tmp_tv_cache,
                 RegistrationMode::Normal,
-                ParamKind::Default(expr.t()),
+                ParamKind::Default(default_t),
                 false,
             )?;
             default_params.push(pt);
         }
-        // HACK: should avoid cloning
-        let mut lambda_ctx = Context::instant(
-            Str::ever("<lambda>"),
-            self.cfg.clone(),
-            0,
-            self.shared.clone(),
-            self.clone(),
-        );
-        let return_t = v_enum(set! {lambda_ctx.eval_const_block(&lambda.body)?});
+        let body = self.with_instant_frame(Str::ever("<lambda>"), 0, |lambda_ctx| {
+            lambda_ctx.eval_const_block(&lambda.body)
+        });
+        let return_t = match body {
+            Ok(value) => v_enum(set! {value}),
+            Err(_) if deferred => Type::Obj,
+            Err(errs) => return Err(errs),
+        };
         let sig_t = subr_t(
             SubrKind::from(lambda.op.kind),
             non_default_params.clone(),
@@ -597,7 +998,7 @@ impl Context {
             Expr::Dict(dict) => self.eval_const_dict(dict),
             Expr::Tuple(tuple) => self.eval_const_tuple(tuple),
             Expr::Record(rec) => self.eval_const_record(rec),
-            Expr::Lambda(lambda) => self.eval_const_lambda(lambda),
+            Expr::Lambda(lambda) => self.eval_const_lambda(lambda, false),
             // FIXME: type check
             Expr::TypeAscription(tasc) => self.eval_const_expr(&tasc.expr),
             other => Err(EvalErrors::from(EvalError::not_const_expr(
@@ -627,7 +1028,7 @@ impl Context {
             Expr::Dict(dict) => self.eval_const_dict(dict),
             Expr::Tuple(tuple) => self.eval_const_tuple(tuple),
             Expr::Record(rec) => self.eval_const_record(rec),
-            Expr::Lambda(lambda) => self.eval_const_lambda(lambda),
+            Expr::Lambda(lambda) => self.eval_const_lambda(lambda, false),
             Expr::TypeAscription(tasc) => self.eval_const_expr(&tasc.expr),
             other => Err(EvalErrors::from(EvalError::not_const_expr(
                 self.cfg.input.clone(),
@@ -638,6 +1039,15 @@ impl Context {
         }
     }
 
+    /// Evaluates the body of a constant definition.
+    /// A lambda defined as a constant (e.g. `F = (x, y := 1) -> x + y`) is deferred, since its parameters are bound only by a call.
+    pub(crate) fn eval_const_def_body(&mut self, block: &Block) -> EvalResult<ValueObj> {
+        match (block.len(), block.first()) {
+            (1, Some(Expr::Lambda(lambda))) => self.eval_const_lambda(lambda, true),
+            _ => self.eval_const_block(block),
+        }
+    }
+
     pub(crate) fn eval_const_block(&mut self, block: &Block) -> EvalResult<ValueObj> {
         for chunk in block.iter().rev().skip(1).rev() {
             self.eval_const_chunk(chunk)?;
@@ -645,7 +1055,12 @@ impl Context {
         self.eval_const_chunk(block.last().unwrap())
     }
 
-    fn eval_bin(&self, op: OpKind, lhs: ValueObj, rhs: ValueObj) -> EvalResult<ValueObj> {
+    pub(crate) fn eval_bin(
+        &self,
+        op: OpKind,
+        lhs: ValueObj,
+        rhs: ValueObj,
+    ) -> EvalResult<ValueObj> {
         match op {
             Add => lhs.try_add(rhs).ok_or_else(|| {
                 EvalErrors::from(EvalError::unreachable(
@@ -682,6 +1097,13 @@ impl Context {
                     line!(),
                 ))
             }),
+            Mod => lhs.try_mod(rhs).ok_or_else(|| {
+                EvalErrors::from(EvalError::unreachable(
+                    self.cfg.input.clone(),
+                    fn_name!(),
+                    line!(),
+                ))
+            }),
             Gt => lhs.try_gt(rhs).ok_or_else(|| {
                 EvalErrors::from(EvalError::unreachable(
                     self.cfg.input.clone(),
@@ -724,9 +1146,25 @@ impl Context {
                     line!(),
                 ))
             }),
+            In => lhs.try_in(rhs).ok_or_else(|| {
+                EvalErrors::from(EvalError::unreachable(
+                    self.cfg.input.clone(),
+                    fn_name!(),
+                    line!(),
+                ))
+            }),
+            NotIn => match lhs.try_in(rhs) {
+                Some(ValueObj::Bool(b)) => Ok(ValueObj::Bool(!b)),
+                _ => Err(EvalErrors::from(EvalError::unreachable(
+                    self.cfg.input.clone(),
+                    fn_name!(),
+                    line!(),
+                ))),
+            },
             Or | BitOr => match (lhs, rhs) {
                 (ValueObj::Bool(l), ValueObj::Bool(r)) => Ok(ValueObj::Bool(l || r)),
                 (ValueObj::Int(l), ValueObj::Int(r)) => Ok(ValueObj::Int(l | r)),
+                (ValueObj::Set(l), ValueObj::Set(r)) => Ok(ValueObj::Set(l.union(&r))),
                 (ValueObj::Type(lhs), ValueObj::Type(rhs)) => Ok(self.eval_or_type(lhs, rhs)),
                 _ => Err(EvalErrors::from(EvalError::unreachable(
                     self.cfg.input.clone(),
@@ -737,6 +1175,7 @@ impl Context {
             And | BitAnd => match (lhs, rhs) {
                 (ValueObj::Bool(l), ValueObj::Bool(r)) => Ok(ValueObj::Bool(l && r)),
                 (ValueObj::Int(l), ValueObj::Int(r)) => Ok(ValueObj::Int(l & r)),
+                (ValueObj::Set(l), ValueObj::Set(r)) => Ok(ValueObj::Set(l.intersection(&r))),
                 (ValueObj::Type(lhs), ValueObj::Type(rhs)) => Ok(self.eval_and_type(lhs, rhs)),
                 _ => Err(EvalErrors::from(EvalError::unreachable(
                     self.cfg.input.clone(),
@@ -747,6 +1186,9 @@ impl Context {
             BitXor => match (lhs, rhs) {
                 (ValueObj::Bool(l), ValueObj::Bool(r)) => Ok(ValueObj::Bool(l ^ r)),
                 (ValueObj::Int(l), ValueObj::Int(r)) => Ok(ValueObj::Int(l ^ r)),
+                (ValueObj::Set(l), ValueObj::Set(r)) => {
+                    Ok(ValueObj::Set(l.difference(&r).concat(r.difference(&l))))
+                }
                 _ => Err(EvalErrors::from(EvalError::unreachable(
                     self.cfg.input.clone(),
                     fn_name!(),
@@ -859,6 +1301,38 @@ impl Context {
             (TyParam::Array(l), TyParam::Array(r)) if op == OpKind::Add => {
                 Ok(TyParam::Array([l, r].concat()))
             }
+            // `{x = Int} + {x = Nat; y = Str}` => `{x = Nat; y = Str}`
+            (TyParam::Record(l), TyParam::Record(r)) if op == OpKind::Add => {
+                Ok(TyParam::Record(l.concat(r)))
+            }
+            // `{x = Int; y = Str} - {"y"}` => `{x = Int}`
+            (TyParam::Record(l), TyParam::Set(names)) if op == OpKind::Sub => {
+                let rec = l
+                    .into_iter()
+                    .filter(|(field, _)| !names.contains(&TyParam::value(field.symbol.clone())))
+                    .collect();
+                Ok(TyParam::Record(rec))
+            }
+            // `{1, 2} or {2, 3}` => `{1, 2, 3}`
+            (TyParam::Set(l), TyParam::Set(r)) if op.is_set_operation() => {
+                let set = match op {
+                    OpKind::Or | OpKind::BitOr => l.union(&r),
+                    OpKind::And | OpKind::BitAnd => l.intersection(&r),
+                    OpKind::Sub => l.difference(&r),
+                    _ => l.difference(&r).concat(r.difference(&l)),
+                };
+                Ok(TyParam::Set(set))
+            }
+            // the membership is decidable only if the elements are values (`?T in {1}` is not)
+            (elem, TyParam::Set(set)) if matches!(op, OpKind::In | OpKind::NotIn) => {
+                let contains = set.contains(&elem);
+                let is_value = |tp: &TyParam| matches!(tp, TyParam::Value(_));
+                if contains || (is_value(&elem) && set.iter().all(is_value)) {
+                    Ok(TyParam::value(contains == (op == OpKind::In)))
+                } else {
+                    Ok(TyParam::bin(op, elem, TyParam::Set(set)))
+                }
+            }
             (TyParam::FreeVar(fv), r) if fv.is_linked() => {
                 self.eval_bin_tp(op, fv.crack().clone(), r)
             }
@@ -890,18 +1364,41 @@ impl Context {
         }
     }
 
-    fn eval_unary_val(&self, op: OpKind, val: ValueObj) -> EvalResult<ValueObj> {
+    pub(crate) fn eval_unary_val(&self, op: OpKind, val: ValueObj) -> EvalResult<ValueObj> {
         match op {
-            Pos => Err(EvalErrors::from(EvalError::unreachable(
-                self.cfg.input.clone(),
-                fn_name!(),
-                line!(),
-            ))),
-            Neg => Err(EvalErrors::from(EvalError::unreachable(
-                self.cfg.input.clone(),
-                fn_name!(),
-                line!(),
-            ))),
+            Pos => match val {
+                ValueObj::Nat(_) | ValueObj::Int(_) | ValueObj::Ratio(_) | ValueObj::Float(_) => {
+                    Ok(val)
+                }
+                #[cfg(feature = "bigint")]
+                ValueObj::BigInt(_) => Ok(val),
+                _ => Err(EvalErrors::from(EvalError::unreachable(
+                    self.cfg.input.clone(),
+                    fn_name!(),
+                    line!(),
+                ))),
+            },
+            Neg => match val {
+                ValueObj::Nat(n) if n <= i32::MAX as u64 => Ok(ValueObj::Int(-(n as i32))),
+                ValueObj::Int(i) if i != i32::MIN => Ok(ValueObj::Int(-i)),
+                ValueObj::Ratio(_) | ValueObj::Float(_) => Ok(-val),
+                // `-i == 0 - i`, which may be a `BigInt`
+                #[cfg(feature = "bigint")]
+                ValueObj::Nat(_) | ValueObj::Int(_) | ValueObj::BigInt(_) => {
+                    ValueObj::Int(0).try_sub(val).ok_or_else(|| {
+                        EvalErrors::from(EvalError::unreachable(
+                            self.cfg.input.clone(),
+                            fn_name!(),
+                            line!(),
+                        ))
+                    })
+                }
+                _ => Err(EvalErrors::from(EvalError::unreachable(
+                    self.cfg.input.clone(),
+                    fn_name!(),
+                    line!(),
+                ))),
+            },
             Invert => Err(EvalErrors::from(EvalError::unreachable(
                 self.cfg.input.clone(),
                 fn_name!(),
@@ -937,8 +1434,8 @@ impl Context {
             ValueObj::Bool(b) => Ok(ValueObj::Nat(b as u64 + 1)),
             ValueObj::Nat(n) => Ok(ValueObj::Nat(n + 1)),
             ValueObj::Int(n) => Ok(ValueObj::Int(n + 1)),
-            // TODO:
-            ValueObj::Float(n) => Ok(ValueObj::Float(n + f64::EPSILON)),
+            ValueObj::Float(n) => Ok(ValueObj::Float(next_after(n, f64::INFINITY))),
+            ValueObj::Ratio(r) => Ok(ValueObj::Float(ratio_next_after(r, f64::INFINITY))),
             ValueObj::Inf | ValueObj::NegInf => Ok(val),
             _ => Err(EvalErrors::from(EvalError::unreachable(
                 self.cfg.input.clone(),
@@ -953,8 +1450,8 @@ impl Context {
             ValueObj::Bool(_) => Ok(ValueObj::Nat(0)),
             ValueObj::Nat(n) => Ok(ValueObj::Nat(n.saturating_sub(1))),
             ValueObj::Int(n) => Ok(ValueObj::Int(n - 1)),
-            // TODO:
-            ValueObj::Float(n) => Ok(ValueObj::Float(n - f64::EPSILON)),
+            ValueObj::Float(n) => Ok(ValueObj::Float(next_after(n, f64::NEG_INFINITY))),
+            ValueObj::Ratio(r) => Ok(ValueObj::Float(ratio_next_after(r, f64::NEG_INFINITY))),
             ValueObj::Inf | ValueObj::NegInf => Ok(val),
             _ => Err(EvalErrors::from(EvalError::unreachable(
                 self.cfg.input.clone(),
@@ -1040,6 +1537,32 @@ impl Context {
         }
     }
 
+    /// Enters a nested type evaluation.
+    /// If the nesting depth exceeds `cfg.max_type_depth`, returns a "type too complex" error instead of overflowing the stack.
+    fn enter_type_eval(
+        &self,
+        t_loc: &impl Locational,
+        typ: impl FnOnce() -> Type,
+    ) -> EvalResult<EvalDepthGuard> {
+        let depth = EVAL_DEPTH.with(|depth| {
+            depth.set(depth.get() + 1);
+            depth.get()
+        });
+        let guard = EvalDepthGuard;
+        if depth > self.cfg.max_type_depth {
+            Err(EvalErrors::from(EvalError::type_too_complex_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                t_loc.loc(),
+                self.caused_by(),
+                &typ(),
+                self.cfg.max_type_depth,
+            )))
+        } else {
+            Ok(guard)
+        }
+    }
+
     /// Evaluate `substituted`.
     /// If the evaluation fails, return a harmless type (filled with `Failure`) and errors
     pub(crate) fn eval_t_params(
@@ -1048,6 +1571,9 @@ impl Context {
         level: usize,
         t_loc: &impl Locational,
     ) -> Result<Type, (Type, EvalErrors)> {
+        let _guard = self
+            .enter_type_eval(t_loc, || substituted.clone())
+            .map_err(|errs| (Failure, errs))?;
         match substituted {
             Type::FreeVar(fv) if fv.is_linked() => {
                 self.eval_t_params(fv.crack().clone(), level, t_loc)
@@ -1204,6 +1730,7 @@ impl Context {
         if let Never | Failure = lhs {
             return Ok(lhs);
         }
+        let _guard = self.enter_type_eval(t_loc, || proj(lhs.clone(), rhs.clone()))?;
         // Currently Erg does not allow projection-types to be evaluated with type variables included.
         // All type variables will be dereferenced or fail.
         let (sub, opt_sup) = match lhs.clone() {
@@ -1222,6 +1749,17 @@ impl Context {
         if sub == Type::Never {
             return Ok(proj(lhs, rhs));
         }
+        // e.g. `?U(:> ?T.Output).Output`: evaluate the inner projection first
+        let sub = match sub {
+            Type::Proj {
+                lhs: inner,
+                rhs: inner_rhs,
+            } => match self.eval_proj(*inner.clone(), inner_rhs.clone(), level, t_loc) {
+                Ok(evaled) if !matches!(evaled, Type::Proj { .. }) => evaled,
+                _ => return Ok(proj(lhs, rhs)),
+            },
+            sub => sub,
+        };
         // in Methods
         if let Some(ctx) = self.get_same_name_context(&sub.qual_name()) {
             if let Some(t) =
@@ -1514,11 +2052,17 @@ impl Context {
     fn convert_type_to_array(&self, ty: Type) -> Result<Vec<ValueObj>, Type> {
         match ty {
             Type::Poly { name, params } if &name[..] == "Array" || &name[..] == "Array!" => {
-                let Ok(t) = self.convert_tp_into_type(params[0].clone()) else {
+                // the length may be unknown (e.g. `Array(Int, N)`) or ill-formed
+                let (Some(t), Some(TyParam::Value(ValueObj::Nat(len)))) =
+                    (params.first(), params.get(1))
+                else {
                     return Err(poly(name, params));
                 };
-                let TyParam::Value(ValueObj::Nat(len)) = params[1] else { unreachable!() };
-                Ok(vec![ValueObj::builtin_type(t); len as usize])
+                let len = *len as usize;
+                let Ok(t) = self.convert_tp_into_type(t.clone()) else {
+                    return Err(poly(name, params));
+                };
+                Ok(vec![ValueObj::builtin_type(t); len])
             }
             _ => Err(ty),
         }
@@ -1565,45 +2109,24 @@ impl Context {
             if let ValueObj::Type(quant_projected_t) = obj {
                 let projected_t = quant_projected_t.into_typ();
                 let (quant_sub, _) = self.get_type(&sub.qual_name()).unwrap();
-                if let Some(sup) = opt_sup {
-                    if let Some(quant_sup) = methods.impl_of() {
-                        // T -> Int, M -> 2
-                        self.substitute_typarams(&quant_sup, sup)
-                            .map_err(|errs| {
-                                Self::undo_substitute_typarams(&quant_sup);
-                                errs
-                            })
-                            .ok()?;
+                // Int -> T, 2 -> M, 4 -> N (the substitutions are undone after the evaluation)
+                return probe_generalized(|| {
+                    if let Some(sup) = opt_sup {
+                        if let Some(quant_sup) = methods.impl_of() {
+                            // T -> Int, M -> 2
+                            self.substitute_typarams(&quant_sup, sup).ok()?;
+                        }
                     }
-                }
-                // T -> Int, N -> 4
-                self.substitute_typarams(quant_sub, sub)
-                    .map_err(|errs| {
-                        Self::undo_substitute_typarams(quant_sub);
-                        errs
-                    })
-                    .ok()?;
-                // [T; M+N] -> [Int; 4+2] -> [Int; 6]
-                let res = self.eval_t_params(projected_t, level, t_loc).ok();
-                if let Some(t) = res {
+                    // T -> Int, N -> 4
+                    self.substitute_typarams(quant_sub, sub).ok()?;
+                    // [T; M+N] -> [Int; 4+2] -> [Int; 6]
+                    let t = self.eval_t_params(projected_t, level, t_loc).ok()?;
                     let mut tv_cache = TyVarCache::new(self.level, self);
-                    let t = self.detach(t, &mut tv_cache);
-                    // Int -> T, 2 -> M, 4 -> N
-                    Self::undo_substitute_typarams(quant_sub);
-                    if let Some(quant_sup) = methods.impl_of() {
-                        Self::undo_substitute_typarams(&quant_sup);
-                    }
-                    return Some(t);
-                }
-                Self::undo_substitute_typarams(quant_sub);
-                if let Some(quant_sup) = methods.impl_of() {
-                    Self::undo_substitute_typarams(&quant_sup);
-                }
+                    Some(self.detach(t, &mut tv_cache))
+                });
             } else {
-                log!(err "{obj}");
-                if DEBUG_MODE {
-                    todo!()
-                }
+                // e.g. `Output` is defined as a non-type constant
+                log!(err "{obj} is not a type");
             }
         }
         None
@@ -1667,8 +2190,9 @@ impl Context {
     /// qt: Iterable(T), st: Array(Int, 3)
     /// qt: Array(T, N), st: Array!(Int, 3) # TODO
     /// ```
-    /// use `undo_substitute_typarams` after executing this method
+    /// The substitution must be undone by rolling back the unification trail (e.g. call this in `probe`)
     pub(crate) fn substitute_typarams(&self, qt: &Type, st: &Type) -> EvalResult<()> {
+        debug_assert!(is_recording(), "the substitution cannot be undone");
         let qtps = qt.typarams();
         let stps = st.typarams();
         if qt.qual_name() != st.qual_name() || qtps.len() != stps.len() {
@@ -1686,6 +2210,7 @@ impl Context {
     }
 
     pub(crate) fn overwrite_typarams(&self, qt: &Type, st: &Type) -> EvalResult<()> {
+        debug_assert!(is_recording(), "the substitution cannot be undone");
         let qtps = qt.typarams();
         let stps = st.typarams();
         if qt.qual_name() != st.qual_name() || qtps.len() != stps.len() {
@@ -1781,27 +2306,6 @@ impl Context {
         Ok(())
     }
 
-    pub(crate) fn undo_substitute_typarams(substituted_q: &Type) {
-        for tp in substituted_q.typarams().into_iter() {
-            match tp {
-                TyParam::FreeVar(fv) if fv.is_undoable_linked() => fv.undo(),
-                TyParam::Type(t) if t.is_free_var() => {
-                    let Ok(subst) = <&FreeTyVar>::try_from(t.as_ref()) else { unreachable!() };
-                    if subst.is_undoable_linked() {
-                        subst.undo();
-                    }
-                }
-                TyParam::Type(t) => {
-                    Self::undo_substitute_typarams(&t);
-                }
-                TyParam::Value(ValueObj::Type(t)) => {
-                    Self::undo_substitute_typarams(t.typ());
-                }
-                _ => {}
-            }
-        }
-    }
-
     pub(crate) fn eval_proj_call(
         &self,
         lhs: TyParam,
@@ -1810,6 +2314,9 @@ impl Context {
         level: usize,
         t_loc: &impl Locational,
     ) -> EvalResult<Type> {
+        let _guard = self.enter_type_eval(t_loc, || {
+            proj_call(lhs.clone(), attr_name.clone(), args.clone())
+        })?;
         let t = self.get_tp_t(&lhs)?;
         for ty_ctx in self.get_nominal_super_type_ctxs(&t).ok_or_else(|| {
             EvalError::type_not_found(
@@ -1845,10 +2352,15 @@ impl Context {
                     }
                     let args = ValueArgs::new(pos_args, dict! {});
                     let t = self.call(subr, args, t_loc.loc())?;
-                    let t = self
-                        .convert_value_into_type(t)
-                        .unwrap_or_else(|value| todo!("Type::try_from {value}"));
-                    return Ok(t);
+                    return self.convert_value_into_type(t).map_err(|value| {
+                        EvalErrors::from(EvalError::tp_to_type_error(
+                            self.cfg.input.clone(),
+                            line!() as usize,
+                            &TyParam::value(value),
+                            t_loc.loc(),
+                            self.caused_by(),
+                        ))
+                    });
                 } else {
                     return feature_error!(self, t_loc.loc(), "??");
                 }
@@ -1869,10 +2381,15 @@ impl Context {
                         }
                         let args = ValueArgs::new(pos_args, dict! {});
                         let t = self.call(subr, args, t_loc.loc())?;
-                        let t = self
-                            .convert_value_into_type(t)
-                            .unwrap_or_else(|value| todo!("Type::try_from {value}"));
-                        return Ok(t);
+                        return self.convert_value_into_type(t).map_err(|value| {
+                            EvalErrors::from(EvalError::tp_to_type_error(
+                                self.cfg.input.clone(),
+                                line!() as usize,
+                                &TyParam::value(value),
+                                t_loc.loc(),
+                                self.caused_by(),
+                            ))
+                        });
                     } else {
                         return feature_error!(self, t_loc.loc(), "??");
                     }
@@ -2158,3 +2675,444 @@ impl Context {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use erg_common::set;
+    use erg_common::set::Set;
+
+    use crate::context::Context;
+    use crate::ty::free::Constraint;
+    use crate::ty::typaram::{OpKind, TyParam};
+    use crate::ty::value::ValueObj;
+    use crate::ty::{Predicate, Type};
+
+    /// A constant expression of numbers and booleans.
+    #[derive(Debug, Clone)]
+    enum Expr {
+        Lit(ValueObj),
+        Bin(OpKind, Box<Expr>, Box<Expr>),
+    }
+
+    /// A value of Python. `Int` and `Nat` of Erg are both `Int`.
+    #[derive(Debug, Clone, Copy)]
+    enum PyValue {
+        Int(i128),
+        Float(f64),
+        Bool(bool),
+    }
+
+    impl PartialEq for PyValue {
+        fn eq(&self, other: &Self) -> bool {
+            match (self, other) {
+                (Self::Int(l), Self::Int(r)) => l == r,
+                // distinguishes `0.0` and `-0.0`
+                (Self::Float(l), Self::Float(r)) => {
+                    (l.is_nan() && r.is_nan()) || l.to_bits() == r.to_bits()
+                }
+                (Self::Bool(l), Self::Bool(r)) => l == r,
+                _ => false,
+            }
+        }
+    }
+
+    impl From<ValueObj> for PyValue {
+        fn from(value: ValueObj) -> Self {
+            match value {
+                ValueObj::Int(i) => Self::Int(i as i128),
+                ValueObj::Nat(n) => Self::Int(n as i128),
+                #[cfg(feature = "bigint")]
+                ValueObj::BigInt(i) => Self::Int(i128::try_from(&i).unwrap()),
+                ValueObj::Float(f) => Self::Float(f),
+                ValueObj::Bool(b) => Self::Bool(b),
+                other => panic!("unexpected value: {other}"),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    enum PyError {
+        /// `ZeroDivisionError` is raised in Python
+        ZeroDivision,
+        /// the result cannot be computed by the reference evaluator (e.g. too large integers)
+        Unknown,
+    }
+
+    #[derive(Debug)]
+    struct Expected {
+        value: PyValue,
+        /// all the intermediate integers fit in `Int` (`i32`), so the evaluation must succeed
+        must_succeed: bool,
+    }
+
+    const MAX_EXACT_FLOAT_INT: i128 = 1 << 53;
+
+    fn fits_in_int(i: i128) -> bool {
+        i32::try_from(i).is_ok()
+    }
+
+    fn floordiv(l: i128, r: i128) -> i128 {
+        // ⌊l / r⌋ == ⌊-l / -r⌋, and `div_euclid` is the floor division for a positive divisor
+        if r > 0 {
+            l.div_euclid(r)
+        } else {
+            (-l).div_euclid(-r)
+        }
+    }
+
+    fn eval_int(op: OpKind, l: i128, r: i128) -> Result<PyValue, PyError> {
+        let int = |i: Option<i128>| i.map(PyValue::Int).ok_or(PyError::Unknown);
+        match op {
+            OpKind::Add => int(l.checked_add(r)),
+            OpKind::Sub => int(l.checked_sub(r)),
+            OpKind::Mul => int(l.checked_mul(r)),
+            OpKind::Div | OpKind::FloorDiv | OpKind::Mod if r == 0 => Err(PyError::ZeroDivision),
+            OpKind::Div if l.abs() > MAX_EXACT_FLOAT_INT || r.abs() > MAX_EXACT_FLOAT_INT => {
+                Err(PyError::Unknown)
+            }
+            OpKind::Div => Ok(PyValue::Float(l as f64 / r as f64)),
+            OpKind::FloorDiv => Ok(PyValue::Int(floordiv(l, r))),
+            OpKind::Mod => Ok(PyValue::Int(l - r * floordiv(l, r))),
+            OpKind::Gt => Ok(PyValue::Bool(l > r)),
+            OpKind::Ge => Ok(PyValue::Bool(l >= r)),
+            OpKind::Lt => Ok(PyValue::Bool(l < r)),
+            OpKind::Le => Ok(PyValue::Bool(l <= r)),
+            OpKind::Eq => Ok(PyValue::Bool(l == r)),
+            OpKind::Ne => Ok(PyValue::Bool(l != r)),
+            _ => unreachable!("{op}"),
+        }
+    }
+
+    /// `float_rem` of CPython (Objects/floatobject.c). `%` of `f64` is `fmod`.
+    fn py_float_mod(l: f64, r: f64) -> f64 {
+        let rem = l % r;
+        if rem == 0.0 {
+            // the sign of the divisor
+            0.0f64.copysign(r)
+        } else if (r < 0.0) != (rem < 0.0) {
+            rem + r
+        } else {
+            rem
+        }
+    }
+
+    /// `_float_div_mod` of CPython (Objects/floatobject.c), which is used by `float.__floordiv__`.
+    fn py_float_floordiv(l: f64, r: f64) -> f64 {
+        let rem = l % r;
+        let mut div = (l - rem) / r;
+        // the remainder is adjusted to the sign of the divisor
+        if rem != 0.0 && (r < 0.0) != (rem < 0.0) {
+            div -= 1.0;
+        }
+        if div == 0.0 {
+            // the sign of the true quotient
+            0.0f64.copysign(l / r)
+        } else {
+            let floor = div.floor();
+            if div - floor > 0.5 {
+                floor + 1.0
+            } else {
+                floor
+            }
+        }
+    }
+
+    fn eval_float(op: OpKind, l: f64, r: f64) -> Result<PyValue, PyError> {
+        match op {
+            OpKind::Add => Ok(PyValue::Float(l + r)),
+            OpKind::Sub => Ok(PyValue::Float(l - r)),
+            OpKind::Mul => Ok(PyValue::Float(l * r)),
+            OpKind::Div | OpKind::FloorDiv | OpKind::Mod if r == 0.0 => Err(PyError::ZeroDivision),
+            OpKind::Div => Ok(PyValue::Float(l / r)),
+            OpKind::FloorDiv => Ok(PyValue::Float(py_float_floordiv(l, r))),
+            OpKind::Mod => Ok(PyValue::Float(py_float_mod(l, r))),
+            OpKind::Gt => Ok(PyValue::Bool(l > r)),
+            OpKind::Ge => Ok(PyValue::Bool(l >= r)),
+            OpKind::Lt => Ok(PyValue::Bool(l < r)),
+            OpKind::Le => Ok(PyValue::Bool(l <= r)),
+            OpKind::Eq => Ok(PyValue::Bool(l == r)),
+            OpKind::Ne => Ok(PyValue::Bool(l != r)),
+            _ => unreachable!("{op}"),
+        }
+    }
+
+    fn as_float(i: i128) -> Result<f64, PyError> {
+        // Python compares an integer and a float exactly
+        if i.abs() > MAX_EXACT_FLOAT_INT {
+            Err(PyError::Unknown)
+        } else {
+            Ok(i as f64)
+        }
+    }
+
+    /// Evaluates the expression as Python does.
+    fn reference(expr: &Expr) -> Result<Expected, PyError> {
+        match expr {
+            Expr::Lit(lit) => Ok(Expected {
+                value: PyValue::from(lit.clone()),
+                must_succeed: true,
+            }),
+            Expr::Bin(op, l, r) => {
+                let (l, r) = (reference(l)?, reference(r)?);
+                let value = match (op, l.value, r.value) {
+                    (OpKind::And, PyValue::Bool(l), PyValue::Bool(r)) => PyValue::Bool(l && r),
+                    (OpKind::Or, PyValue::Bool(l), PyValue::Bool(r)) => PyValue::Bool(l || r),
+                    (_, PyValue::Int(l), PyValue::Int(r)) => eval_int(*op, l, r)?,
+                    (_, PyValue::Int(l), PyValue::Float(r)) => eval_float(*op, as_float(l)?, r)?,
+                    (_, PyValue::Float(l), PyValue::Int(r)) => eval_float(*op, l, as_float(r)?)?,
+                    (_, PyValue::Float(l), PyValue::Float(r)) => eval_float(*op, l, r)?,
+                    _ => unreachable!(),
+                };
+                let must_succeed = l.must_succeed
+                    && r.must_succeed
+                    && !matches!(value, PyValue::Int(i) if !fits_in_int(i));
+                Ok(Expected {
+                    value,
+                    must_succeed,
+                })
+            }
+        }
+    }
+
+    fn eval(ctx: &Context, expr: &Expr) -> Option<ValueObj> {
+        match expr {
+            Expr::Lit(lit) => Some(lit.clone()),
+            Expr::Bin(op, l, r) => ctx.eval_bin(*op, eval(ctx, l)?, eval(ctx, r)?).ok(),
+        }
+    }
+
+    fn float_lit() -> impl Strategy<Value = f64> {
+        prop_oneof![
+            (-400i32..400).prop_map(|i| i as f64 / 4.0),
+            Just(0.1),
+            Just(-0.0),
+            Just(1e308),
+            Just(f64::INFINITY),
+            Just(f64::NEG_INFINITY),
+            Just(f64::NAN),
+        ]
+    }
+
+    fn num_expr() -> impl Strategy<Value = Expr> {
+        let leaf = prop_oneof![
+            // non-negative integers are `Nat`
+            (-100i32..100).prop_map(|i| Expr::Lit(ValueObj::from(i))),
+            prop_oneof![Just(i32::MIN), Just(i32::MAX)].prop_map(|i| Expr::Lit(ValueObj::Int(i))),
+            Just(Expr::Lit(ValueObj::Nat(u32::MAX as u64))),
+            float_lit().prop_map(|f| Expr::Lit(ValueObj::Float(f))),
+        ];
+        let op = prop_oneof![
+            Just(OpKind::Add),
+            Just(OpKind::Sub),
+            Just(OpKind::Mul),
+            Just(OpKind::Div),
+            Just(OpKind::FloorDiv),
+            Just(OpKind::Mod),
+        ];
+        leaf.prop_recursive(4, 32, 2, move |inner| {
+            (op.clone(), inner.clone(), inner)
+                .prop_map(|(op, l, r)| Expr::Bin(op, Box::new(l), Box::new(r)))
+        })
+    }
+
+    fn bool_expr() -> impl Strategy<Value = Expr> {
+        let cmp = prop_oneof![
+            Just(OpKind::Gt),
+            Just(OpKind::Ge),
+            Just(OpKind::Lt),
+            Just(OpKind::Le),
+            Just(OpKind::Eq),
+            Just(OpKind::Ne),
+        ];
+        let leaf = prop_oneof![
+            any::<bool>().prop_map(|b| Expr::Lit(ValueObj::Bool(b))),
+            (cmp, num_expr(), num_expr()).prop_map(|(op, l, r)| Expr::Bin(
+                op,
+                Box::new(l),
+                Box::new(r)
+            )),
+        ];
+        let op = prop_oneof![Just(OpKind::And), Just(OpKind::Or)];
+        leaf.prop_recursive(3, 16, 2, move |inner| {
+            (op.clone(), inner.clone(), inner)
+                .prop_map(|(op, l, r)| Expr::Bin(op, Box::new(l), Box::new(r)))
+        })
+    }
+
+    fn check(expr: &Expr) -> Result<(), TestCaseError> {
+        let ctx = Context::default_with_name("<module>");
+        let actual = eval(&ctx, expr);
+        match reference(expr) {
+            Ok(expected) => {
+                if let Some(actual) = actual {
+                    prop_assert_eq!(PyValue::from(actual), expected.value);
+                } else {
+                    prop_assert!(!expected.must_succeed, "{expr:?} should be {expected:?}");
+                }
+            }
+            Err(PyError::ZeroDivision) => prop_assert!(actual.is_none(), "{actual:?}"),
+            Err(PyError::Unknown) => {}
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn arithmetic_follows_python(expr in num_expr()) {
+            check(&expr)?;
+        }
+
+        #[test]
+        fn boolean_follows_python(expr in bool_expr()) {
+            check(&expr)?;
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint_arithmetic() {
+        let ctx = Context::default_with_name("<module>");
+        let int = |s: &'static str| ValueObj::from_str(Type::Int, s.into()).unwrap();
+        let two_32 = ctx
+            .eval_bin(OpKind::Mul, ValueObj::Nat(65536), ValueObj::Nat(65536))
+            .unwrap();
+        let two_64 = ctx.eval_bin(OpKind::Mul, two_32.clone(), two_32).unwrap();
+        assert!(two_64.is_bigint());
+        assert_eq!(two_64, int("18446744073709551616"));
+        let neg_two_64 = ctx.eval_unary_val(OpKind::Neg, two_64.clone()).unwrap();
+        assert_eq!(
+            ctx.eval_bin(OpKind::FloorDiv, neg_two_64, ValueObj::Nat(3))
+                .ok(),
+            Some(int("-6148914691236517206"))
+        );
+        assert_eq!(
+            ctx.eval_bin(OpKind::Mod, two_64.clone(), ValueObj::Int(-7))
+                .ok(),
+            Some(ValueObj::Int(-5))
+        );
+        // normalized to `Nat`
+        assert_eq!(
+            ctx.eval_bin(OpKind::Sub, two_64.clone(), ValueObj::Nat(1))
+                .ok(),
+            Some(ValueObj::Nat(u64::MAX))
+        );
+        assert_eq!(
+            ctx.eval_bin(OpKind::Gt, two_64, ValueObj::Float(1e19)).ok(),
+            Some(ValueObj::Bool(true))
+        );
+    }
+
+    /// The expected values are computed by Python (`l // r` and `l % r`).
+    #[test]
+    fn float_edge_cases() {
+        let ctx = Context::default_with_name("<module>");
+        let cases = [
+            (1.0, 0.1, 9.0, 0.09999999999999995),
+            (-1.0, 0.1, -10.0, 5.551115123125783e-17),
+            (-0.0, 1.0, -0.0, 0.0),
+            (0.0, -1.0, -0.0, -0.0),
+            (5.5, -2.0, -3.0, -0.5),
+            (-5.5, 2.0, -3.0, 0.5),
+            (f64::INFINITY, 1.0, f64::NAN, f64::NAN),
+            (1.0, f64::INFINITY, 0.0, 1.0),
+            (-1.0, f64::INFINITY, -1.0, f64::INFINITY),
+            (1.0, f64::NEG_INFINITY, -1.0, f64::NEG_INFINITY),
+            (1e308, 1e-308, f64::INFINITY, 3.498445546245627e-309),
+        ];
+        for (l, r, div, rem) in cases {
+            let floordiv = ctx
+                .eval_bin(OpKind::FloorDiv, ValueObj::Float(l), ValueObj::Float(r))
+                .unwrap();
+            assert_eq!(PyValue::from(floordiv), PyValue::Float(div), "{l} // {r}");
+            let modulo = ctx
+                .eval_bin(OpKind::Mod, ValueObj::Float(l), ValueObj::Float(r))
+                .unwrap();
+            assert_eq!(PyValue::from(modulo), PyValue::Float(rem), "{l} % {r}");
+            // the reference evaluator of `arithmetic_follows_python`
+            assert_eq!(PyValue::Float(py_float_floordiv(l, r)), PyValue::Float(div));
+            assert_eq!(PyValue::Float(py_float_mod(l, r)), PyValue::Float(rem));
+        }
+    }
+
+    #[test]
+    fn succ_and_pred_of_floats() {
+        let ctx = Context::default_with_name("<module>");
+        let eval = |tp: TyParam| match ctx.eval_tp(tp).unwrap() {
+            TyParam::Value(ValueObj::Float(f)) => f,
+            other => panic!("unexpected value: {other}"),
+        };
+        // (x, succ(x), pred(x)) by `math.nextafter` of Python
+        let cases = [
+            (1.0, 1.0000000000000002, 0.9999999999999999),
+            (1e16, 1.0000000000000002e16, 9999999999999998.0),
+            (1e-300, 1.0000000000000002e-300, 9.999999999999999e-301),
+            (0.0, 5e-324, -5e-324),
+            (-1.0, -0.9999999999999999, -1.0000000000000002),
+            (f64::MAX, f64::INFINITY, 1.7976931348623155e308),
+        ];
+        for (x, succ, pred) in cases {
+            assert_eq!(eval(TyParam::value(x).succ()), succ, "succ({x})");
+            assert_eq!(eval(TyParam::value(x).pred()), pred, "pred({x})");
+        }
+        // the exact values of `Ratio`s (`0.1f64 > 1/10`, `0.3f64 < 3/10`)
+        let cases = [
+            ("0.1", 0.1, 0.09999999999999999),
+            ("0.3", 0.30000000000000004, 0.3),
+            ("0.5", 0.5000000000000001, 0.49999999999999994),
+        ];
+        for (lit, succ, pred) in cases {
+            let x = TyParam::value(ValueObj::from_str(Type::Ratio, lit.into()).unwrap());
+            assert_eq!(eval(x.clone().succ()), succ, "succ({lit})");
+            assert_eq!(eval(x.pred()), pred, "pred({lit})");
+        }
+    }
+
+    #[test]
+    fn open_float_interval() {
+        let ctx = Context::default_with_name("<module>");
+        // `1e16<..<1e17`
+        let interval = Predicate::and(
+            Predicate::ge("X".into(), TyParam::value(1e16).succ()),
+            Predicate::le("X".into(), TyParam::value(1e17).pred()),
+        );
+        let closed = Predicate::and(
+            Predicate::ge("X".into(), TyParam::value(1.0000000000000002e16)),
+            Predicate::le("X".into(), TyParam::value(9.999999999999998e16)),
+        );
+        assert_eq!(ctx.eval_pred(interval).unwrap(), closed);
+    }
+
+    #[test]
+    fn set_operations() {
+        let ctx = Context::default_with_name("<module>");
+        let set =
+            |elems: &[i32]| -> Set<TyParam> { elems.iter().map(|&i| TyParam::value(i)).collect() };
+        let cases = [
+            (OpKind::Or, set(&[1, 2, 3])),
+            (OpKind::And, set(&[2])),
+            (OpKind::Sub, set(&[1])),
+            (OpKind::BitXor, set(&[1, 3])),
+        ];
+        for (op, expected) in cases {
+            let (l, r) = (TyParam::Set(set(&[1, 2])), TyParam::Set(set(&[2, 3])));
+            let res = ctx.eval_bin_tp(op, l, r).unwrap();
+            assert_eq!(res, TyParam::Set(expected), "{{1, 2}} {op} {{2, 3}}");
+        }
+        let one = TyParam::value(1);
+        let var = TyParam::mono_q("T", Constraint::new_type_of(Type::Int));
+        let res = ctx.eval_bin_tp(OpKind::In, one.clone(), TyParam::Set(set(&[1, 2])));
+        assert_eq!(res.unwrap(), TyParam::value(true));
+        let res = ctx.eval_bin_tp(OpKind::NotIn, one.clone(), TyParam::Set(set(&[2])));
+        assert_eq!(res.unwrap(), TyParam::value(true));
+        // `T` may be `1`
+        let res = ctx.eval_bin_tp(OpKind::In, one, TyParam::Set(set![var.clone()]));
+        let expected = TyParam::bin(OpKind::In, TyParam::value(1), TyParam::Set(set![var]));
+        assert_eq!(res.unwrap(), expected);
+        // compared by `==` (`1 == 1.0`)
+        let floats = ValueObj::Array(vec![ValueObj::Float(1.0)].into());
+        let res = ctx.eval_bin(OpKind::In, ValueObj::Int(1), floats);
+        assert_eq!(res.unwrap(), ValueObj::Bool(true));
+    }
+}
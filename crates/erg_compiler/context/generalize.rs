@@ -9,7 +9,7 @@ use erg_common::{dict, fn_name, set};
 use erg_common::{fmt_vec, log};
 
 use crate::ty::constructors::*;
-use crate::ty::free::{CanbeFree, Constraint, Free, HasLevel};
+use crate::ty::free::{probe, CanbeFree, Constraint, Free, HasLevel};
 use crate::ty::typaram::{TyParam, TyParamLambda};
 use crate::ty::value::ValueObj;
 use crate::ty::{HasType, Predicate, SubrType, Type};
@@ -269,7 +269,10 @@ impl Generalizer {
         }
     }
 
-    fn generalize_constraint<T: CanbeFree + Send + Clone>(&mut self, fv: &Free<T>) -> Constraint {
+    fn generalize_constraint<T: CanbeFree + Send + Clone + 'static>(
+        &mut self,
+        fv: &Free<T>,
+    ) -> Constraint {
         if let Some((sub, sup)) = fv.get_subsup() {
             let sub = self.generalize_t(sub, true);
             let sup = self.generalize_t(sup, true);
@@ -912,22 +915,14 @@ impl Context {
     }
 
     fn poly_class_trait_impl_exists(&self, class: &Type, trait_: &Type) -> bool {
-        let mut super_exists = false;
-        for imp in self.get_trait_impls(trait_).into_iter() {
-            self.substitute_typarams(&imp.sub_type, class).unwrap_or(());
-            self.substitute_typarams(&imp.sup_trait, trait_)
-                .unwrap_or(());
-            if self.supertype_of(&imp.sub_type, class) && self.supertype_of(&imp.sup_trait, trait_)
-            {
-                super_exists = true;
-                Self::undo_substitute_typarams(&imp.sub_type);
-                Self::undo_substitute_typarams(&imp.sup_trait);
-                break;
-            }
-            Self::undo_substitute_typarams(&imp.sub_type);
-            Self::undo_substitute_typarams(&imp.sup_trait);
-        }
-        super_exists
+        self.get_trait_impls(trait_).into_iter().any(|imp| {
+            probe(|| {
+                self.substitute_typarams(&imp.sub_type, class).unwrap_or(());
+                self.substitute_typarams(&imp.sup_trait, trait_)
+                    .unwrap_or(());
+                self.supertype_of(&imp.sub_type, class) && self.supertype_of(&imp.sup_trait, trait_)
+            })
+        })
     }
 
     fn check_trait_impl(
@@ -1232,6 +1227,12 @@ impl Context {
                 Ok(())
             }
             hir::Expr::TypeAsc(tasc) => self.resolve_expr_t(&mut tasc.expr, qnames),
+            hir::Expr::Coerce(coerce) => {
+                let t = mem::take(&mut coerce.t);
+                let mut dereferencer = Dereferencer::simple(self, qnames, coerce);
+                coerce.t = dereferencer.deref_tyvar(t)?;
+                self.resolve_expr_t(&mut coerce.expr, qnames)
+            }
             hir::Expr::Code(chunks) | hir::Expr::Compound(chunks) => {
                 for chunk in chunks.iter_mut() {
                     self.resolve_expr_t(chunk, qnames)?;
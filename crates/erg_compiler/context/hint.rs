@@ -1,11 +1,17 @@
+use erg_common::consts::PYTHON_MODE;
+use erg_common::erg_util::BUILTIN_ERG_MODS;
+use erg_common::levenshtein::get_similar_name;
+use erg_common::python_util::BUILTIN_PYTHON_MODS;
+use erg_common::set::Set;
 use erg_common::style::{Attribute, Color, StyledStrings, THEME};
 use erg_common::{option_enum_unwrap, switch_lang};
 
 use crate::ty::typaram::TyParam;
 use crate::ty::value::ValueObj;
-use crate::ty::{HasType, Predicate, SubrKind, Type};
+use crate::ty::{HasType, ParamTy, Predicate, SubrKind, SubrType, Type};
 
 use crate::context::Context;
+use crate::error::ordinal_num;
 
 const HINT: Color = THEME.colors.hint;
 const ERR: Color = THEME.colors.error;
@@ -83,6 +89,22 @@ impl Context {
             expected.clone()
         };
         let mut hint = StyledStrings::default();
+        // keyword enums, e.g. `mode: {"r", "w", "a"}`
+        if let Some(literals) = expected.str_literals() {
+            let literals = literals
+                .iter()
+                .map(|lit| format!("\"{lit}\""))
+                .collect::<Vec<_>>()
+                .join(", ");
+            switch_lang!(
+                "japanese" => hint.push_str("許可されている値: "),
+                "simplified_chinese" => hint.push_str("允许的值: "),
+                "traditional_chinese" => hint.push_str("允許的值: "),
+                "english" => hint.push_str("Allowed values: "),
+            );
+            hint.push_str_with_color_and_attr(&literals, HINT, ATTR);
+            return Some(hint.to_string());
+        }
         match (&expected, &found) {
             (Type::Subr(expt), Type::Subr(fnd)) => {
                 if let (SubrKind::Func, SubrKind::Proc) = (expt.kind, fnd.kind) {
@@ -188,6 +210,113 @@ impl Context {
         }
     }
 
+    /// The reason why the overload `candidate` does not accept `input` (the types of the passed arguments).
+    /// `input` contains `self` if `candidate` is a method.
+    pub(crate) fn get_overload_mismatch_reason(
+        &self,
+        candidate: &Type,
+        input: &SubrType,
+    ) -> String {
+        let no_match = || {
+            switch_lang! {
+                "japanese" => "引数の型が一致しません".to_string(),
+                "simplified_chinese" => "参数类型不匹配".to_string(),
+                "traditional_chinese" => "參數類型不匹配".to_string(),
+                "english" => "the argument types do not match".to_string(),
+            }
+        };
+        let Type::Subr(subr) = candidate else {
+            return no_match();
+        };
+        let self_offset = usize::from(candidate.is_method());
+        let params = subr
+            .non_default_params
+            .iter()
+            .chain(subr.default_params.iter())
+            .collect::<Vec<_>>();
+        let mismatch = |nth: usize, param: &ParamTy, found: &Type| {
+            let name = param
+                .name()
+                .map_or(String::new(), |name| format!(" ({name})"));
+            let expected = param.typ();
+            switch_lang! {
+                "japanese" => format!("{nth}番目の引数{name}: {expected}が期待されましたが、{found}が渡されました"),
+                "simplified_chinese" => format!("第{nth}个参数{name}: 预期{expected}，但找到{found}"),
+                "traditional_chinese" => format!("第{nth}個參數{name}: 預期{expected}，但找到{found}"),
+                "english" => format!("the {} argument{name}: expected {expected}, but found {found}", ordinal_num(nth)),
+            }
+        };
+        let mut passed = Set::new();
+        for (i, arg) in input.non_default_params.iter().enumerate() {
+            let Some(param) = params.get(i).copied().or(subr.var_params.as_deref()) else {
+                let expected = params.len() - self_offset;
+                let found = input.non_default_params.len() - self_offset;
+                return switch_lang! {
+                    "japanese" => format!("位置引数が多すぎます({expected}個まで受け取りますが、{found}個渡されました)"),
+                    "simplified_chinese" => format!("位置参数过多(最多{expected}个，但传递了{found}个)"),
+                    "traditional_chinese" => format!("位置參數過多(最多{expected}個，但傳遞了{found}個)"),
+                    "english" => format!("too many positional arguments (takes at most {expected}, but {found} were given)"),
+                };
+            };
+            if let Some(name) = param.name() {
+                passed.insert(name.clone());
+            }
+            if !self.subtype_of(arg.typ(), param.typ()) {
+                return mismatch(i + 1 - self_offset, param, arg.typ());
+            }
+        }
+        for kw in input.default_params.iter() {
+            let Some(name) = kw.name() else {
+                continue;
+            };
+            let Some((i, param)) = params
+                .iter()
+                .enumerate()
+                .find(|(_, pt)| pt.name() == Some(name))
+            else {
+                return switch_lang! {
+                    "japanese" => format!("予期しない名前付き引数{name}があります"),
+                    "simplified_chinese" => format!("意外的关键字参数{name}"),
+                    "traditional_chinese" => format!("意外的關鍵字參數{name}"),
+                    "english" => format!("unexpected keyword argument {name}"),
+                };
+            };
+            if !passed.insert(name.clone()) {
+                return switch_lang! {
+                    "japanese" => format!("引数{name}が複数回渡されています"),
+                    "simplified_chinese" => format!("参数{name}被传递了多次"),
+                    "traditional_chinese" => format!("參數{name}被傳遞了多次"),
+                    "english" => format!("argument {name} is passed multiple times"),
+                };
+            }
+            if !self.subtype_of(kw.typ(), param.typ()) {
+                return mismatch(i + 1 - self_offset, param, kw.typ());
+            }
+        }
+        let missing = subr
+            .non_default_params
+            .iter()
+            .enumerate()
+            .skip(input.non_default_params.len())
+            .filter(|(_, pt)| pt.name().is_none_or(|name| !passed.contains(name)))
+            .map(|(i, pt)| {
+                pt.name()
+                    .map_or(ordinal_num(i + 1 - self_offset), |name| name.to_string())
+            })
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let missing = missing.join(", ");
+            return switch_lang! {
+                "japanese" => format!("引数が足りません: {missing}"),
+                "simplified_chinese" => format!("缺少参数: {missing}"),
+                "traditional_chinese" => format!("缺少參數: {missing}"),
+                "english" => format!("missing argument(s): {missing}"),
+            };
+        }
+        // e.g. the return type or the type variables do not match
+        no_match()
+    }
+
     pub(crate) fn get_no_candidate_hint(&self, proj: &Type) -> Option<String> {
         match proj {
             Type::Proj { lhs, rhs: _ } => {
@@ -209,4 +338,68 @@ impl Context {
             _ => None,
         }
     }
+
+    /// Suggests the import line which makes `name` available, e.g.
+    /// `math = pyimport "math"` for `math`, `{sqrt;} = pyimport "math"` for `sqrt`.
+    /// Only the builtin modules and the modules already loaded in this session are searched.
+    pub(crate) fn get_import_suggestion(&self, name: &str) -> Option<String> {
+        if BUILTIN_ERG_MODS.contains(&name) {
+            return Some(Self::import_line(name, None, false));
+        } else if BUILTIN_PYTHON_MODS.contains(&name) {
+            return Some(Self::import_line(name, None, true));
+        }
+        // (module name, is Python module, public names)
+        let mut modules = vec![];
+        for (cache, py) in [
+            (self.opt_mod_cache()?, false),
+            (self.opt_py_mod_cache()?, true),
+        ] {
+            for (_, entry) in cache.ref_inner().iter() {
+                let ctx = &entry.module.context;
+                if ctx.name.starts_with(['<', '_']) || ctx.cfg.input.path() == self.cfg.input.path()
+                {
+                    continue;
+                }
+                if &ctx.name[..] == name {
+                    return Some(Self::import_line(name, None, py));
+                }
+                let publics = ctx
+                    .locals
+                    .iter()
+                    .chain(ctx.decls.iter())
+                    .filter(|(_, vi)| vi.vis.is_public())
+                    .map(|(vn, _)| vn.inspect().clone())
+                    .collect::<Vec<_>>();
+                modules.push((ctx.name.clone(), py, publics));
+            }
+        }
+        // the iteration order of the caches is not stable
+        modules.sort_by(|(l, _, _), (r, _, _)| l.len().cmp(&r.len()).then_with(|| l.cmp(r)));
+        if let Some((mod_name, py, _)) = modules
+            .iter()
+            .find(|(_, _, publics)| publics.iter().any(|public| &public[..] == name))
+        {
+            return Some(Self::import_line(mod_name, Some(name), *py));
+        }
+        modules.iter().find_map(|(mod_name, py, publics)| {
+            let similar = get_similar_name(publics.iter().map(|public| &public[..]), name)?;
+            Some(Self::import_line(mod_name, Some(similar), *py))
+        })
+    }
+
+    fn import_line(mod_name: &str, item: Option<&str>, py: bool) -> String {
+        match (item, PYTHON_MODE) {
+            (Some(item), true) => format!("from {mod_name} import {item}"),
+            (None, true) => format!("import {mod_name}"),
+            (Some(item), false) => {
+                let import = if py { "pyimport" } else { "import" };
+                format!("{{{item};}} = {import} \"{mod_name}\"")
+            }
+            (None, false) => {
+                let import = if py { "pyimport" } else { "import" };
+                let var = mod_name.rsplit(['.', '/']).next().unwrap_or(mod_name);
+                format!("{var} = {import} \"{mod_name}\"")
+            }
+        }
+    }
 }
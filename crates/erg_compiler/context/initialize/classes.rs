@@ -60,6 +60,12 @@ impl Context {
             8,
         );
         obj.register_py_builtin(FUNDAMENTAL_STR, fn0_met(Obj, Str), Some(FUNDAMENTAL_STR), 9);
+        obj.register_py_builtin(
+            FUNDAMENTAL_DOC,
+            or(Str, NoneType),
+            Some(FUNDAMENTAL_DOC),
+            10,
+        );
         let mut obj_in = Self::builtin_methods(Some(poly(IN, vec![ty_tp(Type)])), 2);
         obj_in.register_builtin_erg_impl(
             OP_IN,
@@ -790,6 +796,18 @@ impl Context {
             Some(FUNC_ENDSWITH),
             69,
         );
+        str_.register_builtin_erg_impl(
+            FUNC_REMOVEPREFIX,
+            fn1_met(Str, Str, Str),
+            Immutable,
+            Visibility::BUILTIN_PUBLIC,
+        );
+        str_.register_builtin_erg_impl(
+            FUNC_REMOVESUFFIX,
+            fn1_met(Str, Str, Str),
+            Immutable,
+            Visibility::BUILTIN_PUBLIC,
+        );
         str_.register_builtin_py_impl(
             FUNC_SPLIT,
             fn_met(
@@ -1278,6 +1296,83 @@ impl Context {
             None,
         )));
         array_.register_builtin_const(UNION_FUNC, Visibility::BUILTIN_PUBLIC, union);
+        // map: |T, U, N|(self: [T; N], func: T -> U) -> [U; N]
+        let array_map_t = fn_met(
+            array_t(T.clone(), N.clone()),
+            vec![kw(KW_FUNC, func1(T.clone(), U.clone()))],
+            None,
+            vec![],
+            array_t(U.clone(), N.clone()),
+        )
+        .quantify();
+        let map = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_MAP,
+            array_map,
+            array_map_t,
+            None,
+        )));
+        array_.register_py_builtin_const(FUNC_MAP, Visibility::BUILTIN_PUBLIC, map, Some(FUNC_MAP));
+        // filter: |T|(self: [T; _], func: T -> Bool) -> [T; _]
+        let array_filter_t = fn_met(
+            unknown_len_array_t(T.clone()),
+            vec![kw(KW_FUNC, func1(T.clone(), Bool))],
+            None,
+            vec![],
+            unknown_len_array_t(T.clone()),
+        )
+        .quantify();
+        let filter = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_FILTER,
+            array_filter,
+            array_filter_t,
+            None,
+        )));
+        array_.register_py_builtin_const(
+            FUNC_FILTER,
+            Visibility::BUILTIN_PUBLIC,
+            filter,
+            Some(FUNC_FILTER),
+        );
+        // fold: |T, U|(self: [T; _], init: U, func: (U, T) -> U) -> U
+        let array_fold_t = fn_met(
+            unknown_len_array_t(T.clone()),
+            vec![
+                kw(KW_INIT, U.clone()),
+                kw(KW_FUNC, func2(U.clone(), T.clone(), U.clone())),
+            ],
+            None,
+            vec![],
+            U.clone(),
+        )
+        .quantify();
+        let fold = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_FOLD,
+            array_fold,
+            array_fold_t,
+            None,
+        )));
+        array_.register_py_builtin_const(
+            FUNC_FOLD,
+            Visibility::BUILTIN_PUBLIC,
+            fold,
+            Some(FUNC_FOLD),
+        );
+        // sort: |T <: Ord, N|(self: [T; N]) -> [T; N]
+        // `list.sort` sorts in place, so `Array.sorted` is called at runtime
+        let O = mono_q(TY_O, subtypeof(mono(ORD)));
+        let array_sort_t = fn0_met(array_t(O.clone(), N.clone()), array_t(O, N.clone())).quantify();
+        let sort = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_SORT,
+            array_sort,
+            array_sort_t,
+            None,
+        )));
+        array_.register_py_builtin_const(
+            FUNC_SORT,
+            Visibility::BUILTIN_PUBLIC,
+            sort,
+            Some(FUNC_SORTED),
+        );
         let mut array_eq = Self::builtin_methods(Some(mono(EQ)), 2);
         array_eq.register_builtin_erg_impl(
             OP_EQ,
@@ -1531,7 +1626,71 @@ impl Context {
             ),
         )
         .quantify();
-        dict_.register_py_builtin(FUNC_GET, get_t, Some(FUNC_GET), 9);
+        let get = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_GET, dict_get, get_t, None,
+        )));
+        dict_.register_py_builtin_const(FUNC_GET, Visibility::BUILTIN_PUBLIC, get, Some(FUNC_GET));
+        // concat: |D, E|(self: Dict(D), other: Dict(E)) -> Dict(D + E)
+        let E = mono_q_tp(TY_E, instanceof(mono(GENERIC_DICT)));
+        let concat_t = fn1_kw_met(
+            dict_t.clone(),
+            kw(KW_OTHER, poly(DICT, vec![E.clone()])),
+            poly(DICT, vec![D.clone() + E]),
+        )
+        .quantify();
+        let concat = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_CONCAT,
+            dict_concat,
+            concat_t,
+            None,
+        )));
+        dict_.register_py_builtin_const(
+            FUNC_CONCAT,
+            Visibility::BUILTIN_PUBLIC,
+            concat,
+            Some(FUNC_CONCAT),
+        );
+        let K = type_q(TY_K);
+        let V = type_q(TY_V);
+        let kv_dict_t = Type::from(dict! { K.clone() => V.clone() });
+        // map: |K, V, K2, V2|(self: {K: V}, func: (K, V) -> (K2, V2)) -> {K2: V2}
+        let (K2, V2) = (type_q("K2"), type_q("V2"));
+        let dict_map_t = fn_met(
+            kv_dict_t.clone(),
+            vec![kw(
+                KW_FUNC,
+                func2(K.clone(), V.clone(), tuple_t(vec![K2.clone(), V2.clone()])),
+            )],
+            None,
+            vec![],
+            dict! { K2 => V2 }.into(),
+        )
+        .quantify();
+        let map = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_MAP, dict_map, dict_map_t, None,
+        )));
+        dict_.register_py_builtin_const(FUNC_MAP, Visibility::BUILTIN_PUBLIC, map, Some(FUNC_MAP));
+        // filter: |K, V|(self: {K: V}, func: (K, V) -> Bool) -> {K: V}
+        let dict_filter_t = fn_met(
+            kv_dict_t.clone(),
+            vec![kw(KW_FUNC, func2(K, V, Bool))],
+            None,
+            vec![],
+            kv_dict_t,
+        )
+        .quantify();
+        let filter = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_FILTER,
+            dict_filter,
+            dict_filter_t,
+            None,
+        )));
+        dict_.register_py_builtin_const(
+            FUNC_FILTER,
+            Visibility::BUILTIN_PUBLIC,
+            filter,
+            Some(FUNC_FILTER),
+        );
         let copy_t = fn0_met(dict_t.clone(), dict_t.clone()).quantify();
         dict_.register_py_builtin(COPY, copy_t, Some(COPY), 7);
         /* Bytes */
@@ -1568,6 +1727,17 @@ impl Context {
             Visibility::BUILTIN_PUBLIC,
         );
         bytes.register_trait(mono(BYTES), bytes_eq);
+        /* Ast */
+        let mut ast = Self::builtin_mono_class(AST, 2);
+        ast.register_superclass(Obj, &obj);
+        let mut ast_eq = Self::builtin_methods(Some(mono(EQ)), 2);
+        ast_eq.register_builtin_erg_impl(
+            OP_EQ,
+            fn1_met(mono(AST), mono(AST), Bool),
+            Const,
+            Visibility::BUILTIN_PUBLIC,
+        );
+        ast.register_trait(mono(AST), ast_eq);
         /* GenericTuple */
         let mut generic_tuple = Self::builtin_mono_class(GENERIC_TUPLE, 1);
         generic_tuple.register_superclass(Obj, &obj);
@@ -2416,6 +2586,8 @@ impl Context {
         self.register_builtin_type(g_dict_t, generic_dict, vis.clone(), Const, Some(DICT));
         self.register_builtin_type(dict_t, dict_, vis.clone(), Const, Some(DICT));
         self.register_builtin_type(mono(BYTES), bytes, vis.clone(), Const, Some(BYTES));
+        // `Ast`s are the strings at runtime
+        self.register_builtin_type(mono(AST), ast, vis.clone(), Const, Some(FUNC_STR));
         self.register_builtin_type(
             mono(GENERIC_TUPLE),
             generic_tuple,
@@ -1,15 +1,20 @@
+use std::cmp::Ordering;
 use std::mem;
+use std::path::PathBuf;
 
 use erg_common::dict::Dict;
-use erg_common::enum_unwrap;
+use erg_common::json::{self, Json};
+use erg_common::set::Set;
+use erg_common::{enum_unwrap, fmt_option};
 
 use crate::context::Context;
 use crate::feature_error;
 use crate::ty::constructors::{and, mono, poly, tuple_t, ty_tp};
 use crate::ty::value::{EvalValueError, EvalValueResult, GenTypeObj, TypeObj, ValueObj};
-use crate::ty::{TyParam, Type, ValueArgs};
+use crate::ty::{Field, TyParam, Type, ValueArgs};
 use erg_common::error::{ErrorCore, ErrorKind, Location, SubMessage};
 use erg_common::style::{Color, StyledStr, StyledString, THEME};
+use erg_common::traits::Stream;
 
 use super::{DICT_ITEMS, DICT_KEYS, DICT_VALUES};
 
@@ -23,17 +28,41 @@ const REQ_ERR: StyledStr = StyledStr::new("Requirement", Some(ERR), None);
 const REQ_WARN: StyledStr = StyledStr::new("Requirement", Some(WARN), None);
 const BASE_ERR: StyledStr = StyledStr::new("Base", Some(ERR), None);
 const BASE_WARN: StyledStr = StyledStr::new("Base", Some(WARN), None);
+const DERIVE_WARN: StyledStr = StyledStr::new("Derive", Some(WARN), None);
+const TYPE_ERR: StyledStr = StyledStr::new("Type", Some(ERR), None);
 
-/// Base := Type or NoneType, Impl := Type -> ClassType
+/// Base := Type or NoneType, Impl := Type, Derive := Type -> ClassType
 pub(crate) fn class_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
     let base = args.remove_left_or_key("Base");
     let impls = args.remove_left_or_key("Impl");
     let impls = impls.map(|v| v.as_type(ctx).unwrap());
+    let derive = match args.remove_left_or_key("Derive") {
+        Some(value) => {
+            let Some(derive) = value.as_type(ctx) else {
+                let derive = StyledString::new(format!("{value}"), Some(ERR), None);
+                return Err(ErrorCore::new(
+                    vec![SubMessage::only_loc(Location::Unknown)],
+                    format!("non-type object {derive} is passed to {DERIVE_WARN}"),
+                    line!() as usize,
+                    ErrorKind::TypeError,
+                    Location::Unknown,
+                )
+                .into());
+            };
+            Some(derive)
+        }
+        None => None,
+    };
     let t = mono(ctx.name.clone());
     match base {
         Some(value) => {
             if let Some(base) = value.as_type(ctx) {
-                Ok(ValueObj::gen_t(GenTypeObj::class(t, Some(base), impls)))
+                Ok(ValueObj::gen_t(GenTypeObj::class(
+                    t,
+                    Some(base),
+                    impls,
+                    derive,
+                )))
             } else {
                 let base = StyledString::new(format!("{value}"), Some(ERR), None);
                 Err(ErrorCore::new(
@@ -46,7 +75,7 @@ pub(crate) fn class_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<
                 .into())
             }
         }
-        None => Ok(ValueObj::gen_t(GenTypeObj::class(t, None, impls))),
+        None => Ok(ValueObj::gen_t(GenTypeObj::class(t, None, impls, derive))),
     }
 }
 
@@ -122,6 +151,50 @@ pub(crate) fn inheritable_func(mut args: ValueArgs, _ctx: &Context) -> EvalValue
     }
 }
 
+/// test: Bool, msg := Str -> NoneType
+///
+/// Fails at compile time if `test` is `False`.
+pub(crate) fn static_assert_func(mut args: ValueArgs, _ctx: &Context) -> EvalValueResult<ValueObj> {
+    let test = args.remove_left_or_key("test").ok_or_else(|| {
+        let test = StyledStr::new("test", Some(ERR), None);
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{test} is not passed"),
+            line!() as usize,
+            ErrorKind::KeyError,
+            Location::Unknown,
+        )
+    })?;
+    match test {
+        ValueObj::Bool(true) => Ok(ValueObj::None),
+        ValueObj::Bool(false) => {
+            let msg = match args.remove_left_or_key("msg") {
+                Some(ValueObj::Str(msg)) => format!("static assertion failed: {msg}"),
+                _ => "static assertion failed".to_string(),
+            };
+            Err(ErrorCore::new(
+                vec![SubMessage::only_loc(Location::Unknown)],
+                msg,
+                line!() as usize,
+                ErrorKind::AssertionError,
+                Location::Unknown,
+            )
+            .into())
+        }
+        other => {
+            let test = StyledString::new(format!("{other}"), Some(ERR), None);
+            Err(ErrorCore::new(
+                vec![SubMessage::only_loc(Location::Unknown)],
+                format!("non-Bool object {test} is passed to static_assert"),
+                line!() as usize,
+                ErrorKind::TypeError,
+                Location::Unknown,
+            )
+            .into())
+        }
+    }
+}
+
 /// Base: Type, Impl := Type -> TraitType
 pub(crate) fn trait_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
     let req = args.remove_left_or_key("Requirement").ok_or_else(|| {
@@ -239,11 +312,278 @@ pub(crate) fn structural_func(mut args: ValueArgs, ctx: &Context) -> EvalValueRe
     Ok(ValueObj::gen_t(GenTypeObj::structural(t, base)))
 }
 
+fn type_arg(args: &mut ValueArgs, name: &str, ctx: &Context) -> EvalValueResult<TypeObj> {
+    let type_ = args.remove_left_or_key("Type").ok_or_else(|| {
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{TYPE_ERR} is not passed"),
+            line!() as usize,
+            ErrorKind::KeyError,
+            Location::Unknown,
+        )
+    })?;
+    type_.as_type(ctx).ok_or_else(|| {
+        let type_ = StyledString::new(format!("{type_}"), Some(ERR), None);
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("non-type object {type_} is passed to {name}"),
+            line!() as usize,
+            ErrorKind::TypeError,
+            Location::Unknown,
+        )
+        .into()
+    })
+}
+
+/// Type -> Str
+///
+/// `type_name(Int) == "Int"`, `type_name(Array(Int, 2)) == "Array"`
+pub(crate) fn type_name_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let t = type_arg(&mut args, "type_name", ctx)?;
+    Ok(ValueObj::Str(t.typ().local_name()))
+}
+
+/// The fields of a record type, or the requirement (and the additional fields) of a class.
+fn type_fields(t: &TypeObj, ctx: &Context) -> Dict<Field, Type> {
+    match t {
+        TypeObj::Builtin {
+            t: Type::Record(fields),
+            ..
+        } => fields.clone(),
+        // e.g. the super class passed to `Inherit`
+        TypeObj::Builtin { t, .. } => match ctx.rec_get_const_obj(&t.local_name()) {
+            Some(ValueObj::Type(gen @ TypeObj::Generated(_))) if gen.typ() == t => {
+                type_fields(gen, ctx)
+            }
+            _ => Dict::new(),
+        },
+        TypeObj::Generated(gen) => {
+            let mut fields = gen
+                .base_or_sup()
+                .map_or(Dict::new(), |base| type_fields(base, ctx));
+            if let Some(additional) = gen.additional() {
+                fields.extend(type_fields(additional, ctx));
+            }
+            fields
+        }
+    }
+}
+
+/// Type -> {Str: Type}
+///
+/// `C = Class { .a = Int; .b = Str }; fields(C) == {"a": Int, "b": Str}`
+pub(crate) fn fields_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let t = type_arg(&mut args, "fields", ctx)?;
+    let fields = type_fields(&t, ctx)
+        .into_iter()
+        .map(|(field, t)| (ValueObj::Str(field.symbol), ValueObj::builtin_type(t)))
+        .collect();
+    Ok(ValueObj::Dict(fields))
+}
+
+/// Type -> Array(Str)
+///
+/// The names of the methods defined in the type (not inherited), in alphabetical order.
+pub(crate) fn methods_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let t = type_arg(&mut args, "methods", ctx)?;
+    let Some((_, type_ctx)) = ctx.get_nominal_type_ctx(t.typ()) else {
+        return Ok(ValueObj::Array(vec![].into()));
+    };
+    let mut methods = type_ctx
+        .methods_list
+        .iter()
+        .map(|(_, methods)| methods)
+        .chain([type_ctx])
+        .flat_map(|methods| methods.locals.iter().chain(methods.decls.iter()))
+        .filter(|(_, vi)| vi.t.is_subr())
+        .map(|(name, _)| name.inspect().clone())
+        .collect::<Set<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    methods.sort_by(|l, r| l[..].cmp(&r[..]));
+    Ok(ValueObj::Array(
+        methods.into_iter().map(ValueObj::Str).collect(),
+    ))
+}
+
+/// Type -> Array(Type)
+///
+/// The super classes (including the indirect ones) and the traits implemented by them, without duplicates.
+pub(crate) fn supers_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let t = type_arg(&mut args, "supers", ctx)?;
+    let mut supers = Vec::<ValueObj>::new();
+    let mut classes = vec![t.typ().clone()];
+    while let Some(class) = classes.pop() {
+        let Some((_, type_ctx)) = ctx.get_nominal_type_ctx(&class) else {
+            continue;
+        };
+        for sup in type_ctx.super_classes.iter() {
+            let sup_obj = ValueObj::builtin_type(sup.clone());
+            if !supers.contains(&sup_obj) {
+                supers.push(sup_obj);
+                classes.push(sup.clone());
+            }
+        }
+        for sup in type_ctx.super_traits.iter() {
+            let sup_obj = ValueObj::builtin_type(sup.clone());
+            if !supers.contains(&sup_obj) {
+                supers.push(sup_obj);
+            }
+        }
+    }
+    Ok(ValueObj::Array(supers.into()))
+}
+
+/// The objects whose member names are all valid field names become records, and the others become dicts.
+fn json_to_value(json: Json) -> ValueObj {
+    let is_field_name = |name: &str| {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+    };
+    match json {
+        Json::Null => ValueObj::None,
+        Json::Bool(b) => ValueObj::Bool(b),
+        Json::Int(i) => u64::try_from(i)
+            .map(ValueObj::Nat)
+            .or_else(|_| i32::try_from(i).map(ValueObj::Int))
+            .unwrap_or(ValueObj::Float(i as f64)),
+        Json::Float(f) => ValueObj::Float(f),
+        Json::Str(s) => ValueObj::Str(s.into()),
+        Json::Array(elems) => ValueObj::Array(elems.into_iter().map(json_to_value).collect()),
+        Json::Object(members) if members.iter().all(|(name, _)| is_field_name(name)) => {
+            let fields = members
+                .into_iter()
+                .map(|(name, value)| (Field::public(name.into()), json_to_value(value)))
+                .collect();
+            ValueObj::Record(fields)
+        }
+        Json::Object(members) => {
+            let kvs = members
+                .into_iter()
+                .map(|(name, value)| (ValueObj::Str(name.into()), json_to_value(value)))
+                .collect();
+            ValueObj::Dict(kvs)
+        }
+    }
+}
+
+/// Str -> Obj
+///
+/// `parse_json("{\"a\": [1, 2]}") == {.a = [1, 2]}`
+pub(crate) fn parse_json_func(mut args: ValueArgs, _ctx: &Context) -> EvalValueResult<ValueObj> {
+    let src = args.remove_left_or_key("src");
+    let Some(ValueObj::Str(src)) = src else {
+        let src = StyledString::new(fmt_option!(src), Some(ERR), None);
+        return Err(ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("expected a Str constant, but found {src}"),
+            line!() as usize,
+            ErrorKind::TypeError,
+            Location::Unknown,
+        )
+        .into());
+    };
+    json::parse(&src).map(json_to_value).map_err(|err| {
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("invalid JSON: {err}"),
+            line!() as usize,
+            ErrorKind::SyntaxError,
+            Location::Unknown,
+        )
+        .into()
+    })
+}
+
+/// Reads the file at `path` (relative to the directory of the current module) at compile time.
+/// The file is registered in the module graph as a dependency of the module,
+/// so that the module is checked again when the file is modified (e.g. `erg check --watch`).
+fn read_included_file(args: &mut ValueArgs, name: &str, ctx: &Context) -> EvalValueResult<Vec<u8>> {
+    let path = args.remove_left_or_key("path");
+    let Some(ValueObj::Str(path)) = path else {
+        let path = StyledString::new(fmt_option!(path), Some(ERR), None);
+        return Err(ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{name} expects a Str constant, but found {path}"),
+            line!() as usize,
+            ErrorKind::TypeError,
+            Location::Unknown,
+        )
+        .into());
+    };
+    let dir = ctx
+        .module_path()
+        .and_then(|path| path.parent())
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+    let path = dir.join(&path[..]);
+    let bytes = std::fs::read(&path).map_err(|err| {
+        let path = StyledString::new(format!("{}", path.display()), Some(ERR), None);
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("cannot read {path}: {err}"),
+            line!() as usize,
+            ErrorKind::IoError,
+            Location::Unknown,
+        )
+    })?;
+    if let (Some(shared), Some(referrer)) = (ctx.shared.as_ref(), ctx.module_path()) {
+        shared.graph.add_node_if_none(referrer);
+        shared.graph.add_node_if_none(&path);
+        // a file that is not a module cannot make a cycle
+        let _ = shared.graph.inc_ref(referrer, path);
+    }
+    Ok(bytes)
+}
+
+/// Str -> Str
+///
+/// `include_str("data.txt")`: the content of `data.txt` (must be UTF-8)
+pub(crate) fn include_str_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let bytes = read_included_file(&mut args, "include_str", ctx)?;
+    let content = String::from_utf8(bytes).map_err(|_| {
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            "the included file is not a valid UTF-8 text (use include_bytes)".to_string(),
+            line!() as usize,
+            ErrorKind::ValueError,
+            Location::Unknown,
+        )
+    })?;
+    Ok(ValueObj::Str(content.into()))
+}
+
+/// Str -> Bytes
+pub(crate) fn include_bytes_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let bytes = read_included_file(&mut args, "include_bytes", ctx)?;
+    Ok(ValueObj::Bytes(bytes.into()))
+}
+
 pub(crate) fn __array_getitem__(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
-    let slf = ctx
-        .convert_value_into_array(args.remove_left_or_key("Self").unwrap())
-        .unwrap_or_else(|err| panic!("{err}, {args}"));
-    let index = enum_unwrap!(args.remove_left_or_key("Index").unwrap(), ValueObj::Nat);
+    let slf = args.remove_left_or_key("Self").unwrap();
+    // e.g. `Array(Int, N)`, whose length is unknown
+    let slf = ctx.convert_value_into_array(slf).map_err(|slf| {
+        let slf = StyledString::new(format!("{slf}"), Some(ERR), None);
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{slf} cannot be indexed at compile time"),
+            line!() as usize,
+            ErrorKind::TypeError,
+            Location::Unknown,
+        )
+    })?;
+    let ValueObj::Nat(index) = args.remove_left_or_key("Index").unwrap() else {
+        return Err(ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            "the index must be a Nat constant".to_string(),
+            line!() as usize,
+            ErrorKind::TypeError,
+            Location::Unknown,
+        )
+        .into());
+    };
     if let Some(v) = slf.get(index as usize) {
         Ok(v.clone())
     } else {
@@ -351,20 +691,42 @@ pub(crate) fn __dict_getitem__(mut args: ValueArgs, ctx: &Context) -> EvalValueR
     }
 }
 
-/// `{Str: Int, Int: Float}.keys() == DictKeys(Str or Int)`
-pub(crate) fn dict_keys(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
-    let slf = args.remove_left_or_key("Self").unwrap();
-    let slf = enum_unwrap!(slf, ValueObj::Dict);
-    let slf = slf
-        .into_iter()
-        .map(|(k, v)| {
-            (
-                ctx.convert_value_into_type(k).unwrap(),
-                ctx.convert_value_into_type(v).unwrap(),
+fn dict_self(args: &mut ValueArgs, name: &str) -> EvalValueResult<Dict<ValueObj, ValueObj>> {
+    match args.remove_left_or_key("Self") {
+        Some(ValueObj::Dict(dict)) => Ok(dict),
+        other => {
+            let other = StyledString::new(fmt_option!(other, else "nothing"), Some(ERR), None);
+            Err(ErrorCore::new(
+                vec![SubMessage::only_loc(Location::Unknown)],
+                format!("{name} expects a dict, but {other} is passed"),
+                line!() as usize,
+                ErrorKind::TypeError,
+                Location::Unknown,
             )
+            .into())
+        }
+    }
+}
+
+/// Returns `None` if the dict is not a dict of types (e.g. `{"a": Int}`).
+fn dict_types(dict: &Dict<ValueObj, ValueObj>, ctx: &Context) -> Option<Dict<Type, Type>> {
+    dict.iter()
+        .map(|(k, v)| {
+            let k = ctx.convert_value_into_type(k.clone()).ok()?;
+            let v = ctx.convert_value_into_type(v.clone()).ok()?;
+            Some((k, v))
         })
-        .collect::<Dict<_, _>>();
-    let union = slf
+        .collect()
+}
+
+/// `{Str: Int, Int: Float}.keys() == DictKeys(Str or Int)`
+/// `{"a": Int, "b": Str}.keys() == {"a", "b"}`
+pub(crate) fn dict_keys(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = dict_self(&mut args, "keys")?;
+    let Some(types) = dict_types(&slf, ctx) else {
+        return Ok(ValueObj::Set(slf.into_iter().map(|(k, _)| k).collect()));
+    };
+    let union = types
         .keys()
         .fold(Type::Never, |union, t| ctx.union(&union, t));
     let keys = poly(DICT_KEYS, vec![ty_tp(union)]);
@@ -372,19 +734,14 @@ pub(crate) fn dict_keys(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<V
 }
 
 /// `{Str: Int, Int: Float}.values() == DictValues(Int or Float)`
+/// `{"a": Int, "b": Str}.values() == [Int, Str]` (the order is unspecified)
 pub(crate) fn dict_values(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
-    let slf = args.remove_left_or_key("Self").unwrap();
-    let slf = enum_unwrap!(slf, ValueObj::Dict);
-    let slf = slf
-        .into_iter()
-        .map(|(k, v)| {
-            (
-                ctx.convert_value_into_type(k).unwrap(),
-                ctx.convert_value_into_type(v).unwrap(),
-            )
-        })
-        .collect::<Dict<_, _>>();
-    let union = slf
+    let slf = dict_self(&mut args, "values")?;
+    let Some(types) = dict_types(&slf, ctx) else {
+        let values = slf.into_values().collect::<Vec<_>>();
+        return Ok(ValueObj::Array(values.into()));
+    };
+    let union = types
         .values()
         .fold(Type::Never, |union, t| ctx.union(&union, t));
     let values = poly(DICT_VALUES, vec![ty_tp(union)]);
@@ -392,25 +749,63 @@ pub(crate) fn dict_values(mut args: ValueArgs, ctx: &Context) -> EvalValueResult
 }
 
 /// `{Str: Int, Int: Float}.items() == DictItems((Str, Int) or (Int, Float))`
+/// `{"a": Int, "b": Str}.items() == {("a", Int), ("b", Str)}`
 pub(crate) fn dict_items(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
-    let slf = args.remove_left_or_key("Self").unwrap();
-    let slf = enum_unwrap!(slf, ValueObj::Dict);
-    let slf = slf
-        .into_iter()
-        .map(|(k, v)| {
-            (
-                ctx.convert_value_into_type(k).unwrap(),
-                ctx.convert_value_into_type(v).unwrap(),
-            )
-        })
-        .collect::<Dict<_, _>>();
-    let union = slf.iter().fold(Type::Never, |union, (k, v)| {
+    let slf = dict_self(&mut args, "items")?;
+    let Some(types) = dict_types(&slf, ctx) else {
+        let items = slf
+            .into_iter()
+            .map(|(k, v)| ValueObj::Tuple(vec![k, v].into()))
+            .collect();
+        return Ok(ValueObj::Set(items));
+    };
+    let union = types.iter().fold(Type::Never, |union, (k, v)| {
         ctx.union(&union, &tuple_t(vec![k.clone(), v.clone()]))
     });
     let items = poly(DICT_ITEMS, vec![ty_tp(union)]);
     Ok(ValueObj::builtin_class(items))
 }
 
+/// `{"a": Int}.get("a") == Int`, `{"a": Int}.get("b", Str) == Str`
+pub(crate) fn dict_get(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = dict_self(&mut args, "get")?;
+    let key = args.remove_left_or_key("key").ok_or_else(|| {
+        let key = StyledStr::new("key", Some(ERR), None);
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{key} is not passed"),
+            line!() as usize,
+            ErrorKind::KeyError,
+            Location::Unknown,
+        )
+    })?;
+    let default = args.remove_left_or_key("default").unwrap_or(ValueObj::None);
+    let value = slf.get(&key).or_else(|| sub_vdict_get(&slf, &key, ctx));
+    Ok(value.cloned().unwrap_or(default))
+}
+
+/// `{"a": Int}.concat({"b": Str}) == {"a": Int, "b": Str}`
+///
+/// The values of `other` take precedence, as with `+`.
+pub(crate) fn dict_concat(mut args: ValueArgs, _ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = dict_self(&mut args, "concat")?;
+    let other = match args.remove_left_or_key("other") {
+        Some(ValueObj::Dict(other)) => other,
+        other => {
+            let other = StyledString::new(fmt_option!(other, else "nothing"), Some(ERR), None);
+            return Err(ErrorCore::new(
+                vec![SubMessage::only_loc(Location::Unknown)],
+                format!("concat expects a dict, but {other} is passed"),
+                line!() as usize,
+                ErrorKind::TypeError,
+                Location::Unknown,
+            )
+            .into());
+        }
+    };
+    Ok(ValueObj::Dict(slf.concat(other)))
+}
+
 /// `[Int, Str].union() == Int or Str`
 pub(crate) fn array_union(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
     let slf = args.remove_left_or_key("Self").unwrap();
@@ -425,6 +820,195 @@ pub(crate) fn array_union(mut args: ValueArgs, ctx: &Context) -> EvalValueResult
     Ok(ValueObj::builtin_type(union))
 }
 
+fn array_self(args: &mut ValueArgs, name: &str) -> EvalValueResult<Vec<ValueObj>> {
+    match args.remove_left_or_key("Self") {
+        Some(ValueObj::Array(arr)) => Ok(arr.to_vec()),
+        other => {
+            let other = StyledString::new(fmt_option!(other, else "nothing"), Some(ERR), None);
+            Err(ErrorCore::new(
+                vec![SubMessage::only_loc(Location::Unknown)],
+                format!("{name} expects an array, but {other} is passed"),
+                line!() as usize,
+                ErrorKind::TypeError,
+                Location::Unknown,
+            )
+            .into())
+        }
+    }
+}
+
+/// Calls a const subroutine passed as an argument (e.g. `func` of `Array.map`).
+fn call_const_subr(
+    func: &ValueObj,
+    args: Vec<ValueObj>,
+    ctx: &Context,
+) -> EvalValueResult<ValueObj> {
+    let ValueObj::Subr(subr) = func else {
+        let func = StyledString::new(format!("{func}"), Some(ERR), None);
+        return Err(ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("non-subroutine object {func} is passed"),
+            line!() as usize,
+            ErrorKind::TypeError,
+            Location::Unknown,
+        )
+        .into());
+    };
+    ctx.call(
+        subr.clone(),
+        ValueArgs::new(args, Dict::new()),
+        Location::Unknown,
+    )
+    .map_err(|mut errs| EvalValueError::from(*errs.remove(0).core))
+}
+
+fn func_arg(args: &mut ValueArgs) -> EvalValueResult<ValueObj> {
+    args.remove_left_or_key("func").ok_or_else(|| {
+        let func = StyledStr::new("func", Some(ERR), None);
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{func} is not passed"),
+            line!() as usize,
+            ErrorKind::KeyError,
+            Location::Unknown,
+        )
+        .into()
+    })
+}
+
+/// `[1, 2].map(x -> x + 1) == [2, 3]`
+pub(crate) fn array_map(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = array_self(&mut args, "map")?;
+    let func = func_arg(&mut args)?;
+    let mapped = slf
+        .into_iter()
+        .map(|elem| call_const_subr(&func, vec![elem], ctx))
+        .collect::<EvalValueResult<Vec<_>>>()?;
+    Ok(ValueObj::Array(mapped.into()))
+}
+
+/// `[1, 2, 3].filter(x -> x > 1) == [2, 3]`
+pub(crate) fn array_filter(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = array_self(&mut args, "filter")?;
+    let func = func_arg(&mut args)?;
+    let mut filtered = vec![];
+    for elem in slf.into_iter() {
+        match call_const_subr(&func, vec![elem.clone()], ctx)? {
+            ValueObj::Bool(true) => filtered.push(elem),
+            ValueObj::Bool(false) => {}
+            other => {
+                let other = StyledString::new(format!("{other}"), Some(ERR), None);
+                return Err(ErrorCore::new(
+                    vec![SubMessage::only_loc(Location::Unknown)],
+                    format!("filter expects a predicate, but it returned {other}"),
+                    line!() as usize,
+                    ErrorKind::TypeError,
+                    Location::Unknown,
+                )
+                .into());
+            }
+        }
+    }
+    Ok(ValueObj::Array(filtered.into()))
+}
+
+/// `[1, 2, 3].fold(0, (acc, x) -> acc + x) == 6`
+pub(crate) fn array_fold(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = array_self(&mut args, "fold")?;
+    let init = args.remove_left_or_key("init").ok_or_else(|| {
+        let init = StyledStr::new("init", Some(ERR), None);
+        ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{init} is not passed"),
+            line!() as usize,
+            ErrorKind::KeyError,
+            Location::Unknown,
+        )
+    })?;
+    let func = func_arg(&mut args)?;
+    slf.into_iter().try_fold(init, |acc, elem| {
+        call_const_subr(&func, vec![acc, elem], ctx)
+    })
+}
+
+/// `[3, 1, 2].sort() == [1, 2, 3]`
+pub(crate) fn array_sort(mut args: ValueArgs, _ctx: &Context) -> EvalValueResult<ValueObj> {
+    let mut slf = array_self(&mut args, "sort")?;
+    let mut incomparable = None;
+    slf.sort_by(|l, r| {
+        l.try_cmp(r).unwrap_or_else(|| {
+            incomparable.get_or_insert_with(|| (l.clone(), r.clone()));
+            Ordering::Equal
+        })
+    });
+    if let Some((l, r)) = incomparable {
+        let l = StyledString::new(format!("{l}"), Some(ERR), None);
+        let r = StyledString::new(format!("{r}"), Some(ERR), None);
+        return Err(ErrorCore::new(
+            vec![SubMessage::only_loc(Location::Unknown)],
+            format!("{l} and {r} cannot be compared"),
+            line!() as usize,
+            ErrorKind::TypeError,
+            Location::Unknown,
+        )
+        .into());
+    }
+    Ok(ValueObj::Array(slf.into()))
+}
+
+/// `{"a": 1, "b": 2}.map((k, v) -> (k, v * 10)) == {"a": 10, "b": 20}`
+pub(crate) fn dict_map(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = dict_self(&mut args, "map")?;
+    let func = func_arg(&mut args)?;
+    let mut mapped = Dict::new();
+    for (k, v) in slf.into_iter() {
+        match call_const_subr(&func, vec![k, v], ctx)? {
+            ValueObj::Tuple(kv) if kv.len() == 2 => {
+                mapped.insert(kv[0].clone(), kv[1].clone());
+            }
+            other => {
+                let other = StyledString::new(format!("{other}"), Some(ERR), None);
+                return Err(ErrorCore::new(
+                    vec![SubMessage::only_loc(Location::Unknown)],
+                    format!("map expects a key-value pair, but the function returned {other}"),
+                    line!() as usize,
+                    ErrorKind::TypeError,
+                    Location::Unknown,
+                )
+                .into());
+            }
+        }
+    }
+    Ok(ValueObj::Dict(mapped))
+}
+
+/// `{"a": 1, "b": 2}.filter((k, v) -> v > 1) == {"b": 2}`
+pub(crate) fn dict_filter(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<ValueObj> {
+    let slf = dict_self(&mut args, "filter")?;
+    let func = func_arg(&mut args)?;
+    let mut filtered = Dict::new();
+    for (k, v) in slf.into_iter() {
+        match call_const_subr(&func, vec![k.clone(), v.clone()], ctx)? {
+            ValueObj::Bool(true) => {
+                filtered.insert(k, v);
+            }
+            ValueObj::Bool(false) => {}
+            other => {
+                let other = StyledString::new(format!("{other}"), Some(ERR), None);
+                return Err(ErrorCore::new(
+                    vec![SubMessage::only_loc(Location::Unknown)],
+                    format!("filter expects a predicate, but it returned {other}"),
+                    line!() as usize,
+                    ErrorKind::TypeError,
+                    Location::Unknown,
+                )
+                .into());
+            }
+        }
+    }
+    Ok(ValueObj::Dict(filtered))
+}
+
 pub(crate) fn __range_getitem__(mut args: ValueArgs, _ctx: &Context) -> EvalValueResult<ValueObj> {
     let (_name, fields) = enum_unwrap!(
         args.remove_left_or_key("Self").unwrap(),
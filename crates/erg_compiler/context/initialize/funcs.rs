@@ -5,7 +5,7 @@ use erg_common::log;
 use crate::ty::constructors::*;
 use crate::ty::typaram::TyParam;
 use crate::ty::value::ValueObj;
-use crate::ty::{Field, Type, Visibility};
+use crate::ty::{Field, SubrKind, Type, Visibility};
 use Type::*;
 
 use crate::context::initialize::*;
@@ -75,9 +75,14 @@ impl Context {
             poly(ENUMERATE, vec![ty_tp(T.clone())]),
         )
         .quantify();
-        let t_filter = nd_func(
+        // `filter` and `map` are procedures if a procedure is passed
+        let t_filter = nd_subr(
+            SubrKind::Var,
             vec![
-                kw(KW_FUNC, nd_func(vec![anon(T.clone())], None, T.clone())),
+                kw(
+                    KW_FUNC,
+                    nd_subr(SubrKind::Var, vec![anon(T.clone())], None, T.clone()),
+                ),
                 kw(KW_ITERABLE, poly(ITERABLE, vec![ty_tp(T.clone())])),
             ],
             None,
@@ -152,9 +157,13 @@ impl Context {
             ],
             NoneType,
         );
-        let t_map = nd_func(
+        let t_map = nd_subr(
+            SubrKind::Var,
             vec![
-                kw(KW_FUNC, nd_func(vec![anon(T.clone())], None, U.clone())),
+                kw(
+                    KW_FUNC,
+                    nd_subr(SubrKind::Var, vec![anon(T.clone())], None, U.clone()),
+                ),
                 kw(KW_ITERABLE, poly(ITERABLE, vec![ty_tp(T.clone())])),
             ],
             None,
@@ -243,6 +252,11 @@ impl Context {
         )
         .quantify();
         let t_unreachable = d_func(vec![kw(KW_MSG, Obj)], Never);
+        // the return type is replaced with the declared type (see `ASTLowerer::lower_unsafe_python`)
+        let t_unsafe_python = nd_func(vec![kw(KW_CODE, Str)], None, Obj);
+        // the return types are replaced with the declared types, and the calls are compiled inline (see `ASTLowerer::lower_intrinsic`)
+        let t_asm = nd_func(vec![kw(KW_CODE, Str)], Some(kw(KW_ARGS, Obj)), Obj);
+        let t_intrinsic = nd_func(vec![kw(KW_NAME, Str)], Some(kw(KW_ARGS, Obj)), Obj);
         let t_zip = nd_func(
             vec![
                 kw(KW_ITERABLE1, poly(ITERABLE, vec![ty_tp(T.clone())])),
@@ -483,9 +497,18 @@ impl Context {
                 FUNC_TODO,
                 t_unreachable,
                 Immutable,
-                vis,
+                vis.clone(),
                 Some(FUNC_EXIT),
             );
+            self.register_builtin_py_impl(
+                FUNC_UNSAFE_PYTHON,
+                t_unsafe_python,
+                Immutable,
+                vis.clone(),
+                Some(FUNC_UNSAFE_PYTHON__),
+            );
+            self.register_builtin_erg_impl(FUNC_ASM, t_asm, Immutable, vis.clone());
+            self.register_builtin_erg_impl(FUNC_INTRINSIC, t_intrinsic, Immutable, vis);
         } else {
             let t_list = func(
                 vec![],
@@ -531,7 +554,11 @@ impl Context {
         let class_t = func(
             vec![],
             None,
-            vec![kw(KW_REQUIREMENT, or(Type, Ellipsis)), kw(KW_IMPL, Type)],
+            vec![
+                kw(KW_REQUIREMENT, or(Type, Ellipsis)),
+                kw(KW_IMPL, Type),
+                kw(KW_DERIVE, Type),
+            ],
             ClassType,
         );
         let class = ConstSubr::Builtin(BuiltinConstSubr::new(CLASS, class_func, class_t, None));
@@ -586,6 +613,77 @@ impl Context {
             None,
         ));
         self.register_builtin_const(INHERITABLE, vis.clone(), ValueObj::Subr(inheritable));
+        let static_assert_t = func(
+            vec![kw(KW_TEST, Bool)],
+            None,
+            vec![kw(KW_MSG, Str)],
+            NoneType,
+        );
+        let static_assert = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_STATIC_ASSERT,
+            static_assert_func,
+            static_assert_t,
+            None,
+        ));
+        self.register_builtin_const(
+            FUNC_STATIC_ASSERT,
+            vis.clone(),
+            ValueObj::Subr(static_assert),
+        );
+        // reflection
+        let type_name = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_TYPE_NAME,
+            type_name_func,
+            func1(Type, Str),
+            None,
+        ));
+        self.register_builtin_const(FUNC_TYPE_NAME, vis.clone(), ValueObj::Subr(type_name));
+        let fields = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_FIELDS,
+            fields_func,
+            func1(Type, dict_t(dict! { Str => Type }.into())),
+            None,
+        ));
+        self.register_builtin_const(FUNC_FIELDS, vis.clone(), ValueObj::Subr(fields));
+        let methods = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_METHODS,
+            methods_func,
+            func1(Type, unknown_len_array_t(Str)),
+            None,
+        ));
+        self.register_builtin_const(FUNC_METHODS, vis.clone(), ValueObj::Subr(methods));
+        let supers = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_SUPERS,
+            supers_func,
+            func1(Type, unknown_len_array_t(Type)),
+            None,
+        ));
+        self.register_builtin_const(FUNC_SUPERS, vis.clone(), ValueObj::Subr(supers));
+        let parse_json = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_PARSE_JSON,
+            parse_json_func,
+            nd_func(vec![kw(KW_SRC, Str)], None, Obj),
+            None,
+        ));
+        self.register_builtin_const(FUNC_PARSE_JSON, vis.clone(), ValueObj::Subr(parse_json));
+        let include_str = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_INCLUDE_STR,
+            include_str_func,
+            nd_func(vec![kw(KW_PATH, Str)], None, Str),
+            None,
+        ));
+        self.register_builtin_const(FUNC_INCLUDE_STR, vis.clone(), ValueObj::Subr(include_str));
+        let include_bytes = ConstSubr::Builtin(BuiltinConstSubr::new(
+            FUNC_INCLUDE_BYTES,
+            include_bytes_func,
+            nd_func(vec![kw(KW_PATH, Str)], None, mono(BYTES)),
+            None,
+        ));
+        self.register_builtin_const(
+            FUNC_INCLUDE_BYTES,
+            vis.clone(),
+            ValueObj::Subr(include_bytes),
+        );
         // TODO: register Del function object
         let t_del = nd_func(vec![kw(KW_OBJ, Obj)], None, NoneType);
         self.register_builtin_erg_impl(DEL, t_del, Immutable, vis.clone());
@@ -11,6 +11,7 @@ mod procs;
 mod traits;
 
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
 use erg_common::config::ErgConfig;
 use erg_common::consts::{DEBUG_MODE, ERG_MODE, PYTHON_MODE};
@@ -19,6 +20,7 @@ use erg_common::env::{erg_pystd_path, erg_std_decl_path};
 use erg_common::error::Location;
 #[allow(unused_imports)]
 use erg_common::log;
+use erg_common::python_util::{env_python_version, opt_which_python, PythonVersion};
 use erg_common::Str;
 use erg_common::{set, unique_in_place};
 
@@ -34,8 +36,8 @@ use crate::ty::constructors::*;
 use crate::ty::free::Constraint;
 use crate::ty::value::ValueObj;
 use crate::ty::{
-    BuiltinConstSubr, ClosureData, ConstSubr, GenConstSubr, ParamTy, Predicate, TyParam, Type,
-    Visibility,
+    BuiltinConstSubr, ClosureData, ConstSubr, Field, GenConstSubr, ParamTy, Predicate, TyParam,
+    Type, Visibility,
 };
 use crate::varinfo::{AbsLocation, Mutability, VarInfo, VarKind};
 use Mutability::*;
@@ -98,6 +100,7 @@ const MAPPING: &str = "Mapping";
 const MUTABLE_MAPPING: &str = "Mapping!";
 const EQ: &str = "Eq";
 const ORD: &str = "Ord";
+const HASH: &str = "Hash";
 const TO_STR: &str = "to_str";
 const ORDERING: &str = "Ordering";
 const SEQUENCE: &str = "Sequence";
@@ -125,6 +128,7 @@ const OBJ: &str = "Obj";
 const MUTABLE_OBJ: &str = "Obj!";
 const FUNC_CLONE: &str = "clone";
 const BYTES: &str = "Bytes";
+const AST: &str = "Ast";
 const BYTEARRAY: &str = "ByteArray!";
 const FLOAT: &str = "Float";
 const MUT_FLOAT: &str = "Float!";
@@ -167,6 +171,8 @@ const FUNC_UPPER: &str = "upper";
 const FUNC_TO_INT: &str = "to_int";
 const FUNC_STARTSWITH: &str = "startswith";
 const FUNC_ENDSWITH: &str = "endswith";
+const FUNC_REMOVEPREFIX: &str = "removeprefix";
+const FUNC_REMOVESUFFIX: &str = "removesuffix";
 const FUNC_CAPITALIZE: &str = "capitalize";
 const FUNC_CONTAINS: &str = "contains";
 const FUNC_SPLIT: &str = "split";
@@ -245,6 +251,7 @@ const ISSUPERSET: &str = "issuperset";
 const SYMMETRIC_DIFFERENCE: &str = "symmetric_difference";
 const MEMORYVIEW: &str = "MemoryView";
 const UNION_FUNC: &str = "union";
+const FUNC_FOLD: &str = "fold";
 const FUNC_INC: &str = "inc";
 const PROC_INC: &str = "inc!";
 const FUNC_DEC: &str = "dec";
@@ -351,12 +358,24 @@ const FUNC_NAT__: &str = "nat__";
 const FUNC_PANIC: &str = "panic";
 const FUNC_UNREACHABLE: &str = "unreachable";
 const FUNC_TODO: &str = "todo";
+const FUNC_UNSAFE_PYTHON: &str = "unsafe_python";
+const FUNC_UNSAFE_PYTHON__: &str = "unsafe_python__";
+const FUNC_ASM: &str = "asm";
+const FUNC_INTRINSIC: &str = "intrinsic";
 const SUBSUME: &str = "Subsume";
 const INHERIT: &str = "Inherit";
 const INHERITABLE: &str = "Inheritable";
 const DEL: &str = "Del";
 const PATCH: &str = "Patch";
 const STRUCTURAL: &str = "Structural";
+const FUNC_STATIC_ASSERT: &str = "static_assert";
+const FUNC_TYPE_NAME: &str = "type_name";
+const FUNC_FIELDS: &str = "fields";
+const FUNC_METHODS: &str = "methods";
+const FUNC_SUPERS: &str = "supers";
+const FUNC_PARSE_JSON: &str = "parse_json";
+const FUNC_INCLUDE_STR: &str = "include_str";
+const FUNC_INCLUDE_BYTES: &str = "include_bytes";
 const KEYS: &str = "keys";
 const VALUES: &str = "values";
 const ITEMS: &str = "items";
@@ -414,6 +433,7 @@ const FUNDAMENTAL_MODULE: &str = "__module__";
 const FUNDAMENTAL_SIZEOF: &str = "__sizeof__";
 const FUNDAMENTAL_REPR: &str = "__repr__";
 const FUNDAMENTAL_DICT: &str = "__dict__";
+const FUNDAMENTAL_DOC: &str = "__doc__";
 const FUNDAMENTAL_BYTES: &str = "__bytes__";
 const FUNDAMENTAL_GETITEM: &str = "__getitem__";
 const FUNDAMENTAL_TUPLE_GETITEM: &str = "__Tuple_getitem__";
@@ -432,6 +452,7 @@ const FALSE: &str = "False";
 const NONE: &str = "None";
 const NOT_IMPLEMENTED: &str = "NotImplemented";
 const ELLIPSIS: &str = "Ellipsis";
+const CFG: &str = "Cfg";
 const SITEBUILTINS_PRINTER: &str = "_sitebuiltins._Printer";
 const PY: &str = "py";
 const PYIMPORT: &str = "pyimport";
@@ -471,8 +492,10 @@ const KW_ARGS: &str = "args";
 const KW_IDX: &str = "idx";
 const KW_LHS: &str = "lhs";
 const KW_RHS: &str = "rhs";
+const KW_OTHER: &str = "other";
 const KW_ELEM: &str = "elem";
 const KW_FUNC: &str = "func";
+const KW_INIT: &str = "init";
 const KW_ITERABLE: &str = "iterable";
 const KW_INDEX: &str = "index";
 const KW_KEY: &str = "key";
@@ -485,6 +508,7 @@ const KW_MSG: &str = "msg";
 const KW_STR: &str = "str";
 const KW_I: &str = "i";
 const KW_SRC: &str = "src";
+const KW_PATH: &str = "path";
 const KW_THEN: &str = "then";
 const KW_ELSE: &str = "else";
 const KW_OBJ: &str = "obj";
@@ -511,6 +535,7 @@ const KW_STOP: &str = "stop";
 const KW_STEP: &str = "step";
 const KW_REQUIREMENT: &str = "Requirement";
 const KW_IMPL: &str = "Impl";
+const KW_DERIVE: &str = "Derive";
 const KW_ADDITIONAL: &str = "Additional";
 const KW_SUPER: &str = "Super";
 const KW_MAXSPLIT: &str = "maxsplit";
@@ -677,6 +702,17 @@ impl Context {
     }
 
     fn register_builtin_const(&mut self, name: &str, vis: Visibility, obj: ValueObj) {
+        self.register_py_builtin_const(name, vis, obj, None);
+    }
+
+    /// `py_name` is used when the const subroutine is called at runtime.
+    fn register_py_builtin_const(
+        &mut self,
+        name: &str,
+        vis: Visibility,
+        obj: ValueObj,
+        py_name: Option<&'static str>,
+    ) {
         if self.rec_get_const_obj(name).is_some() {
             panic!("already registered: {} {name}", self.name);
         } else {
@@ -693,7 +729,7 @@ impl Context {
                 Builtin,
                 None,
                 impl_of,
-                None,
+                py_name.map(Str::ever),
                 AbsLocation::unknown(),
             );
             self.consts.insert(VarName::from_str(Str::rc(name)), obj);
@@ -984,6 +1020,48 @@ impl Context {
         }
     }
 
+    /// `Cfg = {py_major = 3; py_minor = 11; debug = True; release = False; ...}`
+    ///
+    /// The user-defined flags (`-D key=value`) are added (or override the above).
+    fn compile_time_flags(&self) -> ValueObj {
+        static ENV_PY_VERSION: OnceLock<PythonVersion> = OnceLock::new();
+        let py_version = self.cfg.target_version.unwrap_or_else(|| {
+            *ENV_PY_VERSION.get_or_init(|| {
+                if opt_which_python().is_ok() {
+                    env_python_version()
+                } else {
+                    PythonVersion::default()
+                }
+            })
+        });
+        let debug = !self.cfg.is_release();
+        let mut flags = dict! {
+            Field::public(Str::ever("py_major")) => ValueObj::Nat(py_version.major as u64),
+            Field::public(Str::ever("py_minor")) => ValueObj::Nat(py_version.minor.unwrap_or(0) as u64),
+            Field::public(Str::ever("debug")) => ValueObj::Bool(debug),
+            Field::public(Str::ever("release")) => ValueObj::Bool(!debug),
+        };
+        for (key, value) in self.cfg.flags.iter() {
+            let value = match &value[..] {
+                "true" | "True" => ValueObj::Bool(true),
+                "false" | "False" => ValueObj::Bool(false),
+                _ => {
+                    if let Ok(nat) = value.parse::<u64>() {
+                        ValueObj::Nat(nat)
+                    } else if let Ok(int) = value.parse::<i32>() {
+                        ValueObj::Int(int)
+                    } else if let Ok(float) = value.parse::<f64>() {
+                        ValueObj::Float(float)
+                    } else {
+                        ValueObj::Str(Str::rc(value))
+                    }
+                }
+            };
+            flags.insert(Field::public(Str::rc(key)), value);
+        }
+        ValueObj::Record(flags)
+    }
+
     fn init_module_consts(&mut self) {
         let vis = if PYTHON_MODE {
             Visibility::BUILTIN_PUBLIC
@@ -1002,9 +1080,10 @@ impl Context {
                 FUNC_MODULE,
                 module(TyParam::value(self.get_module().unwrap().name.clone())),
                 Immutable,
-                vis,
+                vis.clone(),
                 None,
             );
+            self.register_builtin_const(CFG, vis, self.compile_time_flags());
         }
     }
 
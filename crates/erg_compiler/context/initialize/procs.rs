@@ -117,6 +117,13 @@ impl Context {
             U,
         )
         .quantify();
+        // removed at compile time in a release build (see `ASTLowerer::lower_debug_only`)
+        let t_debug_only = proc(
+            vec![kw("proc!", proc0(Obj))],
+            None,
+            vec![kw("when", Bool)],
+            NoneType,
+        );
         self.register_builtin_py_impl("dir!", t_dir, Immutable, vis.clone(), Some("dir"));
         self.register_py_builtin("print!", t_print, Some("print"), 81);
         self.register_builtin_py_impl("id!", t_id, Immutable, vis.clone(), Some("id"));
@@ -131,6 +138,13 @@ impl Context {
         self.register_builtin_py_impl("locals!", t_locals, Immutable, vis.clone(), Some("locals"));
         self.register_builtin_py_impl("next!", t_next, Immutable, vis.clone(), Some("next"));
         self.register_py_builtin("open!", t_open, Some("open"), 198);
+        self.register_builtin_py_impl(
+            "debug_only!",
+            t_debug_only,
+            Immutable,
+            vis.clone(),
+            Some("debug_only__"),
+        );
         let name = if PYTHON_MODE { "if" } else { "if__" };
         self.register_builtin_py_impl("if!", t_if, Immutable, vis.clone(), Some(name));
         let name = if PYTHON_MODE { "for" } else { "for__" };
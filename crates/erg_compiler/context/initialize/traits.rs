@@ -202,6 +202,12 @@ impl Context {
         let Slf = mono_q(SELF, subtypeof(mono(ORD)));
         let op_t = fn1_met(Slf.clone(), Slf, or(mono(ORDERING), NoneType)).quantify();
         ord.register_builtin_erg_decl(OP_CMP, op_t, Visibility::BUILTIN_PUBLIC);
+        /* Hash */
+        let mut hash = Self::builtin_mono_trait(HASH, 2);
+        let Slf = mono_q(SELF, subtypeof(mono(HASH)));
+        // __hash__: |Self <: Hash| (self: Self) -> Nat
+        let op_t = fn0_met(Slf, Nat).quantify();
+        hash.register_builtin_erg_decl(FUNDAMENTAL_HASH, op_t, Visibility::BUILTIN_PUBLIC);
         /* Iterable */
         let mut iterable = Self::builtin_poly_trait(ITERABLE, vec![PS::t_nd(TY_T)], 2);
         iterable.register_superclass(poly(OUTPUT, vec![ty_tp(T.clone())]), &output);
@@ -469,6 +475,7 @@ impl Context {
         );
         self.register_builtin_type(mono(EQ), eq, vis.clone(), Const, None);
         self.register_builtin_type(mono(ORD), ord, vis.clone(), Const, None);
+        self.register_builtin_type(mono(HASH), hash, vis.clone(), Const, None);
         self.register_builtin_type(mono(NUM), num, vis.clone(), Const, None);
         self.register_builtin_type(
             poly(SEQUENCE, vec![ty_tp(T.clone())]),
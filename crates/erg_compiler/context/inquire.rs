@@ -3,6 +3,7 @@ use std::option::Option; // conflicting to Type::Option
 use std::path::{Path, PathBuf};
 
 use erg_common::consts::{ERG_MODE, PYTHON_MODE};
+use erg_common::dict::Dict;
 use erg_common::error::{ErrorCore, Location, SubMessage};
 use erg_common::io::Input;
 use erg_common::levenshtein;
@@ -14,12 +15,14 @@ use erg_common::{
     dict, fmt_option, fmt_slice, get_hash, log, option_enum_unwrap, set, switch_lang,
 };
 
-use erg_parser::ast::{self, Identifier, VarName};
-use erg_parser::token::Token;
+use erg_parser::ast::{self, Identifier, VarName, VisModifierSpec};
+use erg_parser::token::{Token, TokenKind};
 
-use crate::ty::constructors::{anon, fn_met, free_var, func, mono, poly, proc, proj, ref_, subr_t};
+use crate::ty::constructors::{
+    anon, array_t, bin_op, fn1_met, fn_met, free_var, func, mono, poly, proc, proj, ref_, subr_t,
+};
 use crate::ty::free::{Constraint, FreeTyParam};
-use crate::ty::typaram::TyParam;
+use crate::ty::typaram::{OpKind, TyParam};
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{
     Field, GuardType, HasType, ParamTy, Predicate, SubrKind, SubrType, Type, Visibility,
@@ -27,9 +30,9 @@ use crate::ty::{
 use Type::*;
 
 use crate::context::instantiate_spec::ConstTemplate;
-use crate::context::{Context, RegistrationMode, TraitImpl, TyVarCache, Variance};
+use crate::context::{Context, OuterContext, RegistrationMode, TraitImpl, TyVarCache, Variance};
 use crate::error::{
-    binop_to_dname, ordinal_num, readable_name, unaryop_to_dname, SingleTyCheckResult,
+    binop_to_dname, codes, ordinal_num, readable_name, unaryop_to_dname, SingleTyCheckResult,
     TyCheckError, TyCheckErrors, TyCheckResult,
 };
 use crate::varinfo::{AbsLocation, Mutability, VarInfo, VarKind};
@@ -40,6 +43,18 @@ use RegistrationMode::*;
 use super::instantiate_spec::ParamKind;
 use super::{ContextKind, MethodPair};
 
+/// (operator name, the method Python calls at runtime, the reflected one)
+/// e.g. `/` is `__div__` in Erg, but `__truediv__` in Python
+const DUNDER_BINOPS: [(&str, &str, &str); 7] = [
+    ("__add__", "__add__", "__radd__"),
+    ("__sub__", "__sub__", "__rsub__"),
+    ("__mul__", "__mul__", "__rmul__"),
+    ("__div__", "__truediv__", "__rtruediv__"),
+    ("__floordiv__", "__floordiv__", "__rfloordiv__"),
+    ("__pow__", "__pow__", "__rpow__"),
+    ("__mod__", "__mod__", "__rmod__"),
+];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SubstituteResult {
     Ok,
@@ -187,12 +202,26 @@ impl Context {
                 }
                 Ok(ctxs)
             }
+            // e.g. `mods[0]` (mods: [Module("foo"); _])
+            other if other.ref_t().is_module() => self
+                .get_mod_with_t(other.ref_t())
+                .map(|ctx| vec![ctx])
+                .ok_or_else(|| {
+                    TyCheckError::no_var_error(
+                        namespace.cfg.input.clone(),
+                        line!() as usize,
+                        obj.loc(),
+                        namespace.caused_by(),
+                        &obj.to_string(),
+                        None,
+                    )
+                }),
             // TODO: change error
             _ => Err(TyCheckError::no_var_error(
-                self.cfg.input.clone(),
+                namespace.cfg.input.clone(),
                 line!() as usize,
                 obj.loc(),
-                self.caused_by(),
+                namespace.caused_by(),
                 &obj.to_string(),
                 None,
             )),
@@ -214,8 +243,10 @@ impl Context {
             .ok_or_else(|| {
                 let (similar_info, similar_name) =
                     self.get_similar_name_and_info(ident.inspect()).unzip();
+                // `self` may be the context of another module (e.g. `os` of `os.pth`),
+                // but `ident` is in the module of `namespace`
                 TyCheckError::detailed_no_var_error(
-                    self.cfg.input.clone(),
+                    namespace.cfg.input.clone(),
                     line!() as usize,
                     ident.loc(),
                     namespace.name.to_string(),
@@ -223,6 +254,7 @@ impl Context {
                     similar_name,
                     similar_info,
                 )
+                .with_import_suggestion(namespace.get_import_suggestion(ident.inspect()))
             })
     }
 
@@ -263,10 +295,10 @@ impl Context {
                 Ok(ctxs)
             }
             _ => Err(TyCheckError::no_var_error(
-                self.cfg.input.clone(),
+                namespace.cfg.input.clone(),
                 line!() as usize,
                 obj.loc(),
-                self.caused_by(),
+                namespace.caused_by(),
                 &obj.to_string(),
                 None,
             )),
@@ -367,7 +399,10 @@ impl Context {
                 ParamKind::NonDefault,
                 false,
             )?;
-            union_pat_t = self.union(&union_pat_t, &rhs);
+            // guarded arms are not taken into account for exhaustiveness
+            if lambda.guard.is_none() {
+                union_pat_t = self.union(&union_pat_t, &rhs);
+            }
             arm_ts.push(rhs);
         }
         // NG: expr_t: Nat, union_pat_t: {1, 2}
@@ -431,12 +466,11 @@ impl Context {
     ) -> Triple<VarInfo, TyCheckError> {
         if ident.inspect() == "Self" {
             if let Some(self_t) = self.rec_get_self_t() {
-                return self.rec_get_var_info(
-                    &Identifier::auto(self_t.local_name()),
-                    acc_kind,
-                    input,
-                    namespace,
+                let self_ident = Identifier::new(
+                    VisModifierSpec::Auto,
+                    VarName::new(Token::symbol_with_loc(self_t.local_name(), ident.loc())),
                 );
+                return self.rec_get_var_info(&self_ident, acc_kind, input, namespace);
             }
         }
         if let Some(vi) = self.get_current_scope_var(&ident.name) {
@@ -706,7 +740,7 @@ impl Context {
                 }
             }
         }
-        let coerced = match self.coerce(obj.t(), &()).map_err(|mut es| es.remove(0)) {
+        let coerced = match self.coerce(obj.t(), obj).map_err(|mut es| es.remove(0)) {
             Ok(t) => t,
             Err(e) => {
                 return Triple::Err(e);
@@ -770,7 +804,16 @@ impl Context {
             }
             Type::FreeVar(fv) /* if fv.is_unbound() */ => {
                 let sup = fv.get_super().unwrap();
-                self.get_attr_info_from_attributive(&sup, ident)
+                match self.get_attr_info_from_attributive(&sup, ident) {
+                    // e.g. ?T(:> {.x = Int}) (the element type of `[{.x = 1}, ...]`)
+                    Triple::None => match fv.get_sub() {
+                        Some(sub) if sub != Type::Never => {
+                            self.get_attr_info_from_attributive(&sub, ident)
+                        }
+                        _ => Triple::None,
+                    },
+                    other => other,
+                }
             }
             Type::Ref(t) => self.get_attr_info_from_attributive(t, ident),
             Type::RefMut { before, .. } => {
@@ -831,6 +874,9 @@ impl Context {
         if let Some(attr_name) = attr_name.as_ref() {
             let mut vi =
                 self.search_method_info(obj, attr_name, pos_args, kw_args, input, namespace)?;
+            if let Some(res) = self.get_array_index_call_t(obj, attr_name, pos_args, kw_args, &vi) {
+                return res;
+            }
             vi.t = self.resolve_overload(obj, vi.t, pos_args, kw_args, attr_name)?;
             Ok(vi)
         } else {
@@ -867,6 +913,8 @@ impl Context {
                     .collect(),
                 Obj,
             );
+            let mut matched = vec![];
+            let mut reasons = vec![];
             for ty in intersecs.iter() {
                 match (ty.is_method(), input_t.is_method()) {
                     (true, false) => {
@@ -881,9 +929,28 @@ impl Context {
                     _ => {}
                 }
                 if self.subtype_of(ty, &input_t) {
-                    return Ok(ty.clone());
+                    matched.push(ty);
+                    // comparing with the rest may change the constraints of the type variables
+                    if input_t.has_unbound_var() {
+                        break;
+                    }
+                } else {
+                    let Type::Subr(input) = &input_t else { unreachable!() };
+                    reasons.push((ty.clone(), self.get_overload_mismatch_reason(ty, input)));
                 }
             }
+            // the most specific candidate is chosen (the leftmost one if they are not comparable)
+            let most_specific = matched
+                .iter()
+                .find(|ty| {
+                    matched
+                        .iter()
+                        .all(|other| self.is_more_specific_overload(ty, other))
+                })
+                .or(matched.first());
+            if let Some(ty) = most_specific {
+                return Ok((*ty).clone());
+            }
             let Type::Subr(subr_t) = input_t else { unreachable!() };
             Err(TyCheckError::overload_error(
                 self.cfg.input.clone(),
@@ -892,11 +959,25 @@ impl Context {
                 self.caused_by(),
                 subr_t.non_default_params,
                 subr_t.default_params,
-                intersecs,
+                reasons,
             ))
         }
     }
 
+    /// `l` is at least as specific as `r` if `r` accepts all the arguments `l` accepts.
+    /// The return types are not compared.
+    fn is_more_specific_overload(&self, l: &Type, r: &Type) -> bool {
+        match (l, r) {
+            (Type::Subr(l), Type::Subr(r)) => {
+                let (mut l, mut r) = (l.clone(), r.clone());
+                l.return_t = Box::new(Obj);
+                r.return_t = Box::new(Obj);
+                self.subtype_of(&Type::Subr(r), &Type::Subr(l))
+            }
+            _ => l == r,
+        }
+    }
+
     pub(crate) fn get_same_name_context(&self, name: &str) -> Option<&Context> {
         if &self.name[..] == name {
             Some(self)
@@ -1073,7 +1154,7 @@ impl Context {
             }
         }
         let coerced = self
-            .coerce(obj.t(), &())
+            .coerce(obj.t(), obj)
             .map_err(|mut errs| errs.remove(0))?;
         if &coerced != obj.ref_t() {
             let hash = get_hash(obj.ref_t());
@@ -1143,6 +1224,9 @@ impl Context {
         namespace: &Context,
     ) -> TyCheckResult<VarInfo> {
         erg_common::debug_power_assert!(args.len() == 2);
+        if let Some(vi) = self.get_record_binop_t(op, args, input, namespace) {
+            return vi;
+        }
         let cont = Str::rc(binop_to_dname(op.inspect()));
         // not a `Token::from_str(op.kind, cont)` because ops are defined as symbols
         let symbol = Token::symbol_with_loc(cont, Location::concat(&args[0], &args[1]));
@@ -1164,9 +1248,12 @@ impl Context {
             })?;
         let op = hir::Expr::Accessor(hir::Accessor::private(symbol, t));
         self.get_call_t(&op, &None, args, &[], input, namespace)
-            .map_err(|(_, errs)| {
+            .or_else(|(_, errs)| {
+                if let Some(vi) = self.get_dunder_binop_t(&op, args, input, namespace) {
+                    return Ok(vi);
+                }
                 let hir::Expr::Accessor(hir::Accessor::Ident(op_ident)) = op else {
-                    return errs;
+                    return Err(errs);
                 };
                 let vi = op_ident.vi.clone();
                 let lhs = args[0].expr.clone();
@@ -1176,8 +1263,152 @@ impl Context {
                     .into_iter()
                     .map(|e| self.append_loc_info(e, bin.loc()))
                     .collect();
-                TyCheckErrors::new(errs)
+                Err(TyCheckErrors::new(errs))
+            })
+    }
+
+    /// If the operands do not implement the operator trait (e.g. `Add`),
+    /// `lhs + rhs` is typed as `lhs.__add__(rhs)` or `rhs.__radd__(lhs)`, as Python does at runtime.
+    /// The returned type is in the form of `(lhs, rhs) -> Output` (the parameters of the reflected method are swapped).
+    fn get_dunder_binop_t(
+        &self,
+        op: &hir::Expr,
+        args: &[hir::PosArg],
+        input: &Input,
+        namespace: &Context,
+    ) -> Option<VarInfo> {
+        let (_, dname, reflected) = DUNDER_BINOPS
+            .iter()
+            .find(|(name, _, _)| Some(*name) == op.local_name())?;
+        let (lhs, rhs) = (&args[0], &args[1]);
+        let method = |name: &str| {
+            let symbol = Token::symbol_with_loc(Str::rc(name), Location::concat(lhs, rhs));
+            let dot = Token::from_str(TokenKind::Dot, ".");
+            Some(Identifier::public_from_token(dot, symbol))
+        };
+        if let Ok(vi) = self.get_call_t(
+            &lhs.expr,
+            &method(dname),
+            std::slice::from_ref(rhs),
+            &[],
+            input,
+            namespace,
+        ) {
+            return Some(vi);
+        }
+        let mut vi = self
+            .get_call_t(
+                &rhs.expr,
+                &method(reflected),
+                std::slice::from_ref(lhs),
+                &[],
+                input,
+                namespace,
+            )
+            .ok()?;
+        if let Type::Subr(subr) = &mut vi.t {
+            if subr.non_default_params.len() == 2 {
+                subr.non_default_params.swap(0, 1);
+            }
+        }
+        Some(vi)
+    }
+
+    /// `{x = Int} + {x = Nat; y = Str}: {x = Nat; y = Str}`, `{x = Int; y = Str} - {"y"}: {x = Int}`
+    /// The operands may be type variables bounded by record types (e.g. `|R <: {x = Int}|`).
+    fn get_record_binop_t(
+        &self,
+        op: &Token,
+        args: &[hir::PosArg],
+        input: &Input,
+        namespace: &Context,
+    ) -> Option<TyCheckResult<VarInfo>> {
+        let op_kind = self.try_get_op_kind_from_token(op).ok()?;
+        let (lhs, rhs) = (&args[0].expr, &args[1].expr);
+        let lhs_fields = self.record_fields(lhs.ref_t())?;
+        let (rhs_tp, py_name) = match op_kind {
+            OpKind::Add => {
+                let rhs_fields = self.record_fields(rhs.ref_t())?;
+                (Self::fields_to_tp(rhs_fields), "record_merge")
+            }
+            OpKind::Sub => {
+                let names = Self::field_names(rhs)?;
+                let missing = names
+                    .iter()
+                    .find(|name| !lhs_fields.keys().any(|field| &field.symbol == *name));
+                if let Some(name) = missing {
+                    return Some(Err(TyCheckErrors::from(TyCheckError::no_attr_error(
+                        input.clone(),
+                        line!() as usize,
+                        rhs.loc(),
+                        namespace.caused_by(),
+                        lhs.ref_t(),
+                        name,
+                        None,
+                    ))));
+                }
+                let names = names.into_iter().map(TyParam::value).collect();
+                (TyParam::Set(names), "record_remove")
+            }
+            _ => return None,
+        };
+        let return_t = self
+            .eval_bin_tp(op_kind, Self::fields_to_tp(lhs_fields), rhs_tp)
+            .ok()
+            .and_then(|tp| self.convert_tp_into_type(tp).ok())?;
+        let t = bin_op(lhs.ref_t().clone(), rhs.ref_t().clone(), return_t);
+        Some(Ok(VarInfo::new(
+            t,
+            Mutability::Immutable,
+            Visibility::BUILTIN_PRIVATE,
+            VarKind::Builtin,
+            None,
+            None,
+            Some(Str::ever(py_name)),
+            AbsLocation::unknown(),
+        )))
+    }
+
+    /// The fields of a record type (or a type variable bounded by a record type)
+    fn record_fields(&self, t: &Type) -> Option<Dict<Field, Type>> {
+        match t {
+            _ if t.is_record() => Some(self.fields(t)),
+            FreeVar(fv) if fv.is_unbound() => {
+                let sup = fv.get_super()?;
+                sup.is_record().then(|| self.fields(&sup))
+            }
+            _ => None,
+        }
+    }
+
+    fn fields_to_tp(fields: Dict<Field, Type>) -> TyParam {
+        TyParam::Record(
+            fields
+                .into_iter()
+                .map(|(field, t)| (field, TyParam::t(t)))
+                .collect(),
+        )
+    }
+
+    /// `{"x", "y"}` => `{"x", "y"}` (the names of the fields to be removed)
+    fn field_names(expr: &hir::Expr) -> Option<Set<Str>> {
+        let hir::Expr::Set(hir::Set::Normal(set)) = expr else {
+            return None;
+        };
+        set.elems
+            .pos_args
+            .iter()
+            .map(|arg| match &arg.expr {
+                hir::Expr::Lit(lit) => {
+                    if let ValueObj::Str(name) = &lit.value {
+                        Some(name.clone())
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
             })
+            .collect()
     }
 
     pub(crate) fn get_unaryop_t(
@@ -1299,7 +1530,7 @@ impl Context {
     /// ↓ don't substitute `Int` to `self`
     /// substitute_call(obj: Int, instance: ((self: Int, other: Int) -> Int), [1, 2]) => instance: (Int, Int) -> Int
     /// ```
-    fn substitute_call(
+    pub(crate) fn substitute_call(
         &self,
         obj: &hir::Expr,
         attr_name: &Option<Identifier>,
@@ -1455,18 +1686,6 @@ impl Context {
                         }
                         nth += 1;
                     }
-                    for not_passed in subr
-                        .default_params
-                        .iter()
-                        .filter(|pt| !passed_params.contains(pt.name().unwrap()))
-                    {
-                        if let ParamTy::KwWithDefault { ty, default, .. } = &not_passed {
-                            if let Err(mut es) = self.sub_unify(default, ty, obj, not_passed.name())
-                            {
-                                errs.append(&mut es);
-                            }
-                        }
-                    }
                 } else {
                     // pos_args.len() < non_default_params_len
                     let mut params = non_default_params.chain(subr.default_params.iter());
@@ -1496,13 +1715,16 @@ impl Context {
                         }
                         nth += 1;
                     }
+                    // `self` is already bound to the receiver
+                    let skip = usize::from(is_method);
                     let missing_params = subr
                         .non_default_params
                         .iter()
                         .enumerate()
+                        .skip(skip)
                         .map(|(i, pt)| {
                             pt.name().cloned().unwrap_or_else(|| {
-                                Str::from(format!("({} param)", ordinal_num(i + 1)))
+                                Str::from(format!("({} param)", ordinal_num(i + 1 - skip)))
                             })
                         })
                         .filter(|pt| !passed_params.contains(pt))
@@ -1518,6 +1740,19 @@ impl Context {
                         )));
                     }
                 }
+                // default values are filled in for the parameters not passed
+                // (it is not known whether an unnamed default parameter is passed)
+                for not_passed in subr
+                    .default_params
+                    .iter()
+                    .filter(|pt| pt.name().is_some_and(|name| !passed_params.contains(name)))
+                {
+                    if let ParamTy::KwWithDefault { ty, default, .. } = &not_passed {
+                        if let Err(mut es) = self.sub_unify(default, ty, obj, not_passed.name()) {
+                            errs.append(&mut es);
+                        }
+                    }
+                }
                 if errs.is_empty() {
                     /*if subr.has_qvar() {
                         panic!("{subr} has qvar");
@@ -1709,6 +1944,9 @@ impl Context {
                     errs.into_iter()
                         .map(|e| {
                             log!("err: {e}");
+                            if e.core.code == Some(codes::CYCLIC_TYPE) {
+                                return e;
+                            }
                             TyCheckError::type_mismatch_error(
                                 self.cfg.input.clone(),
                                 line!() as usize,
@@ -1754,6 +1992,9 @@ impl Context {
                 TyCheckErrors::new(
                     errs.into_iter()
                         .map(|e| {
+                            if e.core.code == Some(codes::CYCLIC_TYPE) {
+                                return e;
+                            }
                             TyCheckError::type_mismatch_error(
                                 self.cfg.input.clone(),
                                 line!() as usize,
@@ -1816,6 +2057,9 @@ impl Context {
                     TyCheckErrors::new(
                         errs.into_iter()
                             .map(|e| {
+                                if e.core.code == Some(codes::CYCLIC_TYPE) {
+                                    return e;
+                                }
                                 TyCheckError::type_mismatch_error(
                                     self.cfg.input.clone(),
                                     line!() as usize,
@@ -1914,10 +2158,195 @@ impl Context {
             .map_err(|(t, errs)| (Some(VarInfo { t, ..found.clone() }), errs))?;
         debug_assert!(res.has_no_qvar(), "{res} has qvar");
         log!(info "Params evaluated:\nres: {res}\n");
+        let res = self.resolve_kind_var(res, attr_name, pos_args, kw_args);
+        let res = self.refine_array_slice_t(obj, attr_name, pos_args, res);
         let res = VarInfo { t: res, ..found };
         Ok(res)
     }
 
+    /// Determines the kind of an effect-polymorphic subroutine from the arguments passed to its `~>` parameters.
+    /// e.g. `map(print!, xs)` is a procedure call, while `map(x -> x + 1, xs)` is a function call
+    fn resolve_kind_var(
+        &self,
+        mut callee_t: Type,
+        attr_name: &Option<Identifier>,
+        pos_args: &[hir::PosArg],
+        kw_args: &[hir::KwArg],
+    ) -> Type {
+        let Type::Subr(subr) = &mut callee_t else {
+            return callee_t;
+        };
+        if !subr.kind.is_var() {
+            return callee_t;
+        }
+        let is_kind_var = |pt: &ParamTy| {
+            matches!(pt.typ(), Type::Subr(SubrType { kind: SubrKind::Var, .. }))
+        };
+        let mut non_default_params = subr.non_default_params.iter();
+        if attr_name.is_some() && subr.self_t().is_some() {
+            non_default_params.next();
+        }
+        let pos_params = non_default_params
+            .chain(subr.default_params.iter())
+            .map(Some)
+            .chain(std::iter::repeat(subr.var_params.as_deref()));
+        let pos_effect = pos_args.iter().zip(pos_params).any(|(arg, param)| {
+            param.is_some_and(is_kind_var) && arg.expr.ref_t().is_procedure()
+        });
+        let kw_effect = kw_args.iter().any(|arg| {
+            subr.non_default_params
+                .iter()
+                .chain(subr.default_params.iter())
+                .find(|pt| pt.name() == Some(arg.keyword.inspect()))
+                .is_some_and(is_kind_var)
+                && arg.expr.ref_t().is_procedure()
+        });
+        let kind = if pos_effect || kw_effect {
+            SubrKind::Proc
+        } else {
+            SubrKind::Func
+        };
+        subr.resolve_kind_var(kind);
+        callee_t
+    }
+
+    /// `[T; N].__getitem__(i..<j): [T; j - i]` (if `i` and `j` are constants)
+    /// Otherwise, the length remains erased (`[T; _]`).
+    fn refine_array_slice_t(
+        &self,
+        obj: &hir::Expr,
+        attr_name: &Option<Identifier>,
+        pos_args: &[hir::PosArg],
+        mut subr_t: Type,
+    ) -> Type {
+        if attr_name.as_ref().map(|ident| &ident.inspect()[..]) != Some("__getitem__")
+            || &obj.ref_t().qual_name()[..] != "Array"
+        {
+            return subr_t;
+        }
+        let [hir::PosArg {
+            expr: hir::Expr::BinOp(range),
+        }] = pos_args
+        else {
+            return subr_t;
+        };
+        let Some(len) = self.eval_slice_len(&range.op, &range.lhs, &range.rhs, obj.ref_t()) else {
+            return subr_t;
+        };
+        if let Some(return_t) = subr_t.mut_return_t() {
+            if let Some(TyParam::Type(elem_t)) = return_t.typarams().first() {
+                *return_t = array_t(*elem_t.clone(), len);
+            }
+        }
+        subr_t
+    }
+
+    /// `i..<j` => `j - i`, `i..j` => `j - i + 1` (the stop is clamped to the length of the array)
+    fn eval_slice_len(
+        &self,
+        op: &Token,
+        start: &hir::Expr,
+        stop: &hir::Expr,
+        arr_t: &Type,
+    ) -> Option<TyParam> {
+        let one = || TyParam::value(1usize);
+        let nat = |tp: &TyParam| match tp {
+            TyParam::Value(ValueObj::Nat(n)) => Some(*n),
+            _ => None,
+        };
+        let start = start.ref_t().singleton_value()?.clone();
+        let stop = stop.ref_t().singleton_value()?.clone();
+        // negative indices are counted from the end
+        nat(&start)?;
+        nat(&stop)?;
+        let (start, stop) = match op.kind {
+            TokenKind::RightOpen => (start, stop),
+            TokenKind::Closed => (start, self.eval_bin_tp(OpKind::Add, stop, one()).ok()?),
+            TokenKind::LeftOpen => (
+                self.eval_bin_tp(OpKind::Add, start, one()).ok()?,
+                self.eval_bin_tp(OpKind::Add, stop, one()).ok()?,
+            ),
+            TokenKind::Open => (self.eval_bin_tp(OpKind::Add, start, one()).ok()?, stop),
+            _ => return None,
+        };
+        let len = arr_t.typarams().get(1).and_then(nat);
+        let clamp = |n: u64| len.map_or(n, |len| n.min(len));
+        let (start, stop) = (clamp(nat(&start)?), clamp(nat(&stop)?));
+        let len = self
+            .eval_bin_tp(OpKind::Sub, TyParam::value(stop), TyParam::value(start))
+            .ok()?;
+        match len {
+            TyParam::Value(ValueObj::Int(n)) => Some(TyParam::value(n.max(0) as u64)),
+            TyParam::Value(ValueObj::Nat(n)) => Some(TyParam::value(n)),
+            _ => None,
+        }
+    }
+
+    /// e.g. `X = arr[0]` (the body has been evaluated in `preregister_def`)
+    fn in_const_def_body(&self) -> bool {
+        self.kind == ContextKind::Instant
+            && self
+                .name
+                .rsplit([':', '.'])
+                .next()
+                .is_some_and(|name| name.starts_with(char::is_uppercase))
+    }
+
+    /// `[T; N].__getitem__(i: Int): T`
+    /// An error is reported if the bounds of the type of `i` exceed `0..<N`.
+    /// If `i` is not bounded (e.g. `i: Int`), the access is marked to be checked at runtime (`py_name` is `checked_getitem`).
+    /// Returns `None` if the call is not an array indexing with an integer.
+    fn get_array_index_call_t(
+        &self,
+        obj: &hir::Expr,
+        attr_name: &Identifier,
+        pos_args: &[hir::PosArg],
+        kw_args: &[hir::KwArg],
+        found: &VarInfo,
+    ) -> Option<SingleTyCheckResult<VarInfo>> {
+        if &attr_name.inspect()[..] != "__getitem__"
+            || &obj.ref_t().qual_name()[..] != "Array"
+            || !kw_args.is_empty()
+            // the index has been checked by the constant evaluator
+            || self.in_const_def_body()
+        {
+            return None;
+        }
+        let [hir::PosArg { expr: index }] = pos_args else {
+            return None;
+        };
+        let idx_t = index.ref_t();
+        // the type of `i` is not determined yet (e.g. a lambda parameter), so `i` is constrained by the normal signature
+        if idx_t.is_unbound_var() || !self.subtype_of(idx_t, &Type::Int) {
+            return None;
+        }
+        let arr_t = obj.ref_t();
+        let Some(TyParam::Type(elem_t)) = arr_t.typarams().first().cloned() else {
+            return None;
+        };
+        let len = arr_t.typarams().get(1)?.clone();
+        let t = fn1_met(arr_t.clone(), idx_t.clone(), *elem_t);
+        match self.index_in_bounds(idx_t, &len) {
+            Some(true) => Some(Ok(VarInfo { t, ..found.clone() })),
+            Some(false) => {
+                let err = TyCheckError::index_out_of_bounds_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    index.loc(),
+                    self.caused_by(),
+                    &len,
+                    idx_t,
+                );
+                Some(Err(err))
+            }
+            None => Some(Ok(VarInfo {
+                t,
+                py_name: Some(Str::ever("checked_getitem")),
+                ..found.clone()
+            })),
+        }
+    }
+
     pub(crate) fn get_const_local(
         &self,
         name: &Token,
@@ -2330,7 +2759,7 @@ impl Context {
                         return Some((t, ctx));
                     }
                 }
-                SubrKind::Proc => {
+                SubrKind::Proc | SubrKind::Var => {
                     if let Some((t, ctx)) = self
                         .get_builtins()
                         .unwrap_or(self)
@@ -2467,6 +2896,66 @@ impl Context {
         None
     }
 
+    /// Enumerates the trait implementations of `t`, including the ones inherited from the super classes
+    /// (if a trait is implemented by several classes, the one of the nearest class is returned).
+    /// The implementations are searched in the current and cached module contexts.
+    ///
+    /// Polymorphic types are matched nominally, e.g. the implementations of `Array(T, N)` are returned for `Array(Int, 3)`.
+    pub fn impls_of(&self, t: &Type) -> Vec<TraitImpl> {
+        let mut classes = vec![];
+        // the types defined without the module cache do not register their implementations to `trait_impls`
+        let mut declared = vec![];
+        let mut queue = std::collections::VecDeque::from([t.derefine()]);
+        while let Some(class) = queue.pop_front() {
+            let name = class.qual_name();
+            if classes.contains(&name) {
+                continue;
+            }
+            classes.push(name);
+            if let Some((class, ctx)) = self.get_nominal_type_ctx(&class) {
+                queue.extend(ctx.super_classes.iter().cloned());
+                for sup in ctx.super_traits.iter() {
+                    declared.push(TraitImpl::new(class.clone(), sup.clone()));
+                }
+            }
+        }
+        let mut impls_of_classes = vec![vec![]; classes.len()];
+        let mut push = |imp: TraitImpl| {
+            // e.g. `{I: Int | M <= I <= N} <: Add(...)` is not an implementation of `Int`
+            if imp.sub_type.is_refinement() && &imp.sub_type != t {
+                return;
+            }
+            if let Some(i) = classes
+                .iter()
+                .position(|name| name == &imp.sub_type.qual_name())
+            {
+                if !impls_of_classes[i].contains(&imp) {
+                    impls_of_classes[i].push(imp);
+                }
+            }
+        };
+        if let Some(shared) = self.shared.as_ref() {
+            for impls in shared.trait_impls.ref_inner().values() {
+                for imp in impls.iter() {
+                    push(imp.clone());
+                }
+            }
+        }
+        for imp in declared {
+            push(imp);
+        }
+        let mut res: Vec<TraitImpl> = vec![];
+        for mut impls in impls_of_classes {
+            impls.sort_by_cached_key(|imp| imp.sup_trait.to_string());
+            for imp in impls {
+                if res.iter().all(|r| r.sup_trait != imp.sup_trait) {
+                    res.push(imp);
+                }
+            }
+        }
+        res
+    }
+
     pub(crate) fn get_trait_impls(&self, trait_: &Type) -> Set<TraitImpl> {
         match trait_ {
             // And(Add, Sub) == intersection({Int <: Add(Int), Bool <: Add(Bool) ...}, {Int <: Sub(Int), ...})
@@ -2482,7 +2971,7 @@ impl Context {
                     let lti = l_impls.iter().find(|ti| &ti.sub_type == base).unwrap();
                     let rti = r_impls.iter().find(|ti| &ti.sub_type == base).unwrap();
                     let sup_trait = self.intersection(&lti.sup_trait, &rti.sup_trait);
-                    isec.insert(TraitImpl::new(lti.sub_type.clone(), sup_trait));
+                    isec.insert(TraitImpl::new(lti.sub_type.clone().into_inner(), sup_trait));
                 }
                 isec
             }
@@ -2539,14 +3028,58 @@ impl Context {
             return None;
         };
         if mod_t.is_erg_module() {
-            self.cfg.input.resolve_path(Path::new(&path[..]))
+            self.cfg.resolve_path(Path::new(&path[..]))
         } else if mod_t.is_py_module() {
-            self.cfg.input.resolve_decl_path(Path::new(&path[..]))
+            self.cfg.resolve_decl_path(Path::new(&path[..]))
         } else {
             None
         }
     }
 
+    /// The public interface of the module as a record type.
+    /// e.g. `Module("foo")` -> `{.x = Int; .f = (y: Int) -> Int}`
+    /// If the module `mod_t` imports the current module (transitively), returns the import cycle.
+    /// e.g. `b.er -> a.er -> b.er`
+    pub(crate) fn get_import_cycle(&self, mod_t: &Type) -> Option<String> {
+        let path = self.get_path_with_mod_t(mod_t)?;
+        let current = self.cfg.input.path()?;
+        let chain = self.shared.as_ref()?.graph.import_chain(&path, current)?;
+        if chain.len() < 2 {
+            return None;
+        }
+        let names = Some(current.file_name())
+            .into_iter()
+            .chain(chain.iter().map(|path| path.file_name()))
+            .map(|name| name.unwrap_or_default().to_string_lossy())
+            .collect::<Vec<_>>();
+        Some(names.join(" -> "))
+    }
+
+    /// If `vi` (the `VarInfo` of `name`) is re-exported from another module (e.g. `.x = foo.x`),
+    /// follows the chain of re-exports and returns the `VarInfo` of the original definition.
+    pub fn get_re_export_origin(&self, name: &str, vi: &VarInfo) -> Option<&VarInfo> {
+        let mod_ctx = self.get_mod_with_path(vi.def_loc.module.as_ref()?)?;
+        let (path, orig_name) = mod_ctx.re_exports.get(name)?;
+        let (_, orig) = self.get_mod_with_path(path)?.get_var_info(orig_name)?;
+        self.get_re_export_origin(orig_name, orig).or(Some(orig))
+    }
+
+    pub(crate) fn module_record_t(&self, mod_t: &Type) -> Option<Type> {
+        let mod_ctx = self.get_mod_with_t(mod_t)?;
+        let fields = mod_ctx
+            .local_dir()
+            .into_iter()
+            .filter(|(_, vi)| vi.vis.is_public())
+            .map(|(name, vi)| {
+                (
+                    Field::new(vi.vis.modifier.clone(), name.inspect().clone()),
+                    vi.t.clone(),
+                )
+            })
+            .collect();
+        Some(Type::Record(fields))
+    }
+
     // rec_get_const_localとは違い、位置情報を持たないしエラーとならない
     pub(crate) fn rec_get_const_obj(&self, name: &str) -> Option<&ValueObj> {
         #[cfg(feature = "py_compat")]
@@ -2611,7 +3144,7 @@ impl Context {
             str_namespace.push_str(namespaces.remove(0));
         }
         let path = Path::new(&str_namespace);
-        let mut path = self.cfg.input.resolve_path(path)?;
+        let mut path = self.cfg.resolve_path(path)?;
         for p in namespaces.into_iter() {
             path = Input::try_push_path(path, Path::new(p)).ok()?;
         }
@@ -2693,7 +3226,7 @@ impl Context {
         let name = self.erg_to_py_names.get(name).map_or(name, |s| &s[..]);
         if let Some((t, ctx)) = self.mono_types.get_mut(name) {
             Some((t, ctx))
-        } else if let Some(outer) = self.outer.as_mut() {
+        } else if let Some(outer) = self.outer.as_mut().and_then(OuterContext::get_mut) {
             // builtins cannot be got as mutable
             outer.rec_get_mut_mono_type(name)
         } else {
@@ -2706,7 +3239,7 @@ impl Context {
         let name = self.erg_to_py_names.get(name).map_or(name, |s| &s[..]);
         if let Some((t, ctx)) = self.poly_types.get_mut(name) {
             Some((t, ctx))
-        } else if let Some(outer) = self.outer.as_mut() {
+        } else if let Some(outer) = self.outer.as_mut().and_then(OuterContext::get_mut) {
             outer.rec_get_mut_poly_type(name)
         } else {
             None
@@ -2720,7 +3253,7 @@ impl Context {
             Some((t, ctx))
         } else if let Some((t, ctx)) = self.poly_types.get_mut(name) {
             Some((t, ctx))
-        } else if let Some(outer) = self.outer.as_mut() {
+        } else if let Some(outer) = self.outer.as_mut().and_then(OuterContext::get_mut) {
             outer.rec_get_mut_type(name)
         } else {
             None
@@ -2947,12 +3480,17 @@ impl Context {
         match lhs {
             Type::FreeVar(fv) => {
                 if let Some(sup) = fv.get_super() {
-                    if self.is_trait(&sup) {
+                    // to prevent infinite recursion
+                    // e.g. ?T(<: Add(?R)).Output where ?U(:> ?T.Output) appears in the candidates of `Add`
+                    fv.dummy_link();
+                    let candidates = if self.is_trait(&sup) {
                         self.get_trait_proj_candidates(&sup, rhs)
                     } else {
                         self.eval_proj(sup, rhs.clone(), self.level, &())
                             .map_or(set! {}, |t| set! {t})
-                    }
+                    };
+                    fv.undo();
+                    candidates
                 } else {
                     set! {}
                 }
@@ -2966,7 +3504,7 @@ impl Context {
         let impls = self.get_trait_impls(trait_);
         let candidates = impls.into_iter().filter_map(move |imp| {
             if self.supertype_of(&imp.sup_trait, trait_) {
-                self.eval_t_params(proj(imp.sub_type, rhs), self.level, &())
+                self.eval_t_params(proj(imp.sub_type.into_inner(), rhs), self.level, &())
                     .ok()
             } else {
                 None
@@ -4,6 +4,7 @@ use std::option::Option; // conflicting to Type::Option
 
 use erg_common::dict::Dict;
 use erg_common::enum_unwrap;
+use erg_common::error::Location;
 #[allow(unused)]
 use erg_common::log;
 use erg_common::set::Set;
@@ -629,6 +630,86 @@ impl Context {
         }
     }
 
+    /// Instantiates `quantified` with the explicitly applied type arguments.
+    /// e.g. `id|Int|`, `id|T := Int|`: `|T|(x: T) -> T` => `(x: Int) -> Int`
+    ///
+    /// Positional type arguments are allowed only if the type has exactly one type variable,
+    /// since the order of the type variables is not determined.
+    pub(crate) fn instantiate_with_type_args(
+        &self,
+        quantified: Type,
+        pos_args: &[(Type, Location)],
+        kw_args: &[(Str, Type, Location)],
+        callee: &hir::Expr,
+    ) -> TyCheckResult<Type> {
+        let quant = match quantified {
+            FreeVar(fv) if fv.is_linked() => {
+                let t = fv.crack().clone();
+                return self.instantiate_with_type_args(t, pos_args, kw_args, callee);
+            }
+            Quantified(quant) => *quant,
+            Refinement(refine) if refine.t.is_quantified_subr() => {
+                *enum_unwrap!(*refine.t, Type::Quantified)
+            }
+            other => {
+                return Err(TyCheckErrors::from(TyCheckError::not_polymorphic_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    callee.loc(),
+                    self.caused_by(),
+                    &other,
+                )));
+            }
+        };
+        let mut tmp_tv_cache = TyVarCache::new(self.level, self);
+        let ty = self.instantiate_t_inner(quant, &mut tmp_tv_cache, callee)?;
+        if let Some(self_t) = ty.self_t() {
+            self.sub_unify(callee.ref_t(), self_t, callee, Some(&Str::ever("self")))?;
+        }
+        let params = tmp_tv_cache
+            .tyvar_instances
+            .keys()
+            .map(|name| name.inspect().clone())
+            .collect::<Vec<_>>();
+        if !pos_args.is_empty() && (params.len() != 1 || pos_args.len() > 1) {
+            let locs = pos_args.iter().map(|(_, loc)| *loc).collect::<Vec<_>>();
+            return Err(TyCheckErrors::from(
+                TyCheckError::ambiguous_type_args_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    Location::stream(&locs),
+                    self.caused_by(),
+                    &ty,
+                    &params,
+                ),
+            ));
+        }
+        let args = pos_args
+            .iter()
+            .map(|(t, loc)| (&params[0], t, loc))
+            .chain(kw_args.iter().map(|(name, t, loc)| (name, t, loc)));
+        for (name, arg_t, loc) in args {
+            let tv = if let Some(tv) = tmp_tv_cache.get_tyvar(name) {
+                tv
+            } else {
+                return Err(TyCheckErrors::from(TyCheckError::tyvar_not_defined_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    name,
+                    *loc,
+                    self.caused_by(),
+                )));
+            };
+            // ?T(<: Eq) := Int
+            self.sub_unify(arg_t, tv, loc, Some(name))?;
+            self.sub_unify(tv, arg_t, loc, Some(name))?;
+        }
+        if cfg!(feature = "debug") && ty.has_qvar() {
+            panic!("{ty} has qvar")
+        }
+        Ok(ty)
+    }
+
     pub(crate) fn instantiate_dummy(&self, quantified: Type) -> TyCheckResult<Type> {
         match quantified {
             FreeVar(fv) if fv.is_linked() => self.instantiate_dummy(fv.crack().clone()),
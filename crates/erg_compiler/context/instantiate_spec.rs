@@ -56,6 +56,8 @@ pub fn token_kind_to_op_kind(kind: TokenKind) -> Option<OpKind> {
         TokenKind::BitXor => Some(OpKind::BitXor),
         TokenKind::Shl => Some(OpKind::Shl),
         TokenKind::Shr => Some(OpKind::Shr),
+        TokenKind::InOp => Some(OpKind::In),
+        TokenKind::NotInOp => Some(OpKind::NotIn),
         _ => None,
     }
 }
@@ -204,7 +206,8 @@ impl Context {
         mode: RegistrationMode,
     ) -> TyCheckResult<Type> {
         let mut tmp_tv_cache = TyVarCache::new(self.level, self);
-        let spec_t = if let Some(t_spec) = t_spec {
+        // `x: _ = ...` is inferred (see `ASTLowerer::warn_type_holes`)
+        let spec_t = if let Some(t_spec) = t_spec.filter(|t_spec| !t_spec.is_hole()) {
             self.instantiate_typespec_full(t_spec, None, &mut tmp_tv_cache, mode, false)?
         } else {
             free_var(self.level, Constraint::new_type_of(Type))
@@ -302,7 +305,11 @@ impl Context {
                 }
             }
         }
-        let spec_return_t = if let Some(t_spec) = sig.return_t_spec.as_ref() {
+        let spec_return_t = if let Some(t_spec) = sig
+            .return_t_spec
+            .as_ref()
+            .filter(|t_spec| !t_spec.t_spec.is_hole())
+        {
             let opt_decl_t = opt_decl_sig_t
                 .as_ref()
                 .map(|subr| ParamTy::Pos(subr.return_t.as_ref().clone()));
@@ -469,12 +476,7 @@ impl Context {
                         tmp_tv_cache,
                         not_found_is_qvar,
                     ) {
-                        return self.eval_proj(
-                            receiver_t,
-                            t.inspect().clone(),
-                            self.level,
-                            predecl,
-                        );
+                        return self.eval_proj(receiver_t, t.inspect().clone(), self.level, t);
                     }
                 }
                 let ctxs = self.get_singular_ctxs(namespace.as_ref(), self)?;
@@ -540,7 +542,16 @@ impl Context {
                 if let Some(TyParam::Type(t)) = self.get_tp_from_tv_cache(other, tmp_tv_cache) {
                     return Ok(*t);
                 }
-                if let Some(outer) = &self.outer {
+                // `R = {.x = Int}`: `R` is the record type `{.x = Int}` (not the nominal type `Record`)
+                if let Some(typ) = self
+                    .rec_get_const_obj(other)
+                    .and_then(|obj| obj.as_type(self))
+                {
+                    if typ.typ().is_record() {
+                        return Ok(typ.into_typ());
+                    }
+                }
+                if let Some(outer) = self.get_outer() {
                     if let Ok(t) =
                         outer.instantiate_mono_t(ident, opt_decl_t, tmp_tv_cache, not_found_is_qvar)
                     {
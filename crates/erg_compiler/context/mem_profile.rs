@@ -0,0 +1,152 @@
+//! Memory usage instrumentation of `Context` (the `mem_profile` feature).
+//!
+//! `Context` is cloned heavily (e.g. `pop` copies the outer context if it is shared with a clone).
+//! With the `mem_profile` feature, every clone is counted per context name with the approximate size of the cloned data,
+//! and `report` lists the contexts that cost the most.
+//! The sizes are approximate: only the tables (`Dict`s and `Vec`s) of the context are measured, not the heap data of their elements.
+use std::fmt::Write as _;
+use std::mem::size_of;
+use std::sync::Mutex;
+
+use erg_common::consts::MEM_PROFILE;
+use erg_common::dict::Dict;
+use erg_common::Str;
+
+use super::Context;
+
+/// The approximate size of a table of a context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableSize {
+    pub field: &'static str,
+    pub len: usize,
+    /// bytes of the entries, excluding the nested contexts
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CloneStat {
+    pub count: usize,
+    /// total bytes cloned (see `Context::own_size`)
+    pub bytes: usize,
+}
+
+static CLONE_STATS: Mutex<Vec<(Str, CloneStat)>> = Mutex::new(vec![]);
+
+fn dict_size<K, V>(field: &'static str, dict: &Dict<K, V>) -> TableSize {
+    TableSize {
+        field,
+        len: dict.len(),
+        bytes: dict.capacity() * (size_of::<K>() + size_of::<V>()),
+    }
+}
+
+fn vec_size<T>(field: &'static str, vec: &[T]) -> TableSize {
+    TableSize {
+        field,
+        len: vec.len(),
+        bytes: std::mem::size_of_val(vec),
+    }
+}
+
+impl Context {
+    /// The approximate sizes of the tables of this context (largest first).
+    /// The nested contexts (e.g. the contexts of the types defined here) are not included.
+    pub fn table_sizes(&self) -> Vec<TableSize> {
+        let mut sizes = vec![
+            dict_size("const_param_defaults", &self.const_param_defaults),
+            dict_size("method_to_traits", &self.method_to_traits),
+            dict_size("method_to_classes", &self.method_to_classes),
+            dict_size("method_impl_patches", &self.method_impl_patches),
+            dict_size("decls", &self.decls),
+            dict_size("future_defined_locals", &self.future_defined_locals),
+            dict_size("deleted_locals", &self.deleted_locals),
+            dict_size("locals", &self.locals),
+            dict_size("consts", &self.consts),
+            dict_size("mono_types", &self.mono_types),
+            dict_size("poly_types", &self.poly_types),
+            dict_size("patches", &self.patches),
+            dict_size("erg_to_py_names", &self.erg_to_py_names),
+            dict_size("re_exports", &self.re_exports),
+            vec_size("preds", &self.preds),
+            vec_size("super_classes", &self.super_classes),
+            vec_size("super_traits", &self.super_traits),
+            vec_size("methods_list", &self.methods_list),
+            vec_size("params", &self.params),
+            vec_size("params_spec", &self.params_spec),
+            vec_size("guards", &self.guards),
+        ];
+        sizes.sort_by_key(|size| std::cmp::Reverse(size.bytes));
+        sizes
+    }
+
+    /// The approximate size of this context, excluding the nested contexts and the outer context.
+    pub fn own_size(&self) -> usize {
+        size_of::<Self>() + self.table_sizes().iter().map(|s| s.bytes).sum::<usize>()
+    }
+
+    /// The approximate size of this context, including the nested contexts and the outer contexts.
+    /// This is the size copied by `clone`.
+    pub fn approx_size(&self) -> usize {
+        let nested = self
+            .mono_types
+            .values()
+            .chain(self.poly_types.values())
+            .map(|(_, ctx)| ctx)
+            .chain(self.patches.values())
+            .chain(self.methods_list.iter().map(|(_, ctx)| ctx))
+            .map(|ctx| ctx.approx_size())
+            .sum::<usize>();
+        let outer = self.get_outer().map_or(0, |outer| outer.approx_size());
+        self.own_size() + nested + outer
+    }
+}
+
+/// Called by `Context::clone`. Does nothing unless the `mem_profile` feature is enabled.
+#[inline]
+pub(crate) fn record_clone(ctx: &Context) {
+    if !MEM_PROFILE {
+        return;
+    }
+    let bytes = ctx.own_size();
+    let mut stats = CLONE_STATS.lock().unwrap();
+    if let Some((_, stat)) = stats.iter_mut().find(|(name, _)| name == &ctx.name) {
+        stat.count += 1;
+        stat.bytes += bytes;
+    } else {
+        stats.push((ctx.name.clone(), CloneStat { count: 1, bytes }));
+    }
+}
+
+/// The clone statistics per context name (most bytes cloned first).
+pub fn clone_stats() -> Vec<(Str, CloneStat)> {
+    let mut stats = CLONE_STATS.lock().unwrap().clone();
+    stats.sort_by_key(|(_, stat)| std::cmp::Reverse(stat.bytes));
+    stats
+}
+
+pub fn clear_clone_stats() {
+    CLONE_STATS.lock().unwrap().clear();
+}
+
+/// Lists the `top` contexts that cost the most by cloning.
+pub fn report(top: usize) -> String {
+    let stats = clone_stats();
+    let total = stats.iter().map(|(_, stat)| stat.bytes).sum::<usize>();
+    let mut report = format!(
+        "{} contexts cloned {} times ({} KiB in total)\n",
+        stats.len(),
+        stats.iter().map(|(_, stat)| stat.count).sum::<usize>(),
+        total / 1024
+    );
+    let _ = writeln!(report, "{:<32} {:>10} {:>12}", "context", "clones", "KiB");
+    for (name, stat) in stats.iter().take(top) {
+        let _ = writeln!(
+            report,
+            "{:<32} {:>10} {:>12}",
+            &name[..],
+            stat.count,
+            stat.bytes / 1024
+        );
+    }
+    report
+}
@@ -3,6 +3,7 @@
 //! `Context` is used for type inference and type checking.
 #![allow(clippy::result_unit_err)]
 pub mod compare;
+pub mod dump;
 pub mod eval;
 pub mod generalize;
 pub mod hint;
@@ -10,6 +11,7 @@ pub mod initialize;
 pub mod inquire;
 pub mod instantiate;
 pub mod instantiate_spec;
+pub mod mem_profile;
 pub mod register;
 pub mod test;
 pub mod unify;
@@ -18,6 +20,7 @@ use std::fmt;
 use std::mem;
 use std::option::Option; // conflicting to Type::Option
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use erg_common::config::ErgConfig;
 use erg_common::consts::PYTHON_MODE;
@@ -31,6 +34,7 @@ use erg_common::{fmt_option, fn_name, get_hash, log};
 use ast::{DefId, DefKind, VarName};
 use erg_parser::ast;
 use erg_parser::ast::Def;
+use erg_parser::feature::Features;
 use erg_parser::token::Token;
 
 use crate::context::instantiate::TyVarCache;
@@ -40,6 +44,7 @@ use crate::module::SharedModuleGraph;
 use crate::module::{
     SharedCompilerResource, SharedModuleCache, SharedModuleIndex, SharedPromises, SharedTraitImpls,
 };
+use crate::ty::intern::Interned;
 use crate::ty::value::ValueObj;
 use crate::ty::GuardType;
 use crate::ty::ParamTy;
@@ -88,6 +93,9 @@ impl ControlKind {
     pub const fn is_if(&self) -> bool {
         matches!(self, Self::If)
     }
+    pub const fn is_match(&self) -> bool {
+        matches!(self, Self::Match)
+    }
     /// if | if! | while!
     pub const fn is_conditional(&self) -> bool {
         matches!(self, Self::If | Self::While)
@@ -99,8 +107,9 @@ impl ControlKind {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TraitImpl {
-    pub sub_type: Type,
-    pub sup_trait: Type,
+    // interned, because the trait implementations are hashed and compared frequently in trait lookup
+    pub sub_type: Interned<Type>,
+    pub sup_trait: Interned<Type>,
 }
 
 impl std::fmt::Display for TraitImpl {
@@ -110,10 +119,10 @@ impl std::fmt::Display for TraitImpl {
 }
 
 impl TraitImpl {
-    pub const fn new(sub_type: Type, sup_trait: Type) -> Self {
+    pub fn new(sub_type: Type, sup_trait: Type) -> Self {
         Self {
-            sub_type,
-            sup_trait,
+            sub_type: sub_type.intern(),
+            sup_trait: sup_trait.intern(),
         }
     }
 }
@@ -416,17 +425,87 @@ impl MethodPair {
     }
 }
 
+/// The parent scope of a context.
+#[derive(Debug)]
+pub(crate) enum OuterContext {
+    /// shared with the clones of the context, so cloning does not copy the whole scope chain
+    Owned(Arc<Context>),
+    /// the context an instant frame is created in (see `Context::with_instant_frame`)
+    Borrowed(BorrowedContext),
+}
+
+impl Clone for OuterContext {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Owned(outer) => Self::Owned(outer.clone()),
+            // the clone may outlive the borrow, so it owns a copy
+            Self::Borrowed(outer) => Self::Owned(Arc::new(outer.ctx.clone())),
+        }
+    }
+}
+
+impl OuterContext {
+    pub(crate) fn get(&self) -> &Context {
+        match self {
+            Self::Owned(outer) => outer,
+            Self::Borrowed(outer) => outer.ctx,
+        }
+    }
+
+    /// If the outer context is shared with a clone, it is copied (copy-on-write).
+    /// A borrowed context cannot be modified.
+    pub(crate) fn get_mut(&mut self) -> Option<&mut Context> {
+        match self {
+            Self::Owned(outer) => Some(Arc::make_mut(outer)),
+            Self::Borrowed(_) => None,
+        }
+    }
+}
+
+/// A read-only reference to a context that is alive while the `Lease` is held by `Context::with_instant_frame`.
+#[derive(Debug)]
+pub(crate) struct BorrowedContext {
+    ctx: &'static Context,
+    /// counted by `Lease`
+    _lease: Arc<()>,
+}
+
+/// Held by `Context::with_instant_frame` while the borrowed context is alive.
+/// If a `BorrowedContext` is still alive when the borrow ends (i.e. it has been moved out of the frame),
+/// the process is aborted rather than leaving a dangling reference.
+struct Lease(Arc<()>);
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.0) > 1 {
+            log!(err "a borrowed context outlives its instant frame");
+            std::process::abort();
+        }
+    }
+}
+
+impl Lease {
+    fn borrow(&self, ctx: &Context) -> BorrowedContext {
+        // SAFETY: `ctx` outlives `self`, and `self` is not dropped while the returned reference is alive (see `Drop for Lease`)
+        let ctx = unsafe { &*(ctx as *const Context) };
+        BorrowedContext {
+            ctx,
+            _lease: self.0.clone(),
+        }
+    }
+}
+
 /// Represents the context of the current scope
 ///
 /// Recursive functions/methods are highlighted with the prefix `rec_`, as performance may be significantly degraded.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Context {
     pub name: Str,
     pub kind: ContextKind,
     pub(crate) cfg: ErgConfig,
     pub(crate) preds: Vec<Predicate>,
     /// for looking up the parent scope
-    pub(crate) outer: Option<Box<Context>>,
+    pub(crate) outer: Option<OuterContext>,
     // e.g. { "Add": [ConstObjTemplate::App("Self", vec![])])
     pub(crate) const_param_defaults: Dict<Str, Vec<ConstTemplate>>,
     // Superclasses/supertraits by a patch are not included here
@@ -457,7 +536,8 @@ pub struct Context {
     /// ```
     /// => params: vec![(None, [T; 2]), (Some("z"), U)]
     /// => locals: {"x": T, "y": T}
-    /// TODO: impl params desugaring and replace to `Dict`
+    /// (nested patterns are desugared into the definitions of `locals`)
+    /// TODO: replace to `Dict`
     pub(crate) params: Vec<(Option<VarName>, VarInfo)>,
     pub(crate) params_spec: Vec<ParamSpec>,
     pub(crate) locals: Dict<VarName, VarInfo>,
@@ -476,9 +556,50 @@ pub struct Context {
     pub(crate) higher_order_caller: Vec<Str>,
     pub(crate) guards: Vec<GuardType>,
     pub(crate) erg_to_py_names: Dict<Str, Str>,
+    /// K: re-exported (public) name, V: (path of the source module, name in the source module)
+    /// e.g. `.x = foo.x` => {"x": ("foo.er", "x")}
+    pub(crate) re_exports: Dict<VarName, (PathBuf, Str)>,
     pub(crate) level: usize,
 }
 
+// not derived, to count the clones (see `mem_profile`)
+impl Clone for Context {
+    fn clone(&self) -> Self {
+        mem_profile::record_clone(self);
+        Self {
+            name: self.name.clone(),
+            kind: self.kind.clone(),
+            cfg: self.cfg.clone(),
+            preds: self.preds.clone(),
+            outer: self.outer.clone(),
+            const_param_defaults: self.const_param_defaults.clone(),
+            super_classes: self.super_classes.clone(),
+            super_traits: self.super_traits.clone(),
+            methods_list: self.methods_list.clone(),
+            method_to_traits: self.method_to_traits.clone(),
+            method_to_classes: self.method_to_classes.clone(),
+            method_impl_patches: self.method_impl_patches.clone(),
+            decls: self.decls.clone(),
+            future_defined_locals: self.future_defined_locals.clone(),
+            deleted_locals: self.deleted_locals.clone(),
+            params: self.params.clone(),
+            params_spec: self.params_spec.clone(),
+            locals: self.locals.clone(),
+            consts: self.consts.clone(),
+            mono_types: self.mono_types.clone(),
+            poly_types: self.poly_types.clone(),
+            patches: self.patches.clone(),
+            shared: self.shared.clone(),
+            tv_cache: self.tv_cache.clone(),
+            higher_order_caller: self.higher_order_caller.clone(),
+            guards: self.guards.clone(),
+            erg_to_py_names: self.erg_to_py_names.clone(),
+            re_exports: self.re_exports.clone(),
+            level: self.level,
+        }
+    }
+}
+
 impl Default for Context {
     #[inline]
     fn default() -> Self {
@@ -505,13 +626,7 @@ impl fmt::Display for Context {
 
 impl ContextProvider for Context {
     fn dir(&self) -> Dict<&VarName, &VarInfo> {
-        let mut vars = self.type_dir(self);
-        if let Some(outer) = self.get_outer() {
-            vars.guaranteed_extend(outer.dir());
-        } else if let Some(builtins) = self.get_builtins() {
-            vars.guaranteed_extend(builtins.locals.iter());
-        }
-        vars
+        self.dir_info().vars
     }
 
     fn get_receiver_ctx(&self, receiver_name: &str) -> Option<&Context> {
@@ -532,11 +647,69 @@ impl ContextProvider for Context {
     }
 }
 
+/// The result of `Context::dir_info`.
+#[derive(Debug)]
+pub struct DirInfo<'c> {
+    pub vars: Dict<&'c VarName, &'c VarInfo>,
+    /// `false` if the builtin variables are not included because the builtin module is not available
+    /// (e.g. the context is constructed without a module cache)
+    pub builtins_available: bool,
+}
+
 impl Context {
     pub fn dir(&self) -> Dict<&VarName, &VarInfo> {
         ContextProvider::dir(self)
     }
 
+    /// Same as `Context::dir`, but also reports whether the builtin variables could be included.
+    pub fn dir_info(&self) -> DirInfo<'_> {
+        let mut vars = self.type_dir(self);
+        let builtins_available = if let Some(outer) = self.get_outer() {
+            let outer = outer.dir_info();
+            vars.guaranteed_extend(outer.vars);
+            outer.builtins_available
+        } else if let Some(builtins) = self.get_builtins() {
+            vars.guaranteed_extend(builtins.locals.iter());
+            true
+        } else {
+            self.is_builtins()
+        };
+        DirInfo {
+            vars,
+            builtins_available,
+        }
+    }
+
+    /// Same as `Context::dir`, but returns only the variables accessible from `caller`.
+    /// Private variables of other modules and classes are excluded,
+    /// so this is suitable for completion.
+    pub fn dir_from(&self, caller: &Context) -> Dict<&VarName, &VarInfo> {
+        self.dir()
+            .into_iter()
+            .filter(|(_, vi)| caller.is_accessible(vi))
+            .collect()
+    }
+
+    /// Same as `Context::local_dir`, but returns only the variables accessible from `caller`.
+    pub fn local_dir_from(&self, caller: &Context) -> Dict<&VarName, &VarInfo> {
+        self.local_dir()
+            .into_iter()
+            .filter(|(_, vi)| caller.is_accessible(vi))
+            .collect()
+    }
+
+    /// Whether the variable can be referred from this context (without `::` or `.`).
+    pub fn is_accessible(&self, vi: &VarInfo) -> bool {
+        if !vi.vis.compatible(&ast::AccessModifier::Auto, self) {
+            return false;
+        }
+        // the namespaces of different modules may have the same name (e.g. `<module>`)
+        vi.vis.is_public()
+            || &vi.vis.def_namespace[..] == "<builtins>"
+            || vi.def_loc.module.is_none()
+            || vi.def_loc.module.as_deref() == self.module_path()
+    }
+
     pub fn get_receiver_ctx(&self, receiver_name: &str) -> Option<&Context> {
         ContextProvider::get_receiver_ctx(self, receiver_name)
     }
@@ -570,6 +743,7 @@ impl Context {
         self.poly_types.remove(name);
         self.patches.remove(name);
         self.erg_to_py_names.remove(name);
+        self.re_exports.remove(name);
         self.locals
             .remove(name)
             .or_else(|| self.locals.remove(name))
@@ -652,7 +826,7 @@ impl Context {
             cfg,
             kind,
             preds: vec![],
-            outer: outer.map(Box::new),
+            outer: outer.map(|outer| OuterContext::Owned(Arc::new(outer))),
             super_classes: vec![],
             super_traits: vec![],
             methods_list: vec![],
@@ -675,6 +849,7 @@ impl Context {
             higher_order_caller: vec![],
             guards: vec![],
             erg_to_py_names: Dict::default(),
+            re_exports: Dict::default(),
             level,
         }
     }
@@ -976,6 +1151,30 @@ impl Context {
         )
     }
 
+    /// Calls `f` with an instant context whose outer context is `self`.
+    /// `self` is borrowed, not cloned, and cannot be modified through the instant context.
+    pub(crate) fn with_instant_frame<T>(
+        &self,
+        name: Str,
+        capacity: usize,
+        f: impl FnOnce(&mut Context) -> T,
+    ) -> T {
+        // dropped after `frame`
+        let lease = Lease(Arc::new(()));
+        let mut frame = Self::with_capacity(
+            name,
+            self.cfg.clone(),
+            ContextKind::Instant,
+            vec![],
+            None,
+            self.shared.clone(),
+            capacity,
+            Self::TOP_LEVEL,
+        );
+        frame.outer = Some(OuterContext::Borrowed(lease.borrow(self)));
+        f(&mut frame)
+    }
+
     pub(crate) fn module_path(&self) -> Option<&Path> {
         self.cfg.input.path()
     }
@@ -990,11 +1189,13 @@ impl Context {
     }
 
     pub(crate) fn get_outer(&self) -> Option<&Context> {
-        self.outer.as_ref().map(|x| x.as_ref())
+        self.outer.as_ref().map(OuterContext::get)
     }
 
+    /// If the outer context is shared with a clone of this context, it is copied (copy-on-write).
+    /// Returns `None` if the outer context is borrowed by an instant context.
     pub(crate) fn get_mut_outer(&mut self) -> Option<&mut Context> {
-        self.outer.as_mut().map(|x| x.as_mut())
+        self.outer.as_mut().and_then(OuterContext::get_mut)
     }
 
     pub(crate) fn impl_of(&self) -> Option<Type> {
@@ -1016,19 +1217,19 @@ impl Context {
         }
     }
 
+    fn is_builtins(&self) -> bool {
+        self.kind == ContextKind::Module && &self.path()[..] == "<builtins>"
+    }
+
     /// Returns None if self is `<builtins>`.
     /// This avoids infinite loops.
+    /// Also returns None if the builtin module is not available (e.g. the context is constructed without a module cache).
     pub(crate) fn get_builtins(&self) -> Option<&Context> {
         // builtins中で定義した型等はmod_cacheがNoneになっている
-        if self.kind != ContextKind::Module || &self.path()[..] != "<builtins>" {
+        if !self.is_builtins() {
             self.shared
                 .as_ref()
-                .map(|shared| {
-                    shared
-                        .mod_cache
-                        .raw_ref_ctx(Path::new("<builtins>"))
-                        .unwrap()
-                })
+                .and_then(|shared| shared.mod_cache.raw_ref_ctx(Path::new("<builtins>")))
                 .map(|mod_ctx| &mod_ctx.context)
         } else {
             None
@@ -1078,6 +1279,15 @@ impl Context {
         );
     }
 
+    /// `x` => `{self.name}.x` (public) or `{self.name}::x` (private)
+    pub(crate) fn mangle_name(&self, name: &str, vis: &VisibilityModifier) -> String {
+        if vis.is_public() {
+            format!("{parent}.{name}", parent = self.name)
+        } else {
+            format!("{parent}::{name}", parent = self.name)
+        }
+    }
+
     pub(crate) fn grow(
         &mut self,
         name: &str,
@@ -1085,13 +1295,9 @@ impl Context {
         vis: VisibilityModifier,
         tv_cache: Option<TyVarCache>,
     ) {
-        let name = if vis.is_public() {
-            format!("{parent}.{name}", parent = self.name)
-        } else {
-            format!("{parent}::{name}", parent = self.name)
-        };
+        let name = self.mangle_name(name, &vis);
         log!(info "{}: current namespace: {name}", fn_name!());
-        self.outer = Some(Box::new(mem::take(self)));
+        self.outer = Some(OuterContext::Owned(Arc::new(mem::take(self))));
         if let Some(tv_cache) = tv_cache.as_ref() {
             self.assign_bounds(tv_cache)
         };
@@ -1109,10 +1315,10 @@ impl Context {
     }
 
     pub fn pop(&mut self) -> Context {
-        if let Some(parent) = self.outer.as_mut() {
+        if let Some(parent) = self.get_mut_outer() {
             let parent = mem::take(parent);
             let ctx = mem::take(self);
-            *self = *parent;
+            *self = parent;
             log!(info "{}: current namespace: {}", fn_name!(), self.name);
             ctx
         } else {
@@ -1242,6 +1448,8 @@ impl Context {
 pub struct ModuleContext {
     pub context: Context,
     pub scope: Dict<Str, Context>,
+    /// unstable features enabled by `#!feature(...)`
+    pub features: Features,
 }
 
 impl ModuleContext {
@@ -1249,6 +1457,7 @@ impl ModuleContext {
         Self {
             context: toplevel,
             scope,
+            features: Features::empty(),
         }
     }
 
@@ -4,21 +4,22 @@ use std::io::{BufRead, BufReader};
 use std::option::Option;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Arc;
 use std::thread::sleep;
 use std::time::{Duration, SystemTime};
 
-use erg_common::config::ErgMode;
+use erg_common::config::{ErgConfig, ErgMode, ModuleRoot};
 use erg_common::consts::{ERG_MODE, PYTHON_MODE};
 use erg_common::dict::Dict;
-use erg_common::env::{is_pystd_main_module, is_std_decl_path};
+use erg_common::env::{case_sensitive_paths, is_pystd_main_module, is_std_decl_path};
 use erg_common::erg_util::BUILTIN_ERG_MODS;
 use erg_common::io::Input;
 use erg_common::levenshtein::get_similar_name;
-use erg_common::pathutil::{DirKind, FileKind};
-use erg_common::python_util::BUILTIN_PYTHON_MODS;
+use erg_common::pathutil::{access_error, DirKind, FileKind};
+use erg_common::python_util::{is_extension_module, opt_which_python, BUILTIN_PYTHON_MODS};
 use erg_common::set::Set;
 use erg_common::spawn::spawn_new_thread;
-use erg_common::traits::{Locational, Stream};
+use erg_common::traits::{Locational, Runnable, Stream};
 use erg_common::triple::Triple;
 use erg_common::{dict, get_hash, log, set, unique_in_place, Str};
 
@@ -27,9 +28,11 @@ use ast::{
     VarName,
 };
 use erg_parser::ast;
+use erg_parser::build_ast::ASTBuilder;
 
 use crate::ty::constructors::{
-    free_var, func, func0, func1, proc, ref_, ref_mut, tp_enum, unknown_len_array_t, v_enum,
+    fn0_met, fn1_met, free_var, func, func0, func1, mono, or, proc, ref_, ref_mut, tp_enum,
+    unknown_len_array_t, v_enum,
 };
 use crate::ty::free::{Constraint, HasLevel};
 use crate::ty::typaram::TyParam;
@@ -44,7 +47,10 @@ use crate::error::readable_name;
 use crate::error::{
     CompileError, CompileErrors, CompileResult, TyCheckError, TyCheckErrors, TyCheckResult,
 };
-use crate::hir::Literal;
+use crate::hir::{Literal, HIR};
+use crate::module::{DeferredModule, ParseResult, SharedCompilerResource, SharedModuleCache};
+use crate::signature::SignatureExtractor;
+use crate::stub::PyiConverter;
 use crate::varinfo::{AbsLocation, Mutability, VarInfo, VarKind};
 use crate::{feature_error, hir};
 use Mutability::*;
@@ -992,7 +998,7 @@ impl Context {
                     let tv_cache = self.instantiate_ty_bounds(&sig.bounds, PreRegister)?;
                     let vis = self.instantiate_vis_modifier(sig.vis())?;
                     self.grow(__name__, ContextKind::Proc, vis, Some(tv_cache));
-                    let (obj, const_t) = match self.eval_const_block(&def.body.block) {
+                    let (obj, const_t) = match self.eval_const_def_body(&def.body.block) {
                         Ok(obj) => (obj.clone(), v_enum(set! {obj})),
                         Err(errs) => {
                             self.pop();
@@ -1034,7 +1040,7 @@ impl Context {
                     let kind = ContextKind::from(def);
                     let vis = self.instantiate_vis_modifier(sig.vis())?;
                     self.grow(__name__, kind, vis, None);
-                    let (obj, const_t) = match self.eval_const_block(&def.body.block) {
+                    let (obj, const_t) = match self.eval_const_def_body(&def.body.block) {
                         Ok(obj) => (obj.clone(), v_enum(set! {obj})),
                         Err(errs) => {
                             self.pop();
@@ -1302,7 +1308,10 @@ impl Context {
                         self.level,
                     );
                     self.gen_class_new_method(&gen, &mut ctx)?;
-                    self.register_gen_mono_type(ident, gen, ctx, Const)
+                    // the class is registered even if some traits cannot be derived
+                    let derived = self.gen_class_derived_impls(ident, &gen, &mut ctx);
+                    self.register_gen_mono_type(ident, gen, ctx, Const)?;
+                    derived
                 } else {
                     let params = gen
                         .typ()
@@ -1319,7 +1328,10 @@ impl Context {
                         self.level,
                     );
                     self.gen_class_new_method(&gen, &mut ctx)?;
-                    self.register_gen_poly_type(ident, gen, ctx, Const)
+                    // the class is registered even if some traits cannot be derived
+                    let derived = self.gen_class_derived_impls(ident, &gen, &mut ctx);
+                    self.register_gen_poly_type(ident, gen, ctx, Const)?;
+                    derived
                 }
             }
             GenTypeObj::Subclass(_) => {
@@ -1584,6 +1596,109 @@ impl Context {
         Ok(())
     }
 
+    /// `C = Class {x = Int}, Derive := Eq and Show`
+    /// => `C|<: Eq|.__eq__: (self: C, other: C) -> Bool`, `C|<: Show|.to_str: (self: Ref(C)) -> Str`
+    /// The bodies are generated by the code generator from the fields.
+    fn gen_class_derived_impls(
+        &self,
+        ident: &Identifier,
+        gen: &GenTypeObj,
+        ctx: &mut Context,
+    ) -> CompileResult<()> {
+        let Some(derive) = gen.derive() else {
+            return Ok(());
+        };
+        let class = gen.typ();
+        let fields = match gen.base_or_sup() {
+            Some(TypeObj::Builtin {
+                t: Type::Record(rec),
+                ..
+            }) => rec
+                .iter()
+                .map(|(field, t)| (field.symbol.clone(), t.clone()))
+                .collect(),
+            Some(base) => vec![(Str::ever("base"), base.typ().clone())],
+            None => vec![],
+        };
+        let mut traits = derive.typ().intersection_types();
+        // Ord: Eq
+        if traits.contains(&mono("Ord")) && !traits.contains(&mono("Eq")) {
+            traits.push(mono("Eq"));
+        }
+        let mut errs = CompileErrors::empty();
+        for trait_ in traits {
+            let (name, t, py_name) = match &trait_.qual_name()[..] {
+                "Eq" => (
+                    "__eq__",
+                    fn1_met(class.clone(), class.clone(), Type::Bool),
+                    None,
+                ),
+                "Ord" => {
+                    let ordering = or(mono("Ordering"), Type::NoneType);
+                    (
+                        "__cmp__",
+                        fn1_met(class.clone(), class.clone(), ordering),
+                        None,
+                    )
+                }
+                "Hash" => ("__hash__", fn0_met(class.clone(), Type::Nat), None),
+                "Show" => (
+                    "to_str",
+                    fn0_met(ref_(class.clone()), Type::Str),
+                    Some(Str::ever("__str__")),
+                ),
+                _ => {
+                    errs.push(CompileError::not_derivable_error(
+                        self.cfg.input.clone(),
+                        line!() as usize,
+                        ident.loc(),
+                        self.caused_by(),
+                        &trait_,
+                    ));
+                    continue;
+                }
+            };
+            // any object can be shown and hashed (by its identity) at runtime
+            if name == "__eq__" || name == "__cmp__" {
+                for (field, field_t) in fields.iter() {
+                    if !self.subtype_of(field_t, &trait_) {
+                        let hint =
+                            format!("the type of the field `{field}` must implement {trait_}");
+                        errs.push(CompileError::no_trait_impl_error(
+                            self.cfg.input.clone(),
+                            line!() as usize,
+                            field_t,
+                            &trait_,
+                            ident.loc(),
+                            self.caused_by(),
+                            Some(hint),
+                        ));
+                    }
+                }
+            }
+            let mut methods = Self::methods(
+                Some(trait_.clone()),
+                self.cfg.clone(),
+                self.shared.clone(),
+                2,
+                self.level,
+            );
+            methods.register_fixed_auto_impl(
+                name,
+                t,
+                Immutable,
+                Visibility::BUILTIN_PUBLIC,
+                py_name,
+            )?;
+            ctx.register_trait(class.clone(), methods);
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+
     pub(crate) fn register_type_alias(
         &mut self,
         ident: &Identifier,
@@ -1844,16 +1959,186 @@ impl Context {
         ))
     }
 
-    fn import_erg_mod(&self, __name__: &Str, loc: &impl Locational) -> CompileResult<PathBuf> {
-        let path = match self.cfg.input.resolve_real_path(Path::new(&__name__[..])) {
-            Some(path) => path,
-            None => {
-                return Err(self.import_err(line!(), __name__, loc));
+    fn import_io_err(
+        &self,
+        line: u32,
+        __name__: &Str,
+        path: &Path,
+        err: &std::io::Error,
+        candidates: &[PathBuf],
+        loc: &impl Locational,
+    ) -> TyCheckErrors {
+        TyCheckErrors::from(TyCheckError::import_io_error(
+            self.cfg.input.clone(),
+            line as usize,
+            __name__,
+            path,
+            err,
+            candidates,
+            loc.loc(),
+            self.caused_by(),
+        ))
+    }
+
+    /// If the module was not resolved because a candidate file exists but is not accessible
+    /// (e.g. permission denied, broken symbolic link), reports it instead of "not found".
+    fn inaccessible_mod_err(
+        &self,
+        line: u32,
+        __name__: &Str,
+        candidates: &[PathBuf],
+        loc: &impl Locational,
+    ) -> Option<TyCheckErrors> {
+        candidates.iter().find_map(|path| {
+            access_error(path)
+                .map(|err| self.import_io_err(line, __name__, path, &err, candidates, loc))
+        })
+    }
+
+    /// If paths are case-insensitive, `Foo.er` and `foo.er` would be registered in the module cache as the same module.
+    /// On case-sensitive file systems, they are distinct modules, but a warning is reported because the program is not portable.
+    fn check_case_collision(
+        &self,
+        path: &Path,
+        __name__: &Str,
+        loc: &impl Locational,
+    ) -> CompileResult<()> {
+        let collisions = self.shared().dir_entries.case_collisions(path);
+        if collisions.is_empty() {
+            return Ok(());
+        }
+        if case_sensitive_paths() {
+            let warn = CompileError::module_collision_warning(
+                self.cfg.input.clone(),
+                line!() as usize,
+                __name__,
+                &collisions,
+                loc.loc(),
+                self.caused_by(),
+            );
+            self.shared().warns.extend(CompileErrors::from(warn));
+            return Ok(());
+        }
+        Err(TyCheckErrors::from(TyCheckError::module_collision_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            __name__,
+            &collisions,
+            loc.loc(),
+            self.caused_by(),
+        )))
+    }
+
+    /// Modules of the same name in the same root are ambiguous (an error),
+    /// and those in the later roots are shadowed by `path` (a warning).
+    /// Namespace packages (directories) neither make nor are made ambiguous.
+    fn check_mod_ambiguity(
+        &self,
+        root: ModuleRoot,
+        path: &Path,
+        found: &[(ModuleRoot, PathBuf)],
+        __name__: &Str,
+        loc: &impl Locational,
+    ) -> CompileResult<()> {
+        if path.is_dir() {
+            return Ok(());
+        }
+        // the same file may be found in multiple roots (e.g. the project directory is also a search path)
+        let mut files: Vec<&(ModuleRoot, PathBuf)> = vec![];
+        for file in found.iter().filter(|(_, path)| path.is_file()) {
+            if files.iter().all(|(_, path)| path != &file.1) {
+                files.push(file);
             }
+        }
+        let ambiguous = files
+            .iter()
+            .filter(|(r, _)| *r == root)
+            .map(|(_, path)| path.clone())
+            .collect::<Vec<_>>();
+        if ambiguous.len() > 1 {
+            return Err(TyCheckErrors::from(TyCheckError::ambiguous_module_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                __name__,
+                &ambiguous,
+                loc.loc(),
+                self.caused_by(),
+            )));
+        }
+        let shadowed = files
+            .iter()
+            .filter(|(r, _)| *r != root)
+            .map(|(_, path)| path.clone())
+            .collect::<Vec<_>>();
+        if !shadowed.is_empty() {
+            let warn = CompileError::module_shadowing_warning(
+                self.cfg.input.clone(),
+                line!() as usize,
+                __name__,
+                path,
+                &shadowed,
+                loc.loc(),
+                self.caused_by(),
+            );
+            self.shared().warns.extend(CompileErrors::from(warn));
+        }
+        Ok(())
+    }
+
+    /// A namespace package (`--namespace-packages`) has no source, so it is registered as an empty module.
+    /// The submodules are imported with their own paths (e.g. `import "foo/bar"`).
+    fn register_namespace_pkg(&self, path: PathBuf, __name__: &Str) -> PathBuf {
+        if self.mod_cache().get(&path).is_none() {
+            let cfg = self.cfg.inherit(path.clone());
+            let ctx = Context::new_module(__name__.clone(), cfg, self.shared().clone());
+            let hir = HIR::new(__name__.clone(), hir::Module::empty());
+            self.mod_cache()
+                .register(path.clone(), Some(hir), ModuleContext::new(ctx, dict! {}));
+        }
+        path
+    }
+
+    /// The same physical module may be reachable through multiple paths (e.g. a hard-linked vendored copy).
+    /// Such a module is checked only once and the path is registered as an alias,
+    /// so that the nominal types defined in the module unify across the import paths.
+    fn dedup_mod_path(&self, path: PathBuf, cache: &SharedModuleCache) -> PathBuf {
+        if self.shared.is_none() || self.mod_registered(&path) || cache.get(&path).is_some() {
+            return path;
+        }
+        let orig = cache
+            .find_same_file(&path)
+            .or_else(|| self.promises().find_same_file(&path));
+        if let Some(orig) = orig {
+            log!(info "{} is an alias of {}", path.display(), orig.display());
+            cache.register_alias(path, orig.clone());
+            orig.to_path_buf()
+        } else {
+            path
+        }
+    }
+
+    fn import_erg_mod(&self, __name__: &Str, loc: &impl Locational) -> CompileResult<PathBuf> {
+        let found = self.cfg.resolve_real_path_all(Path::new(&__name__[..]));
+        let Some((root, path)) = found.first().cloned() else {
+            let candidates = self
+                .cfg
+                .real_path_candidates(Path::new(&__name__[..]))
+                .into_iter()
+                .map(|(_, path)| path)
+                .collect::<Vec<_>>();
+            return Err(self
+                .inaccessible_mod_err(line!(), __name__, &candidates, loc)
+                .unwrap_or_else(|| self.import_err(line!(), __name__, loc)));
         };
+        self.check_mod_ambiguity(root, &path, &found, __name__, loc)?;
+        self.check_case_collision(&path, __name__, loc)?;
+        if path.is_dir() {
+            return Ok(self.register_namespace_pkg(path, __name__));
+        }
         if ERG_MODE {
             self.check_mod_vis(path.as_path(), __name__, loc)?;
         }
+        let path = self.dedup_mod_path(path, self.mod_cache());
         if let Some(referrer) = self.cfg.input.path() {
             if self.shared().graph.inc_ref(referrer, path.clone()).is_err() {
                 self.build_cyclic_mod(&path);
@@ -1974,16 +2259,40 @@ impl Context {
             return Ok(path);
         }
         let mut cfg = self.cfg.inherit(path.clone());
-        let src = cfg
-            .input
-            .try_read()
-            .map_err(|_| self.import_err(line!(), __name__, loc))?;
+        // the module may have been parsed in advance (`--parallel-parse`)
+        let prefetched = self.shared().ast_cache.remove(&path);
+        let mut src = if prefetched.is_none() {
+            cfg.input
+                .try_read()
+                .map_err(|err| self.import_io_err(line!(), __name__, &path, &err, &[], loc))?
+        } else {
+            String::new()
+        };
+        // with `--lazy-import`, only the signature is checked first (unless the body is being forced)
+        let lazy = self.cfg.lazy_import && self.mod_cache().get(&path).is_none();
         let name = __name__.clone();
         let _path = path.clone();
         let shared = self.shared.as_ref().unwrap().inherit(path.clone());
         let run = move || {
+            let prefetched = if lazy {
+                let parsed = prefetched
+                    .unwrap_or_else(|| ASTBuilder::new(cfg.copy()).build(std::mem::take(&mut src)));
+                let res =
+                    Self::check_mod_signature(cfg.copy(), name.clone(), &shared, &_path, parsed);
+                match res {
+                    Ok(()) => return,
+                    Err(parsed) => Some(parsed),
+                }
+            } else {
+                prefetched
+            };
             let mut builder = HIRBuilder::new_with_cache(cfg, name, shared.clone());
-            match builder.build(src, "exec") {
+            let res = if let Some(parsed) = prefetched {
+                builder.check_parsed(parsed, "exec")
+            } else {
+                builder.build(src, "exec")
+            };
+            match res {
                 Ok(artifact) => {
                     shared.mod_cache.register(
                         _path.clone(),
@@ -2008,6 +2317,78 @@ impl Context {
         Ok(path)
     }
 
+    /// Checks only the public signature of the module and registers it as a deferred module (`--lazy-import`).
+    /// The parse result is given back if the signature cannot be extracted or checked.
+    fn check_mod_signature(
+        cfg: ErgConfig,
+        name: Str,
+        shared: &SharedCompilerResource,
+        path: &Path,
+        parsed: ParseResult,
+    ) -> Result<(), ParseResult> {
+        let Some(sig) = parsed
+            .as_ref()
+            .ok()
+            .and_then(|artifact| SignatureExtractor::extract(&artifact.ast))
+        else {
+            return Err(parsed);
+        };
+        let mut builder = HIRBuilder::new_with_cache(cfg, name.clone(), shared.clone());
+        if builder.check(sig, "exec").is_err() {
+            return Err(parsed);
+        }
+        let deferred = DeferredModule {
+            name,
+            parsed: Arc::new(parsed),
+        };
+        shared
+            .mod_cache
+            .register_deferred(path, builder.pop_mod_ctx().unwrap(), deferred);
+        Ok(())
+    }
+
+    /// Checks the body of the module deferred by `--lazy-import`, since a member of the module is used.
+    pub(crate) fn force_deferred_mod(&self, mod_t: &Type) {
+        if !self.cfg.lazy_import || self.shared.is_none() {
+            return;
+        }
+        let Some(path) = self.get_path_with_mod_t(mod_t) else {
+            return;
+        };
+        // wait for the signature check
+        if self.get_mod_with_path(&path).is_some() {
+            self.check_deferred_mod(&path);
+        }
+    }
+
+    /// Checks the bodies of all the modules deferred by `--lazy-import` (required to generate code).
+    pub(crate) fn force_all_deferred_mods(&self) {
+        self.promises().join_all();
+        loop {
+            let paths = self.mod_cache().deferred_paths();
+            if paths.is_empty() {
+                break;
+            }
+            for path in paths {
+                self.check_deferred_mod(&path);
+            }
+            self.promises().join_all();
+        }
+    }
+
+    fn check_deferred_mod(&self, path: &Path) {
+        let Some(deferred) = self.mod_cache().take_deferred(path) else {
+            return;
+        };
+        self.promises().remove_finished(path);
+        if let Ok(parsed) = Arc::try_unwrap(deferred.parsed) {
+            self.shared().ast_cache.register(path.to_path_buf(), parsed);
+        }
+        if let Err(errs) = self.build_erg_mod(path.to_path_buf(), &deferred.name, &()) {
+            self.shared().errors.extend(errs);
+        }
+    }
+
     fn similar_builtin_py_mod_name(&self, name: &Str) -> Option<Str> {
         get_similar_name(BUILTIN_PYTHON_MODS.into_iter(), name).map(Str::rc)
     }
@@ -2061,19 +2442,29 @@ impl Context {
         let Ok(status) = line.parse::<PylyzerStatus>() else {
             return Availability::Available;
         };
-        let Some(meta) = metadata(&status.file).ok() else {
+        let Some(hash) = Self::source_hash(&status.file) else {
             return Availability::NotFound;
         };
-        let dummy_hash = meta.len();
-        if status.hash != dummy_hash {
+        if status.hash != hash {
             Availability::OutOfDate
         } else {
             Availability::Available
         }
     }
 
+    /// The hash of the source of a declaration file, recorded in its status header.
+    /// Stubs (`.pyi`) are hashed by their content, and pylyzer records the length of the `.py` file.
+    fn source_hash(path: &Path) -> Option<u64> {
+        if path.extension().is_some_and(|ext| ext == "pyi") {
+            let src = std::fs::read(path).ok()?;
+            Some(get_hash(&src) as u64)
+        } else {
+            metadata(path).ok().map(|meta| meta.len())
+        }
+    }
+
     fn get_decl_path(&self, __name__: &Str, loc: &impl Locational) -> CompileResult<PathBuf> {
-        match self.cfg.input.resolve_decl_path(Path::new(&__name__[..])) {
+        match self.cfg.resolve_decl_path(Path::new(&__name__[..])) {
             Some(path) => {
                 if self.cfg.input.decl_file_is(&path) {
                     return Ok(path);
@@ -2105,6 +2496,22 @@ impl Context {
                 if let Ok(path) = self.try_gen_py_decl_file(__name__) {
                     return Ok(path);
                 }
+                let candidates = self
+                    .cfg
+                    .input
+                    .local_decl_path_candidates(Path::new(&__name__[..]));
+                if let Some(errs) = self.inaccessible_mod_err(line!(), __name__, &candidates, loc) {
+                    return Err(errs);
+                }
+                if self.is_extension_module(__name__) {
+                    return Err(TyCheckErrors::from(TyCheckError::extension_module_error(
+                        self.cfg.input.clone(),
+                        line!() as usize,
+                        __name__,
+                        loc.loc(),
+                        self.caused_by(),
+                    )));
+                }
                 let err = TyCheckError::import_error(
                     self.cfg.input.clone(),
                     line!() as usize,
@@ -2121,11 +2528,27 @@ impl Context {
         }
     }
 
+    /// C extension modules have no Python source, so the declaration files cannot be generated.
+    /// The declarations are looked up in the builtin signature database (`lib/pystd`, `lib/external`) and `search_paths`.
+    fn is_extension_module(&self, __name__: &Str) -> bool {
+        let py_command = match self.cfg.py_command {
+            Some(py_command) => py_command.to_string(),
+            None => match opt_which_python() {
+                Ok(py_command) => py_command,
+                Err(_) => return false,
+            },
+        };
+        is_extension_module(&py_command, __name__)
+    }
+
     fn try_gen_py_decl_file(&self, __name__: &Str) -> Result<PathBuf, ()> {
         if let Ok(path) = self.cfg.input.resolve_py(Path::new(&__name__[..])) {
             if self.cfg.input.path() == Some(path.as_path()) {
                 return Ok(path);
             }
+            if let Some(decl_path) = Self::try_gen_decl_from_pyi(&path) {
+                return Ok(decl_path);
+            }
             let (out, err) = if self.cfg.mode == ErgMode::LanguageServer || self.cfg.quiet_repl {
                 (Stdio::null(), Stdio::null())
             } else {
@@ -2143,7 +2566,7 @@ impl Context {
                 .spawn()
                 .and_then(|mut child| child.wait())
             {
-                if let Some(path) = self.cfg.input.resolve_decl_path(Path::new(&__name__[..])) {
+                if let Some(path) = self.cfg.resolve_decl_path(Path::new(&__name__[..])) {
                     let size = metadata(&path).unwrap().len();
                     // if pylyzer crashed
                     if !status.success() && size == 0 {
@@ -2159,9 +2582,42 @@ impl Context {
         Err(())
     }
 
+    /// If a stub file (`foo.pyi`) exists next to `foo.py`, convert it to `__pycache__/foo.d.er`.
+    /// The generated file has a status header so that it is regenerated when the stub is modified.
+    fn try_gen_decl_from_pyi(py_path: &Path) -> Option<PathBuf> {
+        let pyi_path = py_path.with_extension("pyi");
+        let src = std::fs::read_to_string(&pyi_path).ok()?;
+        let mut decl_path = py_path.parent()?.join("__pycache__");
+        std::fs::create_dir_all(&decl_path).ok()?;
+        decl_path.push(py_path.file_stem()?);
+        decl_path.set_extension("d.er");
+        let hash = Self::source_hash(&pyi_path)?;
+        // the stub has not been modified since the last conversion
+        let up_to_date = File::open(&decl_path).ok().and_then(|file| {
+            let mut line = "".to_string();
+            BufReader::new(file).read_line(&mut line).ok()?;
+            let status = line.parse::<PylyzerStatus>().ok()?;
+            Some(status.file == pyi_path && status.hash == hash)
+        });
+        if up_to_date == Some(true) {
+            return Some(decl_path);
+        }
+        let status = PylyzerStatus {
+            status: CheckStatus::Succeed,
+            file: pyi_path.clone(),
+            timestamp: SystemTime::now(),
+            hash,
+        };
+        let decl = PyiConverter::new().convert(&src);
+        std::fs::write(&decl_path, format!("{status}\n{decl}")).ok()?;
+        Some(decl_path)
+    }
+
     fn import_py_mod(&self, __name__: &Str, loc: &impl Locational) -> CompileResult<PathBuf> {
         let py_mod_cache = self.py_mod_cache();
         let path = self.get_decl_path(__name__, loc)?;
+        self.check_case_collision(&path, __name__, loc)?;
+        let path = self.dedup_mod_path(path, py_mod_cache);
         // module itself
         if self.cfg.input.path() == Some(path.as_path()) {
             return Ok(path);
@@ -2188,7 +2644,7 @@ impl Context {
         let src = cfg
             .input
             .try_read()
-            .map_err(|_| self.import_err(line!(), __name__, loc))?;
+            .map_err(|err| self.import_io_err(line!(), __name__, &path, &err, &[], loc))?;
         let mut builder = HIRBuilder::new_with_cache(
             cfg,
             self.mod_name(&path),
@@ -2197,6 +2653,7 @@ impl Context {
         match builder.build(src, "declare") {
             Ok(artifact) => {
                 let ctx = builder.pop_mod_ctx().unwrap();
+                self.shared().py_exceptions.register_decls(&ctx.context);
                 py_mod_cache.register(path.clone(), Some(artifact.object), ctx);
                 Ok(path)
             }
@@ -2215,9 +2672,7 @@ impl Context {
             .map_ok_or(false, |vi| vi.muty.is_const());
         let is_builtin = self
             .get_builtins()
-            .unwrap()
-            .get_var_kv(ident.inspect())
-            .is_some();
+            .is_some_and(|builtins| builtins.get_var_kv(ident.inspect()).is_some());
         if is_const || is_builtin {
             Err(TyCheckErrors::from(TyCheckError::del_error(
                 self.cfg.input.clone(),
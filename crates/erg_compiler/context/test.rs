@@ -1,14 +1,18 @@
 //! test module for `Context`
 use erg_common::traits::StructuralEq;
-use erg_common::Str;
+use erg_common::{dict, set, Str};
 
-use crate::ty::constructors::{func1, mono, mono_q, poly, refinement, ty_tp};
+use erg_parser::token::{Token, TokenKind};
+
+use crate::ty::constructors::{func, func1, mono, mono_q, poly, refinement, ty_tp};
 use crate::ty::free::Constraint;
-use crate::ty::typaram::TyParam;
-use crate::ty::{Predicate, Type};
+use crate::ty::typaram::{OpKind, TyParam};
+use crate::ty::value::ValueObj;
+use crate::ty::{Field, ParamTy, Predicate, Type};
 use Type::*;
 
 use crate::context::Context;
+use crate::hir;
 
 impl Context {
     pub fn assert_var_type(&self, varname: &str, ty: &Type) -> Result<(), ()> {
@@ -96,4 +100,39 @@ impl Context {
         assert!(self.subtype_of(&(Int | Str), &(Int | Str | Code | NoneType)));
         Ok(())
     }
+
+    pub fn test_unnamed_default_param(&self) -> Result<(), ()> {
+        // `(Int, ?Int) -> Int` (the default parameter has no name)
+        let f_t = func(vec![ParamTy::Pos(Int)], None, vec![ParamTy::Pos(Int)], Int);
+        let f = hir::Expr::Accessor(hir::Accessor::private_with_line(Str::ever("f"), 1));
+        let lit = |n: u64| {
+            let lit = hir::Literal::new(
+                ValueObj::Nat(n),
+                Token::from_str(TokenKind::NatLit, &n.to_string()),
+            );
+            hir::PosArg::new(hir::Expr::Lit(lit))
+        };
+        self.substitute_call(&f, &None, &f_t, &[lit(1)], &[])
+            .map_err(|_| ())?;
+        self.substitute_call(&f, &None, &f_t, &[lit(1), lit(2)], &[])
+            .map_err(|_| ())?;
+        Ok(())
+    }
+
+    pub fn test_record_ops(&self) -> Result<(), ()> {
+        let (x, y) = (Field::public(Str::ever("x")), Field::public(Str::ever("y")));
+        let lhs = TyParam::Record(dict! { x.clone() => ty_tp(Int), y.clone() => ty_tp(Str) });
+        let rhs = TyParam::Record(dict! { x.clone() => ty_tp(Nat) });
+        // {.x = Int; .y = Str} + {.x = Nat} == {.x = Nat; .y = Str}
+        let merged = self
+            .eval_bin_tp(OpKind::Add, lhs.clone(), rhs)
+            .map_err(|_| ())?;
+        let expected = TyParam::Record(dict! { x.clone() => ty_tp(Nat), y => ty_tp(Str) });
+        assert_eq!(merged, expected);
+        // {.x = Int; .y = Str} - {"y"} == {.x = Int}
+        let names = TyParam::Set(set! { TyParam::value("y") });
+        let removed = self.eval_bin_tp(OpKind::Sub, lhs, names).map_err(|_| ())?;
+        assert_eq!(removed, TyParam::Record(dict! { x => ty_tp(Int) }));
+        Ok(())
+    }
 }
@@ -3,13 +3,14 @@ use std::mem;
 use std::option::Option;
 
 use erg_common::fresh::FRESH_GEN;
+use erg_common::trace::GLOBAL_TRACER;
 use erg_common::traits::Locational;
 use erg_common::Str;
 #[allow(unused_imports)]
 use erg_common::{fmt_vec, fn_name, log};
 
 use crate::ty::constructors::*;
-use crate::ty::free::{Constraint, FreeKind, HasLevel, GENERIC_LEVEL};
+use crate::ty::free::{probe_generalized, Constraint, FreeKind, HasLevel, GENERIC_LEVEL};
 use crate::ty::typaram::{OpKind, TyParam};
 use crate::ty::value::ValueObj;
 use crate::ty::{Predicate, SubrType, Type};
@@ -24,6 +25,33 @@ use ValueObj::{Inf, NegInf};
 
 use super::initialize::const_func::sub_tpdict_get;
 
+/// Returns the types from `t` down to the occurrence of `var` (e.g. `Array(?T), ?T`),
+/// following the components that `occur` checks.
+fn cycle_path(var: &Type, t: &Type) -> Option<Vec<Type>> {
+    let inner = match t {
+        FreeVar(fv) if fv.is_linked() => return cycle_path(var, &fv.crack()),
+        _ if t == var => return Some(vec![t.clone()]),
+        Subr(subr) => subr
+            .non_default_params
+            .iter()
+            .chain(subr.var_params.as_deref())
+            .chain(subr.default_params.iter())
+            .map(|pt| pt.typ())
+            .chain([subr.return_t.as_ref()])
+            .find_map(|t| cycle_path(var, t)),
+        Poly { params, .. } => params.iter().find_map(|tp| match tp {
+            TyParam::Type(t) => cycle_path(var, t),
+            _ => None,
+        }),
+        Or(l, r) | And(l, r) => cycle_path(var, l).or_else(|| cycle_path(var, r)),
+        _ => None,
+    };
+    inner.map(|mut path| {
+        path.insert(0, t.clone());
+        path
+    })
+}
+
 impl Context {
     /// ```erg
     /// occur(?T, ?T) ==> OK
@@ -42,36 +70,55 @@ impl Context {
         maybe_sup: &Type,
         loc: &impl Locational,
     ) -> TyCheckResult<()> {
+        self.occur_rec(maybe_sub, maybe_sup).map_err(|var| {
+            let path = [maybe_sup, maybe_sub]
+                .into_iter()
+                .filter_map(|t| cycle_path(&var, t))
+                .find(|path| path.len() > 1)
+                .unwrap_or_default();
+            TyCheckErrors::from(TyCheckError::cyclic_type_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                &var,
+                &path,
+                loc.loc(),
+                self.caused_by(),
+            ))
+        })
+    }
+
+    /// Returns the free type variable that occurs cyclically
+    fn occur_rec(&self, maybe_sub: &Type, maybe_sup: &Type) -> Result<(), Type> {
         if maybe_sub == maybe_sup {
             return Ok(());
         }
         match (maybe_sub, maybe_sup) {
-            (FreeVar(fv), _) if fv.is_linked() => self.occur(&fv.crack(), maybe_sup, loc),
-            (_, FreeVar(fv)) if fv.is_linked() => self.occur(maybe_sub, &fv.crack(), loc),
+            (FreeVar(fv), _) if fv.is_linked() => self.occur_rec(&fv.crack(), maybe_sup),
+            (_, FreeVar(fv)) if fv.is_linked() => self.occur_rec(maybe_sub, &fv.crack()),
             (Subr(subr), FreeVar(fv)) if fv.is_unbound() => {
                 for default_t in subr.default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(default_t, maybe_sup, loc)?;
+                    self.occur_inner(default_t, maybe_sup)?;
                 }
                 if let Some(var_params) = subr.var_params.as_ref() {
-                    self.occur_inner(var_params.typ(), maybe_sup, loc)?;
+                    self.occur_inner(var_params.typ(), maybe_sup)?;
                 }
                 for non_default_t in subr.non_default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(non_default_t, maybe_sup, loc)?;
+                    self.occur_inner(non_default_t, maybe_sup)?;
                 }
-                self.occur_inner(&subr.return_t, maybe_sup, loc)?;
+                self.occur_inner(&subr.return_t, maybe_sup)?;
                 Ok(())
             }
             (FreeVar(fv), Subr(subr)) if fv.is_unbound() => {
                 for default_t in subr.default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(maybe_sub, default_t, loc)?;
+                    self.occur_inner(maybe_sub, default_t)?;
                 }
                 if let Some(var_params) = subr.var_params.as_ref() {
-                    self.occur_inner(maybe_sub, var_params.typ(), loc)?;
+                    self.occur_inner(maybe_sub, var_params.typ())?;
                 }
                 for non_default_t in subr.non_default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(maybe_sub, non_default_t, loc)?;
+                    self.occur_inner(maybe_sub, non_default_t)?;
                 }
-                self.occur_inner(maybe_sub, &subr.return_t, loc)?;
+                self.occur_inner(maybe_sub, &subr.return_t)?;
                 Ok(())
             }
             (Subr(lhs), Subr(rhs)) => {
@@ -81,11 +128,11 @@ impl Context {
                     .map(|pt| pt.typ())
                     .zip(rhs.default_params.iter().map(|pt| pt.typ()))
                 {
-                    self.occur(lhs, rhs, loc)?;
+                    self.occur_rec(lhs, rhs)?;
                 }
                 if let Some(lhs) = lhs.var_params.as_ref() {
                     if let Some(rhs) = rhs.var_params.as_ref() {
-                        self.occur(lhs.typ(), rhs.typ(), loc)?;
+                        self.occur_rec(lhs.typ(), rhs.typ())?;
                     }
                 }
                 for (lhs, rhs) in lhs
@@ -94,9 +141,9 @@ impl Context {
                     .map(|pt| pt.typ())
                     .zip(rhs.non_default_params.iter().map(|pt| pt.typ()))
                 {
-                    self.occur(lhs, rhs, loc)?;
+                    self.occur_rec(lhs, rhs)?;
                 }
-                self.occur(&lhs.return_t, &rhs.return_t, loc)?;
+                self.occur_rec(&lhs.return_t, &rhs.return_t)?;
                 Ok(())
             }
             (Poly { params, .. }, FreeVar(fv)) if fv.is_unbound() => {
@@ -107,7 +154,7 @@ impl Context {
                         None
                     }
                 }) {
-                    self.occur_inner(param, maybe_sup, loc)?;
+                    self.occur_inner(param, maybe_sup)?;
                 }
                 Ok(())
             }
@@ -119,73 +166,61 @@ impl Context {
                         None
                     }
                 }) {
-                    self.occur_inner(maybe_sub, param, loc)?;
+                    self.occur_inner(maybe_sub, param)?;
                 }
                 Ok(())
             }
             (Or(l, r), Or(l2, r2)) | (And(l, r), And(l2, r2)) => self
-                .occur(l, l2, loc)
-                .and(self.occur(r, r2, loc))
-                .or(self.occur(l, r2, loc).and(self.occur(r, l2, loc))),
+                .occur_rec(l, l2)
+                .and(self.occur_rec(r, r2))
+                .or(self.occur_rec(l, r2).and(self.occur_rec(r, l2))),
             (lhs, Or(l, r)) | (lhs, And(l, r)) => {
-                self.occur_inner(lhs, l, loc)?;
-                self.occur_inner(lhs, r, loc)
+                self.occur_inner(lhs, l)?;
+                self.occur_inner(lhs, r)
             }
             /*(Or(l, r), rhs) | (And(l, r), rhs) => {
-                self.occur_inner(l, rhs, loc)?;
-                self.occur_inner(r, rhs, loc)
+                self.occur_inner(l, rhs)?;
+                self.occur_inner(r, rhs)
             }*/
             _ => Ok(()),
         }
     }
 
-    pub(crate) fn occur_inner(
-        &self,
-        maybe_sub: &Type,
-        maybe_sup: &Type,
-        loc: &impl Locational,
-    ) -> TyCheckResult<()> {
+    fn occur_inner(&self, maybe_sub: &Type, maybe_sup: &Type) -> Result<(), Type> {
         match (maybe_sub, maybe_sup) {
-            (FreeVar(fv), _) if fv.is_linked() => self.occur_inner(&fv.crack(), maybe_sup, loc),
-            (_, FreeVar(fv)) if fv.is_linked() => self.occur_inner(maybe_sub, &fv.crack(), loc),
+            (FreeVar(fv), _) if fv.is_linked() => self.occur_inner(&fv.crack(), maybe_sup),
+            (_, FreeVar(fv)) if fv.is_linked() => self.occur_inner(maybe_sub, &fv.crack()),
             (FreeVar(sub), FreeVar(sup)) => {
                 if sub.is_unbound() && sup.is_unbound() && sub == sup {
-                    Err(TyCheckErrors::from(TyCheckError::subtyping_error(
-                        self.cfg.input.clone(),
-                        line!() as usize,
-                        maybe_sub,
-                        maybe_sup,
-                        loc.loc(),
-                        self.caused_by(),
-                    )))
+                    Err(maybe_sub.clone())
                 } else {
                     Ok(())
                 }
             }
             (Subr(subr), FreeVar(fv)) if fv.is_unbound() => {
                 for default_t in subr.default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(default_t, maybe_sup, loc)?;
+                    self.occur_inner(default_t, maybe_sup)?;
                 }
                 if let Some(var_params) = subr.var_params.as_ref() {
-                    self.occur_inner(var_params.typ(), maybe_sup, loc)?;
+                    self.occur_inner(var_params.typ(), maybe_sup)?;
                 }
                 for non_default_t in subr.non_default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(non_default_t, maybe_sup, loc)?;
+                    self.occur_inner(non_default_t, maybe_sup)?;
                 }
-                self.occur_inner(&subr.return_t, maybe_sup, loc)?;
+                self.occur_inner(&subr.return_t, maybe_sup)?;
                 Ok(())
             }
             (FreeVar(fv), Subr(subr)) if fv.is_unbound() => {
                 for default_t in subr.default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(maybe_sub, default_t, loc)?;
+                    self.occur_inner(maybe_sub, default_t)?;
                 }
                 if let Some(var_params) = subr.var_params.as_ref() {
-                    self.occur_inner(maybe_sub, var_params.typ(), loc)?;
+                    self.occur_inner(maybe_sub, var_params.typ())?;
                 }
                 for non_default_t in subr.non_default_params.iter().map(|pt| pt.typ()) {
-                    self.occur_inner(maybe_sub, non_default_t, loc)?;
+                    self.occur_inner(maybe_sub, non_default_t)?;
                 }
-                self.occur_inner(maybe_sub, &subr.return_t, loc)?;
+                self.occur_inner(maybe_sub, &subr.return_t)?;
                 Ok(())
             }
             (Subr(lhs), Subr(rhs)) => {
@@ -195,11 +230,11 @@ impl Context {
                     .map(|pt| pt.typ())
                     .zip(rhs.default_params.iter().map(|pt| pt.typ()))
                 {
-                    self.occur_inner(lhs, rhs, loc)?;
+                    self.occur_inner(lhs, rhs)?;
                 }
                 if let Some(lhs) = lhs.var_params.as_ref() {
                     if let Some(rhs) = rhs.var_params.as_ref() {
-                        self.occur_inner(lhs.typ(), rhs.typ(), loc)?;
+                        self.occur_inner(lhs.typ(), rhs.typ())?;
                     }
                 }
                 for (lhs, rhs) in lhs
@@ -208,9 +243,9 @@ impl Context {
                     .map(|pt| pt.typ())
                     .zip(rhs.non_default_params.iter().map(|pt| pt.typ()))
                 {
-                    self.occur_inner(lhs, rhs, loc)?;
+                    self.occur_inner(lhs, rhs)?;
                 }
-                self.occur_inner(&lhs.return_t, &rhs.return_t, loc)?;
+                self.occur_inner(&lhs.return_t, &rhs.return_t)?;
                 Ok(())
             }
             (Poly { params, .. }, FreeVar(fv)) if fv.is_unbound() => {
@@ -221,7 +256,7 @@ impl Context {
                         None
                     }
                 }) {
-                    self.occur_inner(param, maybe_sup, loc)?;
+                    self.occur_inner(param, maybe_sup)?;
                 }
                 Ok(())
             }
@@ -233,22 +268,42 @@ impl Context {
                         None
                     }
                 }) {
-                    self.occur_inner(maybe_sub, param, loc)?;
+                    self.occur_inner(maybe_sub, param)?;
                 }
                 Ok(())
             }
             (lhs, Or(l, r)) | (lhs, And(l, r)) => {
-                self.occur_inner(lhs, l, loc)?;
-                self.occur_inner(lhs, r, loc)
+                self.occur_inner(lhs, l)?;
+                self.occur_inner(lhs, r)
             }
             (Or(l, r), rhs) | (And(l, r), rhs) => {
-                self.occur_inner(l, rhs, loc)?;
-                self.occur_inner(r, rhs, loc)
+                self.occur_inner(l, rhs)?;
+                self.occur_inner(r, rhs)
             }
             _ => Ok(()),
         }
     }
 
+    /// ```erg
+    /// occur_tp(?N, ?N) ==> OK
+    /// occur_tp(?N, ?N + 1) ==> Error
+    /// occur_tp(?T, Array(?T)) ==> Error
+    /// ```
+    fn occur_tp(&self, var: &TyParam, tp: &TyParam, loc: &impl Locational) -> TyCheckResult<()> {
+        if var != tp && tp.contains_tp(var) {
+            Err(TyCheckErrors::from(TyCheckError::cyclic_type_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                var,
+                &[tp.clone(), var.clone()],
+                loc.loc(),
+                self.caused_by(),
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
     /// allow_divergence = trueにすると、Num型変数と±Infの単一化を許す
     pub(crate) fn sub_unify_tp(
         &self,
@@ -307,6 +362,7 @@ impl Context {
                             sub_fv.update_constraint(new_constraint, false);
                         }
                     } else {
+                        self.occur_tp(maybe_sub, sup_tp, loc)?;
                         maybe_sub.link(sup_tp);
                     }
                     Ok(())
@@ -345,6 +401,7 @@ impl Context {
                             sup_fv.update_constraint(new_constraint, false);
                         }
                     } else {
+                        self.occur_tp(maybe_sup, sub_tp, loc)?;
                         maybe_sup.link(sub_tp);
                     }
                     Ok(())
@@ -621,6 +678,7 @@ impl Context {
         loc: &impl Locational,
         param_name: Option<&Str>,
     ) -> TyCheckResult<()> {
+        let _timer = GLOBAL_TRACER.accumulate("unify");
         log!(info "trying sub_unify:\nmaybe_sub: {maybe_sub}\nmaybe_sup: {maybe_sup}");
         // In this case, there is no new information to be gained
         // この場合、特に新しく得られる情報はない
@@ -949,9 +1007,10 @@ impl Context {
                     }
                 }
             }
+            // {.x = Int; .y = Int} <: {.x = ?T}
             (Record(sub_rec), Record(sup_rec)) => {
-                for (k, l) in sub_rec.iter() {
-                    if let Some(r) = sup_rec.get(k) {
+                for (k, r) in sup_rec.iter() {
+                    if let Some(l) = sub_rec.get(k) {
                         self.sub_unify(l, r, loc, param_name)?;
                     } else {
                         return Err(TyCheckErrors::from(TyCheckError::subtyping_error(
@@ -1242,33 +1301,31 @@ impl Context {
             // sub_def_t: Zip(T, U) ==> Zip(Int, Str)
             // super_traits: [Iterable((T, U)), ...] ==> [Iterable((Int, Str)), ...]
             // TODO: user-defined types substitution
-            self.substitute_typarams(sub_def_t, maybe_sub)
-                .map_err(|errs| {
-                    Self::undo_substitute_typarams(sub_def_t);
-                    errs
-                })?;
-            let sups = if self.is_class(maybe_sup) {
-                sub_ctx.super_classes.iter()
-            } else {
-                sub_ctx.super_traits.iter()
-            };
-            let mut min_compatible = None;
-            for sup_ty in sups {
-                if self.subtype_of(sup_ty, maybe_sup) {
-                    if let Some(min) = min_compatible {
-                        if self.subtype_of(sup_ty, min) {
+            // the substitution is undone after instantiating the super type
+            let sub_instance = probe_generalized(|| {
+                self.substitute_typarams(sub_def_t, maybe_sub)?;
+                let sups = if self.is_class(maybe_sup) {
+                    sub_ctx.super_classes.iter()
+                } else {
+                    sub_ctx.super_traits.iter()
+                };
+                let mut min_compatible = None;
+                for sup_ty in sups {
+                    if self.subtype_of(sup_ty, maybe_sup) {
+                        if let Some(min) = min_compatible {
+                            if self.subtype_of(sup_ty, min) {
+                                min_compatible = Some(sup_ty);
+                            }
+                        } else {
                             min_compatible = Some(sup_ty);
                         }
-                    } else {
-                        min_compatible = Some(sup_ty);
                     }
                 }
-            }
-            if let Some(sup_ty) = min_compatible {
-                let sub_instance = self.instantiate_def_type(sup_ty).map_err(|errs| {
-                    Self::undo_substitute_typarams(sub_def_t);
-                    errs
-                })?;
+                min_compatible
+                    .map(|sup_ty| self.instantiate_def_type(sup_ty))
+                    .transpose()
+            })?;
+            if let Some(sub_instance) = sub_instance {
                 let variances = self
                     .get_nominal_type_ctx(&sub_instance)
                     .map(|(_, ctx)| ctx.type_params_variance().into_iter().map(Some).collect())
@@ -1279,16 +1336,10 @@ impl Context {
                     .zip(sup_params.iter())
                     .zip(variances)
                 {
-                    self.sub_unify_tp(l_maybe_sub, r_maybe_sup, variance, loc, false)
-                        .map_err(|errs| {
-                            Self::undo_substitute_typarams(sub_def_t);
-                            errs
-                        })?;
+                    self.sub_unify_tp(l_maybe_sub, r_maybe_sup, variance, loc, false)?;
                 }
-                Self::undo_substitute_typarams(sub_def_t);
                 return Ok(());
             }
-            Self::undo_substitute_typarams(sub_def_t);
         }
         Err(TyCheckErrors::from(TyCheckError::unification_error(
             self.cfg.input.clone(),
@@ -0,0 +1,321 @@
+//! defines `CrashReporter`.
+//!
+//! When the compiler panics (e.g. reaches `todo!()` or `unreachable!()`),
+//! the panic is caught at the driver boundary and a crash report is written to the current directory
+//! (`erg-crash-<timestamp>-<pid>.txt`). The report contains the compiler version, the phase in which the panic occurred,
+//! and a reproducer minimized by delta debugging over the top-level chunks of the source.
+//! Nothing is sent anywhere.
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::Cell;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use erg_common::config::{ErgConfig, ErgMode};
+use erg_common::consts::{BUILD_DATE, GIT_HASH_SHORT, SEMVER};
+use erg_common::trace::{current_module, current_phases};
+use erg_common::traits::{ExitStatus, Runnable};
+
+use crate::transpile::Transpiler;
+use crate::Compiler;
+
+/// The same exit code as Rust's panics
+pub const CRASH_EXIT_CODE: i32 = 101;
+/// The maximum number of compilations performed to minimize the reproducer
+const MAX_TRIALS: usize = 64;
+
+/// the first panic since the last `CrashReporter::reset`
+static LAST_CRASH: Mutex<Option<Crash>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crash {
+    pub message: String,
+    /// `file:line:col` in the compiler
+    pub location: String,
+    pub thread: String,
+    /// the phases being entered (outermost first)
+    pub phases: Vec<&'static str>,
+    /// the module being processed
+    pub module: Option<String>,
+    pub backtrace: Option<String>,
+}
+
+impl Crash {
+    fn from_panic(info: &PanicHookInfo) -> Self {
+        let payload = info.payload();
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "<unknown>".to_string()
+        };
+        let location = info.location().map_or("<unknown>".to_string(), |loc| {
+            format!("{}:{}:{}", loc.file(), loc.line(), loc.column())
+        });
+        // respects `RUST_BACKTRACE`
+        let backtrace = Backtrace::capture();
+        let backtrace =
+            (backtrace.status() == BacktraceStatus::Captured).then(|| backtrace.to_string());
+        Self {
+            message,
+            location,
+            thread: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            phases: current_phases(),
+            module: current_module(),
+            backtrace,
+        }
+    }
+
+    /// e.g. `build_ast > parse`
+    pub fn phase(&self) -> String {
+        if self.phases.is_empty() {
+            "<unknown>".to_string()
+        } else {
+            self.phases.join(" > ")
+        }
+    }
+}
+
+fn lock_last_crash() -> std::sync::MutexGuard<'static, Option<Crash>> {
+    LAST_CRASH.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Splits the source into top-level chunks (each includes the indented lines that follow it).
+/// Feature attributes (`#!feature(...)`) at the top of the source are kept as the first chunk.
+fn split_chunks(src: &str) -> (String, Vec<String>) {
+    let mut header = String::new();
+    let mut chunks: Vec<String> = vec![];
+    for line in src.split_inclusive('\n') {
+        let is_continuation = line.trim().is_empty()
+            || line.starts_with([' ', '\t', ')', ']', '}'])
+            || line.starts_with("#!") && chunks.is_empty();
+        match chunks.last_mut() {
+            Some(chunk) if is_continuation => chunk.push_str(line),
+            None if is_continuation => header.push_str(line),
+            _ => chunks.push(line.to_string()),
+        }
+    }
+    (header, chunks)
+}
+
+/// Catches internal compiler errors (panics) and reports them.
+///
+/// ```no_run
+/// use erg_common::config::ErgConfig;
+/// use erg_common::traits::Runnable;
+/// use erg_compiler::crash::CrashReporter;
+/// use erg_compiler::Compiler;
+///
+/// let cfg = ErgConfig::default();
+/// let stat = CrashReporter::guard(cfg.copy(), || Compiler::run(cfg));
+/// std::process::exit(stat.code);
+/// ```
+#[derive(Debug)]
+pub struct CrashReporter {
+    cfg: ErgConfig,
+    crash: Crash,
+}
+
+impl CrashReporter {
+    /// Replaces the default panic hook (which prints the panic message) with one that records the panic.
+    pub fn install_hook() {
+        panic::set_hook(Box::new(|info| {
+            let mut last = lock_last_crash();
+            if last.is_none() {
+                *last = Some(Crash::from_panic(info));
+            }
+        }));
+    }
+
+    fn reset() {
+        lock_last_crash().take();
+    }
+
+    /// Runs `f`, returning the first panic (including the ones in the spawned threads) if it panicked.
+    pub fn catch<T>(f: impl FnOnce() -> T) -> Result<T, Crash> {
+        Self::reset();
+        panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+            lock_last_crash().take().unwrap_or_else(|| Crash {
+                message: payload
+                    .downcast_ref::<&str>()
+                    .map_or("<unknown>".to_string(), |s| s.to_string()),
+                location: "<unknown>".to_string(),
+                thread: "<unknown>".to_string(),
+                phases: vec![],
+                module: None,
+                backtrace: None,
+            })
+        })
+    }
+
+    /// Runs the driver `f`. If it panics, a crash report is written and `CRASH_EXIT_CODE` is returned.
+    pub fn guard(cfg: ErgConfig, f: impl FnOnce() -> ExitStatus) -> ExitStatus {
+        Self::install_hook();
+        match Self::catch(f) {
+            Ok(stat) => stat,
+            Err(crash) => Self { cfg, crash }.report(),
+        }
+    }
+
+    /// Minimizes the source by delta debugging (ddmin) over the top-level chunks.
+    /// `reproduces` should return `true` if the given source still causes the failure.
+    pub fn minimize(src: &str, mut reproduces: impl FnMut(&str) -> bool) -> String {
+        let (header, mut chunks) = split_chunks(src);
+        let trials = Cell::new(0);
+        let mut test = |chunks: &[String]| {
+            trials.set(trials.get() + 1);
+            reproduces(&format!("{header}{}", chunks.concat()))
+        };
+        let mut n = 2;
+        while chunks.len() >= 2 {
+            let size = chunks.len().div_ceil(n);
+            let subsets = chunks.chunks(size).map(<[_]>::to_vec).collect::<Vec<_>>();
+            let mut reduced = None;
+            for subset in subsets.iter() {
+                if test(subset) {
+                    reduced = Some((subset.clone(), 2));
+                    break;
+                }
+            }
+            // if there are only two subsets, the complements have already been tested
+            if reduced.is_none() && subsets.len() > 2 {
+                for i in 0..subsets.len() {
+                    let complement = subsets
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .flat_map(|(_, subset)| subset.iter().cloned())
+                        .collect::<Vec<_>>();
+                    if test(&complement) {
+                        reduced = Some((complement, (n - 1).max(2)));
+                        break;
+                    }
+                }
+            }
+            match reduced {
+                Some((reduced, next_n)) => {
+                    chunks = reduced;
+                    n = next_n;
+                }
+                None if n >= chunks.len() => break,
+                None => n = (n * 2).min(chunks.len()),
+            }
+            if trials.get() >= MAX_TRIALS {
+                break;
+            }
+        }
+        format!("{header}{}", chunks.concat())
+    }
+
+    /// Whether compiling `src` panics at the same location as the original crash
+    fn reproduces(&self, src: &str) -> bool {
+        let src = src.to_string();
+        let cfg = self.cfg.copy();
+        let res = match self.cfg.mode {
+            ErgMode::Transpile => Self::catch(|| {
+                let _ = Transpiler::new(cfg).transpile(src, "exec");
+            }),
+            _ => Self::catch(|| {
+                let _ = Compiler::new(cfg).compile(src, "exec");
+            }),
+        };
+        res.is_err_and(|crash| crash.location == self.crash.location)
+    }
+
+    fn minimized_reproducer(&self) -> Option<String> {
+        if self.cfg.input.is_repl() {
+            return None;
+        }
+        let src = self.cfg.input.clone().try_read().ok()?;
+        if !self.reproduces(&src) {
+            return None;
+        }
+        Some(Self::minimize(&src, |src| self.reproduces(src)))
+    }
+
+    fn crash_report(&self, reproducer: Option<&str>) -> String {
+        let crash = &self.crash;
+        let mut report = String::new();
+        let _ = writeln!(report, "# Erg crash report");
+        let _ = writeln!(
+            report,
+            "version: {SEMVER} ({GIT_HASH_SHORT}, {BUILD_DATE}) on {}/{}",
+            std::env::consts::ARCH,
+            std::env::consts::OS
+        );
+        let _ = writeln!(report, "mode: {}", self.cfg.mode);
+        let _ = writeln!(
+            report,
+            "input: {}",
+            self.cfg.input.unescaped_path().display()
+        );
+        let _ = writeln!(report, "phase: {}", crash.phase());
+        if let Some(module) = &crash.module {
+            let _ = writeln!(report, "module: {module}");
+        }
+        let _ = writeln!(report, "thread: {}", crash.thread);
+        let _ = writeln!(report, "location: {}", crash.location);
+        let _ = writeln!(report, "message: {}", crash.message);
+        match reproducer {
+            Some(reproducer) => {
+                let _ = writeln!(
+                    report,
+                    "\n## minimized reproducer\n\n```erg\n{}\n```",
+                    reproducer.trim_end()
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    report,
+                    "\n## reproducer\n\n(the crash could not be reproduced from the input)"
+                );
+            }
+        }
+        if let Some(backtrace) = &crash.backtrace {
+            let _ = writeln!(report, "\n## backtrace\n\n{backtrace}");
+        }
+        report
+    }
+
+    fn write_report(report: &str) -> Option<PathBuf> {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |dur| dur.as_secs());
+        let file_name = format!("erg-crash-{secs}-{}.txt", std::process::id());
+        [
+            PathBuf::from(&file_name),
+            std::env::temp_dir().join(&file_name),
+        ]
+        .into_iter()
+        .find(|path| fs::write(path, report).is_ok())
+    }
+
+    fn report(self) -> ExitStatus {
+        let crash = &self.crash;
+        eprintln!(
+            "error: internal compiler error: {}\n  --> panicked at {} (phase: {})",
+            crash.message,
+            crash.location,
+            crash.phase()
+        );
+        eprintln!("note: the compiler unexpectedly panicked. This is a bug.");
+        eprintln!("note: minimizing the input...");
+        let reproducer = self.minimized_reproducer();
+        match Self::write_report(&self.crash_report(reproducer.as_deref())) {
+            Some(path) => eprintln!(
+                "note: a crash report has been written to {}",
+                path.display()
+            ),
+            None => eprintln!("note: failed to write a crash report"),
+        }
+        eprintln!(
+            "note: please report the bug with the crash report at https://github.com/erg-lang/erg/issues"
+        );
+        ExitStatus::new(CRASH_EXIT_CODE, 0, 1)
+    }
+}
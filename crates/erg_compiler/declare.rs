@@ -688,7 +688,7 @@ impl ASTLowerer {
         let (t, ty_obj) = match t {
             Type::ClassType => {
                 let t = mono(format!("{}{ident}", self.module.context.path()));
-                let ty_obj = GenTypeObj::class(t.clone(), None, None);
+                let ty_obj = GenTypeObj::class(t.clone(), None, None, None);
                 let t = v_enum(set! { ValueObj::builtin_class(t) });
                 (t, Some(ty_obj))
             }
@@ -706,7 +706,7 @@ impl ASTLowerer {
                     .map(|p| ty_tp(type_q(p.name().unwrap_or(&Str::ever("_")))))
                     .collect();
                 let t = poly(format!("{}{ident}", self.module.context.path()), params);
-                let ty_obj = GenTypeObj::class(t.clone(), None, None);
+                let ty_obj = GenTypeObj::class(t.clone(), None, None, None);
                 let t = v_enum(set! { ValueObj::builtin_class(t) });
                 (t, Some(ty_obj))
             }
@@ -3,9 +3,9 @@
 //! 関数や不変型に副作用がないかチェックする
 
 use erg_common::config::ErgConfig;
-use erg_common::log;
 use erg_common::traits::{Locational, Stream};
 use erg_common::Str;
+use erg_common::{log, trace_span};
 use erg_parser::token::TokenKind;
 
 use crate::error::{EffectError, EffectErrors};
@@ -82,6 +82,7 @@ impl SideEffectChecker {
     pub fn check(mut self, hir: HIR) -> Result<HIR, (HIR, EffectErrors)> {
         self.path_stack.push(Visibility::private(hir.name.clone()));
         self.block_stack.push(Module);
+        let _span = trace_span!("effect_check");
         log!(info "the side-effects checking process has started.{RESET}");
         // At the top level, there is no problem with side effects, only check for purity violations.
         // トップレベルでは副作用があっても問題なく、純粋性違反がないかのみチェックする
@@ -177,6 +178,9 @@ impl SideEffectChecker {
                 Expr::TypeAsc(tasc) => {
                     self.check_expr(&tasc.expr);
                 }
+                Expr::Coerce(coerce) => {
+                    self.check_expr(&coerce.expr);
+                }
                 Expr::Lambda(lambda) => {
                     let is_proc = lambda.is_procedural();
                     if is_proc {
@@ -188,6 +192,9 @@ impl SideEffectChecker {
                             .push(Visibility::private(Str::ever("<lambda>")));
                         self.block_stack.push(Func);
                     }
+                    if let Some(guard) = &lambda.guard {
+                        self.check_expr(guard);
+                    }
                     lambda.body.iter().for_each(|chunk| self.check_expr(chunk));
                     self.path_stack.pop();
                     self.block_stack.pop();
@@ -438,6 +445,9 @@ impl SideEffectChecker {
                     self.block_stack.push(Func);
                 }
                 self.check_params(&lambda.params);
+                if let Some(guard) = &lambda.guard {
+                    self.check_expr(guard);
+                }
                 lambda.body.iter().for_each(|chunk| self.check_expr(chunk));
                 self.path_stack.pop();
                 self.block_stack.pop();
@@ -445,6 +455,9 @@ impl SideEffectChecker {
             Expr::TypeAsc(type_asc) => {
                 self.check_expr(&type_asc.expr);
             }
+            Expr::Coerce(coerce) => {
+                self.check_expr(&coerce.expr);
+            }
             Expr::Accessor(acc) => {
                 if !self.in_context_effects_allowed() && acc.ref_t().is_mut_type() {
                     self.errs.push(EffectError::touch_mut_error(
@@ -466,7 +479,8 @@ impl SideEffectChecker {
     pub(crate) fn is_impure(expr: &Expr) -> bool {
         match expr {
             Expr::Call(call) => {
-                call.ref_t().is_procedure()
+                call.signature_t().is_some_and(|t| t.is_procedure())
+                    || call.ref_t().is_procedure()
                     || call
                         .args
                         .pos_args
@@ -517,7 +531,9 @@ impl SideEffectChecker {
                 _ => todo!(),
             },
             Expr::Lambda(lambda) => {
-                lambda.op.is_procedural() || lambda.body.iter().any(Self::is_impure)
+                lambda.op.is_procedural()
+                    || lambda.guard.as_deref().is_some_and(Self::is_impure)
+                    || lambda.body.iter().any(Self::is_impure)
             }
             Expr::Def(def) => def.sig.is_procedural() || def.body.block.iter().any(Self::is_impure),
             /*
@@ -0,0 +1,236 @@
+//! Stable error codes of the compiler errors (`E0001`, ...).
+//!
+//! The code is attached to the error by its constructor (e.g. `TyCheckError::type_mismatch_error` => `E0004`).
+//! Codes are never reused or renumbered, even if the error is removed, so that editors and documents can refer to them.
+//! `erg explain <code>` prints the entry of this table.
+use erg_common::error::ErrorCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCodeEntry {
+    pub code: ErrorCode,
+    pub summary: &'static str,
+    pub description: &'static str,
+    /// erroneous code that raises this error
+    pub example: Option<&'static str>,
+}
+
+macro_rules! error_codes {
+    ($($num: literal $name: ident: $summary: literal, $description: literal $(, example: $example: literal)?;)*) => {
+        $(pub const $name: ErrorCode = ErrorCode($num);)*
+
+        pub const ERROR_CODES: &[ErrorCodeEntry] = &[
+            $(ErrorCodeEntry {
+                code: $name,
+                summary: $summary,
+                description: $description,
+                example: error_codes!(@example $($example)?),
+            },)*
+        ];
+    };
+    (@example $example: literal) => { Some($example) };
+    (@example) => { None };
+}
+
+error_codes! {
+    1 COMPILER_BUG: "compiler bug",
+"The compiler reached a state that should be impossible.
+This is not a problem of your code. Please report it to https://github.com/erg-lang/erg/issues with the code that raised it.";
+
+    2 NO_TYPE_SPEC: "missing type specification",
+"The type of the variable must be specified explicitly here.";
+
+    3 NOT_CALLABLE: "not callable",
+"The object is not a callable object that takes the given arguments.";
+
+    4 TYPE_MISMATCH: "type mismatch",
+"The type of the value does not match the expected type (e.g. the type of the parameter).
+Pass a value of the expected type, or convert it explicitly.
+If the expected type is too narrow, consider widening the type specification.",
+    example: "f(x: Int) = x + 1
+f \"a\"";
+
+    5 RETURN_TYPE: "return type mismatch",
+"The type of the value returned from the subroutine does not match the declared return type.
+The return type is the type of the last expression of the body.",
+    example: "f(x: Int): Str = x";
+
+    6 UNINITIALIZED: "uninitialized variable",
+"The variable is declared but not defined.
+A declaration (`x: T`) must be followed by a definition (`x = ...`) in the same scope.";
+
+    7 ARGUMENT: "wrong number of positional arguments",
+"The number of the positional arguments does not match the number of the parameters.";
+
+    8 PARAM: "wrong number of parameters",
+"The subroutine does not take the number of parameters that the expected subroutine type requires.
+e.g. a function passed to `map` must take exactly one parameter.";
+
+    9 DEFAULT_PARAM: "default parameters are not accepted",
+"The subroutine passed here must not have default parameters.";
+
+    10 DEFAULT_PARAM_NOT_FOUND: "no such default parameter",
+"The subroutine type requires a default parameter that the subroutine does not have.";
+
+    11 MATCH: "non-exhaustive patterns",
+"The arms of `match` do not cover all the possible types of the value.
+Add arms for the missing types, or a wildcard arm (`_ -> ...`).",
+    example: "f(x: Int or Str) = match x:
+    (i: Int) -> i
+f 1";
+
+    12 INFER: "type inference failed",
+"The type of the expression cannot be inferred. Specify the type explicitly.";
+
+    13 TOO_MANY_ARGS: "too many arguments",
+"More arguments are passed than the subroutine takes.",
+    example: "f(x: Int) = x
+f 1, 2";
+
+    14 ARGS_MISSING: "missing arguments",
+"Some of the required (non-default) arguments are not passed.
+Note that a keyword argument whose name is not a parameter does not fill the parameter.",
+    example: "f(x: Int, y: Int) = x + y
+f 1";
+
+    15 MULTIPLE_ARGS: "argument passed multiple times",
+"The same parameter is given both as a positional argument and as a keyword argument (or as two keyword arguments).";
+
+    16 UNEXPECTED_KW_ARG: "unexpected keyword argument",
+"The subroutine has no parameter with the name of the keyword argument.",
+    example: "f(x: Int, y := 1) = x + y
+f 1, z := 2";
+
+    17 UNIFICATION: "unification failed",
+"The two types must be the same, but they cannot be unified.";
+
+    18 RE_UNIFICATION: "re-unification failed",
+"A type variable that was already fixed to a type cannot be unified with another type.";
+
+    19 SUBTYPING: "unsatisfiable subtype constraint",
+"The expression requires a type to be a subtype of another type, but it is not.
+The type checker may fail to infer the types. If so, specify the types explicitly.";
+
+    20 INVARIANT: "ambiguous invariant type parameter",
+"The type parameter of an invariant type (e.g. `Array!(T)`) cannot be determined uniquely.
+Specify the type parameter explicitly.";
+
+    21 PRED_UNIFICATION: "predicate unification failed",
+"The predicates of the refinement types (e.g. `{I: Int | I >= 0}`) cannot be unified.";
+
+    22 NO_CANDIDATE: "no candidate for projection",
+"No type satisfies the projection type (e.g. `T.Output`).";
+
+    23 NO_TRAIT_IMPL: "trait not implemented",
+"The class does not implement the required trait.";
+
+    24 METHOD_DEFINITION: "methods cannot be defined",
+"Methods can be defined only for the classes defined in the module (or their patches).";
+
+    25 SPECIALIZATION: "specialization failed",
+"The member of the trait cannot be specialized to the given type.";
+
+    26 TRAIT_MEMBER_TYPE: "trait member type mismatch",
+"The type of the member implemented in the class does not match the type declared in the trait.";
+
+    27 TRAIT_MEMBER_NOT_DEFINED: "trait member not implemented",
+"The class declares that it implements the trait, but a member of the trait is not defined.
+Define all the members declared in the trait.",
+    example: "Greet = Trait { .greet = (self: Self) -> Str }
+C = Class()
+C|<: Greet|.
+    hello self = \"hello\"";
+
+    28 NOT_IN_TRAIT: "member not declared in trait",
+"The member defined in the trait implementation block is not declared in the trait.
+Define it in a normal method block (`C.`) instead.",
+    example: "Greet = Trait { .greet = (self: Self) -> Str }
+C = Class()
+C|<: Greet|.
+    greet self = \"hi\"
+    hello self = \"hello\"";
+
+    29 TYVAR_NOT_DEFINED: "undefined type variable",
+"The type variable used in the bound is not defined.";
+
+    30 AMBIGUOUS_TYPE: "ambiguous type",
+"The type of the expression cannot be determined uniquely. Specify the type explicitly.";
+
+    31 AMBIGUOUS_METHOD: "ambiguous method",
+"The attribute is found in several types and the type of the receiver is not known.
+Specify the type of the receiver (e.g. `(x: Int).abs()`).";
+
+    32 TP_TO_TYPE: "type expected",
+"A type is expected, but a non-type type argument is given.";
+
+    33 NOT_POLYMORPHIC: "not a polymorphic type",
+"Type arguments are applied to a type that takes no type parameters.";
+
+    34 AMBIGUOUS_TYPE_ARGS: "ambiguous type arguments",
+"The positional type arguments cannot be mapped to the type parameters uniquely.
+Pass them by name (e.g. `T := Int`).";
+
+    35 IMPLICIT_WIDENING: "implicit widening",
+"The type is widened implicitly (e.g. `Int` to `Int or Str`), which is prohibited by default.
+Widen it explicitly with `as`.";
+
+    36 OVERLOAD: "overload resolution failed",
+"None of the overloaded types of the subroutine accepts the given arguments.";
+
+    37 SELF_TYPE: "invalid use of `Self`",
+"`Self` can be used only in the definitions of classes, traits and their methods.",
+    example: "f(x: Self) = x";
+
+    38 NOT_CONST_EXPR: "not a constant expression",
+"A compile-time constant is required here (e.g. in a type, a `static_assert` or a constant definition), but the expression cannot be evaluated at compile time.
+Constants are the literals, the constant names (uppercase names) and the calls of constant subroutines with constant arguments.";
+
+    39 INVALID_LITERAL: "invalid literal",
+"The literal cannot be converted to a value (e.g. an integer literal that is too large).",
+    example: "x = 99999999999999999999999999999999999999999";
+
+    40 TYPE_HOLE: "type hole",
+"This is a warning. `_` in an expression or in a type specification of a variable or a return type is a type hole.
+The hole type-checks against any type, and the compiler reports the type inferred for it.
+Replace the hole with an expression or a type before running the program.",
+    example: "x: Int = _";
+
+    41 CYCLIC_TYPE: "cyclic type",
+"A type variable would be unified with a type that contains the variable itself (e.g. `?T` with `Array(?T)`), which makes an infinite type.
+The error shows how the variable occurs in the type. Specify the types explicitly.",
+    example: "f x = x x";
+
+    42 TYPE_TOO_COMPLEX: "type too complex",
+"The type is nested too deeply to be evaluated (e.g. a long chain of projection types such as `T.Output.Output...`).
+The limit of the nesting depth can be raised with `--max-type-depth`, but consider simplifying the type.";
+
+    43 NOT_DERIVABLE: "trait cannot be derived",
+"The trait passed to `Derive` of `Class` cannot be generated automatically.
+The derivable traits are `Eq`, `Ord`, `Hash` and `Show`. Implement the other traits by hand.",
+    example: "C = Class {x = Int}, Derive := Iterable(Int)";
+
+    44 CYCLIC_IMPORT: "cyclic import",
+"The modules import each other, and an attribute of a module in the cycle is referred to before the module is initialized.
+Cyclic imports are allowed as long as the definitions do not depend on each other (e.g. mutually recursive functions).
+The error shows the chain of the imports. Move the definitions shared by the modules into a separate module.";
+
+    45 AMBIGUOUS_MODULE: "ambiguous module",
+"The module path refers to more than one module in the same search root (e.g. both `foo.er` and `foo/__init__.er`, or `foo.er` in two dependencies).
+Remove or rename one of them. Modules in different roots are not ambiguous: the one in the earlier root (see `--module-search-order`) shadows the others with a warning.";
+
+    46 INVALID_ASM: "invalid inline bytecode",
+"The instructions of `asm` cannot be verified.
+Only the opcodes that operate on the stack (e.g. `BINARY_OP`, `SWAP`, `BUILD_TUPLE`) can be used, and the arguments must be consumed and exactly one result must be left on the stack.
+`asm` is available only when the target is Python 3.11.",
+    example: "f(x: Int, y: Int): Int = asm \"BINARY_OP 0; BINARY_OP 0\", x, y";
+
+    47 INDEX_OUT_OF_BOUNDS: "index out of bounds",
+"The index of the array can be out of bounds (`i < 0` or `N <= i` for `[T; N]`).
+The bounds of the index are taken from its type (e.g. `{I: Nat | I <= 2}`, or `{3}` for the literal `3`).
+An index whose type is not bounded (e.g. `Int`) is checked at runtime instead.",
+    example: "arr = [1, 2, 3]
+arr[3]";
+}
+
+pub fn get_entry(code: ErrorCode) -> Option<&'static ErrorCodeEntry> {
+    ERROR_CODES.iter().find(|entry| entry.code == code)
+}
@@ -0,0 +1,256 @@
+//! Structural diff of two types, used to render large type mismatches.
+//!
+//! Only the differing parts are highlighted, and the record fields that both types share are folded into `...`.
+//! ```txt
+//! expected: {.tags = Array(Str, 2); ...}
+//! but found: {.tags = Array(Int, 2); ...}
+//! ```
+use erg_common::style::{Color, Stylize};
+
+use crate::ty::{SubrType, TyParam, Type};
+
+use super::{ATTR, ERR, HINT};
+
+/// Types shorter than this are printed as they are.
+const DIFF_THRESHOLD: usize = 40;
+
+/// Returns the rendered `(expect, found)`, or `None` if the types are not large records/subroutines.
+pub fn diff_types(expect: &Type, found: &Type) -> Option<(String, String)> {
+    let expect = unlink(expect);
+    let found = unlink(found);
+    let structural = matches!(
+        (&expect, &found),
+        (Type::Record(_), Type::Record(_)) | (Type::Subr(_), Type::Subr(_))
+    );
+    let large = expect.to_string().len().max(found.to_string().len()) >= DIFF_THRESHOLD;
+    if structural && large && !same(&expect, &found) {
+        Some(diff(&expect, &found))
+    } else {
+        None
+    }
+}
+
+fn unlink(t: &Type) -> Type {
+    match t {
+        Type::FreeVar(fv) if fv.is_linked() => unlink(&fv.crack()),
+        _ => t.clone(),
+    }
+}
+
+fn highlight(s: impl ToString, color: Color) -> String {
+    s.to_string().with_color_and_attr(color, ATTR).to_string()
+}
+
+/// The literal types of the found value (e.g. `{1}`) are compatible with their base types (e.g. `Nat`).
+fn same(expect: &Type, found: &Type) -> bool {
+    expect == found || *expect == found.derefine()
+}
+
+fn diff(expect: &Type, found: &Type) -> (String, String) {
+    if same(expect, found) {
+        return (expect.to_string(), found.to_string());
+    }
+    match (unlink(expect), unlink(found)) {
+        (Type::Record(l), Type::Record(r)) => {
+            let (mut lhs, mut rhs) = (vec![], vec![]);
+            let mut folded = false;
+            for (field, lt) in l.iter() {
+                match r.get(field) {
+                    Some(rt) if same(lt, rt) => folded = true,
+                    Some(rt) => {
+                        let (lt, rt) = diff(lt, rt);
+                        lhs.push(format!("{field} = {lt}"));
+                        rhs.push(format!("{field} = {rt}"));
+                    }
+                    None => lhs.push(highlight(format!("{field} = {lt}"), HINT)),
+                }
+            }
+            for (field, rt) in r.iter().filter(|(field, _)| !l.contains_key(*field)) {
+                rhs.push(highlight(format!("{field} = {rt}"), ERR));
+            }
+            if folded {
+                lhs.push("...".to_string());
+                rhs.push("...".to_string());
+            }
+            (
+                format!("{{{}}}", lhs.join("; ")),
+                format!("{{{}}}", rhs.join("; ")),
+            )
+        }
+        (Type::Subr(l), Type::Subr(r)) if same_arity(&l, &r) => diff_subr(&l, &r),
+        (
+            Type::Poly {
+                name: ln,
+                params: lps,
+            },
+            Type::Poly {
+                name: rn,
+                params: rps,
+            },
+        ) if ln == rn && lps.len() == rps.len() => {
+            let (lhs, rhs): (Vec<_>, Vec<_>) = lps
+                .iter()
+                .zip(rps.iter())
+                .map(|(lp, rp)| diff_tp(lp, rp))
+                .unzip();
+            (
+                format!("{ln}({})", lhs.join(", ")),
+                format!("{rn}({})", rhs.join(", ")),
+            )
+        }
+        (l, r) => (highlight(l, HINT), highlight(r, ERR)),
+    }
+}
+
+fn diff_tp(expect: &TyParam, found: &TyParam) -> (String, String) {
+    match (expect, found) {
+        (TyParam::Type(l), TyParam::Type(r)) => diff(l, r),
+        (l, r) if l == r => (l.to_string(), r.to_string()),
+        (l, r) => (highlight(l, HINT), highlight(r, ERR)),
+    }
+}
+
+fn same_arity(l: &SubrType, r: &SubrType) -> bool {
+    l.non_default_params.len() == r.non_default_params.len()
+        && l.var_params.is_some() == r.var_params.is_some()
+        && l.default_params.len() == r.default_params.len()
+}
+
+fn diff_subr(expect: &SubrType, found: &SubrType) -> (String, String) {
+    let (mut lhs, mut rhs) = (vec![], vec![]);
+    let mut push =
+        |prefix: &str, lt: &Type, rt: &Type, l_name: Option<&str>, r_name: Option<&str>| {
+            let (lt, rt) = diff(lt, rt);
+            let (l_name, r_name) = match (l_name, r_name) {
+                (Some(l), Some(r)) if l != r => (highlight(l, HINT), highlight(r, ERR)),
+                (l, r) => (
+                    l.unwrap_or_default().to_string(),
+                    r.unwrap_or_default().to_string(),
+                ),
+            };
+            let sep = |name: &str| if name.is_empty() { "" } else { ": " };
+            lhs.push(format!("{prefix}{l_name}{}{lt}", sep(&l_name)));
+            rhs.push(format!("{prefix}{r_name}{}{rt}", sep(&r_name)));
+        };
+    for (lp, rp) in expect
+        .non_default_params
+        .iter()
+        .zip(found.non_default_params.iter())
+    {
+        push(
+            "",
+            lp.typ(),
+            rp.typ(),
+            lp.name().map(|s| &s[..]),
+            rp.name().map(|s| &s[..]),
+        );
+    }
+    if let (Some(lp), Some(rp)) = (&expect.var_params, &found.var_params) {
+        push(
+            "*",
+            lp.typ(),
+            rp.typ(),
+            lp.name().map(|s| &s[..]),
+            rp.name().map(|s| &s[..]),
+        );
+    }
+    for (lp, rp) in expect
+        .default_params
+        .iter()
+        .zip(found.default_params.iter())
+    {
+        let (lt, rt) = diff(lp.typ(), rp.typ());
+        let (l_name, r_name) = (lp.name().unwrap(), rp.name().unwrap());
+        if l_name == r_name {
+            lhs.push(format!("{l_name} := {lt}"));
+            rhs.push(format!("{r_name} := {rt}"));
+        } else {
+            lhs.push(format!("{} := {lt}", highlight(l_name, HINT)));
+            rhs.push(format!("{} := {rt}", highlight(r_name, ERR)));
+        }
+    }
+    let (l_arrow, r_arrow) = if expect.kind == found.kind {
+        (
+            expect.kind.arrow().to_string(),
+            found.kind.arrow().to_string(),
+        )
+    } else {
+        (
+            highlight(expect.kind.arrow(), HINT),
+            highlight(found.kind.arrow(), ERR),
+        )
+    };
+    let (l_ret, r_ret) = diff(&expect.return_t, &found.return_t);
+    (
+        format!("({}) {l_arrow} {l_ret}", lhs.join(", ")),
+        format!("({}) {r_arrow} {r_ret}", rhs.join(", ")),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use erg_common::dict;
+    use erg_common::style::remove_style;
+
+    use crate::ty::constructors::{array_t, func, kw};
+    use crate::ty::{Field, TyParam, Type};
+
+    use super::diff_types;
+
+    fn field(name: &'static str) -> Field {
+        Field::public(name.into())
+    }
+
+    #[test]
+    fn record_diff() {
+        let expect = Type::Record(dict! {
+            field("name") => Type::Str,
+            field("age") => Type::Nat,
+            field("tags") => array_t(Type::Str, TyParam::value(2usize)),
+        });
+        let found = Type::Record(dict! {
+            field("name") => Type::Str,
+            field("age") => Type::Nat,
+            field("tags") => array_t(Type::Int, TyParam::value(2usize)),
+        });
+        let (expect, found) = diff_types(&expect, &found).unwrap();
+        assert_eq!(remove_style(&expect), "{.tags = Array(Str, 2); ...}");
+        assert_eq!(remove_style(&found), "{.tags = Array(Int, 2); ...}");
+        // only `Str`/`Int` is highlighted
+        assert_ne!(expect, remove_style(&expect));
+        assert!(expect.starts_with("{.tags = Array("));
+    }
+
+    #[test]
+    fn subr_diff() {
+        let expect = func(
+            vec![kw("user_name", Type::Str), kw("user_age", Type::Nat)],
+            None,
+            vec![kw("verbose", Type::Bool)],
+            Type::Str,
+        );
+        let found = func(
+            vec![kw("user_name", Type::Str), kw("user_age", Type::Str)],
+            None,
+            vec![kw("verbose", Type::Bool)],
+            Type::Str,
+        );
+        let (expect, found) = diff_types(&expect, &found).unwrap();
+        assert_eq!(
+            remove_style(&expect),
+            "(user_name: Str, user_age: Nat, verbose := Bool) -> Str"
+        );
+        assert_eq!(
+            remove_style(&found),
+            "(user_name: Str, user_age: Str, verbose := Bool) -> Str"
+        );
+        assert!(found.starts_with("(user_name: Str, user_age: "));
+    }
+
+    #[test]
+    fn small_types_are_not_diffed() {
+        let expect = Type::Record(dict! { field("a") => Type::Int });
+        let found = Type::Record(dict! { field("a") => Type::Str });
+        assert!(diff_types(&expect, &found).is_none());
+    }
+}
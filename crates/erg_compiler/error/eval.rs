@@ -1,8 +1,10 @@
 use erg_common::error::{ErrorCore, ErrorKind::*, Location, SubMessage};
 use erg_common::io::Input;
+use erg_common::style::StyledString;
 use erg_common::switch_lang;
 
 use crate::error::*;
+use crate::ty::Type;
 
 pub type EvalError = CompileError;
 pub type EvalErrors = CompileErrors;
@@ -23,7 +25,8 @@ impl EvalError {
                 errno,
                 NotConstExpr,
                 loc,
-            ),
+            )
+            .with_code(codes::NOT_CONST_EXPR),
             input,
             caused_by,
         )
@@ -42,7 +45,41 @@ impl EvalError {
                 errno,
                 SyntaxError,
                 loc,
-            ),
+            )
+            .with_code(codes::INVALID_LITERAL),
+            input,
+            caused_by,
+        )
+    }
+    pub fn type_too_complex_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        typ: &Type,
+        max_depth: usize,
+    ) -> Self {
+        let hint = switch_lang!(
+            "japanese" => format!("型の評価のネストの深さが上限({max_depth})を超えました。型を単純にするか、--max-type-depthで上限を引き上げてください。"),
+            "simplified_chinese" => format!("类型求值的嵌套深度超过了上限({max_depth})。请简化类型, 或使用--max-type-depth提高上限。"),
+            "traditional_chinese" => format!("類型求值的嵌套深度超過了上限({max_depth})。請簡化類型, 或使用--max-type-depth提高上限。"),
+            "english" => format!("the nesting depth of the type evaluation exceeds the limit ({max_depth}). Simplify the type, or raise the limit with --max-type-depth"),
+        );
+        let typ = StyledString::new(format!("{typ}"), Some(ERR), Some(ATTR));
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+                switch_lang!(
+                    "japanese" => format!("{typ}は複雑すぎて評価できません"),
+                    "simplified_chinese" => format!("{typ}过于复杂, 无法求值"),
+                    "traditional_chinese" => format!("{typ}過於複雜, 無法求值"),
+                    "english" => format!("{typ} is too complex to evaluate"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            )
+            .with_code(codes::TYPE_TOO_COMPLEX),
             input,
             caused_by,
         )
@@ -1,5 +1,8 @@
-use erg_common::error::{ErrorCore, ErrorKind::*, Location, SubMessage};
+use std::path::{Path, PathBuf};
+
+use erg_common::error::{ErrorCore, ErrorKind, ErrorKind::*, Location, SubMessage};
 use erg_common::io::Input;
+use erg_common::opcode311::AsmError;
 use erg_common::style::{StyledStr, StyledString, StyledStrings, Stylize};
 use erg_common::traits::Locational;
 use erg_common::{switch_lang, Str};
@@ -255,6 +258,28 @@ impl LowerError {
         )
     }
 
+    /// Appends a hint with the import line found by `Context::get_import_suggestion`.
+    pub fn with_import_suggestion(mut self, import: Option<String>) -> Self {
+        let Some(import) = import else {
+            return self;
+        };
+        let import = import.with_color_and_attr(HINT, ATTR);
+        let msg = switch_lang!(
+            "japanese" => format!("インポートし忘れていませんか?: {import}"),
+            "simplified_chinese" => format!("您是否忘记了导入?: {import}"),
+            "traditional_chinese" => format!("您是否忘記了導入?: {import}"),
+            "english" => format!("did you mean to import it?: {import}"),
+        );
+        if let Some(sub) = self.core.sub_messages.first_mut() {
+            let hint = match sub.get_hint() {
+                Some(hint) => format!("{hint}\n{msg}"),
+                None => msg,
+            };
+            sub.set_hint(hint);
+        }
+        self
+    }
+
     pub fn access_before_def_error(
         input: Input,
         errno: usize,
@@ -796,6 +821,32 @@ impl LowerError {
         )
     }
 
+    /// `kind`: the exception raised in the test (e.g. `AssertionError`)
+    pub fn test_failure_error(
+        input: Input,
+        errno: usize,
+        kind: ErrorKind,
+        msg: &str,
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let desc = if msg.is_empty() && kind == AssertionError {
+            switch_lang!(
+                "japanese" => "アサーションに失敗しました".to_string(),
+                "simplified_chinese" => "断言失败".to_string(),
+                "traditional_chinese" => "斷言失敗".to_string(),
+                "english" => "assertion failed".to_string(),
+            )
+        } else {
+            msg.to_string()
+        };
+        Self::new(
+            ErrorCore::new(vec![SubMessage::only_loc(loc)], desc, errno, kind, loc),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn module_env_error(
         input: Input,
         errno: usize,
@@ -812,6 +863,119 @@ impl LowerError {
         Self::file_error(input, errno, desc, loc, caused_by, None)
     }
 
+    /// Module files whose names differ only in case (e.g. `Foo.er` and `foo.er`)
+    /// cannot be distinguished on case-insensitive file systems.
+    pub fn module_collision_error(
+        input: Input,
+        errno: usize,
+        mod_name: &str,
+        collisions: &[PathBuf],
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let files = collisions
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let desc = switch_lang!(
+            "japanese" => format!("モジュール{mod_name}のファイル名が大文字小文字の違いのみで衝突しています: {files}"),
+            "simplified_chinese" => format!("模块{mod_name}的文件名仅大小写不同, 发生冲突: {files}"),
+            "traditional_chinese" => format!("模塊{mod_name}的檔名僅大小寫不同, 發生衝突: {files}"),
+            "english" => format!("the file names of module {mod_name} collide (they differ only in case): {files}"),
+        );
+        let hint = switch_lang!(
+            "japanese" => "ファイル名を変更してください (大文字小文字を区別しないファイルシステムでは同じファイルとして扱われます)",
+            "simplified_chinese" => "请重命名文件 (在不区分大小写的文件系统中它们被视为同一文件)",
+            "traditional_chinese" => "請重命名檔案 (在不區分大小寫的檔案系統中它們被視為同一檔案)",
+            "english" => "rename the files (they are treated as the same file on case-insensitive file systems)",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.into()))],
+                desc,
+                errno,
+                ImportError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
+    /// `mod_name` refers to multiple modules in the same search root (e.g. `foo.er` and `foo/__init__.er`)
+    pub fn ambiguous_module_error(
+        input: Input,
+        errno: usize,
+        mod_name: &str,
+        paths: &[PathBuf],
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let files = paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let desc = switch_lang!(
+            "japanese" => format!("モジュール{mod_name}が曖昧です(候補: {files})"),
+            "simplified_chinese" => format!("模块{mod_name}不明确 (候选: {files})"),
+            "traditional_chinese" => format!("模組{mod_name}不明確 (候選: {files})"),
+            "english" => format!("module {mod_name} is ambiguous (candidates: {files})"),
+        );
+        let hint = switch_lang!(
+            "japanese" => "いずれかのファイルを削除するか名前を変更してください",
+            "simplified_chinese" => "请删除或重命名其中一个文件",
+            "traditional_chinese" => "請刪除或重命名其中一個檔案",
+            "english" => "remove or rename one of the files",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.into()))],
+                desc,
+                errno,
+                ImportError,
+                loc,
+            )
+            .with_code(codes::AMBIGUOUS_MODULE),
+            input,
+            caused_by,
+        )
+    }
+
+    /// A C extension module (which has no Python source) was imported, but no declaration file was found.
+    pub fn extension_module_error(
+        input: Input,
+        errno: usize,
+        mod_name: &str,
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let desc = switch_lang!(
+            "japanese" => format!("{mod_name}はC拡張モジュールですが、宣言ファイルが見つかりません"),
+            "simplified_chinese" => format!("{mod_name}是C扩展模块, 但找不到声明文件"),
+            "traditional_chinese" => format!("{mod_name}是C擴展模塊, 但找不到聲明文件"),
+            "english" => format!("{mod_name} is a C extension module, but its declaration file is not found"),
+        );
+        let hint = switch_lang!(
+            "japanese" => format!("{mod_name}.d.erを作成し、--search-pathで指定したディレクトリに置いてください"),
+            "simplified_chinese" => format!("请创建{mod_name}.d.er并放在--search-path指定的目录中"),
+            "traditional_chinese" => format!("請創建{mod_name}.d.er並放在--search-path指定的目錄中"),
+            "english" => format!("create {mod_name}.d.er and put it in a directory specified by --search-path"),
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+                desc,
+                errno,
+                ImportError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn import_error(
         input: Input,
         errno: usize,
@@ -978,6 +1142,87 @@ impl LowerError {
         )
     }
 
+    /// The module file was found, but could not be accessed (e.g. permission denied, broken symbolic link).
+    #[allow(clippy::too_many_arguments)]
+    pub fn import_io_error(
+        input: Input,
+        errno: usize,
+        mod_name: &str,
+        path: &Path,
+        err: &std::io::Error,
+        candidates: &[PathBuf],
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let path = path.display();
+        let desc = switch_lang!(
+            "japanese" => format!("モジュール{mod_name}({path})を読み込めません: {err}"),
+            "simplified_chinese" => format!("无法读取模块{mod_name}({path}): {err}"),
+            "traditional_chinese" => format!("無法讀取模塊{mod_name}({path}): {err}"),
+            "english" => format!("cannot read module {mod_name} ({path}): {err}"),
+        );
+        let hint = (!candidates.is_empty()).then(|| {
+            let tried = switch_lang!(
+                "japanese" => "探索したパス",
+                "simplified_chinese" => "尝试过的路径",
+                "traditional_chinese" => "嘗試過的路徑",
+                "english" => "candidate paths tried",
+            );
+            let candidates = candidates
+                .iter()
+                .map(|path| format!("    {}", path.display()))
+                .collect::<Vec<_>>();
+            format!("{tried}:\n{}", candidates.join("\n"))
+        });
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], hint)],
+                desc,
+                errno,
+                ImportError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
+    /// `attr` of `mod_name` cannot be resolved because `mod_name` is not initialized yet in the import cycle `cycle`
+    pub fn cyclic_import_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        mod_name: &str,
+        attr: &str,
+        cycle: &str,
+    ) -> Self {
+        let found = StyledString::new(format!("{mod_name}.{attr}"), Some(ERR), Some(ATTR));
+        let hint = switch_lang!(
+            "japanese" => "モジュール間で共有する定義を別のモジュールに移して、循環を解消してください",
+            "simplified_chinese" => "请将模块间共享的定义移到单独的模块中，以消除循环",
+            "traditional_chinese" => "請將模組間共享的定義移到單獨的模組中，以消除循環",
+            "english" => "move the definitions shared by these modules into a separate module to break the cycle",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.to_string()))],
+                switch_lang!(
+                    "japanese" => format!("{found}は初期化前のため参照できません (循環インポート: {cycle})"),
+                    "simplified_chinese" => format!("{found}尚未初始化，无法引用 (循环导入: {cycle})"),
+                    "traditional_chinese" => format!("{found}尚未初始化，無法引用 (循環導入: {cycle})"),
+                    "english" => format!("{found} cannot be referred to before it is initialized (cyclic import: {cycle})"),
+                ),
+                errno,
+                ImportError,
+                loc,
+            )
+            .with_code(codes::CYCLIC_IMPORT),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn inner_typedef_error(
         input: Input,
         errno: usize,
@@ -1052,6 +1297,196 @@ impl LowerError {
             caused_by,
         )
     }
+
+    pub fn unsafe_python_type_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let hint = switch_lang!(
+            "japanese" => "例: `f: (x: Int) -> Int = unsafe_python \"\"\"...\"\"\"`",
+            "simplified_chinese" => "例如: `f: (x: Int) -> Int = unsafe_python \"\"\"...\"\"\"`",
+            "traditional_chinese" => "例如: `f: (x: Int) -> Int = unsafe_python \"\"\"...\"\"\"`",
+            "english" => "e.g. `f: (x: Int) -> Int = unsafe_python \"\"\"...\"\"\"`",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(
+                    loc,
+                    vec![],
+                    Some(hint.to_string()),
+                )],
+                switch_lang!(
+                    "japanese" => "unsafe_pythonの値は型を指定した変数に束縛されなければなりません",
+                    "simplified_chinese" => "unsafe_python 的值必须绑定到指定了类型的变量",
+                    "traditional_chinese" => "unsafe_python 的值必須綁定到指定了類型的變數",
+                    "english" => "the value of unsafe_python must be bound to a variable with a type specification",
+                ),
+                errno,
+                TypeError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
+    /// `asm` and `intrinsic`
+    pub fn intrinsic_type_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        name: &str,
+    ) -> Self {
+        let hint = switch_lang!(
+            "japanese" => format!("例: `add(x: Int, y: Int): Int = {name} ...`"),
+            "simplified_chinese" => format!("例如: `add(x: Int, y: Int): Int = {name} ...`"),
+            "traditional_chinese" => format!("例如: `add(x: Int, y: Int): Int = {name} ...`"),
+            "english" => format!("e.g. `add(x: Int, y: Int): Int = {name} ...`"),
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+                switch_lang!(
+                    "japanese" => format!("{name}の値は型を指定した変数(または戻り値の型を指定したサブルーチン)に束縛されなければなりません"),
+                    "simplified_chinese" => format!("{name} 的值必须绑定到指定了类型的变量(或指定了返回类型的子程序)"),
+                    "traditional_chinese" => format!("{name} 的值必須綁定到指定了類型的變數(或指定了返回類型的子程序)"),
+                    "english" => format!("the value of {name} must be bound to a variable with a type specification (or a subroutine with a return type specification)"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            )
+            .with_code(codes::NO_TYPE_SPEC),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn invalid_asm_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        err: &AsmError,
+    ) -> Self {
+        let msg = match err {
+            AsmError::UnknownOpcode(name) => switch_lang!(
+                "japanese" => format!("{name}はasmで使用できるオペコードではありません"),
+                "simplified_chinese" => format!("{name}不是可以在asm中使用的操作码"),
+                "traditional_chinese" => format!("{name}不是可以在asm中使用的操作碼"),
+                "english" => format!("{name} is not an opcode available in asm"),
+            ),
+            AsmError::MissingArg(op) => switch_lang!(
+                "japanese" => format!("{op}には引数が必要です"),
+                "simplified_chinese" => format!("{op}需要参数"),
+                "traditional_chinese" => format!("{op}需要參數"),
+                "english" => format!("{op} takes an argument"),
+            ),
+            AsmError::UnexpectedArg(op) => switch_lang!(
+                "japanese" => format!("{op}は引数を取りません"),
+                "simplified_chinese" => format!("{op}不接受参数"),
+                "traditional_chinese" => format!("{op}不接受參數"),
+                "english" => format!("{op} takes no argument"),
+            ),
+            AsmError::InvalidArg(op, arg) => switch_lang!(
+                "japanese" => format!("{arg}は{op}の引数として不正です"),
+                "simplified_chinese" => format!("{arg}不是{op}的有效参数"),
+                "traditional_chinese" => format!("{arg}不是{op}的有效參數"),
+                "english" => format!("{arg} is not a valid argument of {op}"),
+            ),
+            AsmError::StackUnderflow { op, depth } => switch_lang!(
+                "japanese" => format!("スタックアンダーフロー: {op}の実行時、スタックには{depth}個の値しかありません"),
+                "simplified_chinese" => format!("栈下溢: 执行{op}时栈中只有{depth}个值"),
+                "traditional_chinese" => format!("堆疊下溢: 執行{op}時堆疊中只有{depth}個值"),
+                "english" => format!("stack underflow: the stack has only {depth} value(s) when {op} is executed"),
+            ),
+            AsmError::Unbalanced { depth } => switch_lang!(
+                "japanese" => format!("スタック効果が釣り合っていません: 最後にスタックに残る値は1個でなければなりませんが、{depth}個です"),
+                "simplified_chinese" => format!("栈效应不平衡: 最后栈中必须只剩1个值，但剩下{depth}个"),
+                "traditional_chinese" => format!("堆疊效應不平衡: 最後堆疊中必須只剩1個值，但剩下{depth}個"),
+                "english" => format!("unbalanced stack effect: exactly 1 value must be left on the stack, but {depth} value(s) are left"),
+            ),
+        };
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                msg,
+                errno,
+                BytecodeError,
+                loc,
+            )
+            .with_code(codes::INVALID_ASM),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn asm_version_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        version: &str,
+    ) -> Self {
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("asmはPython 3.11向けにのみ使用できます (ターゲット: Python {version})"),
+                    "simplified_chinese" => format!("asm只能用于Python 3.11 (目标: Python {version})"),
+                    "traditional_chinese" => format!("asm只能用於Python 3.11 (目標: Python {version})"),
+                    "english" => format!("asm is available only for Python 3.11 (target: Python {version})"),
+                ),
+                errno,
+                BytecodeError,
+                loc,
+            )
+            .with_code(codes::INVALID_ASM),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn int_div_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        lhs_t: &Type,
+        rhs_t: &Type,
+    ) -> Self {
+        let lhs_t = StyledString::new(format!("{lhs_t}"), Some(ERR), Some(ATTR));
+        let rhs_t = StyledString::new(format!("{rhs_t}"), Some(ERR), Some(ATTR));
+        let hint = switch_lang!(
+            "japanese" => "切り捨て除算には`//`を、浮動小数点数の除算には`Float(x) / y`を使ってください",
+            "simplified_chinese" => "整除请使用`//`, 浮点除法请使用`Float(x) / y`",
+            "traditional_chinese" => "整除請使用`//`, 浮點除法請使用`Float(x) / y`",
+            "english" => "use `//` for the floor division, or `Float(x) / y` for the true division",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(
+                    loc,
+                    vec![],
+                    Some(hint.to_string()),
+                )],
+                switch_lang!(
+                    "japanese" => format!("{lhs_t} / {rhs_t}は割り切れない可能性があります"),
+                    "simplified_chinese" => format!("{lhs_t} / {rhs_t}可能无法整除"),
+                    "traditional_chinese" => format!("{lhs_t} / {rhs_t}可能無法整除"),
+                    "english" => format!("{lhs_t} / {rhs_t} may not be divisible"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
 }
 
 impl LowerWarning {
@@ -1114,6 +1549,39 @@ impl LowerWarning {
         )
     }
 
+    pub fn type_hole_warning(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        typ: &Type,
+    ) -> Self {
+        let typ = StyledString::new(format!("{typ}"), Some(HINT), Some(ATTR));
+        let hint = switch_lang!(
+            "japanese" => "`_`を式または型に置き換えてください",
+            "simplified_chinese" => "请将`_`替换为表达式或类型",
+            "traditional_chinese" => "請將`_`替換為表達式或類型",
+            "english" => "replace `_` with an expression or a type",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.into()))],
+                switch_lang!(
+                    "japanese" => format!("型ホールの型は{typ}と推論されました"),
+                    "simplified_chinese" => format!("类型洞的类型被推断为{typ}"),
+                    "traditional_chinese" => format!("類型洞的類型被推斷為{typ}"),
+                    "english" => format!("the type of the hole is inferred as {typ}"),
+                ),
+                errno,
+                TypeWarning,
+                loc,
+            )
+            .with_code(codes::TYPE_HOLE),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn builtin_exists_warning(
         input: Input,
         errno: usize,
@@ -1140,6 +1608,79 @@ impl LowerWarning {
         )
     }
 
+    /// `path` (which is imported as `mod_name`) shadows the modules of the same name in the later search roots
+    pub fn module_shadowing_warning(
+        input: Input,
+        errno: usize,
+        mod_name: &str,
+        path: &Path,
+        shadowed: &[PathBuf],
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let shadowed = shadowed
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let path = path.display();
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("モジュール{mod_name}({path})は{shadowed}を隠しています"),
+                    "simplified_chinese" => format!("模块{mod_name}({path})遮蔽了{shadowed}"),
+                    "traditional_chinese" => format!("模組{mod_name}({path})遮蔽了{shadowed}"),
+                    "english" => format!("module {mod_name} ({path}) shadows {shadowed}"),
+                ),
+                errno,
+                ImportWarning,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
+    /// The files of `mod_name` are distinguished on this (case-sensitive) file system,
+    /// but the module cannot be imported correctly on case-insensitive file systems.
+    pub fn module_collision_warning(
+        input: Input,
+        errno: usize,
+        mod_name: &str,
+        collisions: &[PathBuf],
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let files = collisions
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let hint = switch_lang!(
+            "japanese" => "大文字小文字を区別しないファイルシステム(Windows, macOSなど)では同じファイルとして扱われます",
+            "simplified_chinese" => "在不区分大小写的文件系统 (Windows, macOS等) 中它们被视为同一文件",
+            "traditional_chinese" => "在不區分大小寫的檔案系統 (Windows, macOS等) 中它們被視為同一檔案",
+            "english" => "they are treated as the same file on case-insensitive file systems (e.g. Windows, macOS)",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.into()))],
+                switch_lang!(
+                    "japanese" => format!("モジュール{mod_name}のファイル名が大文字小文字の違いのみで衝突しています: {files}"),
+                    "simplified_chinese" => format!("模块{mod_name}的文件名仅大小写不同: {files}"),
+                    "traditional_chinese" => format!("模塊{mod_name}的檔名僅大小寫不同: {files}"),
+                    "english" => format!("the file names of module {mod_name} differ only in case: {files}"),
+                ),
+                errno,
+                ImportWarning,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn use_cast_warning(input: Input, errno: usize, loc: Location, caused_by: String) -> Self {
         Self::new(
             ErrorCore::new(
@@ -1159,6 +1700,36 @@ impl LowerWarning {
         )
     }
 
+    pub fn unreachable_code_warning(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let hint = switch_lang!(
+            "japanese" => "直前の式の型はNeverです(値を返しません)",
+            "simplified_chinese" => "前一个表达式的类型是Never(不返回值)",
+            "traditional_chinese" => "前一個表達式的類型是Never(不返回值)",
+            "english" => "the type of the preceding expression is Never (it does not return)",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.into()))],
+                switch_lang!(
+                    "japanese" => "到達不能なコードです",
+                    "simplified_chinese" => "无法到达的代码",
+                    "traditional_chinese" => "無法到達的程式碼",
+                    "english" => "unreachable code",
+                ),
+                errno,
+                UnusedWarning,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn same_name_instance_attr_warning(
         input: Input,
         errno: usize,
@@ -1184,4 +1755,59 @@ impl LowerWarning {
             caused_by,
         )
     }
+
+    pub fn decl_not_found_warning(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        module: &str,
+        name: &str,
+    ) -> Self {
+        let name = StyledStr::new(readable_name(name), Some(WARN), Some(ATTR));
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("{name}が宣言されていますが、実行環境のモジュール{module}には存在しません"),
+                    "simplified_chinese" => format!("{name}已声明，但运行环境的模块{module}中不存在"),
+                    "traditional_chinese" => format!("{name}已聲明，但執行環境的模組{module}中不存在"),
+                    "english" => format!("{name} is declared, but does not exist in the runtime module {module}"),
+                ),
+                errno,
+                AttributeWarning,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn decl_signature_warning(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        name: &str,
+        runtime_sig: &str,
+    ) -> Self {
+        let name = StyledStr::new(readable_name(name), Some(WARN), Some(ATTR));
+        let sig = StyledString::new(runtime_sig, Some(WARN), Some(ATTR));
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("{name}の宣言されたパラメータは実行時のシグネチャ{sig}と一致しません"),
+                    "simplified_chinese" => format!("{name}的声明参数与运行时签名{sig}不匹配"),
+                    "traditional_chinese" => format!("{name}的聲明參數與執行時簽名{sig}不匹配"),
+                    "english" => format!("the declared parameters of {name} do not match the runtime signature {sig}"),
+                ),
+                errno,
+                TypeWarning,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
 }
@@ -1,3 +1,5 @@
+pub mod codes;
+pub mod diff;
 pub mod eval;
 pub mod lower;
 pub mod tycheck;
@@ -13,10 +15,12 @@ use erg_common::traits::{Locational, Stream};
 use erg_common::{impl_display_and_error, impl_stream, switch_lang};
 
 use erg_parser::error::{ParseError, ParseErrors, ParserRunnerError, ParserRunnerErrors};
+use erg_parser::feature::Feature;
 
 pub use crate::error::eval::*;
 pub use crate::error::lower::*;
 pub use crate::error::tycheck::*;
+use crate::crash::Crash;
 use crate::hir::Expr;
 
 /// `unreachable!(self: Context)`
@@ -217,6 +221,9 @@ const ATTR: Attribute = Attribute::Bold;
 #[cfg(feature = "pretty")]
 const ATTR: Attribute = Attribute::Underline;
 
+/// the maximum length of the AST snippet attached to an internal compiler error
+const SNIPPET_LEN: usize = 80;
+
 const URL: StyledStr = StyledStr::new(
     "https://github.com/erg-lang/erg",
     Some(ACCENT),
@@ -258,6 +265,53 @@ impl CompileError {
         )
     }
 
+    /// A panic caught in the `--no-panic` mode.
+    /// `snippet` is the AST of the chunk being lowered when the compiler panicked.
+    pub fn internal_compiler_error(
+        input: Input,
+        loc: Location,
+        crash: &Crash,
+        snippet: Option<String>,
+        caused_by: String,
+    ) -> Self {
+        let mut hints = vec![format!(
+            "panicked at {} (phase: {})",
+            crash.location,
+            crash.phase()
+        )];
+        if let Some(snippet) = snippet {
+            let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+            let snippet = match snippet.char_indices().nth(SNIPPET_LEN) {
+                Some((idx, _)) => format!("{}...", &snippet[..idx]),
+                None => snippet,
+            };
+            hints.push(format!("AST: {snippet}"));
+        }
+        let hint = switch_lang!(
+            "japanese" => format!("これはErg compilerのバグです、開発者に報告して下さい ({URL})"),
+            "simplified_chinese" => format!("这是Erg编译器的错误，请报告给{URL}"),
+            "traditional_chinese" => format!("這是Erg編譯器的錯誤，請報告給{URL}"),
+            "english" => format!("this is a bug of the Erg compiler, please report it to {URL}"),
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, hints, Some(hint))],
+                switch_lang!(
+                    "japanese" => format!("コンパイラの内部エラー: {}", crash.message),
+                    "simplified_chinese" => format!("编译器内部错误: {}", crash.message),
+                    "traditional_chinese" => format!("編譯器內部錯誤: {}", crash.message),
+                    "english" => format!("internal compiler error: {}", crash.message),
+                ),
+                0,
+                CompilerSystemError,
+                loc,
+            )
+            .with_code(codes::COMPILER_BUG),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn stack_bug(
         input: Input,
         loc: Location,
@@ -314,6 +368,38 @@ caused from: {fn_name}"),
         )
     }
 
+    pub fn unstable_feature_error(
+        input: Input,
+        loc: Location,
+        name: &str,
+        feat: Feature,
+        caused_by: String,
+    ) -> Self {
+        let attr = StyledString::new(format!("#!feature({feat})"), Some(HINT), Some(ATTR));
+        let hint = switch_lang!(
+            "japanese" => format!("モジュールの先頭に{attr}を追加すると使用できます"),
+            "simplified_chinese" => format!("在模块顶部添加{attr}即可使用"),
+            "traditional_chinese" => format!("在模塊頂部添加{attr}即可使用"),
+            "english" => format!("add {attr} at the top of the module to enable it"),
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+                switch_lang!(
+                    "japanese" => format!("この機能({name})は不安定です"),
+                    "simplified_chinese" => format!("此功能（{name}）不稳定"),
+                    "traditional_chinese" => format!("此功能（{name}）不穩定"),
+                    "english" => format!("this feature({name}) is unstable"),
+                ),
+                0,
+                FeatureError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn system_exit() -> Self {
         Self::new(
             ErrorCore::new(
@@ -426,6 +512,38 @@ impl EffectError {
             caused_by,
         )
     }
+
+    pub fn cyclic_init_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        cycle: &str,
+        effectful_mod: &str,
+        caused_by: String,
+    ) -> Self {
+        let hint = switch_lang!(
+            "japanese" => format!("{effectful_mod}はトップレベルに副作用を持つため、初期化順序が定まりません。副作用をプロシージャに移すか、循環を解消してください"),
+            "simplified_chinese" => format!("{effectful_mod}在顶层有副作用，因此初始化顺序不确定。请将副作用移入过程中，或消除循环"),
+            "traditional_chinese" => format!("{effectful_mod}在頂層有副作用，因此初始化順序不確定。請將副作用移入過程中，或消除循環"),
+            "english" => format!("{effectful_mod} has top-level side effects, so the initialization order is ambiguous. Move the side effects into a procedure, or break the cycle"),
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+                switch_lang!(
+                    "japanese" => format!("モジュールが循環的にインポートされています: {cycle}"),
+                    "simplified_chinese" => format!("模块被循环导入: {cycle}"),
+                    "traditional_chinese" => format!("模塊被循環導入: {cycle}"),
+                    "english" => format!("modules are imported cyclically: {cycle}"),
+                ),
+                errno,
+                HasEffect,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
 }
 
 pub type OwnershipError = CompileError;
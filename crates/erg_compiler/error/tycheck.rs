@@ -7,6 +7,7 @@ use erg_common::style::{StyledStr, StyledString, StyledStrings, Stylize};
 use erg_common::traits::{Locational, NoTypeDisplay};
 use erg_common::{fmt_iter, fmt_option_map, fmt_vec, fmt_vec_split_with, switch_lang, Str};
 
+use crate::error::diff::diff_types;
 use crate::error::*;
 use crate::ty::{ParamTy, Predicate, TyParam, Type};
 
@@ -21,7 +22,11 @@ impl TyCheckError {
     }
 
     pub fn unreachable(input: Input, fn_name: &str, line: u32) -> Self {
-        Self::new(ErrorCore::unreachable(fn_name, line), input, "".to_string())
+        Self::new(
+            ErrorCore::unreachable(fn_name, line).with_code(codes::COMPILER_BUG),
+            input,
+            "".to_string(),
+        )
     }
 
     pub fn checker_bug(
@@ -43,7 +48,8 @@ impl TyCheckError {
                 errno,
                 CompilerSystemError,
                 loc,
-            ),
+            )
+            .with_code(codes::COMPILER_BUG),
             input,
             "".to_string(),
         )
@@ -69,7 +75,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::NO_TYPE_SPEC),
             input,
             caused_by,
         )
@@ -99,7 +106,8 @@ impl TyCheckError {
                 errno,
                 NotImplementedError,
                 callee.loc(),
-            ),
+            )
+            .with_code(codes::NOT_CALLABLE),
             input,
             caused_by,
         )
@@ -128,6 +136,7 @@ impl TyCheckError {
             None => "".to_owned(),
         };
         let name = format!("{name}{ord}").with_color_and_attr(WARN, ATTR);
+        let diff = diff_types(expect, found);
         let mut expct = StyledStrings::default();
         switch_lang!(
             "japanese" => expct.push_str("予期した型: "),
@@ -135,7 +144,10 @@ impl TyCheckError {
             "traditional_chinese" => expct.push_str("預期: "),
             "english" => expct.push_str("expected: "),
         );
-        expct.push_str_with_color_and_attr(format!("{expect}"), HINT, ATTR);
+        match &diff {
+            Some((expect, _)) => expct.push_str(expect),
+            None => expct.push_str_with_color_and_attr(format!("{expect}"), HINT, ATTR),
+        }
 
         let mut fnd = StyledStrings::default();
         switch_lang!(
@@ -144,7 +156,10 @@ impl TyCheckError {
             "traditional_chinese" => fnd.push_str("但找到: "),
             "english" =>fnd.push_str("but found: "),
         );
-        fnd.push_str_with_color_and_attr(format!("{found}"), ERR, ATTR);
+        match &diff {
+            Some((_, found)) => fnd.push_str(found),
+            None => fnd.push_str_with_color_and_attr(format!("{found}"), ERR, ATTR),
+        }
         Self::new(
             ErrorCore::new(
                 vec![SubMessage::ambiguous_new(
@@ -161,7 +176,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::TYPE_MISMATCH),
             input,
             caused_by,
         )
@@ -213,7 +229,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::RETURN_TYPE),
             input,
             caused_by,
         )
@@ -239,7 +256,8 @@ impl TyCheckError {
                 errno,
                 NameError,
                 loc,
-            ),
+            )
+            .with_code(codes::UNINITIALIZED),
             input,
             caused_by,
         )
@@ -287,7 +305,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::ARGUMENT),
             input,
             caused_by,
         )
@@ -335,7 +354,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::PARAM),
             input,
             caused_by,
         )
@@ -360,7 +380,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::DEFAULT_PARAM),
             input,
             caused_by,
         )
@@ -400,7 +421,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::DEFAULT_PARAM_NOT_FOUND),
             input,
             caused_by,
         )
@@ -433,7 +455,8 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::MATCH),
             input,
             caused_by,
         )
@@ -458,18 +481,27 @@ impl TyCheckError {
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::INFER),
             input,
             caused_by,
         )
     }
 
     pub fn dummy_infer_error(input: Input, fn_name: &str, line: u32) -> Self {
-        Self::new(ErrorCore::unreachable(fn_name, line), input, "".to_owned())
+        Self::new(
+            ErrorCore::unreachable(fn_name, line).with_code(codes::COMPILER_BUG),
+            input,
+            "".to_owned(),
+        )
     }
 
     pub fn not_relation(input: Input, fn_name: &str, line: u32) -> Self {
-        Self::new(ErrorCore::unreachable(fn_name, line), input, "".to_owned())
+        Self::new(
+            ErrorCore::unreachable(fn_name, line).with_code(codes::COMPILER_BUG),
+            input,
+            "".to_owned(),
+        )
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -521,7 +553,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::TOO_MANY_ARGS),
             input,
             caused_by,
         )
@@ -550,7 +583,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::ARGS_MISSING),
             input,
             caused_by,
         )
@@ -578,7 +612,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::MULTIPLE_ARGS),
             input,
             caused_by,
         )
@@ -619,7 +654,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::UNEXPECTED_KW_ARG),
             input,
             caused_by,
         )
@@ -665,7 +701,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::UNIFICATION),
             input,
             caused_by,
         )
@@ -711,7 +748,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::RE_UNIFICATION),
             input,
             caused_by,
         )
@@ -764,7 +802,64 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::SUBTYPING),
+            input,
+            caused_by,
+        )
+    }
+
+    /// `path`: the types from the type that `var` would be linked to, down to `var` itself
+    /// (e.g. `var: ?T, path: [Array(Array(?T)), Array(?T), ?T]`)
+    pub fn cyclic_type_error<T: Display>(
+        input: Input,
+        errno: usize,
+        var: &T,
+        path: &[T],
+        loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let styled = |t: &T, color| {
+            let mut s = StyledStrings::default();
+            s.push_str_with_color_and_attr(format!("{t}"), color, ATTR);
+            s
+        };
+        let mut cycle = vec![];
+        if let Some(outermost) = path.first() {
+            let mut link = styled(var, ERR);
+            link.push_str(" = ");
+            link.push_str(&styled(outermost, HINT).to_string());
+            cycle.push(link.to_string());
+        }
+        for (outer, inner) in path.iter().zip(path.iter().skip(1)) {
+            let (outer, inner) = (styled(outer, HINT), styled(inner, ERR));
+            cycle.push(switch_lang!(
+                "japanese" => format!("{outer}は{inner}を含みます"),
+                "simplified_chinese" => format!("{outer}包含{inner}"),
+                "traditional_chinese" => format!("{outer}包含{inner}"),
+                "english" => format!("{outer} contains {inner}"),
+            ));
+        }
+        let hint = switch_lang!(
+            "japanese" => "型変数が自身を含む型に束縛されるため、型が無限に展開されます。型を明示的に指定してください。",
+            "simplified_chinese" => "类型变量将绑定到包含自身的类型，因此该类型将无限展开。请明确指定类型。",
+            "traditional_chinese" => "類型變量將綁定到包含自身的類型，因此該類型將無限展開。請明確指定類型。",
+            "english" => "the type variable would be bound to a type containing itself, so the type would be infinite. Please specify the type explicitly.",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, cycle, Some(hint.to_string()))],
+                switch_lang!(
+                    "japanese" => format!("循環した型です: {}は自身を含む型になります", styled(var, ERR)),
+                    "simplified_chinese" => format!("循环类型: {}将成为包含自身的类型", styled(var, ERR)),
+                    "traditional_chinese" => format!("循環類型: {}將成為包含自身的類型", styled(var, ERR)),
+                    "english" => format!("cyclic type: {} would be a type containing itself", styled(var, ERR)),
+                ),
+                errno,
+                TypeError,
+                loc,
+            )
+            .with_code(codes::CYCLIC_TYPE),
             input,
             caused_by,
         )
@@ -811,7 +906,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::INVARIANT),
             input,
             caused_by,
         )
@@ -857,7 +953,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::PRED_UNIFICATION),
             input,
             caused_by,
         )
@@ -883,7 +980,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::NO_CANDIDATE),
             input,
             caused_by,
         )
@@ -910,7 +1008,45 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::NO_TRAIT_IMPL),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn not_derivable_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        trait_: &Type,
+    ) -> Self {
+        let found = trait_.to_string().with_color_and_attr(ERR, ATTR);
+        let hint = switch_lang!(
+            "japanese" => "導出できるトレイトはEq, Ord, Hash, Showです",
+            "simplified_chinese" => "可派生的trait为Eq、Ord、Hash和Show",
+            "traditional_chinese" => "可派生的trait為Eq、Ord、Hash和Show",
+            "english" => "the derivable traits are Eq, Ord, Hash and Show",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(
+                    loc,
+                    vec![],
+                    Some(hint.to_string()),
+                )],
+                switch_lang!(
+                    "japanese" => format!("{found}は導出できません"),
+                    "simplified_chinese" => format!("{found}无法派生"),
+                    "traditional_chinese" => format!("{found}無法派生"),
+                    "english" => format!("{found} cannot be derived"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            )
+            .with_code(codes::NOT_DERIVABLE),
             input,
             caused_by,
         )
@@ -945,7 +1081,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 MethodError,
                 loc,
-            ),
+            )
+            .with_code(codes::METHOD_DEFINITION),
             input,
             caused_by,
         )
@@ -1017,7 +1154,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::TRAIT_MEMBER_TYPE),
             input,
             caused_by,
         )
@@ -1047,7 +1185,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::TRAIT_MEMBER_NOT_DEFINED),
             input,
             caused_by,
         )
@@ -1077,7 +1216,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::NOT_IN_TRAIT),
             input,
             caused_by,
         )
@@ -1103,7 +1243,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::TYVAR_NOT_DEFINED),
             input,
             caused_by,
         )
@@ -1183,7 +1324,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 expr.loc(),
-            ),
+            )
+            .with_code(codes::AMBIGUOUS_TYPE),
             input,
             caused_by,
         )
@@ -1234,7 +1376,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 attr.loc(),
-            ),
+            )
+            .with_code(codes::AMBIGUOUS_METHOD),
             input,
             caused_by,
         )
@@ -1259,7 +1402,72 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::TP_TO_TYPE),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn not_polymorphic_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        t: &Type,
+    ) -> Self {
+        let found = StyledString::new(format!("{t}"), Some(ERR), Some(ATTR));
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("{found}は多相型ではないので、型引数を適用できません"),
+                    "simplified_chinese" => format!("{found}不是多态类型，因此无法应用类型参数"),
+                    "traditional_chinese" => format!("{found}不是多態類型，因此無法應用類型參數"),
+                    "english" => format!("{found} is not polymorphic, so type arguments cannot be applied"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            )
+            .with_code(codes::NOT_POLYMORPHIC),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn ambiguous_type_args_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        t: &Type,
+        params: &[Str],
+    ) -> Self {
+        let found = StyledString::new(format!("{t}"), Some(ERR), Some(ATTR));
+        let hint = params.first().map(|param| {
+            let param = StyledString::new(format!("{param} := ..."), Some(HINT), Some(ATTR));
+            switch_lang!(
+                "japanese" => format!("型引数を名前で指定してください(例: {param})"),
+                "simplified_chinese" => format!("请按名称指定类型参数(例如: {param})"),
+                "traditional_chinese" => format!("請按名稱指定類型參數(例如: {param})"),
+                "english" => format!("specify the type arguments by name (e.g. {param})"),
+            )
+        });
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], hint)],
+                switch_lang!(
+                    "japanese" => format!("{found}の位置型引数は曖昧です"),
+                    "simplified_chinese" => format!("{found}的位置类型参数不明确"),
+                    "traditional_chinese" => format!("{found}的位置類型參數不明確"),
+                    "english" => format!("the positional type arguments of {found} are ambiguous"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            )
+            .with_code(codes::AMBIGUOUS_TYPE_ARGS),
             input,
             caused_by,
         )
@@ -1286,7 +1494,7 @@ passed keyword args:    {kw_args_len}"
             "english" => format!("Implicitly widening {before_} to {after_} is prohibited by default. Consider widening the type explicitly using `as`."),
         );
         // actually, this error will be overwritten, only `input`, `hint` and `loc` is useful
-        Self::type_mismatch_error(
+        let mut err = Self::type_mismatch_error(
             input,
             errno,
             loc,
@@ -1297,9 +1505,12 @@ passed keyword args:    {kw_args_len}"
             &Type::Uninited,
             None,
             Some(hint),
-        )
+        );
+        err.core.code = Some(codes::IMPLICIT_WIDENING);
+        err
     }
 
+    /// `candidates`: the overloaded types and the reasons why they do not match
     pub fn overload_error(
         input: Input,
         errno: usize,
@@ -1307,21 +1518,55 @@ passed keyword args:    {kw_args_len}"
         caused_by: String,
         pos_args: Vec<ParamTy>,
         kw_args: Vec<ParamTy>,
-        found: Vec<Type>,
+        candidates: Vec<(Type, String)>,
     ) -> Self {
+        let candidates = candidates
+            .iter()
+            .map(|(t, reason)| format!("{t}\n    {reason}"))
+            .collect::<Vec<_>>();
         Self::new(
             ErrorCore::new(
                 vec![],
                 switch_lang!(
-                    "japanese" => format!("オーバーロード解決に失敗しました\nオーバーロード型:\n* {}\n渡された位置引数: {}\n渡された名前付き引数: {}", fmt_vec_split_with(&found, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
-                    "simplified_chinese" => format!("无法解析重载\n重载类型:\n* {}\n位置参数: {}\n命名参数: {}", fmt_vec_split_with(&found, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
-                    "traditional_chinese" => format!("無法解析重載\n重載類型:\n* {}\n位置參數: {}\n命名參數: {}", fmt_vec_split_with(&found, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
-                    "english" => format!("cannot resolve overload\noverloaded type:\n* {}\npassed positional arguments: {}\npassed named arguments: {}", fmt_vec_split_with(&found, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
+                    "japanese" => format!("オーバーロード解決に失敗しました\nオーバーロード型:\n* {}\n渡された位置引数: {}\n渡された名前付き引数: {}", fmt_vec_split_with(&candidates, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
+                    "simplified_chinese" => format!("无法解析重载\n重载类型:\n* {}\n位置参数: {}\n命名参数: {}", fmt_vec_split_with(&candidates, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
+                    "traditional_chinese" => format!("無法解析重載\n重載類型:\n* {}\n位置參數: {}\n命名參數: {}", fmt_vec_split_with(&candidates, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
+                    "english" => format!("cannot resolve overload\noverloaded type:\n* {}\npassed positional arguments: {}\npassed named arguments: {}", fmt_vec_split_with(&candidates, "\n* "), fmt_vec(&pos_args), fmt_vec(&kw_args)),
                 ),
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::OVERLOAD),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn index_out_of_bounds_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        len: &TyParam,
+        idx_t: &Type,
+    ) -> Self {
+        let len = StyledString::new(format!("{len}"), Some(WARN), Some(ATTR));
+        let idx_t = StyledString::new(format!("{idx_t}"), Some(ERR), Some(ATTR));
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("インデックス(型: {idx_t})が配列の範囲(長さ: {len})を超えています"),
+                    "simplified_chinese" => format!("索引(类型: {idx_t})超出了数组的范围(长度: {len})"),
+                    "traditional_chinese" => format!("索引(類型: {idx_t})超出了陣列的範圍(長度: {len})"),
+                    "english" => format!("the index (type: {idx_t}) exceeds the bounds of the array (length: {len})"),
+                ),
+                errno,
+                IndexError,
+                loc,
+            )
+            .with_code(codes::INDEX_OUT_OF_BOUNDS),
             input,
             caused_by,
         )
@@ -1340,7 +1585,8 @@ passed keyword args:    {kw_args_len}"
                 errno,
                 TypeError,
                 loc,
-            ),
+            )
+            .with_code(codes::SELF_TYPE),
             input,
             caused_by,
         )
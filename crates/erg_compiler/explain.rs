@@ -0,0 +1,69 @@
+//! defines `Explainer` (`erg explain`).
+//!
+//! `erg explain` prints the description of an error code (see `error::codes`).
+//! Without a code, it lists all the codes.
+//!
+//! ```sh
+//! erg explain E0004
+//! ```
+use std::fmt::Write as _;
+
+use erg_common::config::ErgConfig;
+use erg_common::error::ErrorCode;
+use erg_common::levenshtein::get_similar_name;
+use erg_common::traits::ExitStatus;
+
+use crate::error::codes::{get_entry, ErrorCodeEntry, ERROR_CODES};
+
+pub struct Explainer;
+
+impl Explainer {
+    pub fn explain(entry: &ErrorCodeEntry) -> String {
+        let mut text = format!(
+            "{}: {}\n\n{}\n",
+            entry.code, entry.summary, entry.description
+        );
+        if let Some(example) = entry.example {
+            text += "\nErroneous code example:\n\n";
+            for line in example.lines() {
+                let _ = writeln!(text, "    {line}");
+            }
+        }
+        text
+    }
+
+    pub fn list() -> String {
+        let mut text = String::new();
+        for entry in ERROR_CODES.iter() {
+            let _ = writeln!(text, "{}: {}", entry.code, entry.summary);
+        }
+        text
+    }
+
+    pub fn run(mut cfg: ErgConfig) -> ExitStatus {
+        if cfg.input.is_repl() {
+            print!("{}", Self::list());
+            return ExitStatus::OK;
+        }
+        let code = cfg.input.read();
+        let code = code.trim();
+        match code.parse::<ErrorCode>().ok().and_then(get_entry) {
+            Some(entry) => {
+                print!("{}", Self::explain(entry));
+                ExitStatus::OK
+            }
+            None => {
+                let codes = ERROR_CODES
+                    .iter()
+                    .map(|entry| entry.code.to_string())
+                    .collect::<Vec<_>>();
+                if let Some(similar) = get_similar_name(codes.iter().map(|s| &s[..]), code) {
+                    eprintln!("unknown error code: {code} (did you mean `{similar}`?)");
+                } else {
+                    eprintln!("unknown error code: {code}");
+                }
+                ExitStatus::ERR1
+            }
+        }
+    }
+}
@@ -0,0 +1,406 @@
+//! defines `Fixer` (`erg fix`).
+//!
+//! `erg fix` rewrites deprecated constructs automatically, so that upgrading the compiler doesn't require manual edits.
+//! Each rewrite is a `FixRule` in `RULES`. Every rule sees the token stream (which keeps the source positions),
+//! and the rules with `needs_types` also see the HIR (only if the module passes the type check).
+//!
+//! ```sh
+//! erg fix foo.er
+//! ```
+use std::fmt;
+use std::fs;
+
+use erg_common::config::ErgConfig;
+use erg_common::error::Location;
+use erg_common::normalize_newline;
+use erg_common::traits::{DequeStream, ExitStatus, Locational, Runnable};
+
+use erg_parser::ast::OperationKind;
+use erg_parser::build_ast::ASTBuilder;
+use erg_parser::feature::{Feature, Features};
+use erg_parser::lex::Lexer;
+use erg_parser::token::{TokenKind, TokenStream};
+
+use crate::context::Context;
+use crate::hir::{self, Expr, HIR};
+use crate::lower::ASTLowerer;
+use crate::ty::HasType;
+
+/// A replacement of the source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub loc: Location,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn new(loc: Location, replacement: impl Into<String>) -> Self {
+        Self {
+            loc,
+            replacement: replacement.into(),
+        }
+    }
+
+    pub fn insert(line: u32, col: u32, text: impl Into<String>) -> Self {
+        Self::new(Location::range(line, col, line, col), text)
+    }
+}
+
+/// The information passed to a `FixRule`
+pub struct FixContext<'a> {
+    pub src: &'a str,
+    /// lexed with all the unstable features enabled
+    pub tokens: &'a TokenStream,
+    /// features enabled by `#!feature(...)`
+    pub features: Features,
+    /// `Some` only if the rule `needs_types` and the module passes the type check
+    pub hir: Option<&'a HIR>,
+    pub context: Option<&'a Context>,
+}
+
+impl FixContext<'_> {
+    /// The source code of `loc` (the columns are counted in characters)
+    pub fn source_of(&self, loc: Location) -> Option<&str> {
+        let (begin, end) = span_of(self.src, loc)?;
+        Some(&self.src[begin..end])
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FixRule {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub needs_types: bool,
+    pub fix: fn(&FixContext) -> Vec<Edit>,
+}
+
+/// Rules are applied in this order, each to the result of the previous one.
+pub const RULES: [FixRule; 2] = [
+    FixRule {
+        name: "implicit_mul",
+        description: "`3x` is unstable (`#!feature(implicit_mul)`), replaced with `3 * x`",
+        needs_types: false,
+        fix: fix_implicit_mul,
+    },
+    FixRule {
+        name: "redundant_cast",
+        description: "`typing.cast` is not needed for upcasting, replaced with `as`",
+        needs_types: true,
+        fix: fix_redundant_cast,
+    },
+];
+
+/// `3x` => `3 * x`
+///
+/// The lexer inserts `*` at the position of `x`.
+fn fix_implicit_mul(ctx: &FixContext) -> Vec<Edit> {
+    if ctx.features.contains(Feature::ImplicitMul) {
+        return vec![];
+    }
+    let mut edits = vec![];
+    let tokens = ctx.tokens.ref_payload();
+    let triples = tokens
+        .iter()
+        .zip(tokens.iter().skip(1))
+        .zip(tokens.iter().skip(2));
+    for ((num, star), rhs) in triples {
+        if matches!(num.kind, TokenKind::NatLit | TokenKind::IntLit)
+            && star.is(TokenKind::Star)
+            && star.lineno == rhs.lineno
+            && star.col_begin == rhs.col_begin
+        {
+            if let (Some(line), Some(col)) = (num.loc().ln_end(), num.loc().col_end()) {
+                edits.push(Edit::insert(line, col, " * "));
+            }
+        }
+    }
+    edits
+}
+
+/// `typing.cast(Int, n)` (`n: Nat`) => `(n as Int)`, `typing.cast(Int, i)` (`i: Int`) => `i`
+fn fix_redundant_cast(ctx: &FixContext) -> Vec<Edit> {
+    let mut edits = vec![];
+    if let (Some(hir), Some(context)) = (ctx.hir, ctx.context) {
+        for chunk in hir.module.iter() {
+            walk_calls(chunk, &mut |call| {
+                if call.additional_operation() != Some(OperationKind::Cast) {
+                    return;
+                }
+                // typing.cast(typ, val)
+                let (typ, val) = match (call.args.get(0), call.args.get(1)) {
+                    (Some(typ), Some(val)) => (typ, val),
+                    _ => return,
+                };
+                let (val_t, target_t) = (val.ref_t(), call.ref_t());
+                if !context.subtype_of(val_t, target_t) {
+                    return;
+                }
+                let (val_src, typ_src) = match (ctx.source_of(val.loc()), ctx.source_of(typ.loc()))
+                {
+                    (Some(val_src), Some(typ_src)) => (val_src, typ_src),
+                    _ => return,
+                };
+                let replacement = if context.same_type_of(val_t, target_t) {
+                    if matches!(val, Expr::Accessor(_) | Expr::Lit(_)) {
+                        val_src.to_string()
+                    } else {
+                        format!("({val_src})")
+                    }
+                } else {
+                    format!("({val_src} as {typ_src})")
+                };
+                edits.push(Edit::new(call.loc(), replacement));
+            });
+        }
+    }
+    edits
+}
+
+fn walk_calls(expr: &Expr, f: &mut impl FnMut(&hir::Call)) {
+    match expr {
+        Expr::Call(call) => {
+            f(call);
+            walk_calls(&call.obj, f);
+            for arg in call.args.pos_args.iter() {
+                walk_calls(&arg.expr, f);
+            }
+            if let Some(var_args) = call.args.var_args.as_ref() {
+                walk_calls(&var_args.expr, f);
+            }
+            for arg in call.args.kw_args.iter() {
+                walk_calls(&arg.expr, f);
+            }
+        }
+        Expr::Accessor(hir::Accessor::Attr(attr)) => walk_calls(&attr.obj, f),
+        Expr::BinOp(bin) => {
+            walk_calls(&bin.lhs, f);
+            walk_calls(&bin.rhs, f);
+        }
+        Expr::UnaryOp(unary) => walk_calls(&unary.expr, f),
+        Expr::Array(hir::Array::Normal(arr)) => {
+            for elem in arr.elems.pos_args.iter() {
+                walk_calls(&elem.expr, f);
+            }
+        }
+        Expr::Tuple(hir::Tuple::Normal(tup)) => {
+            for elem in tup.elems.pos_args.iter() {
+                walk_calls(&elem.expr, f);
+            }
+        }
+        Expr::Record(rec) => {
+            for attr in rec.attrs.iter() {
+                for chunk in attr.body.block.iter() {
+                    walk_calls(chunk, f);
+                }
+            }
+        }
+        Expr::Lambda(lambda) => {
+            if let Some(guard) = &lambda.guard {
+                walk_calls(guard, f);
+            }
+            for chunk in lambda.body.iter() {
+                walk_calls(chunk, f);
+            }
+        }
+        Expr::Def(def) => {
+            for chunk in def.body.block.iter() {
+                walk_calls(chunk, f);
+            }
+        }
+        Expr::ClassDef(class_def) => {
+            for method in class_def.methods.iter() {
+                walk_calls(method, f);
+            }
+        }
+        Expr::PatchDef(patch_def) => {
+            for method in patch_def.methods.iter() {
+                walk_calls(method, f);
+            }
+        }
+        Expr::ReDef(redef) => {
+            for chunk in redef.block.iter() {
+                walk_calls(chunk, f);
+            }
+        }
+        Expr::TypeAsc(tasc) => walk_calls(&tasc.expr, f),
+        Expr::Coerce(coerce) => walk_calls(&coerce.expr, f),
+        Expr::Code(block) | Expr::Compound(block) => {
+            for chunk in block.iter() {
+                walk_calls(chunk, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// (line, col) (1-origin line, 0-origin col in characters) => byte offset
+fn offset_of(src: &str, line: u32, col: u32) -> Option<usize> {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        src.match_indices('\n').nth(line as usize - 2)?.0 + 1
+    };
+    let line_src = &src[line_start..];
+    let line_len = line_src.find('\n').unwrap_or(line_src.len());
+    match line_src[..line_len].char_indices().nth(col as usize) {
+        Some((i, _)) => Some(line_start + i),
+        // the end of the line
+        None if col as usize == line_src[..line_len].chars().count() => Some(line_start + line_len),
+        None => None,
+    }
+}
+
+fn span_of(src: &str, loc: Location) -> Option<(usize, usize)> {
+    let begin = offset_of(src, loc.ln_begin()?, loc.col_begin()?)?;
+    let end = offset_of(src, loc.ln_end()?, loc.col_end()?)?;
+    (begin <= end).then_some((begin, end))
+}
+
+/// Applies the edits from the end of the source. Overlapping edits are skipped.
+/// Returns the new source and the applied edits.
+pub fn apply_edits(src: &str, edits: Vec<Edit>) -> (String, Vec<Edit>) {
+    let mut spans = edits
+        .into_iter()
+        .filter_map(|edit| span_of(src, edit.loc).map(|span| (span, edit)))
+        .collect::<Vec<_>>();
+    spans.sort_by_key(|((begin, end), _)| (*begin, *end));
+    let mut applied: Vec<((usize, usize), Edit)> = vec![];
+    for (span, edit) in spans {
+        let overlapped = applied
+            .last()
+            .is_some_and(|((_, prev_end), _)| *prev_end > span.0);
+        if !overlapped {
+            applied.push((span, edit));
+        }
+    }
+    let mut fixed = src.to_string();
+    for ((begin, end), edit) in applied.iter().rev() {
+        fixed.replace_range(*begin..*end, &edit.replacement);
+    }
+    (fixed, applied.into_iter().map(|(_, edit)| edit).collect())
+}
+
+/// A fix applied to the source code
+#[derive(Debug, Clone)]
+pub struct AppliedFix {
+    pub rule: &'static FixRule,
+    /// the location in the source code before the rule was applied
+    pub loc: Location,
+}
+
+impl fmt::Display for AppliedFix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: [{}] {}",
+            self.loc.ln_begin().unwrap_or(0),
+            self.loc.col_begin().unwrap_or(0),
+            self.rule.name,
+            self.rule.description
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Fixer {
+    cfg: ErgConfig,
+}
+
+impl Fixer {
+    pub const fn new(cfg: ErgConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// `#!feature(...)` at the top of the module
+    fn declared_features(src: &str) -> Features {
+        let mut features = Features::empty();
+        for line in src.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            if !line.starts_with('#') {
+                break;
+            }
+            for name in Features::parse_attr(line).unwrap_or_default() {
+                if let Ok(feat) = name.parse() {
+                    features.insert(feat);
+                }
+            }
+        }
+        features
+    }
+
+    fn lower(&self, src: &str) -> Option<(HIR, Context)> {
+        let mut ast_builder = ASTBuilder::new(self.cfg.copy());
+        let artifact = ast_builder.build(src.to_string()).ok()?;
+        let mut lowerer = ASTLowerer::new(self.cfg.copy());
+        let artifact = lowerer.lower(artifact.ast, "exec").ok()?;
+        let module = lowerer.pop_mod_ctx()?;
+        Some((artifact.object, module.context))
+    }
+
+    /// Applies `RULES` to `src`.
+    /// The rules which need types are skipped if the module has errors.
+    pub fn fix(&self, src: &str) -> (String, Vec<AppliedFix>) {
+        let mut src = normalize_newline(src);
+        let mut fixes = vec![];
+        for rule in RULES.iter() {
+            let all = Features::from_iter(Feature::ALL);
+            let tokens = match Lexer::from_str(src.clone()).with_features(all).lex() {
+                Ok(tokens) => tokens,
+                Err(_) => continue,
+            };
+            let typed = if rule.needs_types {
+                match self.lower(&src) {
+                    Some(typed) => Some(typed),
+                    None => continue,
+                }
+            } else {
+                None
+            };
+            let ctx = FixContext {
+                src: &src,
+                tokens: &tokens,
+                features: Self::declared_features(&src),
+                hir: typed.as_ref().map(|(hir, _)| hir),
+                context: typed.as_ref().map(|(_, ctx)| ctx),
+            };
+            let edits = (rule.fix)(&ctx);
+            if edits.is_empty() {
+                continue;
+            }
+            let (fixed, applied) = apply_edits(&src, edits);
+            fixes.extend(applied.into_iter().map(|edit| AppliedFix {
+                rule,
+                loc: edit.loc,
+            }));
+            src = fixed;
+        }
+        (src, fixes)
+    }
+
+    /// Rewrites the file in place (or prints the fixed code if the input is not a file).
+    pub fn run(mut cfg: ErgConfig) -> ExitStatus {
+        let src = cfg.input.read();
+        let (fixed, fixes) = Self::new(cfg.copy()).fix(&src);
+        let path = if let Some(path) = cfg.input.path() {
+            path.to_path_buf()
+        } else {
+            print!("{fixed}");
+            return ExitStatus::OK;
+        };
+        if fixes.is_empty() {
+            println!("nothing to fix: {}", path.display());
+            return ExitStatus::OK;
+        }
+        if let Err(err) = fs::write(&path, fixed) {
+            eprintln!("failed to write {}: {err}", path.display());
+            return ExitStatus::ERR1;
+        }
+        for fix in fixes.iter() {
+            println!("{}:{fix}", path.display());
+        }
+        println!("fixed: {} ({} fixes)", path.display(), fixes.len());
+        ExitStatus::OK
+    }
+}
@@ -1911,6 +1911,8 @@ impl SubrSignature {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Lambda {
     pub params: Params,
+    /// guard of a match arm: `(i: Int) if i > 0 -> ...`
+    pub guard: Option<Box<Expr>>,
     pub op: Token,
     pub body: Block,
     pub id: usize,
@@ -1919,7 +1921,14 @@ pub struct Lambda {
 
 impl NestedDisplay for Lambda {
     fn fmt_nest(&self, f: &mut fmt::Formatter<'_>, level: usize) -> fmt::Result {
-        writeln!(f, "{} {} (: {})", self.params, self.op.content, self.t)?;
+        writeln!(
+            f,
+            "{}{} {} (: {})",
+            self.params,
+            fmt_option!(pre " if ", self.guard),
+            self.op.content,
+            self.t
+        )?;
         self.body.fmt_nest(f, level + 1)
     }
 }
@@ -1927,8 +1936,9 @@ impl NestedDisplay for Lambda {
 impl NoTypeDisplay for Lambda {
     fn to_string_notype(&self) -> String {
         format!(
-            "{} {} {}",
+            "{}{} {} {}",
             self.params.to_string_notype(),
+            fmt_option!(pre " if ", self.guard.as_ref().map(|g| g.to_string_notype())),
             self.op.content,
             self.body.to_string_notype()
         )
@@ -1944,12 +1954,18 @@ impl Lambda {
         Self {
             id,
             params,
+            guard: None,
             op,
             body,
             t,
         }
     }
 
+    pub fn with_guard(mut self, guard: Option<Expr>) -> Self {
+        self.guard = guard.map(Box::new);
+        self
+    }
+
     pub fn is_procedural(&self) -> bool {
         self.op.is(TokenKind::ProcArrow)
     }
@@ -2073,6 +2089,8 @@ impl DefBody {
 pub struct Def {
     pub sig: Signature,
     pub body: DefBody,
+    /// The doc comment preceding the definition (emitted as `__doc__`)
+    pub doc: Option<Str>,
 }
 
 impl NestedDisplay for Def {
@@ -2117,7 +2135,11 @@ impl HasType for Def {
 
 impl Def {
     pub const fn new(sig: Signature, body: DefBody) -> Self {
-        Self { sig, body }
+        Self {
+            sig,
+            body,
+            doc: None,
+        }
     }
 
     pub fn def_kind(&self) -> DefKind {
@@ -2213,6 +2235,8 @@ pub struct ClassDef {
     pub need_to_gen_new: bool,
     pub __new__: Type,
     pub methods: Block,
+    /// The doc comment preceding the definition (emitted as `__doc__`)
+    pub doc: Option<Str>,
 }
 
 impl NestedDisplay for ClassDef {
@@ -2268,6 +2292,7 @@ impl ClassDef {
             need_to_gen_new,
             __new__,
             methods,
+            doc: None,
         }
     }
 }
@@ -2478,6 +2503,67 @@ impl TypeAscription {
     }
 }
 
+/// A conversion inserted by the compiler (not written in the source code).
+/// Currently, this is only used for the record projection:
+/// `{.x = 1; .y = 2}` passed as `{.x = Int}` is converted to `{.x = 1}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Coercion {
+    pub expr: Box<Expr>,
+    /// the type after the conversion
+    pub t: Type,
+}
+
+impl NestedDisplay for Coercion {
+    fn fmt_nest(&self, f: &mut fmt::Formatter<'_>, _level: usize) -> fmt::Result {
+        write!(f, "{} (coerced to {})", self.expr, self.t)
+    }
+}
+
+impl NoTypeDisplay for Coercion {
+    fn to_string_notype(&self) -> String {
+        self.expr.to_string_notype()
+    }
+}
+
+impl_display_from_nested!(Coercion);
+impl_locational!(Coercion, expr);
+
+impl HasType for Coercion {
+    #[inline]
+    fn ref_t(&self) -> &Type {
+        &self.t
+    }
+    #[inline]
+    fn ref_mut_t(&mut self) -> &mut Type {
+        &mut self.t
+    }
+    #[inline]
+    fn signature_t(&self) -> Option<&Type> {
+        None
+    }
+    #[inline]
+    fn signature_mut_t(&mut self) -> Option<&mut Type> {
+        None
+    }
+}
+
+impl Coercion {
+    pub fn new(expr: Expr, t: Type) -> Self {
+        Self {
+            expr: Box::new(expr),
+            t,
+        }
+    }
+
+    /// The fields of the record after the projection
+    pub fn record_fields(&self) -> Option<&HashMap<Field, Type>> {
+        match &self.t {
+            Type::Record(fields) => Some(fields),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Expr {
     Lit(Literal),
@@ -2496,17 +2582,18 @@ pub enum Expr {
     PatchDef(PatchDef),
     ReDef(ReDef),
     TypeAsc(TypeAscription),
+    Coerce(Coercion),
     Code(Block),     // code object
     Compound(Block), // compound statement
     Import(Accessor),
     Dummy(Dummy), // for mapping to Python AST
 }
 
-impl_nested_display_for_chunk_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Set, Import, Dummy);
-impl_no_type_display_for_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Set, Import, Dummy);
+impl_nested_display_for_chunk_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Coerce, Set, Import, Dummy);
+impl_no_type_display_for_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Coerce, Set, Import, Dummy);
 impl_display_from_nested!(Expr);
-impl_locational_for_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Set, Import, Dummy);
-impl_t_for_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Set, Import, Dummy);
+impl_locational_for_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Coerce, Set, Import, Dummy);
+impl_t_for_enum!(Expr; Lit, Accessor, Array, Tuple, Dict, Record, BinOp, UnaryOp, Call, Lambda, Def, ClassDef, PatchDef, ReDef, Code, Compound, TypeAsc, Coerce, Set, Import, Dummy);
 
 impl Default for Expr {
     fn default() -> Self {
@@ -2585,6 +2672,7 @@ impl Expr {
             Self::Call(_) => "call",
             Self::Lambda(_) => "lambda",
             Self::TypeAsc(_) => "type ascription",
+            Self::Coerce(_) => "coercion",
             Self::Def(_) => "definition",
             Self::Code(_) => "code",
             Self::Compound(_) => "compound expression",
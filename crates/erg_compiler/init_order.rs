@@ -0,0 +1,273 @@
+//! defines `InitOrderAnalyzer`.
+//!
+//! Erg modules are linked into a single file (see `HIRLinker`),
+//! and a module is executed at the place where it is first imported.
+//! So the modules are initialized in the post-order of the import graph (the main module is the last).
+//!
+//! If modules import each other, one of them is executed while the other is not fully initialized.
+//! This is harmless if they only define variables, but the result of top-level side effects would depend on the link order,
+//! so such a cycle is rejected.
+use std::path::{Path, PathBuf};
+
+use erg_common::config::ErgConfig;
+use erg_common::error::Location;
+use erg_common::io::Input;
+use erg_common::pathutil::same_file;
+use erg_common::traits::{Locational, Stream};
+use erg_common::{log, trace_span};
+use erg_parser::ast::OperationKind;
+
+use crate::error::{EffectError, EffectErrors};
+use crate::hir::{Array, Dict, Expr, Set, Signature, Tuple, HIR};
+use crate::module::SharedModuleCache;
+use crate::ty::typaram::TyParam;
+use crate::ty::value::ValueObj;
+use crate::ty::HasType;
+
+/// The imports and the top-level side effect of a module
+#[derive(Debug, Default)]
+pub(crate) struct ModuleSummary {
+    /// (path, location of the `import` call)
+    pub(crate) imports: Vec<(PathBuf, Location)>,
+    /// whether the module has top-level side effects
+    pub(crate) has_effect: bool,
+}
+
+impl ModuleSummary {
+    pub(crate) fn new(cfg: &ErgConfig, hir: &HIR) -> Self {
+        let mut summary = Self::default();
+        for chunk in hir.module.iter() {
+            summary.scan(cfg, chunk, true);
+        }
+        summary
+    }
+
+    /// `toplevel`: whether `expr` is evaluated when the module is initialized
+    fn scan(&mut self, cfg: &ErgConfig, expr: &Expr, toplevel: bool) {
+        match expr {
+            Expr::Accessor(_) | Expr::Lit(_) | Expr::Import(_) | Expr::Dummy(_) => {}
+            Expr::Array(array) => match array {
+                Array::Normal(arr) => {
+                    for elem in arr.elems.pos_args.iter() {
+                        self.scan(cfg, &elem.expr, toplevel);
+                    }
+                }
+                Array::WithLength(arr) => {
+                    self.scan(cfg, &arr.elem, toplevel);
+                    self.scan(cfg, &arr.len, toplevel);
+                }
+                Array::Comprehension(_) => {}
+            },
+            Expr::Tuple(Tuple::Normal(tup)) => {
+                for elem in tup.elems.pos_args.iter() {
+                    self.scan(cfg, &elem.expr, toplevel);
+                }
+            }
+            Expr::Set(set) => match set {
+                Set::Normal(st) => {
+                    for elem in st.elems.pos_args.iter() {
+                        self.scan(cfg, &elem.expr, toplevel);
+                    }
+                }
+                Set::WithLength(st) => {
+                    self.scan(cfg, &st.elem, toplevel);
+                    self.scan(cfg, &st.len, toplevel);
+                }
+            },
+            Expr::Dict(dict) => {
+                if let Dict::Normal(dic) = dict {
+                    for kv in dic.kvs.iter() {
+                        self.scan(cfg, &kv.key, toplevel);
+                        self.scan(cfg, &kv.value, toplevel);
+                    }
+                }
+            }
+            Expr::Record(record) => {
+                for attr in record.attrs.iter() {
+                    for chunk in attr.body.block.iter() {
+                        self.scan(cfg, chunk, toplevel);
+                    }
+                }
+            }
+            Expr::BinOp(bin) => {
+                self.scan(cfg, &bin.lhs, toplevel);
+                self.scan(cfg, &bin.rhs, toplevel);
+            }
+            Expr::UnaryOp(unary) => self.scan(cfg, &unary.expr, toplevel),
+            Expr::Call(call) => match call.additional_operation() {
+                Some(OperationKind::Import) => {
+                    let path = expr.ref_t().typarams().into_iter().next();
+                    if let Some(TyParam::Value(ValueObj::Str(path))) = path {
+                        if let Some(path) = cfg.resolve_real_path(Path::new(&path[..])) {
+                            self.imports.push((path, expr.loc()));
+                        }
+                    }
+                }
+                Some(OperationKind::PyImport) => {}
+                _ => {
+                    let is_procedural = call.obj.t().is_procedure()
+                        || call
+                            .attr_name
+                            .as_ref()
+                            .is_some_and(|name| name.is_procedural());
+                    self.has_effect |= toplevel && is_procedural;
+                    self.scan(cfg, &call.obj, toplevel);
+                    for arg in call.args.pos_args.iter() {
+                        self.scan(cfg, &arg.expr, toplevel);
+                    }
+                    if let Some(arg) = call.args.var_args.as_ref() {
+                        self.scan(cfg, &arg.expr, toplevel);
+                    }
+                    for arg in call.args.kw_args.iter() {
+                        self.scan(cfg, &arg.expr, toplevel);
+                    }
+                }
+            },
+            Expr::Def(def) => {
+                // the body of a subroutine is evaluated when it is called
+                let toplevel = toplevel && matches!(def.sig, Signature::Var(_));
+                for chunk in def.body.block.iter() {
+                    self.scan(cfg, chunk, toplevel);
+                }
+            }
+            Expr::Lambda(lambda) => {
+                if let Some(guard) = &lambda.guard {
+                    self.scan(cfg, guard, false);
+                }
+                for chunk in lambda.body.iter() {
+                    self.scan(cfg, chunk, false);
+                }
+            }
+            Expr::ClassDef(class_def) => {
+                for def in class_def.methods.iter() {
+                    self.scan(cfg, def, false);
+                }
+            }
+            Expr::PatchDef(patch_def) => {
+                for def in patch_def.methods.iter() {
+                    self.scan(cfg, def, false);
+                }
+            }
+            Expr::ReDef(redef) => {
+                for chunk in redef.block.iter() {
+                    self.scan(cfg, chunk, toplevel);
+                }
+            }
+            Expr::TypeAsc(tasc) => self.scan(cfg, &tasc.expr, toplevel),
+            Expr::Coerce(coerce) => self.scan(cfg, &coerce.expr, toplevel),
+            Expr::Code(chunks) | Expr::Compound(chunks) => {
+                for chunk in chunks.iter() {
+                    self.scan(cfg, chunk, toplevel);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Frame {
+    path: PathBuf,
+    input: Input,
+    has_effect: bool,
+}
+
+/// Computes the initialization order of the modules linked into the main module.
+/// For example:
+/// * `a.er` imports `b.er`, and `b.er` imports `c.er` → `c.er`, `b.er`, `a.er`
+/// * `a.er` and `b.er` import each other, and `b.er` calls `print!` at the top level → error
+#[derive(Debug)]
+pub struct InitOrderAnalyzer<'a> {
+    cfg: &'a ErgConfig,
+    mod_cache: &'a SharedModuleCache,
+    stack: Vec<Frame>,
+    order: Vec<PathBuf>,
+    errs: EffectErrors,
+}
+
+impl<'a> InitOrderAnalyzer<'a> {
+    pub fn new(cfg: &'a ErgConfig, mod_cache: &'a SharedModuleCache) -> Self {
+        Self {
+            cfg,
+            mod_cache,
+            stack: vec![],
+            order: vec![],
+            errs: EffectErrors::empty(),
+        }
+    }
+
+    /// Returns the paths of the modules in the order of initialization.
+    /// The last one is the main module.
+    pub fn analyze(mut self, hir: &HIR) -> Result<Vec<PathBuf>, EffectErrors> {
+        let _span = trace_span!("analyze_init_order", &hir.name[..]);
+        let path = self.cfg.input.unescaped_path().to_path_buf();
+        let summary = ModuleSummary::new(self.cfg, hir);
+        self.enter(path, self.cfg.input.clone(), summary);
+        log!(info "initialization order: {:?}", self.order);
+        if self.errs.is_empty() {
+            Ok(self.order)
+        } else {
+            Err(self.errs)
+        }
+    }
+
+    fn enter(&mut self, path: PathBuf, input: Input, summary: ModuleSummary) {
+        self.stack.push(Frame {
+            path: path.clone(),
+            input,
+            has_effect: summary.has_effect,
+        });
+        for (import, loc) in summary.imports {
+            self.visit(import, loc);
+        }
+        self.stack.pop();
+        self.order.push(path);
+    }
+
+    fn visit(&mut self, path: PathBuf, loc: Location) {
+        let same = |other: &PathBuf| other == &path || same_file(other, &path);
+        if self.order.iter().any(same) {
+            return;
+        }
+        if let Some(start) = self.stack.iter().position(|frame| same(&frame.path)) {
+            // `self = import "module"` is allowed
+            if start + 1 < self.stack.len() {
+                self.check_cycle(start, loc);
+            }
+            return;
+        }
+        // the config of a module in a cycle may be inherited from the importer (see `Context::build_cyclic_mod`)
+        let cfg = self.cfg.inherit(path.clone());
+        let Some(summary) = self
+            .mod_cache
+            .get(path.as_path())
+            .and_then(|entry| Some(ModuleSummary::new(&cfg, entry.hir.as_ref()?)))
+        else {
+            self.order.push(path);
+            return;
+        };
+        self.enter(path, cfg.input, summary);
+    }
+
+    /// `self.stack[start..]` imports `self.stack[start]` at `loc`
+    fn check_cycle(&mut self, start: usize, loc: Location) {
+        let cycle = &self.stack[start..];
+        let Some(effectful) = cycle.iter().find(|frame| frame.has_effect) else {
+            return;
+        };
+        let names = cycle
+            .iter()
+            .chain(Some(&cycle[0]))
+            .map(|frame| frame.input.unescaped_filename())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        let importer = self.stack.last().unwrap();
+        self.errs.push(EffectError::cyclic_init_error(
+            importer.input.clone(),
+            line!() as usize,
+            loc,
+            &names,
+            effectful.input.unescaped_filename(),
+            "<module>".into(),
+        ));
+    }
+}
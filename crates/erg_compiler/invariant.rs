@@ -0,0 +1,249 @@
+//! defines `InvariantChecker`.
+//!
+//! With the `check_invariants` feature, the HIR is validated after each phase (lowering, checking, optimization),
+//! so that regressions of the type checker are caught close to the cause.
+//! A violation is an internal compiler error, so the checker panics (see `crash.rs`).
+//!
+//! Invariants:
+//! * All HIR nodes are typed (no `Uninited` types)
+//! * No `Failure` types in the HIR of the successful phases
+//! * No dangling free type variables (unbound and not generalized) above the top level
+//!
+//! Nodes synthesized after type checking (e.g. by `HIRLinker`) refer to variables that do not exist in any context, so they are not checked.
+//! The HIRs of the linked modules are checked in their own compilation.
+use erg_common::consts::CHECK_INVARIANTS;
+use erg_common::traits::Locational;
+
+use crate::context::Context;
+use crate::hir::*;
+use crate::ty::free::HasLevel;
+use crate::ty::typaram::TyParam;
+use crate::ty::{HasType, Type};
+
+/// Returns the description of the invalid part of `t`, if any.
+/// Free variables above `level` should have been generalized or dereferenced.
+fn find_invalid_type(t: &Type, level: usize) -> Option<&'static str> {
+    match t {
+        Type::Failure => Some("a `Failure` type"),
+        Type::FreeVar(fv) if fv.is_linked() => find_invalid_type(&fv.crack(), level),
+        Type::FreeVar(fv) => {
+            if !fv.is_generalized() && fv.level().is_some_and(|lev| lev > level) {
+                return Some("a dangling type variable");
+            }
+            let (sub, sup) = fv.get_subsup()?;
+            // `Type::contains_type` links recursive variables to `Failure`, so `Obj` is used as the placeholder
+            fv.do_avoiding_recursion_with(&Type::Obj, || {
+                find_invalid_type(&sub, level).or_else(|| find_invalid_type(&sup, level))
+            })
+        }
+        Type::Ref(t) | Type::Not(t) | Type::Structural(t) | Type::Quantified(t) => {
+            find_invalid_type(t, level)
+        }
+        Type::RefMut { before, after } => find_invalid_type(before, level)
+            .or_else(|| after.as_ref().and_then(|t| find_invalid_type(t, level))),
+        Type::And(lhs, rhs) | Type::Or(lhs, rhs) | Type::Bounded { sub: lhs, sup: rhs } => {
+            find_invalid_type(lhs, level).or_else(|| find_invalid_type(rhs, level))
+        }
+        Type::Callable { param_ts, return_t } => param_ts
+            .iter()
+            .find_map(|t| find_invalid_type(t, level))
+            .or_else(|| find_invalid_type(return_t, level)),
+        Type::Subr(subr) => subr
+            .non_default_params
+            .iter()
+            .chain(subr.var_params.as_deref())
+            .chain(subr.default_params.iter())
+            .find_map(|pt| find_invalid_type(pt.typ(), level))
+            .or_else(|| find_invalid_type(&subr.return_t, level)),
+        Type::Record(rec) => rec.values().find_map(|t| find_invalid_type(t, level)),
+        Type::Refinement(refine) => find_invalid_type(&refine.t, level),
+        Type::Poly { params, .. } => params.iter().find_map(|tp| find_invalid_tp(tp, level)),
+        Type::Proj { lhs, .. } => find_invalid_type(lhs, level),
+        Type::ProjCall { lhs, args, .. } => find_invalid_tp(lhs, level)
+            .or_else(|| args.iter().find_map(|tp| find_invalid_tp(tp, level))),
+        _ => None,
+    }
+}
+
+fn find_invalid_tp(tp: &TyParam, level: usize) -> Option<&'static str> {
+    match tp {
+        TyParam::FreeVar(fv) if fv.is_linked() => find_invalid_tp(&fv.crack(), level),
+        TyParam::FreeVar(fv) => (!fv.is_generalized() && fv.level().is_some_and(|lev| lev > level))
+            .then_some("a dangling type variable"),
+        TyParam::Type(t) | TyParam::Erased(t) => find_invalid_type(t, level),
+        TyParam::Array(tps) | TyParam::Tuple(tps) | TyParam::App { args: tps, .. } => {
+            tps.iter().find_map(|tp| find_invalid_tp(tp, level))
+        }
+        TyParam::Proj { obj, .. } | TyParam::UnaryOp { val: obj, .. } => {
+            find_invalid_tp(obj, level)
+        }
+        TyParam::BinOp { lhs, rhs, .. } => {
+            find_invalid_tp(lhs, level).or_else(|| find_invalid_tp(rhs, level))
+        }
+        _ => None,
+    }
+}
+
+/// Validates the invariants of the HIR. Does nothing unless the `check_invariants` feature is enabled.
+///
+/// ```ignore
+/// InvariantChecker::check(&hir, "lower");
+/// ```
+#[derive(Debug, Default)]
+pub struct InvariantChecker {
+    violations: Vec<String>,
+}
+
+impl InvariantChecker {
+    /// Panics if `hir` (the result of `phase`) violates the invariants.
+    pub fn check(hir: &HIR, phase: &str) {
+        if !CHECK_INVARIANTS {
+            return;
+        }
+        let mut checker = Self::default();
+        for chunk in hir.module.iter() {
+            checker.check_expr(chunk);
+        }
+        if !checker.violations.is_empty() {
+            panic!(
+                "invariant violation(s) after the `{phase}` phase of {}:\n{}",
+                hir.name,
+                checker.violations.join("\n")
+            );
+        }
+    }
+
+    fn is_synthesized(expr: &Expr) -> bool {
+        match expr {
+            Expr::Accessor(acc) => acc.var_info().kind.does_not_exist(),
+            Expr::Call(call) => call.attr_name.as_ref().map_or_else(
+                || Self::is_synthesized(&call.obj),
+                |attr| attr.vi.kind.does_not_exist(),
+            ),
+            _ => false,
+        }
+    }
+
+    fn check_t(&mut self, node: &(impl Locational + std::fmt::Display), t: &Type) {
+        let loc = node.loc();
+        if t == &Type::Uninited {
+            self.violations
+                .push(format!("{loc}: `{node}` is not typed"));
+        } else if let Some(invalid) = find_invalid_type(t, Context::TOP_LEVEL) {
+            self.violations
+                .push(format!("{loc}: `{node}` has {invalid}: {t}"));
+        }
+    }
+
+    fn check_args(&mut self, args: &Args) {
+        for arg in args.pos_args.iter() {
+            self.check_expr(&arg.expr);
+        }
+        if let Some(var_args) = args.var_args.as_ref() {
+            self.check_expr(&var_args.expr);
+        }
+        for arg in args.kw_args.iter() {
+            self.check_expr(&arg.expr);
+        }
+    }
+
+    fn check_block(&mut self, block: &Block) {
+        for chunk in block.iter() {
+            self.check_expr(chunk);
+        }
+    }
+
+    fn check_params(&mut self, params: &Params) {
+        for param in params
+            .non_defaults
+            .iter()
+            .chain(params.var_params.as_deref())
+        {
+            self.check_t(param, &param.vi.t);
+        }
+        for param in params.defaults.iter() {
+            self.check_t(&param.sig, &param.sig.vi.t);
+            self.check_expr(&param.default_val);
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr) {
+        match expr {
+            // `Dummy` and `Import` are not typed (they are just for mapping to Python AST)
+            Expr::Dummy(dummy) => {
+                for chunk in dummy.iter() {
+                    self.check_expr(chunk);
+                }
+                return;
+            }
+            Expr::Import(_) => return,
+            // the types of definitions are checked with their signatures
+            Expr::Def(def) => {
+                if !def.sig.ident().vi.kind.does_not_exist() {
+                    self.check_t(def.sig.ident(), def.sig.ident().ref_t());
+                }
+                if let Signature::Subr(subr) = &def.sig {
+                    self.check_params(&subr.params);
+                }
+                self.check_block(&def.body.block);
+                return;
+            }
+            Expr::ClassDef(class_def) => {
+                self.check_block(&class_def.methods);
+                return;
+            }
+            Expr::PatchDef(patch_def) => {
+                self.check_block(&patch_def.methods);
+                return;
+            }
+            Expr::ReDef(redef) => {
+                self.check_expr(&Expr::Accessor(redef.attr.clone()));
+                self.check_block(&redef.block);
+                return;
+            }
+            // the HIRs of the linked modules (they have been checked in their own compilation)
+            Expr::Code(_) => return,
+            Expr::Compound(block) => {
+                self.check_block(block);
+                return;
+            }
+            Expr::Accessor(Accessor::Attr(attr)) => self.check_expr(&attr.obj),
+            Expr::Array(Array::Normal(arr)) => self.check_args(&arr.elems),
+            Expr::Tuple(Tuple::Normal(tup)) => self.check_args(&tup.elems),
+            Expr::Set(Set::Normal(set)) => self.check_args(&set.elems),
+            Expr::Dict(Dict::Normal(dict)) => {
+                for kv in dict.kvs.iter() {
+                    self.check_expr(&kv.key);
+                    self.check_expr(&kv.value);
+                }
+            }
+            Expr::Record(rec) => {
+                for attr in rec.attrs.iter() {
+                    self.check_block(&attr.body.block);
+                }
+            }
+            Expr::BinOp(bin) => {
+                self.check_expr(&bin.lhs);
+                self.check_expr(&bin.rhs);
+            }
+            Expr::UnaryOp(unary) => self.check_expr(&unary.expr),
+            Expr::Call(call) => {
+                self.check_expr(&call.obj);
+                self.check_args(&call.args);
+            }
+            Expr::Lambda(lambda) => {
+                self.check_params(&lambda.params);
+                if let Some(guard) = &lambda.guard {
+                    self.check_expr(guard);
+                }
+                self.check_block(&lambda.body);
+            }
+            Expr::TypeAsc(tasc) => self.check_expr(&tasc.expr),
+            Expr::Coerce(coerce) => self.check_expr(&coerce.expr),
+            _ => {}
+        }
+        if !Self::is_synthesized(expr) {
+            self.check_t(expr, expr.ref_t());
+        }
+    }
+}
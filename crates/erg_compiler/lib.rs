@@ -6,15 +6,21 @@ pub extern crate erg_parser;
 
 pub mod artifact;
 pub mod build_hir;
+pub mod build_package;
 mod compile;
 pub use compile::*;
 mod codegen;
 pub mod context;
+pub mod crash;
 pub mod declare;
 pub mod desugar_hir;
 pub mod effectcheck;
 pub mod error;
+pub mod explain;
+pub mod fix;
 pub mod hir;
+pub mod init_order;
+pub mod invariant;
 pub mod link_ast;
 pub mod link_hir;
 pub mod lint;
@@ -22,10 +28,21 @@ pub mod lower;
 pub mod module;
 pub mod optimize;
 pub mod ownercheck;
+pub mod pack;
+pub mod prefetch;
+pub mod prune;
+pub mod reprint;
+pub mod signature;
+pub mod sourcemap;
+pub mod stub;
+pub mod test_runner;
 pub mod transpile;
 pub mod ty;
 pub mod varinfo;
+pub mod verify_decl;
+pub mod watch;
 
 pub use build_hir::HIRBuilder;
+pub use codegen::wasm::{exec_wasm, WasmCodeGenerator};
 pub use erg_parser::build_ast::ASTBuilder;
 pub use transpile::Transpiler;
@@ -23,6 +23,22 @@ class Array(list):
             list(filter(lambda x: not f(x), self))
         )
 
+    def map(self, func):
+        return Array(map(func, self))
+
+    def filter(self, func):
+        return Array(filter(func, self))
+
+    def fold(self, init, func):
+        acc = init
+        for x in self:
+            acc = func(acc, x)
+        return acc
+
+    # `sort` of Erg (`list.sort` sorts in place)
+    def sorted(self):
+        return Array(sorted(self))
+
     def __mul__(self, n):
         return then__(list.__mul__(self, n), Array)
 
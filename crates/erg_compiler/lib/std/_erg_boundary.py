@@ -0,0 +1,14 @@
+from _erg_in_operator import in_operator
+
+
+# `x: T = py_value` (`--check-py-boundary`)
+# checks that a value from Python is an instance of the ascribed Erg type
+# `exc` is the exception class mapped from `RuntimeTypeError` by the compiler
+def py_boundary(value, typ, name, exc=TypeError):
+    if not in_operator(value, typ):
+        expected = getattr(typ, "__name__", repr(typ))
+        found = type(value).__name__
+        raise exc(
+            f"{name}: expected {expected}, but found {found} ({value!r}) from Python"
+        )
+    return value
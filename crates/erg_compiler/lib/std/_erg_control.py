@@ -27,8 +27,26 @@ def discard__(obj):
 def assert__(test, msg=None):
     assert test, msg
 
+def debug_only__(proc, when=True):
+    # removed at compile time in a release build
+    if when:
+        proc()
+
 def then__(x, f):
     if x == None or x == NotImplemented:
         return x
     else:
         return f(x)
+
+def unsafe_python__(code):
+    # The snippet is executed in its own namespace, and the value of the last expression is returned
+    import ast, textwrap
+    tree = ast.parse(textwrap.dedent(code), "<unsafe_python>")
+    last = None
+    if tree.body and isinstance(tree.body[-1], ast.Expr):
+        last = ast.Expression(tree.body.pop().value)
+    namespace = {}
+    exec(compile(tree, "<unsafe_python>", "exec"), namespace)
+    if last is None:
+        return None
+    return eval(compile(last, "<unsafe_python>", "eval"), namespace)
@@ -0,0 +1,47 @@
+# `C = Class {x = Int; .y = Str}, Derive := Eq and Ord and Hash and Show`
+# => `derive(C, ("Eq", "Ord", "Hash", "Show"), (("x", "::x"), ("y", "y")))`
+# `fields` are the pairs of the field names and the attribute names (in alphabetical order of the names)
+# the methods compare/hash/show the tuple of the fields
+def derive(cls, traits, fields):
+    def key(self):
+        return tuple(getattr(self, attr) for (_, attr) in fields)
+
+    if "Eq" in traits or "Ord" in traits:
+
+        def __eq__(self, other):
+            if type(self) is not type(other):
+                return NotImplemented
+            return key(self) == key(other)
+
+        cls.__eq__ = __eq__
+    if "Ord" in traits:
+
+        def compare(op):
+            def method(self, other):
+                if type(self) is not type(other):
+                    return NotImplemented
+                return op(key(self), key(other))
+
+            return method
+
+        cls.__lt__ = compare(lambda l, r: l < r)
+        cls.__le__ = compare(lambda l, r: l <= r)
+        cls.__gt__ = compare(lambda l, r: l > r)
+        cls.__ge__ = compare(lambda l, r: l >= r)
+    if "Hash" in traits:
+
+        def __hash__(self):
+            return hash(key(self))
+
+        cls.__hash__ = __hash__
+    if "Show" in traits:
+
+        def __str__(self):
+            values = ", ".join(
+                f"{name}={getattr(self, attr)!r}" for (name, attr) in fields
+            )
+            return f"{type(self).__name__}({values})"
+
+        cls.__str__ = __str__
+        cls.__repr__ = __str__
+    return cls
@@ -1,2 +1,9 @@
 class Dict(dict):
-    pass
+    def concat(self, other):
+        return Dict({**self, **other})
+
+    def map(self, func):
+        return Dict(func(k, v) for k, v in self.items())
+
+    def filter(self, func):
+        return Dict({k: v for k, v in self.items() if func(k, v)})
@@ -0,0 +1,9 @@
+# `arr[i]` where `0 <= i < len(arr)` cannot be proven at compile time
+# unlike `list.__getitem__`, negative indices are not counted from the end
+# `exc` is the exception class mapped from `IndexError` by the compiler
+def checked_getitem(arr, index, exc=IndexError):
+    if index < 0 or len(arr) <= index:
+        raise exc(
+            f"array index out of range: the length is {len(arr)} but the index is {index}"
+        )
+    return arr[index]
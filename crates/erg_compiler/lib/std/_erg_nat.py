@@ -6,7 +6,7 @@ from _erg_control import then__
 
 class Nat(Int):
     def try_new(i):  # -> Result[Nat]
-        if i >= 0:
+        if isinstance(i, int) and i >= 0:
             return Nat(i)
         else:
             return Error("Nat can't be negative")
@@ -119,7 +119,7 @@ class NatMut(IntMut):  # and Nat
         return self
 
     def try_new(i):  # -> Result[Nat]
-        if i >= 0:
+        if isinstance(i, int) and i >= 0:
             return NatMut(i)
         else:
             return Error("Nat can't be negative")
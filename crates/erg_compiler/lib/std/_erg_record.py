@@ -0,0 +1,14 @@
+from collections import namedtuple
+
+
+# `{x = 1} + {y = 2}` (`{..r; y = 2}`)
+# the fields of `rhs` take precedence
+def record_merge(lhs, rhs):
+    fields = {**lhs._asdict(), **rhs._asdict()}
+    return namedtuple("Record", fields.keys())(**fields)
+
+
+# `{x = 1; y = 2} - {"y"}`
+def record_remove(rec, names):
+    fields = {k: v for (k, v) in rec._asdict().items() if k not in names}
+    return namedtuple("Record", fields.keys())(**fields)
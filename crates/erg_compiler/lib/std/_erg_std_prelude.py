@@ -18,6 +18,10 @@ from _erg_dict import Dict
 from _erg_set import Set
 from _erg_in_operator import in_operator
 from _erg_mutate_operator import mutate_operator
+from _erg_record import record_merge, record_remove
+from _erg_index import checked_getitem
+from _erg_boundary import py_boundary
+from _erg_derive import derive
 
 
 class Never:
@@ -27,6 +27,12 @@ class Str(str):
     def contains(self, s):
         return s in self
 
+    def removeprefix(self, prefix):
+        return Str(self[len(prefix) :]) if self.startswith(prefix) else self
+
+    def removesuffix(self, suffix):
+        return Str(self[: -len(suffix)]) if suffix and self.endswith(suffix) else self
+
     def __add__(self, other):
         return then__(str.__add__(self, other), Str)
 
@@ -0,0 +1,147 @@
+# Translates Python tracebacks into Erg source locations at runtime.
+# `install()` is called at the beginning of the modules compiled with `--source-map`.
+# The source map of a code object is searched at `<co_filename>.map` (see `sourcemap.rs`).
+# The exceptions raised by `pyimport`ed modules are reported with the Erg error kinds
+# registered from the declaration files (see `module/exceptions.rs`).
+import linecache
+import sys
+import traceback
+
+_HEADER = "# erg-source-map 1"
+_maps = {}
+_exceptions = {}
+_original_excepthook = sys.excepthook
+
+
+def _parse_loc(s):
+    if "-" in s:
+        begin, end = s.split("-", 1)
+        if ":" in begin and ":" in end:
+            ln_begin, col_begin = begin.split(":")
+            ln_end, col_end = end.split(":")
+            return (int(ln_begin), int(col_begin), int(ln_end), int(col_end))
+        return (int(begin), None, int(end), None)
+    return (int(s), None, int(s), None)
+
+
+def _load_map(filename):
+    if filename in _maps:
+        return _maps[filename]
+    source_map = None
+    try:
+        with open(filename + ".map", encoding="utf-8") as f:
+            lines = f.read().splitlines()
+        if lines[0] == _HEADER:
+            source_map = {"source": lines[1][len("source ") :], "codes": {}}
+            entries = []
+            for line in lines[2:]:
+                if line.startswith("code "):
+                    name, firstlineno = line[len("code ") :].rsplit(" ", 1)
+                    key = (name, int(firstlineno))
+                    entries = []
+                    # code objects cannot be distinguished (e.g. lambdas on the same line)
+                    codes = source_map["codes"]
+                    codes[key] = None if key in codes else entries
+                else:
+                    offset, end, _line, loc = line.split(" ")
+                    entries.append((int(offset), int(end), _parse_loc(loc)))
+    except (OSError, ValueError, IndexError):
+        source_map = None
+    _maps[filename] = source_map
+    return source_map
+
+
+def _lookup(source_map, code, lasti):
+    entries = source_map["codes"].get((code.co_name, code.co_firstlineno))
+    # the innermost expression which contains the instruction
+    for offset, end, loc in reversed(entries or []):
+        if offset <= lasti < end:
+            return loc
+    return None
+
+
+def _format_frame(tb):
+    code = tb.tb_frame.f_code
+    source_map = _load_map(code.co_filename)
+    loc = _lookup(source_map, code, tb.tb_lasti) if source_map else None
+    if loc is None:
+        return "".join(traceback.format_list(traceback.extract_tb(tb, limit=1)))
+    ln_begin, col_begin, ln_end, col_end = loc
+    path = source_map["source"]
+    res = f'  File "{path}", line {ln_begin}, in {code.co_name}\n'
+    src_line = linecache.getline(path, ln_begin).rstrip()
+    if src_line:
+        indent = len(src_line) - len(src_line.lstrip())
+        res += f"    {src_line.strip()}\n"
+        if col_begin is not None:
+            end = col_end if ln_end == ln_begin else len(src_line)
+            if end > col_begin:
+                res += "    " + " " * (col_begin - indent) + "^" * (end - col_begin) + "\n"
+    return res
+
+
+def _qual_name(cls):
+    if cls.__module__ == "builtins":
+        return cls.__qualname__
+    return f"{cls.__module__}.{cls.__qualname__}"
+
+
+# the nearest registered class in the MRO is used (see `SharedPyExceptionMap::resolve`)
+# a module imported from a subdirectory has a package prefix (e.g. `pkg.foo.FooError` for `foo.FooError`)
+def _erg_kind(exc_type):
+    for cls in exc_type.__mro__:
+        name = _qual_name(cls)
+        for registered, kind in _exceptions.items():
+            if name == registered or name.endswith("." + registered):
+                return kind
+    return None
+
+
+# e.g. `foo.FooError (ValueError): message`
+def _format_exception_only(exc):
+    lines = traceback.format_exception_only(type(exc), exc)
+    kind = _erg_kind(type(exc))
+    if kind is not None:
+        name = _qual_name(type(exc))
+        for i, line in enumerate(lines):
+            if line.startswith(name):
+                lines[i] = f"{name} ({kind}){line[len(name) :]}"
+                break
+    return "".join(lines)
+
+
+def _format_exception(exc, seen):
+    seen.add(id(exc))
+    res = ""
+    cause = exc.__cause__
+    context = exc.__context__
+    if cause is not None and id(cause) not in seen:
+        res += _format_exception(cause, seen)
+        res += "\nThe above exception was the direct cause of the following exception:\n\n"
+    elif (
+        context is not None and not exc.__suppress_context__ and id(context) not in seen
+    ):
+        res += _format_exception(context, seen)
+        res += "\nDuring handling of the above exception, another exception occurred:\n\n"
+    tb = exc.__traceback__
+    if tb is not None:
+        res += "Traceback (most recent call last):\n"
+        while tb is not None:
+            res += _format_frame(tb)
+            tb = tb.tb_next
+    res += _format_exception_only(exc)
+    return res
+
+
+def _excepthook(exc_type, exc, tb):
+    try:
+        sys.stderr.write(_format_exception(exc, set()))
+    except Exception:
+        _original_excepthook(exc_type, exc, tb)
+
+
+# `exceptions` is a flattened tuple of the registered classes and their kinds,
+# e.g. `("foo.FooError", "ValueError")`
+def install(exceptions=()):
+    _exceptions.update(zip(exceptions[::2], exceptions[1::2]))
+    sys.excepthook = _excepthook
@@ -0,0 +1,62 @@
+// The host of the WebAssembly modules generated with `--target wasm`.
+// usage: node -e "<this script>" <file.wasm>
+const fs = require("fs");
+
+const path = process.argv[process.argv.length - 1];
+let memory;
+let line = "";
+
+// the same format as `repr(float)` of Python
+const formatFloat = (f) => {
+    if (Number.isNaN(f)) {
+        return "nan";
+    }
+    if (!Number.isFinite(f)) {
+        return f > 0 ? "inf" : "-inf";
+    }
+    const abs = Math.abs(f);
+    if (abs !== 0 && (abs >= 1e16 || abs < 1e-4)) {
+        const [mantissa, exp] = f.toExponential().split("e");
+        return `${mantissa}e${exp[0]}${exp.slice(1).padStart(2, "0")}`;
+    }
+    if (Number.isInteger(f)) {
+        return (Object.is(f, -0) ? "-" : "") + f.toFixed(1);
+    }
+    return f.toString();
+};
+
+const imports = {
+    erg: {
+        write_int: (i) => {
+            line += i.toString();
+        },
+        write_float: (f) => {
+            line += formatFloat(f);
+        },
+        write_bool: (b) => {
+            line += b ? "True" : "False";
+        },
+        write_str: (ptr) => {
+            const len = new DataView(memory.buffer).getUint32(ptr, true);
+            line += new TextDecoder().decode(new Uint8Array(memory.buffer, ptr + 4, len));
+        },
+        write_none: () => {
+            line += "None";
+        },
+        flush_line: () => {
+            process.stdout.write(line + "\n");
+            line = "";
+        },
+    },
+};
+
+WebAssembly.instantiate(fs.readFileSync(path), imports)
+    .then(({ instance }) => {
+        memory = instance.exports.memory;
+        instance.exports.main();
+    })
+    .catch((err) => {
+        process.stdout.write(line);
+        console.error(err);
+        process.exit(1);
+    });
@@ -107,7 +107,7 @@ impl ASTLinker {
                 }
             }
         }
-        let ast = AST::new(ast.name, Module::new(new));
+        let ast = AST::new(ast.name, Module::new(new), ast.features);
         log!(info "the AST-linking process has completed:\n{}", ast);
         if self.errs.is_empty() {
             Ok(ast)
@@ -6,10 +6,10 @@ use std::rc::Rc;
 use erg_common::config::ErgConfig;
 use erg_common::dict::Dict as Dic;
 use erg_common::fresh::SharedFreshNameGenerator;
-use erg_common::pathutil::squash;
+use erg_common::pathutil::{same_file, squash};
 use erg_common::traits::{Locational, Stream};
 use erg_common::Str;
-use erg_common::{enum_unwrap, log};
+use erg_common::{enum_unwrap, log, trace_span};
 
 use erg_parser::ast::{DefId, OperationKind};
 use erg_parser::token::{Token, TokenKind, DOT, EQUAL};
@@ -64,6 +64,7 @@ impl<'a> HIRLinker<'a> {
     }
 
     pub fn link(&self, mut main: HIR) -> HIR {
+        let _span = trace_span!("link");
         log!(info "the linking process has started.");
         for chunk in main.module.iter_mut() {
             self.replace_import(chunk);
@@ -190,6 +191,9 @@ impl<'a> HIRLinker<'a> {
                 }
             }
             Expr::Lambda(lambda) => {
+                if let Some(guard) = lambda.guard.as_deref_mut() {
+                    Self::resolve_pymod_path(guard);
+                }
                 for chunk in lambda.body.iter_mut() {
                     Self::resolve_pymod_path(chunk);
                 }
@@ -211,6 +215,7 @@ impl<'a> HIRLinker<'a> {
                 }
             }
             Expr::TypeAsc(tasc) => Self::resolve_pymod_path(&mut tasc.expr),
+            Expr::Coerce(coerce) => Self::resolve_pymod_path(&mut coerce.expr),
             Expr::Code(chunks) | Expr::Compound(chunks) => {
                 for chunk in chunks.iter_mut() {
                     Self::resolve_pymod_path(chunk);
@@ -323,6 +328,9 @@ impl<'a> HIRLinker<'a> {
                 }
             }
             Expr::Lambda(lambda) => {
+                if let Some(guard) = lambda.guard.as_deref_mut() {
+                    self.replace_import(guard);
+                }
                 for chunk in lambda.body.iter_mut() {
                     self.replace_import(chunk);
                 }
@@ -344,6 +352,7 @@ impl<'a> HIRLinker<'a> {
                 }
             }
             Expr::TypeAsc(tasc) => self.replace_import(&mut tasc.expr),
+            Expr::Coerce(coerce) => self.replace_import(&mut coerce.expr),
             Expr::Code(chunks) | Expr::Compound(chunks) => {
                 for chunk in chunks.iter_mut() {
                     self.replace_import(chunk);
@@ -377,7 +386,7 @@ impl<'a> HIRLinker<'a> {
             unreachable!()
         };
         let path = Path::new(&path[..]);
-        let path = self.cfg.input.resolve_real_path(path).unwrap();
+        let path = self.cfg.resolve_real_path(path).unwrap();
         // # module.er
         // self = import "module"
         // ↓
@@ -436,7 +445,14 @@ impl<'a> HIRLinker<'a> {
             let exec_code = exec.call_expr(args);
             let compound = Block::new(vec![mod_update, exec_code, mod_var]);
             *expr = Expr::Compound(compound);
-        } else if let Some(module) = self.removed_mods.borrow().get(&path) {
+        } else if let Some(module) = self
+            .removed_mods
+            .borrow()
+            .iter()
+            // the same module may be imported through another path (see `Context::dedup_mod_path`)
+            .find(|(removed, _)| removed == &&path || same_file(removed, &path))
+            .map(|(_, module)| module)
+        {
             *expr = module.variable.clone();
         }
     }
@@ -455,7 +471,6 @@ impl<'a> HIRLinker<'a> {
         let mut dir = self.cfg.input.dir();
         let mod_path = self
             .cfg
-            .input
             .resolve_decl_path(Path::new(&mod_name_str[..]))
             .unwrap();
         if !mod_path
@@ -2,6 +2,7 @@
 //! What is implemented here affects subsequent optimizations,
 //! and `erg_linter` does linting that does not affect optimizations.
 
+use erg_common::error::Location;
 #[allow(unused_imports)]
 use erg_common::log;
 use erg_common::traits::{Locational, Runnable, Stream};
@@ -161,12 +162,82 @@ impl ASTLowerer {
         }
     }
 
+    /// ```erg
+    /// f!() =
+    ///     panic "..."
+    ///     print! "unreachable" # WARN
+    /// ```
+    /// The unreachable chunks will be removed by `HIROptimizer`.
+    pub(crate) fn warn_unreachable_code(&mut self, module: &hir::Module) {
+        self.block_reachability_check(module.ref_payload());
+    }
+
+    fn block_reachability_check(&mut self, chunks: &[hir::Expr]) {
+        for (i, chunk) in chunks.iter().enumerate() {
+            self.expr_reachability_check(chunk);
+            if chunk.ref_t() == &Type::Never && i + 1 < chunks.len() {
+                let warn = LowerWarning::unreachable_code_warning(
+                    self.input().clone(),
+                    line!() as usize,
+                    Location::stream(&chunks[i + 1..]),
+                    self.module.context.caused_by(),
+                );
+                self.warns.push(warn);
+                break;
+            }
+        }
+    }
+
+    fn expr_reachability_check(&mut self, expr: &hir::Expr) {
+        match expr {
+            hir::Expr::Def(def) => self.block_reachability_check(def.body.block.ref_payload()),
+            hir::Expr::Lambda(lambda) => self.block_reachability_check(lambda.body.ref_payload()),
+            hir::Expr::ClassDef(class_def) => {
+                for method in class_def.methods.iter() {
+                    self.expr_reachability_check(method);
+                }
+            }
+            hir::Expr::PatchDef(patch_def) => {
+                for method in patch_def.methods.iter() {
+                    self.expr_reachability_check(method);
+                }
+            }
+            hir::Expr::Call(call) => {
+                for arg in call.args.pos_args.iter() {
+                    self.expr_reachability_check(&arg.expr);
+                }
+                if let Some(var_args) = &call.args.var_args {
+                    self.expr_reachability_check(&var_args.expr);
+                }
+                for arg in call.args.kw_args.iter() {
+                    self.expr_reachability_check(&arg.expr);
+                }
+            }
+            hir::Expr::Code(block) | hir::Expr::Compound(block) => {
+                self.block_reachability_check(block.ref_payload());
+            }
+            _ => {}
+        }
+    }
+
     pub(crate) fn inc_ref<L: Locational>(&self, name: &Str, vi: &VarInfo, loc: &L) {
         self.module
             .context
             .inc_ref(name, vi, loc, &self.module.context);
     }
 
+    /// Test functions are called by `erg test` (see `TestRunner`).
+    fn is_test_function(name: &str, vi: &VarInfo) -> bool {
+        let nullary =
+            vi.t.non_default_params()
+                .is_some_and(|params| params.is_empty());
+        let decorated = vi
+            .comptime_decos
+            .as_ref()
+            .is_some_and(|decos| decos.contains("Test"));
+        nullary && (name.starts_with("test_") || decorated)
+    }
+
     pub(crate) fn warn_unused_local_vars(&mut self, mode: &str) {
         if mode == "eval" {
             return;
@@ -178,7 +249,11 @@ impl ASTLowerer {
             }
             let name_is_auto = &value.name[..] == "_"
                 || !Lexer::is_valid_start_symbol_ch(value.name.chars().next().unwrap_or(' '));
-            if value.referrers.is_empty() && value.vi.vis.is_private() && !name_is_auto {
+            if value.referrers.is_empty()
+                && value.vi.vis.is_private()
+                && !name_is_auto
+                && !Self::is_test_function(&value.name, &value.vi)
+            {
                 let input = referee
                     .module
                     .as_ref()
@@ -286,6 +361,21 @@ impl ASTLowerer {
         self.module.context.pop();
     }
 
+    /// Reports the inferred types of the type holes (`_`).
+    pub(crate) fn warn_type_holes(&mut self) {
+        for (loc, t) in std::mem::take(&mut self.holes) {
+            let t = self.module.context.readable_type(t);
+            let warn = LowerWarning::type_hole_warning(
+                self.input().clone(),
+                line!() as usize,
+                loc,
+                self.module.context.caused_by(),
+                &t,
+            );
+            self.warns.push(warn);
+        }
+    }
+
     pub(crate) fn warn_implicit_union(&mut self, hir: &HIR) {
         for chunk in hir.module.iter() {
             self.warn_implicit_union_chunk(chunk);
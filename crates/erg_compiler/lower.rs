@@ -9,29 +9,37 @@ use erg_common::dict;
 use erg_common::dict::Dict;
 use erg_common::error::{Location, MultiErrorDisplay};
 use erg_common::fresh::FreshNameGenerator;
+use erg_common::lang::LanguageCode;
+use erg_common::levenshtein::get_similar_name;
+use erg_common::opcode311::{parse_asm, verify_asm};
+use erg_common::python_util::{env_python_version, BUILTIN_PYTHON_FUNCS};
 use erg_common::set;
 use erg_common::set::Set;
 use erg_common::traits::{ExitStatus, Locational, NoTypeDisplay, Runnable, Stream};
 use erg_common::triple::Triple;
-use erg_common::{fmt_option, fn_name, log, switch_lang, Str};
+use erg_common::{fmt_option, fn_name, log, switch_lang, trace_span, Str};
 
 use erg_parser::ast::{self, AscriptionKind, VisModifierSpec};
 use erg_parser::ast::{OperationKind, TypeSpecWithOp, VarName, AST};
 use erg_parser::build_ast::ASTBuilder;
-use erg_parser::desugar::Desugarer;
+use erg_parser::desugar::{Desugarer, NameOccurrence};
+use erg_parser::feature::Feature;
 use erg_parser::token::{Token, TokenKind};
 use erg_parser::Parser;
 
 use crate::artifact::{CompleteArtifact, IncompleteArtifact};
 use crate::context::instantiate::TyVarCache;
+use crate::crash::CrashReporter;
 use crate::module::SharedCompilerResource;
 use crate::ty::constructors::{
     array_t, free_var, func, guard, mono, poly, proc, refinement, set_t, ty_tp, v_enum,
 };
-use crate::ty::free::Constraint;
+use crate::ty::free::{Constraint, HasLevel};
 use crate::ty::typaram::TyParam;
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
-use crate::ty::{GuardType, HasType, ParamTy, Predicate, Type, Variable, VisibilityModifier};
+use crate::ty::{
+    GuardType, HasType, ParamTy, Predicate, SubrType, Type, Variable, VisibilityModifier,
+};
 
 use crate::context::{
     ClassDefType, Context, ContextKind, ContextProvider, ControlKind, ModuleContext,
@@ -43,8 +51,10 @@ use crate::error::{
 };
 use crate::hir;
 use crate::hir::HIR;
+use crate::invariant::InvariantChecker;
 use crate::link_ast::ASTLinker;
 use crate::varinfo::{VarInfo, VarKind};
+use crate::verify_decl::DeclVerifier;
 use crate::AccessKind;
 use crate::{feature_error, unreachable_error};
 
@@ -76,6 +86,10 @@ pub struct ASTLowerer {
     pub(crate) module: ModuleContext,
     pub(crate) errs: LowerErrors,
     pub(crate) warns: LowerWarnings,
+    /// type holes (`_`) and their types, reported after the types are resolved
+    pub(crate) holes: Vec<(Location, Type)>,
+    /// the location and the AST of the chunk being lowered (recorded only in the `--no-panic` mode)
+    lowering_chunk: Option<(Location, String)>,
     fresh_gen: FreshNameGenerator,
 }
 
@@ -118,11 +132,13 @@ impl Runnable for ASTLowerer {
         self.module.context.initialize();
         self.errs.clear();
         self.warns.clear();
+        self.holes.clear();
     }
 
     fn clear(&mut self) {
         self.errs.clear();
         self.warns.clear();
+        self.holes.clear();
     }
 
     fn exec(&mut self) -> Result<ExitStatus, Self::Errs> {
@@ -179,6 +195,8 @@ impl ASTLowerer {
             cfg,
             errs: LowerErrors::empty(),
             warns: LowerWarnings::empty(),
+            holes: vec![],
+            lowering_chunk: None,
             fresh_gen: FreshNameGenerator::new("lower"),
         }
     }
@@ -189,6 +207,8 @@ impl ASTLowerer {
             module,
             errs: LowerErrors::empty(),
             warns: LowerWarnings::empty(),
+            holes: vec![],
+            lowering_chunk: None,
             fresh_gen: FreshNameGenerator::new("lower"),
         }
     }
@@ -229,6 +249,11 @@ impl ASTLowerer {
     pub fn unregister(&mut self, name: &str) -> Option<VarInfo> {
         self.module.context.unregister(name)
     }
+
+    /// Declares the variable defined by `def` so that `def` can be lowered alone (subroutines must be declared before lowering).
+    pub fn preregister_def(&mut self, def: &ast::Def) -> LowerResult<()> {
+        self.module.context.preregister_def(def)
+    }
 }
 
 impl ASTLowerer {
@@ -294,7 +319,7 @@ impl ASTLowerer {
         let mut union = Type::Never;
         for elem in elems.into_iter() {
             let elem = self.lower_expr(elem.expr)?;
-            let union_ = self.module.context.union(&union, elem.ref_t());
+            let mut union_ = self.module.context.union(&union, elem.ref_t());
             if let Some((l, r)) = union_.union_pair() {
                 match (l.is_unbound_var(), r.is_unbound_var()) {
                     // e.g. [1, "a"]
@@ -309,6 +334,14 @@ impl ASTLowerer {
                             {
                                 return Err(self.elem_err(&l, &r, &elem));
                             } // else(OK): e.g. [1, "a": Str or Int]
+                            if self
+                                .module
+                                .context
+                                .supertype_of(&type_asc.spec.spec_t, &union_)
+                            {
+                                // e.g. [mod1, mod2: {.x = Int}] (the element type is `{.x = Int}`)
+                                union_ = type_asc.spec.spec_t.clone();
+                            }
                         } else if ERG_MODE {
                             return Err(self.elem_err(&l, &r, &elem));
                         }
@@ -630,6 +663,9 @@ impl ASTLowerer {
             }
             ast::Accessor::Attr(attr) => {
                 let obj = self.lower_expr(*attr.obj)?;
+                if obj.ref_t().is_module() {
+                    self.module.context.force_deferred_mod(obj.ref_t());
+                }
                 let vi = match self.module.context.get_attr_info(
                     &obj,
                     &attr.ident,
@@ -643,21 +679,35 @@ impl ASTLowerer {
                     }
                     Triple::None => {
                         let self_t = obj.t();
-                        let (similar_info, similar_name) = self
-                            .module
-                            .context
-                            .get_similar_attr_and_info(&self_t, attr.ident.inspect())
-                            .unzip();
-                        let err = LowerError::detailed_no_attr_error(
-                            self.cfg.input.clone(),
-                            line!() as usize,
-                            attr.ident.loc(),
-                            self.module.context.caused_by(),
-                            &self_t,
-                            attr.ident.inspect(),
-                            similar_name,
-                            similar_info,
-                        );
+                        // the module is not initialized yet because it imports this module
+                        let err = if let Some(cycle) = self.module.context.get_import_cycle(&self_t)
+                        {
+                            LowerError::cyclic_import_error(
+                                self.cfg.input.clone(),
+                                line!() as usize,
+                                attr.ident.loc(),
+                                self.module.context.caused_by(),
+                                &obj.show_acc().unwrap_or_else(|| self_t.to_string()),
+                                attr.ident.inspect(),
+                                &cycle,
+                            )
+                        } else {
+                            let (similar_info, similar_name) = self
+                                .module
+                                .context
+                                .get_similar_attr_and_info(&self_t, attr.ident.inspect())
+                                .unzip();
+                            LowerError::detailed_no_attr_error(
+                                self.cfg.input.clone(),
+                                line!() as usize,
+                                attr.ident.loc(),
+                                self.module.context.caused_by(),
+                                &self_t,
+                                attr.ident.inspect(),
+                                similar_name,
+                                similar_info,
+                            )
+                        };
                         self.errs.push(err);
                         VarInfo::ILLEGAL
                     }
@@ -667,18 +717,496 @@ impl ASTLowerer {
                 let acc = hir::Accessor::Attr(hir::Attribute::new(obj, ident));
                 Ok(acc)
             }
-            ast::Accessor::TypeApp(t_app) => feature_error!(
-                LowerErrors,
-                LowerError,
-                self.module.context,
-                t_app.loc(),
-                "type application"
-            ),
+            ast::Accessor::TypeApp(t_app) => {
+                if self.module.features.contains(Feature::TypeApp) {
+                    self.lower_type_app(t_app)
+                } else {
+                    Err(LowerErrors::from(LowerError::unstable_feature_error(
+                        self.cfg.input.clone(),
+                        t_app.loc(),
+                        "type application",
+                        Feature::TypeApp,
+                        self.module.context.caused_by(),
+                    )))
+                }
+            }
             // TupleAttr, Subscr are desugared
             _ => unreachable_error!(LowerErrors, LowerError, self.module.context),
         }
     }
 
+    /// `Cfg.debug` -> `True`
+    ///
+    /// The compile-time flags (`Cfg`) do not exist at runtime, so they are replaced with literals.
+    fn fold_compile_time_flag(&self, acc: hir::Accessor) -> hir::Expr {
+        let hir::Accessor::Attr(attr) = &acc else {
+            return hir::Expr::Accessor(acc);
+        };
+        let hir::Expr::Accessor(hir::Accessor::Ident(obj)) = attr.obj.as_ref() else {
+            return hir::Expr::Accessor(acc);
+        };
+        if &obj.inspect()[..] != "Cfg" || !obj.vi.kind.is_builtin() {
+            return hir::Expr::Accessor(acc);
+        }
+        let Some(ValueObj::Record(flags)) = self.module.context.rec_get_const_obj("Cfg") else {
+            return hir::Expr::Accessor(acc);
+        };
+        let Some(value) = flags
+            .iter()
+            .find_map(|(field, value)| (&field.symbol == attr.ident.inspect()).then_some(value))
+        else {
+            return hir::Expr::Accessor(acc);
+        };
+        let kind = match value {
+            ValueObj::Bool(_) => TokenKind::BoolLit,
+            ValueObj::Nat(_) => TokenKind::NatLit,
+            ValueObj::Int(_) => TokenKind::IntLit,
+            ValueObj::Float(_) => TokenKind::RatioLit,
+            _ => TokenKind::StrLit,
+        };
+        let loc = acc.loc();
+        let token = Token::new(
+            kind,
+            value.to_string(),
+            loc.ln_begin().unwrap_or(0),
+            loc.col_begin().unwrap_or(0),
+        );
+        hir::Expr::Lit(hir::Literal::new(value.clone(), token))
+    }
+
+    /// `static_assert(test, msg)` is evaluated at compile time and replaced with `None`.
+    fn lower_static_assert(&mut self, call: ast::Call) -> LowerResult<hir::Expr> {
+        let expr = ast::Expr::Call(call.clone());
+        let errs_len = self.errs.len();
+        let call = self.lower_call(call)?;
+        let is_builtin = matches!(
+            call.obj.as_ref(),
+            hir::Expr::Accessor(hir::Accessor::Ident(ident)) if ident.vi.kind.is_builtin()
+        );
+        // the type errors of the arguments have already been reported
+        if !is_builtin || self.errs.len() > errs_len {
+            return Ok(hir::Expr::Call(call));
+        }
+        self.module.context.eval_const_expr(&expr)?;
+        Ok(Self::none_literal(call.loc()))
+    }
+
+    /// `debug_only! do!: ...` is always type-checked, but removed (replaced with `None`)
+    /// in a release build (`--release`) or if `when` (a compile-time constant, `True` by default) is `False`.
+    fn lower_debug_only(&mut self, call: ast::Call) -> LowerResult<hir::Expr> {
+        let when = call
+            .args
+            .kw_args()
+            .iter()
+            .find(|arg| &arg.keyword.content[..] == "when")
+            .map(|arg| &arg.expr)
+            .or_else(|| call.args.pos_args().get(1).map(|arg| &arg.expr))
+            .cloned();
+        let errs_len = self.errs.len();
+        let call = self.lower_call(call)?;
+        let is_builtin = matches!(
+            call.obj.as_ref(),
+            hir::Expr::Accessor(hir::Accessor::Ident(ident)) if ident.vi.kind.is_builtin()
+        );
+        if !is_builtin || self.errs.len() > errs_len {
+            return Ok(hir::Expr::Call(call));
+        }
+        let retained = match when {
+            Some(when) => self.module.context.eval_const_expr(&when)? != ValueObj::Bool(false),
+            None => true,
+        };
+        if self.cfg.is_release() || !retained {
+            Ok(Self::none_literal(call.loc()))
+        } else {
+            Ok(hir::Expr::Call(call))
+        }
+    }
+
+    fn none_literal(loc: Location) -> hir::Expr {
+        let token = Token::new(
+            TokenKind::NoneLit,
+            "None",
+            loc.ln_begin().unwrap_or(0),
+            loc.col_begin().unwrap_or(0),
+        );
+        hir::Expr::Lit(hir::Literal::new(ValueObj::None, token))
+    }
+
+    /// The reflection functions (`type_name`, `fields`, `methods` and `supers`), `parse_json` and `include_str`/`include_bytes` are evaluated at compile time,
+    /// and the call is replaced with the result, since the types cannot be inspected at runtime
+    /// (and the result of `parse_json` gets the precise type of the literal, and the included file need not exist at runtime).
+    fn lower_compile_time_call(&mut self, call: ast::Call) -> LowerResult<hir::Expr> {
+        let expr = ast::Expr::Call(call.clone());
+        let errs_len = self.errs.len();
+        let call = self.lower_call(call)?;
+        let is_builtin = matches!(
+            call.obj.as_ref(),
+            hir::Expr::Accessor(hir::Accessor::Ident(ident)) if ident.vi.kind.is_builtin()
+        );
+        if !is_builtin || self.errs.len() > errs_len {
+            return Ok(hir::Expr::Call(call));
+        }
+        let value = self.module.context.eval_const_expr(&expr)?;
+        // there is no bytes literal in Erg, so the value is embedded as a constant directly
+        if let ValueObj::Bytes(_) = &value {
+            let loc = call.loc();
+            let token = Token::new(
+                TokenKind::StrLit,
+                "",
+                loc.ln_begin().unwrap_or(0),
+                loc.col_begin().unwrap_or(0),
+            );
+            return Ok(hir::Expr::Lit(hir::Literal::new(value, token)));
+        }
+        match Self::value_to_expr(&value, call.loc()) {
+            Some(expr) => self.lower_expr(expr),
+            // e.g. `supers(Int)` has generic traits, which are usable only at compile time (like `Structural(...)`)
+            None => Ok(hir::Expr::Call(call)),
+        }
+    }
+
+    /// `quote(...)` is evaluated at compile time (see `Context::eval_const_quote`).
+    /// The quoted expression itself is not checked, since it is only a template for macros.
+    fn lower_quote(&mut self, call: ast::Call) -> LowerResult<hir::Expr> {
+        let loc = call.loc();
+        // the parameters of the macro are used in the quoted expression
+        if let Some(expr) = call.args.nth_or_key(0, "expr") {
+            let ctx = &self.module.context;
+            Desugarer::walk_idents(expr, &mut |occurrence| {
+                let NameOccurrence::Ref(ident) = occurrence else {
+                    return;
+                };
+                if let Triple::Ok(vi) =
+                    ctx.rec_get_var_info(ident, AccessKind::Name, &self.cfg.input, ctx)
+                {
+                    if vi.t == mono("Ast") {
+                        ctx.inc_ref(ident.inspect(), &vi, &ident.name, ctx);
+                    }
+                }
+            });
+        }
+        let value = self
+            .module
+            .context
+            .eval_const_expr(&ast::Expr::Call(call))?;
+        let token = Token::new(
+            TokenKind::StrLit,
+            "",
+            loc.ln_begin().unwrap_or(0),
+            loc.col_begin().unwrap_or(0),
+        );
+        Ok(hir::Expr::Lit(hir::Literal::new(value, token)))
+    }
+
+    /// Expands the macro call and lowers the expanded expression.
+    /// The expression is lowered in its own namespace (`<macro F>`) like the body of a definition,
+    /// so the names defined in the expansion do not leak into (or overwrite) the names of the caller.
+    fn lower_macro_call(&mut self, call: ast::Call) -> LowerResult<hir::Expr> {
+        log!(info "entered {}({})", fn_name!(), call);
+        let Some(subr) = self.module.context.get_macro(&call) else {
+            return unreachable_error!(LowerErrors, LowerError, self.module.context);
+        };
+        if let ast::Expr::Accessor(acc) = call.obj.as_ref() {
+            self.module.context.inc_ref_acc(acc, &self.module.context);
+        }
+        let expanded = self.module.context.expand_macro(subr, &call)?;
+        log!(info "expanded: {expanded}");
+        let name = format!("<macro {}>", call.obj);
+        self.module
+            .context
+            .grow(&name, ContextKind::Instant, Private, None);
+        let res = self.lower_expr(expanded);
+        self.pop_append_errs();
+        res
+    }
+
+    /// Converts the value into an expression that builds it at runtime.
+    /// Types are referred by their names, so only the named (non-generic) types can be converted.
+    fn value_to_expr(value: &ValueObj, loc: Location) -> Option<ast::Expr> {
+        let (line, col) = (loc.ln_begin().unwrap_or(0), loc.col_begin().unwrap_or(0));
+        let token = |kind, content: String| Token::new(kind, content, line, col);
+        match value {
+            ValueObj::Str(_)
+            | ValueObj::Nat(_)
+            | ValueObj::Int(_)
+            | ValueObj::Float(_)
+            | ValueObj::Bool(_)
+            | ValueObj::None => {
+                let kind = match value {
+                    ValueObj::Str(_) => TokenKind::StrLit,
+                    ValueObj::Nat(_) => TokenKind::NatLit,
+                    ValueObj::Int(_) => TokenKind::IntLit,
+                    ValueObj::Float(_) => TokenKind::RatioLit,
+                    ValueObj::Bool(_) => TokenKind::BoolLit,
+                    _ => TokenKind::NoneLit,
+                };
+                let content = match value {
+                    // the content of a string literal token is unescaped
+                    ValueObj::Str(s) => format!("\"{s}\""),
+                    _ => value.to_string(),
+                };
+                let lit = ast::Literal::new(token(kind, content));
+                Some(ast::Expr::Literal(lit))
+            }
+            ValueObj::Array(elems) => {
+                let elems = elems
+                    .iter()
+                    .map(|elem| Self::value_to_expr(elem, loc).map(ast::PosArg::new))
+                    .collect::<Option<Vec<_>>>()?;
+                let arr = ast::NormalArray::new(
+                    token(TokenKind::LSqBr, "[".into()),
+                    token(TokenKind::RSqBr, "]".into()),
+                    ast::Args::pos_only(elems, None),
+                );
+                Some(ast::Expr::Array(ast::Array::Normal(arr)))
+            }
+            ValueObj::Dict(dict) => {
+                let kvs = dict
+                    .iter()
+                    .map(|(k, v)| {
+                        let k = Self::value_to_expr(k, loc)?;
+                        let v = Self::value_to_expr(v, loc)?;
+                        Some(ast::KeyValue::new(k, v))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                let dict = ast::NormalDict::new(
+                    token(TokenKind::LBrace, "{".into()),
+                    token(TokenKind::RBrace, "}".into()),
+                    kvs,
+                );
+                Some(ast::Expr::Dict(ast::Dict::Normal(dict)))
+            }
+            ValueObj::Record(fields) => {
+                let attrs = fields
+                    .iter()
+                    .map(|(field, v)| {
+                        let symbol = token(TokenKind::Symbol, field.symbol.to_string());
+                        let ident = if field.vis.is_public() {
+                            ast::Identifier::public_from_token(
+                                token(TokenKind::Dot, ".".into()),
+                                symbol,
+                            )
+                        } else {
+                            ast::Identifier::private_from_token(symbol)
+                        };
+                        let sig = ast::VarSignature::new(ast::VarPattern::Ident(ident), None);
+                        let body = ast::DefBody::new_single(Self::value_to_expr(v, loc)?);
+                        Some(ast::Def::new(ast::Signature::Var(sig), body))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                let rec = ast::NormalRecord::new(
+                    token(TokenKind::LBrace, "{".into()),
+                    token(TokenKind::RBrace, "}".into()),
+                    ast::RecordAttrs::new(attrs),
+                );
+                Some(ast::Expr::Record(ast::Record::Normal(rec)))
+            }
+            ValueObj::Type(t) if t.typ().typarams_len().unwrap_or(0) == 0 => {
+                let name = token(TokenKind::Symbol, t.typ().local_name().to_string());
+                Some(ast::Identifier::private_from_token(name).into())
+            }
+            _ => None,
+        }
+    }
+
+    /// `x: T = unsafe_python """..."""`
+    /// The snippet is executed as Python code at runtime (not checked), and the value is trusted to be of type `T`.
+    /// The declaration is required so that the uses of `x` are type-checked.
+    fn lower_unsafe_python(
+        &mut self,
+        call: ast::Call,
+        t_spec: Option<&ast::TypeSpecWithOp>,
+    ) -> LowerResult<hir::Call> {
+        let loc = call.loc();
+        let mut call = self.lower_call(call)?;
+        let is_builtin = matches!(
+            call.obj.as_ref(),
+            hir::Expr::Accessor(hir::Accessor::Ident(ident)) if ident.vi.kind.is_builtin()
+        );
+        if !is_builtin {
+            return Ok(call);
+        }
+        let Some(t_spec) = t_spec else {
+            return Err(LowerErrors::from(LowerError::unsafe_python_type_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                self.module.context.caused_by(),
+            )));
+        };
+        // the snippet is embedded as is
+        if let Some(code) = call
+            .args
+            .get_left_or_key("code")
+            .filter(|code| !matches!(code, hir::Expr::Lit(_)))
+        {
+            return Err(LowerErrors::from(LowerError::not_const_expr(
+                self.cfg.input.clone(),
+                line!() as usize,
+                code.loc(),
+                self.module.context.caused_by(),
+            )));
+        }
+        let spec_t = self.module.context.instantiate_typespec(&t_spec.t_spec)?;
+        *call.ref_mut_t() = spec_t;
+        Ok(call)
+    }
+
+    /// `add(x: Int, y: Int): Int = asm "BINARY_OP 0", x, y`, `n: Nat = intrinsic "len", xs`
+    /// The call is compiled inline (the instructions, or a call of the Python built-in function),
+    /// and the result is trusted to be of the declared type, as with `unsafe_python`.
+    /// The instructions of `asm` are verified: they must consume the arguments and leave exactly one result on the stack.
+    fn lower_intrinsic(
+        &mut self,
+        call: ast::Call,
+        t_spec: Option<&ast::TypeSpecWithOp>,
+    ) -> LowerResult<hir::Call> {
+        let loc = call.loc();
+        let mut call = self.lower_call(call)?;
+        let name = match call.obj.as_ref() {
+            hir::Expr::Accessor(hir::Accessor::Ident(ident)) if ident.vi.kind.is_builtin() => {
+                ident.inspect().clone()
+            }
+            _ => return Ok(call),
+        };
+        let Some(t_spec) = t_spec else {
+            return Err(LowerErrors::from(LowerError::intrinsic_type_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                self.module.context.caused_by(),
+                &name,
+            )));
+        };
+        let key = if &name[..] == "asm" { "code" } else { "name" };
+        let (lit_loc, value) = match call.args.get_left_or_key(key) {
+            Some(hir::Expr::Lit(lit)) => (lit.loc(), lit.value.clone()),
+            Some(other) => {
+                return Err(LowerErrors::from(LowerError::not_const_expr(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    other.loc(),
+                    self.module.context.caused_by(),
+                )));
+            }
+            None => return Ok(call),
+        };
+        let ValueObj::Str(value) = value else {
+            return Ok(call);
+        };
+        if &name[..] == "asm" {
+            if let Some(var_args) = &call.args.var_args {
+                return feature_error!(
+                    LowerErrors,
+                    LowerError,
+                    self.module.context,
+                    var_args.loc(),
+                    "variable-length arguments of asm"
+                );
+            }
+            let version = self.cfg.target_version.unwrap_or_else(env_python_version);
+            if !version.minor_is(3, 11) {
+                return Err(LowerErrors::from(LowerError::asm_version_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    loc,
+                    self.module.context.caused_by(),
+                    version.to_command().trim_start_matches("python"),
+                )));
+            }
+            let argc = call.args.pos_args.len().saturating_sub(1);
+            if let Err(err) = parse_asm(&value).and_then(|instrs| verify_asm(&instrs, argc)) {
+                return Err(LowerErrors::from(LowerError::invalid_asm_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    lit_loc,
+                    self.module.context.caused_by(),
+                    &err,
+                )));
+            }
+        } else if !BUILTIN_PYTHON_FUNCS.contains(&&value[..]) {
+            return Err(LowerErrors::from(LowerError::no_var_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                lit_loc,
+                self.module.context.caused_by(),
+                &value,
+                get_similar_name(BUILTIN_PYTHON_FUNCS.into_iter(), &value),
+            )));
+        }
+        let spec_t = self.module.context.instantiate_typespec(&t_spec.t_spec)?;
+        *call.ref_mut_t() = spec_t;
+        Ok(call)
+    }
+
+    /// `id|Int|`, `id|T := Int|`
+    fn lower_type_app(&mut self, t_app: ast::TypeApp) -> LowerResult<hir::Accessor> {
+        log!(info "entered {}({t_app})", fn_name!());
+        let args = match t_app.type_args.args {
+            ast::TypeAppArgsKind::Args(args) => args,
+            ast::TypeAppArgsKind::SubtypeOf(t_spec) => {
+                return feature_error!(
+                    LowerErrors,
+                    LowerError,
+                    self.module.context,
+                    t_spec.loc(),
+                    "type application with a subtype bound"
+                );
+            }
+        };
+        let obj = match *t_app.obj {
+            ast::Expr::Accessor(acc) => hir::Expr::Accessor(self.lower_acc(acc)?),
+            other => {
+                return feature_error!(
+                    LowerErrors,
+                    LowerError,
+                    self.module.context,
+                    other.loc(),
+                    "type application to non-accessors"
+                );
+            }
+        };
+        let (pos_args, _, kw_args, _) = args.deconstruct();
+        let mut pos_types = vec![];
+        for arg in pos_args.into_iter() {
+            let loc = arg.loc();
+            pos_types.push((self.lower_type_arg(arg.expr)?, loc));
+        }
+        let mut kw_types = vec![];
+        for arg in kw_args.into_iter() {
+            let loc = arg.loc();
+            let name = arg.keyword.content.clone();
+            kw_types.push((name, self.lower_type_arg(arg.expr)?, loc));
+        }
+        let t =
+            self.module
+                .context
+                .instantiate_with_type_args(obj.t(), &pos_types, &kw_types, &obj)?;
+        match obj {
+            hir::Expr::Accessor(mut acc) => {
+                *acc.ref_mut_t() = t;
+                Ok(acc)
+            }
+            _ => unreachable_error!(LowerErrors, LowerError, self.module.context),
+        }
+    }
+
+    fn lower_type_arg(&mut self, expr: ast::Expr) -> LowerResult<Type> {
+        let loc = expr.loc();
+        let name = expr.name();
+        match Parser::expr_to_type_spec(expr) {
+            Ok(t_spec) => Ok(self.module.context.instantiate_typespec(&t_spec)?),
+            Err(_) => Err(LowerErrors::from(LowerError::syntax_error(
+                self.input().clone(),
+                line!() as usize,
+                loc,
+                self.module.context.caused_by(),
+                format!("expected a type, but found {name}"),
+                None,
+            ))),
+        }
+    }
+
     fn lower_ident(&mut self, ident: ast::Identifier) -> LowerResult<hir::Identifier> {
         // `match` is a special form, typing is magic
         let (vi, __name__) = if ident.vis.is_private()
@@ -703,6 +1231,18 @@ impl ASTLowerer {
                     self.errs.push(err);
                     VarInfo::ILLEGAL
                 }
+                // `_` is a type hole, which accepts any type
+                Triple::None if ident.vis.is_private() && &ident.inspect()[..] == "_" => {
+                    let t = free_var(
+                        self.module.context.level,
+                        Constraint::new_type_of(Type::Type),
+                    );
+                    self.holes.push((ident.loc(), t.clone()));
+                    VarInfo {
+                        t,
+                        ..VarInfo::default()
+                    }
+                }
                 Triple::None => {
                     let (similar_info, similar_name) = self
                         .module
@@ -717,6 +1257,9 @@ impl ASTLowerer {
                         ident.inspect(),
                         similar_name,
                         similar_info,
+                    )
+                    .with_import_suggestion(
+                        self.module.context.get_import_suggestion(ident.inspect()),
                     );
                     self.errs.push(err);
                     VarInfo::ILLEGAL
@@ -763,7 +1306,7 @@ impl ASTLowerer {
             }
             TokenKind::Gre => {
                 let value = self.module.context.expr_to_value(rhs.clone())?;
-                let t = value.class();
+                let t = self.guard_refinement_base(&var, &value);
                 let varname = self.fresh_gen.fresh_varname();
                 let pred = Predicate::gt(varname.clone(), TyParam::value(value));
                 let refine = refinement(varname, t, pred);
@@ -771,7 +1314,7 @@ impl ASTLowerer {
             }
             TokenKind::GreEq => {
                 let value = self.module.context.expr_to_value(rhs.clone())?;
-                let t = value.class();
+                let t = self.guard_refinement_base(&var, &value);
                 let varname = self.fresh_gen.fresh_varname();
                 let pred = Predicate::ge(varname.clone(), TyParam::value(value));
                 let refine = refinement(varname, t, pred);
@@ -779,7 +1322,7 @@ impl ASTLowerer {
             }
             TokenKind::Less => {
                 let value = self.module.context.expr_to_value(rhs.clone())?;
-                let t = value.class();
+                let t = self.guard_refinement_base(&var, &value);
                 let varname = self.fresh_gen.fresh_varname();
                 let pred = Predicate::lt(varname.clone(), TyParam::value(value));
                 let refine = refinement(varname, t, pred);
@@ -787,7 +1330,7 @@ impl ASTLowerer {
             }
             TokenKind::LessEq => {
                 let value = self.module.context.expr_to_value(rhs.clone())?;
-                let t = value.class();
+                let t = self.guard_refinement_base(&var, &value);
                 let varname = self.fresh_gen.fresh_varname();
                 let pred = Predicate::le(varname.clone(), TyParam::value(value));
                 let refine = refinement(varname, t, pred);
@@ -797,6 +1340,25 @@ impl ASTLowerer {
         }
     }
 
+    /// `i: Int; i < 10` ==> `i: {I: Int | I < 10}` (not `{I: Nat | I < 10}`)
+    fn guard_refinement_base(&self, var: &Variable, value: &ValueObj) -> Type {
+        let t = value.class();
+        let Variable::Var(name, _) = var else {
+            return t;
+        };
+        match self.module.context.get_var_kv(name) {
+            Some((_, vi))
+                if !vi.t.is_refinement()
+                    && !vi.t.is_union_type()
+                    && !vi.t.has_unbound_var()
+                    && self.module.context.subtype_of(&t, &vi.t) =>
+            {
+                vi.t.clone()
+            }
+            _ => t,
+        }
+    }
+
     fn lower_bin(&mut self, bin: ast::BinOp) -> hir::BinOp {
         log!(info "entered {}({bin})", fn_name!());
         let mut args = bin.args.into_iter();
@@ -840,9 +1402,66 @@ impl ASTLowerer {
         let mut args = args.into_iter();
         let lhs = args.next().unwrap().expr;
         let rhs = args.next().unwrap().expr;
+        self.check_int_div(&bin.op, &lhs, &rhs);
         hir::BinOp::new(bin.op, lhs, rhs, vi)
     }
 
+    /// With `--strictness strict`, `Int / Int` is an error unless the division is exact,
+    /// i.e. the operands are constants (or finite sets of constants) and the dividend is divisible by the divisor.
+    fn check_int_div(&mut self, op: &Token, lhs: &hir::Expr, rhs: &hir::Expr) {
+        if op.kind != TokenKind::Slash || !self.cfg.strictness.checks_int_div() {
+            return;
+        }
+        let ctx = &self.module.context;
+        let (lhs_t, rhs_t) = (lhs.ref_t(), rhs.ref_t());
+        if [lhs_t, rhs_t]
+            .iter()
+            .any(|t| t.is_failure() || t == &&Type::Never || !ctx.subtype_of(t, &Type::Int))
+        {
+            return;
+        }
+        let divisible = Self::int_values(lhs_t)
+            .zip(Self::int_values(rhs_t))
+            .is_some_and(|(lhs, rhs)| {
+                rhs.iter()
+                    .all(|r| *r != 0 && lhs.iter().all(|l| l % r == 0))
+            });
+        if !divisible {
+            self.errs.push(LowerError::int_div_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                Location::concat(lhs, rhs),
+                self.module.context.caused_by(),
+                &self.module.context.readable_type(lhs_t.clone()),
+                &self.module.context.readable_type(rhs_t.clone()),
+            ));
+        }
+    }
+
+    /// The possible values of `t` if `t` is a finite set of integers (e.g. `{2, 4}`)
+    fn int_values(t: &Type) -> Option<Vec<i64>> {
+        match t {
+            Type::FreeVar(fv) if fv.is_linked() => Self::int_values(&fv.crack()),
+            Type::Refinement(refine) if refine.pred.consist_of_equal() => refine
+                .pred
+                .ors()
+                .into_iter()
+                .map(|pred| match pred {
+                    Predicate::Equal {
+                        rhs: TyParam::Value(ValueObj::Int(i)),
+                        ..
+                    } => Some(*i as i64),
+                    Predicate::Equal {
+                        rhs: TyParam::Value(ValueObj::Nat(n)),
+                        ..
+                    } => i64::try_from(*n).ok(),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
     fn lower_unary(&mut self, unary: ast::UnaryOp) -> hir::UnaryOp {
         log!(info "entered {}({unary})", fn_name!());
         let mut args = unary.args.into_iter();
@@ -874,12 +1493,33 @@ impl ASTLowerer {
             Vec::with_capacity(kw_args.len()),
             paren,
         );
+        let kind = self.module.context.control_kind();
+        let is_match = kind.is_some_and(|kind| kind.is_match());
+        // `match x: (_: Int) -> ...; y -> ...`:
+        // `x` is narrowed to `Int` in the first arm, `x` and `y` are narrowed to `(type of x) - Int` in the last arm
+        let mut subject = None;
+        let mut subject_t = Type::Failure;
+        let mut covered = Type::Never;
         for (nth, arg) in pos_args.into_iter().enumerate() {
+            if is_match && nth == 0 {
+                subject = expr_to_variable(&arg.expr);
+            } else if is_match {
+                let guards = self.get_match_arm_guards(
+                    subject.as_ref(),
+                    &subject_t,
+                    &mut covered,
+                    &arg.expr,
+                );
+                self.module.context.guards.extend(guards);
+            }
             match self.lower_expr(arg.expr) {
                 Ok(expr) => {
-                    if let Some(kind) = self.module.context.control_kind() {
+                    if let Some(kind) = kind {
                         self.push_guard(nth, kind, expr.ref_t());
                     }
+                    if is_match && nth == 0 {
+                        subject_t = expr.t();
+                    }
                     hir_args.pos_args.push(hir::PosArg::new(expr))
                 }
                 Err(es) => {
@@ -913,6 +1553,67 @@ impl ASTLowerer {
         hir_args
     }
 
+    /// `match x: (_: Int) -> ...` ==> `x: Int` in the arm
+    /// `match x: (_: Int) -> ...; y -> ...` ==> `x: (type of x) - Int` and `y: (type of x) - Int` in the last arm
+    fn get_match_arm_guards(
+        &self,
+        subject: Option<&Variable>,
+        subject_t: &Type,
+        covered: &mut Type,
+        arm: &ast::Expr,
+    ) -> Vec<GuardType> {
+        let ast::Expr::Lambda(lambda) = arm else {
+            return vec![];
+        };
+        let [param] = &lambda.sig.params.non_defaults[..] else {
+            return vec![];
+        };
+        let Some(t_spec) = param.t_spec.as_ref() else {
+            let rest = self.module.context.diff(subject_t, covered);
+            if subject_t == &Type::Failure
+                || rest == Type::Never
+                || rest.has_qvar()
+                || rest.has_unbound_var()
+            {
+                return vec![];
+            }
+            let mut guards = vec![];
+            if let Some(var) = subject {
+                guards.push(GuardType::new(var.clone(), rest.clone()));
+            }
+            if let ast::ParamPattern::VarName(name) = &param.pat {
+                let var = Variable::Var(name.inspect().clone(), name.loc());
+                guards.push(GuardType::new(var, rest));
+            }
+            return guards;
+        };
+        if !matches!(
+            param.pat,
+            ast::ParamPattern::Discard(_) | ast::ParamPattern::VarName(_)
+        ) {
+            return vec![];
+        }
+        let Ok(to) = self.module.context.instantiate_typespec(&t_spec.t_spec) else {
+            return vec![];
+        };
+        // literal patterns (`1 -> ...`) are desugared into `(_: {1}) -> ...`, they are not class patterns
+        if to.is_refinement()
+            || to.has_qvar()
+            || to.has_unbound_var()
+            || !self.module.context.related(subject_t, &to)
+        {
+            return vec![];
+        }
+        // a guarded arm does not cover the type, e.g. `(i: Int) if i > 0 -> ...`
+        if lambda.sig.guard.is_none() {
+            *covered = self.module.context.union(covered, &to);
+        }
+        subject
+            .map(|var| GuardType::new(var.clone(), to))
+            .into_iter()
+            .collect()
+    }
+
     fn push_guard(&mut self, nth: usize, kind: ControlKind, t: &Type) {
         match t {
             Type::Guard(guard) => match nth {
@@ -959,7 +1660,7 @@ impl ASTLowerer {
         } else {
             None
         };
-        let hir_args = self.lower_args(call.args, &mut errs);
+        let mut hir_args = self.lower_args(call.args, &mut errs);
         let mut obj = match self.lower_expr(*call.obj) {
             Ok(obj) => obj,
             Err(es) => {
@@ -968,6 +1669,9 @@ impl ASTLowerer {
                 return Err(errs);
             }
         };
+        if call.attr_name.is_some() && obj.ref_t().is_module() {
+            self.module.context.force_deferred_mod(obj.ref_t());
+        }
         let mut vi = match self.module.context.get_call_t(
             &obj,
             &call.attr_name,
@@ -1000,6 +1704,7 @@ impl ASTLowerer {
                 *ref_t = guard;
             }
         }
+        self.coerce_record_args(&vi.t, call.attr_name.is_some(), &mut hir_args);
         let attr_name = if let Some(attr_name) = call.attr_name {
             self.inc_ref(attr_name.inspect(), &vi, &attr_name.name);
             Some(hir::Identifier::new(attr_name, None, vi))
@@ -1022,6 +1727,60 @@ impl ASTLowerer {
         Ok(call)
     }
 
+    /// Inserts the record projections for the record arguments wider than the parameters.
+    /// e.g. `f(r: {.x = Int}) = ...; f {.x = 1; .y = 2}` passes `{.x = 1}` (see `hir::Coercion`)
+    fn coerce_record_args(&self, callee_t: &Type, is_attr_call: bool, args: &mut hir::Args) {
+        let Ok(subr) = <&SubrType>::try_from(callee_t) else {
+            return;
+        };
+        let mut non_default_params = subr.non_default_params.iter();
+        if is_attr_call && subr.self_t().is_some() {
+            non_default_params.next();
+        }
+        let default_params = subr
+            .default_params
+            .iter()
+            .take_while(|_| subr.var_params.is_none());
+        for (arg, param) in args
+            .pos_args
+            .iter_mut()
+            .zip(non_default_params.chain(default_params))
+        {
+            self.coerce_record_arg(&mut arg.expr, param.typ());
+        }
+        for kw_arg in args.kw_args.iter_mut() {
+            let param = subr
+                .non_default_params
+                .iter()
+                .chain(subr.default_params.iter())
+                .find(|pt| pt.name() == Some(kw_arg.keyword.inspect()));
+            if let Some(param) = param {
+                self.coerce_record_arg(&mut kw_arg.expr, param.typ());
+            }
+        }
+    }
+
+    fn coerce_record_arg(&self, arg: &mut hir::Expr, param_t: &Type) {
+        if !arg.ref_t().is_record() || !param_t.is_record() {
+            return;
+        }
+        let arg_fields = self.module.context.fields(arg.ref_t());
+        let param_fields = self.module.context.fields(param_t);
+        if arg_fields.len() <= param_fields.len()
+            || param_fields
+                .keys()
+                .any(|field| !arg_fields.contains_key(field))
+        {
+            return;
+        }
+        let fields = arg_fields
+            .into_iter()
+            .filter(|(field, _)| param_fields.contains_key(field))
+            .collect::<Dict<_, _>>();
+        let expr = mem::take(arg);
+        *arg = hir::Expr::Coerce(hir::Coercion::new(expr, Type::Record(fields)));
+    }
+
     /// importing is done in [preregister](https://github.com/erg-lang/erg/blob/ffd33015d540ff5a0b853b28c01370e46e0fcc52/crates/erg_compiler/context/register.rs#L819)
     fn exec_additional_op(&mut self, call: &mut hir::Call) -> LowerResult<()> {
         match call.additional_operation() {
@@ -1192,6 +1951,11 @@ impl ASTLowerer {
                 .context
                 .control_kind()
                 .map_or(false, |k| k.makes_scope());
+        let in_match = self
+            .module
+            .context
+            .control_kind()
+            .is_some_and(|kind| kind.is_match());
         let is_procedural = lambda.is_procedural();
         let id = lambda.id.0;
         let name = format!("<lambda_{id}>");
@@ -1218,7 +1982,7 @@ impl ASTLowerer {
         if let Err(errs) = self.module.context.assign_params(&mut params, None) {
             self.errs.extend(errs);
         }
-        let overwritten = {
+        let mut overwritten = {
             let mut overwritten = vec![];
             let guards = if in_statement {
                 mem::take(&mut self.module.context.guards)
@@ -1232,6 +1996,18 @@ impl ASTLowerer {
             }
             overwritten
         };
+        let guard = match lambda
+            .sig
+            .guard
+            .map(|guard| self.lower_match_guard(*guard, in_match, &mut overwritten))
+            .transpose()
+        {
+            Ok(guard) => guard,
+            Err(errs) => {
+                self.errs.extend(errs);
+                None
+            }
+        };
         if let Err(errs) = self.module.context.preregister(&lambda.body) {
             self.errs.extend(errs);
         }
@@ -1361,7 +2137,53 @@ impl ASTLowerer {
             )
         };
         let t = if ty.has_qvar() { ty.quantify() } else { ty };
-        Ok(hir::Lambda::new(id, params, lambda.op, body, t))
+        Ok(hir::Lambda::new(id, params, lambda.op, body, t).with_guard(guard))
+    }
+
+    /// `match x: (i: Int) if i > 0 -> ...`
+    /// The guard must be a `Bool`, and if it is a comparison such as `i > 0`, `i` is narrowed to `{I: Int | I > 0}` in the arm.
+    fn lower_match_guard(
+        &mut self,
+        guard: ast::Expr,
+        in_match: bool,
+        overwritten: &mut Vec<(VarName, VarInfo)>,
+    ) -> LowerResult<hir::Expr> {
+        if !in_match {
+            return Err(LowerErrors::from(LowerError::syntax_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                guard.loc(),
+                self.module.context.caused_by(),
+                switch_lang!(
+                    "japanese" => "ガードはmatchの分岐でのみ使用できます",
+                    "simplified_chinese" => "守卫只能在match分支中使用",
+                    "traditional_chinese" => "守衛只能在match分支中使用",
+                    "english" => "guards can only be used in match arms",
+                )
+                .to_owned(),
+                None,
+            )));
+        }
+        let guard = self.lower_expr(guard)?;
+        self.module
+            .context
+            .sub_unify(guard.ref_t(), &Type::Bool, &guard, None)?;
+        let mut guards = vec![guard.ref_t()];
+        while let Some(t) = guards.pop() {
+            match t {
+                Type::Guard(guard) => {
+                    if let Err(errs) = self.module.context.cast(guard.clone(), overwritten) {
+                        self.errs.extend(errs);
+                    }
+                }
+                Type::And(lhs, rhs) => {
+                    guards.push(lhs);
+                    guards.push(rhs);
+                }
+                _ => {}
+            }
+        }
+        Ok(guard)
     }
 
     fn lower_def(&mut self, def: ast::Def) -> LowerResult<hir::Def> {
@@ -1455,13 +2277,42 @@ impl ASTLowerer {
         body: ast::DefBody,
     ) -> LowerResult<hir::Def> {
         log!(info "entered {}({sig})", fn_name!());
-        if let Err(errs) = self.module.context.preregister(&body.block) {
-            self.errs.extend(errs);
+        // `y = x: Int` (and `{x: Int}`) does not declare `x`
+        let is_type_asc = body.block.len() == 1
+            && matches!(body.block.first(), Some(ast::Expr::TypeAscription(_)));
+        if !is_type_asc {
+            if let Err(errs) = self.module.context.preregister(&body.block) {
+                self.errs.extend(errs);
+            }
         }
-        match self.lower_block(body.block) {
+        let block = match body.block.first() {
+            Some(ast::Expr::Call(call))
+                if body.block.len() == 1
+                    && call.obj.get_name().is_some_and(|n| n == "unsafe_python") =>
+            {
+                self.lower_unsafe_python(call.clone(), sig.t_spec.as_ref())
+                    .map(|call| hir::Block::new(vec![hir::Expr::Call(call)]))
+            }
+            Some(ast::Expr::Call(call))
+                if body.block.len() == 1
+                    && call
+                        .obj
+                        .get_name()
+                        .is_some_and(|n| n == "asm" || n == "intrinsic") =>
+            {
+                self.lower_intrinsic(call.clone(), sig.t_spec.as_ref())
+                    .map(|call| hir::Block::new(vec![hir::Expr::Call(call)]))
+            }
+            // the ascription is checked here and the expression itself is the body
+            Some(ast::Expr::TypeAscription(tasc)) if is_type_asc => self
+                .lower_type_asc(tasc.clone())
+                .map(|tasc| hir::Block::new(vec![*tasc.expr])),
+            _ => self.lower_block(body.block),
+        };
+        match block {
             Ok(block) => {
                 let found_body_t = block.ref_t();
-                let outer = self.module.context.outer.as_ref().unwrap();
+                let outer = self.module.context.get_outer().unwrap();
                 let opt_expect_body_t = sig
                     .ident()
                     .and_then(|ident| outer.get_current_scope_var(&ident.name))
@@ -1499,12 +2350,37 @@ impl ASTLowerer {
                         }
                     }
                 }
-                let vi = self.module.context.outer.as_mut().unwrap().assign_var_sig(
+                // a variable bound to a lambda (e.g. `double = (* 2)`) is polymorphic like a subroutine definition
+                let found_body_t = if sig.t_spec.is_none()
+                    && block.len() == 1
+                    && matches!(block.first(), Some(hir::Expr::Lambda(_)))
+                {
+                    found_body_t.lift();
+                    let outer = self.module.context.get_outer().unwrap();
+                    outer.generalize_t(found_body_t.clone())
+                } else {
+                    found_body_t.clone()
+                };
+                let vi = self.module.context.get_mut_outer().unwrap().assign_var_sig(
                     &sig,
-                    found_body_t,
+                    &found_body_t,
                     body.id,
                     None,
                 )?;
+                // `.x = foo.x` (and `{.x;} = import "foo"`) re-exports `x` of `foo`
+                if let Some(hir::Expr::Accessor(hir::Accessor::Attr(attr))) = block.first() {
+                    let outer = self.module.context.get_mut_outer().unwrap();
+                    if block.len() == 1 && vi.vis.is_public() && outer.kind.is_module() {
+                        if let Some(path) = outer.get_path_with_mod_t(attr.obj.ref_t()) {
+                            outer
+                                .re_exports
+                                .insert(ident.name.clone(), (path, attr.ident.inspect().clone()));
+                        }
+                    }
+                }
+                if let Some(ts) = sig.t_spec.as_ref().filter(|ts| ts.t_spec.is_hole()) {
+                    self.holes.push((ts.t_spec.loc(), found_body_t));
+                }
                 let ident = hir::Identifier::new(ident, None, vi);
                 let t_spec = if let Some(ts) = sig.t_spec {
                     let spec_t = self.module.context.instantiate_typespec(&ts.t_spec)?;
@@ -1518,7 +2394,7 @@ impl ASTLowerer {
                 Ok(hir::Def::new(hir::Signature::Var(sig), body))
             }
             Err(errs) => {
-                self.module.context.outer.as_mut().unwrap().assign_var_sig(
+                self.module.context.get_mut_outer().unwrap().assign_var_sig(
                     &sig,
                     &Type::Failure,
                     ast::DefId(0),
@@ -1539,8 +2415,7 @@ impl ASTLowerer {
         let registered_t = self
             .module
             .context
-            .outer
-            .as_ref()
+            .get_outer()
             .unwrap()
             .get_current_scope_var(&sig.ident.name)
             .map(|vi| vi.t.clone())
@@ -1554,10 +2429,23 @@ impl ASTLowerer {
                 if let Err(errs) = self.module.context.preregister(&body.block) {
                     self.errs.extend(errs);
                 }
-                match self.lower_block(body.block) {
+                let block = match body.block.first() {
+                    Some(ast::Expr::Call(call))
+                        if body.block.len() == 1
+                            && call
+                                .obj
+                                .get_name()
+                                .is_some_and(|n| n == "asm" || n == "intrinsic") =>
+                    {
+                        self.lower_intrinsic(call.clone(), sig.return_t_spec.as_ref())
+                            .map(|call| hir::Block::new(vec![hir::Expr::Call(call)]))
+                    }
+                    _ => self.lower_block(body.block),
+                };
+                match block {
                     Ok(block) => {
                         let found_body_t = self.module.context.squash_tyvar(block.t());
-                        let vi = match self.module.context.outer.as_mut().unwrap().assign_subr(
+                        let vi = match self.module.context.get_mut_outer().unwrap().assign_subr(
                             &sig,
                             body.id,
                             &found_body_t,
@@ -1569,6 +2457,13 @@ impl ASTLowerer {
                                 vi
                             }
                         };
+                        if let Some(ts) =
+                            sig.return_t_spec.as_ref().filter(|ts| ts.t_spec.is_hole())
+                        {
+                            if let Some(return_t) = vi.t.return_t() {
+                                self.holes.push((ts.t_spec.loc(), return_t.clone()));
+                            }
+                        }
                         let ident = hir::Identifier::new(sig.ident, None, vi);
                         let ret_t_spec = if let Some(ts) = sig.return_t_spec {
                             let spec_t = self.module.context.instantiate_typespec(&ts.t_spec)?;
@@ -1583,7 +2478,7 @@ impl ASTLowerer {
                     }
                     Err(errs) => {
                         self.errs.extend(errs);
-                        let vi = match self.module.context.outer.as_mut().unwrap().assign_subr(
+                        let vi = match self.module.context.get_mut_outer().unwrap().assign_subr(
                             &sig,
                             ast::DefId(0),
                             &Type::Failure,
@@ -1621,8 +2516,7 @@ impl ASTLowerer {
                 }
                 self.module
                     .context
-                    .outer
-                    .as_mut()
+                    .get_mut_outer()
                     .unwrap()
                     .fake_subr_assign(&sig.ident, &sig.decorators, Type::Failure)?;
                 let block = self.lower_block(body.block)?;
@@ -2109,6 +3003,9 @@ impl ASTLowerer {
         Ok(())
     }
 
+    /// The requirements of the supertraits are also checked (e.g. implementing `B = Subsume A` requires the members of `A`).
+    /// If the subtrait redeclares a member (`@Override`), the redeclared type is required.
+    /// A member of a supertrait may be defined in another implementation block (e.g. `C|<: A|.`).
     fn check_methods_compatibility(
         &self,
         impl_trait: &Type,
@@ -2118,44 +3015,84 @@ impl ASTLowerer {
     ) -> (Set<&VarName>, CompileErrors) {
         let mut errors = CompileErrors::empty();
         let mut unverified_names = self.module.context.locals.keys().collect::<Set<_>>();
-        for (decl_name, decl_vi) in trait_ctx.decls.iter() {
-            if let Some((name, vi)) = self.module.context.get_var_kv(decl_name.inspect()) {
-                let def_t = &vi.t;
-                let replaced_decl_t = decl_vi
-                    .t
-                    .clone()
-                    .replace(trait_type, impl_trait)
-                    .replace(impl_trait, class);
-                unverified_names.remove(name);
-                if !self.module.context.supertype_of(&replaced_decl_t, def_t) {
-                    errors.push(LowerError::trait_member_type_error(
+        let outer = self.module.context.get_outer().unwrap();
+        let sup_ctxs = trait_ctx
+            .super_traits
+            .iter()
+            .filter_map(|sup| outer.get_nominal_type_ctx(sup));
+        let mut checked = Set::new();
+        for (origin, origin_ctx) in [(trait_type, trait_ctx)].into_iter().chain(sup_ctxs) {
+            let is_sup = origin != trait_type;
+            for (decl_name, decl_vi) in origin_ctx.decls.iter() {
+                // overridden by the subtrait
+                if !checked.insert(decl_name.inspect()) {
+                    continue;
+                }
+                if let Some((name, vi)) = self.module.context.get_var_kv(decl_name.inspect()) {
+                    let def_t = &vi.t;
+                    let replaced_decl_t = if is_sup {
+                        decl_vi.t.clone().replace(origin, class)
+                    } else {
+                        decl_vi
+                            .t
+                            .clone()
+                            .replace(trait_type, impl_trait)
+                            .replace(impl_trait, class)
+                    };
+                    unverified_names.remove(name);
+                    if !self.module.context.supertype_of(&replaced_decl_t, def_t) {
+                        errors.push(LowerError::trait_member_type_error(
+                            self.cfg.input.clone(),
+                            line!() as usize,
+                            name.loc(),
+                            self.module.context.caused_by(),
+                            name.inspect(),
+                            if is_sup { origin } else { impl_trait },
+                            &decl_vi.t,
+                            &vi.t,
+                            is_sup.then(|| Self::supertrait_hint(origin, impl_trait)),
+                        ));
+                    }
+                } else if is_sup && self.is_defined_in_other_impls(class, decl_name) {
+                    continue;
+                } else {
+                    errors.push(LowerError::trait_member_not_defined_error(
                         self.cfg.input.clone(),
                         line!() as usize,
-                        name.loc(),
                         self.module.context.caused_by(),
-                        name.inspect(),
-                        impl_trait,
-                        &decl_vi.t,
-                        &vi.t,
-                        None,
+                        decl_name.inspect(),
+                        if is_sup { origin } else { impl_trait },
+                        class,
+                        is_sup.then(|| Self::supertrait_hint(origin, impl_trait)),
+                        t_spec.loc(),
                     ));
                 }
-            } else {
-                errors.push(LowerError::trait_member_not_defined_error(
-                    self.cfg.input.clone(),
-                    line!() as usize,
-                    self.module.context.caused_by(),
-                    decl_name.inspect(),
-                    impl_trait,
-                    class,
-                    None,
-                    t_spec.loc(),
-                ));
             }
         }
         (unverified_names, errors)
     }
 
+    fn supertrait_hint(sup: &Type, sub: &Type) -> String {
+        switch_lang!(
+            "japanese" => format!("{sup}は{sub}のスーパートレイトです"),
+            "simplified_chinese" => format!("{sup}是{sub}的超特性"),
+            "traditional_chinese" => format!("{sup}是{sub}的超特性"),
+            "english" => format!("{sup} is a supertrait of {sub}"),
+        )
+    }
+
+    /// Whether `name` is defined in the other method definition blocks of `class`
+    fn is_defined_in_other_impls(&self, class: &Type, name: &VarName) -> bool {
+        let outer = self.module.context.get_outer().unwrap();
+        let Some((_, class_ctx)) = outer.get_nominal_type_ctx(class) else {
+            return false;
+        };
+        class_ctx
+            .methods_list
+            .iter()
+            .any(|(_, methods)| methods.locals.contains_key(name.inspect()))
+    }
+
     fn check_collision_and_push(&mut self, class: Type) {
         let methods = self.module.context.pop();
         let Some((_, class_root)) = self
@@ -2350,6 +3287,7 @@ impl ASTLowerer {
                     similar_name,
                     similar_info,
                 )
+                .with_import_suggestion(self.module.context.get_import_suggestion(ident.inspect()))
             })?;
         match kind {
             AscriptionKind::TypeOf | AscriptionKind::AsCast => {
@@ -2397,9 +3335,51 @@ impl ASTLowerer {
             ast::Expr::Record(rec) => Ok(hir::Expr::Record(self.lower_record(rec)?)),
             ast::Expr::Set(set) => Ok(hir::Expr::Set(self.lower_set(set)?)),
             ast::Expr::Dict(dict) => Ok(hir::Expr::Dict(self.lower_dict(dict)?)),
-            ast::Expr::Accessor(acc) => Ok(hir::Expr::Accessor(self.lower_acc(acc)?)),
+            ast::Expr::Accessor(acc) => {
+                let acc = self.lower_acc(acc)?;
+                Ok(self.fold_compile_time_flag(acc))
+            }
             ast::Expr::BinOp(bin) => Ok(hir::Expr::BinOp(self.lower_bin(bin))),
             ast::Expr::UnaryOp(unary) => Ok(hir::Expr::UnaryOp(self.lower_unary(unary))),
+            ast::Expr::Call(call) if call.obj.get_name().is_some_and(|n| n == "static_assert") => {
+                self.lower_static_assert(call)
+            }
+            ast::Expr::Call(call) if call.obj.get_name().is_some_and(|n| n == "debug_only!") => {
+                self.lower_debug_only(call)
+            }
+            ast::Expr::Call(call)
+                if call.obj.get_name().is_some_and(|n| {
+                    matches!(
+                        &n[..],
+                        "type_name"
+                            | "fields"
+                            | "methods"
+                            | "supers"
+                            | "parse_json"
+                            | "include_str"
+                            | "include_bytes"
+                    )
+                }) =>
+            {
+                self.lower_compile_time_call(call)
+            }
+            ast::Expr::Call(call) if call.obj.get_name().is_some_and(|n| n == "quote") => {
+                self.lower_quote(call)
+            }
+            ast::Expr::Call(call) if self.module.context.get_macro(&call).is_some() => {
+                self.lower_macro_call(call)
+            }
+            ast::Expr::Call(call) if call.obj.get_name().is_some_and(|n| n == "unsafe_python") => {
+                Ok(hir::Expr::Call(self.lower_unsafe_python(call, None)?))
+            }
+            ast::Expr::Call(call)
+                if call
+                    .obj
+                    .get_name()
+                    .is_some_and(|n| n == "asm" || n == "intrinsic") =>
+            {
+                Ok(hir::Expr::Call(self.lower_intrinsic(call, None)?))
+            }
             ast::Expr::Call(call) => Ok(hir::Expr::Call(self.lower_call(call)?)),
             ast::Expr::DataPack(pack) => Ok(hir::Expr::Call(self.lower_pack(pack)?)),
             ast::Expr::Lambda(lambda) => Ok(hir::Expr::Lambda(self.lower_lambda(lambda)?)),
@@ -2463,13 +3443,117 @@ impl ASTLowerer {
         )
     }
 
+    /// Selects the doc comment for `__doc__` from the consecutive doc comments
+    /// (the one in the current language, or the English one), and strips the language code and the indentation.
+    fn python_doc(docs: &[Str]) -> Option<Str> {
+        let docs = docs
+            .iter()
+            .map(|doc| doc.trim_start_matches("'''").trim_end_matches("'''"))
+            .collect::<Vec<_>>();
+        let lang_of = |doc: &&str| {
+            doc.lines()
+                .next()
+                .unwrap_or("")
+                .parse()
+                .unwrap_or(LanguageCode::English)
+        };
+        let doc = docs
+            .iter()
+            .find(|doc| lang_of(doc).matches_feature() && !lang_of(doc).is_pl())
+            .or_else(|| docs.iter().find(|doc| lang_of(doc).is_en()))?;
+        let (first, rest) = doc.split_once('\n').unwrap_or((*doc, ""));
+        let body = if first.trim().is_empty() || first.parse::<LanguageCode>().is_ok() {
+            rest
+        } else {
+            doc
+        };
+        let indent = body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines = body
+            .lines()
+            .map(|line| line.get(indent..).unwrap_or("").trim_end())
+            .collect::<Vec<_>>();
+        let doc = lines.join("\n");
+        let doc = doc.trim_matches('\n');
+        (!doc.is_empty()).then(|| Str::rc(doc))
+    }
+
+    /// Attaches the doc comments to the following definitions (`Def::doc`, `ClassDef::doc`).
+    fn attach_doc_comments(chunks: &mut [hir::Expr]) {
+        let mut docs = vec![];
+        for chunk in chunks.iter_mut() {
+            match chunk {
+                hir::Expr::Lit(lit) if lit.is_doc_comment() => {
+                    if let ValueObj::Str(doc) = &lit.value {
+                        docs.push(doc.clone());
+                    }
+                    continue;
+                }
+                hir::Expr::Def(def) => {
+                    def.doc = Self::python_doc(&docs);
+                    Self::attach_doc_comments(def.body.block.ref_mut_payload());
+                }
+                hir::Expr::ClassDef(class_def) => {
+                    class_def.doc = Self::python_doc(&docs);
+                    Self::attach_doc_comments(class_def.methods.ref_mut_payload());
+                }
+                hir::Expr::PatchDef(patch_def) => {
+                    Self::attach_doc_comments(patch_def.methods.ref_mut_payload());
+                }
+                _ => {}
+            }
+            docs.clear();
+        }
+    }
+
+    /// In the `--no-panic` mode, a panic is caught and reported as an internal compiler error
+    /// with the AST of the chunk being lowered.
     pub fn lower(&mut self, ast: AST, mode: &str) -> Result<CompleteArtifact, IncompleteArtifact> {
+        if !self.cfg.no_panic {
+            return self.lower_module(ast, mode);
+        }
+        CrashReporter::install_hook();
+        match CrashReporter::catch(|| self.lower_module(ast, mode)) {
+            Ok(res) => res,
+            Err(crash) => {
+                let (loc, snippet) = self
+                    .lowering_chunk
+                    .take()
+                    .map_or((Location::Unknown, None), |(loc, ast)| (loc, Some(ast)));
+                // the contexts may be left inconsistent, so the lowering is not continued
+                self.errs.push(LowerError::internal_compiler_error(
+                    self.cfg.input.clone(),
+                    loc,
+                    &crash,
+                    snippet,
+                    self.module.context.caused_by(),
+                ));
+                Err(IncompleteArtifact::new(
+                    None,
+                    LowerErrors::from(self.errs.take_all()),
+                    LowerWarnings::from(self.warns.take_all()),
+                ))
+            }
+        }
+    }
+
+    fn lower_module(
+        &mut self,
+        ast: AST,
+        mode: &str,
+    ) -> Result<CompleteArtifact, IncompleteArtifact> {
+        let _span = trace_span!("lower", ast.name);
         log!(info "the AST lowering process has started.");
         log!(info "the type-checking process has started.");
         if let Some(path) = self.cfg.input.path() {
             let graph = &self.module.context.shared().graph;
             graph.add_node_if_none(path);
         }
+        self.module.features = ast.features;
         let ast = ASTLinker::new(self.cfg.clone())
             .link(ast, mode)
             .map_err(|errs| {
@@ -2494,6 +3578,9 @@ impl ASTLowerer {
             self.errs.extend(errs);
         }
         for chunk in ast.module.into_iter() {
+            if self.cfg.no_panic {
+                self.lowering_chunk = Some((chunk.loc(), chunk.to_string()));
+            }
             match self.lower_chunk(chunk) {
                 Ok(chunk) => {
                     module.push(chunk);
@@ -2503,29 +3590,42 @@ impl ASTLowerer {
                 }
             }
         }
+        self.lowering_chunk = None;
         self.module.context.clear_invalid_vars();
         self.module.context.check_decls().unwrap_or_else(|errs| {
             self.errs.extend(errs);
         });
         let hir = HIR::new(ast.name, module);
         log!(info "HIR (not resolved, current errs: {}):\n{hir}", self.errs.len());
-        let hir = match self.module.context.resolve(hir) {
+        let mut hir = match self.module.context.resolve(hir) {
             Ok(hir) => {
                 log!(info "HIR (resolved):\n{hir}");
                 hir
             }
             Err((hir, errs)) => {
                 self.errs.extend(errs);
+                self.warn_type_holes();
                 log!(err "the resolving process has failed. errs:  {}", self.errs.len());
                 return Err(self.return_incomplete_artifact(hir));
             }
         };
         self.warn_implicit_union(&hir);
+        self.warn_type_holes();
         self.warn_unused_expr(&hir.module, mode);
+        self.warn_unreachable_code(&hir.module);
         self.check_doc_comments(&hir);
+        if !self.cfg.strip_docs {
+            Self::attach_doc_comments(hir.module.ref_mut_payload());
+        }
         self.warn_unused_local_vars(mode);
         if &self.module.context.name[..] == "<module>" || ELS {
-            if ELS {
+            if self.cfg.lazy_import
+                && &self.module.context.name[..] == "<module>"
+                && !matches!(self.cfg.mode, ErgMode::TypeCheck | ErgMode::FullCheck)
+            {
+                // the bodies of the deferred modules are required to generate code
+                self.module.context.force_all_deferred_mods();
+            } else if ELS {
                 self.module.context.shared().promises.join_children();
             } else {
                 self.module.context.shared().promises.join_all();
@@ -2534,9 +3634,14 @@ impl ASTLowerer {
             let warns = self.module.context.shared().warns.take();
             self.errs.extend(errs);
             self.warns.extend(warns);
+            if self.cfg.verify_decls {
+                let verifier = DeclVerifier::new(&self.cfg, self.module.context.shared());
+                self.warns.extend(verifier.verify());
+            }
         }
         if self.errs.is_empty() {
             log!(info "the AST lowering process has completed.");
+            InvariantChecker::check(&hir, "lower");
             Ok(CompleteArtifact::new(
                 hir,
                 LowerWarnings::from(self.warns.take_all()),
@@ -3,13 +3,25 @@ extern crate erg_compiler;
 extern crate erg_parser;
 
 use erg_common::config::{ErgConfig, ErgMode::*};
+use erg_common::consts::MEM_PROFILE;
 use erg_common::spawn::exec_new_thread;
+use erg_common::trace::GLOBAL_TRACER;
 use erg_common::traits::{ExitStatus, Runnable};
 
 use erg_compiler::build_hir::HIRBuilder;
+use erg_compiler::build_package::PackageBuilder;
+use erg_compiler::context::mem_profile;
+use erg_compiler::crash::CrashReporter;
+use erg_compiler::explain::Explainer;
+use erg_compiler::fix::Fixer;
 use erg_compiler::lower::ASTLowerer;
+use erg_compiler::pack::Packer;
+use erg_compiler::sourcemap::TracebackFilter;
+use erg_compiler::stub::PyDeclGenerator;
+use erg_compiler::test_runner::TestRunner;
 use erg_compiler::transpile::Transpiler;
 use erg_compiler::ty::deserialize::Deserializer;
+use erg_compiler::watch::Watcher;
 use erg_compiler::Compiler;
 
 use erg_parser::lex::LexerRunner;
@@ -17,19 +29,43 @@ use erg_parser::ParserRunner;
 
 fn run() {
     let cfg = ErgConfig::parse();
-    let stat = match cfg.mode {
+    let trace_output = cfg.trace_output;
+    let timings = cfg.timings;
+    if trace_output.is_some() || timings {
+        GLOBAL_TRACER.enable();
+    }
+    let stat = CrashReporter::guard(cfg.copy(), || match cfg.mode {
         Lex => LexerRunner::run(cfg),
         Parse => ParserRunner::run(cfg),
         TypeCheck => ASTLowerer::run(cfg),
+        FullCheck if cfg.watch => Watcher::run(cfg),
         FullCheck => HIRBuilder::run(cfg),
         Transpile => Transpiler::run(cfg),
         Compile | Execute => Compiler::run(cfg),
         Read => Deserializer::run(cfg),
+        GenDecl => PyDeclGenerator::run(cfg),
+        Traceback => TracebackFilter::run(cfg),
+        Fix => Fixer::run(cfg),
+        Pack => Packer::run(cfg),
+        Build => PackageBuilder::run(cfg),
+        Explain => Explainer::run(cfg),
+        Test => TestRunner::run(cfg),
         other => {
             println!("invalid mode: {other}");
             ExitStatus::ERR1
         }
-    };
+    });
+    if let Some(path) = trace_output {
+        if let Err(err) = GLOBAL_TRACER.dump(path) {
+            eprintln!("failed to write the trace to {path}: {err}");
+        }
+    }
+    if timings {
+        eprint!("{}", GLOBAL_TRACER.timings());
+    }
+    if MEM_PROFILE {
+        eprint!("{}", mem_profile::report(20));
+    }
     std::process::exit(stat.code);
 }
 
@@ -0,0 +1,97 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use erg_common::dict::Dict;
+use erg_common::shared::Shared;
+
+use erg_parser::ast::AST;
+use erg_parser::error::{CompleteArtifact, IncompleteArtifact, ParserRunnerErrors};
+
+pub type ParseResult =
+    Result<CompleteArtifact<AST, ParserRunnerErrors>, IncompleteArtifact<AST, ParserRunnerErrors>>;
+
+/// Holds the ASTs of the modules parsed ahead of the type checking (see `ModulePrefetcher`).
+/// An entry is taken out when the module is analyzed, so each AST is checked at most once.
+#[derive(Debug, Default)]
+pub struct ASTCache {
+    cache: Dict<PathBuf, ParseResult>,
+}
+
+impl fmt::Display for ASTCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ASTCache {{")?;
+        for path in self.cache.keys() {
+            writeln!(f, "{}, ", path.display())?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl ASTCache {
+    pub fn new() -> Self {
+        Self { cache: Dict::new() }
+    }
+
+    pub fn register(&mut self, path: PathBuf, result: ParseResult) {
+        self.cache.insert(path, result);
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.cache.contains_key(path)
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Option<ParseResult> {
+        self.cache.remove(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn initialize(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SharedASTCache(Shared<ASTCache>);
+
+impl fmt::Display for SharedASTCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Shared{}", self.0)
+    }
+}
+
+impl SharedASTCache {
+    pub fn new() -> Self {
+        Self(Shared::new(ASTCache::new()))
+    }
+
+    pub fn register(&self, path: PathBuf, result: ParseResult) {
+        self.0.borrow_mut().register(path, result);
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.0.borrow().contains(path)
+    }
+
+    pub fn remove(&self, path: &Path) -> Option<ParseResult> {
+        self.0.borrow_mut().remove(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    pub fn initialize(&self) {
+        self.0.borrow_mut().initialize();
+    }
+}
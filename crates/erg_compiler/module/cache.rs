@@ -7,7 +7,7 @@ use std::sync::Arc;
 use erg_common::config::ErgConfig;
 use erg_common::dict::Dict;
 use erg_common::levenshtein::get_similar_name;
-use erg_common::pathutil::NormalizedPathBuf;
+use erg_common::pathutil::{same_file, NormalizedPathBuf};
 use erg_common::shared::{
     MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLockReadGuard, RwLockWriteGuard, Shared,
 };
@@ -15,6 +15,7 @@ use erg_common::Str;
 
 use crate::context::ModuleContext;
 use crate::hir::HIR;
+use crate::module::ParseResult;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ModId(usize);
@@ -31,11 +32,20 @@ impl ModId {
     }
 }
 
+/// A module of which only the public signature has been checked (`--lazy-import`).
+/// The body is checked when a member of the module is used.
+#[derive(Debug, Clone)]
+pub struct DeferredModule {
+    pub name: Str,
+    pub parsed: Arc<ParseResult>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ModuleEntry {
     pub id: ModId, // builtin == 0, __main__ == 1
     pub hir: Option<HIR>,
     pub module: Arc<ModuleContext>,
+    pub deferred: Option<DeferredModule>,
 }
 
 impl fmt::Display for ModuleEntry {
@@ -54,6 +64,7 @@ impl ModuleEntry {
             id,
             hir,
             module: Arc::new(ctx),
+            deferred: None,
         }
     }
 
@@ -62,9 +73,14 @@ impl ModuleEntry {
             id: ModId::builtin(),
             hir: None,
             module: Arc::new(ctx),
+            deferred: None,
         }
     }
 
+    pub const fn is_deferred(&self) -> bool {
+        self.deferred.is_some()
+    }
+
     pub fn cfg(&self) -> &ErgConfig {
         &self.module.context.cfg
     }
@@ -73,9 +89,15 @@ impl ModuleEntry {
 /// Caches checked modules.
 /// In addition to being queried here when re-imported, it is also used when linking
 /// (Erg links all scripts defined in erg and outputs them to a single pyc file).
+///
+/// A module is identified by its physical file.
+/// If the same file is reachable through another path (e.g. a hard-linked vendored copy),
+/// the path is registered as an alias of the cached one, so that the module is checked only once.
 #[derive(Debug, Default)]
 pub struct ModuleCache {
     cache: Dict<NormalizedPathBuf, ModuleEntry>,
+    /// alias -> registered path
+    aliases: Dict<NormalizedPathBuf, NormalizedPathBuf>,
     last_id: usize,
 }
 
@@ -93,6 +115,7 @@ impl ModuleCache {
     pub fn new() -> Self {
         Self {
             cache: Dict::new(),
+            aliases: Dict::new(),
             last_id: 0,
         }
     }
@@ -101,13 +124,19 @@ impl ModuleCache {
     where
         NormalizedPathBuf: Borrow<P>,
     {
-        self.cache.get(path)
+        self.cache.get(path).or_else(|| {
+            let orig: &P = self.aliases.get(path)?.borrow();
+            self.cache.get(orig)
+        })
     }
 
     pub fn get_mut<Q: Eq + Hash + ?Sized>(&mut self, path: &Q) -> Option<&mut ModuleEntry>
     where
         NormalizedPathBuf: Borrow<Q>,
     {
+        if let Some(orig) = self.aliases.get(path).cloned() {
+            return self.cache.get_mut::<NormalizedPathBuf>(&orig);
+        }
         self.cache.get_mut(path)
     }
 
@@ -115,14 +144,81 @@ impl ModuleCache {
         self.last_id += 1;
         let id = ModId::new(self.last_id);
         let entry = ModuleEntry::new(id, hir, ctx);
+        let path = self.aliases.get(&path).cloned().unwrap_or(path);
         self.cache.insert(path, entry);
     }
 
+    /// Registers the signature of the module, deferring the check of the body (`--lazy-import`).
+    pub fn register_deferred(
+        &mut self,
+        path: NormalizedPathBuf,
+        ctx: ModuleContext,
+        deferred: DeferredModule,
+    ) {
+        self.register(path.clone(), None, ctx);
+        if let Some(entry) = self.get_mut::<NormalizedPathBuf>(&path) {
+            entry.deferred = Some(deferred);
+        }
+    }
+
+    /// Takes out the deferred state of the module, so that the body is checked only once.
+    pub fn take_deferred(&mut self, path: &Path) -> Option<DeferredModule> {
+        self.get_mut(path)?.deferred.take()
+    }
+
+    pub fn deferred_paths(&self) -> Vec<NormalizedPathBuf> {
+        self.cache
+            .iter()
+            .filter(|(_, entry)| entry.is_deferred())
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Registers the module shared with another cache (e.g. the builtin module) without copying it.
+    pub fn register_shared(&mut self, path: NormalizedPathBuf, module: Arc<ModuleContext>) {
+        self.last_id += 1;
+        let entry = ModuleEntry {
+            id: ModId::new(self.last_id),
+            hir: None,
+            module,
+            deferred: None,
+        };
+        self.cache.insert(path, entry);
+    }
+
+    /// Registers `alias` as another path of the module `path`.
+    pub fn register_alias(&mut self, alias: NormalizedPathBuf, path: NormalizedPathBuf) {
+        if alias != path {
+            self.aliases.insert(alias, path);
+        }
+    }
+
+    /// Returns the registered path of the module which is the same file as `path`.
+    pub fn find_same_file(&self, path: &Path) -> Option<NormalizedPathBuf> {
+        if let Some(orig) = self.aliases.get(path) {
+            return Some(orig.clone());
+        }
+        self.cache
+            .keys()
+            .find(|registered| {
+                let registered: &Path = registered;
+                registered != path && same_file(registered, path)
+            })
+            .cloned()
+    }
+
     pub fn remove<Q: Eq + Hash + ?Sized>(&mut self, path: &Q) -> Option<ModuleEntry>
     where
         NormalizedPathBuf: Borrow<Q>,
     {
-        self.cache.remove(path)
+        // removing an alias removes the module itself
+        if let Some(orig) = self.aliases.remove(path) {
+            return self.remove::<NormalizedPathBuf>(&orig);
+        }
+        let entry = self.cache.remove(path)?;
+        self.aliases
+            .retain(|_, orig| <NormalizedPathBuf as Borrow<Q>>::borrow(orig) != path);
+        Some(entry)
     }
 
     pub fn remove_by_id(&mut self, id: ModId) -> Option<ModuleEntry> {
@@ -155,6 +251,7 @@ impl ModuleCache {
 
     pub fn clear(&mut self) {
         self.cache.clear();
+        self.aliases.clear();
     }
 }
 
@@ -253,6 +350,29 @@ impl SharedModuleCache {
         self.0.borrow_mut().register(path.into(), hir, ctx);
     }
 
+    pub fn register_shared<P: Into<NormalizedPathBuf>>(&self, path: P, module: Arc<ModuleContext>) {
+        self.0.borrow_mut().register_shared(path.into(), module);
+    }
+
+    pub fn register_deferred<P: Into<NormalizedPathBuf>>(
+        &self,
+        path: P,
+        ctx: ModuleContext,
+        deferred: DeferredModule,
+    ) {
+        self.0
+            .borrow_mut()
+            .register_deferred(path.into(), ctx, deferred);
+    }
+
+    pub fn take_deferred(&self, path: &Path) -> Option<DeferredModule> {
+        self.0.borrow_mut().take_deferred(path)
+    }
+
+    pub fn deferred_paths(&self) -> Vec<NormalizedPathBuf> {
+        self.0.borrow().deferred_paths()
+    }
+
     pub fn remove<Q: Eq + Hash + ?Sized>(&self, path: &Q) -> Option<ModuleEntry>
     where
         NormalizedPathBuf: Borrow<Q>,
@@ -264,6 +384,20 @@ impl SharedModuleCache {
         self.0.borrow_mut().remove_by_id(id)
     }
 
+    pub fn register_alias<P: Into<NormalizedPathBuf>, Q: Into<NormalizedPathBuf>>(
+        &self,
+        alias: P,
+        path: Q,
+    ) {
+        self.0
+            .borrow_mut()
+            .register_alias(alias.into(), path.into());
+    }
+
+    pub fn find_same_file(&self, path: &Path) -> Option<NormalizedPathBuf> {
+        self.0.borrow().find_same_file(path)
+    }
+
     pub fn get_similar_name(&self, name: &str) -> Option<Str> {
         self.0.borrow().get_similar_name(name)
     }
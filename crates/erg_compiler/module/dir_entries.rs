@@ -0,0 +1,84 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use erg_common::dict::Dict;
+use erg_common::pathutil::{case_collisions, dir_entries};
+use erg_common::shared::Shared;
+
+/// Caches the listings of the directories of imported modules.
+/// They are used to find the files whose names differ only in case (see `case_collisions`),
+/// so that a directory is not read again for every import.
+#[derive(Debug, Default)]
+pub struct DirEntries {
+    cache: Dict<PathBuf, Arc<Vec<PathBuf>>>,
+}
+
+impl fmt::Display for DirEntries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DirEntries {{")?;
+        for (dir, entries) in self.cache.iter() {
+            writeln!(f, "{}: {} entries, ", dir.display(), entries.len())?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl DirEntries {
+    pub fn new() -> Self {
+        Self { cache: Dict::new() }
+    }
+
+    pub fn get(&mut self, dir: &Path) -> Arc<Vec<PathBuf>> {
+        if let Some(entries) = self.cache.get(dir) {
+            return entries.clone();
+        }
+        let entries = Arc::new(dir_entries(dir));
+        self.cache.insert(dir.to_path_buf(), entries.clone());
+        entries
+    }
+
+    pub fn remove(&mut self, dir: &Path) {
+        self.cache.remove(dir);
+    }
+
+    pub fn initialize(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SharedDirEntries(Shared<DirEntries>);
+
+impl fmt::Display for SharedDirEntries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Shared{}", self.0)
+    }
+}
+
+impl SharedDirEntries {
+    pub fn new() -> Self {
+        Self(Shared::new(DirEntries::new()))
+    }
+
+    /// Returns the entries in the same directory as `path` whose names are equal to it ignoring case, if any.
+    pub fn case_collisions(&self, path: &Path) -> Vec<PathBuf> {
+        let Some(dir) = path.parent() else {
+            return vec![];
+        };
+        let entries = self.0.borrow_mut().get(dir);
+        case_collisions(path, &entries)
+    }
+
+    /// The listing of the directory of `path` is read again on the next access
+    /// (e.g. when `path` is created, removed or renamed).
+    pub fn invalidate(&self, path: &Path) {
+        if let Some(dir) = path.parent() {
+            self.0.borrow_mut().remove(dir);
+        }
+    }
+
+    pub fn initialize(&self) {
+        self.0.borrow_mut().initialize();
+    }
+}
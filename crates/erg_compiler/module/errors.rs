@@ -1,26 +1,48 @@
+use std::path::PathBuf;
+
+use erg_common::error::ErrorCore;
+use erg_common::set::Set;
 use erg_common::shared::Shared;
 use erg_common::traits::Stream;
 
 use crate::error::CompileErrors;
 
 #[derive(Debug, Clone, Default)]
-pub struct SharedCompileErrors(Shared<CompileErrors>);
+pub struct SharedCompileErrors {
+    errors: Shared<CompileErrors>,
+    /// (error, the path of the module where the error occurred)
+    reported: Shared<Set<(ErrorCore, Option<PathBuf>)>>,
+}
 
 impl SharedCompileErrors {
     pub fn new() -> Self {
-        Self(Shared::new(CompileErrors::empty()))
+        Self {
+            errors: Shared::new(CompileErrors::empty()),
+            reported: Shared::new(Set::new()),
+        }
     }
 
+    /// Errors that have already been reported are skipped
+    /// (the same module may be checked more than once, e.g. in a cyclic import).
     pub fn extend(&self, errors: CompileErrors) {
-        self.0.borrow_mut().extend(errors);
+        let mut ref_errors = self.errors.borrow_mut();
+        let mut reported = self.reported.borrow_mut();
+        for error in errors.into_iter() {
+            let key = (*error.core.clone(), error.input.path().map(PathBuf::from));
+            if reported.insert(key) {
+                ref_errors.push(error);
+            }
+        }
     }
 
     pub fn take(&self) -> CompileErrors {
-        self.0.borrow_mut().take_all().into()
+        *self.reported.borrow_mut() = Set::new();
+        self.errors.borrow_mut().take_all().into()
     }
 
     pub fn clear(&self) {
-        self.0.borrow_mut().clear();
+        *self.reported.borrow_mut() = Set::new();
+        self.errors.borrow_mut().clear();
     }
 }
 
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use erg_common::dict::Dict;
+use erg_common::error::ErrorKind;
+use erg_common::shared::Shared;
+use erg_common::Str;
+
+use crate::context::Context;
+
+/// Maps Python exception classes to Erg error kinds.
+/// Builtin exceptions are resolved by `ErrorKind::from_py_exception`,
+/// and user-defined exception classes are registered from declaration files (`.d.er`).
+///
+/// ```erg
+/// # foo.d.er
+/// .ValueError: ClassType
+/// .FooError: ClassType
+/// .FooError <: ValueError
+/// ```
+/// -> `foo.FooError` is treated as `ValueError`
+#[derive(Debug, Default)]
+pub struct PyExceptionMap {
+    map: Dict<Str, ErrorKind>,
+}
+
+impl fmt::Display for PyExceptionMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PyExceptionMap {{")?;
+        for (name, kind) in self.map.iter() {
+            writeln!(f, "{name}: {kind}, ")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl PyExceptionMap {
+    pub fn new() -> Self {
+        Self { map: Dict::new() }
+    }
+
+    pub fn register(&mut self, name: Str, kind: ErrorKind) {
+        self.map.insert(name, kind);
+    }
+
+    /// Registered (user-defined) classes take precedence over the builtin ones.
+    pub fn get(&self, name: &str) -> Option<ErrorKind> {
+        self.map
+            .get(name)
+            .copied()
+            .or_else(|| ErrorKind::from_py_exception(name))
+    }
+
+    pub fn registered(&self) -> impl Iterator<Item = (&Str, &ErrorKind)> {
+        self.map.iter()
+    }
+
+    pub fn initialize(&mut self) {
+        self.map.clear();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SharedPyExceptionMap(Shared<PyExceptionMap>);
+
+impl fmt::Display for SharedPyExceptionMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Shared{}", self.0)
+    }
+}
+
+impl SharedPyExceptionMap {
+    pub fn new() -> Self {
+        Self(Shared::new(PyExceptionMap::new()))
+    }
+
+    pub fn register(&self, name: Str, kind: ErrorKind) {
+        self.0.borrow_mut().register(name, kind);
+    }
+
+    pub fn get(&self, name: &str) -> Option<ErrorKind> {
+        self.0.borrow().get(name)
+    }
+
+    /// The Python exception class raised by the compiled code for `kind`.
+    pub fn py_exception(&self, kind: ErrorKind) -> &'static str {
+        kind.to_py_exception()
+    }
+
+    /// The registered classes and the kinds they are reported as,
+    /// e.g. `["foo.FooError", "ValueError"]` (flattened to be embedded in the compiled code).
+    pub fn registered(&self) -> Vec<Str> {
+        self.0
+            .borrow()
+            .registered()
+            .flat_map(|(name, kind)| [name.clone(), Str::from(kind.to_string())])
+            .collect()
+    }
+
+    /// Resolves an exception class raised at runtime (e.g. by a `pyimport`ed module).
+    /// `mro` is the qualified names of the class and its base classes,
+    /// e.g. `["foo.FooError", "ValueError", "Exception", "BaseException"]`.
+    /// The first registered or builtin class is used, so an undeclared subclass is treated as its nearest known base class.
+    /// Unknown classes are treated as `UserException`.
+    pub fn resolve(&self, mro: &[&str]) -> ErrorKind {
+        mro.iter()
+            .find_map(|name| self.get(name))
+            .unwrap_or(ErrorKind::UserException)
+    }
+
+    /// Registers the exception classes declared in `ctx` (a declaration module).
+    /// Classes are resolved repeatedly because a class may inherit from another class declared later.
+    pub fn register_decls(&self, ctx: &Context) {
+        loop {
+            let mut changed = false;
+            for (t, class_ctx) in ctx.mono_types.values() {
+                let name = t.qual_name();
+                if self.0.borrow().map.contains_key(&name) {
+                    continue;
+                }
+                // `.FooError <: ValueError` in a declaration file registers `ValueError` as a super trait
+                let mut sups = class_ctx
+                    .super_classes
+                    .iter()
+                    .chain(class_ctx.super_traits.iter());
+                let kind = sups.find_map(|sup| {
+                    self.get(&sup.qual_name())
+                        .or_else(|| ErrorKind::from_py_exception(&sup.local_name()))
+                });
+                if let Some(kind) = kind {
+                    self.register(name, kind);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    pub fn initialize(&self) {
+        self.0.borrow_mut().initialize();
+    }
+}
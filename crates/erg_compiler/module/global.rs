@@ -4,8 +4,11 @@ use erg_common::config::ErgConfig;
 
 use crate::context::Context;
 
+use super::ast_cache::SharedASTCache;
 use super::cache::SharedModuleCache;
+use super::dir_entries::SharedDirEntries;
 use super::errors::{SharedCompileErrors, SharedCompileWarnings};
+use super::exceptions::SharedPyExceptionMap;
 use super::graph::SharedModuleGraph;
 use super::impls::SharedTraitImpls;
 use super::index::SharedModuleIndex;
@@ -22,16 +25,45 @@ pub struct SharedCompilerResource {
     /// e.g. { "Named": [(Type, Named), (Func, Named), ...], "Add": [(Nat, Add(Nat)), (Int, Add(Int)), ...], ... }
     pub trait_impls: SharedTraitImpls,
     pub promises: SharedPromises,
+    /// K: name of a Python exception class, V: the corresponding Erg error kind
+    pub py_exceptions: SharedPyExceptionMap,
     pub errors: SharedCompileErrors,
     pub warns: SharedCompileWarnings,
+    /// the ASTs parsed in advance by `ModulePrefetcher` (`--parallel-parse`)
+    pub ast_cache: SharedASTCache,
+    /// the listings of the directories of imported modules
+    pub dir_entries: SharedDirEntries,
 }
 
 impl SharedCompilerResource {
     /// Initialize the shared compiler resource.
     /// This API is normally called only once throughout the compilation phase.
     pub fn new(cfg: ErgConfig) -> Self {
+        let self_ = Self::without_builtins(&cfg);
+        Context::init_builtins(cfg, self_.clone());
+        self_
+    }
+
+    /// Creates a new resource which shares the builtin module of `self` (via `Arc`) instead of initializing it again.
+    /// This saves the time and memory of `new` when many resources are created (e.g. one per package).
+    ///
+    /// NOTE: The trait implementations registered in `self` are copied,
+    /// so `self` should be a resource that has not checked any module yet.
+    pub fn with_builtins_of(&self, cfg: ErgConfig) -> Self {
+        let Some(builtins) = self.mod_cache.get_ctx(Path::new("<builtins>")) else {
+            return Self::new(cfg);
+        };
+        let self_ = Self::without_builtins(&cfg);
+        self_.mod_cache.register_shared("<builtins>", builtins);
+        for (name, impls) in self.trait_impls.ref_inner().iter() {
+            self_.trait_impls.register(name.clone(), impls.clone());
+        }
+        self_
+    }
+
+    fn without_builtins(cfg: &ErgConfig) -> Self {
         let graph = SharedModuleGraph::new();
-        let self_ = Self {
+        Self {
             mod_cache: SharedModuleCache::new(),
             py_mod_cache: SharedModuleCache::new(),
             index: SharedModuleIndex::new(),
@@ -43,11 +75,12 @@ impl SharedCompilerResource {
                     .path()
                     .map_or(PathBuf::default(), |p| p.canonicalize().unwrap_or_default()),
             ),
+            py_exceptions: SharedPyExceptionMap::new(),
             errors: SharedCompileErrors::new(),
             warns: SharedCompileWarnings::new(),
-        };
-        Context::init_builtins(cfg, self_.clone());
-        self_
+            ast_cache: SharedASTCache::new(),
+            dir_entries: SharedDirEntries::new(),
+        }
     }
 
     pub fn inherit(&self, path: PathBuf) -> Self {
@@ -62,8 +95,11 @@ impl SharedCompilerResource {
         self.index.initialize();
         self.graph.initialize();
         self.trait_impls.initialize();
+        self.py_exceptions.initialize();
         self.errors.clear();
         self.warns.clear();
+        self.ast_cache.initialize();
+        self.dir_entries.initialize();
     }
 
     pub fn clear(&self, path: &Path) {
@@ -71,12 +107,17 @@ impl SharedCompilerResource {
         self.py_mod_cache.remove(path);
         self.index.remove_path(path);
         self.graph.remove(path);
+        self.promises.remove_finished(path);
+        self.ast_cache.remove(path);
+        self.dir_entries.invalidate(path);
     }
 
     pub fn rename_path(&self, old: &Path, new: PathBuf) {
         self.mod_cache.rename_path(old, new.clone());
         self.py_mod_cache.rename_path(old, new.clone());
         self.index.rename_path(old, new.clone());
+        self.dir_entries.invalidate(old);
+        self.dir_entries.invalidate(&new);
         self.graph.rename_path(old, new);
     }
 }
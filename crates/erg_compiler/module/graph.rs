@@ -96,6 +96,42 @@ impl ModuleGraph {
         ancestors
     }
 
+    /// Returns the import chain from `path` to `target` (both ends included), if `path` depends on `target` transitively.
+    /// ```erg
+    /// # a.er
+    /// b = import "b"
+    /// # b.er
+    /// c = import "c"
+    /// ```
+    /// -> `import_chain(a, c) == Some([a, b, c])`
+    pub fn import_chain(&self, path: &Path, target: &Path) -> Option<Vec<NormalizedPathBuf>> {
+        let path = NormalizedPathBuf::new(path.to_path_buf());
+        let target = NormalizedPathBuf::new(target.to_path_buf());
+        let mut visited = set! {};
+        self.import_chain_inner(path, &target, &mut visited)
+    }
+
+    fn import_chain_inner(
+        &self,
+        path: NormalizedPathBuf,
+        target: &NormalizedPathBuf,
+        visited: &mut Set<NormalizedPathBuf>,
+    ) -> Option<Vec<NormalizedPathBuf>> {
+        if &path == target {
+            return Some(vec![path]);
+        }
+        if !visited.insert(path.clone()) {
+            return None;
+        }
+        for parent in self.parents(&path)?.iter() {
+            if let Some(mut chain) = self.import_chain_inner(parent.clone(), target, visited) {
+                chain.insert(0, path);
+                return Some(chain);
+            }
+        }
+        None
+    }
+
     pub fn add_node_if_none(&mut self, path: &Path) {
         let path = NormalizedPathBuf::new(path.to_path_buf());
         if self.0.iter().all(|n| n.id != path) {
@@ -209,6 +245,10 @@ impl SharedModuleGraph {
         self.0.borrow().ancestors(path)
     }
 
+    pub fn import_chain(&self, path: &Path, target: &Path) -> Option<Vec<NormalizedPathBuf>> {
+        self.0.borrow().import_chain(path, target)
+    }
+
     pub fn add_node_if_none(&self, path: &Path) {
         self.0.borrow_mut().add_node_if_none(path);
     }
@@ -8,7 +8,7 @@ use erg_common::set::Set;
 use erg_common::shared::{MappedRwLockReadGuard, RwLockReadGuard, Shared};
 use erg_common::Str;
 
-use crate::varinfo::{AbsLocation, VarInfo};
+use crate::varinfo::{AbsLocation, SymbolId, VarInfo};
 
 pub struct Members<'a>(MappedRwLockReadGuard<'a, Dict<AbsLocation, ModuleIndexValue>>);
 
@@ -55,6 +55,27 @@ impl ModuleIndexValue {
     pub fn push_ref(&mut self, referrer: AbsLocation) {
         self.referrers.insert(referrer);
     }
+
+    /// e.g. `<module>::C::f`, `<module>::g::<lambda>::x`
+    ///
+    /// The IDs of lambdas (`<lambda_{id}>`) are erased because they depend on the source location.
+    pub fn qual_name(&self) -> Str {
+        let namespace = self
+            .vi
+            .vis
+            .def_namespace
+            .split("::")
+            .map(|seg| {
+                if seg.starts_with("<lambda_") && seg.ends_with('>') {
+                    "<lambda>"
+                } else {
+                    seg
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("::");
+        Str::from(format!("{namespace}::{}", self.name))
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -95,6 +116,39 @@ impl ModuleIndex {
         self.members.get(referee)
     }
 
+    /// The definitions with the qualified name `qual_name` in `module`, in order of appearance
+    fn homonyms(&self, module: Option<&Path>, qual_name: &str) -> Vec<&AbsLocation> {
+        let mut locs = self
+            .members
+            .iter()
+            .filter(|(loc, value)| {
+                loc.module.as_deref() == module && &value.qual_name()[..] == qual_name
+            })
+            .map(|(loc, _)| loc)
+            .collect::<Vec<_>>();
+        locs.sort_by_key(|loc| loc.loc);
+        locs
+    }
+
+    pub fn symbol_id(&self, referee: &AbsLocation) -> Option<SymbolId> {
+        let qual_name = self.members.get(referee)?.qual_name();
+        let disambiguator = self
+            .homonyms(referee.module.as_deref(), &qual_name)
+            .into_iter()
+            .position(|loc| loc == referee)?;
+        Some(SymbolId::new(
+            referee.module.clone(),
+            qual_name,
+            disambiguator,
+        ))
+    }
+
+    pub fn resolve_symbol_id(&self, id: &SymbolId) -> Option<&AbsLocation> {
+        self.homonyms(id.module.as_deref(), &id.qual_name)
+            .get(id.disambiguator)
+            .copied()
+    }
+
     pub fn initialize(&mut self) {
         self.members.clear();
     }
@@ -177,6 +231,14 @@ impl SharedModuleIndex {
         }
     }
 
+    pub fn symbol_id(&self, referee: &AbsLocation) -> Option<SymbolId> {
+        self.0.borrow().symbol_id(referee)
+    }
+
+    pub fn resolve_symbol_id(&self, id: &SymbolId) -> Option<AbsLocation> {
+        self.0.borrow().resolve_symbol_id(id).cloned()
+    }
+
     pub fn members(&self) -> Members {
         Members(RwLockReadGuard::map(self.0.borrow(), |mi| &mi.members))
     }
@@ -1,13 +1,19 @@
+pub mod ast_cache;
 pub mod cache;
+pub mod dir_entries;
 pub mod errors;
+pub mod exceptions;
 pub mod global;
 pub mod graph;
 pub mod impls;
 pub mod index;
 pub mod promise;
 
+pub use ast_cache::*;
 pub use cache::*;
+pub use dir_entries::*;
 pub use errors::*;
+pub use exceptions::*;
 pub use global::*;
 pub use graph::*;
 pub use impls::*;
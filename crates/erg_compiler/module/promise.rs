@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use std::thread::{current, JoinHandle, ThreadId};
 
 use erg_common::dict::Dict;
-use erg_common::pathutil::NormalizedPathBuf;
+use erg_common::pathutil::{same_file, NormalizedPathBuf};
 use erg_common::shared::Shared;
 
 use super::SharedModuleGraph;
@@ -106,6 +106,18 @@ impl SharedPromises {
         self.promises.borrow().get(path).is_some()
     }
 
+    /// Returns the registered path which is the same file as `path` (e.g. a hard link).
+    pub fn find_same_file(&self, path: &Path) -> Option<NormalizedPathBuf> {
+        self.promises
+            .borrow()
+            .keys()
+            .find(|registered| {
+                let registered: &Path = registered;
+                registered != path && same_file(registered, path)
+            })
+            .cloned()
+    }
+
     pub fn is_finished(&self, path: &Path) -> bool {
         self.promises
             .borrow()
@@ -113,9 +125,28 @@ impl SharedPromises {
             .is_some_and(|promise| promise.is_finished())
     }
 
+    /// Removes the promise of the module so that the module will be checked again when imported.
+    /// The promise is not removed if the module is still being checked.
+    pub fn remove_finished(&self, path: &Path) {
+        let mut promises = self.promises.borrow_mut();
+        if promises
+            .get(path)
+            .is_some_and(|promise| promise.is_finished())
+        {
+            promises.remove(path);
+        }
+    }
+
     fn join_checked(&self, path: &Path, promise: Promise) -> std::thread::Result<()> {
-        let Promise::Running{ handle, parent } = promise else {
-            return Ok(());
+        let (handle, parent) = match promise {
+            Promise::Running { handle, parent } => (handle, parent),
+            // `take` replaced the finished promise with `Joining`, so put it back
+            // (otherwise the promise cannot be removed by `remove_finished`)
+            Promise::Finished => {
+                *self.promises.borrow_mut().get_mut(path).unwrap() = Promise::Finished;
+                return Ok(());
+            }
+            Promise::Joining => return Ok(()),
         };
         if self.graph.ancestors(path).contains(&self.path) || handle.thread().id() == current().id()
         {
@@ -0,0 +1,241 @@
+//! defines `ConstantFolder`.
+//!
+//! Folds constant expressions in the HIR before codegen.
+//!
+//! ```erg
+//! x = 1 + 2 * 3
+//! s = "foo" + "bar"
+//! b = False and f()
+//! ```
+//! ↓
+//! ```erg
+//! x = 7
+//! s = "foobar"
+//! b = False # if `f()` has no side effects
+//! ```
+use erg_common::traits::Locational;
+use erg_parser::token::{Token, TokenKind};
+
+use crate::context::Context;
+use crate::effectcheck::SideEffectChecker;
+use crate::hir::*;
+use crate::ty::typaram::OpKind;
+use crate::ty::value::ValueObj;
+use crate::ty::{HasType, Type};
+
+/// Folds constant arithmetic, string concatenation, and boolean operations using `Context::eval_bin`.
+///
+/// Only the operations whose results are guaranteed to be the same as those of Python are folded.
+/// For example, integer operations that overflow `ValueObj` (without the `bigint` feature), division by zero (raises `ZeroDivisionError`),
+/// and `Str * Nat` (may bloat the constant table) are not folded.
+#[derive(Debug)]
+pub struct ConstantFolder<'c> {
+    ctx: &'c Context,
+}
+
+impl<'c> ConstantFolder<'c> {
+    pub const fn new(ctx: &'c Context) -> Self {
+        Self { ctx }
+    }
+
+    pub fn fold(&self, mut hir: HIR) -> HIR {
+        for chunk in hir.module.iter_mut() {
+            self.fold_expr(chunk);
+        }
+        hir
+    }
+
+    fn fold_args(&self, args: &mut Args) {
+        for arg in args.pos_args.iter_mut() {
+            self.fold_expr(&mut arg.expr);
+        }
+        if let Some(var_args) = args.var_args.as_mut() {
+            self.fold_expr(&mut var_args.expr);
+        }
+        for arg in args.kw_args.iter_mut() {
+            self.fold_expr(&mut arg.expr);
+        }
+    }
+
+    fn fold_block(&self, block: &mut Block) {
+        for chunk in block.iter_mut() {
+            self.fold_expr(chunk);
+        }
+    }
+
+    fn fold_expr(&self, expr: &mut Expr) {
+        match expr {
+            Expr::BinOp(bin) => {
+                self.fold_expr(&mut bin.lhs);
+                self.fold_expr(&mut bin.rhs);
+                if let Some(folded) = self.fold_bin(bin) {
+                    *expr = folded;
+                }
+            }
+            Expr::UnaryOp(unary) => {
+                self.fold_expr(&mut unary.expr);
+                if let Some(folded) = self.fold_unary(unary) {
+                    *expr = folded;
+                }
+            }
+            Expr::Call(call) => {
+                self.fold_expr(&mut call.obj);
+                self.fold_args(&mut call.args);
+            }
+            Expr::Accessor(Accessor::Attr(attr)) => self.fold_expr(&mut attr.obj),
+            Expr::Array(Array::Normal(arr)) => self.fold_args(&mut arr.elems),
+            Expr::Tuple(Tuple::Normal(tup)) => self.fold_args(&mut tup.elems),
+            Expr::Set(Set::Normal(set)) => self.fold_args(&mut set.elems),
+            Expr::Dict(Dict::Normal(dict)) => {
+                for kv in dict.kvs.iter_mut() {
+                    self.fold_expr(&mut kv.key);
+                    self.fold_expr(&mut kv.value);
+                }
+            }
+            Expr::Record(rec) => {
+                for attr in rec.attrs.iter_mut() {
+                    self.fold_block(&mut attr.body.block);
+                }
+            }
+            Expr::Lambda(lambda) => {
+                if let Some(guard) = lambda.guard.as_deref_mut() {
+                    self.fold_expr(guard);
+                }
+                self.fold_block(&mut lambda.body)
+            }
+            Expr::Def(def) => self.fold_block(&mut def.body.block),
+            Expr::ClassDef(class_def) => self.fold_block(&mut class_def.methods),
+            Expr::PatchDef(patch_def) => self.fold_block(&mut patch_def.methods),
+            Expr::ReDef(redef) => self.fold_block(&mut redef.block),
+            Expr::TypeAsc(tasc) => self.fold_expr(&mut tasc.expr),
+            Expr::Coerce(coerce) => self.fold_expr(&mut coerce.expr),
+            Expr::Code(block) | Expr::Compound(block) => self.fold_block(block),
+            _ => {}
+        }
+    }
+
+    fn fold_bin(&self, bin: &mut BinOp) -> Option<Expr> {
+        let op = self.ctx.try_get_op_kind_from_token(&bin.op).ok()?;
+        let (lit, other, lit_is_lhs) = match (bin.lhs.as_ref(), bin.rhs.as_ref()) {
+            (Expr::Lit(lhs), Expr::Lit(rhs)) => {
+                let (lhs, rhs) = (
+                    Self::runtime_value(&lhs.value),
+                    Self::runtime_value(&rhs.value),
+                );
+                if !Self::is_foldable_bin(op, &lhs, &rhs) {
+                    return None;
+                }
+                let value = self.ctx.eval_bin(op, lhs, rhs).ok()?;
+                return Self::literal(value, bin).map(Expr::Lit);
+            }
+            (Expr::Lit(lit), other) => (lit, other, true),
+            (other, Expr::Lit(lit)) => (lit, other, false),
+            _ => return None,
+        };
+        let b = match lit.value {
+            ValueObj::Bool(b) => b,
+            _ => return None,
+        };
+        if !matches!(op, OpKind::And | OpKind::Or)
+            || !self.ctx.subtype_of(other.ref_t(), &Type::Bool)
+        {
+            return None;
+        }
+        // `and`/`or` are compiled to `&`/`|`, so the other operand is evaluated regardless of `lit`
+        if b == matches!(op, OpKind::And) {
+            // `True and x` == `x`, `False or x` == `x`
+            let other = if lit_is_lhs {
+                bin.rhs.as_mut()
+            } else {
+                bin.lhs.as_mut()
+            };
+            Some(std::mem::take(other))
+        } else if SideEffectChecker::is_pure(other) {
+            // `False and x` == `False`, `True or x` == `True`
+            Some(Expr::Lit(lit.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn fold_unary(&self, unary: &UnaryOp) -> Option<Expr> {
+        let lit = match unary.expr.as_ref() {
+            Expr::Lit(lit) => lit,
+            _ => return None,
+        };
+        let op = self.ctx.try_get_op_kind_from_token(&unary.op).ok()?;
+        if !matches!(op, OpKind::Pos | OpKind::Neg) {
+            return None;
+        }
+        let value = self.ctx.eval_unary_val(op, lit.value.clone()).ok()?;
+        Self::literal(value, unary).map(Expr::Lit)
+    }
+
+    /// `Ratio`s are exact in the compiler, but they are `float`s at runtime (e.g. `0.1 + 0.2 != 0.3`).
+    fn runtime_value(value: &ValueObj) -> ValueObj {
+        match value {
+            ValueObj::Ratio(r) => ValueObj::Float(r.to_f64()),
+            other => other.clone(),
+        }
+    }
+
+    fn as_i32(value: &ValueObj) -> Option<i32> {
+        match value {
+            ValueObj::Nat(n) => i32::try_from(*n).ok(),
+            ValueObj::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    fn is_integer(value: &ValueObj) -> bool {
+        matches!(value, ValueObj::Nat(_) | ValueObj::Int(_)) || value.is_bigint()
+    }
+
+    /// The arithmetic of `eval_bin` follows Python, and fails if the result overflows `ValueObj`.
+    /// Integers are converted to floats exactly only if they are small enough.
+    fn is_foldable_bin(op: OpKind, lhs: &ValueObj, rhs: &ValueObj) -> bool {
+        use OpKind::*;
+        let is_arith_or_cmp = matches!(
+            op,
+            Add | Sub | Mul | Div | FloorDiv | Mod | Eq | Ne | Lt | Le | Gt | Ge
+        );
+        match (lhs, rhs) {
+            (ValueObj::Str(_), ValueObj::Str(_)) => matches!(op, Add | Eq | Ne),
+            (ValueObj::Bool(_), ValueObj::Bool(_)) => matches!(op, And | Or | Eq | Ne),
+            (ValueObj::Float(_), ValueObj::Float(_)) => is_arith_or_cmp,
+            (ValueObj::Float(_), int) | (int, ValueObj::Float(_)) => {
+                Self::as_i32(int).is_some() && is_arith_or_cmp
+            }
+            (l, r) if Self::is_integer(l) && Self::is_integer(r) => {
+                // `Int / Int` is a float
+                is_arith_or_cmp
+                    && (op != Div || Self::as_i32(l).is_some() && Self::as_i32(r).is_some())
+            }
+            _ => false,
+        }
+    }
+
+    fn literal(value: ValueObj, loc: &impl Locational) -> Option<Literal> {
+        let kind = match value {
+            ValueObj::Nat(_) => TokenKind::NatLit,
+            ValueObj::Int(_) => TokenKind::IntLit,
+            #[cfg(feature = "bigint")]
+            ValueObj::BigInt(_) if value.class() == Type::Nat => TokenKind::NatLit,
+            #[cfg(feature = "bigint")]
+            ValueObj::BigInt(_) => TokenKind::IntLit,
+            ValueObj::Float(_) => TokenKind::RatioLit,
+            // e.g. `-0.1`, whose notation is exact
+            ValueObj::Ratio(r) if r.to_decimal().is_some() => TokenKind::RatioLit,
+            ValueObj::Str(_) => TokenKind::StrLit,
+            ValueObj::Bool(_) => TokenKind::BoolLit,
+            _ => return None,
+        };
+        let token = Token::new(
+            kind,
+            value.to_string(),
+            loc.ln_begin().unwrap_or(0),
+            loc.col_begin().unwrap_or(0),
+        );
+        Some(Literal::new(value, token))
+    }
+}
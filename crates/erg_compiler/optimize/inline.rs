@@ -0,0 +1,271 @@
+//! defines `Inliner`.
+//!
+//! Inlines calls of small pure functions to avoid the overhead of Python function calls.
+//!
+//! ```erg
+//! get_x p = p.x
+//! double x = x * 2
+//! print! double get_x(p)
+//! ```
+//! ↓
+//! ```erg
+//! get_x p = p.x
+//! double x = x * 2
+//! print! p.x * 2
+//! ```
+use erg_common::dict::Dict as HashMap;
+use erg_common::traits::Stream;
+
+use crate::effectcheck::SideEffectChecker;
+use crate::hir::*;
+use crate::ty::HasType;
+use crate::varinfo::AbsLocation;
+
+use erg_parser::ast::ParamPattern;
+
+/// The maximum cost of the functions inlined without `@Inline`
+const AUTO_INLINE_COST: usize = 8;
+/// The maximum cost of the functions marked with `@Inline`
+const MAX_INLINE_COST: usize = 32;
+
+#[derive(Debug)]
+struct Inlinable {
+    /// the definition locations of the parameters
+    params: Vec<AbsLocation>,
+    body: Expr,
+}
+
+/// Inlines calls of small pure functions at their call sites.
+///
+/// A module-level function is inlined if:
+/// * it is not a procedure, and its body is a single pure expression
+/// * it has only non-default parameters without patterns
+/// * its body consists of literals, parameters, attribute accesses, operators, tuples and arrays
+///   (so the body refers to no variables other than the parameters)
+/// * the cost of the body (the number of the nodes) is at most `AUTO_INLINE_COST` (`MAX_INLINE_COST` if marked with `@Inline`)
+///
+/// and the call site passes all arguments positionally.
+/// To preserve the evaluation order, a non-trivial argument (other than literals and variables)
+/// is only allowed for a function with a single parameter used exactly once.
+#[derive(Debug, Default)]
+pub struct Inliner {
+    inlinables: HashMap<AbsLocation, Inlinable>,
+}
+
+impl Inliner {
+    pub fn inline(mut hir: HIR) -> HIR {
+        let mut inliner = Self::default();
+        for chunk in hir.module.iter() {
+            if let Expr::Def(def) = chunk {
+                inliner.register(def);
+            }
+        }
+        if inliner.inlinables.is_empty() {
+            return hir;
+        }
+        for chunk in hir.module.iter_mut() {
+            inliner.inline_expr(chunk);
+        }
+        hir
+    }
+
+    fn register(&mut self, def: &Def) {
+        let subr = match &def.sig {
+            Signature::Subr(subr) => subr,
+            Signature::Var(_) => return,
+        };
+        if subr.ident.ref_t().is_procedure()
+            || subr.params.var_params.is_some()
+            || !subr.params.defaults.is_empty()
+            || def.body.block.len() != 1
+        {
+            return;
+        }
+        let mut params = vec![];
+        for param in subr.params.non_defaults.iter() {
+            if !matches!(param.raw.pat, ParamPattern::VarName(_)) {
+                return;
+            }
+            params.push(param.vi.def_loc.clone());
+        }
+        let body = def.body.block.first().unwrap();
+        let max_cost = if subr
+            .ident
+            .vi
+            .comptime_decos
+            .as_ref()
+            .is_some_and(|decos| decos.contains("Inline"))
+        {
+            MAX_INLINE_COST
+        } else {
+            AUTO_INLINE_COST
+        };
+        if Self::cost(body, &params).is_some_and(|cost| cost <= max_cost)
+            && SideEffectChecker::is_pure(body)
+        {
+            let inlinable = Inlinable {
+                params,
+                body: body.clone(),
+            };
+            self.inlinables
+                .insert(subr.ident.vi.def_loc.clone(), inlinable);
+        }
+    }
+
+    /// Returns `None` if `expr` cannot be inlined.
+    fn cost(expr: &Expr, params: &[AbsLocation]) -> Option<usize> {
+        match expr {
+            Expr::Lit(_) => Some(1),
+            Expr::Accessor(Accessor::Ident(ident)) => {
+                params.contains(&ident.vi.def_loc).then_some(1)
+            }
+            Expr::Accessor(Accessor::Attr(attr)) => Some(1 + Self::cost(&attr.obj, params)?),
+            Expr::BinOp(bin) => {
+                Some(1 + Self::cost(&bin.lhs, params)? + Self::cost(&bin.rhs, params)?)
+            }
+            Expr::UnaryOp(unary) => Some(1 + Self::cost(&unary.expr, params)?),
+            Expr::Tuple(Tuple::Normal(NormalTuple { elems, .. }))
+            | Expr::Array(Array::Normal(NormalArray { elems, .. })) => {
+                if elems.var_args.is_some() || !elems.kw_args.is_empty() {
+                    return None;
+                }
+                elems
+                    .pos_args
+                    .iter()
+                    .try_fold(1, |acc, arg| Some(acc + Self::cost(&arg.expr, params)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn count_uses(expr: &Expr, param: &AbsLocation) -> usize {
+        match expr {
+            Expr::Accessor(Accessor::Ident(ident)) => usize::from(&ident.vi.def_loc == param),
+            Expr::Accessor(Accessor::Attr(attr)) => Self::count_uses(&attr.obj, param),
+            Expr::BinOp(bin) => {
+                Self::count_uses(&bin.lhs, param) + Self::count_uses(&bin.rhs, param)
+            }
+            Expr::UnaryOp(unary) => Self::count_uses(&unary.expr, param),
+            Expr::Tuple(Tuple::Normal(NormalTuple { elems, .. }))
+            | Expr::Array(Array::Normal(NormalArray { elems, .. })) => elems
+                .pos_args
+                .iter()
+                .map(|arg| Self::count_uses(&arg.expr, param))
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    fn substitute(expr: &mut Expr, params: &[AbsLocation], args: &[Expr]) {
+        match expr {
+            Expr::Accessor(Accessor::Ident(ident)) => {
+                if let Some(i) = params.iter().position(|param| param == &ident.vi.def_loc) {
+                    *expr = args[i].clone();
+                }
+            }
+            Expr::Accessor(Accessor::Attr(attr)) => Self::substitute(&mut attr.obj, params, args),
+            Expr::BinOp(bin) => {
+                Self::substitute(&mut bin.lhs, params, args);
+                Self::substitute(&mut bin.rhs, params, args);
+            }
+            Expr::UnaryOp(unary) => Self::substitute(&mut unary.expr, params, args),
+            Expr::Tuple(Tuple::Normal(NormalTuple { elems, .. }))
+            | Expr::Array(Array::Normal(NormalArray { elems, .. })) => {
+                for arg in elems.pos_args.iter_mut() {
+                    Self::substitute(&mut arg.expr, params, args);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn inline_call(&self, call: &Call) -> Option<Expr> {
+        if call.attr_name.is_some() || call.args.var_args.is_some() || !call.args.kw_args.is_empty()
+        {
+            return None;
+        }
+        let inlinable = match call.obj.as_ref() {
+            Expr::Accessor(Accessor::Ident(ident)) => self.inlinables.get(&ident.vi.def_loc)?,
+            _ => return None,
+        };
+        if call.args.pos_args.len() != inlinable.params.len() {
+            return None;
+        }
+        let args = call
+            .args
+            .pos_args
+            .iter()
+            .map(|arg| arg.expr.clone())
+            .collect::<Vec<_>>();
+        for (param, arg) in inlinable.params.iter().zip(args.iter()) {
+            let is_trivial = matches!(arg, Expr::Lit(_) | Expr::Accessor(Accessor::Ident(_)));
+            if !is_trivial
+                && (inlinable.params.len() != 1 || Self::count_uses(&inlinable.body, param) != 1)
+            {
+                return None;
+            }
+        }
+        let mut body = inlinable.body.clone();
+        Self::substitute(&mut body, &inlinable.params, &args);
+        Some(body)
+    }
+
+    fn inline_args(&self, args: &mut Args) {
+        for arg in args.pos_args.iter_mut() {
+            self.inline_expr(&mut arg.expr);
+        }
+        if let Some(var_args) = args.var_args.as_mut() {
+            self.inline_expr(&mut var_args.expr);
+        }
+        for arg in args.kw_args.iter_mut() {
+            self.inline_expr(&mut arg.expr);
+        }
+    }
+
+    fn inline_block(&self, block: &mut Block) {
+        for chunk in block.iter_mut() {
+            self.inline_expr(chunk);
+        }
+    }
+
+    fn inline_expr(&self, expr: &mut Expr) {
+        match expr {
+            Expr::Call(call) => {
+                self.inline_expr(&mut call.obj);
+                self.inline_args(&mut call.args);
+                if let Some(inlined) = self.inline_call(call) {
+                    *expr = inlined;
+                }
+            }
+            Expr::BinOp(bin) => {
+                self.inline_expr(&mut bin.lhs);
+                self.inline_expr(&mut bin.rhs);
+            }
+            Expr::UnaryOp(unary) => self.inline_expr(&mut unary.expr),
+            Expr::Accessor(Accessor::Attr(attr)) => self.inline_expr(&mut attr.obj),
+            Expr::Array(Array::Normal(arr)) => self.inline_args(&mut arr.elems),
+            Expr::Tuple(Tuple::Normal(tup)) => self.inline_args(&mut tup.elems),
+            Expr::Set(Set::Normal(set)) => self.inline_args(&mut set.elems),
+            Expr::Dict(Dict::Normal(dict)) => {
+                for kv in dict.kvs.iter_mut() {
+                    self.inline_expr(&mut kv.key);
+                    self.inline_expr(&mut kv.value);
+                }
+            }
+            Expr::Record(rec) => {
+                for attr in rec.attrs.iter_mut() {
+                    self.inline_block(&mut attr.body.block);
+                }
+            }
+            Expr::Lambda(lambda) => self.inline_block(&mut lambda.body),
+            Expr::Def(def) => self.inline_block(&mut def.body.block),
+            Expr::ClassDef(class_def) => self.inline_block(&mut class_def.methods),
+            Expr::PatchDef(patch_def) => self.inline_block(&mut patch_def.methods),
+            Expr::ReDef(redef) => self.inline_block(&mut redef.block),
+            Expr::TypeAsc(tasc) => self.inline_expr(&mut tasc.expr),
+            Expr::Coerce(coerce) => self.inline_expr(&mut coerce.expr),
+            Expr::Code(block) | Expr::Compound(block) => self.inline_block(block),
+            _ => {}
+        }
+    }
+}
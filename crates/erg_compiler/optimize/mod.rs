@@ -0,0 +1,176 @@
+pub mod const_fold;
+pub mod inline;
+pub mod peephole;
+
+use erg_common::config::ErgConfig;
+use erg_common::traits::Stream;
+
+use crate::context::Context;
+use crate::effectcheck::SideEffectChecker;
+use crate::hir::*;
+use crate::module::SharedCompilerResource;
+use crate::ty::{HasType, Type};
+
+use const_fold::ConstantFolder;
+use inline::Inliner;
+
+/// Optimizes a `HIR`.
+/// This should not be used in the context of sequential execution (e.g. REPL), since it assumes that the given code is all there is.
+/// The optimizer determines the optimization level using `opt_level` in `cfg: ErgConfig`.
+///
+/// The passes are applied in the following order:
+/// 1. inlining of small pure functions (`Inliner`)
+/// 2. constant folding (`ConstantFolder`)
+/// 3. dead code elimination
+#[derive(Debug)]
+pub struct HIROptimizer {
+    cfg: ErgConfig,
+    shared: SharedCompilerResource,
+}
+
+impl HIROptimizer {
+    /// `ctx` is the context of the module, used to evaluate constant expressions.
+    pub fn optimize(
+        cfg: ErgConfig,
+        shared: SharedCompilerResource,
+        ctx: &Context,
+        hir: HIR,
+    ) -> HIR {
+        let mut optimizer = HIROptimizer { cfg, shared };
+        if optimizer.cfg.opt_level == 0 || optimizer.cfg.input.is_repl() {
+            return hir;
+        }
+        let hir = optimizer.inline_functions(hir);
+        let hir = optimizer.fold_constants(ctx, hir);
+        optimizer.eliminate_dead_code(hir)
+    }
+
+    fn inline_functions(&mut self, hir: HIR) -> HIR {
+        Inliner::inline(hir)
+    }
+
+    fn fold_constants(&mut self, ctx: &Context, hir: HIR) -> HIR {
+        ConstantFolder::new(ctx).fold(hir)
+    }
+
+    fn eliminate_unused_variables(&mut self, mut hir: HIR) -> HIR {
+        for chunk in hir.module.iter_mut() {
+            self.eliminate_unused_def(chunk);
+        }
+        hir
+    }
+
+    fn eliminate_unused_def(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Def(def) => {
+                if self
+                    .shared
+                    .index
+                    .get_refs(&def.sig.ident().vi.def_loc)
+                    .unwrap()
+                    .referrers
+                    .is_empty()
+                    && SideEffectChecker::is_pure(expr)
+                {
+                    *expr = Expr::Dummy(Dummy::empty());
+                }
+            }
+            Expr::Call(call) => {
+                for arg in call.args.pos_args.iter_mut() {
+                    self.eliminate_unused_def(&mut arg.expr);
+                }
+            }
+            Expr::Code(block) | Expr::Compound(block) => {
+                for chunk in block.iter_mut() {
+                    self.eliminate_unused_def(chunk);
+                }
+            }
+            Expr::Lambda(lambda) => {
+                for chunk in lambda.body.iter_mut() {
+                    self.eliminate_unused_def(chunk);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn eliminate_dead_code(&mut self, hir: HIR) -> HIR {
+        let hir = self.eliminate_unreachable_code(hir);
+        let hir = self.eliminate_discarded_variables(hir);
+        self.eliminate_unused_variables(hir)
+    }
+
+    /// The chunks following a `Never` type expression are removed (warned by `ASTLowerer`).
+    /// ```erg
+    /// f!() =
+    ///     panic "..."
+    ///     print! "unreachable"
+    /// ```
+    /// ↓
+    /// ```erg
+    /// f!() =
+    ///     panic "..."
+    /// ```
+    fn eliminate_unreachable_code(&mut self, mut hir: HIR) -> HIR {
+        self.eliminate_unreachable_chunks(hir.module.ref_mut_payload());
+        hir
+    }
+
+    fn eliminate_unreachable_chunks(&mut self, chunks: &mut Vec<Expr>) {
+        if let Some(i) = chunks
+            .iter()
+            .position(|chunk| chunk.ref_t() == &Type::Never)
+        {
+            chunks.truncate(i + 1);
+        }
+        for chunk in chunks.iter_mut() {
+            self.eliminate_unreachable_expr(chunk);
+        }
+    }
+
+    fn eliminate_unreachable_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Def(def) => self.eliminate_unreachable_chunks(def.body.block.ref_mut_payload()),
+            Expr::Lambda(lambda) => {
+                self.eliminate_unreachable_chunks(lambda.body.ref_mut_payload())
+            }
+            Expr::ClassDef(class_def) => {
+                for method in class_def.methods.iter_mut() {
+                    self.eliminate_unreachable_expr(method);
+                }
+            }
+            Expr::PatchDef(patch_def) => {
+                for method in patch_def.methods.iter_mut() {
+                    self.eliminate_unreachable_expr(method);
+                }
+            }
+            Expr::Call(call) => {
+                for arg in call.args.pos_args.iter_mut() {
+                    self.eliminate_unreachable_expr(&mut arg.expr);
+                }
+                if let Some(var_args) = call.args.var_args.as_mut() {
+                    self.eliminate_unreachable_expr(&mut var_args.expr);
+                }
+                for arg in call.args.kw_args.iter_mut() {
+                    self.eliminate_unreachable_expr(&mut arg.expr);
+                }
+            }
+            Expr::Code(block) | Expr::Compound(block) => {
+                self.eliminate_unreachable_chunks(block.ref_mut_payload());
+            }
+            _ => {}
+        }
+    }
+
+    /// ```erg
+    /// _ = 1
+    /// (a, _) = (1, True)
+    /// ```
+    /// ↓
+    /// ```erg
+    /// a = 1
+    /// ```
+    fn eliminate_discarded_variables(&mut self, hir: HIR) -> HIR {
+        hir
+    }
+}
@@ -0,0 +1,635 @@
+//! defines `PeepholeOptimizer`.
+//!
+//! Optimizes the instructions of a code object emitted by the code generator.
+//!
+//! ```python
+//!  0 LOAD_NAME                 0 (x)
+//!  2 POP_JUMP_FORWARD_IF_FALSE 4 (to 12)
+//!  4 LOAD_CONST                0 (None)
+//!  6 POP_TOP
+//!  8 LOAD_CONST                1 (1)
+//! 10 RETURN_VALUE
+//! 12 JUMP_FORWARD              1 (to 16)
+//! 14 NOP
+//! 16 LOAD_CONST                2 (2)
+//! 18 RETURN_VALUE
+//! ```
+//! ↓
+//! ```python
+//!  0 LOAD_NAME                 0 (x)
+//!  2 POP_JUMP_FORWARD_IF_FALSE 2 (to 8)
+//!  4 LOAD_CONST                1 (1)
+//!  6 RETURN_VALUE
+//!  8 LOAD_CONST                2 (2)
+//! 10 RETURN_VALUE
+//! ```
+use erg_common::opcode::CommonOpcode;
+use erg_common::opcode308::Opcode308;
+use erg_common::opcode310::Opcode310;
+use erg_common::opcode311::Opcode311;
+use erg_common::python_util::PythonVersion;
+
+use crate::sourcemap::SourceMapEntry;
+use crate::ty::codeobj::CodeObj;
+
+/// How the argument of a jump instruction represents the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JumpKind {
+    /// relative to the next instruction
+    Forward,
+    /// relative to the next instruction (3.11~)
+    Backward,
+    Absolute,
+}
+
+#[derive(Debug, Clone)]
+struct Instr {
+    op: u8,
+    /// for jump instructions, the argument is recalculated from `target`
+    arg: usize,
+    /// the index of the target instruction
+    target: Option<usize>,
+    /// the offset of the instruction in the original code (including `EXTENDED_ARG`s)
+    offset: usize,
+    alive: bool,
+}
+
+/// Peephole optimizer over the instructions of a `CodeObj`.
+///
+/// The instructions are decoded (resolving `EXTENDED_ARG`s and jump targets), rewritten, and assembled again.
+/// The following patterns are optimized:
+/// * `NOP` is removed
+/// * `LOAD_CONST` + `POP_TOP` is removed
+/// * `COPY 1` (`DUP_TOP`) + `POP_TOP` is removed
+/// * `SWAP 2` (`ROT_TWO`) + `SWAP 2` (`ROT_TWO`) is removed
+/// * an unconditional jump to the next instruction is removed
+/// * a jump to an unconditional jump is redirected to the final target (jump threading)
+///
+/// A pattern is not removed if a jump targets the middle of it.
+/// The line number table (`lnotab`) and the source map entries are updated according to the new offsets.
+/// If the code contains something the optimizer does not understand (e.g. an exception table), it is left as it is.
+#[derive(Debug)]
+pub struct PeepholeOptimizer {
+    py_version: PythonVersion,
+    instrs: Vec<Instr>,
+}
+
+impl PeepholeOptimizer {
+    /// Returns `true` if `code` has been changed.
+    pub fn optimize(
+        code: &mut CodeObj,
+        spans: &mut [SourceMapEntry],
+        py_version: PythonVersion,
+    ) -> bool {
+        if !code.exceptiontable.is_empty() {
+            return false;
+        }
+        let mut optimizer = Self {
+            py_version,
+            instrs: vec![],
+        };
+        if !optimizer.decode(&code.code) {
+            return false;
+        }
+        if !optimizer.rewrite() {
+            return false;
+        }
+        let (bytes, offsets) = optimizer.assemble();
+        let map_offset = |old: usize| {
+            let idx = optimizer.instrs.partition_point(|instr| instr.offset < old);
+            offsets.get(idx).copied().unwrap_or(bytes.len())
+        };
+        if py_version.minor < Some(11) {
+            code.lnotab = Self::remap_lnotab(&code.lnotab, map_offset);
+        }
+        for span in spans.iter_mut() {
+            span.offset = map_offset(span.offset);
+            span.end = map_offset(span.end);
+        }
+        code.code = bytes;
+        true
+    }
+
+    fn minor(&self) -> u8 {
+        self.py_version.minor.unwrap_or(11)
+    }
+
+    /// jump arguments are instruction units (2 bytes) since 3.10
+    fn jump_unit(&self) -> usize {
+        if self.minor() >= 10 {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn jump_kind(&self, op: u8) -> Option<JumpKind> {
+        if self.minor() >= 11 {
+            let forward = [
+                Opcode311::FOR_ITER,
+                Opcode311::SEND,
+                Opcode311::JUMP_FORWARD,
+                Opcode311::JUMP_IF_FALSE_OR_POP,
+                Opcode311::JUMP_IF_TRUE_OR_POP,
+                Opcode311::POP_JUMP_FORWARD_IF_FALSE,
+                Opcode311::POP_JUMP_FORWARD_IF_TRUE,
+            ];
+            let backward = [
+                Opcode311::JUMP_BACKWARD,
+                Opcode311::POP_JUMP_BACKWARD_IF_FALSE,
+                Opcode311::POP_JUMP_BACKWARD_IF_TRUE,
+            ];
+            if forward.iter().any(|&fwd| fwd as u8 == op) {
+                Some(JumpKind::Forward)
+            } else if backward.iter().any(|&bwd| bwd as u8 == op) {
+                Some(JumpKind::Backward)
+            } else {
+                None
+            }
+        } else {
+            let mut relative = vec![
+                Opcode310::FOR_ITER as u8,
+                Opcode310::JUMP_FORWARD as u8,
+                Opcode310::SETUP_WITH as u8,
+            ];
+            if self.minor() <= 8 {
+                relative.push(Opcode308::CALL_FINALLY as u8);
+            }
+            let absolute = [
+                Opcode310::JUMP_IF_FALSE_OR_POP,
+                Opcode310::JUMP_IF_TRUE_OR_POP,
+                Opcode310::JUMP_ABSOLUTE,
+                Opcode310::POP_JUMP_IF_FALSE,
+                Opcode310::POP_JUMP_IF_TRUE,
+            ];
+            if relative.contains(&op) {
+                Some(JumpKind::Forward)
+            } else if absolute.iter().any(|&abs| abs as u8 == op) {
+                Some(JumpKind::Absolute)
+            } else {
+                None
+            }
+        }
+    }
+
+    fn is_unconditional_jump(&self, op: u8) -> bool {
+        if self.minor() >= 11 {
+            op == Opcode311::JUMP_FORWARD as u8 || op == Opcode311::JUMP_BACKWARD as u8
+        } else {
+            op == Opcode310::JUMP_FORWARD as u8 || op == Opcode310::JUMP_ABSOLUTE as u8
+        }
+    }
+
+    /// Returns the opcode that has the same semantics as the jump instruction `op` and can jump backward (or forward).
+    /// `FOR_ITER`, `SETUP_WITH`, etc. are not redirected, so `None` is returned.
+    fn redirected_op(&self, op: u8, backward: bool) -> Option<u8> {
+        if self.minor() >= 11 {
+            let pairs = [
+                (Opcode311::JUMP_FORWARD, Opcode311::JUMP_BACKWARD),
+                (
+                    Opcode311::POP_JUMP_FORWARD_IF_FALSE,
+                    Opcode311::POP_JUMP_BACKWARD_IF_FALSE,
+                ),
+                (
+                    Opcode311::POP_JUMP_FORWARD_IF_TRUE,
+                    Opcode311::POP_JUMP_BACKWARD_IF_TRUE,
+                ),
+            ];
+            if let Some((fwd, bwd)) = pairs
+                .iter()
+                .find(|(fwd, bwd)| *fwd as u8 == op || *bwd as u8 == op)
+            {
+                Some(if backward { *bwd } else { *fwd } as u8)
+            } else if !backward
+                && (op == Opcode311::JUMP_IF_FALSE_OR_POP as u8
+                    || op == Opcode311::JUMP_IF_TRUE_OR_POP as u8)
+            {
+                Some(op)
+            } else {
+                None
+            }
+        } else if backward && op == Opcode310::JUMP_FORWARD as u8 {
+            Some(Opcode310::JUMP_ABSOLUTE as u8)
+        } else if self.jump_kind(op) == Some(JumpKind::Absolute)
+            || op == Opcode310::JUMP_FORWARD as u8
+        {
+            Some(op)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `false` if `code` cannot be decoded.
+    fn decode(&mut self, code: &[u8]) -> bool {
+        if !code.len().is_multiple_of(2) {
+            return false;
+        }
+        let mut targets = vec![];
+        let mut offset = 0;
+        let mut arg = 0;
+        for (i, unit) in code.chunks(2).enumerate() {
+            let (op, byte) = (unit[0], unit[1]);
+            arg = (arg << 8) | byte as usize;
+            if op == CommonOpcode::EXTENDED_ARG as u8 {
+                continue;
+            }
+            let next = (i + 1) * 2;
+            let target = match self.jump_kind(op) {
+                Some(JumpKind::Forward) => Some(next + arg * self.jump_unit()),
+                Some(JumpKind::Backward) => next.checked_sub(arg * self.jump_unit()),
+                Some(JumpKind::Absolute) => Some(arg * self.jump_unit()),
+                None => None,
+            };
+            if self.jump_kind(op).is_some() && target.is_none() {
+                return false;
+            }
+            targets.push(target);
+            self.instrs.push(Instr {
+                op,
+                arg,
+                target: None,
+                offset,
+                alive: true,
+            });
+            offset = next;
+            arg = 0;
+        }
+        // trailing `EXTENDED_ARG`
+        if offset != code.len() {
+            return false;
+        }
+        for (i, target) in targets.into_iter().enumerate() {
+            if let Some(target) = target {
+                match self
+                    .instrs
+                    .binary_search_by_key(&target, |instr| instr.offset)
+                {
+                    Ok(idx) => self.instrs[i].target = Some(idx),
+                    // jump into the middle of an instruction
+                    Err(_) => return false,
+                }
+            }
+        }
+        true
+    }
+
+    fn next_alive(&self, idx: usize) -> Option<usize> {
+        (idx..self.instrs.len()).find(|&i| self.instrs[i].alive)
+    }
+
+    /// the target of the jump instruction, skipping the removed instructions
+    fn effective_target(&self, idx: usize) -> Option<usize> {
+        self.instrs[idx]
+            .target
+            .and_then(|target| self.next_alive(target))
+    }
+
+    fn is_jump_target(&self, idx: usize) -> bool {
+        (0..self.instrs.len())
+            .any(|i| self.instrs[i].alive && self.effective_target(i) == Some(idx))
+    }
+
+    fn is_op(&self, op: u8, op311: Opcode311, op310: Opcode310) -> bool {
+        if self.minor() >= 11 {
+            op == op311 as u8
+        } else {
+            op == op310 as u8
+        }
+    }
+
+    /// Returns `true` if the pair of instructions (`fst`, `snd`) does nothing.
+    fn is_noop_pair(&self, fst: &Instr, snd: &Instr) -> bool {
+        let pop_top = self.is_op(snd.op, Opcode311::POP_TOP, Opcode310::POP_TOP);
+        let load_const = self.is_op(fst.op, Opcode311::LOAD_CONST, Opcode310::LOAD_CONST);
+        let dup_top = if self.minor() >= 11 {
+            fst.op == Opcode311::COPY as u8 && fst.arg == 1
+        } else {
+            fst.op == Opcode310::DUP_TOP as u8
+        };
+        let is_rot2 = |instr: &Instr| {
+            if self.minor() >= 11 {
+                instr.op == Opcode311::SWAP as u8 && instr.arg == 2
+            } else {
+                instr.op == Opcode310::ROT_TWO as u8
+            }
+        };
+        (pop_top && (load_const || dup_top)) || (is_rot2(fst) && is_rot2(snd))
+    }
+
+    /// Applies the rewriting rules until nothing changes.
+    /// Returns `true` if anything has been changed.
+    fn rewrite(&mut self) -> bool {
+        let mut changed = false;
+        // each iteration removes or redirects at least one instruction
+        for _ in 0..=self.instrs.len() * 2 {
+            if !self.rewrite_once() {
+                break;
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Follows the chain of unconditional jumps from `idx`.
+    /// Returns `None` if the chain is a loop.
+    fn final_target(&self, idx: usize) -> Option<usize> {
+        let mut cur = idx;
+        for _ in 0..self.instrs.len() {
+            if !self.is_unconditional_jump(self.instrs[cur].op) {
+                return Some(cur);
+            }
+            cur = self.effective_target(cur)?;
+        }
+        None
+    }
+
+    fn rewrite_once(&mut self) -> bool {
+        let mut changed = false;
+        let mut i = 0;
+        while let Some(cur) = self.next_alive(i) {
+            i = cur + 1;
+            let op = self.instrs[cur].op;
+            if op == CommonOpcode::NOP as u8 {
+                self.instrs[cur].alive = false;
+                changed = true;
+                continue;
+            }
+            let next = self.next_alive(cur + 1);
+            if let Some(next) = next {
+                if self.is_noop_pair(&self.instrs[cur], &self.instrs[next])
+                    && !self.is_jump_target(next)
+                {
+                    self.instrs[cur].alive = false;
+                    self.instrs[next].alive = false;
+                    i = next + 1;
+                    changed = true;
+                    continue;
+                }
+            }
+            let target = if let Some(target) = self.effective_target(cur) {
+                target
+            } else {
+                continue;
+            };
+            if self.is_unconditional_jump(op) && Some(target) == next {
+                self.instrs[cur].alive = false;
+                changed = true;
+                continue;
+            }
+            // jump threading
+            if let Some(final_target) = self.final_target(target) {
+                if final_target == target {
+                    continue;
+                }
+                if let Some(new_op) = self.redirected_op(op, final_target <= cur) {
+                    self.instrs[cur].op = new_op;
+                    self.instrs[cur].target = Some(final_target);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// the number of `EXTENDED_ARG`s required for `arg` (the code generator uses 1 or 3)
+    fn ext_len(arg: usize) -> usize {
+        if arg <= 0xFF {
+            0
+        } else if arg <= 0xFFFF {
+            1
+        } else {
+            3
+        }
+    }
+
+    /// Returns the code and the new offset of each instruction (the offset of the next alive instruction if removed).
+    fn assemble(&mut self) -> (Vec<u8>, Vec<usize>) {
+        let alive = (0..self.instrs.len())
+            .filter(|&i| self.instrs[i].alive)
+            .collect::<Vec<_>>();
+        let mut exts = alive
+            .iter()
+            .map(|&i| {
+                if self.instrs[i].target.is_some() {
+                    0
+                } else {
+                    Self::ext_len(self.instrs[i].arg)
+                }
+            })
+            .collect::<Vec<_>>();
+        // the sizes of the jump instructions depend on the offsets, so iterate until they are fixed
+        let mut offsets = vec![0; self.instrs.len() + 1];
+        loop {
+            let mut alive_offsets = Vec::with_capacity(alive.len() + 1);
+            let mut offset = 0;
+            for ext in exts.iter() {
+                alive_offsets.push(offset);
+                offset += (ext + 1) * 2;
+            }
+            alive_offsets.push(offset);
+            let mut rank = 0;
+            for (i, instr) in self.instrs.iter().enumerate() {
+                offsets[i] = alive_offsets[rank];
+                if instr.alive {
+                    rank += 1;
+                }
+            }
+            offsets[self.instrs.len()] = offset;
+            let mut fixed = true;
+            for (j, &i) in alive.iter().enumerate() {
+                if let Some(target) = self.instrs[i].target {
+                    let next = alive_offsets[j + 1];
+                    let target = offsets[target];
+                    let arg = match self.jump_kind(self.instrs[i].op) {
+                        Some(JumpKind::Forward) => (target - next) / self.jump_unit(),
+                        Some(JumpKind::Backward) => (next - target) / self.jump_unit(),
+                        Some(JumpKind::Absolute) | None => target / self.jump_unit(),
+                    };
+                    self.instrs[i].arg = arg;
+                    if Self::ext_len(arg) > exts[j] {
+                        exts[j] = Self::ext_len(arg);
+                        fixed = false;
+                    }
+                }
+            }
+            if fixed {
+                break;
+            }
+        }
+        let mut code = Vec::with_capacity(offsets[self.instrs.len()]);
+        for (&i, &ext) in alive.iter().zip(exts.iter()) {
+            let instr = &self.instrs[i];
+            let bytes = (instr.arg as u32).to_be_bytes();
+            for byte in bytes[4 - (ext + 1)..3].iter() {
+                code.push(CommonOpcode::EXTENDED_ARG as u8);
+                code.push(*byte);
+            }
+            code.push(instr.op);
+            code.push(bytes[3]);
+        }
+        offsets.pop();
+        (code, offsets)
+    }
+
+    /// `lnotab`: `[sdelta, ldelta, sdelta, ldelta, ..]`
+    fn remap_lnotab(lnotab: &[u8], map_offset: impl Fn(usize) -> usize) -> Vec<u8> {
+        let mut new_lnotab = Vec::with_capacity(lnotab.len());
+        let mut offset = 0;
+        let mut prev_new_offset = 0;
+        for pair in lnotab.chunks(2) {
+            let (sdelta, ldelta) = (pair[0], pair.get(1).copied().unwrap_or(0));
+            offset += sdelta as usize;
+            let new_offset = map_offset(offset);
+            let mut sdelta = new_offset - prev_new_offset;
+            while sdelta > 255 {
+                new_lnotab.push(255);
+                new_lnotab.push(0);
+                sdelta -= 255;
+            }
+            new_lnotab.push(sdelta as u8);
+            new_lnotab.push(ldelta);
+            prev_new_offset = new_offset;
+        }
+        new_lnotab
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use erg_common::error::Location;
+    use Opcode311::*;
+
+    const PY311: PythonVersion = PythonVersion::new(3, Some(11), Some(0));
+    const PY310: PythonVersion = PythonVersion::new(3, Some(10), Some(0));
+
+    fn code_obj(instrs: &[(u8, u8)]) -> CodeObj {
+        let mut code = CodeObj::empty(vec![], "<test>", "<module>", 1, 0);
+        for (op, arg) in instrs {
+            code.code.push(*op);
+            code.code.push(*arg);
+        }
+        code
+    }
+
+    fn instrs_311(instrs: &[(Opcode311, u8)]) -> Vec<(u8, u8)> {
+        instrs.iter().map(|(op, arg)| (*op as u8, *arg)).collect()
+    }
+
+    fn optimize(code: &mut CodeObj, py_version: PythonVersion) -> usize {
+        PeepholeOptimizer::optimize(code, &mut [], py_version);
+        code.code.len() / 2
+    }
+
+    #[test]
+    fn test_remove_noop_pairs() {
+        let mut code = code_obj(&instrs_311(&[
+            (RESUME, 0),
+            (NOP, 0),
+            (LOAD_CONST, 0),
+            (POP_TOP, 0),
+            (LOAD_NAME, 0),
+            (COPY, 1),
+            (POP_TOP, 0),
+            (SWAP, 2),
+            (SWAP, 2),
+            (RETURN_VALUE, 0),
+        ]));
+        assert_eq!(optimize(&mut code, PY311), 3);
+        assert_eq!(
+            code.code,
+            [RESUME as u8, 0, LOAD_NAME as u8, 0, RETURN_VALUE as u8, 0]
+        );
+    }
+
+    #[test]
+    fn test_keep_jump_target() {
+        let instrs = instrs_311(&[
+            (LOAD_NAME, 0),
+            (POP_JUMP_FORWARD_IF_FALSE, 1), // to `POP_TOP`
+            (LOAD_CONST, 0),
+            (POP_TOP, 0),
+            (LOAD_CONST, 0),
+            (RETURN_VALUE, 0),
+        ]);
+        let mut code = code_obj(&instrs);
+        assert_eq!(optimize(&mut code, PY311), 6);
+        assert_eq!(code.code, code_obj(&instrs).code);
+    }
+
+    #[test]
+    fn test_remove_jump_to_next() {
+        let mut code = code_obj(&instrs_311(&[
+            (LOAD_NAME, 0),
+            (POP_JUMP_FORWARD_IF_FALSE, 2), // to the second `LOAD_CONST`
+            (LOAD_CONST, 0),
+            (JUMP_FORWARD, 0),
+            (LOAD_CONST, 1),
+            (RETURN_VALUE, 0),
+        ]));
+        assert_eq!(optimize(&mut code, PY311), 5);
+        // the target is shifted
+        assert_eq!(&code.code[2..4], [POP_JUMP_FORWARD_IF_FALSE as u8, 1]);
+    }
+
+    #[test]
+    fn test_jump_threading() {
+        let mut code = code_obj(&instrs_311(&[
+            (LOAD_NAME, 0),
+            (POP_JUMP_FORWARD_IF_FALSE, 2), // to `JUMP_FORWARD`
+            (LOAD_CONST, 0),
+            (RETURN_VALUE, 0),
+            (JUMP_FORWARD, 1), // to the last `LOAD_CONST`
+            (LOAD_CONST, 1),
+            (LOAD_CONST, 2),
+            (RETURN_VALUE, 0),
+        ]));
+        assert_eq!(optimize(&mut code, PY311), 8);
+        assert_eq!(&code.code[2..4], [POP_JUMP_FORWARD_IF_FALSE as u8, 4]);
+    }
+
+    #[test]
+    fn test_jump_threading_backward() {
+        let mut code = code_obj(&instrs_311(&[
+            (LOAD_NAME, 0),                 // 0
+            (POP_JUMP_FORWARD_IF_FALSE, 2), // to `JUMP_BACKWARD`
+            (LOAD_CONST, 0),
+            (RETURN_VALUE, 0),
+            (JUMP_BACKWARD, 5), // to `LOAD_NAME`
+        ]));
+        assert_eq!(optimize(&mut code, PY311), 5);
+        assert_eq!(&code.code[2..4], [POP_JUMP_BACKWARD_IF_FALSE as u8, 2]);
+    }
+
+    #[test]
+    fn test_extended_arg() {
+        // LOAD_CONST 256; POP_TOP; LOAD_CONST 0; RETURN_VALUE
+        let mut code = code_obj(&instrs_311(&[
+            (EXTENDED_ARG, 1),
+            (LOAD_CONST, 0),
+            (POP_TOP, 0),
+            (LOAD_CONST, 0),
+            (RETURN_VALUE, 0),
+        ]));
+        assert_eq!(optimize(&mut code, PY311), 2);
+    }
+
+    #[test]
+    fn test_lnotab_and_spans() {
+        let mut code = code_obj(&[
+            (Opcode310::LOAD_CONST as u8, 0),
+            (Opcode310::POP_TOP as u8, 0),
+            (Opcode310::LOAD_NAME as u8, 0),
+            (Opcode310::POP_TOP as u8, 0),
+            (Opcode310::LOAD_CONST as u8, 0),
+            (Opcode310::RETURN_VALUE as u8, 0),
+        ]);
+        // line 2: offset 4, line 3: offset 8
+        code.lnotab = vec![4, 1, 4, 1];
+        let mut spans = [SourceMapEntry::new(4, 8, 2, Location::Unknown)];
+        assert!(PeepholeOptimizer::optimize(&mut code, &mut spans, PY310));
+        assert_eq!(code.code.len() / 2, 4);
+        assert_eq!(code.lnotab, [0, 1, 4, 1]);
+        assert_eq!((spans[0].offset, spans[0].end), (0, 4));
+    }
+}
@@ -7,7 +7,7 @@ use erg_common::set::Set;
 use erg_common::style::colors::DEBUG_MAIN;
 use erg_common::traits::{Locational, Stream};
 use erg_common::Str;
-use erg_common::{impl_display_from_debug, log};
+use erg_common::{impl_display_from_debug, log, trace_span};
 use erg_parser::ast::{ParamPattern, VarName};
 
 use crate::ty::{HasType, Ownership, Visibility};
@@ -65,6 +65,7 @@ impl OwnershipChecker {
     // moveされた後の変数が使用されていないかチェックする
     // ProceduralでないメソッドでRefMutが使われているかはSideEffectCheckerでチェックする
     pub fn check(&mut self, hir: HIR) -> Result<HIR, (HIR, OwnershipErrors)> {
+        let _span = trace_span!("ownership_check");
         log!(info "the ownership checking process has started.{RESET}");
         if self.full_path() != ("::".to_string() + &hir.name[..]) {
             self.path_stack.push(Visibility::private(hir.name.clone()));
@@ -253,12 +254,18 @@ impl OwnershipChecker {
                 self.path_stack.push(name_and_vis);
                 self.dict
                     .insert(Str::from(self.full_path()), LocalVars::default());
+                if let Some(guard) = &lambda.guard {
+                    self.check_expr(guard, Ownership::Ref, false);
+                }
                 self.check_block(&lambda.body);
                 self.path_stack.pop();
             }
             Expr::TypeAsc(asc) => {
                 self.check_expr(&asc.expr, ownership, chunk);
             }
+            Expr::Coerce(coerce) => {
+                self.check_expr(&coerce.expr, ownership, chunk);
+            }
             _ => {}
         }
     }
@@ -0,0 +1,351 @@
+//! defines `Packer` (`erg pack`).
+//!
+//! `erg pack` bundles a compiled script into a single executable zip archive (`.pyz`, the same format as `zipapp`),
+//! so that an application can be shipped as one file.
+//! Erg modules are linked into the main `.pyc` at compile time, so the archive contains:
+//!
+//! * `__main__.py`: a launcher that checks the Python version and executes the script
+//! * `<name>.pyc`: the compiled script
+//! * the Python files of the Erg standard library (`_erg_*.py`)
+//! * the local Python modules imported with `pyimport` (found in `ModuleCache`)
+//! * `manifest.txt`: the description of the archive, including the initialization order of the linked Erg modules
+//!
+//! The modules of the Python standard library and the site-packages are not bundled.
+//!
+//! ```sh
+//! erg pack foo.er
+//! python foo.pyz
+//! ```
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use erg_common::config::ErgConfig;
+use erg_common::env::{erg_std_path, is_std_decl_path, python_site_packages};
+use erg_common::error::{Location, MultiErrorDisplay};
+use erg_common::python_util::env_magic_number;
+use erg_common::serialize::get_ver_from_magic_num;
+use erg_common::switch_lang;
+use erg_common::traits::{ExitStatus, Runnable};
+
+use crate::artifact::ErrorArtifact;
+use crate::error::{CompileError, CompileErrors};
+use crate::module::SharedCompilerResource;
+use crate::Compiler;
+
+const SHEBANG: &str = "#!/usr/bin/env python3\n";
+
+/// The entries are written with this time (1980-01-01 00:00, the minimum of the MS-DOS format),
+/// so that the same sources produce the same archive (the timestamp of the `.pyc` is also cleared).
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+
+/// A file in the archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackEntry {
+    /// the path in the archive (separated by `/`, a directory ends with `/`)
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl PackEntry {
+    pub fn new(name: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            data,
+        }
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Writes `entries` as an uncompressed zip archive prefixed with a shebang line.
+pub fn write_archive(entries: &[PackEntry]) -> Vec<u8> {
+    let mut archive = SHEBANG.as_bytes().to_vec();
+    let mut central_dir = vec![];
+    for entry in entries {
+        let offset = archive.len() as u32;
+        let crc = crc32(&entry.data);
+        let size = entry.data.len() as u32;
+        let name = entry.name.as_bytes();
+        // local file header
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        archive.extend(20u16.to_le_bytes()); // version needed to extract (2.0)
+        archive.extend(0u16.to_le_bytes()); // flags
+        archive.extend(0u16.to_le_bytes()); // stored (no compression)
+        archive.extend(DOS_TIME.to_le_bytes());
+        archive.extend(DOS_DATE.to_le_bytes());
+        archive.extend(crc.to_le_bytes());
+        archive.extend(size.to_le_bytes());
+        archive.extend(size.to_le_bytes());
+        archive.extend((name.len() as u16).to_le_bytes());
+        archive.extend(0u16.to_le_bytes()); // extra field length
+        archive.extend(name);
+        archive.extend(&entry.data);
+        // central directory header
+        central_dir.extend(0x0201_4b50u32.to_le_bytes());
+        central_dir.extend(((3u16 << 8) | 20).to_le_bytes()); // made by UNIX, version 2.0
+        central_dir.extend(20u16.to_le_bytes());
+        central_dir.extend(0u16.to_le_bytes());
+        central_dir.extend(0u16.to_le_bytes());
+        central_dir.extend(DOS_TIME.to_le_bytes());
+        central_dir.extend(DOS_DATE.to_le_bytes());
+        central_dir.extend(crc.to_le_bytes());
+        central_dir.extend(size.to_le_bytes());
+        central_dir.extend(size.to_le_bytes());
+        central_dir.extend((name.len() as u16).to_le_bytes());
+        central_dir.extend([0; 8]); // extra field, comment, disk number, internal attributes
+        let mode = if entry.name.ends_with('/') {
+            0o40755u32 // drwxr-xr-x
+        } else {
+            0o100644 // -rw-r--r--
+        };
+        central_dir.extend((mode << 16).to_le_bytes());
+        central_dir.extend(offset.to_le_bytes());
+        central_dir.extend(name);
+    }
+    let central_dir_offset = archive.len() as u32;
+    let central_dir_size = central_dir.len() as u32;
+    archive.extend(central_dir);
+    // end of central directory record
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend([0; 4]); // disk numbers
+    archive.extend((entries.len() as u16).to_le_bytes());
+    archive.extend((entries.len() as u16).to_le_bytes());
+    archive.extend(central_dir_size.to_le_bytes());
+    archive.extend(central_dir_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes()); // comment length
+    archive
+}
+
+#[derive(Debug)]
+pub struct Packer {
+    cfg: ErgConfig,
+}
+
+impl Packer {
+    pub const fn new(cfg: ErgConfig) -> Self {
+        Self { cfg }
+    }
+
+    fn launcher(pyc_name: &str, py_magic_num: u32) -> String {
+        let ver = get_ver_from_magic_num(py_magic_num);
+        format!(
+            "# generated by `erg pack`
+import importlib.util
+import marshal
+import sys
+
+data = __loader__.get_data(\"{pyc_name}\")
+if data[:4] != importlib.util.MAGIC_NUMBER:
+    sys.exit(\"this archive requires Python {}.{}\")
+exec(marshal.loads(data[16:]), {{\"__name__\": \"__main__\", \"__builtins__\": __builtins__}})
+",
+            ver.major,
+            ver.minor.unwrap_or(0),
+        )
+    }
+
+    /// The Erg modules are listed in the order of initialization (see `InitOrderAnalyzer`).
+    fn manifest(pyc_name: &str, py_magic_num: u32, init_order: &[PathBuf], cwd: &Path) -> String {
+        let ver = get_ver_from_magic_num(py_magic_num);
+        let mut manifest = format!(
+            "# generated by `erg pack`
+entry = \"{pyc_name}\"
+python = \"{}.{}\"
+# the Erg modules linked into the entry, in the order of initialization
+init_order = [
+",
+            ver.major,
+            ver.minor.unwrap_or(0),
+        );
+        for path in init_order {
+            let path = path.strip_prefix(cwd).unwrap_or(path);
+            let path = path.to_string_lossy().replace('\\', "/");
+            manifest.push_str(&format!("    \"{path}\",\n"));
+        }
+        manifest.push_str("]\n");
+        manifest
+    }
+
+    /// Returns the paths of the Python sources of the local modules imported with `pyimport`.
+    /// A declaration file `foo.d.er` (or `__pycache__/foo.d.er`) corresponds to `foo.py` or the package `foo/`.
+    fn local_py_modules(shared: &SharedCompilerResource) -> Vec<PathBuf> {
+        let mut modules = vec![];
+        for path in shared.py_mod_cache.ref_inner().keys() {
+            let path: &Path = path.as_ref();
+            if is_std_decl_path(path)
+                || python_site_packages()
+                    .iter()
+                    .any(|site| path.starts_with(site))
+            {
+                continue;
+            }
+            let Some(dir) = path.parent() else {
+                continue;
+            };
+            let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+            let module = if file_name == "__init__.d.er" {
+                Some(dir.to_path_buf())
+            } else if let Some(stem) = file_name.strip_suffix(".d.er") {
+                let dir = if dir.ends_with("__pycache__") {
+                    dir.parent().unwrap_or(dir)
+                } else {
+                    dir
+                };
+                let py = dir.join(format!("{stem}.py"));
+                let package = dir.join(stem);
+                if py.is_file() {
+                    Some(py)
+                } else if package.join("__init__.py").is_file() {
+                    Some(package)
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+            if let Some(module) = module.filter(|module| !modules.contains(module)) {
+                modules.push(module);
+            }
+        }
+        modules.sort();
+        modules
+    }
+
+    /// Adds the `.py` files in `path` (a file or a package directory) as `prefix/...`.
+    fn add_py_files(path: &Path, prefix: &str, entries: &mut Vec<PackEntry>) -> io::Result<()> {
+        let name = path.file_name().and_then(|f| f.to_str()).unwrap_or("_");
+        let name = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{prefix}/{name}")
+        };
+        if path.is_dir() {
+            let mut children = fs::read_dir(path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            children.sort();
+            for child in children {
+                if child.is_dir() || child.extension().is_some_and(|ext| ext == "py") {
+                    Self::add_py_files(&child, &name, entries)?;
+                }
+            }
+        } else if !entries.iter().any(|entry| entry.name == name) {
+            entries.push(PackEntry::new(name, fs::read(path)?));
+        }
+        Ok(())
+    }
+
+    fn io_error(&self, path: &Path, err: io::Error) -> ErrorArtifact {
+        let path = path.display();
+        let desc = switch_lang!(
+            "japanese" => format!("{path}を読み込めませんでした: {err}"),
+            "simplified_chinese" => format!("无法读取{path}: {err}"),
+            "traditional_chinese" => format!("無法讀取{path}: {err}"),
+            "english" => format!("failed to read {path}: {err}"),
+        );
+        let err = CompileError::file_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            desc,
+            Location::Unknown,
+            "<module>".into(),
+            None,
+        );
+        ErrorArtifact::new(CompileErrors::from(err), CompileErrors::empty())
+    }
+
+    /// Compiles the script and returns the entries of the archive.
+    pub fn pack(&mut self) -> Result<Vec<PackEntry>, ErrorArtifact> {
+        let mut compiler = Compiler::new(self.cfg.copy());
+        let src = self.cfg.input.read();
+        let arti = compiler.compile(src, "exec")?;
+        arti.warns.write_all_stderr();
+        let py_magic_num = self.cfg.py_magic_num.unwrap_or_else(env_magic_number);
+        let (mut pyc, _stats) = arti.object.into_pyc_bytes(Some(py_magic_num));
+        // the timestamp is not checked because the source is not bundled
+        pyc[8..12].fill(0);
+        let pyc_name = format!("{}.pyc", self.cfg.input.unescaped_file_stem());
+        let mut entries = vec![
+            PackEntry::new(
+                "__main__.py",
+                Self::launcher(&pyc_name, py_magic_num).into(),
+            ),
+            PackEntry::new(pyc_name, pyc),
+        ];
+        let cwd = std::env::current_dir()
+            .and_then(fs::canonicalize)
+            .unwrap_or_default();
+        for module in Self::local_py_modules(compiler.shared()) {
+            // the modules are imported with the names relative to the current directory (e.g. `foo.bar`)
+            let prefix = module
+                .parent()
+                .and_then(|dir| dir.strip_prefix(&cwd).ok())
+                .map(|dir| {
+                    dir.components()
+                        .filter_map(|c| c.as_os_str().to_str())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                })
+                .unwrap_or_default();
+            // zipimport finds namespace packages only if the directories have their entries
+            let mut dir = String::new();
+            for component in prefix.split('/').filter(|c| !c.is_empty()) {
+                dir.push_str(component);
+                dir.push('/');
+                if !entries.iter().any(|entry| entry.name == dir) {
+                    entries.push(PackEntry::new(dir.clone(), vec![]));
+                }
+            }
+            Self::add_py_files(&module, &prefix, &mut entries)
+                .map_err(|err| self.io_error(&module, err))?;
+        }
+        let mut std_files = fs::read_dir(erg_std_path())
+            .and_then(|dir| dir.map(|entry| entry.map(|e| e.path())).collect())
+            .unwrap_or_else(|_| vec![]);
+        std_files.sort();
+        for file in std_files {
+            if file.extension().is_some_and(|ext| ext == "py") {
+                Self::add_py_files(&file, "", &mut entries)
+                    .map_err(|err| self.io_error(&file, err))?;
+            }
+        }
+        let manifest = Self::manifest(&entries[1].name, py_magic_num, compiler.init_order(), &cwd);
+        entries.push(PackEntry::new("manifest.txt", manifest.into()));
+        Ok(entries)
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let path = cfg.dump_pyz_path();
+        let entries = match Self::new(cfg.copy()).pack() {
+            Ok(entries) => entries,
+            Err(eart) => {
+                eart.warns.write_all_stderr();
+                eart.errors.write_all_stderr();
+                return ExitStatus::ERR1;
+            }
+        };
+        if let Err(err) = fs::write(&path, write_archive(&entries)) {
+            eprintln!("failed to write {}: {err}", path.display());
+            return ExitStatus::ERR1;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o755));
+        }
+        println!("packed: {} ({} files)", path.display(), entries.len());
+        ExitStatus::OK
+    }
+}
@@ -0,0 +1,161 @@
+//! defines `ModulePrefetcher` (`--parallel-parse`).
+//!
+//! The prefetcher follows the `import` calls of the entry module and lexes/parses the reachable Erg modules on a pool of threads,
+//! one level of the module graph at a time.
+//! The results are stored in `SharedASTCache`, and the checker takes them out instead of reading and parsing the modules by itself.
+//! Imports that cannot be resolved statically (e.g. those inside subroutines) are parsed by the checker as usual.
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::thread::available_parallelism;
+
+use erg_common::config::ErgConfig;
+use erg_common::set::Set;
+use erg_common::spawn::spawn_new_thread;
+use erg_common::traits::{Runnable, Stream};
+use erg_common::{log, trace_span};
+
+use erg_parser::ast::{Call, Expr, Module, OperationKind, AST};
+use erg_parser::build_ast::ASTBuilder;
+
+use crate::hir::Literal;
+use crate::module::{ParseResult, SharedCompilerResource};
+use crate::ty::ValueObj;
+
+/// (path, result, the paths of the modules it imports)
+type Prefetched = (PathBuf, ParseResult, Vec<PathBuf>);
+
+#[derive(Debug)]
+pub struct ModulePrefetcher {
+    cfg: ErgConfig,
+    shared: SharedCompilerResource,
+    jobs: usize,
+}
+
+impl ModulePrefetcher {
+    /// `cfg` is the configuration of the module whose imports are followed.
+    /// The number of threads defaults to the available parallelism.
+    pub fn new(cfg: ErgConfig, shared: SharedCompilerResource) -> Self {
+        let jobs = available_parallelism().map_or(1, NonZeroUsize::get);
+        Self { cfg, shared, jobs }
+    }
+
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Parses the modules (transitively) imported by `ast` and registers them in the AST cache.
+    /// Returns the number of the parsed modules.
+    pub fn prefetch(&self, ast: &AST) -> usize {
+        let _span = trace_span!("prefetch", ast.name);
+        let mut visited = Set::new();
+        let mut queue = Self::imports(&self.cfg, &ast.module);
+        let mut count = 0;
+        while !queue.is_empty() {
+            let targets = queue
+                .into_iter()
+                .filter(|path| visited.insert(path.clone()) && !self.is_analyzed(path))
+                .collect::<Vec<_>>();
+            queue = vec![];
+            for (path, result, imports) in self.parse_all(targets) {
+                queue.extend(imports);
+                self.shared.ast_cache.register(path, result);
+                count += 1;
+            }
+        }
+        log!(info "{count} modules are prefetched");
+        count
+    }
+
+    fn is_analyzed(&self, path: &Path) -> bool {
+        self.shared.mod_cache.get(path).is_some()
+            || self.shared.promises.is_registered(path)
+            || self.shared.ast_cache.contains(path)
+    }
+
+    /// The modules are distributed to the threads in round-robin order.
+    /// If a thread panics, the checker parses its modules again (and reports the error).
+    fn parse_all(&self, targets: Vec<PathBuf>) -> Vec<Prefetched> {
+        if targets.is_empty() {
+            return vec![];
+        }
+        let jobs = self.jobs.min(targets.len());
+        let mut buckets = vec![vec![]; jobs];
+        for (i, path) in targets.into_iter().enumerate() {
+            buckets[i % jobs].push(path);
+        }
+        let handles = buckets
+            .into_iter()
+            .enumerate()
+            .map(|(i, bucket)| {
+                let cfg = self.cfg.copy();
+                spawn_new_thread(
+                    move || {
+                        bucket
+                            .into_iter()
+                            .filter_map(|path| Self::parse(&cfg, path))
+                            .collect::<Vec<_>>()
+                    },
+                    &format!("prefetch-{i}"),
+                )
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok())
+            .flatten()
+            .collect()
+    }
+
+    /// Unreadable modules are not cached (the checker reports the IO error).
+    fn parse(cfg: &ErgConfig, path: PathBuf) -> Option<Prefetched> {
+        let mut cfg = cfg.inherit(path.clone());
+        let src = cfg.input.try_read().ok()?;
+        let mut builder = ASTBuilder::new(cfg.copy());
+        let result = builder.build(src);
+        let imports = match &result {
+            Ok(artifact) => Self::imports(&cfg, &artifact.ast.module),
+            Err(artifact) => artifact
+                .ast
+                .as_ref()
+                .map_or(vec![], |ast| Self::imports(&cfg, &ast.module)),
+        };
+        Some((path, result, imports))
+    }
+
+    /// Collects the Erg modules imported at the top level of `module` (e.g. `foo = import "foo"`).
+    fn imports(cfg: &ErgConfig, module: &Module) -> Vec<PathBuf> {
+        module
+            .iter()
+            .filter_map(|expr| {
+                let call = Self::import_call(expr)?;
+                let Some(Expr::Literal(lit)) = call.args.get_left_or_key("Path") else {
+                    return None;
+                };
+                let Ok(Literal {
+                    value: ValueObj::Str(name),
+                    ..
+                }) = Literal::try_from(lit.token.clone())
+                else {
+                    return None;
+                };
+                // namespace packages (`--namespace-packages`) have no source
+                cfg.resolve_real_path(Path::new(&name[..]))
+                    .filter(|path| path.is_file())
+            })
+            .collect()
+    }
+
+    fn import_call(expr: &Expr) -> Option<&Call> {
+        match expr {
+            Expr::Def(def) if def.def_kind().is_erg_import() => match def.body.block.first() {
+                Some(Expr::Call(call)) => Some(call),
+                _ => None,
+            },
+            Expr::Call(call) if call.additional_operation() == Some(OperationKind::Import) => {
+                Some(call)
+            }
+            _ => None,
+        }
+    }
+}
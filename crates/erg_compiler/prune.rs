@@ -0,0 +1,106 @@
+//! defines `ModulePruner`.
+//!
+//! Every Erg module registered in `SharedModuleCache` is linked into the main module (see `HIRLinker`),
+//! even if the variable it is bound to is never used.
+//! With `--prune-modules`, such imports are removed before linking.
+//! This is opt-in because the top-level side effects of the removed modules are also removed.
+use std::path::{Path, PathBuf};
+
+use erg_common::config::ErgConfig;
+use erg_common::pathutil::same_file;
+use erg_common::traits::Stream;
+use erg_common::{log, trace_span};
+use erg_parser::ast::OperationKind;
+
+use crate::hir::{Dummy, Expr, Signature, HIR};
+use crate::init_order::ModuleSummary;
+use crate::module::SharedCompilerResource;
+
+/// Removes the modules which are not reachable from the main module.
+/// For example:
+/// ```erg
+/// # main.er
+/// foo = import "foo" # `foo` is never used
+/// bar = import "bar"
+/// print! bar.x
+/// ```
+/// `foo = import "foo"` is removed, and `foo.er` (and the modules only `foo.er` imports) is removed from the module cache.
+/// `import "foo"` which is not bound to a variable is always kept.
+#[derive(Debug)]
+pub struct ModulePruner<'a> {
+    cfg: &'a ErgConfig,
+    shared: &'a SharedCompilerResource,
+}
+
+impl<'a> ModulePruner<'a> {
+    pub fn new(cfg: &'a ErgConfig, shared: &'a SharedCompilerResource) -> Self {
+        Self { cfg, shared }
+    }
+
+    /// Returns the paths of the removed modules.
+    pub fn prune(&self, hir: &mut HIR) -> Vec<PathBuf> {
+        let _span = trace_span!("prune_modules", &hir.name[..]);
+        let mut reachable = vec![self.cfg.input.unescaped_path().to_path_buf()];
+        let mut unvisited = self.prune_imports(self.cfg, hir);
+        while let Some(path) = unvisited.pop() {
+            if reachable.iter().any(|r| r == &path || same_file(r, &path)) {
+                continue;
+            }
+            let cfg = self.cfg.inherit(path.clone());
+            if let Some(mut entry) = self.shared.mod_cache.get_mut(path.as_path()) {
+                if let Some(hir) = entry.hir.as_mut() {
+                    unvisited.extend(self.prune_imports(&cfg, hir));
+                }
+            }
+            reachable.push(path);
+        }
+        let unreachable = self
+            .shared
+            .mod_cache
+            .ref_inner()
+            .iter()
+            .filter_map(|(path, entry)| {
+                let path: &Path = path;
+                let reachable = reachable.iter().any(|r| r == path || same_file(r, path));
+                (entry.hir.is_some() && !reachable).then(|| path.to_path_buf())
+            })
+            .collect::<Vec<_>>();
+        for path in unreachable.iter() {
+            self.shared.mod_cache.remove(path.as_path());
+        }
+        log!(info "pruned modules: {unreachable:?}");
+        unreachable
+    }
+
+    /// Removes the unused `x = import "..."` in `hir` and returns the modules still imported.
+    fn prune_imports(&self, cfg: &ErgConfig, hir: &mut HIR) -> Vec<PathBuf> {
+        for chunk in hir.module.iter_mut() {
+            if self.is_unused_import(chunk) {
+                *chunk = Expr::Dummy(Dummy::empty());
+            }
+        }
+        ModuleSummary::new(cfg, hir)
+            .imports
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    fn is_unused_import(&self, expr: &Expr) -> bool {
+        let Expr::Def(def) = expr else {
+            return false;
+        };
+        let is_import = matches!(
+            def.body.block.first(),
+            Some(Expr::Call(call)) if def.body.block.len() == 1
+                && call.additional_operation() == Some(OperationKind::Import)
+        );
+        is_import
+            && matches!(def.sig, Signature::Var(_))
+            && self
+                .shared
+                .index
+                .get_refs(&def.sig.ident().vi.def_loc)
+                .is_some_and(|refs| refs.referrers.is_empty())
+    }
+}
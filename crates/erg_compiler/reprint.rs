@@ -0,0 +1,596 @@
+//! prints HIR back to Erg source code, and checks that the printed code has the same types as the original.
+//!
+//! HIRをErgのソースコードに戻し、型が元のコードと一致するか検査する
+//! (脱糖・低水準化の検証用)
+use std::fmt;
+
+use erg_common::config::ErgConfig;
+use erg_common::error::MultiErrorDisplay;
+use erg_common::traits::{Runnable, Stream};
+
+use erg_parser::ast::{ParamPattern, VarName};
+use erg_parser::build_ast::ASTBuilder;
+use erg_parser::token::TokenKind;
+
+use crate::context::Context;
+use crate::error::CompileErrors;
+use crate::hir::{
+    Accessor, Args, Array, Block, ClassDef, Def, Dict, Expr, Identifier, Lambda,
+    NonDefaultParamSignature, Params, PatchDef, Record, Set, Signature, Tuple, HIR,
+};
+use crate::lower::ASTLowerer;
+use crate::ty::constructors::mono;
+use crate::ty::value::{GenTypeObj, ValueObj};
+
+/// Variables generated by the desugarer (e.g. `%v_desugar_1`) cannot be written in the source code
+fn escape_name(name: &str) -> String {
+    name.replace('%', "__erg_")
+}
+
+/// Prints HIR as (re-parsable) Erg source code.
+/// Type information not written in the original code is not printed.
+#[derive(Debug, Default)]
+pub struct HIRPrinter {
+    level: usize,
+}
+
+impl HIRPrinter {
+    pub const fn new() -> Self {
+        Self { level: 0 }
+    }
+
+    pub fn print(&mut self, hir: &HIR) -> String {
+        let mut code = String::new();
+        for expr in hir.module.iter() {
+            code += &self.print_stmt(expr);
+        }
+        code
+    }
+
+    fn indent(&self) -> String {
+        "    ".repeat(self.level)
+    }
+
+    /// Whether `expr` can be printed in one line
+    fn is_inline(expr: &Expr) -> bool {
+        match expr {
+            Expr::Def(_) | Expr::ClassDef(_) | Expr::PatchDef(_) | Expr::ReDef(_) => false,
+            Expr::Compound(_) | Expr::Code(_) | Expr::Dummy(_) => false,
+            Expr::Lambda(lambda) => Self::is_inline_block(&lambda.body),
+            Expr::Call(call) => Self::is_inline_args(&call.args) && Self::is_inline(&call.obj),
+            Expr::BinOp(bin) => Self::is_inline(&bin.lhs) && Self::is_inline(&bin.rhs),
+            Expr::UnaryOp(unary) => Self::is_inline(&unary.expr),
+            Expr::Accessor(Accessor::Attr(attr)) => Self::is_inline(&attr.obj),
+            Expr::TypeAsc(tasc) => Self::is_inline(&tasc.expr),
+            Expr::Coerce(coerce) => Self::is_inline(&coerce.expr),
+            Expr::Array(Array::Normal(arr)) => Self::is_inline_args(&arr.elems),
+            Expr::Tuple(Tuple::Normal(tup)) => Self::is_inline_args(&tup.elems),
+            Expr::Set(Set::Normal(set)) => Self::is_inline_args(&set.elems),
+            Expr::Dict(Dict::Normal(dict)) => dict
+                .kvs
+                .iter()
+                .all(|kv| Self::is_inline(&kv.key) && Self::is_inline(&kv.value)),
+            Expr::Record(rec) => rec
+                .attrs
+                .iter()
+                .all(|def| Self::is_inline_block(&def.body.block)),
+            _ => true,
+        }
+    }
+
+    fn is_inline_block(block: &Block) -> bool {
+        block.len() == 1 && Self::is_inline(block.first().unwrap())
+    }
+
+    fn is_inline_args(args: &Args) -> bool {
+        args.pos_args.iter().all(|arg| Self::is_inline(&arg.expr))
+            && args.var_args.iter().all(|arg| Self::is_inline(&arg.expr))
+            && args.kw_args.iter().all(|arg| Self::is_inline(&arg.expr))
+    }
+
+    fn print_stmt(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Compound(block) | Expr::Code(block) => {
+                let mut code = String::new();
+                for expr in block.iter() {
+                    code += &self.print_stmt(expr);
+                }
+                code
+            }
+            Expr::Dummy(dummy) => {
+                let mut code = String::new();
+                for expr in dummy.iter() {
+                    code += &self.print_stmt(expr);
+                }
+                code
+            }
+            Expr::Def(def) => self.print_def(def, false),
+            Expr::ClassDef(class_def) => self.print_class_def(class_def),
+            Expr::PatchDef(patch_def) => self.print_patch_def(patch_def),
+            Expr::ReDef(redef) => {
+                let attr = self.print_acc(&redef.attr);
+                format!("{}{attr} ={}", self.indent(), self.print_body(&redef.block))
+            }
+            other => format!("{}{}\n", self.indent(), self.print_expr(other)),
+        }
+    }
+
+    fn print_block(&mut self, block: &Block) -> String {
+        self.level += 1;
+        let mut code = String::new();
+        for expr in block.iter() {
+            code += &self.print_stmt(expr);
+        }
+        self.level -= 1;
+        code
+    }
+
+    /// ` expr\n` or `\n    block\n`
+    fn print_body(&mut self, block: &Block) -> String {
+        if Self::is_inline_block(block) {
+            format!(" {}\n", self.print_expr(block.first().unwrap()))
+        } else {
+            format!("\n{}", self.print_block(block))
+        }
+    }
+
+    fn print_ident(ident: &Identifier) -> String {
+        if ident.vis().is_public() {
+            format!(".{}", escape_name(ident.inspect()))
+        } else {
+            escape_name(ident.inspect())
+        }
+    }
+
+    fn print_acc(&mut self, acc: &Accessor) -> String {
+        match acc {
+            Accessor::Ident(ident) => escape_name(ident.inspect()),
+            Accessor::Attr(attr) => {
+                let obj = self.print_operand(&attr.obj);
+                Self::print_attr(obj, &attr.ident)
+            }
+        }
+    }
+
+    fn print_attr(obj: String, ident: &Identifier) -> String {
+        if ident.raw.vis.is_private() || ident.vis().is_private() {
+            format!("{obj}::{}", escape_name(ident.inspect()))
+        } else {
+            format!("{obj}.{}", escape_name(ident.inspect()))
+        }
+    }
+
+    /// Encloses `expr` in parentheses if it is not atomic.
+    fn print_operand(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::BinOp(_) | Expr::UnaryOp(_) | Expr::Lambda(_) | Expr::TypeAsc(_) => {
+                format!("({})", self.print_expr(expr))
+            }
+            _ => self.print_expr(expr),
+        }
+    }
+
+    fn print_expr(&mut self, expr: &Expr) -> String {
+        match expr {
+            // the string literals split by the interpolation desugaring are not closed
+            Expr::Lit(lit) if matches!(lit.value, ValueObj::Str(_)) => lit.value.to_string(),
+            Expr::Lit(lit) => lit.token.content.to_string(),
+            Expr::Accessor(acc) | Expr::Import(acc) => self.print_acc(acc),
+            Expr::Array(arr) => match arr {
+                Array::Normal(arr) => format!("[{}]", self.print_args(&arr.elems)),
+                Array::WithLength(arr) => {
+                    let elem = self.print_expr(&arr.elem);
+                    format!("[{elem}; {}]", self.print_expr(&arr.len))
+                }
+                Array::Comprehension(arr) => {
+                    let elem = self.print_expr(&arr.elem);
+                    format!("[{elem} | {}]", self.print_expr(&arr.guard))
+                }
+            },
+            Expr::Tuple(Tuple::Normal(tup)) => {
+                if tup.elems.len() == 1 {
+                    format!("({},)", self.print_args(&tup.elems))
+                } else {
+                    format!("({})", self.print_args(&tup.elems))
+                }
+            }
+            Expr::Set(set) => match set {
+                Set::Normal(set) => format!("{{{}}}", self.print_args(&set.elems)),
+                Set::WithLength(set) => {
+                    let elem = self.print_expr(&set.elem);
+                    format!("{{{elem}; {}}}", self.print_expr(&set.len))
+                }
+            },
+            Expr::Dict(Dict::Normal(dict)) => {
+                if dict.kvs.is_empty() {
+                    return "{:}".into();
+                }
+                let kvs = dict
+                    .kvs
+                    .iter()
+                    .map(|kv| {
+                        let key = self.print_expr(&kv.key);
+                        format!("{key}: {}", self.print_expr(&kv.value))
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", kvs.join(", "))
+            }
+            Expr::Dict(Dict::Comprehension(dict)) => {
+                let key = self.print_expr(&dict.key);
+                let value = self.print_expr(&dict.value);
+                format!("{{{key}: {value} | {}}}", self.print_expr(&dict.guard))
+            }
+            Expr::Record(rec) => self.print_record(rec),
+            Expr::BinOp(bin) => {
+                let lhs = self.print_operand(&bin.lhs);
+                let rhs = self.print_operand(&bin.rhs);
+                format!("{lhs} {} {rhs}", bin.op.content)
+            }
+            Expr::UnaryOp(unary) => {
+                let expr = self.print_operand(&unary.expr);
+                if unary.op.content.chars().all(char::is_alphabetic) {
+                    format!("{} {expr}", unary.op.content)
+                } else {
+                    format!("{}{expr}", unary.op.content)
+                }
+            }
+            Expr::Call(call) => {
+                let obj = self.print_operand(&call.obj);
+                let obj = if let Some(attr) = &call.attr_name {
+                    Self::print_attr(obj, attr)
+                } else {
+                    obj
+                };
+                if Self::is_inline_args(&call.args) {
+                    format!("{obj}({})", self.print_args(&call.args))
+                } else {
+                    self.print_block_args(obj, &call.args)
+                }
+            }
+            Expr::Lambda(lambda) => self.print_lambda(lambda),
+            Expr::TypeAsc(tasc) => {
+                let expr = self.print_operand(&tasc.expr);
+                format!("{expr}{}", tasc.spec.raw)
+            }
+            // the coercion is implicit
+            Expr::Coerce(coerce) => self.print_expr(&coerce.expr),
+            Expr::Def(_)
+            | Expr::ClassDef(_)
+            | Expr::PatchDef(_)
+            | Expr::ReDef(_)
+            | Expr::Compound(_)
+            | Expr::Code(_)
+            | Expr::Dummy(_) => {
+                // statements are printed as a block
+                let code = self.print_stmt(expr);
+                code.trim_end().to_string()
+            }
+        }
+    }
+
+    fn print_args(&mut self, args: &Args) -> String {
+        let mut code = vec![];
+        for arg in args.pos_args.iter() {
+            code.push(self.print_expr(&arg.expr));
+        }
+        if let Some(var_args) = &args.var_args {
+            code.push(format!("*{}", self.print_expr(&var_args.expr)));
+        }
+        for arg in args.kw_args.iter() {
+            code.push(format!(
+                "{} := {}",
+                arg.keyword.content,
+                self.print_expr(&arg.expr)
+            ));
+        }
+        code.join(", ")
+    }
+
+    /// ```erg
+    /// match x:
+    ///     (i: Int) ->
+    ///         ...
+    ///     _ -> ...
+    /// ```
+    fn print_block_args(&mut self, obj: String, args: &Args) -> String {
+        let mut pos_args = args.pos_args.iter();
+        let mut code = match pos_args.next() {
+            Some(first) => format!("{obj} {}:", self.print_expr(&first.expr)),
+            None => format!("{obj}:"),
+        };
+        self.level += 1;
+        for arg in pos_args {
+            code += &format!("\n{}{}", self.indent(), self.print_expr(&arg.expr));
+        }
+        if let Some(var_args) = &args.var_args {
+            code += &format!("\n{}*{}", self.indent(), self.print_expr(&var_args.expr));
+        }
+        for arg in args.kw_args.iter() {
+            let expr = self.print_expr(&arg.expr);
+            code += &format!("\n{}{} := {expr}", self.indent(), arg.keyword.content);
+        }
+        self.level -= 1;
+        code
+    }
+
+    fn print_record(&mut self, rec: &Record) -> String {
+        if rec.attrs.is_empty() {
+            return "{=}".into();
+        }
+        let attrs = rec
+            .attrs
+            .iter()
+            .map(|def| {
+                let name = Self::print_ident(def.sig.ident());
+                let body = self.print_body(&def.body.block);
+                format!("{name} ={}", body.trim_end())
+            })
+            .collect::<Vec<_>>();
+        format!("{{{}}}", attrs.join("; "))
+    }
+
+    fn print_param(param: &NonDefaultParamSignature) -> String {
+        let pat = match &param.raw.pat {
+            ParamPattern::Discard(_) => "_".into(),
+            ParamPattern::VarName(name) => escape_name(name.inspect()),
+            other => other.to_string(),
+        };
+        match &param.raw.t_spec {
+            // type specifications generated by the desugarer may contain `?` (to be inferred)
+            Some(t_spec) if !t_spec.to_string().contains('?') => format!("{pat}{t_spec}"),
+            _ => pat,
+        }
+    }
+
+    fn print_params(&mut self, params: &Params) -> String {
+        let mut code = vec![];
+        for param in params.non_defaults.iter() {
+            code.push(Self::print_param(param));
+        }
+        if let Some(var_params) = &params.var_params {
+            code.push(format!("*{}", Self::print_param(var_params)));
+        }
+        for param in params.defaults.iter() {
+            let default = self.print_expr(&param.default_val);
+            code.push(format!("{} := {default}", Self::print_param(&param.sig)));
+        }
+        format!("({})", code.join(", "))
+    }
+
+    fn print_lambda(&mut self, lambda: &Lambda) -> String {
+        // `() -> x` => `do(x)`, `() => x` => `do!(x)`
+        if lambda.params.is_empty() && lambda.params.var_params.is_none() {
+            let do_ = if lambda.op.is(TokenKind::ProcArrow) {
+                "do!"
+            } else {
+                "do"
+            };
+            return if Self::is_inline_block(&lambda.body) {
+                format!("{do_}({})", self.print_expr(lambda.body.first().unwrap()))
+            } else {
+                format!("{do_}:\n{}", self.print_block(&lambda.body).trim_end())
+            };
+        }
+        let params = self.print_params(&lambda.params);
+        let body = self.print_body(&lambda.body);
+        format!("{params} {}{}", lambda.op.content, body.trim_end())
+    }
+
+    /// If `bare` is `true`, the visibility is not printed (for the methods).
+    fn print_sig(&mut self, sig: &Signature, bare: bool) -> String {
+        match sig {
+            Signature::Var(var) => {
+                let name = if bare {
+                    escape_name(var.ident.inspect())
+                } else {
+                    Self::print_ident(&var.ident)
+                };
+                match &var.t_spec {
+                    Some(t_spec) => format!("{name}{}", t_spec.raw),
+                    None => name,
+                }
+            }
+            Signature::Subr(subr) => {
+                let name = if bare {
+                    escape_name(subr.ident.inspect())
+                } else {
+                    Self::print_ident(&subr.ident)
+                };
+                let bounds = if subr.bounds.is_empty() {
+                    "".to_string()
+                } else {
+                    let bounds = subr
+                        .bounds
+                        .iter()
+                        .map(|bound| bound.to_string())
+                        .collect::<Vec<_>>();
+                    format!("|{}|", bounds.join(", "))
+                };
+                let params = self.print_params(&subr.params);
+                let ret = subr
+                    .return_t_spec
+                    .as_ref()
+                    .map_or("".to_string(), |t_spec| t_spec.raw.to_string());
+                format!("{name}{bounds}{params}{ret}")
+            }
+        }
+    }
+
+    fn print_def(&mut self, def: &Def, bare: bool) -> String {
+        let mut code = String::new();
+        if let Some(decos) = &def.sig.ident().vi.comptime_decos {
+            for deco in decos.iter() {
+                code += &format!("{}@{deco}\n", self.indent());
+            }
+        }
+        let sig = self.print_sig(&def.sig, bare);
+        let body = self.print_body(&def.body.block);
+        code += &format!("{}{sig} {}{body}", self.indent(), def.body.op.content);
+        code
+    }
+
+    /// The methods are merged into one block in HIR,
+    /// so they are divided into private (`C::`) and public (`C.`) blocks again.
+    fn print_methods(&mut self, class: &str, methods: &Block) -> String {
+        let (privates, publics): (Vec<_>, Vec<_>) = methods
+            .iter()
+            .partition(|expr| matches!(expr, Expr::Def(def) if def.sig.ident().vis().is_private()));
+        let mut code = String::new();
+        for (vis, methods) in [("::", privates), (".", publics)] {
+            if methods.is_empty() {
+                continue;
+            }
+            code += &format!("{}{class}{vis}\n", self.indent());
+            self.level += 1;
+            for method in methods {
+                match method {
+                    Expr::Def(def) => code += &self.print_def(def, true),
+                    other => code += &self.print_stmt(other),
+                }
+            }
+            self.level -= 1;
+        }
+        code
+    }
+
+    fn print_class_def(&mut self, class_def: &ClassDef) -> String {
+        let mut code = String::new();
+        let inheritable = class_def
+            .obj
+            .impls()
+            .is_some_and(|impls| impls.contains_intersec(&mono("InheritableType")));
+        if inheritable {
+            code += &format!("{}@Inheritable\n", self.indent());
+        }
+        let sig = self.print_sig(&class_def.sig, false);
+        let require_or_sup = class_def
+            .require_or_sup
+            .as_ref()
+            .map(|expr| self.print_operand(expr));
+        let body = match (&class_def.obj, require_or_sup) {
+            (GenTypeObj::Subclass(_), Some(sup)) => format!("Inherit {sup}"),
+            (_, Some(req)) => format!("Class {req}"),
+            (_, None) => "Class()".into(),
+        };
+        code += &format!("{}{sig} = {body}\n", self.indent());
+        code += &self.print_methods(class_def.sig.ident().inspect(), &class_def.methods);
+        code
+    }
+
+    fn print_patch_def(&mut self, patch_def: &PatchDef) -> String {
+        let sig = self.print_sig(&patch_def.sig, false);
+        let base = self.print_operand(&patch_def.base);
+        let mut code = format!("{}{sig} = Patch {base}\n", self.indent());
+        code += &self.print_methods(patch_def.sig.ident().inspect(), &patch_def.methods);
+        code
+    }
+}
+
+#[derive(Debug)]
+pub enum RoundTripError {
+    /// The original code could not be checked
+    Original(CompileErrors),
+    /// The printed code could not be checked
+    Printed { code: String, errs: CompileErrors },
+    /// The type of the variable has been changed in the printed code
+    TypeMismatch {
+        code: String,
+        name: VarName,
+        original: String,
+        printed: Option<String>,
+    },
+}
+
+impl fmt::Display for RoundTripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Original(errs) => write!(f, "the original code has {} errors", errs.len()),
+            Self::Printed { code, errs } => {
+                writeln!(f, "the printed code has {} errors:", errs.len())?;
+                write!(f, "{code}")
+            }
+            Self::TypeMismatch {
+                code,
+                name,
+                original,
+                printed,
+            } => {
+                let printed = printed.as_deref().unwrap_or("(undefined)");
+                writeln!(f, "type of `{name}` mismatched: {original} vs {printed}")?;
+                write!(f, "{code}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoundTripError {}
+
+impl RoundTripError {
+    pub fn write_all_stderr(&self) {
+        match self {
+            Self::Original(errs) | Self::Printed { errs, .. } => errs.write_all_stderr(),
+            Self::TypeMismatch { .. } => {}
+        }
+        eprintln!("{self}");
+    }
+}
+
+/// Checks the code, prints the HIR back to Erg code, re-checks the printed code and compares the types.
+/// This is used as an oracle for the desugarer and the lowerer.
+#[derive(Debug)]
+pub struct RoundTripChecker {
+    cfg: ErgConfig,
+}
+
+impl RoundTripChecker {
+    pub const fn new(cfg: ErgConfig) -> Self {
+        Self { cfg }
+    }
+
+    fn lower(cfg: ErgConfig, src: String) -> Result<(HIR, Context), CompileErrors> {
+        let mut ast_builder = ASTBuilder::new(cfg.copy());
+        let artifact = ast_builder
+            .build(src)
+            .map_err(|artifact| CompileErrors::from(artifact.errors))?;
+        let mut lowerer = ASTLowerer::new(cfg);
+        let artifact = lowerer
+            .lower(artifact.ast, "exec")
+            .map_err(|artifact| artifact.errors)?;
+        let module = lowerer.pop_mod_ctx().unwrap();
+        Ok((artifact.object, module.context))
+    }
+
+    /// Returns the printed code if the check succeeds.
+    pub fn check(&self, src: String) -> Result<String, RoundTripError> {
+        let (hir, original) =
+            Self::lower(self.cfg.copy(), src).map_err(RoundTripError::Original)?;
+        let code = HIRPrinter::new().print(&hir);
+        let (_, printed) = match Self::lower(self.cfg.copy(), code.clone()) {
+            Ok(res) => res,
+            Err(errs) => return Err(RoundTripError::Printed { code, errs }),
+        };
+        for (name, vi) in original.locals.iter() {
+            // skip the variables generated by the desugarer (e.g. `%1`)
+            if !name
+                .inspect()
+                .starts_with(|c: char| c.is_alphabetic() || c == '_')
+            {
+                continue;
+            }
+            let printed_t = printed.locals.get(name).map(|vi| &vi.t);
+            // free type variables are not equal to each other unless they are the same object
+            let same = printed_t.is_some_and(|t| {
+                original.same_type_of(&vi.t, t) || t.to_string() == vi.t.to_string()
+            });
+            if !same {
+                return Err(RoundTripError::TypeMismatch {
+                    code,
+                    name: name.clone(),
+                    original: vi.t.to_string(),
+                    printed: printed_t.map(|t| t.to_string()),
+                });
+            }
+        }
+        Ok(code)
+    }
+}
@@ -0,0 +1,119 @@
+//! defines `SignatureExtractor` (`--lazy-import`).
+//!
+//! The extractor reduces the AST of a module to its public signature:
+//! the bodies of the public definitions whose types are specified are replaced with type holes (`_`),
+//! and the private definitions and the top-level expressions are dropped.
+//! The imports, the constants (e.g. type aliases) and the class definitions are kept as they are.
+//! The importers can resolve the members of the module with the reduced module,
+//! and the whole module is checked when one of the members is used.
+use erg_common::traits::{Locational, Stream};
+use erg_common::Str;
+
+use erg_parser::ast::{
+    Accessor, Block, ClassAttr, ClassAttrs, Def, DefBody, Dummy, Expr, Identifier, Methods, Module,
+    Params, Signature, AST,
+};
+
+#[derive(Debug, Default)]
+pub struct SignatureExtractor;
+
+impl SignatureExtractor {
+    /// Returns `None` if the type of a public member cannot be determined without checking the body
+    /// (e.g. `.f x = x + 1`) or the module modifies the other types (e.g. patches).
+    pub fn extract(ast: &AST) -> Option<AST> {
+        let mut module = Module::with_capacity(ast.module.len());
+        for chunk in ast.module.iter() {
+            if let Some(chunk) = Self::extract_chunk(chunk)? {
+                module.push(chunk);
+            }
+        }
+        Some(AST::new(ast.name.clone(), module, ast.features))
+    }
+
+    /// `Some(None)`: the chunk is not a part of the signature
+    fn extract_chunk(chunk: &Expr) -> Option<Option<Expr>> {
+        match chunk {
+            Expr::Def(def) => Some(Self::extract_def(def)?.map(Expr::Def)),
+            Expr::ClassDef(class_def) => {
+                let mut class_def = class_def.clone();
+                let mut methods_list = Vec::with_capacity(class_def.methods_list.len());
+                for methods in class_def.methods_list.iter() {
+                    methods_list.extend(Self::extract_methods(methods)?);
+                }
+                class_def.methods_list = methods_list;
+                Some(Some(Expr::ClassDef(class_def)))
+            }
+            Expr::Methods(methods) => Some(Self::extract_methods(methods)?.map(Expr::Methods)),
+            Expr::TypeAscription(_) => Some(Some(chunk.clone())),
+            // e.g. `{.x; .y} = import "foo"`
+            Expr::Dummy(dummy) => {
+                let mut exprs = vec![];
+                for expr in dummy.exprs.iter() {
+                    exprs.extend(Self::extract_chunk(expr)?);
+                }
+                Some((!exprs.is_empty()).then(|| Expr::Dummy(Dummy::new(dummy.loc, exprs))))
+            }
+            Expr::PatchDef(_) | Expr::ReDef(_) => None,
+            _ => Some(None),
+        }
+    }
+
+    fn extract_methods(methods: &Methods) -> Option<Option<Methods>> {
+        let mut attrs = vec![];
+        for attr in methods.attrs.iter() {
+            match attr {
+                ClassAttr::Def(def) => {
+                    attrs.extend(Self::extract_def(def)?.map(ClassAttr::Def));
+                }
+                ClassAttr::Decl(_) | ClassAttr::Doc(_) => attrs.push(attr.clone()),
+            }
+        }
+        if attrs.is_empty() {
+            return Some(None);
+        }
+        let mut methods = methods.clone();
+        methods.attrs = ClassAttrs::new(attrs);
+        Some(Some(methods))
+    }
+
+    fn extract_def(def: &Def) -> Option<Option<Def>> {
+        if !def.def_kind().is_other() || def.is_const() {
+            return Some(Some(def.clone()));
+        }
+        if def.sig.vis().is_private() {
+            return Some(None);
+        }
+        let specified = match &def.sig {
+            Signature::Var(sig) => sig.t_spec.is_some(),
+            Signature::Subr(sig) => {
+                sig.return_t_spec.is_some() && Self::params_are_specified(&sig.params)
+            }
+        };
+        if specified {
+            let hole = Identifier::private_with_line(Str::ever("_"), def.body.ln_begin()?);
+            let body = DefBody::new(
+                def.body.op.clone(),
+                Block::new(vec![Expr::Accessor(Accessor::Ident(hole))]),
+                def.body.id,
+            );
+            Some(Some(Def::new(def.sig.clone(), body)))
+        } else if def.body.block.len() == 1
+            && matches!(def.body.block.first(), Some(Expr::Literal(_)))
+        {
+            Some(Some(def.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn params_are_specified(params: &Params) -> bool {
+        params
+            .non_defaults
+            .iter()
+            .chain(params.var_params.as_deref())
+            .chain(params.defaults.iter().map(|param| &param.sig))
+            .all(|param| {
+                param.t_spec.is_some() || param.inspect().is_some_and(|name| &name[..] == "self")
+            })
+    }
+}
@@ -0,0 +1,335 @@
+//! defines `SourceMap` and `TracebackFilter`.
+//!
+//! A source map is a side-car file (`<filename>.er.map`) emitted alongside the `.pyc` file (with `--source-map`).
+//! It maps the bytecode ranges and the line numbers of the generated code objects to the Erg source spans,
+//! so that Python tracebacks can be translated back to the Erg source locations
+//! (by `erg traceback`, or at runtime by the `_erg_traceback` excepthook injected into the module).
+//!
+//! ```text
+//! # erg-source-map 1
+//! source foo.er
+//! code <module> 1
+//! 0 6 1 1:0-1:5
+//! 6 20 2 2:0-2:12
+//! code f 1
+//! ...
+//! ```
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::error::Location;
+use erg_common::traits::ExitStatus;
+use erg_common::Str;
+
+const HEADER: &str = "# erg-source-map 1";
+
+fn fmt_loc(loc: &Location) -> Option<String> {
+    match loc {
+        Location::Range {
+            ln_begin,
+            col_begin,
+            ln_end,
+            col_end,
+        } => Some(format!("{ln_begin}:{col_begin}-{ln_end}:{col_end}")),
+        Location::LineRange(begin, end) => Some(format!("{begin}-{end}")),
+        Location::Line(line) => Some(format!("{line}")),
+        Location::Unknown => None,
+    }
+}
+
+fn parse_loc(s: &str) -> Option<Location> {
+    match s.split_once('-') {
+        Some((begin, end)) => match (begin.split_once(':'), end.split_once(':')) {
+            (Some((ln_begin, col_begin)), Some((ln_end, col_end))) => Some(Location::range(
+                ln_begin.parse().ok()?,
+                col_begin.parse().ok()?,
+                ln_end.parse().ok()?,
+                col_end.parse().ok()?,
+            )),
+            (None, None) => Some(Location::LineRange(begin.parse().ok()?, end.parse().ok()?)),
+            _ => None,
+        },
+        None => Some(Location::Line(s.parse().ok()?)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMapEntry {
+    /// bytecode offset
+    pub offset: usize,
+    /// the end of the bytecode range (exclusive)
+    pub end: usize,
+    /// the line number reported by Python (decoded from `lnotab`)
+    pub line: u32,
+    pub loc: Location,
+}
+
+impl SourceMapEntry {
+    pub const fn new(offset: usize, end: usize, line: u32, loc: Location) -> Self {
+        Self {
+            offset,
+            end,
+            line,
+            loc,
+        }
+    }
+}
+
+/// source map of a code object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSourceMap {
+    pub name: Str,
+    pub firstlineno: u32,
+    pub entries: Vec<SourceMapEntry>,
+}
+
+impl CodeSourceMap {
+    pub const fn new(name: Str, firstlineno: u32, entries: Vec<SourceMapEntry>) -> Self {
+        Self {
+            name,
+            firstlineno,
+            entries,
+        }
+    }
+
+    /// The outermost expression on the line is returned.
+    pub fn lookup_line(&self, line: u32) -> Option<&Location> {
+        self.entries
+            .iter()
+            .find(|entry| entry.line == line)
+            .map(|entry| &entry.loc)
+    }
+
+    /// The innermost expression which contains the instruction is returned.
+    pub fn lookup_offset(&self, offset: usize) -> Option<&Location> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.offset <= offset && offset < entry.end)
+            .map(|entry| &entry.loc)
+    }
+}
+
+/// NOTE: Code objects of the linked modules are also included in the source map of the main module,
+/// but their spans refer to the linked module files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    pub source: String,
+    pub codes: Vec<CodeSourceMap>,
+}
+
+impl fmt::Display for SourceMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "source {}", self.source)?;
+        for code in self.codes.iter() {
+            writeln!(f, "code {} {}", code.name, code.firstlineno)?;
+            for entry in code.entries.iter() {
+                if let Some(loc) = fmt_loc(&entry.loc) {
+                    writeln!(f, "{} {} {} {loc}", entry.offset, entry.end, entry.line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SourceMap {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            codes: vec![],
+        }
+    }
+
+    pub fn push(&mut self, code: CodeSourceMap) {
+        self.codes.push(code);
+    }
+
+    pub fn parse(src: &str) -> Option<Self> {
+        let mut lines = src.lines();
+        if lines.next()? != HEADER {
+            return None;
+        }
+        let source = lines.next()?.strip_prefix("source ")?;
+        let mut map = Self::new(source);
+        for line in lines {
+            if let Some(code) = line.strip_prefix("code ") {
+                let (name, firstlineno) = code.rsplit_once(' ')?;
+                map.push(CodeSourceMap::new(
+                    Str::rc(name),
+                    firstlineno.parse().ok()?,
+                    vec![],
+                ));
+            } else {
+                let mut fields = line.split(' ');
+                let offset = fields.next()?.parse().ok()?;
+                let end = fields.next()?.parse().ok()?;
+                let lineno = fields.next()?.parse().ok()?;
+                let loc = parse_loc(fields.next()?)?;
+                let code = map.codes.last_mut()?;
+                code.entries
+                    .push(SourceMapEntry::new(offset, end, lineno, loc));
+            }
+        }
+        Some(map)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Option<Self> {
+        Self::parse(&fs::read_to_string(path).ok()?)
+    }
+
+    pub fn dump<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_string().as_bytes())
+    }
+
+    /// Resolves the location of a traceback frame.
+    /// If Python cannot report the line number (e.g. `line -1`), the location of the definition is returned
+    /// (`exact` is `false`).
+    pub fn resolve(&self, name: &str, line: i64) -> Option<(Location, bool)> {
+        let mut codes = self.codes.iter().filter(|code| &code.name[..] == name);
+        if let Ok(line) = u32::try_from(line) {
+            if let Some(loc) = codes.clone().find_map(|code| code.lookup_line(line)) {
+                return Some((*loc, true));
+            }
+        }
+        let code = codes.next()?;
+        // ambiguous (e.g. lambdas), or the module has no definition
+        if codes.next().is_some() || name == "<module>" {
+            return None;
+        }
+        Some((Location::Line(code.firstlineno), false))
+    }
+}
+
+/// a traceback frame: `  File "foo.er", line 3, in f`
+struct Frame<'a> {
+    file: &'a str,
+    line: i64,
+    name: &'a str,
+}
+
+impl<'a> Frame<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let rest = line.trim_start().strip_prefix("File \"")?;
+        let (file, rest) = rest.split_once("\", line ")?;
+        let (line, name) = rest.split_once(", in ")?;
+        Some(Self {
+            file,
+            line: line.parse().ok()?,
+            name: name.trim_end(),
+        })
+    }
+}
+
+/// Translates the Python tracebacks of the compiled Erg modules back to the Erg source locations.
+///
+/// ```sh
+/// erg compile --source-map foo.er
+/// python3 foo.pyc 2>&1 | erg traceback
+/// ```
+#[derive(Debug, Default)]
+pub struct TracebackFilter {
+    maps: Dict<PathBuf, Option<SourceMap>>,
+}
+
+impl TracebackFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn source_map(&mut self, file: &str) -> Option<&SourceMap> {
+        let path = PathBuf::from(format!("{file}.map"));
+        if !self.maps.contains_key(&path) {
+            let map = SourceMap::load(&path);
+            self.maps.insert(path.clone(), map);
+        }
+        self.maps.get(&path)?.as_ref()
+    }
+
+    fn source_line(source: &str, line: u32) -> Option<String> {
+        let src = fs::read_to_string(source).ok()?;
+        src.lines()
+            .nth(line.checked_sub(1)? as usize)
+            .map(|s| s.to_string())
+    }
+
+    fn translate_frame(&mut self, frame: &Frame) -> Option<String> {
+        let map = self.source_map(frame.file)?;
+        let (loc, exact) = map.resolve(frame.name, frame.line)?;
+        let ln_begin = loc.ln_begin()?;
+        let mut translated = format!(
+            "  File \"{}\", line {ln_begin}, in {}",
+            map.source, frame.name
+        );
+        if !exact {
+            translated.push_str(" (the exact line is unknown)");
+        }
+        if let Some(src_line) = Self::source_line(&map.source, ln_begin) {
+            let indent = src_line.len() - src_line.trim_start().len();
+            translated.push_str(&format!("\n    {}", src_line.trim()));
+            if let (Some(col_begin), Some(col_end), Some(ln_end)) =
+                (loc.col_begin(), loc.col_end(), loc.ln_end())
+            {
+                let col_begin = (col_begin as usize).saturating_sub(indent);
+                let col_end = if ln_end == ln_begin {
+                    (col_end as usize).saturating_sub(indent)
+                } else {
+                    src_line.trim().len()
+                };
+                if exact && col_end > col_begin {
+                    translated.push_str(&format!(
+                        "\n    {}{}",
+                        " ".repeat(col_begin),
+                        "^".repeat(col_end - col_begin)
+                    ));
+                }
+            }
+        }
+        Some(translated)
+    }
+
+    pub fn translate(&mut self, traceback: &str) -> String {
+        let mut translated = vec![];
+        // the source lines printed by Python may be incorrect
+        let mut skip_source = false;
+        for line in traceback.lines() {
+            if skip_source && line.starts_with("    ") {
+                continue;
+            }
+            skip_source = false;
+            match Frame::parse(line).and_then(|frame| self.translate_frame(&frame)) {
+                Some(frame) => {
+                    translated.push(frame);
+                    skip_source = true;
+                }
+                None => translated.push(line.to_string()),
+            }
+        }
+        let mut translated = translated.join("\n");
+        if traceback.ends_with('\n') {
+            translated.push('\n');
+        }
+        translated
+    }
+
+    pub fn run(mut cfg: ErgConfig) -> ExitStatus {
+        let traceback = if cfg.input.is_repl() {
+            let mut buf = String::new();
+            if let Err(err) = io::stdin().read_to_string(&mut buf) {
+                eprintln!("failed to read the traceback: {err}");
+                return ExitStatus::ERR1;
+            }
+            buf
+        } else {
+            cfg.input.read()
+        };
+        print!("{}", Self::new().translate(&traceback));
+        ExitStatus::OK
+    }
+}
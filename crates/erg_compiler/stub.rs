@@ -0,0 +1,984 @@
+//! generates Python stub files (`.pyi`) from checked modules,
+//! and converts Python stub files / modules to Erg declaration files (`.d.er`).
+//!
+//! 型検査済みのモジュールからPythonのスタブファイル(.pyi)を生成する
+//! また、Pythonのスタブファイル・モジュールを宣言ファイル(.d.er)に変換する
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::python_util::opt_which_python;
+use erg_common::set::Set;
+use erg_common::traits::{ExitStatus, Locational};
+use erg_common::Str;
+
+use erg_parser::ast::VarName;
+
+use crate::context::{ClassDefType, Context};
+use crate::ty::typaram::TyParam;
+use crate::ty::{ParamTy, SubrType, Type};
+use crate::varinfo::{VarInfo, VarKind};
+
+fn escape_name(name: &str) -> String {
+    name.replace('!', "__erg_proc__")
+        .replace('$', "__erg_shared__")
+}
+
+/// `<module>::C` -> `C`, `<module>.C` -> `C`, `http.client.Response` -> `http.client.Response`
+fn local_name(name: &str) -> &str {
+    if name.starts_with("<module>") || name.contains("::") {
+        name.rsplit(['.', ':']).next().unwrap_or(name)
+    } else {
+        name
+    }
+}
+
+/// `0` -> `T`, `1` -> `U`, ..., `7` -> `T1`
+fn tyvar_name(n: usize) -> String {
+    const NAMES: [&str; 7] = ["T", "U", "V", "W", "X", "Y", "Z"];
+    if n < NAMES.len() {
+        NAMES[n].to_string()
+    } else {
+        format!("{}{}", NAMES[n % NAMES.len()], n / NAMES.len())
+    }
+}
+
+/// Generates a `.pyi` stub describing the public API of a module.
+/// Erg types are converted to PEP 604 (`X | Y`) / PEP 695 (`def f[T](x: T) -> T`) annotations.
+#[derive(Debug, Default)]
+pub struct PyStubGenerator {
+    level: usize,
+    /// renaming of anonymous type variables (e.g. `%2` -> `T`) in the current definition
+    tyvar_names: Dict<Str, String>,
+    typing_imports: Set<&'static str>,
+    abc_imports: Set<&'static str>,
+}
+
+impl PyStubGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn generate(mut self, ctx: &Context) -> String {
+        let mut code = String::new();
+        let mut vars = ctx
+            .locals
+            .iter()
+            .chain(ctx.decls.iter())
+            .filter(|(name, vi)| vi.vis.is_public() && !name.inspect().starts_with('%'))
+            .collect::<Vec<_>>();
+        vars.sort_by(|(l, _), (r, _)| l.loc().cmp(&r.loc()).then(l.inspect().cmp(r.inspect())));
+        for (name, vi) in vars {
+            code += &self.gen_var(ctx, name, vi);
+        }
+        let mut prelude = String::new();
+        if !self.abc_imports.is_empty() {
+            let imports = self.abc_imports.iter().copied().collect::<Vec<_>>();
+            prelude += &format!("from collections.abc import {}\n", imports.join(", "));
+        }
+        if !self.typing_imports.is_empty() {
+            let imports = self.typing_imports.iter().copied().collect::<Vec<_>>();
+            prelude += &format!("from typing import {}\n", imports.join(", "));
+        }
+        if !prelude.is_empty() {
+            prelude.push('\n');
+        }
+        prelude + &code
+    }
+
+    pub fn dump<P: AsRef<Path>>(self, ctx: &Context, path: P) -> std::io::Result<()> {
+        let mut f = File::create(path)?;
+        f.write_all(self.generate(ctx).as_bytes())
+    }
+
+    fn indent(&self) -> String {
+        "    ".repeat(self.level)
+    }
+
+    fn gen_var(&mut self, ctx: &Context, name: &VarName, vi: &VarInfo) -> String {
+        let py_name = vi
+            .py_name
+            .as_ref()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| escape_name(name.inspect()));
+        if let Some((_, class_ctx)) = ctx.mono_types.get(name.inspect()) {
+            return self.gen_class(&py_name, class_ctx);
+        }
+        match &vi.t {
+            Type::Subr(subr) => self.gen_def(&py_name, subr, &Set::new(), false),
+            Type::Quantified(quant) => match quant.as_ref() {
+                Type::Subr(subr) => {
+                    let qvars = vi.t.qvars().into_iter().map(|(name, _)| name).collect();
+                    self.gen_def(&py_name, subr, &qvars, false)
+                }
+                _ => format!("{}{py_name}: {}\n", self.indent(), self.gen_type(&vi.t)),
+            },
+            t => format!("{}{py_name}: {}\n", self.indent(), self.gen_type(t)),
+        }
+    }
+
+    fn gen_class(&mut self, py_name: &str, class_ctx: &Context) -> String {
+        let bases = class_ctx
+            .super_classes
+            .iter()
+            .filter(|sup| sup != &&Type::Obj)
+            .map(|sup| self.gen_type(sup))
+            .collect::<Vec<_>>();
+        let mut code = if bases.is_empty() {
+            format!("{}class {py_name}:\n", self.indent())
+        } else {
+            format!("{}class {py_name}({}):\n", self.indent(), bases.join(", "))
+        };
+        self.level += 1;
+        let mut body = String::new();
+        let mut attrs = class_ctx
+            .decls
+            .iter()
+            .filter(|(_, vi)| vi.vis.is_public() && matches!(vi.kind, VarKind::InstanceAttr))
+            .collect::<Vec<_>>();
+        attrs.sort_by(|(l, _), (r, _)| l.inspect().cmp(r.inspect()));
+        for (name, vi) in attrs {
+            body += &format!(
+                "{}{}: {}\n",
+                self.indent(),
+                escape_name(name.inspect()),
+                self.gen_type(&vi.t)
+            );
+        }
+        for (def_t, methods) in class_ctx.methods_list.iter() {
+            if let ClassDefType::ImplTrait { .. } = def_t {
+                continue;
+            }
+            let mut methods = methods
+                .locals
+                .iter()
+                .chain(methods.decls.iter())
+                .filter(|(_, vi)| vi.vis.is_public())
+                .collect::<Vec<_>>();
+            methods.sort_by_key(|(l, _)| l.loc());
+            for (name, vi) in methods {
+                // constructors are represented by the instance attributes
+                if matches!(&name.inspect()[..], "new" | "__new__" | "__init__") {
+                    continue;
+                }
+                let py_name = escape_name(name.inspect());
+                let qvars = vi.t.qvars().into_iter().map(|(name, _)| name).collect();
+                let t = match &vi.t {
+                    Type::Quantified(quant) => quant.as_ref(),
+                    t => t,
+                };
+                match t {
+                    Type::Subr(subr) => {
+                        let is_method = subr.self_t().is_some();
+                        body += &self.gen_def(&py_name, subr, &qvars, is_method);
+                    }
+                    t => {
+                        body += &format!("{}{py_name}: {}\n", self.indent(), self.gen_type(t));
+                    }
+                }
+            }
+        }
+        if body.is_empty() {
+            body = format!("{}...\n", self.indent());
+        }
+        self.level -= 1;
+        code += &body;
+        code
+    }
+
+    fn gen_def(
+        &mut self,
+        py_name: &str,
+        subr: &SubrType,
+        qvars: &Set<Str>,
+        is_method: bool,
+    ) -> String {
+        self.tyvar_names = qvars
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), tyvar_name(i)))
+            .collect();
+        let mut params = vec![];
+        let mut non_defaults = subr.non_default_params.iter();
+        if is_method {
+            non_defaults.next();
+            params.push("self".to_string());
+        }
+        let mut positional_only = false;
+        for (i, pt) in non_defaults.enumerate() {
+            match pt {
+                ParamTy::Pos(t) => {
+                    positional_only = true;
+                    params.push(format!("_{i}: {}", self.gen_type(t)));
+                }
+                ParamTy::Kw { name, ty } | ParamTy::KwWithDefault { name, ty, .. } => {
+                    if positional_only {
+                        params.push("/".into());
+                        positional_only = false;
+                    }
+                    params.push(format!("{}: {}", escape_name(name), self.gen_type(ty)));
+                }
+            }
+        }
+        if positional_only {
+            params.push("/".into());
+        }
+        if let Some(var_params) = &subr.var_params {
+            let name = var_params.name().map_or("args".into(), |n| escape_name(n));
+            params.push(format!("*{name}: {}", self.gen_type(var_params.typ())));
+        }
+        for pt in subr.default_params.iter() {
+            let name = pt.name().map_or("_".into(), |n| escape_name(n));
+            params.push(format!("{name}: {} = ...", self.gen_type(pt.typ())));
+        }
+        let return_t = self.gen_type(&subr.return_t);
+        let typarams = if qvars.is_empty() {
+            "".to_string()
+        } else {
+            let qvars = qvars
+                .iter()
+                .map(|name| self.tyvar_name(name))
+                .collect::<Vec<_>>();
+            format!("[{}]", qvars.join(", "))
+        };
+        self.tyvar_names.clear();
+        format!(
+            "{}def {py_name}{typarams}({}) -> {return_t}: ...\n",
+            self.indent(),
+            params.join(", "),
+        )
+    }
+
+    fn tyvar_name(&self, name: &Str) -> String {
+        self.tyvar_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    fn gen_typaram(&mut self, tp: &TyParam) -> String {
+        match tp {
+            TyParam::Type(t) => self.gen_type(t),
+            TyParam::Value(val) => match <&Type>::try_from(val) {
+                Ok(t) => self.gen_type(t),
+                Err(_) => "object".into(),
+            },
+            TyParam::FreeVar(fv) if fv.is_linked() => self.gen_typaram(&fv.crack()),
+            TyParam::FreeVar(fv) => fv
+                .unbound_name()
+                .map_or("object".into(), |name| self.tyvar_name(&name)),
+            _ => "object".into(),
+        }
+    }
+
+    /// Converts an Erg type to a Python type annotation.
+    pub fn gen_type(&mut self, t: &Type) -> String {
+        match t {
+            Type::Obj | Type::Inf | Type::NegInf | Type::Code | Type::Frame => "object".into(),
+            Type::Int | Type::Nat => "int".into(),
+            Type::Ratio | Type::Float => "float".into(),
+            Type::Complex => "complex".into(),
+            Type::Bool | Type::Guard(_) => "bool".into(),
+            Type::Str => "str".into(),
+            Type::NoneType => "None".into(),
+            Type::Type | Type::ClassType | Type::TraitType | Type::Patch => "type".into(),
+            Type::Never | Type::Failure => {
+                self.typing_imports.insert("NoReturn");
+                "NoReturn".into()
+            }
+            Type::Mono(name) => match name.trim_end_matches('!') {
+                "Int" | "Nat" => "int".into(),
+                "Float" | "Ratio" => "float".into(),
+                "Bool" => "bool".into(),
+                "Str" => "str".into(),
+                "Bytes" => "bytes".into(),
+                name => local_name(name).to_string(),
+            },
+            Type::Poly { name, params } => {
+                let params = params
+                    .iter()
+                    .map(|tp| self.gen_typaram(tp))
+                    .collect::<Vec<_>>();
+                match name.trim_end_matches('!') {
+                    "Array" => format!("list[{}]", params[0]),
+                    "Set" => format!("set[{}]", params[0]),
+                    "Tuple" => match t.typarams().first() {
+                        Some(TyParam::Array(ts) | TyParam::Tuple(ts)) => {
+                            let ts = ts.iter().map(|tp| self.gen_typaram(tp)).collect::<Vec<_>>();
+                            if ts.is_empty() {
+                                "tuple[()]".into()
+                            } else {
+                                format!("tuple[{}]", ts.join(", "))
+                            }
+                        }
+                        _ => "tuple".into(),
+                    },
+                    "Dict" => match t.typarams().first() {
+                        Some(TyParam::Dict(dict)) if dict.len() == 1 => {
+                            let (k, v) = dict.iter().next().unwrap();
+                            format!("dict[{}, {}]", self.gen_typaram(k), self.gen_typaram(v))
+                        }
+                        _ => "dict".into(),
+                    },
+                    "Range" => "range".into(),
+                    name @ ("Iterable" | "Iterator" | "Sequence" | "Mapping") => {
+                        self.abc_imports.insert(match name {
+                            "Iterable" => "Iterable",
+                            "Iterator" => "Iterator",
+                            "Sequence" => "Sequence",
+                            _ => "Mapping",
+                        });
+                        format!("{name}[{}]", params.join(", "))
+                    }
+                    name => local_name(name).to_string(),
+                }
+            }
+            Type::Ref(t) | Type::RefMut { before: t, .. } => self.gen_type(t),
+            Type::Subr(subr) => {
+                self.abc_imports.insert("Callable");
+                let params = subr
+                    .non_default_params
+                    .iter()
+                    .chain(subr.default_params.iter())
+                    .map(|pt| self.gen_type(pt.typ()))
+                    .collect::<Vec<_>>();
+                format!(
+                    "Callable[[{}], {}]",
+                    params.join(", "),
+                    self.gen_type(&subr.return_t)
+                )
+            }
+            Type::Callable { param_ts, return_t } => {
+                self.abc_imports.insert("Callable");
+                let params = param_ts
+                    .iter()
+                    .map(|t| self.gen_type(t))
+                    .collect::<Vec<_>>();
+                format!(
+                    "Callable[[{}], {}]",
+                    params.join(", "),
+                    self.gen_type(return_t)
+                )
+            }
+            Type::Refinement(refine) => self.gen_type(&refine.t),
+            Type::Quantified(t) => self.gen_type(t),
+            Type::Or(l, r) => format!("{} | {}", self.gen_type(l), self.gen_type(r)),
+            // Python has no intersection types
+            Type::And(l, _) => self.gen_type(l),
+            Type::Bounded { sup, .. } => self.gen_type(sup),
+            Type::FreeVar(fv) if fv.is_linked() => self.gen_type(&fv.crack()),
+            Type::FreeVar(fv) => fv
+                .unbound_name()
+                .map_or("object".into(), |name| self.tyvar_name(&name)),
+            _ => "object".into(),
+        }
+    }
+}
+
+/// Converts a Python stub file (`.pyi`) to an Erg declaration file (`.d.er`).
+/// Unsupported annotations are degraded to `Obj`.
+///
+/// ```python
+/// def f(x: int, y: str = ...) -> list[int]: ...
+/// ```
+/// ↓
+/// ```erg
+/// .f: (x: Int, y := Str) -> [Int; _]
+/// ```
+#[derive(Debug, Default)]
+pub struct PyiConverter {
+    /// type variables declared with `TypeVar`
+    tyvars: Set<String>,
+    /// classes defined in the stub
+    classes: Set<String>,
+}
+
+/// A logical line of a Python stub
+#[derive(Debug)]
+struct PyiLine {
+    indent: usize,
+    text: String,
+}
+
+/// A function signature converted to Erg
+#[derive(Debug)]
+struct PyiSignature {
+    tyvars: Vec<String>,
+    params: Vec<String>,
+    ret: String,
+}
+
+impl fmt::Display for PyiSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.tyvars.is_empty() {
+            write!(f, "|{}|", self.tyvars.join(", "))?;
+        }
+        write!(f, "({}) -> {}", self.params.join(", "), self.ret)
+    }
+}
+
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut depth = 0i32;
+    let mut in_str = None;
+    let mut start = 0;
+    let mut res = vec![];
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_str.is_some() => escaped = true,
+            '"' | '\'' if in_str == Some(c) => in_str = None,
+            '"' | '\'' if in_str.is_none() => in_str = Some(c),
+            _ if in_str.is_some() => {}
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ if c == sep && depth == 0 => {
+                res.push(s[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    let last = s[start..].trim();
+    if !last.is_empty() {
+        res.push(last);
+    }
+    res
+}
+
+/// `'a'` -> `"a"` (Erg has no single-quoted strings)
+fn convert_literal(lit: &str) -> String {
+    let Some(quote) = lit.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+        return lit.to_string();
+    };
+    let Some(content) = lit[1..].strip_suffix(quote) else {
+        return lit.to_string();
+    };
+    let mut res = String::from('"');
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\'') => res.push('\''),
+                Some(c) => {
+                    res.push('\\');
+                    res.push(c);
+                }
+                None => res.push('\\'),
+            },
+            '"' => res.push_str("\\\""),
+            _ => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// `list[int]` -> `("list", Some("int"))`
+fn split_subscript(s: &str) -> (&str, Option<&str>) {
+    match (s.find('['), s.ends_with(']')) {
+        (Some(i), true) => (s[..i].trim(), Some(&s[i + 1..s.len() - 1])),
+        _ => (s, None),
+    }
+}
+
+impl PyiConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn convert(mut self, src: &str) -> String {
+        let lines = Self::logical_lines(src);
+        for line in lines.iter() {
+            if line.indent != 0 {
+                continue;
+            }
+            if let Some(class) = line.text.strip_prefix("class ") {
+                let name = class
+                    .split(['(', ':', '['])
+                    .next()
+                    .unwrap_or_default()
+                    .trim();
+                self.classes.insert(name.to_string());
+            } else if let Some((name, rhs)) = line.text.split_once('=') {
+                if rhs.trim().starts_with("TypeVar(") {
+                    self.tyvars.insert(name.trim().to_string());
+                }
+            }
+        }
+        let mut code = String::new();
+        // (indent, name, end of the `.C.` header)
+        let mut class: Option<(usize, String, usize)> = None;
+        let mut decorators = vec![];
+        let mut defined = Set::new();
+        for line in lines.iter() {
+            if let Some((indent, _, header_end)) = &class {
+                if line.indent <= *indent {
+                    Self::remove_empty_block(&mut code, *header_end);
+                    class = None;
+                }
+            }
+            let text = &line.text[..];
+            if let Some(deco) = text.strip_prefix('@') {
+                decorators.push(deco.trim().to_string());
+                continue;
+            }
+            let decos = std::mem::take(&mut decorators);
+            if text.starts_with("import ") || text.starts_with("from ") || text == "..." {
+                continue;
+            }
+            let prefix = if class.is_some() { "    " } else { "." };
+            let owner = class.as_ref().map(|(_, name, _)| name.clone());
+            if let Some(class_def) = text.strip_prefix("class ") {
+                if class.is_some() {
+                    // nested classes are not supported
+                    continue;
+                }
+                let (name, bases) = match class_def.split_once('(') {
+                    Some((name, rest)) => (name.trim(), rest.rsplit_once(')').map(|(b, _)| b)),
+                    None => (class_def.trim_end_matches(':').trim(), None),
+                };
+                let name = name.split('[').next().unwrap_or_default().trim();
+                code += &format!(".{name}: ClassType\n");
+                for base in bases.map(|b| split_top_level(b, ',')).unwrap_or_default() {
+                    if self.classes.contains(base) {
+                        code += &format!(".{name} <: .{base}\n");
+                    }
+                }
+                code += &format!(".{name}.\n");
+                class = Some((line.indent, name.to_string(), code.len()));
+                continue;
+            }
+            if let Some(def) = text
+                .strip_prefix("def ")
+                .or_else(|| text.strip_prefix("async def "))
+            {
+                let Some((name, _)) = def.split_once(['(', '[']) else {
+                    continue;
+                };
+                let name = name.trim();
+                // only the first signature of overloaded functions is used
+                let key = format!("{}.{name}", owner.as_deref().unwrap_or_default());
+                if !defined.insert(key) || (name.starts_with('_') && !name.starts_with("__")) {
+                    continue;
+                }
+                let is_property = decos.iter().any(|d| d == "property");
+                let is_static = decos.iter().any(|d| d == "staticmethod");
+                let Some(mut sig) = self.convert_def(def, owner.as_deref(), is_static) else {
+                    continue;
+                };
+                if is_property {
+                    code += &format!("{prefix}{name}: {}\n", sig.ret);
+                } else if let (Some(owner), "__init__") = (&owner, name) {
+                    // `__init__(self, ...)` -> `__call__: (...) -> .C`
+                    if !sig.params.is_empty() {
+                        sig.params.remove(0);
+                    }
+                    sig.ret = format!(".{owner}");
+                    code += &format!("{prefix}__call__: {sig}\n");
+                } else {
+                    code += &format!("{prefix}{name}: {sig}\n");
+                }
+                continue;
+            }
+            if let Some((name, t)) = text.split_once(':') {
+                let name = name.trim();
+                if !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+                    || (name.starts_with('_') && !name.starts_with("__"))
+                {
+                    continue;
+                }
+                let t = t.split_once('=').map_or(t, |(t, _)| t);
+                let t = self.convert_type(t.trim());
+                code += &format!("{prefix}{name}: {t}\n");
+            }
+        }
+        if let Some((_, _, header_end)) = class {
+            Self::remove_empty_block(&mut code, header_end);
+        }
+        code
+    }
+
+    /// Removes the `.C.` header if the class has no members (an empty block is a syntax error).
+    fn remove_empty_block(code: &mut String, header_end: usize) {
+        if code.len() == header_end {
+            let header_start = code[..header_end - 1].rfind('\n').map_or(0, |i| i + 1);
+            code.truncate(header_start);
+        }
+    }
+
+    /// Splits `src` into logical lines (bracketed multi-line expressions are joined).
+    fn logical_lines(src: &str) -> Vec<PyiLine> {
+        let mut lines = vec![];
+        let mut depth = 0i32;
+        let mut current: Option<PyiLine> = None;
+        for raw in src.lines() {
+            let code = match raw.find('#') {
+                Some(i) if !raw[..i].contains(['"', '\'']) => &raw[..i],
+                _ => raw,
+            };
+            if code.trim().is_empty() {
+                continue;
+            }
+            for c in code.chars() {
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    _ => {}
+                }
+            }
+            if let Some(line) = current.as_mut() {
+                line.text.push(' ');
+                line.text.push_str(code.trim());
+            } else {
+                let indent = code.len() - code.trim_start().len();
+                current = Some(PyiLine {
+                    indent,
+                    text: code.trim().to_string(),
+                });
+            }
+            if depth <= 0 {
+                depth = 0;
+                lines.extend(current.take());
+            }
+        }
+        lines.extend(current);
+        lines
+    }
+
+    /// `f(x: int, y: str = ...) -> bool: ...` -> `(x: Int, y := Str) -> Bool`
+    fn convert_def(&self, def: &str, owner: Option<&str>, is_static: bool) -> Option<PyiSignature> {
+        let open = def.find('(')?;
+        let close = def.rfind(')')?;
+        let mut tyvars = vec![];
+        // PEP 695: def f[T](x: T) -> T
+        if let Some((_, typarams)) = def[..open].split_once('[') {
+            for tv in split_top_level(typarams.trim_end_matches(']'), ',') {
+                tyvars.push(tv.split(':').next().unwrap_or_default().trim().to_string());
+            }
+        }
+        let mut params = vec![];
+        for (i, param) in split_top_level(&def[open + 1..close], ',')
+            .into_iter()
+            .enumerate()
+        {
+            if param == "/" || param == "*" || param.starts_with("**") {
+                continue;
+            }
+            let (lhs, default) = match param.split_once('=') {
+                Some((lhs, _)) => (lhs.trim(), true),
+                None => (param, false),
+            };
+            let (name, t) = match lhs.split_once(':') {
+                Some((name, t)) => (name.trim(), Some(self.convert_type(t.trim()))),
+                None => (lhs.trim(), None),
+            };
+            let t = match (i, owner, t) {
+                (0, Some(owner), None) if !is_static && (name == "self" || name == "cls") => {
+                    format!(".{owner}")
+                }
+                (_, _, Some(t)) => t,
+                _ => "Obj".into(),
+            };
+            self.collect_tyvars(&t, &mut tyvars);
+            if let Some(name) = name.strip_prefix('*') {
+                params.push(format!("*{name}: {t}"));
+            } else if default {
+                params.push(format!("{name} := {t}"));
+            } else {
+                params.push(format!("{name}: {t}"));
+            }
+        }
+        let ret = def[close + 1..]
+            .trim()
+            .strip_prefix("->")
+            .map(|ret| ret.rsplit_once(':').map_or(ret, |(ret, _)| ret))
+            .map_or("Obj".into(), |ret| self.convert_type(ret.trim()));
+        self.collect_tyvars(&ret, &mut tyvars);
+        Some(PyiSignature {
+            tyvars,
+            params,
+            ret,
+        })
+    }
+
+    fn collect_tyvars(&self, t: &str, tyvars: &mut Vec<String>) {
+        for word in t.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+            if self.tyvars.contains(word) && !tyvars.iter().any(|tv| tv == word) {
+                tyvars.push(word.to_string());
+            }
+        }
+    }
+
+    /// Converts a Python type annotation to an Erg type specification.
+    pub fn convert_type(&self, t: &str) -> String {
+        let t = t.trim().trim_matches(|c| c == '"' || c == '\'');
+        let union = split_top_level(t, '|');
+        if union.len() > 1 {
+            let ts = union
+                .into_iter()
+                .map(|t| self.convert_type(t))
+                .collect::<Vec<_>>();
+            return ts.join(" or ");
+        }
+        let (name, args) = split_subscript(t);
+        let args = args.map_or(vec![], |args| split_top_level(args, ','));
+        let name = name
+            .trim_start_matches("typing.")
+            .trim_start_matches("builtins.");
+        let name = name.trim_start_matches("collections.abc.");
+        match (name, &args[..]) {
+            ("int", _) => "Int".into(),
+            ("float", _) => "Float".into(),
+            ("complex", _) => "Complex".into(),
+            ("str", _) => "Str".into(),
+            ("bool", _) => "Bool".into(),
+            ("bytes", _) => "Bytes".into(),
+            ("None" | "NoneType", _) => "NoneType".into(),
+            ("NoReturn" | "Never", _) => "Never".into(),
+            ("type" | "Type", _) => "Type".into(),
+            ("list" | "List", [elem]) => format!("[{}; _]", self.convert_type(elem)),
+            ("set" | "Set", [elem]) => format!("{{{}; _}}", self.convert_type(elem)),
+            ("dict" | "Dict", [k, v]) => {
+                format!("{{{}: {}}}", self.convert_type(k), self.convert_type(v))
+            }
+            // a variadic tuple is treated as an array of unknown length
+            ("tuple" | "Tuple", [elem, "..."]) => format!("[{}; _]", self.convert_type(elem)),
+            ("tuple" | "Tuple", ["()"]) => "()".into(),
+            ("tuple" | "Tuple", []) => "GenericTuple".into(),
+            ("tuple" | "Tuple", ts) => {
+                let ts = ts.iter().map(|t| self.convert_type(t)).collect::<Vec<_>>();
+                format!("({},)", ts.join(", "))
+            }
+            ("Optional", [t]) => format!("{} or NoneType", self.convert_type(t)),
+            ("Union", ts) if !ts.is_empty() => {
+                let ts = ts.iter().map(|t| self.convert_type(t)).collect::<Vec<_>>();
+                ts.join(" or ")
+            }
+            ("Callable", [params, ret]) => {
+                let params = params.trim().trim_start_matches('[').trim_end_matches(']');
+                let params = split_top_level(params, ',')
+                    .into_iter()
+                    .map(|t| self.convert_type(t))
+                    .collect::<Vec<_>>();
+                format!("({}) -> {}", params.join(", "), self.convert_type(ret))
+            }
+            ("Literal", lits) if !lits.is_empty() => {
+                let lits = lits
+                    .iter()
+                    .map(|lit| convert_literal(lit))
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", lits.join(", "))
+            }
+            (name @ ("Iterable" | "Iterator" | "Sequence"), [elem]) => {
+                format!("{name}({})", self.convert_type(elem))
+            }
+            (name, []) if self.tyvars.contains(name) => name.to_string(),
+            (name, []) if self.classes.contains(name) => format!(".{name}"),
+            _ => "Obj".into(),
+        }
+    }
+}
+
+/// Prints a `.pyi`-like skeleton of the public surface of the module `sys.argv[1]`.
+/// Missing annotations are inferred from literals where possible.
+const PY_SKELETON_SCRIPT: &str = r#"
+import ast, sys
+
+def is_public(name):
+    return not name.startswith('_') or (name.startswith('__') and name.endswith('__'))
+
+def literal_type(node):
+    if isinstance(node, ast.Constant):
+        return 'None' if node.value is None else type(node.value).__name__
+    if isinstance(node, (ast.List, ast.Set)):
+        elems = {literal_type(elt) for elt in node.elts}
+        kind = 'list' if isinstance(node, ast.List) else 'set'
+        return f'{kind}[{elems.pop()}]' if len(elems) == 1 and None not in elems else None
+    if isinstance(node, ast.Dict):
+        keys = {literal_type(k) for k in node.keys if k is not None}
+        values = {literal_type(v) for v in node.values}
+        if len(keys) == 1 and len(values) == 1 and None not in keys | values:
+            return f'dict[{keys.pop()}, {values.pop()}]'
+    if isinstance(node, ast.Tuple) and node.elts:
+        elems = [literal_type(elt) for elt in node.elts]
+        return None if None in elems else f'tuple[{", ".join(elems)}]'
+    return None
+
+def annot(node, value=None):
+    if node is not None:
+        return ast.unparse(node)
+    return literal_type(value) if value is not None else None
+
+def return_type(func):
+    if func.returns is not None:
+        return ast.unparse(func.returns)
+    rets = set()
+    nodes = list(func.body)
+    while nodes:
+        node = nodes.pop()
+        if isinstance(node, (ast.FunctionDef, ast.AsyncFunctionDef, ast.ClassDef, ast.Lambda)):
+            continue
+        if isinstance(node, (ast.Yield, ast.YieldFrom, ast.Await)):
+            return None
+        if isinstance(node, ast.Return):
+            rets.add(annot(None, node.value) if node.value is not None else 'None')
+        nodes.extend(ast.iter_child_nodes(node))
+    if not rets:
+        return 'None'
+    return rets.pop() if len(rets) == 1 and None not in rets else None
+
+def param(arg, default=None):
+    t = annot(arg.annotation, default)
+    t = None if t == 'None' else t
+    res = arg.arg if t is None else f'{arg.arg}: {t}'
+    return res if default is None else f'{res} = ...'
+
+def signature(func):
+    args = func.args
+    posargs = args.posonlyargs + args.args
+    defaults = [None] * (len(posargs) - len(args.defaults)) + args.defaults
+    params = [param(arg, d) for arg, d in zip(posargs, defaults)]
+    if args.vararg:
+        params.append('*' + param(args.vararg))
+    elif args.kwonlyargs:
+        params.append('*')
+    params += [param(arg, d) for arg, d in zip(args.kwonlyargs, args.kw_defaults)]
+    if args.kwarg:
+        params.append('**' + param(args.kwarg))
+    ret = return_type(func)
+    ret = '' if ret is None else f' -> {ret}'
+    return f'{func.name}({", ".join(params)}){ret}: ...'
+
+def emit_def(func, indent):
+    for deco in func.decorator_list:
+        if isinstance(deco, ast.Name) and deco.id in ('property', 'staticmethod', 'classmethod'):
+            print(f'{indent}@{deco.id}')
+    print(f'{indent}def {signature(func)}')
+
+def assigns(stmt):
+    if isinstance(stmt, ast.AnnAssign) and isinstance(stmt.target, ast.Name):
+        yield stmt.target.id, annot(stmt.annotation)
+    elif isinstance(stmt, ast.Assign):
+        for target in stmt.targets:
+            if isinstance(target, ast.Name):
+                yield target.id, annot(None, stmt.value)
+
+def self_attrs(init):
+    params = {arg.arg: ast.unparse(arg.annotation) for arg in init.args.args if arg.annotation}
+    for stmt in ast.walk(init):
+        if isinstance(stmt, ast.AnnAssign):
+            targets, t = [stmt.target], annot(stmt.annotation)
+        elif isinstance(stmt, ast.Assign):
+            targets, t = stmt.targets, annot(None, stmt.value)
+            if isinstance(stmt.value, ast.Name):
+                t = params.get(stmt.value.id)
+        else:
+            continue
+        for target in targets:
+            if isinstance(target, ast.Attribute) and isinstance(target.value, ast.Name) and target.value.id == 'self':
+                yield target.attr, t
+
+def emit_class(cls):
+    bases = ', '.join(ast.unparse(base) for base in cls.bases)
+    print(f'class {cls.name}({bases}):' if bases else f'class {cls.name}:')
+    members = {}
+    for stmt in cls.body:
+        for name, t in assigns(stmt):
+            members.setdefault(name, t)
+        if isinstance(stmt, (ast.FunctionDef, ast.AsyncFunctionDef)) and stmt.name == '__init__':
+            for name, t in self_attrs(stmt):
+                members.setdefault(name, t)
+    for name, t in members.items():
+        if is_public(name):
+            print(f'    {name}: {t or "object"}')
+    for stmt in cls.body:
+        if isinstance(stmt, (ast.FunctionDef, ast.AsyncFunctionDef)) and is_public(stmt.name):
+            emit_def(stmt, '    ')
+    print('    ...')
+
+with open(sys.argv[1], encoding='utf-8') as f:
+    tree = ast.parse(f.read())
+exports = None
+for stmt in tree.body:
+    for name, _ in assigns(stmt):
+        if name == '__all__' and isinstance(stmt.value, (ast.List, ast.Tuple)):
+            exports = {elt.value for elt in stmt.value.elts if isinstance(elt, ast.Constant)}
+def exported(name):
+    return name in exports if exports is not None else is_public(name) and not name.startswith('__')
+for stmt in tree.body:
+    if isinstance(stmt, ast.ClassDef) and exported(stmt.name):
+        emit_class(stmt)
+    elif isinstance(stmt, (ast.FunctionDef, ast.AsyncFunctionDef)) and exported(stmt.name):
+        emit_def(stmt, '')
+    else:
+        for name, t in assigns(stmt):
+            if exported(name):
+                print(f'{name}: {t or "object"}')
+"#;
+
+/// Generates a skeleton declaration file (`.d.er`) from the public surface of a Python module.
+/// The module is not executed; its AST is inspected by the Python interpreter,
+/// and the resulting stub is converted by `PyiConverter`.
+#[derive(Debug)]
+pub struct PyDeclGenerator {
+    cfg: ErgConfig,
+}
+
+impl PyDeclGenerator {
+    pub fn new(cfg: ErgConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// Returns the generated `.d.er` code.
+    pub fn generate(&self, path: &Path) -> Result<String, String> {
+        let py_command = match self.cfg.py_command {
+            Some(py_command) => py_command.to_string(),
+            None => opt_which_python()?,
+        };
+        let out = Command::new(py_command)
+            .arg("-c")
+            .arg(PY_SKELETON_SCRIPT)
+            .arg(path)
+            .output()
+            .map_err(|err| err.to_string())?;
+        if !out.status.success() {
+            return Err(String::from_utf8_lossy(&out.stderr).to_string());
+        }
+        let stub = String::from_utf8_lossy(&out.stdout);
+        Ok(PyiConverter::new().convert(&stub))
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let Some(path) = cfg.input.path() else {
+            eprintln!("{:?} is not a filename", cfg.input);
+            return ExitStatus::ERR1;
+        };
+        let decl_path = cfg.dump_decl_path();
+        if decl_path.exists() {
+            eprintln!("{} already exists", decl_path.display());
+            return ExitStatus::ERR1;
+        }
+        let code = match Self::new(cfg.copy()).generate(path) {
+            Ok(code) => code,
+            Err(err) => {
+                eprintln!("failed to generate a declaration file: {err}");
+                return ExitStatus::ERR1;
+            }
+        };
+        match File::create(&decl_path).and_then(|mut f| f.write_all(code.as_bytes())) {
+            Ok(()) => {
+                println!("generated: {}", decl_path.display());
+                ExitStatus::OK
+            }
+            Err(err) => {
+                eprintln!("failed to write {}: {err}", decl_path.display());
+                ExitStatus::ERR1
+            }
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! defines `TestRunner` (`erg test`).
+//!
+//! `erg test` discovers the test functions in the entry module and the local Erg modules imported by it
+//! (the modules in the same directory or its subdirectories), runs them and reports the results.
+//! A test function is a top-level subroutine that can be called without arguments and
+//! is named `test_*` or decorated with `@Test`.
+//!
+//! Each module containing tests is compiled separately into a temporary directory along with its source map (see `SourceMap`).
+//! A generated driver script executes the modules in one Python process, calls the test functions and writes the results (a JSON object per line).
+//! The location of a failure is resolved by the source maps (the innermost frame that has one),
+//! so that a failed `assert` is reported at the Erg source like a compile error.
+//!
+//! ```sh
+//! erg test foo.er
+//! ```
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use erg_common::config::ErgConfig;
+use erg_common::env::erg_std_path;
+use erg_common::error::{ErrorKind, Location, MultiErrorDisplay};
+use erg_common::io::Input;
+use erg_common::json::{self, Json};
+use erg_common::python_util::exec_pyc;
+use erg_common::switch_lang;
+use erg_common::traits::{ExitStatus, Locational, Runnable, Stream};
+use erg_common::Str;
+
+use erg_parser::ast::{Accessor, Expr, Signature, SubrSignature, AST};
+use erg_parser::build_ast::ASTBuilder;
+
+use crate::artifact::ErrorArtifact;
+use crate::codegen::escape_name;
+use crate::error::{CompileError, CompileErrors};
+use crate::module::SharedPyExceptionMap;
+use crate::ty::VisibilityModifier;
+use crate::Compiler;
+
+/// Distinguishes the temporary directories of the runners in the same process.
+static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Executes the modules listed in `plan.txt` and writes the results of the tests to `results.txt`.
+const DRIVER: &str = "# generated by `erg test`
+import json
+import marshal
+import os
+import sys
+
+_dir = os.path.dirname(os.path.abspath(__file__))
+modules = []
+with open(os.path.join(_dir, \"plan.txt\"), encoding=\"utf-8\") as f:
+    for line in f.read().splitlines():
+        kind, *args = line.split(\"\\t\")
+        if kind == \"std\":
+            sys.path.insert(0, args[0])
+        elif kind == \"path\":
+            # appended so as not to shadow the standard modules
+            sys.path.append(args[0])
+        elif kind == \"module\":
+            modules.append((args, []))
+        elif kind == \"test\":
+            modules[-1][1].append(args[0])
+
+import _erg_traceback
+
+
+def qual_name(cls):
+    if cls.__module__ == \"builtins\":
+        return cls.__qualname__
+    return cls.__module__ + \".\" + cls.__qualname__
+
+
+def failure(exc):
+    # the exception class and its base classes
+    kind = [qual_name(cls) for cls in type(exc).__mro__ if cls is not object]
+    res = {\"status\": \"failed\", \"kind\": kind, \"msg\": str(exc)}
+    # the innermost frame which has a source map
+    tb = exc.__traceback__
+    while tb is not None:
+        code = tb.tb_frame.f_code
+        source_map = _erg_traceback._load_map(code.co_filename)
+        if source_map is not None:
+            loc = _erg_traceback._lookup(source_map, code, tb.tb_lasti)
+            if loc is not None:
+                res[\"path\"] = source_map[\"source\"]
+                res[\"loc\"] = list(loc)
+        tb = tb.tb_next
+    return res
+
+
+with open(os.path.join(_dir, \"results.txt\"), \"w\", encoding=\"utf-8\") as out:
+    for (name, pyc, map_base), tests in modules:
+        with open(pyc, \"rb\") as f:
+            code = marshal.loads(f.read()[16:])
+        _erg_traceback._maps[code.co_filename] = _erg_traceback._load_map(map_base)
+        namespace = {\"__name__\": name, \"__builtins__\": __builtins__}
+        try:
+            exec(code, namespace)
+        except Exception as exc:
+            res = failure(exc)
+            for _ in tests:
+                out.write(json.dumps(res) + \"\\n\")
+            continue
+        for test in tests:
+            try:
+                namespace[test]()
+                res = {\"status\": \"ok\"}
+            except Exception as exc:
+                res = failure(exc)
+            out.write(json.dumps(res) + \"\\n\")
+            out.flush()
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// the module which defines the test
+    pub path: PathBuf,
+    pub name: Str,
+    /// the name in the compiled module (private names are mangled)
+    pub py_name: Str,
+    pub loc: Location,
+}
+
+impl TestCase {
+    /// e.g. `foo.er::test_add`
+    pub fn display_name(&self, cwd: &Path) -> String {
+        let path = self.path.strip_prefix(cwd).unwrap_or(&self.path);
+        format!("{}::{}", path.display(), self.name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub case: TestCase,
+    /// `None` if the test passed
+    pub failure: Option<CompileError>,
+}
+
+impl TestResult {
+    pub const fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub struct TestRunner {
+    cfg: ErgConfig,
+    /// the exception classes declared by the imported modules
+    py_exceptions: SharedPyExceptionMap,
+}
+
+impl TestRunner {
+    pub fn new(cfg: ErgConfig) -> Self {
+        Self {
+            cfg,
+            py_exceptions: SharedPyExceptionMap::new(),
+        }
+    }
+
+    fn is_test(sig: &SubrSignature) -> bool {
+        let name = sig.ident.inspect();
+        let decorated = sig.decorators.iter().any(|deco| {
+            matches!(deco.expr(), Expr::Accessor(Accessor::Ident(ident)) if &ident.inspect()[..] == "Test")
+        });
+        sig.params.non_defaults.is_empty() && (decorated || name.starts_with("test_"))
+    }
+
+    /// Returns the test functions defined at the top level of `ast`.
+    pub fn discover(path: &Path, ast: &AST) -> Vec<TestCase> {
+        let mut cases = vec![];
+        for chunk in ast.module.iter() {
+            let Expr::Def(def) = chunk else {
+                continue;
+            };
+            let Signature::Subr(sig) = &def.sig else {
+                continue;
+            };
+            if !Self::is_test(sig) {
+                continue;
+            }
+            let vis = if sig.ident.vis.is_private() {
+                VisibilityModifier::Private
+            } else {
+                VisibilityModifier::Public
+            };
+            let py_name = escape_name(
+                sig.ident.inspect(),
+                &vis,
+                sig.ident.name.ln_begin().unwrap_or(0),
+                sig.ident.name.col_begin().unwrap_or(0),
+            );
+            cases.push(TestCase {
+                path: path.to_path_buf(),
+                name: sig.ident.inspect().clone(),
+                py_name,
+                loc: sig.ident.loc(),
+            });
+        }
+        cases
+    }
+
+    fn io_error(&self, path: &Path, err: io::Error) -> ErrorArtifact {
+        let path = path.display();
+        let desc = switch_lang!(
+            "japanese" => format!("{path}に書き込めませんでした: {err}"),
+            "simplified_chinese" => format!("无法写入{path}: {err}"),
+            "traditional_chinese" => format!("無法寫入{path}: {err}"),
+            "english" => format!("failed to write {path}: {err}"),
+        );
+        let err = CompileError::file_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            desc,
+            Location::Unknown,
+            "<module>".into(),
+            None,
+        );
+        ErrorArtifact::new(CompileErrors::from(err), CompileErrors::empty())
+    }
+
+    /// The local Erg modules (including the entry), in the order of initialization.
+    fn test_modules(&mut self) -> Result<Vec<PathBuf>, ErrorArtifact> {
+        let mut compiler = Compiler::new(self.cfg.copy());
+        let src = self.cfg.input.read();
+        let arti = compiler.compile(src, "exec")?;
+        arti.warns.write_all_stderr();
+        self.py_exceptions = compiler.shared().py_exceptions.clone();
+        let entry = self.cfg.input.full_path();
+        let root = entry
+            .parent()
+            .and_then(|dir| fs::canonicalize(dir).ok())
+            .unwrap_or_default();
+        let mut modules = compiler
+            .init_order()
+            .iter()
+            .filter(|path| fs::canonicalize(path).is_ok_and(|path| path.starts_with(&root)))
+            .cloned()
+            .collect::<Vec<_>>();
+        if modules.is_empty() {
+            modules.push(entry);
+        }
+        Ok(modules)
+    }
+
+    fn result(&self, case: TestCase, res: Option<&Json>, code: Option<i32>) -> TestResult {
+        let member = |name: &str| match res {
+            Some(Json::Object(members)) => members
+                .iter()
+                .find_map(|(key, value)| (key == name).then_some(value)),
+            _ => None,
+        };
+        let str_member = |name: &str| match member(name) {
+            Some(Json::Str(s)) => Some(s.as_str()),
+            _ => None,
+        };
+        if res.is_some() && str_member("status") == Some("ok") {
+            return TestResult {
+                case,
+                failure: None,
+            };
+        }
+        let (kind, msg) = if res.is_some() {
+            let kind = match member("kind") {
+                Some(Json::Array(mro)) => {
+                    let mro = mro
+                        .iter()
+                        .filter_map(|cls| match cls {
+                            Json::Str(name) => Some(name.as_str()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    self.py_exceptions.resolve(&mro)
+                }
+                _ => ErrorKind::Exception,
+            };
+            (kind, str_member("msg").unwrap_or("").to_string())
+        } else {
+            let code = code.map_or("unknown".to_string(), |code| code.to_string());
+            let msg = switch_lang!(
+                "japanese" => format!("テストプロセスが終了したため実行されませんでした (終了コード: {code})"),
+                "simplified_chinese" => format!("测试进程已退出, 未运行 (退出码: {code})"),
+                "traditional_chinese" => format!("測試進程已退出, 未運行 (退出碼: {code})"),
+                "english" => format!("not run because the test process exited (exit code: {code})"),
+            );
+            (ErrorKind::SystemExit, msg)
+        };
+        let loc = match member("loc") {
+            Some(Json::Array(loc)) => {
+                let num = |i: usize| match loc.get(i) {
+                    Some(Json::Int(n)) => u32::try_from(*n).ok(),
+                    _ => None,
+                };
+                match (num(0), num(1), num(2), num(3)) {
+                    (Some(ln_begin), Some(col_begin), Some(ln_end), Some(col_end)) => {
+                        Location::range(ln_begin, col_begin, ln_end, col_end)
+                    }
+                    (Some(ln_begin), _, Some(ln_end), _) if ln_begin == ln_end => {
+                        Location::Line(ln_begin)
+                    }
+                    (Some(ln_begin), _, Some(ln_end), _) => Location::LineRange(ln_begin, ln_end),
+                    _ => case.loc,
+                }
+            }
+            _ => case.loc,
+        };
+        let path = str_member("path").map_or(case.path.clone(), PathBuf::from);
+        let failure = CompileError::test_failure_error(
+            Input::file(path),
+            line!() as usize,
+            kind,
+            &msg,
+            loc,
+            case.name.to_string(),
+        );
+        TestResult {
+            case,
+            failure: Some(failure),
+        }
+    }
+
+    /// Compiles the modules and runs the tests in them.
+    pub fn run_tests(&mut self) -> Result<Vec<TestResult>, ErrorArtifact> {
+        let dir = std::env::temp_dir().join(format!(
+            "erg_test_{}_{}",
+            std::process::id(),
+            RUN_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).map_err(|err| self.io_error(&dir, err))?;
+        let res = self.run_tests_in(&dir);
+        let _ = fs::remove_dir_all(&dir);
+        res
+    }
+
+    fn run_tests_in(&mut self, dir: &Path) -> Result<Vec<TestResult>, ErrorArtifact> {
+        let mut plan = format!("std\t{}\n", erg_std_path().display());
+        // the local Python modules are imported relative to the current directory (e.g. `tests.foo`)
+        if let Ok(dir) = std::env::current_dir() {
+            plan.push_str(&format!("path\t{}\n", dir.display()));
+        }
+        let mut cases = vec![];
+        for (i, path) in self.test_modules()?.into_iter().enumerate() {
+            let mut cfg = self.cfg.inherit(path.clone());
+            // the private tests are not referenced, so they would be removed as dead code
+            cfg.opt_level = 0;
+            let src = cfg.input.read();
+            let Ok(arti) = ASTBuilder::new(cfg.copy()).build(src.clone()) else {
+                continue;
+            };
+            let tests = Self::discover(&path, &arti.ast);
+            if tests.is_empty() {
+                continue;
+            }
+            // the warnings have been reported when the entry was compiled
+            let mut compiler = Compiler::new(cfg);
+            let arti = compiler.compile(src, "exec")?;
+            let pyc = dir.join(format!("{i}.pyc"));
+            arti.object
+                .dump_as_pyc(&pyc, self.cfg.py_magic_num)
+                .map_err(|err| self.io_error(&pyc, err))?;
+            let map_base = dir.join(format!("{i}.er"));
+            let map = dir.join(format!("{i}.er.map"));
+            compiler
+                .source_map()
+                .dump(&map)
+                .map_err(|err| self.io_error(&map, err))?;
+            let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("_");
+            plan.push_str(&format!(
+                "module\t{name}\t{}\t{}\n",
+                pyc.display(),
+                map_base.display()
+            ));
+            for test in tests.iter() {
+                plan.push_str(&format!("test\t{}\n", test.py_name));
+            }
+            cases.extend(tests);
+        }
+        if cases.is_empty() {
+            return Ok(vec![]);
+        }
+        let plan_path = dir.join("plan.txt");
+        fs::write(&plan_path, plan).map_err(|err| self.io_error(&plan_path, err))?;
+        let driver = dir.join("driver.py");
+        fs::write(&driver, DRIVER).map_err(|err| self.io_error(&driver, err))?;
+        let code = exec_pyc(
+            driver.to_string_lossy(),
+            self.cfg.py_command,
+            &[],
+            self.cfg.output.clone(),
+        );
+        let results = fs::read_to_string(dir.join("results.txt")).unwrap_or_default();
+        let mut results = results.lines().map(|line| json::parse(line).ok());
+        Ok(cases
+            .into_iter()
+            .map(|case| self.result(case, results.next().flatten().as_ref(), code))
+            .collect())
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let results = match Self::new(cfg.copy()).run_tests() {
+            Ok(results) => results,
+            Err(eart) => {
+                eart.warns.write_all_stderr();
+                eart.errors.write_all_stderr();
+                return ExitStatus::ERR1;
+            }
+        };
+        let cwd = std::env::current_dir()
+            .and_then(fs::canonicalize)
+            .unwrap_or_default();
+        println!("running {} tests", results.len());
+        let mut failures = CompileErrors::empty();
+        for result in results.iter() {
+            let name = result.case.display_name(&cwd);
+            if let Some(failure) = &result.failure {
+                println!("test {name} ... FAILED");
+                failures.push(failure.clone());
+            } else {
+                println!("test {name} ... ok");
+            }
+        }
+        // each error is followed by an empty line
+        println!();
+        failures.write_all_stderr();
+        let passed = results.len() - failures.len();
+        let status = if failures.is_empty() { "ok" } else { "FAILED" };
+        println!(
+            "test result: {status}. {passed} passed; {} failed",
+            failures.len()
+        );
+        ExitStatus::new(i32::from(!failures.is_empty()), 0, failures.len())
+    }
+}
@@ -0,0 +1,22 @@
+VERSION = "1.0"
+_cache = {}
+
+
+def add(x: int, y=1):
+    return x + y
+
+
+class Point:
+    def __init__(self, x: float, y: float):
+        self.x = x
+        self.y = y
+
+    def norm(self) -> float:
+        return (self.x**2 + self.y**2) ** 0.5
+
+    def _scale(self, k):
+        self.x *= k
+
+
+class Point3(Point):
+    pass
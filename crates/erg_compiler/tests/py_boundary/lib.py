@@ -0,0 +1,6 @@
+# `lib.d.er` does not match the implementation
+answer = "42"
+
+
+def double(x):
+    return x * 2
@@ -0,0 +1,14 @@
+class FooError(ValueError):
+    pass
+
+
+class BarError(Exception):
+    pass
+
+
+def foo():
+    raise FooError("foo")
+
+
+def bar():
+    raise BarError("bar")
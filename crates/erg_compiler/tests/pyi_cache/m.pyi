@@ -0,0 +1 @@
+def f(x: int) -> int: ...
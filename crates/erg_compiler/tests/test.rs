@@ -1,17 +1,49 @@
-use erg_common::config::ErgConfig;
-use erg_common::error::MultiErrorDisplay;
-use erg_common::io::Output;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use erg_common::config::{ErgConfig, ErgMode, ModuleRoot, PackageManifest, Strictness};
+use erg_common::dict::Dict;
+use erg_common::env::case_sensitive_paths;
+use erg_common::error::{ErrorDisplay, ErrorKind, Location, MultiErrorDisplay};
+use erg_common::io::{Input, Output};
+use erg_common::pathutil::NormalizedPathBuf;
+use erg_common::python_util::{exec_pyc, opt_which_python, PythonVersion};
+use erg_common::registry::Registry;
 use erg_common::spawn::exec_new_thread;
-use erg_common::traits::Runnable;
+use erg_common::style::remove_style;
+use erg_common::traits::{Runnable, Stream};
+use erg_common::Str;
 
+use erg_compiler::build_hir::HIRBuilder;
+use erg_compiler::build_package::PackageBuilder;
 use erg_compiler::context::{Context, ModuleContext};
-use erg_compiler::error::CompileErrors;
+use erg_compiler::crash::CrashReporter;
+use erg_compiler::erg_parser::ast::VarName;
+use erg_compiler::erg_parser::build_ast::ASTBuilder;
+use erg_compiler::error::codes::{
+    AMBIGUOUS_MODULE, COMPILER_BUG, ERROR_CODES, TYPE_HOLE, TYPE_TOO_COMPLEX,
+};
+use erg_compiler::error::{CompileError, CompileErrors};
+use erg_compiler::fix::Fixer;
 use erg_compiler::lower::ASTLowerer;
+use erg_compiler::module::SharedCompilerResource;
+use erg_compiler::pack::{write_archive, Packer};
+use erg_compiler::prefetch::ModulePrefetcher;
+use erg_compiler::reprint::RoundTripChecker;
+use erg_compiler::sourcemap::SourceMap;
+use erg_compiler::stub::{PyDeclGenerator, PyStubGenerator, PyiConverter};
+use erg_compiler::test_runner::TestRunner;
 
+use erg_compiler::ty::codeobj::CodeObj;
 use erg_compiler::ty::constructors::{
     func0, func1, func2, kw, mono, nd_func, nd_proc, or, poly, proc1, subtype_q, ty_tp, type_q,
 };
+use erg_compiler::ty::value::ValueObj;
+use erg_compiler::ty::TyParam;
 use erg_compiler::ty::Type::*;
+use erg_compiler::varinfo::{SymbolId, VarInfo};
+use erg_compiler::watch::Watcher;
+use erg_compiler::{exec_wasm, Compiler};
 
 fn load_file(path: &'static str) -> Result<ModuleContext, CompileErrors> {
     let mut cfg = ErgConfig::with_main_path(path.into());
@@ -67,6 +99,440 @@ fn _test_infer_types() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+fn test_pyi_stub() -> Result<(), ()> {
+    exec_new_thread(_test_pyi_stub, "test_pyi_stub")
+}
+
+fn _test_pyi_stub() -> Result<(), ()> {
+    let module = load_file("tests/stub.er").map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    let stub = PyStubGenerator::new().generate(&module.context);
+    assert_eq!(
+        stub,
+        "\
+class C:
+    x: int
+    y: str
+    def f(self, z: int) -> int: ...
+def id[T](x: T) -> T: ...
+def u(x: int | str) -> int | str: ...
+t: tuple[int, str]
+"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pyi_decl_cache() -> Result<(), ()> {
+    exec_new_thread(_test_pyi_decl_cache, "test_pyi_decl_cache")
+}
+
+fn _test_pyi_decl_cache() -> Result<(), ()> {
+    let dir = std::env::temp_dir().join(format!("erg_pyi_cache_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for file in ["main.er", "m.py", "m.pyi"] {
+        std::fs::copy(format!("tests/pyi_cache/{file}"), dir.join(file)).unwrap();
+    }
+    let check = || {
+        let mut cfg = ErgConfig::with_main_path(dir.join("main.er"));
+        cfg.output = Output::Null;
+        ASTLowerer::new(cfg)
+            .exec()
+            .map_err(|errs| errs.write_all_stderr())
+    };
+    let decl_path = dir.join("__pycache__").join("m.d.er");
+    let mark = |path: &Path| {
+        let decl = std::fs::read_to_string(path).unwrap();
+        std::fs::write(path, format!("{decl}# marked\n")).unwrap();
+    };
+    let marked = |path: &Path| {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .ends_with("# marked\n")
+    };
+    let res = check().and_then(|_| {
+        mark(&decl_path);
+        check()
+    });
+    // the stub is not modified, so the declaration file is not regenerated
+    let kept = marked(&decl_path);
+    let res = res.and_then(|_| {
+        // the length of the stub is not changed
+        std::fs::write(dir.join("m.pyi"), "def f(y: int) -> int: ...\n").unwrap();
+        check()
+    });
+    let regenerated = !marked(&decl_path);
+    std::fs::remove_dir_all(&dir).unwrap();
+    res?;
+    assert!(kept);
+    assert!(regenerated);
+    Ok(())
+}
+
+#[test]
+fn test_gen_decl() -> Result<(), String> {
+    let decl = PyDeclGenerator::new(ErgConfig::default()).generate("tests/gen_decl.py".as_ref())?;
+    assert_eq!(
+        decl,
+        "\
+.VERSION: Str
+.add: (x: Int, y := Int) -> Obj
+.Point: ClassType
+.Point.
+    x: Float
+    y: Float
+    __call__: (x: Float, y: Float) -> .Point
+    norm: (self: .Point) -> Float
+.Point3: ClassType
+.Point3 <: .Point
+"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pyi_converter() {
+    let stub = r#"
+Mode = Literal['r', "w", 'it\'s', 'say "hi"', 1, True]
+def f(xs: tuple[int, ...], ys: Tuple[str, ...]) -> tuple[int, str]: ...
+def g(x: tuple[()], y: tuple) -> tuple[list[int], ...]: ...
+"#;
+    assert_eq!(
+        PyiConverter::new().convert(stub),
+        r#".f: (xs: [Int; _], ys: [Str; _]) -> (Int, Str,)
+.g: (x: (), y: GenericTuple) -> [[Int; _]; _]
+"#
+    );
+    let converter = PyiConverter::new();
+    assert_eq!(
+        converter.convert_type(r#"Literal['r', "w", 'it\'s', 'say "hi"', 1, True]"#),
+        r#"{"r", "w", "it's", "say \"hi\"", 1, True}"#
+    );
+    assert_eq!(converter.convert_type("Literal[b'x']"), "{b'x'}");
+}
+
+#[cfg(feature = "mem_profile")]
+#[test]
+fn test_instant_frame_clones() -> Result<(), ()> {
+    exec_new_thread(_test_instant_frame_clones, "test_instant_frame_clones")
+}
+
+#[cfg(feature = "mem_profile")]
+fn _test_instant_frame_clones() -> Result<(), ()> {
+    use erg_compiler::context::mem_profile;
+
+    load_file("tests/instant_frame.er").map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    // the names are unique to this test, as the other tests run in parallel
+    let clones = mem_profile::clone_stats()
+        .into_iter()
+        .filter(|(name, _)| name.starts_with("<module>::Frame_"))
+        .map(|(_, stat)| stat.count)
+        .sum::<usize>();
+    assert_eq!(clones, 0);
+    Ok(())
+}
+
+#[test]
+fn test_source_map() -> Result<(), ()> {
+    exec_new_thread(_test_source_map, "test_source_map")
+}
+
+fn _test_source_map() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/source_map.er".into());
+    cfg.output = Output::Null;
+    let mut compiler = Compiler::new(cfg);
+    let src = compiler.cfg.input.read();
+    compiler.compile(src, "exec").map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    let map = compiler.source_map();
+    assert_eq!(
+        map.resolve("f", 3),
+        Some((Location::range(3, 4, 3, 17), true))
+    );
+    assert_eq!(map.resolve("f", -1), Some((Location::Line(2), false)));
+    assert_eq!(map.resolve("<module>", -1), None);
+    assert_eq!(SourceMap::parse(&map.to_string()).as_ref(), Some(map));
+    Ok(())
+}
+
+#[test]
+fn test_debug_info() -> Result<(), ()> {
+    exec_new_thread(_test_debug_info, "test_debug_info")
+}
+
+fn _test_debug_info() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/source_map.er".into());
+    cfg.output = Output::Null;
+    cfg.target_version = Some(PythonVersion::new(3, Some(11), Some(0)));
+    let mut compiler = Compiler::new(cfg);
+    let src = compiler.cfg.input.read();
+    let arti = compiler.compile(src, "exec").map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    let f = arti
+        .object
+        .consts
+        .iter()
+        .find_map(|cons| match cons {
+            ValueObj::Code(code) if &code.name[..] == "f" => Some(code),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(f.varnames, vec![Str::ever("x")]);
+    let positions = f.positions();
+    assert_eq!(positions.len(), f.code.len() / 2);
+    assert!(positions.contains(&(Some(3), Some(3), Some(4), Some(17))));
+    let mut code = f.as_ref().clone();
+    let positions = vec![
+        (Some(1), Some(1), None, None),
+        (Some(300), Some(302), Some(200), Some(3)),
+        (None, None, None, None),
+        (Some(2), Some(2), Some(4), Some(5)),
+    ];
+    code.set_positions(&positions);
+    assert_eq!(code.positions(), positions);
+    Ok(())
+}
+
+#[test]
+fn test_search_path() -> Result<(), ()> {
+    exec_new_thread(_test_search_path, "test_search_path")
+}
+
+fn _test_search_path() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/search_path.er".into());
+    cfg.output = Output::Null;
+    cfg.search_paths.push("tests/sigpack".into());
+    let mut lowerer = ASTLowerer::new(cfg);
+    lowerer.exec().map_err(|errs| errs.write_all_stderr())?;
+    let module = lowerer.pop_mod_ctx().unwrap();
+    module.context.assert_var_type("x", &Int)?;
+    Ok(())
+}
+
+#[test]
+fn test_module_resolution() -> Result<(), ()> {
+    exec_new_thread(_test_module_resolution, "test_module_resolution")
+}
+
+fn _test_module_resolution() -> Result<(), ()> {
+    let lower = |main: &str, namespace_packages: bool| {
+        let mut cfg =
+            ErgConfig::with_main_path(format!("tests/module_resolution/proj/{main}").into());
+        cfg.output = Output::Null;
+        cfg.search_paths.push("tests/module_resolution/deps".into());
+        cfg.namespace_packages = namespace_packages;
+        let ast = ASTBuilder::new(cfg.copy())
+            .build(cfg.input.read())
+            .unwrap()
+            .ast;
+        ASTLowerer::new(cfg).lower(ast, "exec")
+    };
+    let art = lower("main.er", true).map_err(|iart| iart.errors.write_all_stderr())?;
+    let shadowed = art
+        .warns
+        .iter()
+        .filter(|warn| warn.core.kind == ErrorKind::ImportWarning)
+        .map(|warn| remove_style(&warn.core.main_message))
+        .collect::<Vec<_>>();
+    assert_eq!(shadowed.len(), 1, "{}", art.warns);
+    assert!(shadowed[0].ends_with("deps/shadow.er"), "{}", shadowed[0]);
+    // a directory without `__init__.er` is not a module by default
+    assert!(lower("main.er", false).is_err());
+    let errs = lower("ambiguous.er", true).unwrap_err().errors;
+    assert!(
+        errs.iter()
+            .any(|err| err.core.code == Some(AMBIGUOUS_MODULE)),
+        "{errs}"
+    );
+    // the dependencies are searched first
+    let mut cfg = ErgConfig::with_main_path("tests/module_resolution/proj/main.er".into());
+    cfg.search_paths.push("tests/module_resolution/deps".into());
+    cfg.module_search_order = ModuleRoot::parse_order("deps").unwrap();
+    let shadow = cfg.resolve_real_path(Path::new("shadow")).unwrap();
+    assert!(shadow.ends_with("deps/shadow.er"), "{}", shadow.display());
+    Ok(())
+}
+
+#[test]
+fn test_dedup_hard_link() -> Result<(), ()> {
+    exec_new_thread(_test_dedup_hard_link, "test_dedup_hard_link")
+}
+
+fn _test_dedup_hard_link() -> Result<(), ()> {
+    // git cannot track hard links
+    let dir = std::env::temp_dir().join(format!("erg_dedup_{}", std::process::id()));
+    let vendor = dir.join("vendor");
+    std::fs::create_dir_all(&vendor).unwrap();
+    std::fs::copy("tests/dedup/foo.er", dir.join("foo.er")).unwrap();
+    std::fs::copy("tests/dedup/main.er", dir.join("main.er")).unwrap();
+    let _ = std::fs::remove_file(vendor.join("foo.er"));
+    std::fs::hard_link(dir.join("foo.er"), vendor.join("foo.er")).unwrap();
+    let mut cfg = ErgConfig::with_main_path(dir.join("main.er"));
+    cfg.output = Output::Null;
+    let mut lowerer = ASTLowerer::new(cfg);
+    let res = lowerer.exec().map_err(|errs| errs.write_all_stderr());
+    std::fs::remove_dir_all(&dir).unwrap();
+    res?;
+    let module = lowerer.pop_mod_ctx().unwrap();
+    module.context.assert_var_type("x", &Int)?;
+    Ok(())
+}
+
+/// Checks `main.er` in a temporary directory prepared by `setup`.
+#[cfg(unix)]
+fn check_in_temp_dir(name: &str, main: &str, setup: impl FnOnce(&Path)) -> CompileErrors {
+    let dir = std::env::temp_dir().join(format!("erg_{name}_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.er"), main).unwrap();
+    setup(&dir);
+    let mut cfg = ErgConfig::with_main_path(dir.join("main.er"));
+    cfg.output = Output::Null;
+    let res = ASTLowerer::new(cfg).exec();
+    std::fs::remove_dir_all(&dir).unwrap();
+    res.err()
+        .map_or(CompileErrors::empty(), CompileErrors::from)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_import_broken_symlink() -> Result<(), ()> {
+    exec_new_thread(_test_import_broken_symlink, "test_import_broken_symlink")
+}
+
+#[cfg(unix)]
+fn _test_import_broken_symlink() -> Result<(), ()> {
+    use std::os::unix::fs::symlink;
+    let main = "foo = import \"foo\"\nbar = pyimport \"bar\"\nx: Int = \"a\"\n";
+    let errs = check_in_temp_dir("broken_symlink", main, |dir| {
+        symlink(dir.join("not_exist.er"), dir.join("foo.er")).unwrap();
+        symlink(dir.join("not_exist.d.er"), dir.join("bar.d.er")).unwrap();
+    });
+    let import_errs = errs
+        .iter()
+        .filter(|err| err.core.kind == ErrorKind::ImportError)
+        .collect::<Vec<_>>();
+    assert_eq!(import_errs.len(), 2, "{errs}");
+    for (err, file) in import_errs.iter().zip(["foo.er", "bar.d.er"]) {
+        assert!(
+            err.core.main_message.contains("broken symbolic link"),
+            "{errs}"
+        );
+        assert!(err.core.main_message.contains(file), "{errs}");
+        // the other candidates are listed
+        let hint = err.core.get_hint().unwrap_or_default();
+        assert!(hint.contains("__init__"), "{hint}");
+    }
+    // the rest of the module is still checked
+    assert!(
+        errs.iter().any(|err| err.core.kind == ErrorKind::TypeError),
+        "{errs}"
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_import_permission_denied() -> Result<(), ()> {
+    exec_new_thread(
+        _test_import_permission_denied,
+        "test_import_permission_denied",
+    )
+}
+
+#[cfg(unix)]
+fn _test_import_permission_denied() -> Result<(), ()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut open_err = None;
+    let errs = check_in_temp_dir("permission_denied", "foo = import \"foo\"\n", |dir| {
+        let path = dir.join("foo.er");
+        std::fs::write(&path, ".x = 1\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o000)).unwrap();
+        open_err = std::fs::File::open(&path).err();
+    });
+    // permissions are not checked for the superuser
+    let Some(open_err) = open_err else {
+        return Ok(());
+    };
+    assert_eq!(errs.len(), 1, "{errs}");
+    assert_eq!(errs[0].core.kind, ErrorKind::ImportError);
+    assert!(errs[0].core.main_message.contains("foo.er"), "{errs}");
+    assert!(
+        errs[0].core.main_message.contains(&open_err.to_string()),
+        "{errs}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_case_collision() -> Result<(), ()> {
+    exec_new_thread(_test_case_collision, "test_case_collision")
+}
+
+fn _test_case_collision() -> Result<(), ()> {
+    // git cannot check out such files on case-insensitive file systems
+    let dir = std::env::temp_dir().join(format!("erg_case_collision_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.er"), "foo = import \"foo\"\nprint! foo.x\n").unwrap();
+    std::fs::write(dir.join("foo.er"), ".x = 1\n").unwrap();
+    std::fs::write(dir.join("Foo.er"), ".x = 2\n").unwrap();
+    // `Foo.er` overwrote `foo.er`
+    if std::fs::read_dir(&dir).unwrap().count() < 3 {
+        std::fs::remove_dir_all(&dir).unwrap();
+        return Ok(());
+    }
+    let mut cfg = ErgConfig::with_main_path(dir.join("main.er"));
+    cfg.output = Output::Null;
+    let ast = ASTBuilder::new(cfg.copy())
+        .build(cfg.input.read())
+        .unwrap()
+        .ast;
+    let res = ASTLowerer::new(cfg).lower(ast, "exec");
+    std::fs::remove_dir_all(&dir).unwrap();
+    let (errs, warns) = match res {
+        Ok(art) => (CompileErrors::empty(), art.warns),
+        Err(iart) => (iart.errors, iart.warns),
+    };
+    let is_collision = |err: &&CompileError| {
+        err.core.kind.is_warning() == case_sensitive_paths()
+            && remove_style(&err.core.main_message).contains("Foo.er")
+    };
+    if case_sensitive_paths() {
+        // distinct modules, but not portable
+        assert!(errs.is_empty(), "{errs}");
+        assert_eq!(warns.iter().filter(is_collision).count(), 1, "{warns}");
+    } else {
+        assert_eq!(errs.iter().filter(is_collision).count(), 1, "{errs}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_access_chain_loc() -> Result<(), ()> {
+    exec_new_thread(_test_access_chain_loc, "test_access_chain_loc")
+}
+
+fn _test_access_chain_loc() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/access_chain.er".into());
+    cfg.output = Output::Null;
+    let input = cfg.input.clone();
+    let mut lowerer = ASTLowerer::new(cfg);
+    let errs = lowerer.exec().unwrap_err();
+    assert!(errs.len() >= 2);
+    // the errors point at the segment that failed (`pth` of `os.pth.Sep`, `Nope` of `C.Nope`), in this file
+    let expected = [Location::range(4, 8, 4, 11), Location::range(5, 7, 5, 11)];
+    for err in errs.iter() {
+        assert_eq!(err.input, input);
+        assert!(expected.contains(&err.core.loc), "{}", err.core.loc);
+    }
+    Ok(())
+}
+
 #[test]
 fn test_refinement_subtyping() -> Result<(), ()> {
     let context = Context::default_with_name("<module>");
@@ -95,6 +561,122 @@ fn test_intersection() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+fn test_unnamed_default_param() -> Result<(), ()> {
+    let context = Context::default_with_name("<module>");
+    context.test_unnamed_default_param()?;
+    Ok(())
+}
+
+#[test]
+fn test_record_ops() -> Result<(), ()> {
+    let context = Context::default_with_name("<module>");
+    context.test_record_ops()?;
+    Ok(())
+}
+
+/// Set `ERG_UPDATE_GOLDEN=1` to update the golden file.
+#[cfg(not(any(feature = "py_compat", feature = "debug")))]
+#[test]
+fn test_builtins_golden() -> Result<(), String> {
+    let golden_path = Path::new("tests/builtins.golden");
+    let shared = SharedCompilerResource::new(ErgConfig::default());
+    let dump = shared
+        .mod_cache
+        .get(Path::new("<builtins>"))
+        .unwrap()
+        .module
+        .context
+        .dump();
+    if std::env::var("ERG_UPDATE_GOLDEN").is_ok() {
+        std::fs::write(golden_path, dump).map_err(|err| err.to_string())?;
+        return Ok(());
+    }
+    let golden = std::fs::read_to_string(golden_path).map_err(|err| err.to_string())?;
+    let diff = Context::diff_dump(&golden, &dump);
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "the builtin context differs from {}:\n{diff}\nrun with `ERG_UPDATE_GOLDEN=1` if the change is intended",
+            golden_path.display()
+        ))
+    }
+}
+
+/// Removes the path of the fixture and the internal error numbers (`[#0123]`) from the rendered diagnostic.
+fn normalize_diagnostic(rendered: &str, path: &Path) -> String {
+    let file_name = path.file_name().unwrap().to_string_lossy();
+    let mut normalized = remove_style(rendered).replace(&path.display().to_string(), &file_name);
+    while let Some(start) = normalized.find("[#") {
+        let Some(len) = normalized[start..].find(']') else {
+            break;
+        };
+        normalized.replace_range(start..=start + len, "");
+    }
+    normalized
+}
+
+/// Compiles each `tests/diagnostics/*.er` and compares the rendered errors and warnings with `*.stderr`.
+/// Set `ERG_UPDATE_GOLDEN=1` to update (bless) the snapshots.
+#[cfg(not(any(
+    feature = "debug",
+    feature = "japanese",
+    feature = "simplified_chinese",
+    feature = "traditional_chinese",
+    feature = "unicode",
+    feature = "pretty"
+)))]
+#[test]
+fn test_diagnostics_snapshots() -> Result<(), String> {
+    exec_new_thread(_test_diagnostics_snapshots, "test_diagnostics_snapshots")
+}
+
+fn _test_diagnostics_snapshots() -> Result<(), String> {
+    let update = std::env::var("ERG_UPDATE_GOLDEN").is_ok();
+    let mut fixtures = std::fs::read_dir("tests/diagnostics")
+        .map_err(|err| err.to_string())?
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "er"))
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    let mut failures = 0;
+    for path in fixtures {
+        let mut cfg = ErgConfig::with_main_path(path.clone());
+        cfg.output = Output::Null;
+        let src = cfg.input.read();
+        let (errors, warns) = match HIRBuilder::new(cfg).build(src, "exec") {
+            Ok(art) => (CompileErrors::empty(), art.warns),
+            Err(art) => (art.errors, art.warns),
+        };
+        let actual = errors
+            .iter()
+            .chain(warns.iter())
+            .map(|err| normalize_diagnostic(&err.show(), &path))
+            .collect::<String>();
+        let snapshot_path = path.with_extension("stderr");
+        if update {
+            std::fs::write(&snapshot_path, actual).map_err(|err| err.to_string())?;
+            continue;
+        }
+        let expected = std::fs::read_to_string(&snapshot_path).unwrap_or_default();
+        if expected != actual {
+            eprintln!(
+                "{}:\n--- expected\n{expected}+++ actual\n{actual}",
+                snapshot_path.display()
+            );
+            failures += 1;
+        }
+    }
+    if failures == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "{failures} diagnostics snapshot(s) differ, run with `ERG_UPDATE_GOLDEN=1` if the change is intended"
+        ))
+    }
+}
+
 /*
 #[test]
 fn test_patch() -> Result<(), ()> {
@@ -118,12 +700,1089 @@ fn test_resolve_trait_inner1() -> Result<(), ()> {
 }
 */
 
-// #[test]
-fn _test_dir() -> Result<(), ()> {
+#[test]
+fn test_impls_of() -> Result<(), ()> {
+    exec_new_thread(_test_impls_of, "test_impls_of")
+}
+
+fn _test_impls_of() -> Result<(), ()> {
+    let module = load_file("tests/impls.er").map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    let context = &module.context;
+    let int_impls = context.impls_of(&Int);
+    assert!(int_impls
+        .iter()
+        .any(|imp| imp.sub_type == Int && imp.sup_trait == mono("Eq")));
+    assert!(int_impls.iter().all(|imp| !imp.sub_type.is_refinement()));
+    let c = mono("<module>::C");
+    let add_c = poly("Add", vec![ty_tp(c.clone())]);
+    let c_impls = context.impls_of(&c);
+    assert!(c_impls
+        .iter()
+        .any(|imp| imp.sub_type == c && imp.sup_trait == add_c));
+    // inherited from `C`
+    let d_impls = context.impls_of(&mono("<module>::D"));
+    assert!(d_impls
+        .iter()
+        .any(|imp| imp.sub_type == c && imp.sup_trait == add_c));
+    Ok(())
+}
+
+#[test]
+fn test_dir_without_builtins() {
+    // without a module cache
     let context = Context::default_with_name("<module>");
-    let vars = context.dir();
-    for (name, vi) in vars.into_iter() {
-        println!("{name}: {vi}");
+    assert!(!context.dir_info().builtins_available);
+    let shared = SharedCompilerResource::new(ErgConfig::default());
+    let module = Context::module(
+        "<module>".into(),
+        ErgConfig::default(),
+        Some(shared.clone()),
+        10,
+    );
+    let info = module.dir_info();
+    assert!(info.builtins_available);
+    assert!(info.vars.keys().any(|name| name.inspect() == "print!"));
+    // the module cache without `<builtins>`
+    shared.mod_cache.remove(Path::new("<builtins>"));
+    let info = module.dir_info();
+    assert!(!info.builtins_available);
+    assert!(info.vars.is_empty());
+    assert!(module.get_var_info("print!").is_none());
+}
+
+#[test]
+fn test_dir_from() -> Result<(), ()> {
+    exec_new_thread(_test_dir_from, "test_dir_from")
+}
+
+fn _test_dir_from() -> Result<(), ()> {
+    let module = load_file("tests/dir_vis/main.er").map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    let names = |dir: Dict<&VarName, &VarInfo>| {
+        dir.into_iter()
+            .map(|(name, _)| name.inspect().to_string())
+            .collect::<Vec<_>>()
+    };
+    let foo = module.context.get_receiver_ctx("foo").unwrap();
+    let from_main = names(foo.local_dir_from(&module.context));
+    assert!(from_main.contains(&"pub".to_string()));
+    assert!(!from_main.contains(&"priv".to_string()));
+    let from_foo = names(foo.local_dir_from(foo));
+    assert!(from_foo.contains(&"priv".to_string()));
+    let class = foo.get_receiver_ctx("C").unwrap();
+    let from_main = names(class.local_dir_from(&module.context));
+    assert!(from_main.contains(&"public".to_string()));
+    assert!(!from_main.contains(&"private".to_string()));
+    // the builtins are visible from everywhere
+    assert!(names(module.context.dir_from(&module.context)).contains(&"print!".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_re_export() -> Result<(), ()> {
+    exec_new_thread(_test_re_export, "test_re_export")
+}
+
+fn _test_re_export() -> Result<(), ()> {
+    let module = load_file("tests/re_export/main.er").map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    let bar = module.context.get_receiver_ctx("bar").unwrap();
+    let origin = |name: &str| {
+        let (_, vi) = bar.get_var_info(name).unwrap();
+        module
+            .context
+            .get_re_export_origin(name, vi)
+            .and_then(|vi| vi.def_loc.module.clone())
+    };
+    // `bar.x` => `foo.x` => `baz.x`
+    assert!(origin("x").is_some_and(|path| path.ends_with("baz.er")));
+    // `{.y;} = import "foo"`
+    assert!(origin("y").is_some_and(|path| path.ends_with("foo.er")));
+    assert!(origin("z").is_none());
+    Ok(())
+}
+
+#[test]
+fn test_wasm() -> Result<(), ()> {
+    exec_new_thread(_test_wasm, "test_wasm")
+}
+
+fn _test_wasm() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/wasm.er".into());
+    cfg.output = Output::Null;
+    let mut compiler = Compiler::new(cfg);
+    let src = compiler.cfg.input.read();
+    let path = "tests/wasm.wasm";
+    compiler
+        .compile_and_dump_as_wasm(path, src, "exec")
+        .map_err(|eart| {
+            eart.errors.write_all_stderr();
+        })?;
+    let wasm = std::fs::read(path).unwrap();
+    assert_eq!(&wasm[..8], b"\0asm\x01\0\0\0");
+    // Node.js is optional
+    if Command::new("node").arg("--version").output().is_ok() {
+        assert_eq!(exec_wasm(path, Stdio::null()), Some(0));
+    }
+    std::fs::remove_file(path).unwrap();
+    // unsupported features are reported as errors
+    let errs = compiler
+        .compile_and_dump_as_wasm(path, "x = [1, 2]".into(), "exec")
+        .unwrap_err()
+        .errors;
+    assert_eq!(errs.len(), 1);
+    assert!(!Path::new(path).exists());
+    Ok(())
+}
+
+#[test]
+fn test_pack() -> Result<(), ()> {
+    exec_new_thread(_test_pack, "test_pack")
+}
+
+fn _test_pack() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/pack/main.er".into());
+    cfg.output = Output::Null;
+    let entries = Packer::new(cfg).pack().map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    let names = entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+    // `foo.er` is linked into `main.pyc`, and `math` is a standard module
+    // `helper` is imported as `tests.pack.helper`
+    assert_eq!(
+        &names[..5],
+        &[
+            "__main__.py",
+            "main.pyc",
+            "tests/",
+            "tests/pack/",
+            "tests/pack/helper.py"
+        ]
+    );
+    assert!(names.contains(&"_erg_std_prelude.py"));
+    assert!(!names.iter().any(|name| name.starts_with("foo")));
+    // `foo.er` is initialized before `main.er`
+    let manifest = entries.iter().find(|e| e.name == "manifest.txt").unwrap();
+    let manifest = String::from_utf8(manifest.data.clone()).unwrap();
+    assert!(manifest.contains("entry = \"main.pyc\""));
+    assert!(manifest.contains("\"tests/pack/foo.er\",\n    \"tests/pack/main.er\","));
+    let archive = write_archive(&entries);
+    assert_eq!(archive, write_archive(&entries));
+    let path = "tests/pack/main.pyz";
+    std::fs::write(path, archive).unwrap();
+    assert_eq!(exec_pyc(path, None, &[], Stdio::null()), Some(0));
+    std::fs::remove_file(path).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_build_package() -> Result<(), ()> {
+    exec_new_thread(_test_build_package, "test_build_package")
+}
+
+fn _test_build_package() -> Result<(), ()> {
+    let mut cfg = ErgConfig::default();
+    cfg.output = Output::Null;
+    let mut builder = PackageBuilder::load(cfg, Path::new("tests/build_pkg/app/src")).unwrap();
+    assert_eq!(builder.manifest().name, "app");
+    assert_eq!(builder.manifest().version, "0.2.0");
+    // `base` (without `erg.toml`) is a dependency of `util`
+    let deps = builder
+        .dependencies()
+        .iter()
+        .map(|pkg| pkg.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(deps, ["base", "util"]);
+    let path = builder.build().map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    assert!(path.ends_with("build/app.pyc"));
+    let pyc = path.to_str().unwrap();
+    assert_eq!(exec_pyc(pyc, None, &[], Stdio::null()), Some(0));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_build_registry_package() -> Result<(), ()> {
+    exec_new_thread(_test_build_registry_package, "test_build_registry_package")
+}
+
+fn _test_build_registry_package() -> Result<(), ()> {
+    let mut cfg = ErgConfig::default();
+    cfg.output = Output::Null;
+    let store = std::env::temp_dir().join("erg_test_registry_store");
+    let _ = std::fs::remove_dir_all(&store);
+    let manifest = PackageManifest::load(Path::new("tests/build_pkg/registry_app")).unwrap();
+    let registry = Registry::new(manifest.registry.clone().unwrap(), store.clone());
+    // the checksum of v0.2.0 in the index does not match the archive
+    let err = registry.install("greet", "0.2.0").unwrap_err();
+    assert!(err.contains("checksum mismatch"), "{err}");
+    assert!(!registry.package_dir("greet", "0.2.0").exists());
+    let mut builder = PackageBuilder::new_with_registry(cfg, manifest, Some(registry)).unwrap();
+    assert!(builder.dependencies()[0].root.ends_with("greet/0.1.0"));
+    let path = builder.build().map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    let pyc = path.to_str().unwrap();
+    assert_eq!(exec_pyc(pyc, None, &[], Stdio::null()), Some(0));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::remove_dir_all(&store).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_prune_modules() -> Result<(), ()> {
+    exec_new_thread(_test_prune_modules, "test_prune_modules")
+}
+
+fn _test_prune_modules() -> Result<(), ()> {
+    let linked_modules = |prune_modules: bool| {
+        let mut cfg = ErgConfig::with_main_path("tests/prune/main.er".into());
+        cfg.output = Output::Null;
+        cfg.prune_modules = prune_modules;
+        let mut compiler = Compiler::new(cfg);
+        let src = compiler.cfg.input.read();
+        compiler.compile(src, "exec").map_err(|eart| {
+            eart.errors.write_all_stderr();
+        })?;
+        let names = compiler
+            .init_order()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        Ok::<_, ()>(names)
+    };
+    assert_eq!(
+        linked_modules(false)?,
+        ["helper.er", "used.er", "unused.er", "main.er"]
+    );
+    // `unused` is not used in `main.er`, and `helper` is not used in `used.er`
+    assert_eq!(linked_modules(true)?, ["used.er", "main.er"]);
+    Ok(())
+}
+
+#[test]
+fn test_parallel_parse() -> Result<(), ()> {
+    exec_new_thread(_test_parallel_parse, "test_parallel_parse")
+}
+
+fn _test_parallel_parse() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/prune/main.er".into());
+    cfg.output = Output::Null;
+    let shared = SharedCompilerResource::new(cfg.copy());
+    let src = cfg.input.read();
+    let ast = ASTBuilder::new(cfg.copy())
+        .build(src)
+        .map_err(|iart| {
+            iart.errors.write_all_stderr();
+        })?
+        .ast;
+    // `used` and `unused` are imported by `main.er`, and `helper` by `used.er`
+    let prefetcher = ModulePrefetcher::new(cfg.copy(), shared.clone()).with_jobs(2);
+    assert_eq!(prefetcher.prefetch(&ast), 3);
+    assert_eq!(shared.ast_cache.len(), 3);
+    cfg.parallel_parse = true;
+    let mut compiler = Compiler::new_with_cache(cfg, shared.clone());
+    let src = compiler.cfg.input.read();
+    compiler.compile(src, "exec").map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    // the prefetched ASTs are taken out by the checker
+    assert!(shared.ast_cache.is_empty());
+    let names = compiler
+        .init_order()
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["helper.er", "used.er", "unused.er", "main.er"]);
+    Ok(())
+}
+
+#[test]
+fn test_lazy_import() -> Result<(), ()> {
+    exec_new_thread(_test_lazy_import, "test_lazy_import")
+}
+
+fn _test_lazy_import() -> Result<(), ()> {
+    let deferred_modules = |mode: ErgMode| -> Result<Vec<String>, ()> {
+        let mut cfg = ErgConfig::with_main_path("tests/lazy_import/main.er".into());
+        cfg.output = Output::Null;
+        cfg.mode = mode;
+        cfg.lazy_import = true;
+        let shared = SharedCompilerResource::new(cfg.copy());
+        let mut builder = HIRBuilder::new_with_cache(cfg, "<module>", shared.clone());
+        builder.build_module().map_err(|iart| {
+            iart.errors.write_all_stderr();
+        })?;
+        Ok(shared
+            .mod_cache
+            .deferred_paths()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect())
+    };
+    // `used.add` is used in `main.er`, so only `unused` stays deferred
+    assert_eq!(deferred_modules(ErgMode::FullCheck)?, ["unused.er"]);
+    // all the modules are checked before generating code
+    assert!(deferred_modules(ErgMode::Compile)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_strip_docs() -> Result<(), ()> {
+    exec_new_thread(_test_strip_docs, "test_strip_docs")
+}
+
+fn _test_strip_docs() -> Result<(), ()> {
+    fn contains_const(code: &CodeObj, value: &ValueObj) -> bool {
+        code.consts.iter().any(|c| match c {
+            ValueObj::Code(inner) => contains_const(inner, value),
+            other => other == value,
+        })
+    }
+    let has_doc = |strip_docs: bool| {
+        let mut cfg = ErgConfig::with_main_path("tests/docstring.er".into());
+        cfg.output = Output::Null;
+        cfg.strip_docs = strip_docs;
+        let mut compiler = Compiler::new(cfg);
+        let src = compiler.cfg.input.read();
+        let code = compiler
+            .compile(src, "exec")
+            .map_err(|eart| {
+                eart.errors.write_all_stderr();
+            })?
+            .object;
+        let doc = ValueObj::from("Returns twice the given number.");
+        Ok::<_, ()>(contains_const(&code, &doc))
+    };
+    assert!(has_doc(false)?);
+    assert!(!has_doc(true)?);
+    Ok(())
+}
+
+#[test]
+fn test_decl_path() -> Result<(), ()> {
+    exec_new_thread(_test_decl_path, "test_decl_path")
+}
+
+fn _test_decl_path() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/decl_path/main.er".into());
+    cfg.output = Output::Null;
+    cfg.decl_paths.push("tests/decl_path/decls".into());
+    let mut lowerer = ASTLowerer::new(cfg);
+    lowerer.exec().map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    let module = lowerer.pop_mod_ctx().unwrap();
+    // `decls/math.d.er` has priority over `lib/pystd/math.d.er`
+    module.context.assert_var_type("x", &Str)?;
+    Ok(())
+}
+
+#[test]
+fn test_verify_decls() -> Result<(), ()> {
+    exec_new_thread(_test_verify_decls, "test_verify_decls")
+}
+
+fn _test_verify_decls() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/verify_decl/main.er".into());
+    cfg.output = Output::Null;
+    cfg.verify_decls = true;
+    let mut lowerer = ASTLowerer::new(cfg);
+    let status = lowerer.exec().map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    // `add` (arity), `sub` and `Counter.decr!` (not found)
+    assert_eq!(status.num_warns, 3);
+    Ok(())
+}
+
+#[test]
+fn test_compile_time_flags() -> Result<(), ()> {
+    exec_new_thread(_test_compile_time_flags, "test_compile_time_flags")
+}
+
+fn _test_compile_time_flags() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/cfg_flags.er".into());
+    cfg.output = Output::Null;
+    cfg.opt_level = 2;
+    cfg.flags = vec![("backend".into(), "fast".into())];
+    let mut lowerer = ASTLowerer::new(cfg);
+    lowerer.exec().map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    let module = lowerer.pop_mod_ctx().unwrap();
+    let const_value = |name: &str| {
+        let (_, vi) = module.context.get_var_info(name).unwrap();
+        vi.t.singleton_value().cloned()
+    };
+    // `-o 2` is a release build
+    assert_eq!(
+        const_value("Mode"),
+        Some(TyParam::value(ValueObj::Str(Str::ever("release"))))
+    );
+    assert_eq!(
+        const_value("Backend"),
+        Some(TyParam::value(ValueObj::Nat(1)))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_debug_only() -> Result<(), ()> {
+    exec_new_thread(_test_debug_only, "test_debug_only")
+}
+
+fn _test_debug_only() -> Result<(), ()> {
+    let debug_only_calls = |opt_level: u8| {
+        let mut cfg = ErgConfig::with_main_path("tests/debug_only.er".into());
+        cfg.opt_level = opt_level;
+        let ast = ASTBuilder::new(cfg.copy())
+            .build(cfg.input.read())
+            .unwrap()
+            .ast;
+        let art = ASTLowerer::new(cfg).lower(ast, "exec").unwrap();
+        art.object.to_string().matches("debug_only!").count()
+    };
+    // the block with `when := False` is removed even in a debug build
+    assert_eq!(debug_only_calls(1), 1);
+    assert_eq!(debug_only_calls(2), 0);
+    Ok(())
+}
+
+#[test]
+fn test_round_trip() -> Result<(), ()> {
+    exec_new_thread(_test_round_trip, "test_round_trip")
+}
+
+fn _test_round_trip() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/roundtrip.er".into());
+    cfg.output = Output::Null;
+    let src = cfg.input.read();
+    RoundTripChecker::new(cfg)
+        .check(src)
+        .map_err(|err| err.write_all_stderr())?;
+    Ok(())
+}
+
+#[test]
+fn test_fix() -> Result<(), ()> {
+    exec_new_thread(_test_fix, "test_fix")
+}
+
+fn _test_fix() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/fix.er".into());
+    cfg.output = Output::Null;
+    let src = cfg.input.read();
+    let (fixed, fixes) = Fixer::new(cfg).fix(&src);
+    let rules = fixes.iter().map(|fix| fix.rule.name).collect::<Vec<_>>();
+    assert_eq!(rules, ["implicit_mul", "redundant_cast", "redundant_cast"]);
+    assert!(fixed.contains("print! 3 * x + 1\n"));
+    assert!(fixed.contains("a = (n as Int)\n"));
+    // downcasting
+    assert!(fixed.contains("b = typing.cast(Nat, -1)\n"));
+    assert!(fixed.contains("f y: Int = (y + 1)\n"));
+    Ok(())
+}
+
+#[test]
+fn test_minimize_crash() -> Result<(), ()> {
+    let src = "#!feature(implicit_mul)\nx = 1\nf a =\n    y = a\n    y\ng b =\n    todo()\n    b\nz = [\n    1,\n]\n";
+    let mut trials = 0;
+    let minimized = CrashReporter::minimize(src, |src| {
+        trials += 1;
+        src.contains("todo()")
+    });
+    assert_eq!(
+        minimized,
+        "#!feature(implicit_mul)\ng b =\n    todo()\n    b\n"
+    );
+    assert!(trials <= 8);
+    Ok(())
+}
+
+#[test]
+fn test_symbol_id() -> Result<(), ()> {
+    exec_new_thread(_test_symbol_id, "test_symbol_id")
+}
+
+fn _test_symbol_id() -> Result<(), ()> {
+    let dir = std::env::temp_dir().join(format!("erg_symbol_id_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.er");
+    let symbol_ids = |src: &str| {
+        std::fs::write(&path, src).unwrap();
+        let mut cfg = ErgConfig::with_main_path(path.clone());
+        cfg.output = Output::Null;
+        let mut lowerer = ASTLowerer::new(cfg);
+        lowerer.exec().map_err(|errs| errs.write_all_stderr())?;
+        let module = lowerer.pop_mod_ctx().unwrap();
+        let index = module.context.index();
+        let mut locs = index
+            .members()
+            .keys()
+            .filter(|loc| loc.module.as_deref() == Some(path.as_path()))
+            .cloned()
+            .collect::<Vec<_>>();
+        locs.sort_by_key(|loc| loc.loc);
+        let ids = locs
+            .iter()
+            .map(|loc| {
+                let id = index.symbol_id(loc).unwrap();
+                assert_eq!(index.resolve_symbol_id(&id).as_ref(), Some(loc));
+                assert_eq!(id.to_string().parse::<SymbolId>(), Ok(id.clone()));
+                id.to_string()
+            })
+            .collect::<Vec<_>>();
+        Ok::<_, ()>(ids)
+    };
+    let src = "C = Class()\nC.\n    f self = 1\nfor! [1], i =>\n    print! i\nfor! [2], i =>\n    print! i\n";
+    let before = symbol_ids(src)?;
+    // an unrelated edit moves all the definitions
+    let after = symbol_ids(&format!("print! 0\n\n{src}"))?;
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(before, after);
+    let id = |qual_name: &str| format!("{}@{qual_name}", path.display());
+    assert!(before.contains(&id("<module>::C::f#0")), "{before:?}");
+    // lambdas are distinguished by the order of appearance
+    assert!(
+        before.contains(&id("<module>::<lambda>::i#0")),
+        "{before:?}"
+    );
+    assert!(
+        before.contains(&id("<module>::<lambda>::i#1")),
+        "{before:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_strict_int_div() -> Result<(), ()> {
+    exec_new_thread(_test_strict_int_div, "test_strict_int_div")
+}
+
+fn _test_strict_int_div() -> Result<(), ()> {
+    let check = |strictness: Strictness| {
+        let mut cfg = ErgConfig::with_main_path("tests/strict_int_div.er".into());
+        cfg.output = Output::Null;
+        cfg.strictness = strictness;
+        ASTLowerer::new(cfg).exec()
+    };
+    // `/` is always the true division by default
+    check(Strictness::Default).map_err(|errs| errs.write_all_stderr())?;
+    let errs = check(Strictness::Strict).unwrap_err();
+    let lines = errs
+        .iter()
+        .filter_map(|err| err.core.loc.ln_begin())
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [1, 7, 10], "{errs}");
+    Ok(())
+}
+
+#[test]
+fn test_check_py_boundary() -> Result<(), ()> {
+    exec_new_thread(_test_check_py_boundary, "test_check_py_boundary")
+}
+
+fn _test_check_py_boundary() -> Result<(), ()> {
+    let exit_code = |check_py_boundary: bool| {
+        let mut cfg = ErgConfig::with_main_path("tests/py_boundary/main.er".into());
+        cfg.output = Output::Null;
+        cfg.check_py_boundary = check_py_boundary;
+        let mut compiler = Compiler::new(cfg);
+        let src = compiler.cfg.input.read();
+        // `lib` is imported as `tests.py_boundary.lib`
+        let path = "py_boundary.pyc";
+        compiler
+            .compile_and_dump_as_pyc(path, src, "exec")
+            .map_err(|eart| {
+                eart.errors.write_all_stderr();
+            })?;
+        let code = exec_pyc(path, None, &[], Stdio::null());
+        std::fs::remove_file(path).unwrap();
+        Ok::<_, ()>(code)
+    };
+    assert_eq!(exit_code(false)?, Some(0));
+    // `lib.answer` is not an `Int`
+    assert_eq!(exit_code(true)?, Some(1));
+    Ok(())
+}
+
+#[test]
+fn test_dump_py() -> Result<(), ()> {
+    exec_new_thread(_test_dump_py, "test_dump_py")
+}
+
+/// The transpiled script must behave the same as the bytecode compiled in the same invocation.
+fn _test_dump_py() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/dump_py.er".into());
+    cfg.output = Output::Null;
+    cfg.dump_py = true;
+    let mut compiler = Compiler::new(cfg);
+    let src = compiler.cfg.input.read();
+    let pyc_path = "dump_py.pyc";
+    compiler
+        .compile_and_dump_as_pyc(pyc_path, src, "exec")
+        .map_err(|eart| {
+            eart.errors.write_all_stderr();
+        })?;
+    let py_path = compiler.cfg.dump_py_path();
+    let python = opt_which_python().unwrap();
+    let run = |path: &Path| {
+        let out = Command::new(&python).arg(path).output().unwrap();
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+    let pyc_out = run(Path::new(pyc_path));
+    let py_out = run(&py_path);
+    std::fs::remove_file(pyc_path).unwrap();
+    std::fs::remove_file(py_path).unwrap();
+    assert_eq!(pyc_out, "3 Hello, world!\n3 2\n6\nbig\n");
+    assert_eq!(py_out, pyc_out);
+    Ok(())
+}
+
+#[test]
+fn test_import_suggestion() -> Result<(), ()> {
+    exec_new_thread(_test_import_suggestion, "test_import_suggestion")
+}
+
+fn _test_import_suggestion() -> Result<(), ()> {
+    let errs = load_file("tests/import_suggestion.er").unwrap_err();
+    let hints = errs
+        .iter()
+        .filter_map(|err| err.core.get_hint())
+        .collect::<Vec<_>>();
+    assert_eq!(hints.len(), 2, "{errs}");
+    assert!(hints[0].contains(r#"random = pyimport "random""#), "{errs}");
+    assert!(hints[1].contains(r#"{sqrt;} = pyimport "math""#), "{errs}");
+    Ok(())
+}
+
+#[test]
+fn test_error_codes() -> Result<(), ()> {
+    exec_new_thread(_test_error_codes, "test_error_codes")
+}
+
+/// The codes must be unique, and each example must raise the error it explains.
+fn _test_error_codes() -> Result<(), ()> {
+    assert!(ERROR_CODES.windows(2).all(|w| w[0].code < w[1].code));
+    for entry in ERROR_CODES.iter() {
+        let Some(example) = entry.example else {
+            continue;
+        };
+        let mut cfg = ErgConfig {
+            input: Input::str(example.to_string()),
+            ..ErgConfig::default()
+        };
+        cfg.output = Output::Null;
+        let ast = ASTBuilder::new(cfg.copy())
+            .build(example.to_string())
+            .unwrap()
+            .ast;
+        // some codes (e.g. `E0040`) are warnings
+        let errs = match ASTLowerer::new(cfg).lower(ast, "exec") {
+            Ok(art) => CompileErrors::from(art.warns),
+            Err(iart) => {
+                let mut errs = CompileErrors::from(iart.errors);
+                errs.extend(CompileErrors::from(iart.warns));
+                errs
+            }
+        };
+        assert!(
+            errs.iter().any(|err| err.core.code == Some(entry.code)),
+            "{}: {errs}",
+            entry.code
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_max_type_depth() -> Result<(), ()> {
+    exec_new_thread(_test_max_type_depth, "test_max_type_depth")
+}
+
+/// A type nested more deeply than `max_type_depth` is reported as too complex (not a crash).
+fn _test_max_type_depth() -> Result<(), ()> {
+    let code = "f|T <: Add(T)|(x: T): T.Output = x + x\nprint! f(1)";
+    let lower = |max_type_depth| {
+        let cfg = ErgConfig {
+            input: Input::str(code.to_string()),
+            output: Output::Null,
+            max_type_depth,
+            ..ErgConfig::default()
+        };
+        let ast = ASTBuilder::new(cfg.copy())
+            .build(code.to_string())
+            .unwrap()
+            .ast;
+        ASTLowerer::new(cfg).lower(ast, "exec")
+    };
+    assert!(lower(ErgConfig::default().max_type_depth).is_ok());
+    let errs = lower(2).unwrap_err().errors;
+    assert!(
+        errs.iter()
+            .any(|err| err.core.code == Some(TYPE_TOO_COMPLEX)),
+        "{errs}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_odd_types() -> Result<(), ()> {
+    exec_new_thread(_test_odd_types, "test_odd_types")
+}
+
+/// Ill-formed types and type-level calls must be reported as errors, not panic the compiler.
+fn _test_odd_types() -> Result<(), ()> {
+    let codes = [
+        "T = [Int, Str]\nU = T[\"a\"]",
+        "T = [Int, Str]\nU = T[-1]",
+        "T = [Int, Str]\nU = T[2]",
+        "U = [][0]",
+        "f|N: Nat|(a: [Int; N]) = a[0]\nprint! f [1, 2]",
+        "C = Class()\nC|<: Add(C)|.\n    Output = 1\n    __add__ self, _ = self\nc = C.new() + C.new()",
+        "f x = x x",
+    ];
+    for code in codes {
+        let cfg = ErgConfig {
+            input: Input::str(code.to_string()),
+            output: Output::Null,
+            ..ErgConfig::default()
+        };
+        let Ok(art) = ASTBuilder::new(cfg.copy()).build(code.to_string()) else {
+            continue;
+        };
+        // only the absence of panics is checked
+        let _ = ASTLowerer::new(cfg).lower(art.ast, "exec");
     }
     Ok(())
 }
+
+#[test]
+fn test_no_panic() -> Result<(), ()> {
+    exec_new_thread(_test_no_panic, "test_no_panic")
+}
+
+fn _test_no_panic() -> Result<(), ()> {
+    // `[x; n]` with a non-integer length is not supported by the lowerer yet
+    let code = "x = 1\na = [1; \"a\"]\n";
+    let cfg = ErgConfig {
+        input: Input::str(code.to_string()),
+        output: Output::Null,
+        no_panic: true,
+        ..ErgConfig::default()
+    };
+    let ast = ASTBuilder::new(cfg.copy())
+        .build(code.to_string())
+        .unwrap()
+        .ast;
+    let Err(art) = ASTLowerer::new(cfg).lower(ast, "exec") else {
+        panic!("the lowering should fail");
+    };
+    let ice = art
+        .errors
+        .iter()
+        .find(|err| err.core.code == Some(COMPILER_BUG))
+        .unwrap();
+    assert!(ice.core.sub_messages.iter().any(|sub| sub
+        .get_msg()
+        .iter()
+        .any(|hint| hint.starts_with("AST:") && hint.contains("[1; \"a\"]"))));
+    Ok(())
+}
+
+#[test]
+fn test_type_hole() -> Result<(), ()> {
+    exec_new_thread(_test_type_hole, "test_type_hole")
+}
+
+fn _test_type_hole() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/type_hole.er".into());
+    let ast = ASTBuilder::new(cfg.copy())
+        .build(cfg.input.read())
+        .unwrap()
+        .ast;
+    let art = ASTLowerer::new(cfg).lower(ast, "exec").unwrap();
+    let holes = art
+        .warns
+        .iter()
+        .filter(|warn| warn.core.code == Some(TYPE_HOLE))
+        .map(|warn| remove_style(&warn.core.main_message))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        holes,
+        [
+            "the type of the hole is inferred as Int",
+            "the type of the hole is inferred as Int",
+            "the type of the hole is inferred as Str",
+            "the type of the hole is inferred as Str",
+        ],
+        "{}",
+        art.warns
+    );
+    Ok(())
+}
+
+#[test]
+fn test_watch() -> Result<(), ()> {
+    exec_new_thread(_test_watch, "test_watch")
+}
+
+fn _test_watch() -> Result<(), ()> {
+    let dir = std::env::temp_dir().join(format!("erg_watch_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("main.er"),
+        "a = import \"a\"\nb = import \"b\"\nprint! a.x + b.y\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("a.er"), ".x = 1\n").unwrap();
+    std::fs::write(dir.join("b.er"), ".y = 2\n").unwrap();
+    let mut cfg = ErgConfig::with_main_path(dir.join("main.er"));
+    cfg.output = Output::Null;
+    let mut watcher = Watcher::new(cfg);
+    assert!(watcher.check().unwrap().is_ok());
+    assert_eq!(watcher.watched_files().count(), 3);
+    assert!(watcher.modified_files().is_empty());
+    let mod_id = |watcher: &Watcher, name: &str| {
+        let path = NormalizedPathBuf::from(dir.join(name));
+        watcher.shared().mod_cache.get(&path).map(|entry| entry.id)
+    };
+    let (a_id, b_id) = (mod_id(&watcher, "a.er"), mod_id(&watcher, "b.er"));
+    let b = std::fs::File::options()
+        .write(true)
+        .truncate(true)
+        .open(dir.join("b.er"))
+        .unwrap();
+    std::io::Write::write_all(&mut &b, b".y = \"a\"\n").unwrap();
+    b.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(1))
+        .unwrap();
+    let modified = watcher.modified_files();
+    assert_eq!(modified, [NormalizedPathBuf::from(dir.join("b.er"))]);
+    watcher.invalidate(&modified);
+    let res = watcher.check().unwrap();
+    // `a` is reused, and `b` is checked again
+    let (new_a_id, new_b_id) = (mod_id(&watcher, "a.er"), mod_id(&watcher, "b.er"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(res.is_err());
+    assert!(a_id.is_some() && b_id.is_some());
+    assert_eq!(a_id, new_a_id);
+    assert_ne!(b_id, new_b_id);
+    assert!(watcher.modified_files().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_watch_include() -> Result<(), ()> {
+    exec_new_thread(_test_watch_include, "test_watch_include")
+}
+
+fn _test_watch_include() -> Result<(), ()> {
+    let dir = std::env::temp_dir().join(format!("erg_watch_include_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.er"), "a = import \"a\"\nprint! a.s\n").unwrap();
+    std::fs::write(dir.join("a.er"), ".s = include_str \"data.txt\"\n").unwrap();
+    std::fs::write(dir.join("data.txt"), "hello").unwrap();
+    let mut cfg = ErgConfig::with_main_path(dir.join("main.er"));
+    cfg.output = Output::Null;
+    let mut watcher = Watcher::new(cfg);
+    assert!(watcher.check().unwrap().is_ok());
+    // main.er, a.er and data.txt
+    assert_eq!(watcher.watched_files().count(), 3);
+    let mod_id = |watcher: &Watcher| {
+        let path = NormalizedPathBuf::from(dir.join("a.er"));
+        watcher.shared().mod_cache.get(&path).map(|entry| entry.id)
+    };
+    let a_id = mod_id(&watcher);
+    let data = std::fs::File::options()
+        .write(true)
+        .truncate(true)
+        .open(dir.join("data.txt"))
+        .unwrap();
+    std::io::Write::write_all(&mut &data, b"world").unwrap();
+    data.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(1))
+        .unwrap();
+    let modified = watcher.modified_files();
+    assert_eq!(modified, [NormalizedPathBuf::from(dir.join("data.txt"))]);
+    watcher.invalidate(&modified);
+    let res = watcher.check().unwrap();
+    // `a` includes the modified file, so it is checked again
+    let new_a_id = mod_id(&watcher);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(res.is_ok());
+    assert!(a_id.is_some());
+    assert_ne!(a_id, new_a_id);
+    assert!(watcher.modified_files().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_shared_builtins() -> Result<(), ()> {
+    exec_new_thread(_test_shared_builtins, "test_shared_builtins")
+}
+
+fn _test_shared_builtins() -> Result<(), ()> {
+    let code = "C = Class { .x = Int }\nC|<: Add(C)|.\n    Output = C\n    __add__ self, other: C = C.new { .x = self.x + other.x }\nc = C.new({ .x = 1 }) + C.new({ .x = 2 })\nprint! c.x + 1\n";
+    let template = SharedCompilerResource::new(ErgConfig::default());
+    let builtins = |shared: &SharedCompilerResource| {
+        shared.mod_cache.get_ctx(Path::new("<builtins>")).unwrap()
+    };
+    let mut sizes = vec![];
+    for _ in 0..2 {
+        let mut cfg = ErgConfig::string(code.into());
+        cfg.output = Output::Null;
+        let shared = template.with_builtins_of(cfg.copy());
+        assert!(std::sync::Arc::ptr_eq(
+            &builtins(&template),
+            &builtins(&shared)
+        ));
+        let mut builder = HIRBuilder::new_with_cache(cfg, "<module>", shared.clone());
+        let art = builder.build(code.into(), "exec");
+        assert!(art.is_ok(), "{}", art.unwrap_err().errors);
+        sizes.push(builder.pop_mod_ctx().unwrap().context.approx_size());
+    }
+    // the modules checked with the shared builtins are independent
+    assert_eq!(sizes[0], sizes[1]);
+    let sizes = builtins(&template).context.table_sizes();
+    assert!(sizes.windows(2).all(|w| w[0].bytes >= w[1].bytes));
+    assert!(sizes
+        .iter()
+        .any(|size| size.field == "mono_types" && size.len > 0));
+    Ok(())
+}
+
+#[test]
+fn test_test_runner() -> Result<(), ()> {
+    exec_new_thread(_test_test_runner, "test_test_runner")
+}
+
+fn _test_test_runner() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/test_runner/main.er".into());
+    cfg.output = Output::Null;
+    let results = TestRunner::new(cfg).run_tests().map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    // the tests of `math.er` (imported by `main.er`) are run first
+    // `helper` and `test_helper` take arguments
+    let summary = results
+        .iter()
+        .map(|res| (&res.case.name[..], res.passed()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            ("test_mul", true),
+            ("test_div", false),
+            ("test_bare_assert", false),
+            ("test_add", true),
+            ("checks_sub", false),
+            ("test_public!", true),
+        ]
+    );
+    // the failures are reported at the Erg source
+    let failures = results
+        .iter()
+        .filter_map(|res| res.failure.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(failures[0].core.kind, ErrorKind::ZeroDivisionError);
+    assert_eq!(failures[0].core.loc, Location::range(7, 8, 7, 14));
+    assert!(failures[0].input.path().unwrap().ends_with("math.er"));
+    assert_eq!(failures[1].core.kind, ErrorKind::AssertionError);
+    assert_eq!(failures[1].core.loc, Location::range(11, 4, 11, 16));
+    assert_eq!(failures[2].core.loc.ln_begin(), Some(10));
+    assert_eq!(failures[2].core.main_message, "3 - 1 should be 2");
+    assert_eq!(failures[2].caused_by, "checks_sub");
+    Ok(())
+}
+
+#[test]
+fn test_py_exception_map() -> Result<(), ()> {
+    exec_new_thread(_test_py_exception_map, "test_py_exception_map")
+}
+
+fn _test_py_exception_map() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/py_exceptions/main.er".into());
+    cfg.output = Output::Null;
+    let mut compiler = Compiler::new(cfg);
+    let src = compiler.cfg.input.read();
+    compiler.compile(src, "exec").map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    let py_exceptions = &compiler.shared().py_exceptions;
+    // `.FooError <: ValueError` is declared in `errs.d.er`
+    assert_eq!(
+        py_exceptions.get("errs.FooError"),
+        Some(ErrorKind::ValueError)
+    );
+    assert_eq!(py_exceptions.get("errs.BarError"), None);
+    assert_eq!(py_exceptions.get("KeyError"), Some(ErrorKind::KeyError));
+    // the nearest known class in the MRO is used
+    assert_eq!(
+        py_exceptions.resolve(&["errs.BarError", "Exception", "BaseException"]),
+        ErrorKind::Exception
+    );
+    assert_eq!(
+        py_exceptions.resolve(&["errs.FooError", "errs.BarError"]),
+        ErrorKind::ValueError
+    );
+    assert_eq!(
+        py_exceptions.resolve(&["errs.BazError"]),
+        ErrorKind::UserException
+    );
+    // the exceptions raised by the `pyimport`ed module are reported with the mapped kinds
+    let mut cfg = ErgConfig::with_main_path("tests/py_exceptions/main.er".into());
+    cfg.output = Output::Null;
+    let results = TestRunner::new(cfg).run_tests().map_err(|eart| {
+        eart.errors.write_all_stderr();
+    })?;
+    let kinds = results
+        .iter()
+        .map(|res| res.failure.as_ref().map(|err| err.core.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [Some(ErrorKind::ValueError), Some(ErrorKind::Exception)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_py_exception_traceback() -> Result<(), ()> {
+    exec_new_thread(_test_py_exception_traceback, "test_py_exception_traceback")
+}
+
+/// The uncaught exceptions raised by a `pyimport`ed module are reported with the mapped kinds.
+fn _test_py_exception_traceback() -> Result<(), ()> {
+    let mut cfg = ErgConfig::with_main_path("tests/py_exceptions/raise.er".into());
+    cfg.output = Output::Null;
+    let mut compiler = Compiler::new(cfg);
+    let src = compiler.cfg.input.read();
+    let path = "py_exceptions.pyc";
+    compiler
+        .compile_and_dump_as_pyc(path, src, "exec")
+        .map_err(|eart| {
+            eart.errors.write_all_stderr();
+        })?;
+    let out = Command::new(opt_which_python().unwrap())
+        .arg(path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.ends_with("errs.FooError (ValueError): foo\n"),
+        "{stderr}"
+    );
+    Ok(())
+}
@@ -0,0 +1,12 @@
+def add(x, y):
+    return x + y
+
+def greet(name, greeting="Hello"):
+    return f"{greeting}, {name}"
+
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+    def incr(self):
+        self.count += 1
@@ -20,12 +20,14 @@ use crate::context::{Context, ContextProvider, ModuleContext};
 use crate::desugar_hir::HIRDesugarer;
 use crate::error::{CompileError, CompileErrors};
 use crate::hir::{
-    Accessor, Args, Array, BinOp, Block, Call, ClassDef, Def, Dict, Expr, Identifier, Lambda,
-    Literal, Params, PatchDef, ReDef, Record, Set, Signature, Tuple, UnaryOp, HIR,
+    Accessor, Args, Array, BinOp, Block, Call, ClassDef, Coercion, Def, Dict, Expr, Identifier,
+    Lambda, Literal, Params, PatchDef, ReDef, Record, Set, Signature, Tuple, UnaryOp, HIR,
 };
+use crate::init_order::InitOrderAnalyzer;
 use crate::link_hir::HIRLinker;
 use crate::module::SharedCompilerResource;
-use crate::ty::value::ValueObj;
+use crate::prune::ModulePruner;
+use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::Type;
 use crate::varinfo::VarInfo;
 
@@ -144,8 +146,7 @@ impl Runnable for Transpiler {
     }
 
     fn exec(&mut self) -> Result<ExitStatus, Self::Errs> {
-        let mut path = self.cfg.dump_path();
-        path.set_extension("py");
+        let path = self.cfg.dump_py_path();
         let src = self.cfg.input.read();
         let artifact = self.transpile(src, "exec").map_err(|eart| {
             eart.warns.write_all_stderr();
@@ -233,7 +234,13 @@ impl Transpiler {
         src: String,
         mode: &str,
     ) -> Result<CompleteArtifact, ErrorArtifact> {
-        let artifact = self.builder.build(src, mode)?;
+        let mut artifact = self.builder.build(src, mode)?;
+        if self.cfg.prune_modules && !self.cfg.input.is_repl() {
+            ModulePruner::new(&self.cfg, &self.shared).prune(&mut artifact.object);
+        }
+        InitOrderAnalyzer::new(&self.cfg, &self.shared.mod_cache)
+            .analyze(&artifact.object)
+            .map_err(|errs| ErrorArtifact::new(errs, artifact.warns.clone()))?;
         let linker = HIRLinker::new(&self.cfg, &self.shared.mod_cache);
         let hir = linker.link(artifact.object);
         let desugared = HIRDesugarer::desugar(hir);
@@ -264,6 +271,9 @@ pub struct ScriptGenerator {
     namedtuple_loaded: bool,
     mutate_op_loaded: bool,
     in_op_loaded: bool,
+    record_ops_loaded: bool,
+    checked_getitem_loaded: bool,
+    derive_loaded: bool,
     range_ops_loaded: bool,
     builtin_types_loaded: bool,
     builtin_control_loaded: bool,
@@ -279,6 +289,9 @@ impl ScriptGenerator {
             namedtuple_loaded: false,
             mutate_op_loaded: false,
             in_op_loaded: false,
+            record_ops_loaded: false,
+            checked_getitem_loaded: false,
+            derive_loaded: false,
             range_ops_loaded: false,
             builtin_types_loaded: false,
             builtin_control_loaded: false,
@@ -347,6 +360,27 @@ impl ScriptGenerator {
         }
     }
 
+    fn load_record_ops_if_not(&mut self) {
+        if !self.record_ops_loaded {
+            self.prelude += &Self::replace_import(include_str!("lib/std/_erg_record.py"));
+            self.record_ops_loaded = true;
+        }
+    }
+
+    fn load_checked_getitem_if_not(&mut self) {
+        if !self.checked_getitem_loaded {
+            self.prelude += &Self::replace_import(include_str!("lib/std/_erg_index.py"));
+            self.checked_getitem_loaded = true;
+        }
+    }
+
+    fn load_derive_if_not(&mut self) {
+        if !self.derive_loaded {
+            self.prelude += &Self::replace_import(include_str!("lib/std/_erg_derive.py"));
+            self.derive_loaded = true;
+        }
+    }
+
     fn load_mutate_op_if_not(&mut self) {
         if !self.mutate_op_loaded {
             self.prelude += &Self::replace_import(include_str!("lib/std/_erg_mutate_operator.py"));
@@ -404,6 +438,8 @@ impl ScriptGenerator {
 
     fn transpile_expr(&mut self, expr: Expr) -> String {
         match expr {
+            // the doc comments are emitted as docstrings (see `transpile_docstring`)
+            Expr::Lit(lit) if lit.is_doc_comment() => "None".to_string(),
             Expr::Lit(lit) => self.transpile_lit(lit),
             Expr::Call(call) => self.transpile_call(call),
             Expr::BinOp(bin) => self.transpile_binop(bin),
@@ -431,6 +467,7 @@ impl ScriptGenerator {
                 other => todo!("transpiling {other}"),
             },
             Expr::Record(rec) => self.transpile_record(rec),
+            Expr::Coerce(coerce) => self.transpile_coercion(coerce),
             Expr::Tuple(tuple) => match tuple {
                 Tuple::Normal(tup) => {
                     let mut code = "(".to_string();
@@ -487,10 +524,24 @@ impl ScriptGenerator {
     }
 
     fn transpile_lit(&mut self, lit: Literal) -> String {
+        // the token of an interpolated string is a fragment (e.g. `"\{`, `}, world!"`)
+        if let ValueObj::Str(s) = &lit.value {
+            self.load_builtin_types_if_not();
+            return format!("Str(\"{}\")", s.escape());
+        }
+        // embedded by `include_bytes` (the display of `Bytes` is a valid Python bytes literal)
+        if let ValueObj::Bytes(_) = &lit.value {
+            return lit.value.to_string();
+        }
+        // `Ast`s are the strings at runtime (see `ValueObj::Ast`)
+        if let ValueObj::Ast(expr) = &lit.value {
+            self.load_builtin_types_if_not();
+            return format!("Str(\"{}\")", Str::from(expr.to_string()).escape());
+        }
         let escaped = Self::escape_str(&lit.token.content);
         if matches!(
             &lit.value,
-            ValueObj::Bool(_) | ValueObj::Int(_) | ValueObj::Nat(_) | ValueObj::Str(_)
+            ValueObj::Bool(_) | ValueObj::Int(_) | ValueObj::Nat(_)
         ) {
             self.load_builtin_types_if_not();
             format!("{}({escaped})", lit.value.class())
@@ -522,7 +573,34 @@ impl ScriptGenerator {
         format!("NamedTuple__('Record', {attrs}){values}")
     }
 
+    /// `{.x = 1; .y = 2}` (as `{.x = Int}`) -> `{.x = 1}`
+    fn transpile_coercion(&mut self, coerce: Coercion) -> String {
+        let Some(fields) = coerce.record_fields() else {
+            return self.transpile_expr(*coerce.expr);
+        };
+        self.load_namedtuple_if_not();
+        let mut attrs = "[".to_string();
+        let mut values = "(".to_string();
+        for field in fields.keys() {
+            attrs += &format!("'{}',", field.symbol);
+            values += &format!("rec__.{},", field.symbol);
+        }
+        attrs += "]";
+        values += ")";
+        let expr = self.transpile_expr(*coerce.expr);
+        format!("(lambda rec__: NamedTuple__('Record', {attrs}){values})({expr})")
+    }
+
     fn transpile_binop(&mut self, bin: BinOp) -> String {
+        if let Some(py_name @ ("record_merge" | "record_remove")) = bin.info.py_name.as_deref() {
+            let mut code = format!("{py_name}(");
+            self.load_record_ops_if_not();
+            code += &self.transpile_expr(*bin.lhs);
+            code.push(',');
+            code += &self.transpile_expr(*bin.rhs);
+            code.push(')');
+            return code;
+        }
         match bin.op.kind {
             TokenKind::Closed | TokenKind::LeftOpen | TokenKind::RightOpen | TokenKind::Open => {
                 self.load_range_ops_if_not();
@@ -713,9 +791,14 @@ impl ScriptGenerator {
             self.level += 1;
             code += &"    ".repeat(self.level);
             let target = arm.params.non_defaults.get(0).unwrap();
+            let guard = arm
+                .guard
+                .map(|guard| format!(" if {}", self.transpile_expr(*guard)))
+                .unwrap_or_default();
             match &target.raw.pat {
                 ParamPattern::VarName(param) => {
-                    code += &format!("case {}__:\n", &param.token().content);
+                    let param = replace_non_symbolic(&param.token().content);
+                    code += &format!("case {param}__{guard}:\n");
                     code += &self.transpile_block(arm.body, StoreTmp(tmp.clone()));
                     self.level -= 1;
                 }
@@ -724,14 +807,14 @@ impl ScriptGenerator {
                         Some(TypeSpec::Enum(enum_t)) => {
                             let values = ValueObj::vec_from_const_args(enum_t.clone());
                             if values.len() == 1 {
-                                code += &format!("case {}:\n", values[0]);
+                                code += &format!("case {}{guard}:\n", values[0]);
                             } else {
                                 todo!()
                             }
                         }
                         Some(_) => todo!(),
                         None => {
-                            code += "case _:\n";
+                            code += &format!("case _{guard}:\n");
                         }
                     }
                     code += &self.transpile_block(arm.body, StoreTmp(tmp.clone()));
@@ -750,6 +833,14 @@ impl ScriptGenerator {
     fn transpile_simple_call(&mut self, call: Call) -> String {
         let is_py_api = if let Some(attr) = &call.attr_name {
             let is_py_api = attr.is_py_api();
+            if attr.vi.py_name.as_deref() == Some("checked_getitem") {
+                self.load_checked_getitem_if_not();
+                return format!(
+                    "checked_getitem({}, {})",
+                    self.transpile_expr(*call.obj),
+                    self.transpile_args(call.args, is_py_api, false)
+                );
+            }
             if let Some(name) = debind(attr) {
                 let name = demangle(&name);
                 return format!(
@@ -915,15 +1006,28 @@ impl ScriptGenerator {
                     Self::transpile_ident(subr.ident),
                     self.transpile_params(subr.params)
                 );
+                code += &self.transpile_docstring(def.doc);
                 code += &self.transpile_block(def.body.block, Return);
                 code
             }
         }
     }
 
+    fn transpile_docstring(&self, doc: Option<Str>) -> String {
+        let Some(doc) = doc else {
+            return String::new();
+        };
+        format!(
+            "{}'{}'\n",
+            "    ".repeat(self.level + 1),
+            Self::escape_str(&doc.replace('\\', "\\\\"))
+        )
+    }
+
     fn transpile_classdef(&mut self, classdef: ClassDef) -> String {
         let class_name = Self::transpile_ident(classdef.sig.into_ident());
         let mut code = format!("class {class_name}():\n");
+        code += &self.transpile_docstring(classdef.doc);
         let mut init_method = format!(
             "{}def __init__(self, param__):\n",
             "    ".repeat(self.level + 1)
@@ -948,9 +1052,38 @@ impl ScriptGenerator {
             code += &format!("def new(x): return {class_name}.__call__(x)\n");
         }
         code += &self.transpile_block(classdef.methods, Discard);
+        if let Some(derive) = classdef.obj.derive() {
+            code += &self.transpile_derive(&class_name, derive.typ(), &classdef.obj);
+        }
         code
     }
 
+    /// see `_erg_derive.py`
+    fn transpile_derive(&mut self, class_name: &str, derive: &Type, obj: &GenTypeObj) -> String {
+        self.load_derive_if_not();
+        let mut traits = String::new();
+        for t in derive.intersection_types() {
+            traits += &format!("\"{}\",", t.qual_name());
+        }
+        let mut fields = String::new();
+        if let Some(TypeObj::Builtin {
+            t: Type::Record(rec),
+            ..
+        }) = obj.base_or_sup()
+        {
+            let mut keys = rec.keys().collect::<Vec<_>>();
+            keys.sort_by(|l, r| l.symbol.cmp(&r.symbol));
+            for field in keys {
+                let vis = if field.vis.is_private() { "__" } else { "" };
+                fields += &format!("(\"{0}\", \"{0}{vis}\"),", field.symbol);
+            }
+        }
+        format!(
+            "\n{}derive({class_name}, ({traits}), ({fields}))\n",
+            "    ".repeat(self.level)
+        )
+    }
+
     fn transpile_patchdef(&mut self, patch_def: PatchDef) -> String {
         let mut code = String::new();
         for chunk in patch_def.methods.into_iter() {
@@ -16,22 +16,12 @@ use erg_common::serialize::*;
 use erg_common::Str;
 
 use super::deserialize::{DeserializeResult, Deserializer};
+use super::serialize::{SerializeStats, Serializer};
 use super::value::ValueObj;
 use super::{HasType, Type, TypePair};
 
 pub fn consts_into_bytes(consts: Vec<ValueObj>, python_ver: PythonVersion) -> Vec<u8> {
-    let mut tuple = vec![];
-    if consts.len() > u8::MAX as usize {
-        tuple.push(DataTypePrefix::Tuple as u8);
-        tuple.append(&mut (consts.len() as u32).to_le_bytes().to_vec());
-    } else {
-        tuple.push(DataTypePrefix::SmallTuple as u8);
-        tuple.push(consts.len() as u8);
-    }
-    for obj in consts {
-        tuple.append(&mut obj.into_bytes(python_ver));
-    }
-    tuple
+    Serializer::new(python_ver).serialize_consts(consts)
 }
 
 pub fn jump_abs_addr(minor_ver: u8, op: u8, idx: usize, arg: usize) -> usize {
@@ -79,6 +69,54 @@ fn jump_abs_addr_311(op: Opcode311, idx: usize, arg: usize) -> usize {
     }
 }
 
+/// A source position of an instruction (`(line, end_line, col, end_col)`), same as the elements of `co_positions()`
+pub type CodePosition = (Option<u32>, Option<u32>, Option<u32>, Option<u32>);
+
+// location table (Python 3.11~): see Objects/locations.md in CPython for details
+const LOCATION_SHORT_FORM_MAX: u8 = 9;
+const LOCATION_ONE_LINE_FORM: u8 = 10;
+const LOCATION_NO_COLUMNS: u8 = 13;
+const LOCATION_LONG_FORM: u8 = 14;
+const LOCATION_NONE: u8 = 15;
+
+fn write_varint(table: &mut Vec<u8>, mut val: u32) {
+    while val >= 64 {
+        table.push(0x40 | (val & 63) as u8);
+        val >>= 6;
+    }
+    table.push(val as u8);
+}
+
+fn write_svarint(table: &mut Vec<u8>, val: i64) {
+    let val = if val < 0 {
+        ((-val as u32) << 1) | 1
+    } else {
+        (val as u32) << 1
+    };
+    write_varint(table, val);
+}
+
+fn read_varint(iter: &mut impl Iterator<Item = u8>) -> u32 {
+    let mut byte = iter.next().unwrap_or(0);
+    let mut val = (byte & 63) as u32;
+    let mut shift = 0;
+    while byte & 64 != 0 {
+        byte = iter.next().unwrap_or(0);
+        shift += 6;
+        val |= ((byte & 63) as u32) << shift;
+    }
+    val
+}
+
+fn read_svarint(iter: &mut impl Iterator<Item = u8>) -> i64 {
+    let val = read_varint(iter);
+    if val & 1 != 0 {
+        -((val >> 1) as i64)
+    } else {
+        (val >> 1) as i64
+    }
+}
+
 /// Kind can be multiple (e.g. Local + Cell = 0x60)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -356,76 +394,22 @@ impl CodeObj {
     }
 
     pub fn into_bytes(self, python_ver: PythonVersion) -> Vec<u8> {
-        let mut bytes = vec![DataTypePrefix::Code as u8];
-        bytes.append(&mut self.argcount.to_le_bytes().to_vec());
-        if python_ver.minor >= Some(8) {
-            bytes.append(&mut self.posonlyargcount.to_le_bytes().to_vec());
-        }
-        bytes.append(&mut self.kwonlyargcount.to_le_bytes().to_vec());
-        if python_ver.minor < Some(11) {
-            bytes.append(&mut self.nlocals.to_le_bytes().to_vec());
-        }
-        bytes.append(&mut self.stacksize.to_le_bytes().to_vec());
-        bytes.append(&mut self.flags.to_le_bytes().to_vec());
-        // co_code is represented as PyStrObject (Not Ascii, Unicode)
-        bytes.append(&mut raw_string_into_bytes(self.code));
-        bytes.append(&mut consts_into_bytes(self.consts, python_ver)); // write as PyTupleObject
-        bytes.append(&mut strs_into_bytes(self.names));
-        Self::dump_locals(
-            self.varnames,
-            self.freevars,
-            self.cellvars,
-            &mut bytes,
-            python_ver,
-        );
-        bytes.append(&mut str_into_bytes(self.filename, false));
-        bytes.append(&mut str_into_bytes(self.name, true));
-        if python_ver.minor >= Some(11) {
-            bytes.append(&mut str_into_bytes(self.qualname, true));
-        }
-        bytes.append(&mut self.firstlineno.to_le_bytes().to_vec());
-        // lnotab is represented as PyStrObject
-        bytes.append(&mut raw_string_into_bytes(self.lnotab));
-        if python_ver.minor >= Some(11) {
-            bytes.append(&mut raw_string_into_bytes(self.exceptiontable));
-        }
-        bytes
-    }
-
-    fn dump_locals(
-        varnames: Vec<Str>,
-        freevars: Vec<Str>,
-        cellvars: Vec<Str>,
-        bytes: &mut Vec<u8>,
-        python_ver: PythonVersion,
-    ) {
-        if python_ver.minor >= Some(11) {
-            let varnames = varnames
-                .into_iter()
-                .filter(|n| !freevars.contains(n) && !cellvars.contains(n))
-                .collect::<Vec<_>>();
-            let localspluskinds = [
-                vec![FastKind::Local as u8; varnames.len()],
-                vec![FastKind::Free as u8; freevars.len()],
-                vec![FastKind::Cell as u8 + FastKind::Local as u8; cellvars.len()],
-            ]
-            .concat();
-            let localsplusnames = [varnames, freevars, cellvars].concat();
-            bytes.append(&mut strs_into_bytes(localsplusnames));
-            bytes.append(&mut raw_string_into_bytes(localspluskinds));
-        } else {
-            bytes.append(&mut strs_into_bytes(varnames));
-            bytes.append(&mut strs_into_bytes(freevars));
-            bytes.append(&mut strs_into_bytes(cellvars));
-        }
+        Serializer::new(python_ver).serialize_code(self)
     }
 
     pub fn dump_as_pyc<P: AsRef<Path>>(
         self,
         path: P,
         py_magic_num: Option<u32>,
-    ) -> std::io::Result<()> {
+    ) -> std::io::Result<SerializeStats> {
         let mut file = File::create(path)?;
+        let (bytes, stats) = self.into_pyc_bytes(py_magic_num);
+        file.write_all(&bytes[..])?;
+        Ok(stats)
+    }
+
+    /// Returns the contents of a `.pyc` file (the header + the serialized code object).
+    pub fn into_pyc_bytes(self, py_magic_num: Option<u32>) -> (Vec<u8>, SerializeStats) {
         let mut bytes = Vec::with_capacity(16);
         let py_magic_num = py_magic_num.unwrap_or_else(env_magic_number);
         let python_ver = get_ver_from_magic_num(py_magic_num);
@@ -433,9 +417,104 @@ impl CodeObj {
         bytes.append(&mut vec![0; 4]); // padding
         bytes.append(&mut get_timestamp_bytes().to_vec());
         bytes.append(&mut vec![0; 4]); // padding
-        bytes.append(&mut self.into_bytes(python_ver));
-        file.write_all(&bytes[..])?;
-        Ok(())
+        let mut ser = Serializer::new(python_ver);
+        bytes.append(&mut ser.serialize_code(self));
+        (bytes, ser.stats())
+    }
+
+    /// Encodes the positions of the instructions (one per code unit) as the location table (`co_linetable`) of Python 3.11.
+    /// The table is stored in `lnotab`, which is dumped as `co_linetable` for 3.11.
+    pub fn set_positions(&mut self, positions: &[CodePosition]) {
+        let mut table = vec![];
+        let mut prev_line = self.firstlineno as i64;
+        for chunk in positions.chunk_by(|l, r| l == r) {
+            for units in chunk.chunks(8) {
+                let len = units.len() as u8 - 1;
+                let entry = |code: u8| 0x80 | (code << 3) | len;
+                match units[0] {
+                    (None, ..) => table.push(entry(LOCATION_NONE)),
+                    (Some(line), end_line, col, end_col) => {
+                        let delta = line as i64 - prev_line;
+                        let end_line = end_line.unwrap_or(line);
+                        match (col, end_col) {
+                            (Some(col), Some(end_col))
+                                if end_line == line
+                                    && (0..3).contains(&delta)
+                                    && col < 128
+                                    && end_col < 128 =>
+                            {
+                                table.push(entry(LOCATION_ONE_LINE_FORM + delta as u8));
+                                table.push(col as u8);
+                                table.push(end_col as u8);
+                            }
+                            (None, None) if end_line == line => {
+                                table.push(entry(LOCATION_NO_COLUMNS));
+                                write_svarint(&mut table, delta);
+                            }
+                            _ => {
+                                table.push(entry(LOCATION_LONG_FORM));
+                                write_svarint(&mut table, delta);
+                                write_varint(&mut table, end_line.saturating_sub(line));
+                                write_varint(&mut table, col.map_or(0, |c| c + 1));
+                                write_varint(&mut table, end_col.map_or(0, |c| c + 1));
+                            }
+                        }
+                        prev_line = line as i64;
+                    }
+                }
+            }
+        }
+        self.lnotab = table;
+    }
+
+    /// Decodes the location table of Python 3.11 (the inverse of `set_positions`).
+    pub fn positions(&self) -> Vec<CodePosition> {
+        let mut positions = vec![];
+        let mut line = self.firstlineno as i64;
+        let mut iter = self.lnotab.iter().copied();
+        while let Some(first) = iter.next() {
+            let code = (first >> 3) & 15;
+            let len = (first & 7) as usize + 1;
+            let pos = match code {
+                LOCATION_NONE => (None, None, None, None),
+                LOCATION_LONG_FORM => {
+                    line += read_svarint(&mut iter);
+                    let end_line = line + read_varint(&mut iter) as i64;
+                    let col = read_varint(&mut iter).checked_sub(1);
+                    let end_col = read_varint(&mut iter).checked_sub(1);
+                    (Some(line as u32), Some(end_line as u32), col, end_col)
+                }
+                LOCATION_NO_COLUMNS => {
+                    line += read_svarint(&mut iter);
+                    (Some(line as u32), Some(line as u32), None, None)
+                }
+                LOCATION_ONE_LINE_FORM..=12 => {
+                    line += (code - LOCATION_ONE_LINE_FORM) as i64;
+                    let col = iter.next().unwrap_or(0) as u32;
+                    let end_col = iter.next().unwrap_or(0) as u32;
+                    (
+                        Some(line as u32),
+                        Some(line as u32),
+                        Some(col),
+                        Some(end_col),
+                    )
+                }
+                _ => {
+                    debug_assert!(code <= LOCATION_SHORT_FORM_MAX);
+                    let second = iter.next().unwrap_or(0);
+                    let col = code as u32 * 8 + ((second >> 4) & 7) as u32;
+                    let end_col = col + (second & 15) as u32;
+                    (
+                        Some(line as u32),
+                        Some(line as u32),
+                        Some(col),
+                        Some(end_col),
+                    )
+                }
+            };
+            positions.extend(std::iter::repeat_n(pos, len));
+        }
+        positions
     }
 
     fn tables_info(&self) -> String {
@@ -504,13 +583,27 @@ impl CodeObj {
         let mut sdelta = lnotab_iter.next().unwrap_or(&0);
         let mut ldelta = lnotab_iter.next().unwrap_or(&0);
         let mut instrs = "".to_string();
-        writeln!(instrs, "lnotab: {:?}", self.lnotab).unwrap();
-        if *sdelta != 0 {
-            writeln!(instrs, "{lineno}:").unwrap();
-        }
+        // 3.11~: `lnotab` is the location table
+        let positions = if py_ver.and_then(|pv| pv.minor) >= Some(11) {
+            writeln!(instrs, "linetable: {:?}", self.lnotab).unwrap();
+            Some(self.positions())
+        } else {
+            writeln!(instrs, "lnotab: {:?}", self.lnotab).unwrap();
+            if *sdelta != 0 {
+                writeln!(instrs, "{lineno}:").unwrap();
+            }
+            None
+        };
+        let mut prev_line = None;
         let mut extended_arg = vec![];
         loop {
-            if *sdelta as usize == line_offset {
+            if let Some(positions) = positions.as_ref() {
+                let line = positions.get(idx / 2).and_then(|pos| pos.0);
+                if let Some(lineno) = line.filter(|_| line != prev_line) {
+                    writeln!(instrs, "{lineno}:").unwrap();
+                    prev_line = line;
+                }
+            } else if *sdelta as usize == line_offset {
                 line_offset = 0;
                 lineno += *ldelta as u32;
                 writeln!(instrs, "{lineno}:").unwrap();
@@ -344,6 +344,16 @@ pub fn nd_func(params: Vec<ParamTy>, var_params: Option<ParamTy>, ret: Type) ->
     func(params, var_params, vec![], ret)
 }
 
+#[inline]
+pub fn nd_subr(
+    kind: SubrKind,
+    params: Vec<ParamTy>,
+    var_params: Option<ParamTy>,
+    ret: Type,
+) -> Type {
+    subr_t(kind, params, var_params, vec![], ret)
+}
+
 #[inline]
 pub fn nd_proc(params: Vec<ParamTy>, var_params: Option<ParamTy>, ret: Type) -> Type {
     proc(params, var_params, vec![], ret)
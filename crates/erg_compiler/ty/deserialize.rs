@@ -6,7 +6,7 @@ use erg_common::config::ErgConfig;
 use erg_common::dict::Dict;
 use erg_common::error::{ErrorCore, ErrorKind, Location, SubMessage};
 use erg_common::python_util::PythonVersion;
-use erg_common::serialize::DataTypePrefix;
+use erg_common::serialize::{DataTypePrefix, FLAG_REF};
 use erg_common::traits::ExitStatus;
 use erg_common::{fn_name, switch_lang};
 use erg_common::{ArcArray, Str};
@@ -99,6 +99,8 @@ pub struct Deserializer {
     str_cache: CacheSet<str>,
     arr_cache: CacheSet<[ValueObj]>,
     _dict_cache: CacheSet<Dict<ValueObj, ValueObj>>,
+    /// the objects that can be referred by `TYPE_REF`
+    refs: Vec<ValueObj>,
 }
 
 impl Deserializer {
@@ -107,6 +109,7 @@ impl Deserializer {
             str_cache: CacheSet::new(),
             arr_cache: CacheSet::new(),
             _dict_cache: CacheSet::new(),
+            refs: vec![],
         }
     }
 
@@ -160,11 +163,44 @@ impl Deserializer {
         v: &mut Vec<u8>,
         python_ver: PythonVersion,
     ) -> DeserializeResult<ValueObj> {
-        match DataTypePrefix::from(v.remove(0)) {
+        let prefix = v.remove(0);
+        if prefix & FLAG_REF == 0 {
+            return self.deserialize_const_body(prefix, v, python_ver);
+        }
+        // the index is reserved before the elements are read
+        let idx = self.refs.len();
+        self.refs.push(ValueObj::None);
+        let obj = self.deserialize_const_body(prefix & !FLAG_REF, v, python_ver)?;
+        self.refs[idx] = obj.clone();
+        Ok(obj)
+    }
+
+    fn deserialize_const_body(
+        &mut self,
+        prefix: u8,
+        v: &mut Vec<u8>,
+        python_ver: PythonVersion,
+    ) -> DeserializeResult<ValueObj> {
+        match DataTypePrefix::from(prefix) {
+            DataTypePrefix::Ref => {
+                let idx = Self::deserialize_u32(v) as usize;
+                self.refs
+                    .get(idx)
+                    .cloned()
+                    .ok_or_else(DeserializeError::file_broken_error)
+            }
             DataTypePrefix::Int32 => {
                 let bytes = Self::consume::<4>(v);
                 Ok(ValueObj::Int(i32::from_le_bytes(bytes)))
             }
+            DataTypePrefix::Long => {
+                let len = Self::deserialize_u32(v) as i32;
+                let digits = (0..len.unsigned_abs())
+                    .map(|_| u16::from_le_bytes(Self::consume::<2>(v)))
+                    .collect::<Vec<_>>();
+                ValueObj::from_long_digits(len < 0, &digits)
+                    .ok_or_else(DeserializeError::file_broken_error)
+            }
             DataTypePrefix::BinFloat => {
                 let bytes = Self::consume::<8>(v);
                 Ok(ValueObj::Float(f64::from_le_bytes(bytes)))
@@ -174,7 +210,12 @@ impl Deserializer {
                 let bytes = v.drain(..len as usize).collect();
                 Ok(self.get_cached_str(&String::from_utf8(bytes)?))
             }
-            DataTypePrefix::Str | DataTypePrefix::Unicode => {
+            DataTypePrefix::Str => {
+                let len = Self::deserialize_u32(v);
+                let bytes = v.drain(..len as usize).collect::<Vec<_>>();
+                Ok(ValueObj::Bytes(bytes.into()))
+            }
+            DataTypePrefix::Unicode => {
                 let len = Self::deserialize_u32(v);
                 let bytes = v.drain(..len as usize).collect();
                 Ok(self.get_cached_str(&String::from_utf8(bytes)?))
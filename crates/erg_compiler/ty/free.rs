@@ -1,4 +1,4 @@
-use std::cell::{Ref, RefMut};
+use std::cell::{Ref, RefCell, RefMut};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::mem;
@@ -19,6 +19,150 @@ pub type Id = usize;
 pub const GENERIC_LEVEL: usize = usize::MAX;
 static UNBOUND_ID: AtomicUsize = AtomicUsize::new(0);
 
+struct TrailEntry {
+    /// the variable was generalized (quantified) before the change
+    generalized: bool,
+    restore: Box<dyn FnOnce()>,
+}
+
+thread_local! {
+    /// The unification trail: the previous states of the free variables changed since the outermost snapshot was taken.
+    /// `None` if no snapshot is taken (the changes are not recorded).
+    static TRAIL: RefCell<Option<Vec<TrailEntry>>> = const { RefCell::new(None) };
+}
+
+fn record<T: Send + Clone + 'static>(fv: &Free<T>) {
+    TRAIL.with(|trail| {
+        if let Some(trail) = trail.borrow_mut().as_mut() {
+            let fv = fv.clone();
+            let prev = fv.clone_inner();
+            trail.push(TrailEntry {
+                generalized: prev.is_generalized(),
+                restore: Box::new(move || *fv.0.borrow_mut() = prev),
+            });
+        }
+    });
+}
+
+/// Returns `true` if the changes of the free variables are recorded (i.e. a snapshot is taken).
+pub fn is_recording() -> bool {
+    TRAIL.with(|trail| trail.borrow().is_some())
+}
+
+/// A point of the unification trail.
+/// All the changes of the free variables (links, constraints, levels) made after the snapshot is taken
+/// can be undone by `rollback`, so speculative unifications do not leak.
+/// Snapshots can be nested. Dropping a snapshot commits it.
+///
+/// The trail is thread-local, as are the states of the free variables (see `Forkable`).
+#[derive(Debug)]
+#[must_use]
+pub struct Snapshot {
+    len: usize,
+    outermost: bool,
+    done: bool,
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        if !self.done {
+            self.finish(|_| false);
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn take() -> Self {
+        TRAIL.with(|trail| {
+            let mut trail = trail.borrow_mut();
+            match trail.as_ref() {
+                Some(entries) => Self {
+                    len: entries.len(),
+                    outermost: false,
+                    done: false,
+                },
+                None => {
+                    *trail = Some(vec![]);
+                    Self {
+                        len: 0,
+                        outermost: true,
+                        done: false,
+                    }
+                }
+            }
+        })
+    }
+
+    /// Undoes all the changes made after `self` is taken.
+    pub fn rollback(mut self) {
+        self.finish(|_| true);
+    }
+
+    /// Undoes the changes of the generalized type variables made after `self` is taken (e.g. substitutions of the type parameters),
+    /// and keeps the others (e.g. the links of the type variables of the expression being checked).
+    pub fn rollback_generalized(mut self) {
+        self.finish(|entry| entry.generalized);
+    }
+
+    /// Keeps the changes made after `self` is taken.
+    /// If `self` is nested, the changes can still be undone by the outer snapshot.
+    pub fn commit(mut self) {
+        self.finish(|_| false);
+    }
+
+    fn finish(&mut self, undo: fn(&TrailEntry) -> bool) {
+        self.done = true;
+        let undone = TRAIL.with(|trail| {
+            let mut trail = trail.borrow_mut();
+            let entries = trail.as_mut().unwrap();
+            let mut undone = vec![];
+            for entry in entries.split_off(self.len) {
+                if undo(&entry) {
+                    undone.push(entry);
+                } else {
+                    entries.push(entry);
+                }
+            }
+            if self.outermost {
+                *trail = None;
+            }
+            undone
+        });
+        // the restorations are not recorded (they bypass `Free::borrow_mut`)
+        for entry in undone.into_iter().rev() {
+            (entry.restore)();
+        }
+    }
+}
+
+/// Calls `f`, then undoes all the changes of the free variables made by `f`.
+pub fn probe<R>(f: impl FnOnce() -> R) -> R {
+    let snapshot = Snapshot::take();
+    let res = f();
+    snapshot.rollback();
+    res
+}
+
+/// Calls `f`, then undoes the changes of the generalized type variables made by `f` (see `Snapshot::rollback_generalized`).
+pub fn probe_generalized<R>(f: impl FnOnce() -> R) -> R {
+    let snapshot = Snapshot::take();
+    let res = f();
+    snapshot.rollback_generalized();
+    res
+}
+
+/// Calls `f`, and undoes all the changes of the free variables made by `f` if it fails.
+pub fn speculate<R, E>(f: impl FnOnce() -> Result<R, E>) -> Result<R, E> {
+    let snapshot = Snapshot::take();
+    let res = f();
+    if res.is_ok() {
+        snapshot.commit();
+    } else {
+        snapshot.rollback();
+    }
+    res
+}
+
 pub trait HasLevel {
     fn level(&self) -> Option<Level>;
     fn set_level(&self, lev: Level);
@@ -488,6 +632,15 @@ impl<T> FreeKind<T> {
     pub const fn is_undoable_linked(&self) -> bool {
         matches!(self, Self::UndoableLinked { .. })
     }
+
+    /// Returns `true` if `self` is (or was, before the undoable link) generalized.
+    pub fn is_generalized(&self) -> bool {
+        match self {
+            Self::Unbound { lev, .. } | Self::NamedUnbound { lev, .. } => *lev == GENERIC_LEVEL,
+            Self::UndoableLinked { previous, .. } => previous.is_generalized(),
+            Self::Linked(_) => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -596,15 +749,20 @@ impl<T: LimitedDisplay + Send + Clone> LimitedDisplay for Free<T> {
     }
 }
 
+impl<T: Send + Clone + 'static> Free<T> {
+    /// The change is recorded in the unification trail if a `Snapshot` is taken.
+    #[track_caller]
+    pub fn borrow_mut(&self) -> RefMut<'_, FreeKind<T>> {
+        record(self);
+        self.0.borrow_mut()
+    }
+}
+
 impl<T: Send + Clone> Free<T> {
     #[track_caller]
     pub fn borrow(&self) -> Ref<'_, FreeKind<T>> {
         self.0.borrow()
     }
-    #[track_caller]
-    pub fn borrow_mut(&self) -> RefMut<'_, FreeKind<T>> {
-        self.0.borrow_mut()
-    }
     /// very unsafe, use `force_replace` instead whenever possible
     pub fn as_ptr(&self) -> *mut FreeKind<T> {
         self.0.as_ptr()
@@ -948,7 +1106,7 @@ impl<T: Default + Clone + fmt::Debug + Send + Sync + 'static> Free<T> {
     }
 }
 
-impl<T: CanbeFree + Send + Clone> Free<T> {
+impl<T: CanbeFree + Send + Clone + 'static> Free<T> {
     pub fn get_type(&self) -> Option<Type> {
         self.constraint().and_then(|c| c.get_type().cloned())
     }
@@ -1057,6 +1215,8 @@ mod tests {
     use crate::ty::*;
     use crate::*;
 
+    use super::{is_recording, Snapshot, GENERIC_LEVEL};
+
     #[test]
     fn cmp_freevar() {
         enable_overflow_stacktrace!();
@@ -1069,4 +1229,35 @@ mod tests {
         assert_eq!(t, t);
         assert_eq!(t, u);
     }
+
+    #[test]
+    fn rollback_undoes_links() {
+        let (t, u) = (
+            free_var(1, Constraint::new_type_of(Type::Type)),
+            free_var(1, Constraint::new_type_of(Type::Type)),
+        );
+        let outer = Snapshot::take();
+        t.link(&Type::Int);
+        let inner = Snapshot::take();
+        u.link(&Type::Str);
+        inner.commit();
+        assert!(!u.is_unbound_var());
+        outer.rollback();
+        assert!(t.is_unbound_var() && u.is_unbound_var());
+        assert!(!is_recording());
+    }
+
+    #[test]
+    fn rollback_generalized_keeps_other_links() {
+        let (q, t) = (
+            free_var(GENERIC_LEVEL, Constraint::new_type_of(Type::Type)),
+            free_var(1, Constraint::new_type_of(Type::Type)),
+        );
+        let snapshot = Snapshot::take();
+        q.undoable_link(&Type::Int);
+        t.link(&Type::Int);
+        snapshot.rollback_generalized();
+        assert!(q.is_unbound_var());
+        assert_eq!(t, Type::Int);
+    }
 }
@@ -0,0 +1,245 @@
+//! Interning of `Type`s.
+//!
+//! Structurally equal closed types (types without free type variables) share one allocation.
+//! `Interned` also keeps the hash of the type, so hashing it does not traverse the type.
+//! The trait implementations (`TraitImpl`) are interned, because they are hashed and compared repeatedly in trait lookup
+//! (e.g. `get_simple_trait_impls` unions the sets of the implementations of all the outer contexts).
+//! Other types (e.g. the operands of `supertype_of`) are not interned and are compared structurally as before.
+//!
+//! `Type`'s `==` is not a purely structural comparison (e.g. `And`/`Or` are commutative),
+//! so equal types may be interned separately, and then they are compared with `==`.
+//! Types with free type variables are not interned, because they can be changed by unification.
+//! They are wrapped in `Interned` as they are.
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock, Weak};
+
+use erg_common::dict::Dict;
+use erg_common::get_hash;
+use erg_common::shared::Shared;
+
+use super::Type;
+
+/// The dead entries are removed when the cache gets this size for the first time.
+const MIN_PURGE_LEN: usize = 1 << 10;
+
+/// The interned types are held weakly, so a type is freed when it is no longer used.
+/// The cache lives as long as the process (e.g. ELS), so the entries of the freed types are removed
+/// when the number of the entries doubles since the last removal.
+struct TypeCache {
+    types: Dict<usize, Vec<Weak<Type>>>,
+    len: usize,
+    purge_len: usize,
+}
+
+impl Default for TypeCache {
+    fn default() -> Self {
+        Self {
+            types: Dict::new(),
+            len: 0,
+            purge_len: MIN_PURGE_LEN,
+        }
+    }
+}
+
+impl TypeCache {
+    fn get(&self, t: &Type, hash: usize) -> Option<Arc<Type>> {
+        self.types
+            .get(&hash)?
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|interned| interned.as_ref() == t)
+    }
+
+    fn insert(&mut self, interned: &Arc<Type>, hash: usize) {
+        if self.len >= self.purge_len {
+            self.types.retain(|_, types| {
+                types.retain(|t| t.strong_count() > 0);
+                !types.is_empty()
+            });
+            self.len = self.types.values().map(Vec::len).sum();
+            self.purge_len = (self.len * 2).max(MIN_PURGE_LEN);
+        }
+        self.types
+            .entry(hash)
+            .or_default()
+            .push(Arc::downgrade(interned));
+        self.len += 1;
+    }
+}
+
+static TYPE_CACHE: OnceLock<Shared<TypeCache>> = OnceLock::new();
+
+fn intern_type(t: Type, hash: usize) -> Arc<Type> {
+    let cache = TYPE_CACHE.get_or_init(Shared::default);
+    if let Some(interned) = cache.borrow().get(&t, hash) {
+        return interned;
+    } // the read lock is dropped
+    let mut cache = cache.borrow_mut();
+    // may be interned by another thread in the meantime
+    if let Some(interned) = cache.get(&t, hash) {
+        return interned;
+    }
+    let interned = Arc::new(t);
+    cache.insert(&interned, hash);
+    interned
+}
+
+/// A shared handle of an (interned) value.
+/// If both share the allocation, `==` is a pointer check.
+#[derive(Clone)]
+pub struct Interned<T> {
+    value: Arc<T>,
+    /// the hash of `value` if it is interned (the other values can be changed)
+    hash: Option<usize>,
+}
+
+impl<T: PartialEq> PartialEq for Interned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        // the hashes are not compared first, because `Type`'s hash is not commutative (e.g. `And`/`Or`)
+        Arc::ptr_eq(&self.value, &other.value) || self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Interned<T> {}
+
+impl<T: PartialEq> PartialEq<T> for Interned<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.value.as_ref() == other
+    }
+}
+
+impl<T: Hash> Hash for Interned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // the same as an interned value for an equal value that is not interned
+        state.write_usize(self.hash.unwrap_or_else(|| get_hash(&self.value)))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Interned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T> Deref for Interned<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> AsRef<T> for Interned<T> {
+    fn as_ref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Clone> Interned<T> {
+    pub fn into_inner(self) -> T {
+        Arc::try_unwrap(self.value).unwrap_or_else(|value| value.as_ref().clone())
+    }
+}
+
+impl<T> Interned<T> {
+    pub const fn is_interned(&self) -> bool {
+        self.hash.is_some()
+    }
+}
+
+impl From<Type> for Interned<Type> {
+    fn from(t: Type) -> Self {
+        if t.is_closed() {
+            let hash = get_hash(&t);
+            Self {
+                value: intern_type(t, hash),
+                hash: Some(hash),
+            }
+        } else {
+            Self {
+                value: Arc::new(t),
+                hash: None,
+            }
+        }
+    }
+}
+
+impl Type {
+    /// Returns `true` if `self` has no free type variables (except for the linked ones).
+    /// A closed type is never changed by unification.
+    pub fn is_closed(&self) -> bool {
+        !self.has_qvar() && !self.has_unbound_var() && !self.has_undoable_linked_var()
+    }
+
+    pub fn intern(self) -> Interned<Type> {
+        Interned::from(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use erg_common::get_hash;
+
+    use crate::ty::constructors::{and, array_t, free_var, or, poly};
+    use crate::ty::free::Constraint;
+    use crate::ty::{TyParam, Type};
+
+    #[test]
+    fn closed_types_are_shared() {
+        let add_int = || {
+            poly(
+                "Add",
+                vec![TyParam::t(array_t(Type::Int, TyParam::value(2usize)))],
+            )
+        };
+        let (l, r) = (add_int().intern(), add_int().intern());
+        assert!(l.is_interned());
+        assert!(std::ptr::eq(&*l, &*r));
+        assert_eq!(l, r);
+        assert_ne!(l, poly("Add", vec![TyParam::t(Type::Int)]).intern());
+    }
+
+    #[test]
+    fn unused_types_are_freed() {
+        let t = || poly("UnusedTypesAreFreed", vec![TyParam::t(Type::Int)]);
+        let l = t().intern();
+        let weak = Arc::downgrade(&l.value);
+        assert_eq!(l.hash, Some(get_hash(&t())));
+        drop(l);
+        assert!(weak.upgrade().is_none());
+        assert!(t().intern().is_interned());
+    }
+
+    #[test]
+    fn open_types_are_not_shared() {
+        let t = free_var(1, Constraint::new_type_of(Type::Type));
+        let (l, r) = (t.clone().intern(), t.intern());
+        assert!(!l.is_interned());
+        assert!(!std::ptr::eq(&*l, &*r));
+        assert_eq!(l, r);
+    }
+
+    #[test]
+    fn commutative_types_are_equal() {
+        let (l, r) = (
+            and(Type::Int, Type::Str).intern(),
+            and(Type::Str, Type::Int).intern(),
+        );
+        assert!(l.is_interned() && r.is_interned());
+        assert_eq!(l, r);
+        let (l, r) = (
+            or(Type::Int, Type::NoneType).intern(),
+            or(Type::NoneType, Type::Int).intern(),
+        );
+        assert_eq!(l, r);
+    }
+}
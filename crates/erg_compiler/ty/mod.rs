@@ -12,7 +12,10 @@ pub mod const_subr;
 pub mod constructors;
 pub mod deserialize;
 pub mod free;
+pub mod intern;
 pub mod predicate;
+pub mod rational;
+pub mod serialize;
 pub mod typaram;
 pub mod value;
 pub mod vis;
@@ -381,6 +384,25 @@ impl SubrType {
         }
     }
 
+    /// Replaces the kind variable (`SubrKind::Var`) of `self` and its subroutine parameters with `kind`.
+    pub fn resolve_kind_var(&mut self, kind: SubrKind) {
+        if self.kind.is_var() {
+            self.kind = kind;
+        }
+        let params = self
+            .non_default_params
+            .iter_mut()
+            .chain(self.var_params.as_deref_mut())
+            .chain(self.default_params.iter_mut());
+        for pt in params {
+            if let Type::Subr(subr) = pt.typ_mut() {
+                if subr.kind.is_var() {
+                    subr.kind = kind;
+                }
+            }
+        }
+    }
+
     pub fn contains_tvar(&self, target: &FreeTyVar) -> bool {
         self.non_default_params
             .iter()
@@ -601,16 +623,16 @@ impl LimitedDisplay for RefinementType {
             .iter()
             .all(|p| p.is_equal() && p.subject() == first_subj);
         if is_simple_type && is_simple_preds {
-            write!(f, "{{")?;
-            for (i, pred) in self.pred.ors().into_iter().enumerate() {
-                if i > 0 {
-                    write!(f, ", ")?;
-                }
+            // the predicates are stored in a hash set, so the elements are sorted to make the output stable
+            let mut elems = vec![];
+            for pred in self.pred.ors().into_iter() {
                 let (_, rhs) = enum_unwrap!(pred, Predicate::Equal { lhs, rhs });
-                rhs.limited_fmt(f, limit - 1)?;
+                let mut elem = String::new();
+                rhs.limited_fmt(&mut elem, limit - 1)?;
+                elems.push(elem);
             }
-            write!(f, "}}")?;
-            Ok(())
+            elems.sort_unstable();
+            write!(f, "{{{}}}", elems.join(", "))
         } else {
             write!(f, "{{{}: ", self.var)?;
             self.t.limited_fmt(f, limit - 1)?;
@@ -646,12 +668,41 @@ impl RefinementType {
     pub fn invert(self) -> Self {
         Self::new(self.var, Type::Obj, !*self.pred)
     }
+
+    /// {1, 2}.enum_elems() == Some({1, 2})
+    /// {I: Int | I >= 0}.enum_elems() == None
+    pub fn enum_elems(&self) -> Option<Set<TyParam>> {
+        if *self.pred == Predicate::FALSE {
+            return Some(Set::new());
+        }
+        self.pred
+            .ors()
+            .into_iter()
+            .map(|pred| match pred {
+                Predicate::Equal { lhs, rhs } if lhs == &self.var => Some(rhs.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// {1, 2}.with_enum_elems({3}) == {3}
+    pub fn with_enum_elems(self, t: Type, elems: Set<TyParam>) -> Self {
+        let pred = elems
+            .into_iter()
+            .map(|tp| Predicate::eq(self.var.clone(), tp))
+            .fold(Predicate::FALSE, |acc, p| acc | p);
+        Self::new(self.var, t, pred)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SubrKind {
     Func,
     Proc,
+    /// The kind variable of an effect-polymorphic subroutine (`~>`).
+    /// The kind is determined at each call: it is `Proc` if a procedure is passed to a parameter of kind `Var`, otherwise `Func`.
+    /// e.g. `map: |T, U|(func: T ~> U, iterable: Iterable(T)) ~> Map(U)`
+    Var,
 }
 
 impl From<TokenKind> for SubrKind {
@@ -669,6 +720,7 @@ impl SubrKind {
         match self {
             Self::Func => Str::ever("->"),
             Self::Proc => Str::ever("=>"),
+            Self::Var => Str::ever("~>"),
         }
     }
 
@@ -678,6 +730,9 @@ impl SubrKind {
     pub fn is_proc(&self) -> bool {
         matches!(self, Self::Proc)
     }
+    pub fn is_var(&self) -> bool {
+        matches!(self, Self::Var)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1825,6 +1880,34 @@ impl Type {
         }
     }
 
+    /// Returns the sorted values of a string literal union (a.k.a. keyword enum).
+    /// ```erg
+    /// {"r", "w", "a"}.str_literals() == Some(["a", "r", "w"])
+    /// Str.str_literals() == None
+    /// ```
+    pub fn str_literals(&self) -> Option<Vec<Str>> {
+        match self {
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().str_literals(),
+            Self::Refinement(refine) if refine.t.as_ref() == &Type::Str => {
+                let mut literals = vec![];
+                for pred in refine.pred.ors() {
+                    if let Predicate::Equal {
+                        rhs: TyParam::Value(ValueObj::Str(lit)),
+                        ..
+                    } = pred
+                    {
+                        literals.push(lit.clone());
+                    } else {
+                        return None;
+                    }
+                }
+                literals.sort_by(|l, r| l[..].cmp(&r[..]));
+                Some(literals)
+            }
+            _ => None,
+        }
+    }
+
     pub fn is_record(&self) -> bool {
         match self {
             Self::FreeVar(fv) if fv.is_linked() => fv.crack().is_record(),
@@ -2158,7 +2241,7 @@ impl Type {
                 ..
             }) => Str::ever("Func"),
             Self::Subr(SubrType {
-                kind: SubrKind::Proc,
+                kind: SubrKind::Proc | SubrKind::Var,
                 ..
             }) => Str::ever("Proc"),
             Self::Callable { .. } => Str::ever("Callable"),
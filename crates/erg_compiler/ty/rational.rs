@@ -0,0 +1,342 @@
+//! Exact rational numbers (the values of `Ratio` literals).
+//!
+//! A `Ratio` literal (e.g. `0.1`) is kept as a fraction in the compiler,
+//! so that the constant evaluation (e.g. of the predicates of refinement types) has no rounding errors of `f64`.
+//! The value is converted to a float only at code generation (see `Rational::to_f64`).
+//! The numerator and the denominator are `i128`. The operations return `None` if they overflow,
+//! and `ValueObj` falls back to `f64` in that case.
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A reduced fraction. The denominator is always positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+
+impl fmt::Display for Rational {
+    /// The exact decimal notation if there is one (e.g. `0.125`), otherwise `numer/denom` (e.g. `1/3`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_decimal() {
+            Some(decimal) => write!(f, "{decimal}"),
+            None => write!(f, "{}/{}", self.numer, self.denom),
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let (Some(l), Some(r)) = (
+            self.numer.checked_mul(other.denom),
+            other.numer.checked_mul(self.denom),
+        ) {
+            return l.cmp(&r);
+        }
+        // compare the integer parts, then the reciprocals of the fractional parts (never overflows)
+        let (mut l, mut r) = (*self, *other);
+        let mut reversed = false;
+        loop {
+            let (lq, lm) = l.floor_rem();
+            let (rq, rm) = r.floor_rem();
+            let ord = match (lq.cmp(&rq), lm == 0, rm == 0) {
+                (Ordering::Equal, true, true) => Ordering::Equal,
+                (Ordering::Equal, true, false) => Ordering::Less,
+                (Ordering::Equal, false, true) => Ordering::Greater,
+                (Ordering::Equal, false, false) => {
+                    // `lm/l.denom < rm/r.denom` iff `l.denom/lm > r.denom/rm`
+                    (l, r) = (Self::reduced(l.denom, lm), Self::reduced(r.denom, rm));
+                    reversed = !reversed;
+                    continue;
+                }
+                (ord, _, _) => ord,
+            };
+            return if reversed { ord.reverse() } else { ord };
+        }
+    }
+}
+
+fn gcd(mut l: i128, mut r: i128) -> i128 {
+    while r != 0 {
+        (l, r) = (r, l % r);
+    }
+    l.abs()
+}
+
+impl Rational {
+    pub const ZERO: Self = Self { numer: 0, denom: 1 };
+
+    /// `None` if `denom == 0` or the fraction cannot be normalized (`i128::MIN` is involved).
+    pub fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 || numer == i128::MIN || denom == i128::MIN {
+            return None;
+        }
+        let (numer, denom) = if denom < 0 {
+            (-numer, -denom)
+        } else {
+            (numer, denom)
+        };
+        Some(Self::reduced(numer, denom))
+    }
+
+    /// `denom` must be positive.
+    fn reduced(numer: i128, denom: i128) -> Self {
+        let gcd = gcd(numer, denom);
+        Self {
+            numer: numer / gcd,
+            denom: denom / gcd,
+        }
+    }
+
+    pub const fn from_int(n: i64) -> Self {
+        Self {
+            numer: n as i128,
+            denom: 1,
+        }
+    }
+
+    pub const fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    pub const fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        self.numer == 0
+    }
+
+    pub const fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    /// Parses a decimal literal (e.g. `1.5`, `.5`, `3.`, `1e-3`). Underscores must be removed in advance.
+    pub fn parse(lit: &str) -> Option<Self> {
+        if let Some(lit) = lit.strip_prefix('-') {
+            return Self::parse(lit)?.checked_neg();
+        }
+        let (mantissa, exp) = match lit.find(['e', 'E']) {
+            Some(idx) => (&lit[..idx], lit[idx + 1..].parse::<i32>().ok()?),
+            None => (lit, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int.is_empty() && frac.is_empty() {
+            return None;
+        }
+        let mut numer = 0i128;
+        for c in int.chars().chain(frac.chars()) {
+            let digit = c.to_digit(10)? as i128;
+            numer = numer.checked_mul(10)?.checked_add(digit)?;
+        }
+        let scale = i32::try_from(frac.len()).ok()?.checked_sub(exp)?;
+        let pow = 10i128.checked_pow(scale.unsigned_abs())?;
+        if scale >= 0 {
+            Self::new(numer, pow)
+        } else {
+            Self::new(numer.checked_mul(pow)?, 1)
+        }
+    }
+
+    /// The exact value of a finite float, if it fits.
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+        if f == 0.0 {
+            return Some(Self::ZERO);
+        }
+        let bits = f.to_bits();
+        let biased_exp = ((bits >> 52) & 0x7ff) as i32;
+        let fraction = bits & ((1 << 52) - 1);
+        // `f == mantissa * 2^exp`
+        let (mantissa, exp) = if biased_exp == 0 {
+            (fraction, -1074) // subnormal
+        } else {
+            (fraction | (1 << 52), biased_exp - 1075)
+        };
+        let zeros = mantissa.trailing_zeros();
+        let (mantissa, exp) = ((mantissa >> zeros) as i128, exp + zeros as i32);
+        let numer = if f < 0.0 { -mantissa } else { mantissa };
+        let pow = 2i128.checked_pow(exp.unsigned_abs())?;
+        if exp >= 0 {
+            Self::new(numer.checked_mul(pow)?, 1)
+        } else {
+            Self::new(numer, pow)
+        }
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Self::new(self.numer.checked_neg()?, self.denom)
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let gcd = gcd(self.denom, other.denom);
+        let denom = (self.denom / gcd).checked_mul(other.denom)?;
+        let numer = (self.numer.checked_mul(other.denom / gcd)?)
+            .checked_add(other.numer.checked_mul(self.denom / gcd)?)?;
+        Self::new(numer, denom)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(other.checked_neg()?)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        // cross-reduce first to avoid needless overflows
+        let (g1, g2) = (gcd(self.numer, other.denom), gcd(other.numer, self.denom));
+        let numer = (self.numer / g1).checked_mul(other.numer / g2)?;
+        let denom = (self.denom / g2).checked_mul(other.denom / g1)?;
+        Self::new(numer, denom)
+    }
+
+    /// `None` if `other` is zero.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        self.checked_mul(Self::new(other.denom, other.numer)?)
+    }
+
+    /// Python's `divmod`: the quotient is floored, and the remainder has the sign of the divisor.
+    /// `None` if `other` is zero.
+    pub fn checked_divmod(self, other: Self) -> Option<(Self, Self)> {
+        let (q, _) = self.checked_div(other)?.floor_rem();
+        let q = Self::new(q, 1)?;
+        let m = self.checked_sub(q.checked_mul(other)?)?;
+        Some((q, m))
+    }
+
+    /// The floored integer part and the (non-negative) numerator of the rest.
+    fn floor_rem(&self) -> (i128, i128) {
+        (
+            self.numer.div_euclid(self.denom),
+            self.numer.rem_euclid(self.denom),
+        )
+    }
+
+    /// The nearest `f64`. The result is correctly rounded if the denominator divides a power of 10
+    /// (e.g. the value of a literal) or both the numerator and the denominator fit in the mantissa.
+    pub fn to_f64(&self) -> f64 {
+        const MANTISSA_MAX: i128 = 1 << f64::MANTISSA_DIGITS;
+        if self.numer.abs() <= MANTISSA_MAX && self.denom <= MANTISSA_MAX {
+            return self.numer as f64 / self.denom as f64;
+        }
+        if let Some((digits, scale)) = self.decimal_digits() {
+            if let Ok(f) = format!("{digits}e-{scale}").parse() {
+                return f;
+            }
+        }
+        self.numer as f64 / self.denom as f64
+    }
+
+    /// `numer/denom == digits * 10^-scale`, if the denominator divides `10^scale`.
+    fn decimal_digits(&self) -> Option<(i128, u32)> {
+        let mut scale = 0;
+        let mut rest = self.denom;
+        for factor in [2, 5] {
+            let mut n = 0;
+            while rest % factor == 0 {
+                rest /= factor;
+                n += 1;
+            }
+            scale = u32::max(scale, n);
+        }
+        if rest != 1 {
+            return None;
+        }
+        let multiplier = 10i128.checked_pow(scale)? / self.denom;
+        Some((self.numer.checked_mul(multiplier)?, scale))
+    }
+
+    /// The exact decimal notation (e.g. `-0.125`, `3.0`), if there is one.
+    pub fn to_decimal(&self) -> Option<String> {
+        let (digits, scale) = self.decimal_digits()?;
+        if scale == 0 {
+            return Some(format!("{digits}.0"));
+        }
+        let sign = if digits < 0 { "-" } else { "" };
+        let digits = format!(
+            "{:0>width$}",
+            digits.unsigned_abs(),
+            width = scale as usize + 1
+        );
+        let (int, frac) = digits.split_at(digits.len() - scale as usize);
+        Some(format!("{sign}{int}.{frac}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rational;
+
+    fn ratio(lit: &str) -> Rational {
+        Rational::parse(lit).unwrap()
+    }
+
+    #[test]
+    fn parse_literals() {
+        assert_eq!(ratio("1.5"), Rational::new(3, 2).unwrap());
+        assert_eq!(ratio(".5"), Rational::new(1, 2).unwrap());
+        assert_eq!(ratio("3."), Rational::from_int(3));
+        assert_eq!(ratio("3000.2e-4"), Rational::new(30002, 100000).unwrap());
+        assert_eq!(ratio("1e3"), Rational::from_int(1000));
+        assert_eq!(Rational::parse("1e400"), None);
+        assert_eq!(Rational::parse("."), None);
+    }
+
+    #[test]
+    fn exact_arithmetic() {
+        let sum = ratio("0.1").checked_add(ratio("0.2")).unwrap();
+        assert_eq!(sum, ratio("0.3"));
+        assert_eq!(sum.to_string(), "0.3");
+        let third = Rational::from_int(1)
+            .checked_div(Rational::from_int(3))
+            .unwrap();
+        assert_eq!(third.to_string(), "1/3");
+        assert_eq!(
+            third.checked_mul(Rational::from_int(3)),
+            Some(Rational::from_int(1))
+        );
+        assert_eq!(third.checked_div(Rational::ZERO), None);
+        // Python: divmod(-7.5, 2) == (-4.0, 0.5)
+        assert_eq!(
+            ratio("-7.5").checked_divmod(Rational::from_int(2)),
+            Some((Rational::from_int(-4), ratio("0.5")))
+        );
+        assert_eq!(ratio("-0.125").to_string(), "-0.125");
+    }
+
+    #[test]
+    fn compare_without_overflow() {
+        let big = Rational::new(i128::MAX - 1, i128::MAX).unwrap();
+        let bigger = Rational::new(i128::MAX - 2, i128::MAX - 1).unwrap();
+        assert!(big > bigger);
+        assert!(ratio("0.3") > ratio("0.29999999999999999"));
+        assert!(ratio("-0.5") < Rational::ZERO);
+    }
+
+    #[test]
+    fn from_f64() {
+        assert_eq!(Rational::from_f64(0.5), Rational::new(1, 2));
+        assert_eq!(Rational::from_f64(-3.0), Some(Rational::from_int(-3)));
+        // `0.1f64` is slightly greater than `1/10`
+        assert!(Rational::from_f64(0.1).unwrap() > ratio("0.1"));
+        assert_eq!(Rational::from_f64(1e300), None);
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+    }
+
+    #[test]
+    fn to_f64() {
+        assert_eq!(ratio("0.1").to_f64(), 0.1);
+        assert_eq!(ratio("-2.5e-3").to_f64(), -2.5e-3);
+        // rounded once (not `numer as f64 / denom as f64`)
+        assert_eq!(ratio("0.30000000000000000000000001").to_f64(), 0.3);
+    }
+}
@@ -0,0 +1,463 @@
+//! オブジェクトをバイトコードにシリアライズする
+//!
+//! Identical strings, tuples and code objects in a module (including the nested code objects) are serialized only once,
+//! and the later occurrences are replaced with references (`TYPE_REF`) to the first one.
+//! Constant strings that look like identifiers are interned, as CPython does.
+use std::fmt;
+
+use erg_common::dict::Dict;
+use erg_common::python_util::PythonVersion;
+use erg_common::serialize::*;
+use erg_common::{switch_lang, Str};
+
+use super::codeobj::{CodeObj, FastKind};
+use super::value::ValueObj;
+
+/// The size of a reference (`TYPE_REF` + u32 index).
+/// Smaller objects are always serialized as they are.
+const REF_SIZE: usize = 5;
+
+fn is_name_like(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Statistics of the deduplication, displayed with `--verbose 2`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SerializeStats {
+    /// the number of the objects replaced with references
+    pub shared: usize,
+    pub saved_bytes: usize,
+}
+
+impl fmt::Display for SerializeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deduplicated {} constant(s) ({} bytes saved)",
+            self.shared, self.saved_bytes
+        )
+    }
+}
+
+/// A part of the identity of a serialized object.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum KeyPart {
+    Bytes(Vec<u8>),
+    /// a nested shared object (the index of its identity)
+    Shared(u32),
+}
+
+/// The bytes written by a serializing function, and the identity of the object.
+#[derive(Debug, Default)]
+struct Output {
+    bytes: Vec<u8>,
+    /// the bytes of the object itself, where the nested shared objects are replaced with their identities
+    /// (so each byte of the module is kept in a key only once)
+    key: Vec<KeyPart>,
+    /// the length of the object serialized without references
+    plain_len: usize,
+}
+
+impl Output {
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+        self.plain_len += bytes.len();
+        match self.key.last_mut() {
+            Some(KeyPart::Bytes(last)) => last.extend_from_slice(bytes),
+            _ => self.key.push(KeyPart::Bytes(bytes.to_vec())),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SharedObjs {
+    /// the identities of the objects serialized so far
+    ids: Dict<Vec<KeyPart>, u32>,
+    /// identity -> the index of the reference
+    refs: Dict<u32, u32>,
+}
+
+#[derive(Debug)]
+pub struct Serializer {
+    python_ver: PythonVersion,
+    /// `None` if the objects are not shared
+    objs: Option<SharedObjs>,
+    /// the number of the objects flagged with `FLAG_REF` so far
+    /// (some prefixes like `ShortAscii` always have the flag, so not all of them are in `refs`)
+    n_flagged: u32,
+    stats: SerializeStats,
+}
+
+impl Serializer {
+    pub fn new(python_ver: PythonVersion) -> Self {
+        Self {
+            python_ver,
+            objs: Some(SharedObjs::default()),
+            n_flagged: 0,
+            stats: SerializeStats::default(),
+        }
+    }
+
+    /// Creates a serializer that does not share objects.
+    pub fn plain(python_ver: PythonVersion) -> Self {
+        Self {
+            python_ver,
+            objs: None,
+            n_flagged: 0,
+            stats: SerializeStats::default(),
+        }
+    }
+
+    pub const fn stats(&self) -> SerializeStats {
+        self.stats
+    }
+
+    /// Serializes `obj` with `serialize`, or writes a reference if the same object has been serialized.
+    ///
+    /// The indices of the references are assigned in the order the objects are started to be read
+    /// (a container reserves its index before its elements), as `marshal` does.
+    /// All the objects serialized through this are flagged with `FLAG_REF`.
+    ///
+    /// The identity of `obj` is built from the identities of the nested objects,
+    /// so each object is serialized only once.
+    fn share<T>(&mut self, obj: T, serialize: fn(&mut Self, T, &mut Output), out: &mut Output) {
+        if self.objs.is_none() {
+            return serialize(self, obj, out);
+        }
+        let (idx, stats) = (self.n_flagged, self.stats);
+        self.n_flagged += 1;
+        let mut this = Output::default();
+        serialize(self, obj, &mut this);
+        let objs = self.objs.as_mut().unwrap();
+        let next_id = objs.ids.len() as u32;
+        let id = *objs.ids.entry(this.key).or_insert(next_id);
+        out.plain_len += this.plain_len;
+        out.key.push(KeyPart::Shared(id));
+        if this.plain_len > REF_SIZE {
+            if let Some(&ref_idx) = objs.refs.get(&id) {
+                // the nested objects have been serialized, so the indices reserved for them are discarded
+                self.n_flagged = idx;
+                self.stats = stats;
+                self.stats.shared += 1;
+                self.stats.saved_bytes += this.plain_len - REF_SIZE;
+                out.bytes.push(DataTypePrefix::Ref as u8);
+                out.bytes.extend_from_slice(&ref_idx.to_le_bytes());
+                return;
+            }
+            objs.refs.insert(id, idx);
+        }
+        this.bytes[0] |= FLAG_REF;
+        out.bytes.append(&mut this.bytes);
+    }
+
+    pub fn serialize_const(&mut self, obj: ValueObj) -> Vec<u8> {
+        let mut out = Output::default();
+        self.write_const(obj, &mut out);
+        out.bytes
+    }
+
+    fn write_const(&mut self, obj: ValueObj, out: &mut Output) {
+        match obj {
+            ValueObj::Int(i) => {
+                out.push(&[DataTypePrefix::Int32 as u8]);
+                out.push(&i.to_le_bytes());
+            }
+            // TODO: Natとしてシリアライズ
+            ValueObj::Nat(n) if n <= i32::MAX as u64 => {
+                out.push(&[DataTypePrefix::Int32 as u8]);
+                out.push(&(n as i32).to_le_bytes());
+            }
+            ValueObj::Nat(n) => out.push(&long_into_bytes(false, &[n as u32, (n >> 32) as u32])),
+            #[cfg(feature = "bigint")]
+            ValueObj::BigInt(i) => {
+                let (sign, magnitude) = i.to_u32_digits();
+                out.push(&long_into_bytes(
+                    sign == num_bigint::Sign::Minus,
+                    &magnitude,
+                ));
+            }
+            ValueObj::Float(f) => {
+                out.push(&[DataTypePrefix::BinFloat as u8]);
+                out.push(&f.to_le_bytes());
+            }
+            ValueObj::Ratio(r) => {
+                out.push(&[DataTypePrefix::BinFloat as u8]);
+                out.push(&r.to_f64().to_le_bytes());
+            }
+            ValueObj::Str(s) => {
+                let is_interned = is_name_like(&s);
+                self.write_str(s, is_interned, out);
+            }
+            ValueObj::Bytes(bytes) => out.push(&raw_string_into_bytes(bytes.to_vec())),
+            ValueObj::Ast(expr) => self.write_str(Str::from(expr.to_string()), false, out),
+            ValueObj::Bool(true) => out.push(&[DataTypePrefix::True as u8]),
+            ValueObj::Bool(false) => out.push(&[DataTypePrefix::False as u8]),
+            ValueObj::Array(arr) => self.write_consts(arr.to_vec(), out),
+            ValueObj::Tuple(tup) => self.write_consts(tup.to_vec(), out),
+            ValueObj::None => out.push(&[DataTypePrefix::None as u8]),
+            ValueObj::Code(c) => self.write_code(*c, out),
+            // Dict
+            other => {
+                panic!(
+                    "{}",
+                    switch_lang!(
+                        "japanese" => format!("このオブジェクトはシリアライズできません: {other}"),
+                        "simplified_chinese" => format!("此对象无法序列化: {other}"),
+                        "traditional_chinese" => format!("此對象無法序列化: {other}"),
+                        "english" => format!("this object cannot be serialized: {other}"),
+                    )
+                )
+            }
+        }
+    }
+
+    fn tuple_header(len: usize) -> Vec<u8> {
+        if len > u8::MAX as usize {
+            [
+                vec![DataTypePrefix::Tuple as u8],
+                (len as u32).to_le_bytes().to_vec(),
+            ]
+            .concat()
+        } else {
+            vec![DataTypePrefix::SmallTuple as u8, len as u8]
+        }
+    }
+
+    /// Serializes `consts` as a tuple.
+    pub fn serialize_consts(&mut self, consts: Vec<ValueObj>) -> Vec<u8> {
+        let mut out = Output::default();
+        self.write_consts(consts, &mut out);
+        out.bytes
+    }
+
+    fn write_consts(&mut self, consts: Vec<ValueObj>, out: &mut Output) {
+        self.share(
+            consts,
+            |ser, consts, out| {
+                out.push(&Self::tuple_header(consts.len()));
+                for obj in consts {
+                    ser.write_const(obj, out);
+                }
+            },
+            out,
+        )
+    }
+
+    pub fn serialize_str(&mut self, s: Str, is_interned: bool) -> Vec<u8> {
+        let mut out = Output::default();
+        self.write_str(s, is_interned, &mut out);
+        out.bytes
+    }
+
+    fn write_str(&mut self, s: Str, is_interned: bool, out: &mut Output) {
+        let serialize: fn(&mut Self, (Str, bool), &mut Output) =
+            |_, (s, is_interned), out| out.push(&str_into_bytes(s, is_interned));
+        self.share((s, is_interned), serialize, out)
+    }
+
+    /// Serializes `names` as a tuple of interned strings.
+    pub fn serialize_strs(&mut self, names: Vec<Str>) -> Vec<u8> {
+        let mut out = Output::default();
+        self.write_strs(names, &mut out);
+        out.bytes
+    }
+
+    fn write_strs(&mut self, names: Vec<Str>, out: &mut Output) {
+        self.share(
+            names,
+            |ser, names, out| {
+                out.push(&Self::tuple_header(names.len()));
+                for name in names {
+                    ser.write_str(name, true, out);
+                }
+            },
+            out,
+        )
+    }
+
+    pub fn serialize_code(&mut self, code: CodeObj) -> Vec<u8> {
+        let mut out = Output::default();
+        self.write_code(code, &mut out);
+        out.bytes
+    }
+
+    fn write_code(&mut self, code: CodeObj, out: &mut Output) {
+        self.share(code, Self::write_code_fields, out)
+    }
+
+    fn write_code_fields(&mut self, code: CodeObj, out: &mut Output) {
+        let python_ver = self.python_ver;
+        out.push(&[DataTypePrefix::Code as u8]);
+        out.push(&code.argcount.to_le_bytes());
+        if python_ver.minor >= Some(8) {
+            out.push(&code.posonlyargcount.to_le_bytes());
+        }
+        out.push(&code.kwonlyargcount.to_le_bytes());
+        if python_ver.minor < Some(11) {
+            out.push(&code.nlocals.to_le_bytes());
+        }
+        out.push(&code.stacksize.to_le_bytes());
+        out.push(&code.flags.to_le_bytes());
+        // co_code is represented as PyStrObject (Not Ascii, Unicode)
+        out.push(&raw_string_into_bytes(code.code));
+        self.write_consts(code.consts, out); // write as PyTupleObject
+        self.write_strs(code.names, out);
+        self.write_locals(code.varnames, code.freevars, code.cellvars, out);
+        self.write_str(code.filename, false, out);
+        self.write_str(code.name, true, out);
+        if python_ver.minor >= Some(11) {
+            self.write_str(code.qualname, true, out);
+        }
+        out.push(&code.firstlineno.to_le_bytes());
+        // lnotab is represented as PyStrObject
+        out.push(&raw_string_into_bytes(code.lnotab));
+        if python_ver.minor >= Some(11) {
+            out.push(&raw_string_into_bytes(code.exceptiontable));
+        }
+    }
+
+    fn write_locals(
+        &mut self,
+        varnames: Vec<Str>,
+        freevars: Vec<Str>,
+        cellvars: Vec<Str>,
+        out: &mut Output,
+    ) {
+        if self.python_ver.minor >= Some(11) {
+            let varnames = varnames
+                .into_iter()
+                .filter(|n| !freevars.contains(n) && !cellvars.contains(n))
+                .collect::<Vec<_>>();
+            let localspluskinds = [
+                vec![FastKind::Local as u8; varnames.len()],
+                vec![FastKind::Free as u8; freevars.len()],
+                vec![FastKind::Cell as u8 + FastKind::Local as u8; cellvars.len()],
+            ]
+            .concat();
+            let localsplusnames = [varnames, freevars, cellvars].concat();
+            self.write_strs(localsplusnames, out);
+            out.push(&raw_string_into_bytes(localspluskinds));
+        } else {
+            self.write_strs(varnames, out);
+            self.write_strs(freevars, out);
+            self.write_strs(cellvars, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ty::deserialize::Deserializer;
+
+    const PY311: PythonVersion = PythonVersion::new(3, Some(11), Some(0));
+    const PY310: PythonVersion = PythonVersion::new(3, Some(10), Some(0));
+
+    fn module() -> CodeObj {
+        let mut inner = CodeObj::empty(vec!["x".into()], "<test>", "inner", 2, 0);
+        inner.consts.push(ValueObj::Str("a shared string".into()));
+        inner.names.push("shared_name".into());
+        let mut module = CodeObj::empty(vec![], "<test>", "<module>", 1, 0);
+        module.consts.push(ValueObj::Str("a shared string".into()));
+        module.consts.push(ValueObj::from(inner.clone()));
+        module.consts.push(ValueObj::from(inner));
+        module.names.push("shared_name".into());
+        module
+    }
+
+    #[test]
+    fn test_dedup_roundtrip() {
+        // "a shared string" in `inner`, the second `inner`, the names of `<module>` and "<test>" in `<module>`
+        // (and the qualnames "inner" and "<module>" for 3.11)
+        for (python_ver, shared) in [(PY310, 4), (PY311, 6)] {
+            let module = module();
+            let plain = Serializer::plain(python_ver).serialize_code(module.clone());
+            let mut ser = Serializer::new(python_ver);
+            let mut bytes = ser.serialize_code(module.clone());
+            assert_eq!(ser.stats().shared, shared);
+            assert_eq!(plain.len() - bytes.len(), ser.stats().saved_bytes);
+            // `Deserializer` does not support the locals of 3.11 (`co_localsplusnames`)
+            if python_ver.minor >= Some(11) {
+                continue;
+            }
+            let deserialized = Deserializer::new()
+                .deserialize_const(&mut bytes, python_ver)
+                .unwrap();
+            assert_eq!(deserialized, ValueObj::from(module));
+            assert!(bytes.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_dedup_nested_tuples() {
+        // the short strings in the second tuple are not flagged again, so the indices of the references are kept
+        // (tuples are deserialized as arrays)
+        let tuple = ValueObj::Array(
+            vec![
+                ValueObj::Str("a".into()),
+                ValueObj::Array(vec![ValueObj::Str("b".into()), ValueObj::Int(1)].into()),
+            ]
+            .into(),
+        );
+        let mut module = CodeObj::empty(vec![], "<test>", "<module>", 1, 0);
+        module.consts.push(tuple.clone());
+        module.consts.push(tuple);
+        module.consts.push(ValueObj::Str("a long string".into()));
+        module.consts.push(ValueObj::Str("a long string".into()));
+        let mut ser = Serializer::new(PY310);
+        let mut bytes = ser.serialize_code(module.clone());
+        assert_eq!(ser.stats().shared, 2);
+        let deserialized = Deserializer::new()
+            .deserialize_const(&mut bytes, PY310)
+            .unwrap();
+        assert_eq!(deserialized, ValueObj::from(module));
+    }
+
+    /// `expected` is `marshal.dumps` of Python (without `FLAG_REF`).
+    fn assert_roundtrip(value: ValueObj, expected: &[u8]) {
+        let mut bytes = Serializer::new(PY311).serialize_const(value.clone());
+        assert_eq!(bytes, expected, "{value}");
+        let deserialized = Deserializer::new()
+            .deserialize_const(&mut bytes, PY311)
+            .unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    fn test_long_roundtrip() {
+        assert_roundtrip(
+            ValueObj::Nat(3000000000),
+            &[108, 3, 0, 0, 0, 0, 94, 160, 101, 2, 0],
+        );
+        assert_roundtrip(
+            ValueObj::Nat(u64::MAX),
+            &[
+                108, 5, 0, 0, 0, 255, 127, 255, 127, 255, 127, 255, 127, 15, 0,
+            ],
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bigint_roundtrip() {
+        assert_roundtrip(
+            ValueObj::from(-(num_bigint::BigInt::from(1) << 70u32)),
+            &[108, 251, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4],
+        );
+    }
+
+    #[test]
+    fn test_intern_name_like_consts() {
+        let mut ser = Serializer::new(PY311);
+        let name = ser.serialize_const(ValueObj::Str("foo_1".into()));
+        let not_name = ser.serialize_const(ValueObj::Str("foo bar".into()));
+        assert_eq!(
+            name[0] & !FLAG_REF,
+            DataTypePrefix::ShortAsciiInterned as u8 & !FLAG_REF
+        );
+        assert_eq!(
+            not_name[0] & !FLAG_REF,
+            DataTypePrefix::ShortAscii as u8 & !FLAG_REF
+        );
+    }
+}
@@ -47,6 +47,8 @@ pub enum OpKind {
     BitXor,
     Shl,
     Shr,
+    In,
+    NotIn,
 }
 
 impl fmt::Display for OpKind {
@@ -77,6 +79,8 @@ impl fmt::Display for OpKind {
             Self::BitXor => write!(f, "^^"),
             Self::Shl => write!(f, "<<"),
             Self::Shr => write!(f, ">>"),
+            Self::In => write!(f, "in"),
+            Self::NotIn => write!(f, "notin"),
         }
     }
 }
@@ -88,6 +92,14 @@ impl OpKind {
             Self::Gt | Self::Lt | Self::Ge | Self::Le | Self::Eq | Self::Ne
         )
     }
+
+    /// union (`or`, `||`), intersection (`and`, `&&`), difference (`-`) and symmetric difference (`^^`)
+    pub fn is_set_operation(&self) -> bool {
+        matches!(
+            self,
+            Self::Or | Self::BitOr | Self::And | Self::BitAnd | Self::Sub | Self::BitXor
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -234,6 +246,9 @@ pub enum TyParam {
 
 impl PartialEq for TyParam {
     fn eq(&self, other: &Self) -> bool {
+        if ref_addr_eq!(self, other) {
+            return true;
+        }
         match (self, other) {
             (Self::Value(l), Self::Value(r)) => l == r,
             (Self::Type(l), Self::Type(r)) => l == r,
@@ -878,12 +893,12 @@ impl TyParam {
         Self::Erased(Box::new(t))
     }
 
-    // if self: Ratio, Succ(self) => self+ε
+    // if self: Ratio, succ(self) is the smallest float greater than self
     pub fn succ(self) -> Self {
         Self::app("succ".into(), vec![self])
     }
 
-    // if self: Ratio, Pred(self) => self-ε
+    // if self: Ratio, pred(self) is the largest float less than self
     pub fn pred(self) -> Self {
         Self::app("pred".into(), vec![self])
     }
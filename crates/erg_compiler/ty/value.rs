@@ -12,12 +12,15 @@ use erg_common::error::{ErrorCore, ErrorKind, Location};
 use erg_common::fresh::FRESH_GEN;
 use erg_common::io::Input;
 use erg_common::python_util::PythonVersion;
-use erg_common::serialize::*;
 use erg_common::set::Set;
 use erg_common::traits::LimitedDisplay;
-use erg_common::{dict, fmt_iter, impl_display_from_debug, log, switch_lang};
+use erg_common::{dict, fmt_iter, impl_display_from_debug, log};
 use erg_common::{ArcArray, Str};
-use erg_parser::ast::{ConstArgs, ConstExpr};
+use erg_parser::ast::{self, ConstArgs, ConstExpr};
+#[cfg(feature = "bigint")]
+use num_bigint::{BigInt, Sign};
+#[cfg(feature = "bigint")]
+use num_traits::ToPrimitive;
 
 use crate::context::eval::type_from_token_kind;
 use crate::context::Context;
@@ -25,7 +28,9 @@ use crate::context::Context;
 use self::value_set::inner_class;
 
 use super::codeobj::CodeObj;
-use super::constructors::{array_t, dict_t, refinement, set_t, tuple_t};
+use super::constructors::{array_t, dict_t, mono, refinement, set_t, tuple_t};
+use super::rational::Rational;
+use super::serialize::Serializer;
 use super::typaram::TyParam;
 use super::{ConstSubr, Field, HasType, Predicate, Type};
 use super::{CONTAINER_OMIT_THRESHOLD, STR_OMIT_THRESHOLD};
@@ -63,14 +68,22 @@ pub struct ClassTypeObj {
     pub t: Type,
     pub base: Option<Box<TypeObj>>,
     pub impls: Option<Box<TypeObj>>,
+    /// the traits whose methods are generated from the fields (`Derive := Eq and Show`)
+    pub derive: Option<Box<TypeObj>>,
 }
 
 impl ClassTypeObj {
-    pub fn new(t: Type, base: Option<TypeObj>, impls: Option<TypeObj>) -> Self {
+    pub fn new(
+        t: Type,
+        base: Option<TypeObj>,
+        impls: Option<TypeObj>,
+        derive: Option<TypeObj>,
+    ) -> Self {
         Self {
             t,
             base: base.map(Box::new),
             impls: impls.map(Box::new),
+            derive: derive.map(Box::new),
         }
     }
 }
@@ -223,8 +236,13 @@ impl LimitedDisplay for GenTypeObj {
 }
 
 impl GenTypeObj {
-    pub fn class(t: Type, require: Option<TypeObj>, impls: Option<TypeObj>) -> Self {
-        GenTypeObj::Class(ClassTypeObj::new(t, require, impls))
+    pub fn class(
+        t: Type,
+        require: Option<TypeObj>,
+        impls: Option<TypeObj>,
+        derive: Option<TypeObj>,
+    ) -> Self {
+        GenTypeObj::Class(ClassTypeObj::new(t, require, impls, derive))
     }
 
     pub fn inherited(
@@ -297,6 +315,13 @@ impl GenTypeObj {
         }
     }
 
+    pub fn derive(&self) -> Option<&TypeObj> {
+        match self {
+            Self::Class(class) => class.derive.as_ref().map(|x| x.as_ref()),
+            _ => None,
+        }
+    }
+
     pub fn additional(&self) -> Option<&TypeObj> {
         match self {
             Self::Subclass(subclass) => subclass.additional.as_ref().map(|x| x.as_ref()),
@@ -454,8 +479,18 @@ impl TypeObj {
 pub enum ValueObj {
     Int(i32),
     Nat(u64),
+    /// An integer that fits in neither `Int` nor `Nat` (see `ValueObj::from(BigInt)`).
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    /// The exact value of a `Ratio` literal (or of the constant arithmetic on them).
+    /// Converted to a float at code generation.
+    Ratio(Rational),
     Float(f64),
     Str(Str),
+    Bytes(ArcArray<u8>),
+    /// A quoted expression (`quote(...)`), which is passed to and returned from macros.
+    /// At runtime, it is a string representation of the expression.
+    Ast(Box<ast::Expr>),
     Bool(bool),
     Array(ArcArray<ValueObj>),
     Set(Set<ValueObj>),
@@ -495,6 +530,21 @@ impl fmt::Debug for ValueObj {
                     write!(f, "{n}")
                 }
             }
+            #[cfg(feature = "bigint")]
+            Self::BigInt(i) => {
+                if cfg!(feature = "debug") {
+                    write!(f, "BigInt({i})")
+                } else {
+                    write!(f, "{i}")
+                }
+            }
+            Self::Ratio(r) => {
+                if cfg!(feature = "debug") {
+                    write!(f, "Ratio({r})")
+                } else {
+                    write!(f, "{r}")
+                }
+            }
             Self::Float(fl) => {
                 // In Rust, .0 is shown omitted.
                 if fl.fract() < 1e-10 {
@@ -508,6 +558,18 @@ impl fmt::Debug for ValueObj {
                 Ok(())
             }
             Self::Str(s) => write!(f, "\"{}\"", s.escape()),
+            Self::Bytes(bytes) => {
+                write!(f, "b\"")?;
+                for &b in bytes.iter() {
+                    match b {
+                        b'"' | b'\\' => write!(f, "\\{}", b as char)?,
+                        0x20..=0x7e => write!(f, "{}", b as char)?,
+                        _ => write!(f, "\\x{b:02x}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Self::Ast(expr) => write!(f, "quote({expr})"),
             Self::Bool(b) => {
                 if *b {
                     write!(f, "True")
@@ -667,6 +729,11 @@ impl Neg for ValueObj {
         match self {
             Self::Int(i) => Self::Int(-i),
             Self::Nat(n) => Self::Int(-(n as i32)),
+            #[cfg(feature = "bigint")]
+            Self::BigInt(i) => Self::from(-i),
+            Self::Ratio(r) => r
+                .checked_neg()
+                .map_or_else(|| Self::Float(-r.to_f64()), Self::Ratio),
             Self::Float(fl) => Self::Float(-fl),
             Self::Inf => Self::NegInf,
             Self::NegInf => Self::Inf,
@@ -681,9 +748,14 @@ impl Hash for ValueObj {
         match self {
             Self::Int(i) => i.hash(state),
             Self::Nat(n) => n.hash(state),
+            #[cfg(feature = "bigint")]
+            Self::BigInt(i) => i.hash(state),
+            Self::Ratio(r) => r.hash(state),
             // TODO:
             Self::Float(f) => f.to_bits().hash(state),
             Self::Str(s) => s.hash(state),
+            Self::Bytes(bytes) => bytes.hash(state),
+            Self::Ast(expr) => expr.hash(state),
             Self::Bool(b) => b.hash(state),
             Self::Array(arr) => arr.hash(state),
             Self::Dict(dict) => dict.hash(state),
@@ -747,6 +819,26 @@ impl From<usize> for ValueObj {
     }
 }
 
+#[cfg(feature = "bigint")]
+impl From<BigInt> for ValueObj {
+    /// `Nat` or `Int` if the value fits in them.
+    fn from(item: BigInt) -> Self {
+        if let Ok(n) = u64::try_from(&item) {
+            ValueObj::Nat(n)
+        } else if let Ok(i) = i32::try_from(&item) {
+            ValueObj::Int(i)
+        } else {
+            ValueObj::BigInt(item)
+        }
+    }
+}
+
+impl From<Rational> for ValueObj {
+    fn from(item: Rational) -> Self {
+        ValueObj::Ratio(item)
+    }
+}
+
 impl From<f64> for ValueObj {
     fn from(item: f64) -> Self {
         ValueObj::Float(item)
@@ -797,6 +889,9 @@ impl TryFrom<&ValueObj> for f64 {
         match val {
             ValueObj::Int(i) => Ok(*i as f64),
             ValueObj::Nat(n) => Ok(*n as f64),
+            #[cfg(feature = "bigint")]
+            ValueObj::BigInt(i) => i.to_f64().ok_or(()),
+            ValueObj::Ratio(r) => Ok(r.to_f64()),
             ValueObj::Float(f) => Ok(*f),
             ValueObj::Inf => Ok(f64::INFINITY),
             ValueObj::NegInf => Ok(f64::NEG_INFINITY),
@@ -841,6 +936,59 @@ impl HasType for ValueObj {
     }
 }
 
+/// Python's `divmod` of integers: the quotient is floored, and the remainder has the sign of the divisor.
+fn int_divmod(l: i128, r: i128) -> Option<(i128, i128)> {
+    let (q, m) = (l.checked_div(r)?, l.checked_rem(r)?);
+    if m != 0 && (m < 0) != (r < 0) {
+        Some((q - 1, m + r))
+    } else {
+        Some((q, m))
+    }
+}
+
+/// Python's `divmod` of `BigInt`s (see `int_divmod`).
+#[cfg(feature = "bigint")]
+fn bigint_divmod(l: BigInt, r: BigInt) -> Option<(BigInt, BigInt)> {
+    if r.sign() == Sign::NoSign {
+        return None;
+    }
+    let (q, m) = (&l / &r, &l % &r);
+    if m.sign() != Sign::NoSign && (m.sign() == Sign::Minus) != (r.sign() == Sign::Minus) {
+        Some((q - 1, m + r))
+    } else {
+        Some((q, m))
+    }
+}
+
+/// Python's `divmod` of floats (a port of `float_divmod` of CPython).
+/// This differs from `((l / r).floor(), l % r)`, e.g. `1.0 // 0.1 == 9.0`.
+fn float_divmod(l: f64, r: f64) -> Option<(f64, f64)> {
+    if r == 0.0 {
+        return None;
+    }
+    let mut m = l % r;
+    let mut div = (l - m) / r;
+    if m != 0.0 {
+        if (r < 0.0) != (m < 0.0) {
+            m += r;
+            div -= 1.0;
+        }
+    } else {
+        m = 0.0f64.copysign(r);
+    }
+    let floordiv = if div != 0.0 {
+        let floordiv = div.floor();
+        if div - floordiv > 0.5 {
+            floordiv + 1.0
+        } else {
+            floordiv
+        }
+    } else {
+        0.0f64.copysign(l / r)
+    };
+    Some((floordiv, m))
+}
+
 impl ValueObj {
     pub const fn builtin_class(t: Type) -> Self {
         ValueObj::Type(TypeObj::Builtin {
@@ -871,19 +1019,33 @@ impl ValueObj {
     pub const fn is_num(&self) -> bool {
         matches!(
             self,
-            Self::Float(_) | Self::Int(_) | Self::Nat(_) | Self::Bool(_)
-        )
+            Self::Float(_) | Self::Ratio(_) | Self::Int(_) | Self::Nat(_) | Self::Bool(_)
+        ) || self.is_bigint()
     }
 
     pub const fn is_float(&self) -> bool {
         matches!(
             self,
-            Self::Float(_) | Self::Int(_) | Self::Nat(_) | Self::Bool(_)
-        )
+            Self::Float(_) | Self::Ratio(_) | Self::Int(_) | Self::Nat(_) | Self::Bool(_)
+        ) || self.is_bigint()
     }
 
     pub const fn is_int(&self) -> bool {
-        matches!(self, Self::Int(_) | Self::Nat(_) | Self::Bool(_))
+        matches!(self, Self::Int(_) | Self::Nat(_) | Self::Bool(_)) || self.is_bigint()
+    }
+
+    #[cfg(feature = "bigint")]
+    pub const fn is_bigint(&self) -> bool {
+        matches!(self, Self::BigInt(_))
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    pub const fn is_bigint(&self) -> bool {
+        false
+    }
+
+    pub const fn is_ratio(&self) -> bool {
+        matches!(self, Self::Ratio(_))
     }
 
     pub const fn is_nat(&self) -> bool {
@@ -904,41 +1066,35 @@ impl ValueObj {
 
     pub fn from_str(t: Type, mut content: Str) -> Option<Self> {
         match t {
-            Type::Int => content.replace('_', "").parse::<i32>().ok().map(Self::Int),
+            Type::Int => {
+                let content = content.replace('_', "");
+                (content.parse::<i32>().ok().map(Self::Int))
+                    .or_else(|| Self::parse_bigint(&content, 10))
+            }
             Type::Nat => {
                 let content = content
                     .trim_start_matches('-') // -0 -> 0
                     .replace('_', "");
-                if content.len() <= 1 {
-                    return content.parse::<u64>().ok().map(Self::Nat);
-                }
-                match &content[0..=1] {
-                    pre @ ("0b" | "0B") => {
-                        let content = content.trim_start_matches(pre);
-                        u64::from_str_radix(content, 2).ok().map(Self::Nat)
-                    }
-                    pre @ ("0o" | "0O") => {
-                        let content = content.trim_start_matches(pre);
-                        u64::from_str_radix(content, 8).ok().map(Self::Nat)
-                    }
-                    pre @ ("0x" | "0X") => {
-                        let content = content.trim_start_matches(pre);
-                        u64::from_str_radix(content, 16).ok().map(Self::Nat)
-                    }
-                    _ => content.parse::<u64>().ok().map(Self::Nat),
-                }
+                let (content, radix) = match content.get(0..=1) {
+                    Some(pre @ ("0b" | "0B")) => (content.trim_start_matches(pre), 2),
+                    Some(pre @ ("0o" | "0O")) => (content.trim_start_matches(pre), 8),
+                    Some(pre @ ("0x" | "0X")) => (content.trim_start_matches(pre), 16),
+                    _ => (&content[..], 10),
+                };
+                (u64::from_str_radix(content, radix).ok().map(Self::Nat))
+                    .or_else(|| Self::parse_bigint(content, radix))
             }
             Type::Float => content
                 .replace('_', "")
                 .parse::<f64>()
                 .ok()
                 .map(Self::Float),
-            // TODO:
-            Type::Ratio => content
-                .replace('_', "")
-                .parse::<f64>()
-                .ok()
-                .map(Self::Float),
+            // a float if the literal cannot be represented exactly (e.g. `1e400`)
+            Type::Ratio => {
+                let content = content.replace('_', "");
+                (Rational::parse(&content).map(Self::Ratio))
+                    .or_else(|| content.parse::<f64>().ok().map(Self::Float))
+            }
             Type::Str => {
                 if &content[..] == "\"\"" {
                     Some(Self::Str(Str::from("")))
@@ -970,58 +1126,7 @@ impl ValueObj {
     }
 
     pub fn into_bytes(self, python_ver: PythonVersion) -> Vec<u8> {
-        match self {
-            Self::Int(i) => [vec![DataTypePrefix::Int32 as u8], i.to_le_bytes().to_vec()].concat(),
-            // TODO: Natとしてシリアライズ
-            Self::Nat(n) => [
-                vec![DataTypePrefix::Int32 as u8],
-                (n as i32).to_le_bytes().to_vec(),
-            ]
-            .concat(),
-            Self::Float(f) => [
-                vec![DataTypePrefix::BinFloat as u8],
-                f.to_le_bytes().to_vec(),
-            ]
-            .concat(),
-            Self::Str(s) => str_into_bytes(s, false),
-            Self::Bool(true) => vec![DataTypePrefix::True as u8],
-            Self::Bool(false) => vec![DataTypePrefix::False as u8],
-            // TODO: SmallTuple
-            Self::Array(arr) => {
-                let mut bytes = Vec::with_capacity(arr.len());
-                bytes.push(DataTypePrefix::Tuple as u8);
-                bytes.append(&mut (arr.len() as u32).to_le_bytes().to_vec());
-                for obj in arr.iter().cloned() {
-                    bytes.append(&mut obj.into_bytes(python_ver));
-                }
-                bytes
-            }
-            Self::Tuple(tup) => {
-                let mut bytes = Vec::with_capacity(tup.len());
-                bytes.push(DataTypePrefix::Tuple as u8);
-                bytes.append(&mut (tup.len() as u32).to_le_bytes().to_vec());
-                for obj in tup.iter().cloned() {
-                    bytes.append(&mut obj.into_bytes(python_ver));
-                }
-                bytes
-            }
-            Self::None => {
-                vec![DataTypePrefix::None as u8]
-            }
-            Self::Code(c) => c.into_bytes(python_ver),
-            // Dict
-            other => {
-                panic!(
-                    "{}",
-                    switch_lang!(
-                        "japanese" => format!("このオブジェクトはシリアライズできません: {other}"),
-                        "simplified_chinese" => format!("此对象无法序列化: {other}"),
-                        "traditional_chinese" => format!("此對象無法序列化: {other}"),
-                        "english" => format!("this object cannot be serialized: {other}"),
-                    )
-                )
-            }
-        }
+        Serializer::new(python_ver).serialize_const(self)
     }
 
     pub fn from_const_expr(expr: ConstExpr) -> Self {
@@ -1046,8 +1151,15 @@ impl ValueObj {
         match self {
             Self::Int(_) => Type::Int,
             Self::Nat(_) => Type::Nat,
-            Self::Float(_) => Type::Float,
+            #[cfg(feature = "bigint")]
+            Self::BigInt(i) if i.sign() == Sign::Minus => Type::Int,
+            #[cfg(feature = "bigint")]
+            Self::BigInt(_) => Type::Nat,
+            // the literals are typed as before, since they are `float`s at runtime (e.g. `Float` is not `Eq`)
+            Self::Ratio(_) | Self::Float(_) => Type::Float,
             Self::Str(_) => Type::Str,
+            Self::Bytes(_) => mono("Bytes"),
+            Self::Ast(_) => mono("Ast"),
             Self::Bool(_) => Type::Bool,
             Self::Array(arr) => array_t(
                 // REVIEW: Never?
@@ -1057,11 +1169,15 @@ impl ValueObj {
                     .unwrap_or(Type::Never),
                 TyParam::value(arr.len()),
             ),
+            // `{"a": Int, "b": Str}.class() == Dict({Str: ClassType})`
             Self::Dict(dict) => {
-                let tp = dict
-                    .iter()
-                    .map(|(k, v)| (TyParam::value(k.clone()), TyParam::value(v.clone())));
-                dict_t(TyParam::Dict(tp.collect()))
+                let mut tp = Dict::new();
+                for (k, v) in dict.iter() {
+                    if !tp.contains_key(&TyParam::t(k.class())) {
+                        tp.insert(TyParam::t(k.class()), TyParam::t(v.class()));
+                    }
+                }
+                dict_t(TyParam::Dict(tp))
             }
             Self::Tuple(tup) => tuple_t(tup.iter().map(|v| v.class()).collect()),
             Self::Set(st) => set_t(inner_class(st), TyParam::value(st.len())),
@@ -1089,11 +1205,10 @@ impl ValueObj {
             return Some(Ordering::Equal);
         }
         match (self, other) {
-            (l, r) if l.is_num() && r.is_num() => {
-                f64::try_from(l).ok()?.partial_cmp(&f64::try_from(r).ok()?)
-            }
+            (l, r) if l.is_num() && r.is_num() => l.num_cmp(r),
             (Self::Inf, n) | (n, Self::NegInf) if n.is_num() => Some(Ordering::Greater),
             (n, Self::Inf) | (Self::NegInf, n) if n.is_num() => Some(Ordering::Less),
+            (Self::Str(l), Self::Str(r)) => Some(l.cmp(r)),
             (Self::NegInf, Self::Inf) => Some(Ordering::Less),
             (Self::Inf, Self::NegInf) => Some(Ordering::Greater),
             // REVIEW: 等しいとみなしてよいのか?
@@ -1118,23 +1233,35 @@ impl ValueObj {
     }
 
     // REVIEW: allow_divergenceオプションを付けるべきか?
+    /// The arithmetic operations follow the semantics of Python.
+    /// `None` is returned if the operation raises an error in Python (e.g. `ZeroDivisionError`),
+    /// or the result does not fit in the value (e.g. the overflow of `Int`).
     pub fn try_add(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l + r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l + r)),
+            (Self::Int(l), Self::Int(r)) => Self::int_from_i128(l as i128 + r as i128),
+            (Self::Nat(l), Self::Nat(r)) => Self::nat_from_u128(l as u128 + r as u128),
             (Self::Float(l), Self::Float(r)) => Some(Self::Float(l + r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l + r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(l as i32 + r)),
-            (Self::Float(l), Self::Nat(r)) => Some(Self::Float(l - r as f64)),
-            (Self::Int(l), Self::Float(r)) => Some(Self::Float(l as f64 - r)),
-            (Self::Nat(l), Self::Float(r)) => Some(Self::Float(l as f64 - r)),
-            (Self::Float(l), Self::Int(r)) => Some(Self::Float(l - r as f64)),
+            (Self::Int(l), Self::Nat(r)) => Self::from_i128(l as i128 + r as i128),
+            (Self::Nat(l), Self::Int(r)) => Self::int_from_i128(l as i128 + r as i128),
+            (Self::Float(l), Self::Nat(r)) => Some(Self::Float(l + r as f64)),
+            (Self::Int(l), Self::Float(r)) => Some(Self::Float(l as f64 + r)),
+            (Self::Nat(l), Self::Float(r)) => Some(Self::Float(l as f64 + r)),
+            (Self::Float(l), Self::Int(r)) => Some(Self::Float(l + r as f64)),
             (Self::Str(l), Self::Str(r)) => Some(Self::Str(Str::from(format!("{l}{r}")))),
             (Self::Array(l), Self::Array(r)) => {
                 let arr = Arc::from([l, r].concat());
                 Some(Self::Array(arr))
             }
             (Self::Dict(l), Self::Dict(r)) => Some(Self::Dict(l.concat(r))),
+            // `{x = 1} + {x = 2; y = 3} == {x = 2; y = 3}`
+            (Self::Record(l), Self::Record(r)) => Some(Self::Record(l.concat(r))),
+            (l, r) if l.is_ratio() || r.is_ratio() => {
+                l.ratio_op(r, Rational::checked_add, |l, r| Some(l + r))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if l.is_bigint() || r.is_bigint() => {
+                l.bigint_op(r, |l, r| Some(l + r), |l, r| Some(l + r))
+            }
             (inf @ (Self::Inf | Self::NegInf), _) | (_, inf @ (Self::Inf | Self::NegInf)) => {
                 Some(inf)
             }
@@ -1144,15 +1271,31 @@ impl ValueObj {
 
     pub fn try_sub(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l - r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Int(l as i32 - r as i32)),
+            (Self::Int(l), Self::Int(r)) => Self::int_from_i128(l as i128 - r as i128),
+            (Self::Nat(l), Self::Nat(r)) => Self::int_from_i128(l as i128 - r as i128),
             (Self::Float(l), Self::Float(r)) => Some(Self::Float(l - r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l - r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from(l as i32 - r)),
+            (Self::Int(l), Self::Nat(r)) => Self::from_i128(l as i128 - r as i128),
+            (Self::Nat(l), Self::Int(r)) => Self::from_i128(l as i128 - r as i128),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l - r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 - r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l - r as f64)),
             (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 - r)),
+            // `{x = 1; y = 2} - {"y"} == {x = 1}`
+            (Self::Record(l), Self::Set(names)) => {
+                let rec = l
+                    .into_iter()
+                    .filter(|(field, _)| !names.contains(&Self::Str(field.symbol.clone())))
+                    .collect();
+                Some(Self::Record(rec))
+            }
+            (Self::Set(l), Self::Set(r)) => Some(Self::Set(l.difference(&r))),
+            (l, r) if l.is_ratio() || r.is_ratio() => {
+                l.ratio_op(r, Rational::checked_sub, |l, r| Some(l - r))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if l.is_bigint() || r.is_bigint() => {
+                l.bigint_op(r, |l, r| Some(l - r), |l, r| Some(l - r))
+            }
             (inf @ (Self::Inf | Self::NegInf), other)
             | (other, inf @ (Self::Inf | Self::NegInf))
                 if other != Self::Inf && other != Self::NegInf =>
@@ -1165,16 +1308,23 @@ impl ValueObj {
 
     pub fn try_mul(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::from(l * r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l * r)),
+            (Self::Int(l), Self::Int(r)) => Self::from_i128(l as i128 * r as i128),
+            (Self::Nat(l), Self::Nat(r)) => Self::nat_from_u128(l as u128 * r as u128),
             (Self::Float(l), Self::Float(r)) => Some(Self::Float(l * r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l * r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(l as i32 * r)),
+            (Self::Int(l), Self::Nat(r)) => Self::int_from_i128(l as i128 * r as i128),
+            (Self::Nat(l), Self::Int(r)) => Self::int_from_i128(l as i128 * r as i128),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l * r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 * r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l * r as f64)),
             (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 * r)),
             (Self::Str(l), Self::Nat(r)) => Some(Self::Str(Str::from(l.repeat(r as usize)))),
+            (l, r) if l.is_ratio() || r.is_ratio() => {
+                l.ratio_op(r, Rational::checked_mul, |l, r| Some(l * r))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if l.is_bigint() || r.is_bigint() => {
+                l.bigint_op(r, |l, r| Some(l * r), |l, r| Some(l * r))
+            }
             (inf @ (Self::Inf | Self::NegInf), _) | (_, inf @ (Self::Inf | Self::NegInf)) => {
                 Some(inf)
             }
@@ -1183,6 +1333,9 @@ impl ValueObj {
     }
 
     pub fn try_div(self, other: Self) -> Option<Self> {
+        if other.is_num_zero() {
+            return None;
+        }
         match (self, other) {
             (Self::Int(l), Self::Int(r)) => Some(Self::Float(l as f64 / r as f64)),
             (Self::Nat(l), Self::Nat(r)) => Some(Self::Float(l as f64 / r as f64)),
@@ -1193,6 +1346,13 @@ impl ValueObj {
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 / r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l / r as f64)),
             (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 / r)),
+            (l, r) if l.is_ratio() || r.is_ratio() => {
+                l.ratio_op(r, Rational::checked_div, |l, r| Some(l / r))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if l.is_bigint() || r.is_bigint() => {
+                Some(Self::Float(l.as_f64()? / r.as_f64()?))
+            }
             // TODO: x/±Inf = 0
             _ => None,
         }
@@ -1200,31 +1360,235 @@ impl ValueObj {
 
     pub fn try_floordiv(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l / r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l / r)),
-            (Self::Float(l), Self::Float(r)) => Some(Self::Float((l / r).floor())),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l / r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(l as i32 / r)),
-            (Self::Float(l), Self::Nat(r)) => Some(Self::Float((l / r as f64).floor())),
-            (Self::Nat(l), Self::Float(r)) => Some(Self::Float((l as f64 / r).floor())),
-            (Self::Float(l), Self::Int(r)) => Some(Self::Float((l / r as f64).floor())),
-            (Self::Int(l), Self::Float(r)) => Some(Self::Float((l as f64 / r).floor())),
+            (Self::Int(l), Self::Int(r)) => {
+                Self::int_from_i128(int_divmod(l as i128, r as i128)?.0)
+            }
+            (Self::Nat(l), Self::Nat(r)) => l.checked_div(r).map(Self::Nat),
+            (Self::Int(l), Self::Nat(r)) => {
+                Self::int_from_i128(int_divmod(l as i128, r as i128)?.0)
+            }
+            (Self::Nat(l), Self::Int(r)) => {
+                Self::int_from_i128(int_divmod(l as i128, r as i128)?.0)
+            }
+            (Self::Float(l), Self::Float(r)) => Some(Self::Float(float_divmod(l, r)?.0)),
+            (Self::Float(l), Self::Nat(r)) => Some(Self::Float(float_divmod(l, r as f64)?.0)),
+            (Self::Nat(l), Self::Float(r)) => Some(Self::Float(float_divmod(l as f64, r)?.0)),
+            (Self::Float(l), Self::Int(r)) => Some(Self::Float(float_divmod(l, r as f64)?.0)),
+            (Self::Int(l), Self::Float(r)) => Some(Self::Float(float_divmod(l as f64, r)?.0)),
+            (l, r) if l.is_ratio() || r.is_ratio() => l.ratio_op(
+                r,
+                |l, r| Some(l.checked_divmod(r)?.0),
+                |l, r| Some(float_divmod(l, r)?.0),
+            ),
+            #[cfg(feature = "bigint")]
+            (l, r) if l.is_bigint() || r.is_bigint() => l.bigint_op(
+                r,
+                |l, r| Some(bigint_divmod(l, r)?.0),
+                |l, r| Some(float_divmod(l, r)?.0),
+            ),
             // TODO: x//±Inf = 0
             _ => None,
         }
     }
 
+    pub fn try_mod(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Int(l), Self::Int(r)) => {
+                Self::int_from_i128(int_divmod(l as i128, r as i128)?.1)
+            }
+            (Self::Nat(l), Self::Nat(r)) => l.checked_rem(r).map(Self::Nat),
+            (Self::Int(l), Self::Nat(r)) => {
+                Self::int_from_i128(int_divmod(l as i128, r as i128)?.1)
+            }
+            (Self::Nat(l), Self::Int(r)) => {
+                Self::int_from_i128(int_divmod(l as i128, r as i128)?.1)
+            }
+            (Self::Float(l), Self::Float(r)) => Some(Self::Float(float_divmod(l, r)?.1)),
+            (Self::Float(l), Self::Nat(r)) => Some(Self::Float(float_divmod(l, r as f64)?.1)),
+            (Self::Nat(l), Self::Float(r)) => Some(Self::Float(float_divmod(l as f64, r)?.1)),
+            (Self::Float(l), Self::Int(r)) => Some(Self::Float(float_divmod(l, r as f64)?.1)),
+            (Self::Int(l), Self::Float(r)) => Some(Self::Float(float_divmod(l as f64, r)?.1)),
+            (l, r) if l.is_ratio() || r.is_ratio() => l.ratio_op(
+                r,
+                |l, r| Some(l.checked_divmod(r)?.1),
+                |l, r| Some(float_divmod(l, r)?.1),
+            ),
+            #[cfg(feature = "bigint")]
+            (l, r) if l.is_bigint() || r.is_bigint() => l.bigint_op(
+                r,
+                |l, r| Some(bigint_divmod(l, r)?.1),
+                |l, r| Some(float_divmod(l, r)?.1),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The inverse of `erg_common::serialize::long_into_bytes`.
+    pub fn from_long_digits(negative: bool, digits: &[u16]) -> Option<Self> {
+        const MAX_I128_DIGITS: usize = 8;
+        #[cfg(feature = "bigint")]
+        if digits.len() > MAX_I128_DIGITS {
+            let magnitude = (digits.iter().rev()).fold(BigInt::from(0), |acc, d| (acc << 15) + d);
+            return Some(Self::from(if negative { -magnitude } else { magnitude }));
+        }
+        if digits.len() > MAX_I128_DIGITS {
+            return None;
+        }
+        let magnitude = (digits.iter().rev()).fold(0i128, |acc, d| (acc << 15) | *d as i128);
+        Self::from_i128(if negative { -magnitude } else { magnitude })
+    }
+
+    fn is_num_zero(&self) -> bool {
+        match self {
+            Self::Int(i) => *i == 0,
+            Self::Nat(n) => *n == 0,
+            Self::Ratio(r) => r.is_zero(),
+            Self::Float(f) => *f == 0.0,
+            _ => false,
+        }
+    }
+
+    /// `Nat` if `n >= 0`, otherwise `Int` (the same as `ValueObj::from(i32)`)
+    fn from_i128(n: i128) -> Option<Self> {
+        if n >= 0 {
+            u64::try_from(n).ok().map(Self::Nat)
+        } else {
+            i32::try_from(n).ok().map(Self::Int)
+        }
+        .or_else(|| Self::overflowed(n))
+    }
+
+    fn int_from_i128(n: i128) -> Option<Self> {
+        (i32::try_from(n).ok().map(Self::Int)).or_else(|| Self::overflowed(n))
+    }
+
+    fn nat_from_u128(n: u128) -> Option<Self> {
+        (u64::try_from(n).ok().map(Self::Nat)).or_else(|| Self::overflowed(n))
+    }
+
+    /// The value of an integer that fits in neither `Int` nor `Nat`.
+    #[cfg(feature = "bigint")]
+    fn overflowed(n: impl Into<BigInt>) -> Option<Self> {
+        Some(Self::from(n.into()))
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    fn overflowed<T>(_n: T) -> Option<Self> {
+        None
+    }
+
+    #[cfg(feature = "bigint")]
+    fn parse_bigint(content: &str, radix: u32) -> Option<Self> {
+        BigInt::parse_bytes(content.as_bytes(), radix).map(Self::from)
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    fn parse_bigint(_content: &str, _radix: u32) -> Option<Self> {
+        None
+    }
+
+    #[cfg(feature = "bigint")]
+    fn as_bigint(&self) -> Option<BigInt> {
+        match self {
+            Self::Int(i) => Some(BigInt::from(*i)),
+            Self::Nat(n) => Some(BigInt::from(*n)),
+            Self::BigInt(i) => Some(i.clone()),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(i) => Some(*i as f64),
+            Self::Nat(n) => Some(*n as f64),
+            Self::Float(f) => Some(*f),
+            Self::BigInt(i) => i.to_f64(),
+            _ => None,
+        }
+    }
+
+    /// Applies `int_op` if both are integers, otherwise `float_op`.
+    /// Called if either operand is a `BigInt`.
+    #[cfg(feature = "bigint")]
+    fn bigint_op(
+        self,
+        other: Self,
+        int_op: impl FnOnce(BigInt, BigInt) -> Option<BigInt>,
+        float_op: impl FnOnce(f64, f64) -> Option<f64>,
+    ) -> Option<Self> {
+        match (self.as_bigint(), other.as_bigint()) {
+            (Some(l), Some(r)) => int_op(l, r).map(Self::from),
+            _ => float_op(self.as_f64()?, other.as_f64()?).map(Self::Float),
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    fn bigint_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.as_bigint(), other.as_bigint()) {
+            (Some(l), Some(r)) => Some(l.cmp(&r)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+
+    /// `Ratio` or an integer that fits in `Rational`
+    fn as_rational(&self) -> Option<Rational> {
+        match self {
+            Self::Ratio(r) => Some(*r),
+            Self::Int(i) => Some(Rational::from_int(*i as i64)),
+            Self::Nat(n) => Rational::new(*n as i128, 1),
+            Self::Bool(b) => Some(Rational::from_int(*b as i64)),
+            #[cfg(feature = "bigint")]
+            Self::BigInt(i) => Rational::new(i128::try_from(i).ok()?, 1),
+            _ => None,
+        }
+    }
+
+    /// Applies `ratio_op` if both are exact, otherwise (or if it overflows) `float_op`.
+    /// Called if either operand is a `Ratio`.
+    fn ratio_op(
+        self,
+        other: Self,
+        ratio_op: impl FnOnce(Rational, Rational) -> Option<Rational>,
+        float_op: impl FnOnce(f64, f64) -> Option<f64>,
+    ) -> Option<Self> {
+        if let (Some(l), Some(r)) = (self.as_rational(), other.as_rational()) {
+            if let Some(res) = ratio_op(l, r) {
+                return Some(Self::Ratio(res));
+            }
+        }
+        let (l, r) = (f64::try_from(&self).ok()?, f64::try_from(&other).ok()?);
+        float_op(l, r).map(Self::Float)
+    }
+
+    /// Compares exactly if both are `Ratio`s or integers, otherwise as floats.
+    fn num_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.as_rational(), other.as_rational()) {
+            (Some(l), Some(r)) if self.is_ratio() || other.is_ratio() => Some(l.cmp(&r)),
+            _ => f64::try_from(self)
+                .ok()?
+                .partial_cmp(&f64::try_from(other).ok()?),
+        }
+    }
+
     pub fn try_gt(self, other: Self) -> Option<Self> {
         match (self, other) {
             (Self::Int(l), Self::Int(r)) => Some(Self::from(l > r)),
             (Self::Nat(l), Self::Nat(r)) => Some(Self::from(l > r)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from(l > r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l > r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from(l as i32 > r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from((l as i128) > r as i128)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i128) > r as i128)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l > r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 > r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l > r as f64)),
             (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 > r)),
+            (l, r) if (l.is_ratio() || r.is_ratio()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.num_cmp(&r) == Some(Ordering::Greater)))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if (l.is_bigint() || r.is_bigint()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.bigint_cmp(&r) == Some(Ordering::Greater)))
+            }
             _ => None,
         }
     }
@@ -1234,12 +1598,25 @@ impl ValueObj {
             (Self::Int(l), Self::Int(r)) => Some(Self::from(l >= r)),
             (Self::Nat(l), Self::Nat(r)) => Some(Self::from(l >= r)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from(l >= r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l >= r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from(l as i32 >= r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from((l as i128) >= r as i128)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i128) >= r as i128)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l >= r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 >= r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l >= r as f64)),
             (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 >= r)),
+            (l, r) if (l.is_ratio() || r.is_ratio()) && l.is_num() && r.is_num() => {
+                Some(Self::from(matches!(
+                    l.num_cmp(&r),
+                    Some(Ordering::Greater | Ordering::Equal)
+                )))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if (l.is_bigint() || r.is_bigint()) && l.is_num() && r.is_num() => {
+                Some(Self::from(matches!(
+                    l.bigint_cmp(&r),
+                    Some(Ordering::Greater | Ordering::Equal)
+                )))
+            }
             _ => None,
         }
     }
@@ -1249,12 +1626,19 @@ impl ValueObj {
             (Self::Int(l), Self::Int(r)) => Some(Self::from(l < r)),
             (Self::Nat(l), Self::Nat(r)) => Some(Self::from(l < r)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from(l < r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l < r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i32) < r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from((l as i128) < r as i128)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i128) < r as i128)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l < r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from((l as f64) < r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l < r as f64)),
             (Self::Int(l), Self::Float(r)) => Some(Self::from((l as f64) < r)),
+            (l, r) if (l.is_ratio() || r.is_ratio()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.num_cmp(&r) == Some(Ordering::Less)))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if (l.is_bigint() || r.is_bigint()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.bigint_cmp(&r) == Some(Ordering::Less)))
+            }
             _ => None,
         }
     }
@@ -1264,12 +1648,25 @@ impl ValueObj {
             (Self::Int(l), Self::Int(r)) => Some(Self::from(l <= r)),
             (Self::Nat(l), Self::Nat(r)) => Some(Self::from(l <= r)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from(l <= r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l <= r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i32) <= r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from((l as i128) <= r as i128)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i128) <= r as i128)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l <= r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from((l as f64) <= r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l <= r as f64)),
             (Self::Int(l), Self::Float(r)) => Some(Self::from((l as f64) <= r)),
+            (l, r) if (l.is_ratio() || r.is_ratio()) && l.is_num() && r.is_num() => {
+                Some(Self::from(matches!(
+                    l.num_cmp(&r),
+                    Some(Ordering::Less | Ordering::Equal)
+                )))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if (l.is_bigint() || r.is_bigint()) && l.is_num() && r.is_num() => {
+                Some(Self::from(matches!(
+                    l.bigint_cmp(&r),
+                    Some(Ordering::Less | Ordering::Equal)
+                )))
+            }
             _ => None,
         }
     }
@@ -1279,8 +1676,8 @@ impl ValueObj {
             (Self::Int(l), Self::Int(r)) => Some(Self::from(l == r)),
             (Self::Nat(l), Self::Nat(r)) => Some(Self::from(l == r)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from(l == r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l == r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from(l as i32 == r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from((l as i128) == r as i128)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i128) == r as i128)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l == r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 == r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l == r as f64)),
@@ -1288,7 +1685,28 @@ impl ValueObj {
             (Self::Str(l), Self::Str(r)) => Some(Self::from(l == r)),
             (Self::Bool(l), Self::Bool(r)) => Some(Self::from(l == r)),
             (Self::Type(l), Self::Type(r)) => Some(Self::from(l == r)),
+            // REVIEW: the elements of sets and the keys of dicts are compared structurally (`{1} != {1.0}`)
+            (Self::Set(l), Self::Set(r)) => Some(Self::from(l == r)),
+            (Self::Dict(l), Self::Dict(r)) => Some(Self::from(l == r)),
+            (Self::Array(l), Self::Array(r)) | (Self::Tuple(l), Self::Tuple(r)) => {
+                if l.len() != r.len() {
+                    return Some(Self::from(false));
+                }
+                for (l, r) in l.iter().zip(r.iter()) {
+                    if l.clone().try_eq(r.clone())? != Self::Bool(true) {
+                        return Some(Self::from(false));
+                    }
+                }
+                Some(Self::from(true))
+            }
             // TODO:
+            (l, r) if (l.is_ratio() || r.is_ratio()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.num_cmp(&r) == Some(Ordering::Equal)))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if (l.is_bigint() || r.is_bigint()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.bigint_cmp(&r) == Some(Ordering::Equal)))
+            }
             _ => None,
         }
     }
@@ -1298,8 +1716,8 @@ impl ValueObj {
             (Self::Int(l), Self::Int(r)) => Some(Self::from(l != r)),
             (Self::Nat(l), Self::Nat(r)) => Some(Self::from(l != r)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from(l != r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l != r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from(l as i32 != r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from((l as i128) != r as i128)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::from((l as i128) != r as i128)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l != r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 != r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l != r as f64)),
@@ -1307,10 +1725,48 @@ impl ValueObj {
             (Self::Str(l), Self::Str(r)) => Some(Self::from(l != r)),
             (Self::Bool(l), Self::Bool(r)) => Some(Self::from(l != r)),
             (Self::Type(l), Self::Type(r)) => Some(Self::from(l != r)),
+            (l @ (Self::Array(_) | Self::Tuple(_) | Self::Set(_) | Self::Dict(_)), r) => {
+                match l.try_eq(r)? {
+                    Self::Bool(eq) => Some(Self::from(!eq)),
+                    _ => None,
+                }
+            }
+            (l, r) if (l.is_ratio() || r.is_ratio()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.num_cmp(&r) != Some(Ordering::Equal)))
+            }
+            #[cfg(feature = "bigint")]
+            (l, r) if (l.is_bigint() || r.is_bigint()) && l.is_num() && r.is_num() => {
+                Some(Self::from(l.bigint_cmp(&r) != Some(Ordering::Equal)))
+            }
             _ => None,
         }
     }
 
+    /// `self in container`. The elements are compared by `try_eq` (e.g. `1 in {1.0}` is `True`).
+    pub fn try_in(self, container: Self) -> Option<Self> {
+        let elems: Vec<Self> = match container {
+            Self::Set(set) => {
+                if set.contains(&self) {
+                    return Some(Self::Bool(true));
+                }
+                set.into_iter().collect()
+            }
+            Self::Array(elems) | Self::Tuple(elems) => elems.to_vec(),
+            Self::Dict(dict) => dict.keys().cloned().collect(),
+            Self::Str(s) => {
+                let Self::Str(sub) = self else {
+                    return None;
+                };
+                return Some(Self::Bool(s.contains(&sub[..])));
+            }
+            _ => return None,
+        };
+        let contains = elems
+            .into_iter()
+            .any(|elem| self.clone().try_eq(elem) == Some(Self::Bool(true)));
+        Some(Self::Bool(contains))
+    }
+
     pub fn try_or(self, other: Self) -> Option<Self> {
         match (self, other) {
             (Self::Bool(l), Self::Bool(r)) => Some(Self::from(l || r)),
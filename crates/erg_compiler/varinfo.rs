@@ -123,6 +123,51 @@ impl VarKind {
     }
 }
 
+/// A location-independent ID of a symbol: `module@qual_name#disambiguator`.
+/// Unlike `DefId` (hash-based) or `AbsLocation`, it is not changed by unrelated edits,
+/// so external indexes and caches can use it as a key across compilations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SymbolId {
+    pub module: Option<PathBuf>,
+    /// e.g. `<module>::C::f`
+    pub qual_name: Str,
+    /// The order of appearance among the symbols with the same `qual_name` in the module
+    pub disambiguator: usize,
+}
+
+impl fmt::Display for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(module) = &self.module {
+            write!(f, "{}@", module.display())?;
+        } else {
+            write!(f, "?@")?;
+        }
+        write!(f, "{}#{}", self.qual_name, self.disambiguator)
+    }
+}
+
+impl std::str::FromStr for SymbolId {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rest, disambiguator) = s.rsplit_once('#').ok_or(())?;
+        let disambiguator = disambiguator.parse().map_err(|_| ())?;
+        let (module, qual_name) = rest.rsplit_once('@').ok_or(())?;
+        let module = (module != "?").then(|| PathBuf::from(module));
+        Ok(Self::new(module, Str::rc(qual_name), disambiguator))
+    }
+}
+
+impl SymbolId {
+    pub const fn new(module: Option<PathBuf>, qual_name: Str, disambiguator: usize) -> Self {
+        Self {
+            module,
+            qual_name,
+            disambiguator,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AbsLocation {
     pub module: Option<PathBuf>,
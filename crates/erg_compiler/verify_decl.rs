@@ -0,0 +1,306 @@
+//! defines `DeclVerifier`.
+//!
+//! The declaration files of Python modules (`.d.er`) are written by hand (or generated by other tools),
+//! so they may drift from the actual Python environment (e.g. a function removed in a newer Python version).
+//! With `--verify-decls`, the declarations used in the compilation are cross-checked with the runtime objects.
+//! The runtime objects are inspected by a helper script (`importlib`, `inspect`), so the Python modules are actually imported.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use erg_common::config::ErgConfig;
+use erg_common::error::Location;
+use erg_common::io::Input;
+use erg_common::python_util::opt_which_python;
+use erg_common::traits::Stream;
+use erg_common::{log, trace_span};
+
+use crate::context::Context;
+use crate::error::{LowerWarning, LowerWarnings};
+use crate::module::SharedCompilerResource;
+use crate::ty::SubrType;
+use crate::varinfo::VarInfo;
+
+/// Reads `{module}\t{attr}` per line from stdin and prints per line:
+/// * `-`: the attribute does not exist
+/// * `?`: the attribute is not a function (or the signature is not available)
+/// * `{required}\t{positional}\t{named}\t{var}\t{kw_var}\t{signature}`
+const INSPECT: &str = "import importlib, inspect, sys
+sys.path.insert(0, sys.argv[1])
+for line in sys.stdin:
+    mod, attr = line.rstrip('\\n').split('\\t')
+    try:
+        obj = importlib.import_module(mod)
+        for name in attr.split('.'):
+            obj = getattr(obj, name)
+    except Exception:
+        print('-')
+        continue
+    try:
+        if not (inspect.isfunction(obj) or inspect.isbuiltin(obj)):
+            raise TypeError
+        sig = inspect.signature(obj)
+    except (TypeError, ValueError):
+        print('?')
+        continue
+    ps = list(sig.parameters.values())
+    pos = [p for p in ps if p.kind in (p.POSITIONAL_ONLY, p.POSITIONAL_OR_KEYWORD)]
+    required = sum(p.default is p.empty for p in pos)
+    named = sum(p.kind not in (p.VAR_POSITIONAL, p.VAR_KEYWORD) for p in ps)
+    var = any(p.kind == p.VAR_POSITIONAL for p in ps)
+    kw_var = any(p.kind == p.VAR_KEYWORD for p in ps)
+    print(required, len(pos), named, int(var), int(kw_var), sig, sep='\\t')
+";
+
+/// The parameters of a runtime function
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RuntimeSignature {
+    /// the number of the positional parameters without default values
+    required: usize,
+    /// the number of the positional parameters
+    positional: usize,
+    /// the number of the parameters except `*args` and `**kwargs`
+    named: usize,
+    var_params: bool,
+    kw_var_params: bool,
+    /// e.g. `(secs, /)`
+    display: String,
+}
+
+impl RuntimeSignature {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(6, '\t');
+        let mut next_num = || fields.next()?.parse::<usize>().ok();
+        let required = next_num()?;
+        let positional = next_num()?;
+        let named = next_num()?;
+        let var_params = next_num()? != 0;
+        let kw_var_params = next_num()? != 0;
+        let display = fields.next()?.to_string();
+        Some(Self {
+            required,
+            positional,
+            named,
+            var_params,
+            kw_var_params,
+            display,
+        })
+    }
+
+    /// Returns `false` if some calls allowed by the declaration fail at runtime.
+    fn accepts(&self, decl: &SubrType) -> bool {
+        let decl_required = decl.non_default_params.len();
+        let decl_named = decl_required + decl.default_params.len();
+        if decl_required < self.required {
+            return false;
+        }
+        if !self.var_params {
+            if decl_required > self.positional || decl.var_params.is_some() {
+                return false;
+            }
+            if !self.kw_var_params && decl_named > self.named {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug)]
+struct DeclQuery {
+    /// e.g. `os.path`
+    module: String,
+    /// the attribute path in Python (e.g. `sleep`, `Path.exists`)
+    attr: String,
+    /// the declaration file
+    decl: PathBuf,
+    loc: Location,
+    /// the declared type of the module-level function (the arity of the methods is not checked)
+    subr: Option<SubrType>,
+}
+
+/// Cross-checks the declarations of the Python modules with the Python environment.
+/// The following drifts are reported as warnings (with the locations in the declaration files):
+/// * the declared attribute (or method) does not exist at runtime
+/// * the declared parameters of a function are incompatible with the runtime signature (arity)
+#[derive(Debug)]
+pub struct DeclVerifier<'a> {
+    cfg: &'a ErgConfig,
+    shared: &'a SharedCompilerResource,
+}
+
+impl<'a> DeclVerifier<'a> {
+    pub fn new(cfg: &'a ErgConfig, shared: &'a SharedCompilerResource) -> Self {
+        Self { cfg, shared }
+    }
+
+    /// `foo.d/bar.d.er` -> `foo.bar`, `foo.d/__init__.d.er` -> `foo`
+    fn module_name(ctx: &Context) -> String {
+        let name = ctx.name.trim_end_matches(".__init__");
+        name.to_string()
+    }
+
+    fn py_name(ctx: &Context, name: &str, vi: &VarInfo) -> String {
+        vi.py_name
+            .as_ref()
+            .or_else(|| ctx.erg_to_py_names.get(name))
+            .map(|py_name| py_name.to_string())
+            .unwrap_or_else(|| name.trim_end_matches('!').to_string())
+    }
+
+    /// Whether `vi` is declared in `decl` (not builtin, not private)
+    fn is_declared_in(vi: &VarInfo, decl: &Path) -> bool {
+        vi.vis.is_public()
+            && !vi.kind.is_builtin()
+            && !vi.def_loc.loc.is_unknown()
+            && vi.def_loc.module.as_deref() == Some(decl)
+    }
+
+    fn collect_queries(&self) -> Vec<DeclQuery> {
+        let mut queries = vec![];
+        for (path, entry) in self.shared.py_mod_cache.ref_inner().iter() {
+            let decl: &Path = path;
+            let ctx = &entry.module.context;
+            let module = Self::module_name(ctx);
+            for (name, vi) in ctx.decls.iter().chain(ctx.locals.iter()) {
+                if !Self::is_declared_in(vi, decl) {
+                    continue;
+                }
+                queries.push(DeclQuery {
+                    module: module.clone(),
+                    attr: Self::py_name(ctx, name.inspect(), vi),
+                    decl: decl.to_path_buf(),
+                    loc: vi.def_loc.loc,
+                    subr: <&SubrType>::try_from(&vi.t).ok().cloned(),
+                });
+            }
+            for (name, (_, class)) in ctx.mono_types.iter() {
+                let Some((_, class_vi)) = ctx.get_var_info(name.inspect()) else {
+                    continue;
+                };
+                if !Self::is_declared_in(class_vi, decl) {
+                    continue;
+                }
+                let class_name = Self::py_name(ctx, name.inspect(), class_vi);
+                let member_ctxs = [class]
+                    .into_iter()
+                    .chain(class.methods_list.iter().map(|(_, methods)| methods));
+                let members = member_ctxs.flat_map(|member_ctx| {
+                    member_ctx
+                        .decls
+                        .iter()
+                        .chain(member_ctx.locals.iter())
+                        .map(move |(name, vi)| (member_ctx, name, vi))
+                });
+                for (member_ctx, name, vi) in members {
+                    // the instance attributes do not exist in the class object
+                    if !Self::is_declared_in(vi, decl) || !vi.t.is_subr() {
+                        continue;
+                    }
+                    queries.push(DeclQuery {
+                        module: module.clone(),
+                        attr: format!(
+                            "{class_name}.{}",
+                            Self::py_name(member_ctx, name.inspect(), vi)
+                        ),
+                        decl: decl.to_path_buf(),
+                        loc: vi.def_loc.loc,
+                        subr: None,
+                    });
+                }
+            }
+        }
+        queries.sort_by_key(|query| {
+            let loc = query.loc;
+            (query.decl.clone(), loc.ln_begin(), loc.col_begin())
+        });
+        queries.dedup_by(|l, r| l.decl == r.decl && l.attr == r.attr);
+        queries
+    }
+
+    /// Runs the helper script and returns the results (one line per query).
+    fn inspect(&self, queries: &[DeclQuery]) -> Result<Vec<String>, String> {
+        let py_command = match self.cfg.py_command {
+            Some(py_command) => py_command.to_string(),
+            None => opt_which_python()?,
+        };
+        let dir = self.cfg.input.dir();
+        let mut child = Command::new(py_command)
+            .arg("-c")
+            .arg(INSPECT)
+            .arg(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("failed to execute Python: {err}"))?;
+        let mut stdin = child.stdin.take().unwrap();
+        let input = queries
+            .iter()
+            .map(|query| format!("{}\t{}\n", query.module, query.attr))
+            .collect::<String>();
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|err| err.to_string())?;
+        drop(stdin);
+        let out = child.wait_with_output().map_err(|err| err.to_string())?;
+        let lines = String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if lines.len() != queries.len() {
+            return Err(format!(
+                "expected {} results, but found {}",
+                queries.len(),
+                lines.len()
+            ));
+        }
+        Ok(lines)
+    }
+
+    pub fn verify(&self) -> LowerWarnings {
+        let _span = trace_span!("verify_decls", self.cfg.input.unescaped_filename());
+        let mut warns = LowerWarnings::empty();
+        let queries = self.collect_queries();
+        if queries.is_empty() {
+            return warns;
+        }
+        let results = match self.inspect(&queries) {
+            Ok(results) => results,
+            Err(err) => {
+                log!(err "failed to inspect the Python environment: {err}");
+                return warns;
+            }
+        };
+        for (query, result) in queries.iter().zip(results) {
+            let input = Input::file(query.decl.clone());
+            if result == "-" {
+                warns.push(LowerWarning::decl_not_found_warning(
+                    input,
+                    line!() as usize,
+                    query.loc,
+                    query.module.clone(),
+                    &query.module,
+                    &query.attr,
+                ));
+                continue;
+            }
+            let (Some(sig), Some(subr)) = (RuntimeSignature::parse(&result), query.subr.as_ref())
+            else {
+                continue;
+            };
+            if !sig.accepts(subr) {
+                warns.push(LowerWarning::decl_signature_warning(
+                    input,
+                    line!() as usize,
+                    query.loc,
+                    query.module.clone(),
+                    &format!("{}.{}", query.module, query.attr),
+                    &sig.display,
+                ));
+            }
+        }
+        log!(info "verified {} declarations", queries.len());
+        warns
+    }
+}
@@ -0,0 +1,176 @@
+//! defines `Watcher` (`erg check --watch`).
+//!
+//! The watcher checks the entry file, then waits for the entry file or one of the modules it imports (or the files included with `include_str`/`include_bytes`) to be modified and checks it again.
+//! Only the modified modules and the modules that depend on them are removed from the `ModuleCache`,
+//! so the unchanged modules are not checked again.
+use std::fs::metadata;
+use std::io;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::error::MultiErrorDisplay;
+use erg_common::pathutil::NormalizedPathBuf;
+use erg_common::set;
+use erg_common::traits::{ExitStatus, Stream};
+use erg_common::Str;
+
+use crate::artifact::{CompleteArtifact, IncompleteArtifact};
+use crate::build_hir::HIRBuilder;
+use crate::module::SharedCompilerResource;
+
+/// interval of polling the modification times of the watched files
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[derive(Debug)]
+pub struct Watcher {
+    cfg: ErgConfig,
+    shared: SharedCompilerResource,
+    /// K: watched file, V: its modification time at the last check
+    mtimes: Dict<NormalizedPathBuf, SystemTime>,
+}
+
+impl Watcher {
+    pub fn new(cfg: ErgConfig) -> Self {
+        let shared = SharedCompilerResource::new(cfg.copy());
+        Self {
+            cfg,
+            shared,
+            mtimes: Dict::new(),
+        }
+    }
+
+    pub fn shared(&self) -> &SharedCompilerResource {
+        &self.shared
+    }
+
+    /// the entry file, the modules imported by it (directly or indirectly) and the files included by them
+    pub fn watched_files(&self) -> impl Iterator<Item = &NormalizedPathBuf> {
+        self.mtimes.keys()
+    }
+
+    /// Checks the entry file, reusing the cached modules, and updates the watched files.
+    /// Fails if the entry file cannot be read.
+    pub fn check(&mut self) -> io::Result<Result<CompleteArtifact, IncompleteArtifact>> {
+        let mut cfg = self.cfg.copy();
+        let res = cfg.input.try_read().map(|src| {
+            if let Some(path) = cfg.input.path() {
+                self.shared.clear(path);
+            }
+            let mut builder =
+                HIRBuilder::new_with_cache(cfg, Str::ever("<module>"), self.shared.clone());
+            builder.build(src, "exec")
+        });
+        self.update_watched_files();
+        res
+    }
+
+    fn update_watched_files(&mut self) {
+        let mut files = self
+            .shared
+            .mod_cache
+            .ref_inner()
+            .keys()
+            .filter(|path| path.is_file())
+            .cloned()
+            .collect::<Vec<_>>();
+        // the files included by `include_str`/`include_bytes`
+        for node in self.shared.graph.ref_inner().iter() {
+            if node.id.is_file() && !files.contains(&node.id) {
+                files.push(node.id.clone());
+            }
+        }
+        if let Some(path) = self.cfg.input.path() {
+            files.push(NormalizedPathBuf::from(path));
+        }
+        let mut mtimes = Dict::new();
+        for path in files {
+            // a file being saved keeps its last modification time
+            if let Some(mtime) = modified_time(&path).or_else(|| self.mtimes.get(&path).copied()) {
+                mtimes.insert(path, mtime);
+            }
+        }
+        self.mtimes = mtimes;
+    }
+
+    /// Returns the watched files modified since the last check.
+    /// The files that cannot be accessed (e.g. being saved) are regarded as not modified yet.
+    pub fn modified_files(&self) -> Vec<NormalizedPathBuf> {
+        self.mtimes
+            .iter()
+            .filter(|(path, mtime)| modified_time(path).is_some_and(|now| now != **mtime))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Removes the modified modules and the modules that depend on them from the cache.
+    pub fn invalidate(&self, modified: &[NormalizedPathBuf]) {
+        let mut invalidated = set! {};
+        let mut stack = modified.to_vec();
+        while let Some(path) = stack.pop() {
+            if !invalidated.contains(&path) {
+                stack.extend(self.shared.graph.children(&path));
+                invalidated.insert(path);
+            }
+        }
+        for path in invalidated.iter() {
+            self.shared.clear(path);
+        }
+    }
+
+    fn wait_for_changes(&self) -> Vec<NormalizedPathBuf> {
+        loop {
+            let modified = self.modified_files();
+            if !modified.is_empty() {
+                return modified;
+            }
+            sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Returns `false` if the entry file cannot be read.
+    fn report(&mut self) -> bool {
+        match self.check() {
+            Ok(Ok(artifact)) => {
+                artifact.warns.write_all_stderr();
+                println!("check passed ({} warnings)", artifact.warns.len());
+                true
+            }
+            Ok(Err(artifact)) => {
+                artifact.warns.write_all_stderr();
+                artifact.errors.write_all_stderr();
+                println!("check failed ({} errors)", artifact.errors.len());
+                true
+            }
+            Err(err) => {
+                eprintln!("cannot read '{}': {err}", self.cfg.input.filename());
+                false
+            }
+        }
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let mut watcher = Self::new(cfg);
+        if !watcher.report() {
+            return ExitStatus::ERR1;
+        }
+        loop {
+            println!(
+                "watching {} files for changes... (Ctrl+C to quit)",
+                watcher.mtimes.len()
+            );
+            let modified = watcher.wait_for_changes();
+            for path in modified.iter() {
+                println!("{} changed, re-checking", path.display());
+            }
+            watcher.invalidate(&modified);
+            watcher.report();
+        }
+    }
+}
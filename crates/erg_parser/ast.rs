@@ -16,6 +16,7 @@ use erg_common::{
 };
 use erg_common::{fmt_vec_split_with, Str};
 
+use crate::feature::Features;
 use crate::token::{Token, TokenKind, EQUAL};
 
 /// Some Erg functions require additional operation by the compiler.
@@ -2455,12 +2456,19 @@ impl fmt::Display for SubrTypeSpec {
         if !self.bounds.is_empty() {
             write!(f, "|{}|", self.bounds)?;
         }
+        let mut params = self
+            .non_defaults
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        if let Some(var_params) = &self.var_params {
+            params.push(format!("*{var_params}"));
+        }
+        params.extend(self.defaults.iter().map(|p| p.to_string()));
         write!(
             f,
-            "({}, {}, {}) {} {}",
-            fmt_vec(&self.non_defaults),
-            fmt_option!(pre "*", &self.var_params),
-            fmt_vec(&self.defaults),
+            "({}) {} {}",
+            params.join(", "),
             self.arrow.content,
             self.return_t
         )
@@ -2770,6 +2778,11 @@ impl TypeSpec {
     pub fn poly(acc: ConstAccessor, args: ConstArgs) -> Self {
         Self::PreDeclTy(PreDeclTypeSpec::Poly(PolyTypeSpec::new(acc, args)))
     }
+
+    /// `_` (type hole)
+    pub fn is_hole(&self) -> bool {
+        matches!(self, Self::PreDeclTy(PreDeclTypeSpec::Mono(ident)) if &ident.inspect()[..] == "_")
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -3672,6 +3685,46 @@ impl ParamRecordPattern {
     }
 }
 
+/// `"prefix" + rest` or `rest + "suffix"`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParamStrPattern {
+    pub(crate) affix: Literal,
+    pub(crate) rest: Box<ParamPattern>,
+    pub(crate) is_prefix: bool,
+}
+
+impl NestedDisplay for ParamStrPattern {
+    fn fmt_nest(&self, f: &mut fmt::Formatter<'_>, _level: usize) -> fmt::Result {
+        if self.is_prefix {
+            write!(f, "{} + {}", self.affix, self.rest)
+        } else {
+            write!(f, "{} + {}", self.rest, self.affix)
+        }
+    }
+}
+
+impl_display_from_nested!(ParamStrPattern);
+
+impl Locational for ParamStrPattern {
+    fn loc(&self) -> Location {
+        if self.is_prefix {
+            Location::concat(&self.affix, self.rest.as_ref())
+        } else {
+            Location::concat(self.rest.as_ref(), &self.affix)
+        }
+    }
+}
+
+impl ParamStrPattern {
+    pub fn new(affix: Literal, rest: ParamPattern, is_prefix: bool) -> Self {
+        Self {
+            affix,
+            rest: Box::new(rest),
+            is_prefix,
+        }
+    }
+}
+
 /// 関数定義や無名関数で使えるパターン
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ParamPattern {
@@ -3682,6 +3735,7 @@ pub enum ParamPattern {
     Array(ParamArrayPattern),
     Tuple(ParamTuplePattern),
     Record(ParamRecordPattern),
+    Str(ParamStrPattern),
     // DataPack(ParamDataPackPattern),
     Ref(VarName),
     RefMut(VarName),
@@ -3696,6 +3750,7 @@ impl NestedDisplay for ParamPattern {
             Self::Array(array) => write!(f, "{array}"),
             Self::Tuple(tuple) => write!(f, "{tuple}"),
             Self::Record(record) => write!(f, "{record}"),
+            Self::Str(str_pat) => write!(f, "{str_pat}"),
             Self::Ref(var_name) => write!(f, "ref {var_name}"),
             Self::RefMut(var_name) => write!(f, "ref! {var_name}"),
         }
@@ -3703,7 +3758,7 @@ impl NestedDisplay for ParamPattern {
 }
 
 impl_display_from_nested!(ParamPattern);
-impl_locational_for_enum!(ParamPattern; Discard, VarName, Lit, Array, Tuple, Record, Ref, RefMut);
+impl_locational_for_enum!(ParamPattern; Discard, VarName, Lit, Array, Tuple, Record, Str, Ref, RefMut);
 
 impl ParamPattern {
     pub const fn inspect(&self) -> Option<&Str> {
@@ -3980,6 +4035,8 @@ pub struct LambdaSignature {
     pub bounds: TypeBoundSpecs,
     pub params: Params,
     pub return_t_spec: Option<TypeSpecWithOp>,
+    /// `(i: Int) if i > 0 -> ...` (only allowed in match arms)
+    pub guard: Option<Box<Expr>>,
 }
 
 impl fmt::Display for LambdaSignature {
@@ -3987,17 +4044,19 @@ impl fmt::Display for LambdaSignature {
         if self.bounds.is_empty() {
             write!(
                 f,
-                "{}{}",
+                "{}{}{}",
                 self.params,
-                fmt_option!(pre ": ", self.return_t_spec)
+                fmt_option!(pre ": ", self.return_t_spec),
+                fmt_option!(pre " if ", self.guard)
             )
         } else {
             write!(
                 f,
-                "|{}|{}{}",
+                "|{}|{}{}{}",
                 self.bounds,
                 self.params,
-                fmt_option!(pre ": ", self.return_t_spec)
+                fmt_option!(pre ": ", self.return_t_spec),
+                fmt_option!(pre " if ", self.guard)
             )
         }
     }
@@ -4027,6 +4086,7 @@ impl LambdaSignature {
             params,
             return_t_spec,
             bounds,
+            guard: None,
         }
     }
 
@@ -4712,13 +4772,19 @@ impl Module {
 pub struct AST {
     pub name: Str,
     pub module: Module,
+    /// features enabled by `#!feature(...)`
+    pub features: Features,
 }
 
 impl_display_for_single_struct!(AST, module);
 
 impl AST {
-    pub const fn new(name: Str, module: Module) -> Self {
-        Self { name, module }
+    pub const fn new(name: Str, module: Module, features: Features) -> Self {
+        Self {
+            name,
+            module,
+            features,
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -1,6 +1,6 @@
 use erg_common::config::ErgConfig;
 use erg_common::traits::{ExitStatus, Runnable};
-use erg_common::Str;
+use erg_common::{trace_span, Str};
 
 use crate::ast::AST;
 use crate::desugar::Desugarer;
@@ -65,15 +65,16 @@ impl ASTBuilder {
         IncompleteArtifact<AST, ParserRunnerErrors>,
     > {
         let name = Str::rc(self.runner.cfg().input.unescaped_filename());
+        let _span = trace_span!("build_ast", name);
         let mut desugarer = Desugarer::new();
         let artifact = self.runner.parse(src).map_err(|iart| {
             iart.map_mod(|module| {
                 let module = desugarer.desugar(module);
-                AST::new(name.clone(), module)
+                AST::new(name.clone(), module, self.runner.features())
             })
         })?;
         let module = desugarer.desugar(artifact.ast);
-        let ast = AST::new(name, module);
+        let ast = AST::new(name, module, self.runner.features());
         Ok(CompleteArtifact::new(
             ast,
             ParserRunnerErrors::convert(self.input(), artifact.warns),
@@ -88,11 +89,10 @@ impl ASTBuilder {
         IncompleteArtifact<AST, ParserRunnerErrors>,
     > {
         let name = Str::rc(self.runner.cfg().input.unescaped_filename());
-        let artifact = self
-            .runner
-            .parse(src)
-            .map_err(|iart| iart.map_mod(|module| AST::new(name.clone(), module)))?;
-        let ast = AST::new(name, artifact.ast);
+        let artifact = self.runner.parse(src).map_err(|iart| {
+            iart.map_mod(|module| AST::new(name.clone(), module, self.runner.features()))
+        })?;
+        let ast = AST::new(name, artifact.ast, self.runner.features());
         Ok(CompleteArtifact::new(
             ast,
             ParserRunnerErrors::convert(self.input(), artifact.warns),
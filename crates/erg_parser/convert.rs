@@ -5,7 +5,7 @@ use crate::ast::*;
 use crate::debug_call_info;
 use crate::debug_exit_info;
 use crate::error::{ParseError, ParseResult};
-use crate::token::{Token, TokenKind};
+use crate::token::{Token, TokenKind, COLON};
 use crate::Parser;
 
 impl Parser {
@@ -447,6 +447,26 @@ impl Parser {
                 debug_exit_info!(self);
                 Ok(param)
             }
+            // a single-field record pattern (`{x}`) is parsed as a set
+            Expr::Set(set) => {
+                let record_pat = self
+                    .convert_set_to_param_record_pat(set)
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                let pat = ParamPattern::Record(record_pat);
+                let param = NonDefaultParamSignature::new(pat, None);
+                debug_exit_info!(self);
+                Ok(param)
+            }
+            // a single-field record pattern with a type specification (`{x: Int}`) is parsed as a dict
+            Expr::Dict(dict) => {
+                let record_pat = self
+                    .convert_dict_to_param_record_pat(dict)
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                let pat = ParamPattern::Record(record_pat);
+                let param = NonDefaultParamSignature::new(pat, None);
+                debug_exit_info!(self);
+                Ok(param)
+            }
             Expr::Tuple(tuple) => {
                 let tuple_pat = self
                     .convert_tuple_to_param_tuple_pat(tuple)
@@ -549,13 +569,23 @@ impl Parser {
         };
         assert_eq!(attr.body.block.len(), 1);
         let first = attr.body.block.remove(0);
-        let Expr::Accessor(rhs) = first else {
-            let err = ParseError::simple_syntax_error(line!() as usize, first.loc());
-            self.errs.push(err);
-            debug_exit_info!(self);
-            return Err(());
+        let rhs = match first {
+            Expr::Accessor(rhs) => self.convert_accessor_to_param_sig(rhs)?,
+            // {x = a: Int} or {x: Int}
+            Expr::TypeAscription(tasc) => self.convert_type_asc_to_param_pattern(tasc, false)?,
+            // {x = {y}}, {x = (a, b)}, {x = [a, b]}
+            nested @ (Expr::Record(_)
+            | Expr::Set(_)
+            | Expr::Dict(_)
+            | Expr::Tuple(_)
+            | Expr::Array(_)) => self.convert_rhs_to_param(nested, false)?,
+            other => {
+                let err = ParseError::simple_syntax_error(line!() as usize, other.loc());
+                self.errs.push(err);
+                debug_exit_info!(self);
+                return Err(());
+            }
         };
-        let rhs = self.convert_accessor_to_param_sig(rhs)?;
         Ok(ParamRecordAttr::new(lhs, rhs))
     }
 
@@ -601,6 +631,72 @@ impl Parser {
         }
     }
 
+    fn convert_set_to_param_record_pat(&mut self, set: Set) -> ParseResult<ParamRecordPattern> {
+        debug_call_info!(self);
+        let Set::Normal(set) = set else {
+            let err = ParseError::simple_syntax_error(line!() as usize, set.loc());
+            self.errs.push(err);
+            debug_exit_info!(self);
+            return Err(());
+        };
+        let mut pats = vec![];
+        for elem in set.elems.into_iters().0 {
+            let attr = match elem.expr {
+                Expr::Accessor(Accessor::Ident(ident)) => {
+                    let rhs = NonDefaultParamSignature::new(
+                        ParamPattern::VarName(ident.name.clone()),
+                        None,
+                    );
+                    ParamRecordAttr::new(ident, rhs)
+                }
+                other => {
+                    let err = ParseError::simple_syntax_error(line!() as usize, other.loc());
+                    self.errs.push(err);
+                    debug_exit_info!(self);
+                    return Err(());
+                }
+            };
+            pats.push(attr);
+        }
+        let attrs = ParamRecordAttrs::new(pats);
+        debug_exit_info!(self);
+        Ok(ParamRecordPattern::new(set.l_brace, attrs, set.r_brace))
+    }
+
+    fn convert_dict_to_param_record_pat(&mut self, dict: Dict) -> ParseResult<ParamRecordPattern> {
+        debug_call_info!(self);
+        let Dict::Normal(dict) = dict else {
+            let err = ParseError::simple_syntax_error(line!() as usize, dict.loc());
+            self.errs.push(err);
+            debug_exit_info!(self);
+            return Err(());
+        };
+        let mut pats = vec![];
+        for KeyValue { key, value } in dict.kvs.into_iter() {
+            let Expr::Accessor(Accessor::Ident(ident)) = key else {
+                let err = ParseError::simple_syntax_error(line!() as usize, key.loc());
+                self.errs.push(err);
+                debug_exit_info!(self);
+                return Err(());
+            };
+            let t_spec = match Parser::expr_to_type_spec(value.clone()) {
+                Ok(t_spec) => t_spec,
+                Err(err) => {
+                    self.errs.push(err);
+                    debug_exit_info!(self);
+                    return Err(());
+                }
+            };
+            let t_spec = TypeSpecWithOp::new(COLON, t_spec, value);
+            let pat = ParamPattern::VarName(ident.name.clone());
+            let rhs = NonDefaultParamSignature::new(pat, Some(t_spec));
+            pats.push(ParamRecordAttr::new(ident, rhs));
+        }
+        let attrs = ParamRecordAttrs::new(pats);
+        debug_exit_info!(self);
+        Ok(ParamRecordPattern::new(dict.l_brace, attrs, dict.r_brace))
+    }
+
     fn convert_tuple_to_param_tuple_pat(&mut self, tuple: Tuple) -> ParseResult<ParamTuplePattern> {
         debug_call_info!(self);
         match tuple {
@@ -704,6 +800,16 @@ impl Parser {
                     let params = Params::single(param);
                     Ok(LambdaSignature::new(params, None, TypeBoundSpecs::empty()))
                 }
+                // `"prefix" + rest`, `rest + "suffix"`
+                TokenKind::Plus => {
+                    let str_pat = self
+                        .convert_bin_to_param_str_pat(bin)
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    let param = NonDefaultParamSignature::new(ParamPattern::Str(str_pat), None);
+                    let params = Params::single(param);
+                    debug_exit_info!(self);
+                    Ok(LambdaSignature::new(params, None, TypeBoundSpecs::empty()))
+                }
                 _ => {
                     let err = ParseError::simple_syntax_error(line!() as usize, bin.loc());
                     self.errs.push(err);
@@ -720,6 +826,37 @@ impl Parser {
         }
     }
 
+    fn convert_bin_to_param_str_pat(&mut self, bin: BinOp) -> ParseResult<ParamStrPattern> {
+        debug_call_info!(self);
+        let loc = bin.loc();
+        let (_op, lhs, rhs) = bin.deconstruct();
+        let (affix, rest, is_prefix) = match (lhs, rhs) {
+            (Expr::Literal(lit), Expr::Accessor(Accessor::Ident(ident)))
+                if lit.token.is(TokenKind::StrLit) =>
+            {
+                (lit, ident, true)
+            }
+            (Expr::Accessor(Accessor::Ident(ident)), Expr::Literal(lit))
+                if lit.token.is(TokenKind::StrLit) =>
+            {
+                (lit, ident, false)
+            }
+            _ => {
+                let err = ParseError::simple_syntax_error(line!() as usize, loc);
+                self.errs.push(err);
+                debug_exit_info!(self);
+                return Err(());
+            }
+        };
+        let rest = if &rest.name.inspect()[..] == "_" {
+            ParamPattern::Discard(rest.name.into_token())
+        } else {
+            ParamPattern::VarName(rest.name)
+        };
+        debug_exit_info!(self);
+        Ok(ParamStrPattern::new(affix, rest, is_prefix))
+    }
+
     fn convert_accessor_to_param_sig(
         &mut self,
         accessor: Accessor,
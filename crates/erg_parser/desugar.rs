@@ -7,21 +7,30 @@
 use erg_common::fresh::FreshNameGenerator;
 use erg_common::traits::{Locational, Stream};
 use erg_common::Str;
-use erg_common::{enum_unwrap, get_hash, log, set};
+use erg_common::{enum_unwrap, get_hash, log, set, trace_span};
 
 use crate::ast::{
     Accessor, Args, Array, ArrayComprehension, ArrayTypeSpec, ArrayWithLength, BinOp, Block, Call,
     ClassAttr, ClassAttrs, ClassDef, ConstExpr, DataPack, Def, DefBody, DefId,
     DefaultParamSignature, Dict, Dummy, Expr, Identifier, KeyValue, KwArg, Lambda, LambdaSignature,
     Literal, Methods, MixedRecord, Module, NonDefaultParamSignature, NormalArray, NormalDict,
-    NormalRecord, NormalSet, NormalTuple, ParamPattern, ParamRecordAttr, ParamTuplePattern, Params,
-    PatchDef, PosArg, ReDef, Record, RecordAttrOrIdent, RecordAttrs, Set as astSet,
-    SetComprehension, SetWithLength, Signature, SubrSignature, Tuple, TupleTypeSpec, TypeAppArgs,
-    TypeAppArgsKind, TypeBoundSpecs, TypeSpec, TypeSpecWithOp, UnaryOp, VarName, VarPattern,
-    VarRecordAttr, VarSignature, VisModifierSpec,
+    NormalRecord, NormalSet, NormalTuple, ParamArrayPattern, ParamPattern, ParamRecordAttr,
+    ParamTuplePattern, Params, PatchDef, PosArg, ReDef, Record, RecordAttrOrIdent, RecordAttrs,
+    Set as astSet, SetComprehension, SetWithLength, Signature, SubrSignature, Tuple, TupleTypeSpec,
+    TypeAppArgs, TypeAppArgsKind, TypeBoundSpecs, TypeSpec, TypeSpecWithOp, UnaryOp, VarName,
+    VarPattern, VarRecordAttr, VarSignature, VisModifierSpec,
 };
 use crate::token::{Token, TokenKind, COLON, DOT};
 
+/// An occurrence of a name in an expression (see `Desugarer::walk_idents`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameOccurrence<'a> {
+    /// a reference to a variable
+    Ref(&'a Identifier),
+    /// a private variable, a private subroutine or a parameter being defined
+    Bind(&'a VarName),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum BufIndex<'i> {
     Array(usize),
@@ -44,10 +53,14 @@ impl Desugarer {
     }
 
     pub fn desugar(&mut self, module: Module) -> Module {
+        let _span = trace_span!("desugar");
         log!(info "the desugaring process has started.");
+        let module = self.desugar_operator_section(module);
         let module = self.desugar_multiple_pattern_def(module);
         let module = self.desugar_pattern_in_module(module);
         let module = Self::desugar_shortened_record(module);
+        let module = self.desugar_nil_safe(module);
+        let module = Self::desugar_concat_op(module);
         let module = Self::desugar_acc(module);
         log!(info "AST (desugared):\n{module}");
         log!(info "the desugaring process has completed.");
@@ -56,10 +69,295 @@ impl Desugarer {
 
     pub fn desugar_simple_expr(expr: Expr) -> Expr {
         let expr = Self::rec_desugar_shortened_record(expr);
-        let expr = Self::rec_desugar_lambda_pattern(&mut Desugarer::new(), expr);
+        let mut desugarer = Desugarer::new();
+        let expr = desugarer.rec_desugar_operator_section(expr);
+        let expr = desugarer.rec_desugar_lambda_pattern(expr);
+        let expr = desugarer.rec_desugar_nil_safe(expr);
+        let expr = Self::rec_desugar_concat_op(expr);
         Self::rec_desugar_acc(expr)
     }
 
+    /// Replaces the identifiers in `expr` (not the attribute names and the names being defined) with the expressions returned by `subst`.
+    /// Used to fill the quoted expressions of macros.
+    pub fn substitute_idents(expr: Expr, subst: &impl Fn(&Identifier) -> Option<Expr>) -> Expr {
+        match expr {
+            Expr::Accessor(Accessor::Ident(ident)) => {
+                subst(&ident).unwrap_or(Expr::Accessor(Accessor::Ident(ident)))
+            }
+            expr => Self::perform_desugar(|expr| Self::substitute_idents(expr, subst), expr),
+        }
+    }
+
+    /// Calls `f` with the names in `expr` without changing it.
+    /// The attribute names (including the fields of records) are not visited,
+    /// and only the names of the simple patterns (e.g. `x` of `x = 1`, not `(x, y) = (1, 2)`) are visited as `Bind`.
+    pub fn walk_idents<'a>(expr: &'a Expr, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        match expr {
+            Expr::Literal(_) => {}
+            Expr::Accessor(acc) => Self::walk_idents_acc(acc, f),
+            Expr::Array(Array::Normal(arr)) => Self::walk_idents_args(&arr.elems, f),
+            Expr::Array(Array::WithLength(arr)) => {
+                Self::walk_idents(&arr.elem.expr, f);
+                Self::walk_idents(&arr.len, f);
+            }
+            Expr::Array(Array::Comprehension(arr)) => {
+                Self::walk_idents(&arr.elem, f);
+                for (_, gen) in arr.generators.iter() {
+                    Self::walk_idents(gen, f);
+                }
+                for guard in arr.guards.iter() {
+                    Self::walk_idents(guard, f);
+                }
+            }
+            Expr::Tuple(Tuple::Normal(tup)) => Self::walk_idents_args(&tup.elems, f),
+            Expr::Set(astSet::Normal(set)) => Self::walk_idents_args(&set.elems, f),
+            Expr::Set(astSet::WithLength(set)) => {
+                Self::walk_idents(&set.elem.expr, f);
+                Self::walk_idents(&set.len, f);
+            }
+            Expr::Set(astSet::Comprehension(set)) => {
+                Self::walk_idents(&set.iter, f);
+                Self::walk_idents(&set.pred, f);
+            }
+            Expr::Dict(Dict::Normal(dict)) => {
+                for kv in dict.kvs.iter() {
+                    Self::walk_idents(&kv.key, f);
+                    Self::walk_idents(&kv.value, f);
+                }
+            }
+            Expr::Dict(_) => {}
+            Expr::Record(record) => Self::walk_idents_record(record, f),
+            Expr::BinOp(bin) => {
+                for arg in bin.args.iter() {
+                    Self::walk_idents(arg, f);
+                }
+            }
+            Expr::UnaryOp(unary) => Self::walk_idents(&unary.args[0], f),
+            Expr::Call(call) => {
+                Self::walk_idents(&call.obj, f);
+                Self::walk_idents_args(&call.args, f);
+            }
+            Expr::DataPack(pack) => {
+                Self::walk_idents(&pack.class, f);
+                Self::walk_idents_record(&pack.args, f);
+            }
+            Expr::Lambda(lambda) => {
+                Self::walk_idents_params(&lambda.sig.params, f);
+                if let Some(guard) = &lambda.sig.guard {
+                    Self::walk_idents(guard, f);
+                }
+                Self::walk_idents_block(&lambda.body, f);
+            }
+            Expr::TypeAscription(tasc) => Self::walk_idents(&tasc.expr, f),
+            Expr::Def(def) => Self::walk_idents_def(def, f),
+            Expr::Methods(methods) => Self::walk_idents_methods(methods, f),
+            Expr::ClassDef(class_def) => {
+                Self::walk_idents_def(&class_def.def, f);
+                for methods in class_def.methods_list.iter() {
+                    Self::walk_idents_methods(methods, f);
+                }
+            }
+            Expr::PatchDef(patch_def) => {
+                Self::walk_idents_def(&patch_def.def, f);
+                for methods in patch_def.methods_list.iter() {
+                    Self::walk_idents_methods(methods, f);
+                }
+            }
+            Expr::ReDef(redef) => {
+                Self::walk_idents_acc(&redef.attr, f);
+                Self::walk_idents(&redef.expr, f);
+            }
+            Expr::Dummy(dummy) => Self::walk_idents_block(&dummy.exprs, f),
+        }
+    }
+
+    fn walk_idents_acc<'a>(acc: &'a Accessor, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        match acc {
+            Accessor::Ident(ident) => f(NameOccurrence::Ref(ident)),
+            Accessor::Attr(attr) => Self::walk_idents(&attr.obj, f),
+            Accessor::TupleAttr(tup) => Self::walk_idents(&tup.obj, f),
+            Accessor::Subscr(sub) => {
+                Self::walk_idents(&sub.obj, f);
+                Self::walk_idents(&sub.index, f);
+            }
+            Accessor::TypeApp(tapp) => {
+                Self::walk_idents(&tapp.obj, f);
+                if let TypeAppArgsKind::Args(args) = &tapp.type_args.args {
+                    Self::walk_idents_args(args, f);
+                }
+            }
+        }
+    }
+
+    // the fields are not visited
+    fn walk_idents_record<'a>(record: &'a Record, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        match record {
+            Record::Normal(rec) => {
+                for attr in rec.attrs.iter() {
+                    Self::walk_idents_block(&attr.body.block, f);
+                }
+            }
+            Record::Mixed(rec) => {
+                for attr in rec.attrs.iter() {
+                    match attr {
+                        RecordAttrOrIdent::Attr(attr) => {
+                            Self::walk_idents_block(&attr.body.block, f)
+                        }
+                        RecordAttrOrIdent::Ident(ident) => f(NameOccurrence::Ref(ident)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn walk_idents_args<'a>(args: &'a Args, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        for arg in args.pos_args() {
+            Self::walk_idents(&arg.expr, f);
+        }
+        if let Some(var_args) = &args.var_args {
+            Self::walk_idents(&var_args.expr, f);
+        }
+        for arg in args.kw_args() {
+            Self::walk_idents(&arg.expr, f);
+        }
+    }
+
+    fn walk_idents_block<'a>(block: &'a Block, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        for chunk in block.iter() {
+            Self::walk_idents(chunk, f);
+        }
+    }
+
+    fn walk_idents_params<'a>(params: &'a Params, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        let non_defaults = params
+            .non_defaults
+            .iter()
+            .chain(params.var_params.as_deref());
+        for sig in non_defaults.chain(params.defaults.iter().map(|param| &param.sig)) {
+            if let ParamPattern::VarName(name) = &sig.pat {
+                f(NameOccurrence::Bind(name));
+            }
+        }
+        for param in params.defaults.iter() {
+            Self::walk_idents(&param.default_val, f);
+        }
+    }
+
+    fn walk_idents_def<'a>(def: &'a Def, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        match &def.sig {
+            Signature::Var(var) => {
+                if let VarPattern::Ident(ident) = &var.pat {
+                    if ident.vis.is_private() {
+                        f(NameOccurrence::Bind(&ident.name));
+                    }
+                }
+            }
+            Signature::Subr(subr) => {
+                if subr.ident.vis.is_private() {
+                    f(NameOccurrence::Bind(&subr.ident.name));
+                }
+                Self::walk_idents_params(&subr.params, f);
+            }
+        }
+        Self::walk_idents_block(&def.body.block, f);
+    }
+
+    fn walk_idents_methods<'a>(methods: &'a Methods, f: &mut impl FnMut(NameOccurrence<'a>)) {
+        for attr in methods.attrs.iter() {
+            match attr {
+                ClassAttr::Def(def) => Self::walk_idents_block(&def.body.block, f),
+                ClassAttr::Decl(decl) => Self::walk_idents(&decl.expr, f),
+                ClassAttr::Doc(_) => {}
+            }
+        }
+    }
+
+    /// Renames the names in `expr` to the names returned by `rename`.
+    /// The names visited by `walk_idents` (including the names being defined) are renamed.
+    pub fn rename_idents(expr: Expr, rename: &impl Fn(&VarName) -> Option<Str>) -> Expr {
+        match expr {
+            Expr::Accessor(Accessor::Ident(mut ident)) => {
+                ident.name = Self::rename_var_name(ident.name, rename);
+                Expr::Accessor(Accessor::Ident(ident))
+            }
+            // the fields are not renamed
+            Expr::Record(record) => {
+                let rec = match record {
+                    Record::Normal(rec) => rec,
+                    Record::Mixed(mixed) => Self::desugar_shortened_record_inner(mixed),
+                };
+                let attrs = rec
+                    .attrs
+                    .into_iter()
+                    .map(|attr| {
+                        let chunks = attr
+                            .body
+                            .block
+                            .into_iter()
+                            .map(|chunk| Self::rename_idents(chunk, rename))
+                            .collect();
+                        let body = DefBody::new(attr.body.op, Block::new(chunks), attr.body.id);
+                        Def::new(attr.sig, body)
+                    })
+                    .collect::<Vec<_>>();
+                Expr::Record(Record::Normal(NormalRecord::new(
+                    rec.l_brace,
+                    rec.r_brace,
+                    RecordAttrs::new(attrs),
+                )))
+            }
+            Expr::Def(mut def) => {
+                match &mut def.sig {
+                    Signature::Var(var) => {
+                        if let VarPattern::Ident(ident) = &mut var.pat {
+                            if ident.vis.is_private() {
+                                ident.name = Self::rename_var_name(ident.name.clone(), rename);
+                            }
+                        }
+                    }
+                    Signature::Subr(subr) => {
+                        if subr.ident.vis.is_private() {
+                            subr.ident.name =
+                                Self::rename_var_name(subr.ident.name.clone(), rename);
+                        }
+                        Self::rename_params(&mut subr.params, rename);
+                    }
+                }
+                Self::perform_desugar(|expr| Self::rename_idents(expr, rename), Expr::Def(def))
+            }
+            Expr::Lambda(mut lambda) => {
+                Self::rename_params(&mut lambda.sig.params, rename);
+                Self::perform_desugar(
+                    |expr| Self::rename_idents(expr, rename),
+                    Expr::Lambda(lambda),
+                )
+            }
+            expr => Self::perform_desugar(|expr| Self::rename_idents(expr, rename), expr),
+        }
+    }
+
+    fn rename_var_name(name: VarName, rename: &impl Fn(&VarName) -> Option<Str>) -> VarName {
+        match rename(&name) {
+            Some(content) => VarName::new(Token {
+                content,
+                ..name.into_token()
+            }),
+            None => name,
+        }
+    }
+
+    fn rename_params(params: &mut Params, rename: &impl Fn(&VarName) -> Option<Str>) {
+        let non_defaults = params
+            .non_defaults
+            .iter_mut()
+            .chain(params.var_params.as_deref_mut());
+        for sig in non_defaults.chain(params.defaults.iter_mut().map(|param| &mut param.sig)) {
+            if let ParamPattern::VarName(name) = &mut sig.pat {
+                *name = Self::rename_var_name(name.clone(), rename);
+            }
+        }
+    }
+
     fn desugar_all_chunks(module: Module, desugar: impl Fn(Expr) -> Expr) -> Module {
         module.into_iter().map(desugar).collect()
     }
@@ -302,6 +600,7 @@ impl Desugarer {
                 if let Some(t_op) = &mut lambda.sig.return_t_spec {
                     *t_op.t_spec_as_expr = desugar(*t_op.t_spec_as_expr.clone());
                 }
+                lambda.sig.guard = lambda.sig.guard.map(|guard| Box::new(desugar(*guard)));
                 lambda.sig.params = Self::perform_desugar_params(desugar, lambda.sig.params);
                 let body = Block::new(chunks);
                 Expr::Lambda(Lambda::new(lambda.sig, lambda.op, body, lambda.id))
@@ -579,6 +878,11 @@ impl Desugarer {
         match expr {
             Expr::Lambda(mut lambda) => {
                 self.desugar_params_patterns(&mut lambda.sig.params, &mut lambda.body);
+                lambda.sig.guard = lambda
+                    .sig
+                    .guard
+                    .map(|guard| Box::new(self.rec_desugar_lambda_pattern(*guard)));
+                self.desugar_str_pattern(&mut lambda);
                 lambda.body = self.desugar_pattern_in_block(lambda.body);
                 Expr::Lambda(lambda)
             }
@@ -586,6 +890,82 @@ impl Desugarer {
         }
     }
 
+    /// ```erg
+    /// match s:
+    ///     "ab" + rest -> ...
+    ///     rest + "ab" if g -> ...
+    /// ```
+    /// ↓
+    /// ```erg
+    /// match s:
+    ///     %1: Str if %1.startswith("ab") ->
+    ///         rest = %1.removeprefix("ab")
+    ///         ...
+    ///     %2: Str if %2.endswith("ab") and g ->
+    ///         rest = %2.removesuffix("ab")
+    ///         ...
+    /// ```
+    fn desugar_str_pattern(&mut self, lambda: &mut Lambda) {
+        let Some(param) = lambda.sig.params.non_defaults.first_mut() else {
+            return;
+        };
+        let ParamPattern::Str(str_pat) = &param.pat else {
+            return;
+        };
+        let line = str_pat.ln_begin().unwrap_or(1);
+        let col = str_pat.col_begin().unwrap_or(0);
+        let (check, strip) = if str_pat.is_prefix {
+            ("startswith", "removeprefix")
+        } else {
+            ("endswith", "removesuffix")
+        };
+        let (buf_name, buf_param) = self.gen_buf_nd_param(line);
+        let buf = Expr::local(&buf_name, line, col);
+        let check = Identifier::public_with_line(DOT, Str::ever(check), line);
+        let cond = buf
+            .clone()
+            .attr_expr(check)
+            .call1(Expr::Literal(str_pat.affix.clone()));
+        if let ParamPattern::VarName(name) = str_pat.rest.as_ref() {
+            let strip = Identifier::public_with_line(DOT, Str::ever(strip), line);
+            let rest = buf
+                .attr_expr(strip)
+                .call1(Expr::Literal(str_pat.affix.clone()));
+            // the guard is evaluated before the body, so `rest` is not defined yet
+            lambda.sig.guard = lambda
+                .sig
+                .guard
+                .take()
+                .map(|guard| Box::new(Self::substitute_local(*guard, name.inspect(), &rest)));
+            let ident = Identifier::new(VisModifierSpec::Private, name.clone());
+            let def = Def::new(Signature::new_var(ident), DefBody::new_single(rest));
+            lambda.body.insert(0, Expr::Def(def));
+        }
+        lambda.sig.guard = Some(Box::new(match lambda.sig.guard.take() {
+            Some(guard) => {
+                let and = Token::new(TokenKind::AndOp, "and", line, col);
+                Expr::BinOp(BinOp::new(and, cond, *guard))
+            }
+            None => cond,
+        }));
+        let str_t = Identifier::private_with_line(Str::ever("Str"), line);
+        let t_spec_as_expr = Expr::Accessor(Accessor::Ident(str_t.clone()));
+        param.t_spec = Some(TypeSpecWithOp::new(
+            COLON,
+            TypeSpec::mono(str_t),
+            t_spec_as_expr,
+        ));
+        param.pat = buf_param;
+    }
+
+    /// Replaces the local variable `name` in `expr` with `new`.
+    fn substitute_local(expr: Expr, name: &str, new: &Expr) -> Expr {
+        match expr {
+            Expr::Accessor(Accessor::Ident(ident)) if &ident.inspect()[..] == name => new.clone(),
+            expr => Self::perform_desugar(|ex| Self::substitute_local(ex, name, new), expr),
+        }
+    }
+
     fn desugar_pattern_in_module(&mut self, module: Module) -> Module {
         Module::new(self.desugar_pattern(module.into_iter()))
     }
@@ -902,7 +1282,31 @@ impl Desugarer {
         NormalRecord::new(record.l_brace, record.r_brace, attrs)
     }
 
-    fn dummy_array_expr(len: Literal) -> Expr {
+    /// `[x, y]` => `[?; 2]`, `[{x; y}, {x = a; y = b}]` => `[{.x = ?; .y = ?}; 2]`
+    ///
+    /// Arrays are homogeneous, so the element type is taken from the first (already desugared) element pattern.
+    fn array_param_t_spec(arr: &ParamArrayPattern, line: u32) -> TypeSpecWithOp {
+        let len = arr.elems.non_defaults.len();
+        let len = Literal::new(Token::new(TokenKind::NatLit, len.to_string(), line, 0));
+        let infer = Token::new(TokenKind::Try, "?", line, 0);
+        let (elem_t_spec, elem) = match arr
+            .elems
+            .non_defaults
+            .first()
+            .and_then(|elem| elem.t_spec.as_ref())
+        {
+            Some(ts) => (ts.t_spec.clone(), *ts.t_spec_as_expr.clone()),
+            None => (
+                TypeSpec::Infer(infer.clone()),
+                Expr::local("Obj", infer.lineno, infer.col_begin),
+            ),
+        };
+        let t_spec = ArrayTypeSpec::new(elem_t_spec, ConstExpr::Lit(len.clone()));
+        let t_spec_as_expr = Self::dummy_array_expr(len, elem);
+        TypeSpecWithOp::new(COLON, TypeSpec::Array(t_spec), t_spec_as_expr)
+    }
+
+    fn dummy_array_expr(len: Literal, elem: Expr) -> Expr {
         let l_sqbr = Token {
             content: "[".into(),
             kind: TokenKind::LSqBr,
@@ -913,7 +1317,6 @@ impl Desugarer {
             kind: TokenKind::RSqBr,
             ..len.token
         };
-        let elem = Expr::local("Obj", l_sqbr.lineno, l_sqbr.col_begin);
         let array = Array::WithLength(ArrayWithLength::new(
             l_sqbr,
             r_sqbr,
@@ -1049,17 +1452,7 @@ impl Desugarer {
                     );
                 }
                 if param.t_spec.is_none() {
-                    let len = arr.elems.non_defaults.len();
-                    let len = Literal::new(Token::new(TokenKind::NatLit, len.to_string(), line, 0));
-                    let infer = Token::new(TokenKind::Try, "?", line, 0);
-                    let t_spec =
-                        ArrayTypeSpec::new(TypeSpec::Infer(infer), ConstExpr::Lit(len.clone()));
-                    let t_spec_as_expr = Self::dummy_array_expr(len);
-                    param.t_spec = Some(TypeSpecWithOp::new(
-                        Token::dummy(TokenKind::Colon, ":"),
-                        TypeSpec::Array(t_spec),
-                        t_spec_as_expr,
-                    ));
+                    param.t_spec = Some(Self::array_param_t_spec(arr, line));
                 }
                 param.pat = buf_param;
             }
@@ -1170,18 +1563,7 @@ impl Desugarer {
         match &mut sig.pat {
             ParamPattern::Tuple(tup) => {
                 let (buf_name, buf_sig) = self.gen_buf_nd_param(line);
-                let ident = Identifier::private(Str::from(&buf_name));
-                new_body.insert(
-                    insertion_idx,
-                    Expr::Def(Def::new(
-                        Signature::Var(VarSignature::new(
-                            VarPattern::Ident(ident),
-                            sig.t_spec.clone(),
-                        )),
-                        body,
-                    )),
-                );
-                insertion_idx += 1;
+                let def_idx = insertion_idx;
                 let mut ty_exprs = vec![];
                 let mut tys = vec![];
                 for (n, elem) in tup.elems.non_defaults.iter_mut().enumerate() {
@@ -1215,22 +1597,13 @@ impl Desugarer {
                     )));
                     sig.t_spec = Some(TypeSpecWithOp::new(COLON, t_spec, t_spec_as_expr));
                 }
+                Self::insert_buf_def(new_body, def_idx, &buf_name, sig, body);
                 sig.pat = buf_sig;
-                insertion_idx
+                insertion_idx + 1
             }
             ParamPattern::Array(arr) => {
                 let (buf_name, buf_sig) = self.gen_buf_nd_param(line);
-                new_body.insert(
-                    insertion_idx,
-                    Expr::Def(Def::new(
-                        Signature::Var(VarSignature::new(
-                            VarPattern::Ident(Identifier::private(Str::from(&buf_name))),
-                            sig.t_spec.clone(),
-                        )),
-                        body,
-                    )),
-                );
-                insertion_idx += 1;
+                let def_idx = insertion_idx;
                 for (n, elem) in arr.elems.non_defaults.iter_mut().enumerate() {
                     insertion_idx = self.desugar_nested_param_pattern(
                         new_body,
@@ -1241,34 +1614,15 @@ impl Desugarer {
                     );
                 }
                 if sig.t_spec.is_none() {
-                    let len = arr.elems.non_defaults.len();
-                    let len = Literal::new(Token::new(TokenKind::NatLit, len.to_string(), line, 0));
-                    let infer = Token::new(TokenKind::Try, "?", line, 0);
-                    let t_spec =
-                        ArrayTypeSpec::new(TypeSpec::Infer(infer), ConstExpr::Lit(len.clone()));
-                    let t_spec_as_expr = Self::dummy_array_expr(len);
-                    sig.t_spec = Some(TypeSpecWithOp::new(
-                        COLON,
-                        TypeSpec::Array(t_spec),
-                        t_spec_as_expr,
-                    ));
+                    sig.t_spec = Some(Self::array_param_t_spec(arr, line));
                 }
+                Self::insert_buf_def(new_body, def_idx, &buf_name, sig, body);
                 sig.pat = buf_sig;
-                insertion_idx
+                insertion_idx + 1
             }
             ParamPattern::Record(rec) => {
                 let (buf_name, buf_sig) = self.gen_buf_nd_param(line);
-                new_body.insert(
-                    insertion_idx,
-                    Expr::Def(Def::new(
-                        Signature::Var(VarSignature::new(
-                            VarPattern::Ident(Identifier::private(Str::from(&buf_name))),
-                            sig.t_spec.clone(),
-                        )),
-                        body,
-                    )),
-                );
-                insertion_idx += 1;
+                let def_idx = insertion_idx;
                 let mut attrs = RecordAttrs::new(vec![]);
                 let mut tys = vec![];
                 for ParamRecordAttr { lhs, rhs } in rec.elems.iter_mut() {
@@ -1309,8 +1663,9 @@ impl Desugarer {
                     ));
                     sig.t_spec = Some(TypeSpecWithOp::new(COLON, t_spec, t_spec_as_expr));
                 }
+                Self::insert_buf_def(new_body, def_idx, &buf_name, sig, body);
                 sig.pat = buf_sig;
-                insertion_idx
+                insertion_idx + 1
             }
             /*
             VarPattern::DataPack(pack) => {
@@ -1353,6 +1708,20 @@ impl Desugarer {
         }
     }
 
+    /// Inserts `buf_name: T = <the element>` before the definitions of the names bound by the nested patterns.
+    /// `T` is the type of the pattern (e.g. `{.x = ?; .y = ?}`), so that the elements can be projected.
+    fn insert_buf_def(
+        new_body: &mut Block,
+        def_idx: usize,
+        buf_name: &str,
+        sig: &NonDefaultParamSignature,
+        body: DefBody,
+    ) {
+        let ident = Identifier::private(Str::rc(buf_name));
+        let v = VarSignature::new(VarPattern::Ident(ident), sig.t_spec.clone());
+        new_body.insert(def_idx, Expr::Def(Def::new(Signature::Var(v), body)));
+    }
+
     fn _desugar_self(module: Module) -> Module {
         Self::desugar_all_chunks(module, Self::_desugar_self_inner)
     }
@@ -1366,6 +1735,211 @@ impl Desugarer {
         todo!()
     }
 
+    fn desugar_operator_section(&self, module: Module) -> Module {
+        module
+            .into_iter()
+            .map(|chunk| self.rec_desugar_operator_section(chunk))
+            .collect()
+    }
+
+    /// `(+ 1)` -> `%v -> %v + 1`
+    /// `(1 +)` -> `%v -> 1 + %v`
+    /// `(+)` -> `(%l, %r) -> %l + %r`
+    ///
+    /// The parser fills the missing operands of sections with empty `Dummy`s.
+    fn rec_desugar_operator_section(&self, expr: Expr) -> Expr {
+        match expr {
+            Expr::BinOp(binop) if binop.args.iter().any(|arg| Self::is_section_hole(arg)) => {
+                let (op, lhs, rhs) = binop.deconstruct();
+                let line = op.lineno;
+                let mut params = vec![];
+                let mut fill = |operand: Expr| {
+                    if Self::is_section_hole(&operand) {
+                        let name = self.var_gen.fresh_varname();
+                        let pat =
+                            ParamPattern::VarName(VarName::from_str_and_line(name.clone(), line));
+                        params.push(NonDefaultParamSignature::new(pat, None));
+                        Expr::local(&name, line, op.col_begin)
+                    } else {
+                        self.rec_desugar_operator_section(operand)
+                    }
+                };
+                let lhs = fill(lhs);
+                let rhs = fill(rhs);
+                let id = DefId(get_hash(&(&op, &lhs, &rhs)));
+                let body = Block::new(vec![Expr::BinOp(BinOp::new(op, lhs, rhs))]);
+                let sig = LambdaSignature::new(
+                    Params::new(params, None, vec![], None),
+                    None,
+                    TypeBoundSpecs::empty(),
+                );
+                let arrow = Token::from_str(TokenKind::FuncArrow, "->");
+                Expr::Lambda(Lambda::new(sig, arrow, body, id))
+            }
+            expr => Self::perform_desugar(|ex| self.rec_desugar_operator_section(ex), expr),
+        }
+    }
+
+    fn is_section_hole(expr: &Expr) -> bool {
+        matches!(expr, Expr::Dummy(dummy) if dummy.is_empty())
+    }
+
+    fn desugar_nil_safe(&mut self, module: Module) -> Module {
+        module
+            .into_iter()
+            .map(|chunk| self.rec_desugar_nil_safe(chunk))
+            .collect()
+    }
+
+    /// `x?.y.z` -> `match x: (%v: NoneType) -> None; %v -> %v.y.z`
+    /// `x ?? y` -> `match x: (%v: NoneType) -> y; %v -> %v`
+    /// These are rewritten here rather than in the checker, so the checker types the branches as an ordinary `match`
+    /// (`x?.y: T or NoneType` if `x.y: T`).
+    fn rec_desugar_nil_safe(&mut self, mut expr: Expr) -> Expr {
+        let buf_name = self.var_gen.fresh_varname();
+        if let Some(receiver) = Self::take_safe_receiver(&mut expr, &buf_name) {
+            let line = receiver.ln_begin().unwrap_or(1);
+            let col = receiver.col_begin().unwrap_or(0);
+            let receiver = self.rec_desugar_nil_safe(receiver);
+            let none = Expr::Literal(Literal::from(Token::new(
+                TokenKind::NoneLit,
+                "None",
+                line,
+                col,
+            )));
+            let is_procedural = Self::is_proc_chain(&expr);
+            let rest = self.rec_desugar_nil_safe(expr);
+            return Self::gen_nil_match(receiver, &buf_name, none, rest, is_procedural);
+        }
+        match expr {
+            Expr::BinOp(binop) if binop.op.is(TokenKind::Coalesce) => {
+                let mut args = binop.args.into_iter();
+                let lhs = self.rec_desugar_nil_safe(*args.next().unwrap());
+                let rhs = *args.next().unwrap();
+                let is_procedural = Self::is_proc_chain(&rhs);
+                let rhs = self.rec_desugar_nil_safe(rhs);
+                let var = Expr::local(&buf_name, binop.op.lineno, binop.op.col_begin);
+                Self::gen_nil_match(lhs, &buf_name, rhs, var, is_procedural)
+            }
+            expr => Self::perform_desugar(|ex| self.rec_desugar_nil_safe(ex), expr),
+        }
+    }
+
+    /// Replaces the receiver of the first `?.` in the access chain with `buf_name`,
+    /// and returns the receiver.
+    fn take_safe_receiver(expr: &mut Expr, buf_name: &str) -> Option<Expr> {
+        match expr {
+            Expr::Accessor(Accessor::Attr(attr)) => {
+                Self::take_safe_receiver(&mut attr.obj, buf_name).or_else(|| {
+                    Self::take_receiver_if_safe(&mut attr.obj, &mut attr.ident, buf_name)
+                })
+            }
+            Expr::Accessor(Accessor::TupleAttr(tattr)) => {
+                Self::take_safe_receiver(&mut tattr.obj, buf_name)
+            }
+            Expr::Accessor(Accessor::Subscr(subscr)) => {
+                Self::take_safe_receiver(&mut subscr.obj, buf_name)
+            }
+            Expr::Call(call) => Self::take_safe_receiver(&mut call.obj, buf_name).or_else(|| {
+                let ident = call.attr_name.as_mut()?;
+                Self::take_receiver_if_safe(&mut call.obj, ident, buf_name)
+            }),
+            _ => None,
+        }
+    }
+
+    /// `x.f!()`, `x.y.f!()`, `f!()` -> true
+    fn is_proc_chain(expr: &Expr) -> bool {
+        match expr {
+            Expr::Accessor(Accessor::Ident(ident)) => ident.is_procedural(),
+            Expr::Accessor(Accessor::Attr(attr)) => {
+                attr.ident.is_procedural() || Self::is_proc_chain(&attr.obj)
+            }
+            Expr::Accessor(Accessor::TupleAttr(tattr)) => Self::is_proc_chain(&tattr.obj),
+            Expr::Accessor(Accessor::Subscr(subscr)) => Self::is_proc_chain(&subscr.obj),
+            Expr::Call(call) => {
+                call.attr_name
+                    .as_ref()
+                    .is_some_and(|ident| ident.is_procedural())
+                    || Self::is_proc_chain(&call.obj)
+            }
+            _ => false,
+        }
+    }
+
+    fn take_receiver_if_safe(
+        obj: &mut Expr,
+        ident: &mut Identifier,
+        buf_name: &str,
+    ) -> Option<Expr> {
+        let VisModifierSpec::Public(dot) = &mut ident.vis else {
+            return None;
+        };
+        if !dot.is(TokenKind::SafeDot) {
+            return None;
+        }
+        dot.kind = TokenKind::Dot;
+        dot.content = Str::ever(".");
+        let var = Expr::local(buf_name, dot.lineno, dot.col_begin);
+        Some(std::mem::replace(obj, var))
+    }
+
+    /// `match subject: (%v: NoneType) -> none_branch; %v -> some_branch`
+    fn gen_nil_match(
+        subject: Expr,
+        buf_name: &str,
+        none_branch: Expr,
+        some_branch: Expr,
+        is_procedural: bool,
+    ) -> Expr {
+        let line = subject.ln_begin().unwrap_or(1);
+        let (op, match_symbol) = if is_procedural {
+            (Token::from_str(TokenKind::ProcArrow, "=>"), "match!")
+        } else {
+            (Token::from_str(TokenKind::FuncArrow, "->"), "match")
+        };
+        let pat = ParamPattern::VarName(VarName::from_str_and_line(Str::rc(buf_name), line));
+        let none_t = Identifier::private_with_line(Str::ever("NoneType"), line);
+        let t_spec_as_expr = Expr::Accessor(Accessor::Ident(none_t.clone()));
+        let t_spec = TypeSpecWithOp::new(COLON, TypeSpec::mono(none_t), t_spec_as_expr);
+        let none_param = NonDefaultParamSignature::new(pat.clone(), Some(t_spec));
+        let sig = LambdaSignature::new(Params::single(none_param), None, TypeBoundSpecs::empty());
+        let id = DefId(get_hash(&(&subject, buf_name, "None")));
+        let none_arm = Lambda::new(sig, op.clone(), Block::new(vec![none_branch]), id);
+        let some_param = NonDefaultParamSignature::new(pat, None);
+        let sig = LambdaSignature::new(Params::single(some_param), None, TypeBoundSpecs::empty());
+        let id = DefId(get_hash(&(&subject, buf_name)));
+        let some_arm = Lambda::new(sig, op, Block::new(vec![some_branch]), id);
+        let args = Args::pos_only(
+            vec![
+                PosArg::new(subject),
+                PosArg::new(Expr::Lambda(none_arm)),
+                PosArg::new(Expr::Lambda(some_arm)),
+            ],
+            None,
+        );
+        Expr::Call(Expr::static_local(match_symbol).call(args))
+    }
+
+    fn desugar_concat_op(module: Module) -> Module {
+        Self::desugar_all_chunks(module, Self::rec_desugar_concat_op)
+    }
+
+    /// `x ++ y` => `x.concat(y)`
+    fn rec_desugar_concat_op(expr: Expr) -> Expr {
+        match expr {
+            Expr::BinOp(binop) if binop.op.is(TokenKind::Concat) => {
+                let mut args = binop.args.into_iter();
+                let lhs = Self::rec_desugar_concat_op(*args.next().unwrap());
+                let rhs = Self::rec_desugar_concat_op(*args.next().unwrap());
+                let concat =
+                    Identifier::public_with_line(DOT, Str::ever("concat"), binop.op.lineno);
+                Expr::Call(Call::new(lhs, Some(concat), Args::single(PosArg::new(rhs))))
+            }
+            expr => Self::perform_desugar(Self::rec_desugar_concat_op, expr),
+        }
+    }
+
     /// x[y] => x.__getitem__(y)
     /// x.0 => x.__Tuple_getitem__(0)
     fn desugar_acc(module: Module) -> Module {
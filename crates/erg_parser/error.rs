@@ -12,6 +12,7 @@ use erg_common::traits::Stream;
 use erg_common::{fmt_iter, fmt_vec_split_with, impl_display_and_error, impl_stream, switch_lang};
 
 use crate::ast::Module;
+use crate::feature::Feature;
 use crate::token::TokenKind;
 
 #[derive(Debug)]
@@ -93,6 +94,72 @@ impl LexError {
         ))
     }
 
+    /// `name` is available only if `feat` is enabled by `#!feature(...)`
+    pub fn unstable_feature_error(errno: usize, loc: Location, name: &str, feat: Feature) -> Self {
+        let main_msg = switch_lang!(
+            "japanese" => format!("この機能({name})は不安定です"),
+            "simplified_chinese" => format!("此功能（{name}）不稳定"),
+            "traditional_chinese" => format!("此功能（{name}）不穩定"),
+            "english" => format!("this feature({name}) is unstable"),
+        );
+        let attr = StyledString::new(format!("#!feature({feat})"), Some(HINT), Some(ATTR));
+        let hint = switch_lang!(
+            "japanese" => format!("モジュールの先頭に{attr}を追加すると使用できます"),
+            "simplified_chinese" => format!("在模块顶部添加{attr}即可使用"),
+            "traditional_chinese" => format!("在模塊頂部添加{attr}即可使用"),
+            "english" => format!("add {attr} at the top of the module to enable it"),
+        );
+        Self::new(ErrorCore::new(
+            vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+            main_msg,
+            errno,
+            FeatureError,
+            loc,
+        ))
+    }
+
+    pub fn unknown_feature_error(errno: usize, loc: Location, name: &str) -> Self {
+        let name = StyledStr::new(name, Some(ERR), Some(ATTR));
+        let features = Feature::ALL
+            .iter()
+            .map(|feat| feat.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let hint = switch_lang!(
+            "japanese" => format!("利用可能な機能: {features}"),
+            "simplified_chinese" => format!("可用的功能: {features}"),
+            "traditional_chinese" => format!("可用的功能: {features}"),
+            "english" => format!("available features: {features}"),
+        );
+        Self::new(ErrorCore::new(
+            vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+            switch_lang!(
+                "japanese" => format!("{name}という機能はありません"),
+                "simplified_chinese" => format!("没有名为{name}的功能"),
+                "traditional_chinese" => format!("沒有名為{name}的功能"),
+                "english" => format!("unknown feature: {name}"),
+            ),
+            errno,
+            FeatureError,
+            loc,
+        ))
+    }
+
+    pub fn misplaced_feature_attr_error(errno: usize, loc: Location) -> Self {
+        Self::new(ErrorCore::new(
+            vec![SubMessage::only_loc(loc)],
+            switch_lang!(
+                "japanese" => "#!feature(...)はモジュールの先頭に置く必要があります",
+                "simplified_chinese" => "#!feature(...)必须位于模块顶部",
+                "traditional_chinese" => "#!feature(...)必須位於模塊頂部",
+                "english" => "#!feature(...) must be placed at the top of the module",
+            ),
+            errno,
+            SyntaxError,
+            loc,
+        ))
+    }
+
     pub fn invalid_none_match(errno: usize, loc: Location, fn_name: &str, line: u32) -> Self {
         let mut err = Self::new(ErrorCore::bug(errno, loc, fn_name, line));
         err.set_hint("None is got");
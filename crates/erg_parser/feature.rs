@@ -0,0 +1,118 @@
+//! defines `Feature` and `Features`.
+//!
+//! Unstable language features are enabled per module by feature attributes at the top of the module.
+//!
+//! ```erg
+//! #!feature(implicit_mul, type_app)
+//! x = 2
+//! print! 3x + 1
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+/// An unstable language feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Feature {
+    /// `3x` is parsed as `3 * x` (lexer)
+    ImplicitMul,
+    /// explicit type application of polymorphic subroutines (e.g. `id|Int|`, `id|T := Int|`) (checker)
+    TypeApp,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Feature {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|feat| feat.as_str() == s)
+            .ok_or(())
+    }
+}
+
+impl Feature {
+    pub const ALL: [Feature; 2] = [Self::ImplicitMul, Self::TypeApp];
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::ImplicitMul => "implicit_mul",
+            Self::TypeApp => "type_app",
+        }
+    }
+
+    const fn bit(&self) -> u32 {
+        1 << (*self as u8)
+    }
+}
+
+/// The set of features enabled in a module
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Features(u32);
+
+impl fmt::Display for Features {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let features = self.iter().map(|feat| feat.as_str()).collect::<Vec<_>>();
+        write!(f, "#!feature({})", features.join(", "))
+    }
+}
+
+impl FromIterator<Feature> for Features {
+    fn from_iter<I: IntoIterator<Item = Feature>>(iter: I) -> Self {
+        let mut features = Self::empty();
+        for feat in iter {
+            features.insert(feat);
+        }
+        features
+    }
+}
+
+impl Features {
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(&self, feat: Feature) -> bool {
+        self.0 & feat.bit() != 0
+    }
+
+    pub fn insert(&mut self, feat: Feature) {
+        self.0 |= feat.bit();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Feature> + '_ {
+        Feature::ALL.into_iter().filter(|feat| self.contains(*feat))
+    }
+
+    /// Parses a feature attribute (a comment line such as `#!feature(implicit_mul, type_app)`).
+    /// Returns `None` if `comment` is not a feature attribute, otherwise the feature names.
+    /// ```
+    /// use erg_parser::feature::Features;
+    /// assert_eq!(Features::parse_attr("#!feature(a, b)"), Some(vec!["a", "b"]));
+    /// assert_eq!(Features::parse_attr("#!feature()"), Some(vec![]));
+    /// assert_eq!(Features::parse_attr("# feature(a)"), None);
+    /// ```
+    pub fn parse_attr(comment: &str) -> Option<Vec<&str>> {
+        let names = comment
+            .strip_prefix("#!feature(")?
+            .trim_end()
+            .strip_suffix(')')?;
+        Some(
+            names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .collect(),
+        )
+    }
+}
@@ -9,9 +9,10 @@ use erg_common::config::ErgConfig;
 use erg_common::io::Input;
 use erg_common::traits::DequeStream;
 use erg_common::traits::{Locational, Runnable, Stream};
-use erg_common::{debug_power_assert, fn_name_full, normalize_newline, switch_lang};
+use erg_common::{debug_power_assert, fn_name_full, normalize_newline, switch_lang, trace_span};
 
 use crate::error::{LexError, LexErrors, LexResult, LexerRunnerError, LexerRunnerErrors};
+use crate::feature::{Feature, Features};
 use crate::token::{Token, TokenCategory, TokenKind, TokenStream};
 use TokenKind::*;
 
@@ -176,6 +177,12 @@ pub struct Lexer /*<'a>*/ {
     /// 0-origin, indicates the column number in which the token appears
     col_token_starts: u32,
     interpol_stack: Vec<Interpolation>,
+    /// features enabled by `#!feature(...)`
+    features: Features,
+    /// `#!feature(...)` is allowed only before the first token (except newlines)
+    in_header: bool,
+    /// a token inserted by the lexer (e.g. `*` of `3x`)
+    pending: Option<Token>,
 }
 
 impl Lexer /*<'a>*/ {
@@ -190,6 +197,9 @@ impl Lexer /*<'a>*/ {
             lineno_token_starts: 0,
             col_token_starts: 0,
             interpol_stack: vec![Interpolation::Not],
+            features: Features::empty(),
+            in_header: true,
+            pending: None,
         }
     }
 
@@ -205,13 +215,28 @@ impl Lexer /*<'a>*/ {
             lineno_token_starts: 0,
             col_token_starts: 0,
             interpol_stack: vec![Interpolation::Not],
+            features: Features::empty(),
+            in_header: true,
+            pending: None,
         }
     }
 
+    /// Enables `features` regardless of `#!feature(...)`.
+    pub fn with_features(mut self, features: Features) -> Self {
+        self.features = features;
+        self
+    }
+
     pub fn lex(self) -> Result<TokenStream, LexErrors> {
+        self.lex_with_features().map(|(ts, _)| ts)
+    }
+
+    /// Returns the token stream and the features enabled by `#!feature(...)`.
+    pub fn lex_with_features(mut self) -> Result<(TokenStream, Features), LexErrors> {
+        let _span = trace_span!("lex");
         let mut result = TokenStream::empty();
         let mut errs = LexErrors::empty();
-        for i in self {
+        for i in self.by_ref() {
             match i {
                 Ok(token) => result.push(token),
                 Err(err) => {
@@ -220,7 +245,7 @@ impl Lexer /*<'a>*/ {
             }
         }
         if errs.is_empty() {
-            Ok(result)
+            Ok((result, self.features))
         } else {
             Err(errs)
         }
@@ -232,6 +257,7 @@ impl Lexer /*<'a>*/ {
         // cannot use String::len() for multi-byte characters
         let cont_len = cont.chars().count();
         let token = Token::new(kind, cont, lineno, col_begin);
+        self.in_header &= kind == Newline;
         self.prev_token = token.clone();
         self.col_token_starts += cont_len as u32;
         token
@@ -243,6 +269,7 @@ impl Lexer /*<'a>*/ {
         // cannot use String::len() for multi-byte characters
         let cont_len = cont.chars().count();
         let token = Token::new(kind, cont, lineno, self.col_token_starts);
+        self.in_header &= kind == Newline;
         self.prev_token = token.clone();
         self.col_token_starts += cont_len as u32;
         token
@@ -387,6 +414,35 @@ impl Lexer /*<'a>*/ {
             }
             s.push(self.consume().unwrap());
         }
+        self.lex_feature_attr(&s)
+    }
+
+    /// `#!feature(implicit_mul, type_app)`
+    fn lex_feature_attr(&mut self, comment: &str) -> LexResult<()> {
+        let names = if let Some(names) = Features::parse_attr(comment) {
+            names
+        } else {
+            return Ok(());
+        };
+        if !self.in_header {
+            let attr = self.emit_token(Illegal, comment);
+            return Err(LexError::misplaced_feature_attr_error(
+                line!() as usize,
+                attr.loc(),
+            ));
+        }
+        for name in names {
+            if let Ok(feat) = name.parse::<Feature>() {
+                self.features.insert(feat);
+            } else {
+                let attr = self.emit_token(Illegal, comment);
+                return Err(LexError::unknown_feature_error(
+                    line!() as usize,
+                    attr.loc(),
+                    name,
+                ));
+            }
+        }
         Ok(())
     }
 
@@ -612,6 +668,7 @@ impl Lexer /*<'a>*/ {
     /// `_` will be removed at compiletime
     fn lex_num(&mut self, first_ch: char) -> LexResult<Token> {
         let mut num = first_ch.to_string();
+        let mut implicit_mul = false;
         while let Some(ch) = self.peek_cur_ch() {
             match ch {
                 // `.` may be a dot operator, don't consume
@@ -621,15 +678,15 @@ impl Lexer /*<'a>*/ {
                 n if n.is_ascii_digit() || n == '_' => {
                     num.push(self.consume().unwrap());
                 }
-                'b' | 'B' => {
+                'b' | 'B' if Self::is_zero(&num) => {
                     num.push(self.consume().unwrap());
                     return self.lex_bin(num);
                 }
-                'o' | 'O' => {
+                'o' | 'O' if Self::is_zero(&num) => {
                     num.push(self.consume().unwrap());
                     return self.lex_oct(num);
                 }
-                'x' | 'X' => {
+                'x' | 'X' if Self::is_zero(&num) => {
                     num.push(self.consume().unwrap());
                     return self.lex_hex(num);
                 }
@@ -639,13 +696,17 @@ impl Lexer /*<'a>*/ {
                         && (self.peek_next_ch() == Some('+') || self.peek_next_ch() == Some('-'))
                     {
                         return self.lex_exponent(num);
-                    } else {
+                    } else if self.features.contains(Feature::ImplicitMul) {
                         // IntLit * Symbol(e.g. 3x + 1)
+                        implicit_mul = true;
+                        break;
+                    } else {
                         let token = self.emit_token(Illegal, &(num + &c.to_string()));
-                        return Err(LexError::feature_error(
+                        return Err(LexError::unstable_feature_error(
                             line!() as usize,
                             token.loc(),
                             "*-less multiply",
+                            Feature::ImplicitMul,
                         ));
                     }
                 }
@@ -659,7 +720,11 @@ impl Lexer /*<'a>*/ {
         } else {
             NatLit
         };
-        Ok(self.emit_token(kind, &num))
+        let token = self.emit_token(kind, &num);
+        if implicit_mul {
+            self.pending = Some(Token::new(Star, "*", token.lineno, self.col_token_starts));
+        }
+        Ok(token)
     }
 
     /// number '.' ~~
@@ -667,7 +732,7 @@ impl Lexer /*<'a>*/ {
     fn lex_num_dot(&mut self, mut num: String) -> LexResult<Token> {
         match self.peek_next_ch() {
             // RatioLit
-            Some(n) if n.is_ascii_digit() && !self.prev_token.is(Dot) => {
+            Some(n) if n.is_ascii_digit() && !matches!(self.prev_token.kind, Dot | SafeDot) => {
                 num.push(self.consume().unwrap());
                 self.lex_ratio(num)
             }
@@ -1182,6 +1247,10 @@ impl Iterator for Lexer /*<'a>*/ {
         if self.prev_token.is(TokenKind::EOF) {
             return None;
         }
+        if let Some(token) = self.pending.take() {
+            self.prev_token = token.clone();
+            return Some(Ok(token));
+        }
         let indent_dedent = self.lex_space_indent_dedent();
         if indent_dedent.is_some() {
             return indent_dedent;
@@ -1354,8 +1423,31 @@ impl Iterator for Lexer /*<'a>*/ {
                     self.accept(Mutate, "!")
                 }
             }
-            Some('?') => self.accept(Try, "?"),
+            Some('?') => match self.peek_cur_ch() {
+                Some('.') => {
+                    self.consume();
+                    self.accept(SafeDot, "?.")
+                }
+                Some('?') => {
+                    self.consume();
+                    self.accept(Coalesce, "??")
+                }
+                _ => self.accept(Try, "?"),
+            },
             Some('+') => {
+                // `x ++ y`, `x++y` (not `x + +y`)
+                if self.peek_cur_ch() == Some('+')
+                    && matches!(
+                        self.prev_token.category(),
+                        TokenCategory::REnclosure
+                            | TokenCategory::Literal
+                            | TokenCategory::StrInterpRight
+                            | TokenCategory::Symbol
+                    )
+                {
+                    self.consume();
+                    return self.accept(Concat, "++");
+                }
                 let kind = match self.op_fix() {
                     Some(OpFix::Infix) => Plus,
                     Some(OpFix::Prefix) => PrePlus,
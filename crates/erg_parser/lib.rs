@@ -9,6 +9,7 @@ pub mod build_ast;
 pub mod convert;
 pub mod desugar;
 pub mod error;
+pub mod feature;
 pub mod lex;
 pub mod parse;
 pub mod token;
@@ -12,7 +12,7 @@ use erg_common::str::Str;
 use erg_common::traits::{DequeStream, ExitStatus, Locational, Runnable, Stream};
 use erg_common::{
     caused_by, debug_power_assert, enum_unwrap, fn_name, impl_display_for_enum,
-    impl_locational_for_enum, log, set, switch_lang, switch_unreachable,
+    impl_locational_for_enum, log, set, switch_lang, switch_unreachable, trace_span,
 };
 
 use crate::ast::*;
@@ -21,6 +21,7 @@ use crate::error::{
     CompleteArtifact, IncompleteArtifact, ParseError, ParseErrors, ParseResult, ParserRunnerError,
     ParserRunnerErrors,
 };
+use crate::feature::Features;
 use crate::lex::Lexer;
 use crate::token::{Token, TokenCategory, TokenKind, TokenStream};
 
@@ -391,6 +392,8 @@ impl Parser {
 #[derive(Debug, Default)]
 pub struct ParserRunner {
     cfg: ErgConfig,
+    /// the features enabled in the most recently parsed source
+    features: Features,
 }
 
 impl Runnable for ParserRunner {
@@ -400,7 +403,10 @@ impl Runnable for ParserRunner {
 
     #[inline]
     fn new(cfg: ErgConfig) -> Self {
-        Self { cfg }
+        Self {
+            cfg,
+            features: Features::empty(),
+        }
     }
 
     #[inline]
@@ -435,6 +441,10 @@ impl Runnable for ParserRunner {
 }
 
 impl ParserRunner {
+    pub const fn features(&self) -> Features {
+        self.features
+    }
+
     pub fn parse_token_stream(
         &mut self,
         ts: TokenStream,
@@ -448,9 +458,11 @@ impl ParserRunner {
         &mut self,
         src: String,
     ) -> Result<CompleteArtifact, IncompleteArtifact<Module, ParserRunnerErrors>> {
-        let ts = Lexer::new(Input::new(InputKind::Str(src), self.cfg.input.id()))
-            .lex()
+        self.features = Features::empty();
+        let (ts, features) = Lexer::new(Input::new(InputKind::Str(src), self.cfg.input.id()))
+            .lex_with_features()
             .map_err(|errs| ParserRunnerErrors::convert(self.input(), errs))?;
+        self.features = features;
         Parser::new(ts)
             .parse()
             .map_err(|iart| iart.map_errs(|errs| ParserRunnerErrors::convert(self.input(), errs)))
@@ -462,6 +474,7 @@ impl Parser {
         if self.tokens.is_empty() {
             return Ok(CompleteArtifact::new(Module::empty(), ParseErrors::empty()));
         }
+        let _span = trace_span!("parse");
         log!(info "the parsing process has started.");
         log!(info "token stream: {}", self.tokens);
         let module = match self.try_reduce_module() {
@@ -1525,6 +1538,62 @@ impl Parser {
         Ok(Methods::new(t_spec, class, vis, attrs))
     }
 
+    fn try_reduce_lambda(&mut self, lhs: Expr, guard: Option<Expr>) -> ParseResult<Lambda> {
+        debug_call_info!(self);
+        let op = self.lpop();
+        let is_multiline_block = self.cur_is(Newline);
+        let mut sig = self
+            .convert_rhs_to_lambda_sig(lhs)
+            .map_err(|_| self.stack_dec(fn_name!()))?;
+        sig.guard = guard.map(Box::new);
+        self.counter.inc();
+        let block = if is_multiline_block {
+            self.try_reduce_block()
+                .map_err(|_| self.stack_dec(fn_name!()))?
+        } else {
+            let expr = self
+                .try_reduce_expr(false, false, false, false)
+                .map_err(|_| self.stack_dec(fn_name!()))?;
+            Block::new(vec![expr])
+        };
+        debug_exit_info!(self);
+        Ok(Lambda::new(sig, op, block, self.counter))
+    }
+
+    /// `i > 0` of `(i: Int) if i > 0 -> ...`
+    /// Unlike `try_reduce_expr`, this does not consume the following lambda operator.
+    fn try_reduce_guard(&mut self) -> ParseResult<Expr> {
+        debug_call_info!(self);
+        let mut stack = Vec::<ExprOrOp>::new();
+        stack.push(ExprOrOp::Expr(
+            self.try_reduce_bin_lhs(false, false)
+                .map_err(|_| self.stack_dec(fn_name!()))?,
+        ));
+        while let Some(op) = self.peek() {
+            if !op.category_is(TC::BinOp) {
+                break;
+            }
+            let op_prec = op.kind.precedence();
+            while let Some(ExprOrOp::Op(prev_op)) = stack.len().checked_sub(2).map(|i| &stack[i]) {
+                if prev_op.kind.precedence() >= op_prec {
+                    collect_last_binop_on_stack(&mut stack);
+                } else {
+                    break;
+                }
+            }
+            stack.push(ExprOrOp::Op(self.lpop()));
+            stack.push(ExprOrOp::Expr(
+                self.try_reduce_bin_lhs(false, false)
+                    .map_err(|_| self.stack_dec(fn_name!()))?,
+            ));
+        }
+        while stack.len() >= 3 {
+            collect_last_binop_on_stack(&mut stack);
+        }
+        debug_exit_info!(self);
+        Ok(enum_unwrap!(stack.pop(), Some:(ExprOrOp::Expr:(_))))
+    }
+
     fn try_reduce_do_block(&mut self) -> ParseResult<Lambda> {
         debug_call_info!(self);
         let do_symbol = self.lpop();
@@ -1770,7 +1839,7 @@ impl Parser {
                         }
                     }
                 }
-                Some(t) if t.is(Dot) => {
+                Some(t) if matches!(t.kind, Dot | SafeDot) => {
                     let dot = self.lpop();
                     match self.lpop() {
                         symbol if symbol.is(Symbol) => {
@@ -1918,28 +1987,39 @@ impl Parser {
         loop {
             match self.peek() {
                 Some(op) if op.category_is(TC::LambdaOp) => {
-                    let op = self.lpop();
-                    let is_multiline_block = self.cur_is(Newline);
+                    if is_str_pattern_on_stack(&stack) {
+                        collect_last_binop_on_stack(&mut stack);
+                    }
                     let lhs = enum_unwrap!(stack.pop(), Some:(ExprOrOp::Expr:(_)));
-                    let sig = self
-                        .convert_rhs_to_lambda_sig(lhs)
+                    let lambda = self
+                        .try_reduce_lambda(lhs, None)
                         .map_err(|_| self.stack_dec(fn_name!()))?;
-                    self.counter.inc();
-                    let block = if is_multiline_block {
-                        self.try_reduce_block()
-                            .map_err(|_| self.stack_dec(fn_name!()))?
-                    } else {
-                        let expr = self
-                            .try_reduce_expr(false, false, false, false)
-                            .map_err(|_| self.stack_dec(fn_name!()))?;
-                        Block::new(vec![expr])
-                    };
-                    stack.push(ExprOrOp::Expr(Expr::Lambda(Lambda::new(
-                        sig,
-                        op,
-                        block,
-                        self.counter,
-                    ))));
+                    stack.push(ExprOrOp::Expr(Expr::Lambda(lambda)));
+                }
+                // match arm with a guard: `(i: Int) if i > 0 -> ...`
+                Some(t)
+                    if t.is(Symbol)
+                        && &t.inspect()[..] == "if"
+                        && (stack.len() == 1 || is_str_pattern_on_stack(&stack)) =>
+                {
+                    self.skip();
+                    if stack.len() > 1 {
+                        collect_last_binop_on_stack(&mut stack);
+                    }
+                    let lhs = enum_unwrap!(stack.pop(), Some:(ExprOrOp::Expr:(_)));
+                    let guard = self
+                        .try_reduce_guard()
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    if !self.cur_category_is(TC::LambdaOp) {
+                        let err = self.skip_and_throw_syntax_err(line!(), caused_by!());
+                        self.errs.push(err);
+                        debug_exit_info!(self);
+                        return Err(());
+                    }
+                    let lambda = self
+                        .try_reduce_lambda(lhs, Some(guard))
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    stack.push(ExprOrOp::Expr(Expr::Lambda(lambda)));
                 }
                 // type ascription
                 Some(op)
@@ -2008,7 +2088,7 @@ impl Parser {
                             })?,
                     ));
                 }
-                Some(t) if t.is(Dot) => {
+                Some(t) if matches!(t.kind, Dot | SafeDot) => {
                     let vis = self.lpop();
                     match self.lpop() {
                         symbol if symbol.is(Symbol) => {
@@ -2339,6 +2419,13 @@ impl Parser {
                     debug_exit_info!(self);
                     return Ok(Expr::Tuple(unit));
                 }
+                if !line_break {
+                    if let Some(section) = self.opt_reduce_operator_section() {
+                        let section = section.map_err(|_| self.stack_dec(fn_name!()))?;
+                        debug_exit_info!(self);
+                        return Ok(section);
+                    }
+                }
                 let mut expr = self
                     .try_reduce_expr(true, false, false, line_break)
                     .map_err(|_| {
@@ -2391,6 +2478,19 @@ impl Parser {
                 debug_exit_info!(self);
                 Ok(Expr::Array(array))
             }
+            Some(t)
+                if t.is(LBrace)
+                    && (self.nth_is(1, Closed)
+                        || (self.nth_is(1, Newline)
+                            && self.nth_is(2, Indent)
+                            && self.nth_is(3, Closed))) =>
+            {
+                let expr = self
+                    .try_reduce_record_spread()
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                debug_exit_info!(self);
+                Ok(expr)
+            }
             Some(t) if t.is(LBrace) => {
                 match self
                     .try_reduce_brace_container()
@@ -2502,7 +2602,7 @@ impl Parser {
                     let r_sqbr = expect_pop!(self, fail_next RSqBr);
                     obj = Expr::Accessor(Accessor::subscr(obj, index, r_sqbr));
                 }
-                Some(t) if t.is(Dot) && obj.col_end() == t.col_begin() => {
+                Some(t) if matches!(t.kind, Dot | SafeDot) && obj.col_end() == t.col_begin() => {
                     let vis = self.lpop();
                     let token = self.lpop();
                     match token.kind {
@@ -2511,7 +2611,8 @@ impl Parser {
                                 Identifier::new(VisModifierSpec::Public(vis), VarName::new(token));
                             obj = obj.attr_expr(ident);
                         }
-                        NatLit => {
+                        // `x?.0` is not supported
+                        NatLit if vis.is(Dot) => {
                             let index = Literal::from(token);
                             obj = obj.tuple_attr_expr(index);
                         }
@@ -2679,6 +2780,150 @@ impl Parser {
         Ok(arr)
     }
 
+    /// `{..r; y = 1}` => `r + {y = 1}`
+    fn try_reduce_record_spread(&mut self) -> ParseResult<Expr> {
+        debug_call_info!(self);
+        let l_brace = expect_pop!(self, fail_next LBrace);
+        let multiline = self.cur_is(Newline);
+        if multiline {
+            self.skip();
+            expect_pop!(self, fail_next Indent);
+        }
+        let spread = expect_pop!(self, fail_next Closed);
+        let base = self
+            .try_reduce_expr(false, false, false, false)
+            .map_err(|_| self.stack_dec(fn_name!()))?;
+        // `{..r}`
+        if multiline && self.cur_is(Newline) && self.nth_is(1, Dedent) {
+            self.skip();
+            self.skip();
+        }
+        if self.cur_is(RBrace) {
+            self.skip();
+            debug_exit_info!(self);
+            return Ok(base);
+        }
+        if !self.cur_is(Semi) && !self.cur_is(Newline) {
+            let err = self.skip_and_throw_invalid_seq_err(
+                caused_by!(),
+                line!() as usize,
+                &[";", "}"],
+                Semi,
+            );
+            self.errs.push(err);
+            debug_exit_info!(self);
+            return Err(());
+        }
+        self.skip();
+        // the rest is parsed as a normal record
+        self.restore(l_brace);
+        let rec = match self
+            .try_reduce_brace_container()
+            .map_err(|_| self.stack_dec(fn_name!()))?
+        {
+            BraceContainer::Record(rec) => rec,
+            other => {
+                let err = ParseError::invalid_record_element_err(line!() as usize, other.loc());
+                self.errs.push(err);
+                debug_exit_info!(self);
+                return Err(());
+            }
+        };
+        let op = Token::new(Plus, "+", spread.lineno, spread.col_begin);
+        debug_exit_info!(self);
+        Ok(Expr::BinOp(BinOp::new(op, base, Expr::Record(rec))))
+    }
+
+    /// Returns the binary operator that `token` means in an operator section.
+    /// `+`, `-` and `*` just after `(` are lexed as unary operators.
+    fn section_operator(token: &Token) -> Option<TokenKind> {
+        match token.kind {
+            PrePlus => Some(Plus),
+            PreMinus => Some(Minus),
+            PreStar => Some(Star),
+            Plus | Minus | Star | Slash | FloorDiv | Mod | Pow | Shl | Shr | BitAnd | BitXor
+            | BitOr | Less | Gre | LessEq | GreEq | DblEq | NotEq | InOp | NotInOp | IsOp
+            | IsNotOp | AndOp | OrOp => Some(token.kind),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the parenthesized expression (the left parenthesis has already been consumed) is an operator section:
+    /// `(+)`, `(+ 1)` (right section) or `(1 +)` (left section).
+    /// A unary operator which sticks to its operand (`(-x)`, `(*x)`) is not a section.
+    /// Returns the index of the operator token and whether the section is a left section.
+    fn lookahead_operator_section(&self) -> Option<(usize, bool)> {
+        let first = self.peek()?;
+        if let Some(kind) = Self::section_operator(first) {
+            let next = self.nth(1)?;
+            let unary_like = first.kind != kind || first.is(Pow);
+            let separated = first.lineno == next.lineno
+                && first.col_end().is_some_and(|end| end < next.col_begin);
+            return (next.is(RParen) || !unary_like || separated).then_some((0, false));
+        }
+        // find the closing parenthesis and check whether an operator is just before it
+        let mut depth = 0usize;
+        let mut idx = 0;
+        loop {
+            let token = self.nth(idx)?;
+            match token.kind {
+                LParen | LSqBr | LBrace => depth += 1,
+                RParen | RSqBr | RBrace if depth == 0 => break,
+                RParen | RSqBr | RBrace => depth -= 1,
+                Newline | Semi | Indent | Dedent if depth == 0 => return None,
+                EOF => return None,
+                _ => {}
+            }
+            idx += 1;
+        }
+        let last = self.nth(idx.checked_sub(1)?)?;
+        let before_last = self.nth(idx.checked_sub(2)?)?;
+        (self.nth_is(idx, RParen)
+            && Self::section_operator(last).is_some()
+            && Self::section_operator(before_last).is_none()
+            && !before_last.is(Dot))
+        .then_some((idx - 1, true))
+    }
+
+    /// Reduces an operator section (the left parenthesis has already been consumed).
+    /// The missing operands are filled with empty `Dummy`s, and `Desugarer` converts the sections into lambdas.
+    /// Returns `None` without consuming any token if the parenthesized expression is not a section.
+    fn opt_reduce_operator_section(&mut self) -> Option<ParseResult<Expr>> {
+        let (op_idx, left) = self.lookahead_operator_section()?;
+        debug_call_info!(self);
+        let mut op = self.tokens.ref_mut_payload().remove(op_idx).unwrap();
+        op.kind = Self::section_operator(&op).unwrap();
+        let hole = Expr::Dummy(Dummy::new(Some(op.loc()), vec![]));
+        let section = if !left && self.cur_is(RParen) {
+            let rhs = Expr::Dummy(Dummy::new(Some(op.loc()), vec![]));
+            BinOp::new(op, hole, rhs)
+        } else {
+            let Ok(operand) = self.try_reduce_expr(false, false, false, false) else {
+                self.stack_dec(fn_name!());
+                return Some(Err(()));
+            };
+            if left {
+                BinOp::new(op, operand, hole)
+            } else {
+                BinOp::new(op, hole, operand)
+            }
+        };
+        if !self.cur_is(RParen) {
+            let err = self.skip_and_throw_invalid_unclosed_err(
+                caused_by!(),
+                line!(),
+                ")",
+                "operator section",
+            );
+            self.errs.push(err);
+            debug_exit_info!(self);
+            return Some(Err(()));
+        }
+        self.skip();
+        debug_exit_info!(self);
+        Some(Ok(Expr::BinOp(section)))
+    }
+
     /// Set, Dict, Record
     fn try_reduce_brace_container(&mut self) -> ParseResult<BraceContainer> {
         debug_call_info!(self);
@@ -2936,6 +3181,17 @@ impl Parser {
                             };
                             attrs.push(RecordAttrOrIdent::Ident(ident));
                         }
+                        Expr::TypeAscription(tasc)
+                            if matches!(tasc.expr.as_ref(), Expr::Accessor(Accessor::Ident(_))) =>
+                        {
+                            let Expr::Accessor(Accessor::Ident(ident)) = *tasc.expr else {
+                                unreachable!()
+                            };
+                            attrs.push(RecordAttrOrIdent::Attr(Self::typed_punned_attr(
+                                ident,
+                                tasc.t_spec,
+                            )));
+                        }
                         other => {
                             let caused_by = caused_by!();
                             log!(err "error caused by: {caused_by}");
@@ -2959,6 +3215,17 @@ impl Parser {
         }
     }
 
+    /// `x: Int` -> `x = (x: Int)`, `.x: Int` -> `.x = (x: Int)`
+    fn typed_punned_attr(ident: Identifier, t_spec: TypeSpecWithOp) -> Def {
+        let var = Expr::local(
+            ident.inspect(),
+            ident.ln_begin().unwrap_or(1),
+            ident.col_begin().unwrap_or(0),
+        );
+        let expr = var.type_asc_expr(t_spec);
+        Def::new(Signature::new_var(ident), DefBody::new_single(expr))
+    }
+
     fn try_reduce_normal_dict_or_set_comp(
         &mut self,
         l_brace: Token,
@@ -2985,6 +3252,22 @@ impl Parser {
                 SetComprehension::new(l_brace, r_brace, var.name.into_token(), colon, rhs, pred);
             debug_exit_info!(self);
             Ok(BraceContainer::Set(Set::Comprehension(set_comp)))
+        } else if self.cur_is(Semi) && matches!(lhs, Expr::Accessor(Accessor::Ident(_))) {
+            // {x: Int; y: Int} (a record whose fields are punned and typed)
+            let Expr::Accessor(Accessor::Ident(ident)) = lhs else {
+                unreachable!()
+            };
+            let t_spec = Self::expr_to_type_spec(rhs.clone()).map_err(|e| {
+                self.errs.push(e);
+                self.stack_dec(fn_name!())
+            })?;
+            let t_spec = TypeSpecWithOp::new(colon, t_spec, rhs);
+            let attr = RecordAttrOrIdent::Attr(Self::typed_punned_attr(ident, t_spec));
+            let record = self
+                .try_reduce_record(l_brace, attr)
+                .map_err(|_| self.stack_dec(fn_name!()))?;
+            debug_exit_info!(self);
+            Ok(BraceContainer::Record(record))
         } else {
             let dict = self
                 .try_reduce_normal_dict(l_brace, lhs, rhs)
@@ -3466,26 +3749,43 @@ impl Parser {
                 }
             }
         } else {
-            let expect_call = self
-                .try_reduce_call_or_acc(false)
-                .map_err(|_| self.stack_dec(fn_name!()))?;
-            let Expr::Call(mut call) = expect_call else {
-                    let caused_by = caused_by!();
-                    log!(err "error caused by: {caused_by}");
-                let err = self.get_stream_op_syntax_error(line!() as usize, expect_call.loc(), caused_by!());
+            // obj |> f(...), obj |> f, obj |> (x -> ...), obj |> (* 2)
+            let in_paren = self.cur_is(LParen);
+            let callee = if in_paren {
+                self.try_reduce_bin_lhs(false, false)
+            } else {
+                self.try_reduce_call_or_acc(false)
+            }
+            .map_err(|_| self.stack_dec(fn_name!()))?;
+            if !in_paren && !matches!(callee, Expr::Accessor(_) | Expr::Call(_)) {
+                let caused_by = caused_by!();
+                log!(err "error caused by: {caused_by}");
+                let err =
+                    self.get_stream_op_syntax_error(line!() as usize, callee.loc(), caused_by!());
                 self.errs.push(err);
                 debug_exit_info!(self);
                 return Err(());
-            };
+            }
             let ExprOrOp::Expr(first_arg) = stack.pop().unwrap() else {
-                    let caused_by = caused_by!();
-                    log!(err "error caused by: {caused_by}");
-                self.errs
-                    .push(ParseError::compiler_bug(line!() as usize, call.loc(), fn_name!(), line!()));
+                let caused_by = caused_by!();
+                log!(err "error caused by: {caused_by}");
+                self.errs.push(ParseError::compiler_bug(
+                    line!() as usize,
+                    callee.loc(),
+                    fn_name!(),
+                    line!(),
+                ));
                 debug_exit_info!(self);
                 return Err(());
             };
-            call.args.insert_pos(0, PosArg::new(first_arg));
+            let call = match callee {
+                Expr::Call(mut call) if !in_paren => {
+                    call.args.insert_pos(0, PosArg::new(first_arg));
+                    call
+                }
+                // obj |> f => f(obj)
+                callee => callee.call(Args::single(PosArg::new(first_arg))),
+            };
             stack.push(ExprOrOp::Expr(Expr::Call(call)));
         }
         debug_exit_info!(self);
@@ -3493,6 +3793,19 @@ impl Parser {
     }
 }
 
+/// `"prefix" + rest` or `rest + "suffix"` (string pattern of a match arm)
+fn is_str_pattern_on_stack(stack: &[ExprOrOp]) -> bool {
+    let is_str_lit = |expr: &ExprOrOp| matches!(expr, ExprOrOp::Expr(Expr::Literal(lit)) if lit.token.is(StrLit));
+    let is_ident =
+        |expr: &ExprOrOp| matches!(expr, ExprOrOp::Expr(Expr::Accessor(Accessor::Ident(_))));
+    match stack {
+        [lhs, ExprOrOp::Op(op), rhs] if op.is(Plus) => {
+            (is_str_lit(lhs) && is_ident(rhs)) || (is_ident(lhs) && is_str_lit(rhs))
+        }
+        _ => false,
+    }
+}
+
 fn collect_last_binop_on_stack(stack: &mut Vec<ExprOrOp>) {
     let rhs = enum_unwrap!(stack.pop(), Some:(ExprOrOp::Expr:(_)));
     let op = enum_unwrap!(stack.pop(), Some:(ExprOrOp::Op:(_)));
@@ -1,10 +1,13 @@
+use std::collections::VecDeque;
 use std::iter::Iterator;
 
 use erg_common::io::Input;
+use erg_common::traits::DequeStream;
 
 // use erg_compiler::parser;
 
 use erg_parser::error::ParseResult;
+use erg_parser::feature::Feature;
 use erg_parser::lex::Lexer;
 use erg_parser::token::*;
 use TokenKind::*;
@@ -454,6 +457,63 @@ fn for_loop() -> ParseResult<()> {
     Ok(())
 }
 
+#[test]
+fn feature_attr() -> ParseResult<()> {
+    let src = "#!feature(implicit_mul)\n3x + 1\n";
+    let (tokens, features) = Lexer::new(Input::str(src.into()))
+        .lex_with_features()
+        .unwrap();
+    assert!(features.contains(Feature::ImplicitMul));
+    assert!(!features.contains(Feature::TypeApp));
+    let token_array = [
+        (Newline, "\n"),
+        (NatLit, "3"),
+        (Star, "*"),
+        (Symbol, "x"),
+        (Plus, "+"),
+        (NatLit, "1"),
+        (Newline, "\n"),
+        (EOF, "\0"),
+    ];
+    let tokens = VecDeque::from(tokens);
+    assert_eq!(tokens.len(), token_array.len());
+    for (tok, (kind, content)) in tokens.into_iter().zip(token_array) {
+        assert_eq!(tok, Token::from_str(kind, content));
+    }
+    // implicit multiplication is not enabled
+    assert!(Lexer::new(Input::str("3x + 1".into())).lex().is_err());
+    assert!(Lexer::new(Input::str("#!feature(foo)".into()))
+        .lex()
+        .is_err());
+    // feature attributes must be placed at the top of the module
+    assert!(Lexer::new(Input::str("x = 1\n#!feature(type_app)".into()))
+        .lex()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn nil_safe_ops() -> ParseResult<()> {
+    let src = "x?.y ?? z.w\n";
+    let tokens = Lexer::new(Input::str(src.into())).lex().unwrap().payload();
+    let token_array = [
+        (Symbol, "x"),
+        (SafeDot, "?."),
+        (Symbol, "y"),
+        (Coalesce, "??"),
+        (Symbol, "z"),
+        (Dot, "."),
+        (Symbol, "w"),
+        (Newline, "\n"),
+        (EOF, "\0"),
+    ];
+    assert_eq!(tokens.len(), token_array.len());
+    for (tok, (kind, content)) in tokens.into_iter().zip(token_array) {
+        assert_eq!(tok, Token::from_str(kind, content));
+    }
+    Ok(())
+}
+
 #[test]
 fn tesop_te_prec() {
     assert_eq!(Mod.precedence(), Some(170));
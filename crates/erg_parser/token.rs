@@ -116,6 +116,10 @@ pub enum TokenKind {
     AndOp,
     /// `or`
     OrOp,
+    /// `??` (nil-coalescing)
+    Coalesce,
+    /// `++` (array concatenation)
+    Concat,
     /// `dot` (scalar product)
     DotOp,
     /// `cross` (vector product)
@@ -150,6 +154,8 @@ pub enum TokenKind {
     Dedent,
     /// .
     Dot,
+    /// `?.` (nil-safe access)
+    SafeDot,
     /// |>
     Pipe,
     /// :
@@ -247,8 +253,8 @@ impl TokenKind {
                 TokenCategory::UnaryOp
             }
             Try => TokenCategory::PostfixOp,
-            Comma | Colon | DblColon | SupertypeOf | SubtypeOf | As | Dot | Pipe | Walrus
-            | Inclusion => TokenCategory::SpecialBinOp,
+            Comma | Colon | DblColon | SupertypeOf | SubtypeOf | As | Dot | SafeDot | Pipe
+            | Walrus | Inclusion => TokenCategory::SpecialBinOp,
             Assign => TokenCategory::DefOp,
             FuncArrow | ProcArrow => TokenCategory::LambdaOp,
             Semi | Newline => TokenCategory::Separator,
@@ -267,11 +273,11 @@ impl TokenKind {
 
     pub const fn precedence(&self) -> Option<usize> {
         let prec = match self {
-            Dot | DblColon => 200,                                    // .
+            Dot | SafeDot | DblColon => 200,                          // . ?.
             Pow => 190,                                               // **
             PrePlus | PreMinus | PreBitNot | RefOp | RefMutOp => 180, // (unary) + - * ~ ref ref!
             Star | Slash | FloorDiv | Mod | CrossOp | DotOp => 170,   // * / // % cross dot
-            Plus | Minus => 160,                                      // + -
+            Plus | Minus | Concat => 160,                             // + - ++
             Shl | Shr => 150,                                         // << >>
             BitAnd => 140,                                            // &&
             BitXor => 130,                                            // ^^
@@ -280,6 +286,7 @@ impl TokenKind {
             Less | Gre | LessEq | GreEq | DblEq | NotEq | InOp | NotInOp | IsOp | IsNotOp => 90, // < > <= >= == != in notin is isnot
             AndOp => 80,                                // and
             OrOp => 70,                                 // or
+            Coalesce => 65,                             // ??
             FuncArrow | ProcArrow | Inclusion => 60,    // -> => <-
             Colon | SupertypeOf | SubtypeOf | As => 50, // : :> <: as
             Comma => 40,                                // ,
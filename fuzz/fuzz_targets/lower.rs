@@ -0,0 +1,35 @@
+//! Parses and lowers arbitrary source code.
+//! Errors are expected, but any panic of the lowering (reported as `COMPILER_BUG` in `--no-panic` mode) is a bug.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use erg_common::config::ErgConfig;
+use erg_common::io::{Input, Output};
+
+use erg_compiler::erg_parser::build_ast::ASTBuilder;
+use erg_compiler::error::codes::COMPILER_BUG;
+use erg_compiler::lower::ASTLowerer;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(code) = std::str::from_utf8(data) else {
+        return;
+    };
+    let cfg = ErgConfig {
+        input: Input::str(code.to_string()),
+        output: Output::Null,
+        no_panic: true,
+        ..ErgConfig::default()
+    };
+    let Ok(art) = ASTBuilder::new(cfg.copy()).build(code.to_string()) else {
+        return;
+    };
+    if let Err(art) = ASTLowerer::new(cfg).lower(art.ast, "exec") {
+        if let Some(ice) = art
+            .errors
+            .iter()
+            .find(|err| err.core.code == Some(COMPILER_BUG))
+        {
+            panic!("{}", ice.core.main_message);
+        }
+    }
+});
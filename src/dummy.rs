@@ -14,7 +14,7 @@ use erg_compiler::hir::Expr;
 use erg_compiler::ty::HasType;
 
 use erg_compiler::error::{CompileError, CompileErrors};
-use erg_compiler::Compiler;
+use erg_compiler::{exec_wasm, Compiler};
 
 pub type EvalError = CompileError;
 pub type EvalErrors = CompileErrors;
@@ -280,6 +280,9 @@ impl Runnable for DummyVM {
     }
 
     fn exec(&mut self) -> Result<ExitStatus, Self::Errs> {
+        if self.cfg().target.is_wasm() {
+            return self.exec_wasm();
+        }
         // Parallel execution is not possible without dumping with a unique file name.
         let filename = self.cfg().dump_pyc_filename();
         let src = self.cfg_mut().input.read();
@@ -384,6 +387,10 @@ impl Runnable for DummyVM {
         }
         Ok(res)
     }
+
+    fn disassemble(&self, name: &str) -> Option<String> {
+        self.compiler.disassemble(name)
+    }
 }
 
 impl DummyVM {
@@ -396,4 +403,26 @@ impl DummyVM {
     pub fn eval(&mut self, src: String) -> Result<String, EvalErrors> {
         Runnable::eval(self, src)
     }
+
+    /// Disassembles a subroutine (e.g. `f`, `C.method`) evaluated so far.
+    pub fn disassemble(&self, name: &str) -> Option<String> {
+        Runnable::disassemble(self, name)
+    }
+
+    /// Compiles the script into WebAssembly and executes it with Node.js (`--target wasm`).
+    fn exec_wasm(&mut self) -> Result<ExitStatus, EvalErrors> {
+        let filename = self.cfg().dump_wasm_filename();
+        let src = self.cfg_mut().input.read();
+        let warns = self
+            .compiler
+            .compile_and_dump_as_wasm(&filename, src, "exec")
+            .map_err(|eart| {
+                eart.warns.write_all_to(&mut self.cfg_mut().output);
+                eart.errors
+            })?;
+        warns.write_all_to(&mut self.cfg_mut().output);
+        let code = exec_wasm(&filename, self.cfg().output.clone());
+        remove_file(&filename).unwrap();
+        Ok(ExitStatus::new(code.unwrap_or(1), warns.len(), 0))
+    }
 }
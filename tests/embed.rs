@@ -17,6 +17,25 @@ fn test_vm_embedding() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+fn test_vm_disassemble() -> Result<(), ()> {
+    let mut vm = DummyVM::default();
+    vm.eval("f x = x + 1".into()).map_err(|es| {
+        es.write_all_stderr();
+    })?;
+    vm.eval("C = Class()\nC.\n    g self = 1".into())
+        .map_err(|es| {
+            es.write_all_stderr();
+        })?;
+    assert!(vm
+        .disassemble("f")
+        .unwrap()
+        .starts_with("Disassembly of <code object f"));
+    assert!(vm.disassemble("C.g").is_some());
+    assert!(vm.disassemble("g").is_none());
+    Ok(())
+}
+
 #[test]
 fn test_transpiler_embedding() -> Result<(), ()> {
     let mut trans = Transpiler::default();
@@ -28,6 +28,18 @@ fn exec_repl_def_func() -> Result<(), ()> {
     )
 }
 
+#[test]
+#[ignore]
+fn exec_repl_dis() -> Result<(), ()> {
+    expect_repl_success(
+        "repl_dis",
+        ["f i =", "i + 1", "", ":dis f", ":dis g", "exit()"]
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect(),
+    )
+}
+
 #[test]
 #[ignore]
 fn exec_repl_for_loop() -> Result<(), ()> {
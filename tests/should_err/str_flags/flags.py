@@ -0,0 +1,6 @@
+def open_file(path, mode="r"):
+    return path + ":" + mode
+
+
+def set_mode(mode):
+    return None
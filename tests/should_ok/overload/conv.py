@@ -0,0 +1,10 @@
+def conv(x):
+    if isinstance(x, str):
+        return int(x)
+    if x >= 0:
+        return x
+    return str(x)
+
+
+def scale(x, factor=2):
+    return x * factor
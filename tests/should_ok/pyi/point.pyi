@@ -0,0 +1,13 @@
+from typing import Optional
+
+class Point:
+    x: int
+    y: int
+    def __init__(self, x: int, y: int) -> None: ...
+    def norm(self) -> int: ...
+
+def add(
+    a: int,
+    b: int = ...,
+) -> int: ...
+def find(xs: list[str], s: str) -> Optional[int]: ...
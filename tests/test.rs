@@ -1,6 +1,6 @@
 mod common;
 use common::{expect_compile_success, expect_end_with, expect_failure, expect_success};
-use erg_common::python_util::{module_exists, opt_which_python};
+use erg_common::python_util::{env_python_version, module_exists, opt_which_python};
 
 #[test]
 fn exec_addition_ok() -> Result<(), ()> {
@@ -22,11 +22,31 @@ fn exec_array_member() -> Result<(), ()> {
     expect_success("tests/should_ok/array_member.er", 0)
 }
 
+#[test]
+fn exec_array_slice() -> Result<(), ()> {
+    expect_success("tests/should_ok/array_slice.er", 0)
+}
+
+#[test]
+fn exec_asm() -> Result<(), ()> {
+    // `asm` is available only for Python 3.11
+    if env_python_version().minor_is(3, 11) {
+        expect_success("tests/should_ok/asm.er", 0)
+    } else {
+        Ok(())
+    }
+}
+
 #[test]
 fn exec_class() -> Result<(), ()> {
     expect_success("examples/class.er", 0)
 }
 
+#[test]
+fn exec_compile_time_flags() -> Result<(), ()> {
+    expect_success("tests/should_ok/compile_time_flags.er", 0)
+}
+
 #[test]
 fn exec_class_test() -> Result<(), ()> {
     expect_success("tests/should_ok/class.er", 0)
@@ -47,6 +67,26 @@ fn exec_comment() -> Result<(), ()> {
     expect_success("tests/should_ok/comment.er", 0)
 }
 
+#[test]
+fn exec_const_array() -> Result<(), ()> {
+    expect_success("tests/should_ok/const_array.er", 0)
+}
+
+#[test]
+fn exec_const_dict() -> Result<(), ()> {
+    expect_success("tests/should_ok/const_dict.er", 0)
+}
+
+#[test]
+fn exec_const_call() -> Result<(), ()> {
+    expect_success("tests/should_ok/const_call.er", 0)
+}
+
+#[test]
+fn exec_const_fold() -> Result<(), ()> {
+    expect_success("tests/should_ok/const_fold.er", 0)
+}
+
 #[test]
 fn exec_control() -> Result<(), ()> {
     expect_success("examples/control.er", 2)
@@ -62,6 +102,11 @@ fn exec_decimal() -> Result<(), ()> {
     expect_success("tests/should_ok/decimal.er", 0)
 }
 
+#[test]
+fn exec_debug_only() -> Result<(), ()> {
+    expect_success("tests/should_ok/debug_only.er", 0)
+}
+
 #[test]
 fn exec_default_param() -> Result<(), ()> {
     expect_success("tests/should_ok/default_param.er", 0)
@@ -72,11 +117,31 @@ fn exec_dependent() -> Result<(), ()> {
     expect_success("tests/should_ok/dependent.er", 0)
 }
 
+#[test]
+fn exec_derive() -> Result<(), ()> {
+    expect_success("tests/should_ok/derive.er", 0)
+}
+
 #[test]
 fn exec_dict() -> Result<(), ()> {
     expect_success("examples/dict.er", 0)
 }
 
+#[test]
+fn exec_docstring() -> Result<(), ()> {
+    expect_success("tests/should_ok/docstring.er", 0)
+}
+
+#[test]
+fn exec_dunder_op() -> Result<(), ()> {
+    expect_success("tests/should_ok/dunder_op.er", 0)
+}
+
+#[test]
+fn exec_effect_poly() -> Result<(), ()> {
+    expect_success("tests/should_ok/effect_poly.er", 0)
+}
+
 #[test]
 fn exec_external() -> Result<(), ()> {
     let py_command = opt_which_python().unwrap();
@@ -87,6 +152,11 @@ fn exec_external() -> Result<(), ()> {
     }
 }
 
+#[test]
+fn exec_feature() -> Result<(), ()> {
+    expect_success("tests/should_ok/feature.er", 0)
+}
+
 #[test]
 fn exec_fib() -> Result<(), ()> {
     expect_success("examples/fib.er", 0)
@@ -123,6 +193,11 @@ fn exec_import_cyclic() -> Result<(), ()> {
     expect_success("tests/should_ok/cyclic/import.er", 0)
 }
 
+#[test]
+fn exec_include() -> Result<(), ()> {
+    expect_success("tests/should_ok/include.er", 0)
+}
+
 #[test]
 fn exec_index() -> Result<(), ()> {
     expect_success("tests/should_ok/index.er", 0)
@@ -143,6 +218,11 @@ fn exec_infer_trait() -> Result<(), ()> {
     expect_success("tests/should_ok/infer_trait.er", 0)
 }
 
+#[test]
+fn exec_inline() -> Result<(), ()> {
+    expect_success("tests/should_ok/inline.er", 0)
+}
+
 #[test]
 fn exec_int() -> Result<(), ()> {
     expect_success("tests/should_ok/int.er", 0)
@@ -158,6 +238,11 @@ fn exec_long() -> Result<(), ()> {
     expect_success("tests/should_ok/long.er", 257)
 }
 
+#[test]
+fn exec_macro() -> Result<(), ()> {
+    expect_success("tests/should_ok/macro.er", 0)
+}
+
 #[test]
 fn exec_mangling() -> Result<(), ()> {
     expect_success("tests/should_ok/mangling.er", 0)
@@ -173,6 +258,16 @@ fn exec_map() -> Result<(), ()> {
     expect_success("tests/should_ok/map.er", 0)
 }
 
+#[test]
+fn exec_match_guard() -> Result<(), ()> {
+    expect_success("tests/should_ok/match_guard.er", 0)
+}
+
+#[test]
+fn exec_module_value() -> Result<(), ()> {
+    expect_success("tests/should_ok/module_value/main.er", 0)
+}
+
 #[test]
 fn exec_mut() -> Result<(), ()> {
     expect_success("examples/mut.er", 0)
@@ -193,11 +288,31 @@ fn exec_nested() -> Result<(), ()> {
     expect_success("tests/should_ok/nested.er", 3)
 }
 
+#[test]
+fn exec_nested_pattern() -> Result<(), ()> {
+    expect_success("tests/should_ok/nested_pattern.er", 0)
+}
+
 #[test]
 fn exec_never() -> Result<(), ()> {
     expect_success("tests/should_ok/never.er", 0)
 }
 
+#[test]
+fn exec_nil_safe() -> Result<(), ()> {
+    expect_success("tests/should_ok/nil_safe.er", 0)
+}
+
+#[test]
+fn exec_overload() -> Result<(), ()> {
+    expect_success("tests/should_ok/overload/main.er", 0)
+}
+
+#[test]
+fn exec_parse_json() -> Result<(), ()> {
+    expect_success("tests/should_ok/parse_json.er", 0)
+}
+
 #[test]
 fn exec_patch() -> Result<(), ()> {
     expect_success("examples/patch.er", 0)
@@ -208,11 +323,21 @@ fn exec_pattern() -> Result<(), ()> {
     expect_success("tests/should_ok/pattern.er", 0)
 }
 
+#[test]
+fn exec_pyi_stub() -> Result<(), ()> {
+    expect_success("tests/should_ok/pyi/import.er", 0)
+}
+
 #[test]
 fn exec_pyimport_test() -> Result<(), ()> {
     expect_success("tests/should_ok/pyimport.er", 2)
 }
 
+#[test]
+fn exec_pyimport_ext() -> Result<(), ()> {
+    expect_success("tests/should_ok/pyimport_ext.er", 0)
+}
+
 #[test]
 fn exec_quantified() -> Result<(), ()> {
     expect_success("examples/quantified.er", 1)
@@ -233,6 +358,26 @@ fn exec_record() -> Result<(), ()> {
     expect_success("examples/record.er", 0)
 }
 
+#[test]
+fn exec_record_coercion() -> Result<(), ()> {
+    expect_success("tests/should_ok/record_coercion.er", 0)
+}
+
+#[test]
+fn exec_record_ops() -> Result<(), ()> {
+    expect_success("tests/should_ok/record_ops.er", 0)
+}
+
+#[test]
+fn exec_record_pattern() -> Result<(), ()> {
+    expect_success("tests/should_ok/record_pattern.er", 0)
+}
+
+#[test]
+fn exec_reflection() -> Result<(), ()> {
+    expect_success("tests/should_ok/reflection.er", 0)
+}
+
 #[test]
 fn exec_refinement() -> Result<(), ()> {
     expect_success("tests/should_ok/refinement.er", 0)
@@ -243,6 +388,11 @@ fn exec_return() -> Result<(), ()> {
     expect_success("tests/should_ok/return.er", 0)
 }
 
+#[test]
+fn exec_section() -> Result<(), ()> {
+    expect_success("tests/should_ok/section.er", 0)
+}
+
 #[test]
 fn exec_self_type() -> Result<(), ()> {
     expect_success("tests/should_ok/self_type.er", 0)
@@ -253,6 +403,16 @@ fn exec_structural_example() -> Result<(), ()> {
     expect_success("examples/structural.er", 0)
 }
 
+#[test]
+fn exec_static_assert() -> Result<(), ()> {
+    expect_success("tests/should_ok/static_assert.er", 0)
+}
+
+#[test]
+fn exec_str_pattern() -> Result<(), ()> {
+    expect_success("tests/should_ok/str_pattern.er", 0)
+}
+
 #[test]
 fn exec_structural() -> Result<(), ()> {
     expect_success("tests/should_ok/structural.er", 0)
@@ -268,6 +428,11 @@ fn exec_trait() -> Result<(), ()> {
     expect_success("examples/trait.er", 0)
 }
 
+#[test]
+fn exec_trait_subsume() -> Result<(), ()> {
+    expect_success("tests/should_ok/trait_subsume.er", 0)
+}
+
 #[test]
 fn exec_tuple() -> Result<(), ()> {
     expect_success("examples/tuple.er", 0)
@@ -283,6 +448,16 @@ fn exec_unpack() -> Result<(), ()> {
     expect_success("examples/unpack.er", 0)
 }
 
+#[test]
+fn exec_unreachable() -> Result<(), ()> {
+    expect_success("tests/should_ok/unreachable.er", 2)
+}
+
+#[test]
+fn exec_unsafe_python() -> Result<(), ()> {
+    expect_success("tests/should_ok/unsafe_python.er", 0)
+}
+
 #[test]
 fn exec_unused_import() -> Result<(), ()> {
     expect_success("tests/should_ok/many_import/unused_import.er", 2)
@@ -310,7 +485,7 @@ fn exec_addition_err() -> Result<(), ()> {
 
 #[test]
 fn exec_args() -> Result<(), ()> {
-    expect_failure("tests/should_err/args.er", 0, 16)
+    expect_failure("tests/should_err/args.er", 0, 17)
 }
 
 #[test]
@@ -318,16 +493,35 @@ fn exec_array_err() -> Result<(), ()> {
     expect_failure("examples/array.er", 0, 1)
 }
 
+#[test]
+fn exec_array_index_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/array_index.er", 0, 6)
+}
+
 #[test]
 fn exec_array_member_err() -> Result<(), ()> {
     expect_failure("tests/should_err/array_member.er", 0, 3)
 }
 
+#[test]
+fn exec_array_slice_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/array_slice.er", 0, 4)
+}
+
 #[test]
 fn exec_as() -> Result<(), ()> {
     expect_failure("tests/should_err/as.er", 0, 6)
 }
 
+#[test]
+fn exec_asm_err() -> Result<(), ()> {
+    if env_python_version().minor_is(3, 11) {
+        expect_failure("tests/should_err/asm.er", 0, 6)
+    } else {
+        Ok(())
+    }
+}
+
 #[test]
 fn exec_assert_cast() -> Result<(), ()> {
     expect_failure("examples/assert_cast.er", 0, 3)
@@ -343,16 +537,67 @@ fn exec_collection_err() -> Result<(), ()> {
     expect_failure("tests/should_err/collection.er", 0, 4)
 }
 
+#[test]
+fn exec_const_array_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/const_array.er", 0, 2)
+}
+
+#[test]
+fn exec_const_call_err() -> Result<(), ()> {
+    // reported by both the constant evaluation and the type checking
+    expect_failure("tests/should_err/const_call.er", 0, 8)
+}
+
+#[test]
+fn exec_const_dict_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/const_dict.er", 0, 2)
+}
+
+#[test]
+fn exec_const_getitem_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/const_getitem.er", 0, 7)
+}
+
+#[test]
+fn exec_cyclic_type_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/cyclic_type.er", 0, 3)
+}
+
+#[test]
+fn exec_debug_only_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/debug_only.er", 0, 2)
+}
+
 #[test]
 fn exec_dependent_err() -> Result<(), ()> {
     expect_failure("tests/should_err/dependent.er", 0, 5)
 }
 
+#[test]
+fn exec_derive_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/derive.er", 0, 9)
+}
+
+#[test]
+fn exec_dunder_op_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/dunder_op.er", 0, 3)
+}
+
+#[test]
+fn exec_effect_poly_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/effect_poly.er", 0, 2)
+}
+
 #[test]
 fn exec_err_import() -> Result<(), ()> {
     expect_failure("tests/should_err/err_import.er", 0, 9)
 }
 
+#[test]
+fn exec_feature_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/feature.er", 0, 3)
+}
+
 /// This file compiles successfully, but causes a run-time error due to incomplete method dispatching
 #[test]
 fn exec_tests_impl() -> Result<(), ()> {
@@ -374,6 +619,21 @@ fn exec_import_cyclic_err() -> Result<(), ()> {
     expect_failure("tests/should_err/cyclic/import.er", 0, 1)
 }
 
+#[test]
+fn exec_import_cyclic_chain() -> Result<(), ()> {
+    expect_failure("tests/should_err/cyclic_chain/import.er", 0, 1)
+}
+
+#[test]
+fn exec_import_cyclic_effect() -> Result<(), ()> {
+    expect_failure("tests/should_err/cyclic_effect/import.er", 0, 1)
+}
+
+#[test]
+fn exec_include_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/include.er", 0, 4)
+}
+
 #[test]
 fn exec_infer_union_array() -> Result<(), ()> {
     expect_failure("tests/should_err/infer_union_array.er", 2, 1)
@@ -389,6 +649,27 @@ fn exec_invalid_param() -> Result<(), ()> {
     expect_failure("tests/should_err/invalid_param.er", 0, 3)
 }
 
+#[test]
+fn exec_macro_err() -> Result<(), ()> {
+    // `Twice` duplicates the erroneous argument, so the error is reported twice
+    expect_failure("tests/should_err/macro.er", 0, 4)
+}
+
+#[test]
+fn exec_match_guard_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/match_guard.er", 0, 4)
+}
+
+#[test]
+fn exec_match_narrowing_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/match_narrowing.er", 0, 4)
+}
+
+#[test]
+fn exec_module_value_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/module_value/main.er", 0, 4)
+}
+
 #[test]
 fn exec_move_check() -> Result<(), ()> {
     expect_failure("examples/move_check.er", 1, 1)
@@ -413,6 +694,21 @@ fn exec_side_effect() -> Result<(), ()> {
     expect_failure("examples/side_effect.er", 5, 4)
 }
 
+#[test]
+fn exec_static_assert_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/static_assert.er", 0, 3)
+}
+
+#[test]
+fn exec_str_flags_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/str_flags/main.er", 0, 3)
+}
+
+#[test]
+fn exec_str_pattern_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/str_pattern.er", 0, 4)
+}
+
 #[test]
 fn exec_structural_err() -> Result<(), ()> {
     expect_failure("tests/should_err/structural.er", 1, 9)
@@ -423,11 +719,26 @@ fn exec_subtyping_err() -> Result<(), ()> {
     expect_failure("tests/should_err/subtyping.er", 0, 17)
 }
 
+#[test]
+fn exec_trait_subsume_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/trait_subsume.er", 0, 2)
+}
+
 #[test]
 fn exec_tuple_err() -> Result<(), ()> {
     expect_failure("tests/should_err/tuple.er", 0, 1)
 }
 
+#[test]
+fn exec_unsafe_python_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/unsafe_python.er", 0, 4)
+}
+
+#[test]
+fn exec_unstable_feature_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/unstable_feature.er", 0, 1)
+}
+
 #[test]
 fn exec_callable() -> Result<(), ()> {
     expect_failure("tests/should_err/callable.er", 0, 6)
@@ -458,16 +769,57 @@ fn exec_mut_dict_err() -> Result<(), ()> {
     expect_failure("tests/should_err/mut_dict.er", 0, 3)
 }
 
+#[test]
+fn exec_nested_pattern_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/nested_pattern.er", 0, 4)
+}
+
+#[test]
+fn exec_nil_safe_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/nil_safe.er", 0, 4)
+}
+
+#[test]
+fn exec_overload_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/overload/main.er", 0, 4)
+}
+
+#[test]
+fn exec_parse_json_err() -> Result<(), ()> {
+    // the invalid JSON texts are reported by both the constant evaluation and the type checking
+    expect_failure("tests/should_err/parse_json.er", 0, 5)
+}
+
 #[test]
 fn exec_quantified_err() -> Result<(), ()> {
     expect_failure("tests/should_err/quantified.er", 0, 3)
 }
 
+#[test]
+fn exec_record_ops_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/record_ops.er", 0, 4)
+}
+
+#[test]
+fn exec_record_pattern_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/record_pattern.er", 1, 4)
+}
+
+#[test]
+fn exec_reflection_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/reflection.er", 0, 4)
+}
+
 #[test]
 fn exec_refinement_err() -> Result<(), ()> {
     expect_failure("tests/should_err/refinement.er", 0, 8)
 }
 
+#[test]
+fn exec_section_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/section.er", 1, 3)
+}
+
 #[test]
 fn exec_var_args_err() -> Result<(), ()> {
     expect_failure("tests/should_err/var_args.er", 0, 3)